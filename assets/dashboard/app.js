@@ -0,0 +1,84 @@
+// Dashboard over the JSON API. Values are only ever set as text, never as HTML.
+"use strict";
+
+const errorBox = document.getElementById("error");
+
+function showError(message) {
+  errorBox.textContent = message;
+  errorBox.hidden = !message;
+}
+
+async function api(method, path, body) {
+  const response = await fetch(path, {
+    method,
+    headers: body ? { "Content-Type": "application/json" } : {},
+    body: body ? JSON.stringify(body) : undefined,
+  });
+  const data = response.status === 204 ? null : await response.json();
+  if (!response.ok) {
+    throw new Error(data && data.message ? data.message : `${method} ${path}: ${response.status}`);
+  }
+  return data;
+}
+
+function cell(row, text) {
+  const td = row.insertCell();
+  td.textContent = text;
+  return td;
+}
+
+async function refresh() {
+  const [users, tasks] = await Promise.all([api("GET", "/users"), api("GET", "/tasks?embed=user")]);
+
+  const userRows = document.getElementById("users");
+  const owners = document.getElementById("task-user");
+  userRows.replaceChildren();
+  owners.replaceChildren();
+  for (const user of users) {
+    const row = userRows.insertRow();
+    cell(row, user.name);
+    cell(row, user.email);
+    cell(row, user.id);
+    owners.add(new Option(user.name, user.id));
+  }
+
+  const taskRows = document.getElementById("tasks");
+  taskRows.replaceChildren();
+  for (const task of tasks) {
+    const row = taskRows.insertRow();
+    cell(row, task.title);
+    cell(row, task.user ? task.user.name : task.userId);
+    cell(row, task.completed ? "done" : "open");
+    const actions = row.insertCell();
+    if (!task.completed) {
+      const button = document.createElement("button");
+      button.textContent = "Complete";
+      button.addEventListener("click", () =>
+        run(() => api("PATCH", `/tasks/${encodeURIComponent(task.id)}/complete`)),
+      );
+      actions.append(button);
+    }
+  }
+}
+
+async function run(action) {
+  try {
+    await action();
+    showError("");
+    await refresh();
+  } catch (error) {
+    showError(error.message);
+  }
+}
+
+document.getElementById("new-task").addEventListener("submit", (event) => {
+  event.preventDefault();
+  const form = event.target;
+  const task = Object.fromEntries(new FormData(form));
+  run(async () => {
+    await api("POST", "/tasks", task);
+    form.reset();
+  });
+});
+
+run(async () => {});
@@ -0,0 +1,50 @@
+//! Encoding response bodies through `Negotiated` versus axum's `Json`.
+//!
+//! `Negotiated` serializes the whole body up front and guards the serializer against
+//! panics, so that a failing `Serialize` impl renders a 500 `ApiError`; this measures
+//! what that costs on a page of tasks.
+//!
+//! ```bash
+//! cargo bench --bench responses
+//! ```
+
+#![expect(missing_docs, reason = "criterion_group! generates an undocumented function")]
+
+use axum::response::IntoResponse;
+use axum::Json;
+use axum_ddd_template::api_types::TaskResponse;
+use axum_ddd_template::shared::infrastructure::http::Negotiated;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+/// A page of 100 tasks, as `GET /tasks` returns it
+fn page() -> Vec<TaskResponse> {
+    (0..100)
+        .map(|i| TaskResponse {
+            id: format!("0b6f2a6e-8d1c-4a7e-9f3b-{i:012}"),
+            user_id: "u-bench".into(),
+            title: format!("Task {i}"),
+            description: "Get 2 liters of milk on the way home".into(),
+            completed: i % 3 == 0,
+            updated_at: Some(chrono::DateTime::UNIX_EPOCH),
+            user: None,
+            description_html: None,
+            checklist: Vec::new(),
+        })
+        .collect()
+}
+
+fn encode(c: &mut Criterion) {
+    let page = page();
+    let mut group = c.benchmark_group("encode_task_page");
+    group.bench_function("json", |b| {
+        b.iter(|| black_box(Json(black_box(&page)).into_response()));
+    });
+    group.bench_function("negotiated", |b| {
+        b.iter(|| black_box(Negotiated(black_box(&page)).into_response()));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
@@ -0,0 +1,157 @@
+//! Boxed versus generic wiring of the feature states on in-memory repositories.
+//!
+//! Compares a use case calling its repository through `Arc<dyn UserRepository>` with
+//! one calling `InMemoryUserRepository` directly, and the same request served by the
+//! full router built on either wiring.
+//!
+//! ```bash
+//! cargo bench --bench wiring
+//! ```
+
+#![expect(clippy::expect_used, reason = "benchmark setup failures should abort")]
+#![expect(missing_docs, reason = "criterion_group! generates an undocumented function")]
+
+use axum::body::Body;
+use axum::http::Request;
+use axum::Router;
+use axum_ddd_template::app::{build_router, feature_toggles, AppState};
+use axum_ddd_template::features::task::domain::{TaskRepository, UnitOfWork};
+use axum_ddd_template::features::task::infrastructure::{
+    InMemoryAttachmentRepository, InMemoryTaskRepository, InMemoryTaskViewPreferencesRepository,
+    InMemoryUnitOfWork, LocalBlobStorage, MarkdownRenderer,
+};
+use axum_ddd_template::features::task::{AttachmentSettings, OnboardingSettings, TaskState};
+use axum_ddd_template::features::user::application::GetUserUseCase;
+use axum_ddd_template::features::user::domain::{User, UserDependents, UserRepository};
+use axum_ddd_template::features::user::infrastructure::{
+    InMemoryEmailChangeRepository, InMemoryUserRepository,
+};
+use axum_ddd_template::features::user::{EmailChangeSettings, UserState};
+use axum_ddd_template::shared::application::DetachedWrites;
+use axum_ddd_template::shared::domain::{TenantId, UserId};
+use axum_ddd_template::shared::infrastructure::config::Config;
+use axum_ddd_template::shared::infrastructure::email::ConsoleEmailSender;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tower::ServiceExt;
+
+const USER: &str = "u-bench";
+
+fn runtime() -> Runtime {
+    Runtime::new().expect("tokio runtime")
+}
+
+async fn seed(users: &dyn UserRepository) {
+    let id = UserId::new(USER).expect("valid user id");
+    let user = User::new(id, "Bench".into(), "bench@example.com").expect("valid user");
+    users.insert(&TenantId::default(), &user).await.expect("seed user");
+}
+
+/// Every feature wired to `users` and `tasks`, as concrete types or `Arc<dyn ..>`
+fn state<U, T>(
+    config: &Config,
+    users: &Arc<U>,
+    tasks: &Arc<T>,
+    dependents: Arc<dyn UserDependents>,
+    unit_of_work: Arc<dyn UnitOfWork>,
+) -> AppState<U, T>
+where
+    U: UserRepository + ?Sized + 'static,
+    T: TaskRepository + ?Sized + 'static,
+{
+    let email_changes = EmailChangeSettings {
+        repository: Arc::new(InMemoryEmailChangeRepository::default()),
+        notifier: None,
+        token_ttl: config.email_change_token_ttl(),
+        token_in_response: config.email_change_token_in_response,
+    };
+    let detached_writes = DetachedWrites::default();
+    let user = UserState::new(
+        users,
+        Some(dependents),
+        email_changes,
+        config.page_limits(),
+        Arc::new(ConsoleEmailSender),
+        &detached_writes,
+    );
+    let attachments = AttachmentSettings {
+        repository: Arc::new(InMemoryAttachmentRepository::default()),
+        storage: Arc::new(
+            LocalBlobStorage::new(&config.blob_local_dir, config.blob_url_ttl())
+                .expect("blob storage"),
+        ),
+    };
+    let email_sender = Arc::new(ConsoleEmailSender);
+    let onboarding = OnboardingSettings { unit_of_work, email_sender };
+    let renderer = Arc::new(MarkdownRenderer);
+    let preferences = Arc::new(InMemoryTaskViewPreferencesRepository::default());
+    let toggles = Arc::new(feature_toggles(config).expect("feature flags"));
+    let task = TaskState::new(
+        config,
+        tasks,
+        users,
+        renderer,
+        attachments,
+        preferences,
+        onboarding,
+        &toggles,
+        &detached_writes,
+    );
+    AppState::from_states(config, Some(Arc::new(user)), Some(Arc::new(task)))
+}
+
+fn use_case(c: &mut Criterion) {
+    let rt = runtime();
+    let users = Arc::new(InMemoryUserRepository::default());
+    rt.block_on(seed(users.as_ref()));
+    let boxed: GetUserUseCase = GetUserUseCase::new(Arc::clone(&users) as Arc<dyn UserRepository>);
+    let generic = GetUserUseCase::new(Arc::clone(&users));
+    let tenant = TenantId::default();
+
+    let mut group = c.benchmark_group("get_user_use_case");
+    group.bench_function("boxed", |b| {
+        b.to_async(&rt).iter(|| async { black_box(boxed.execute(&tenant, USER).await) });
+    });
+    group.bench_function("generic", |b| {
+        b.to_async(&rt).iter(|| async { black_box(generic.execute(&tenant, USER).await) });
+    });
+    group.finish();
+}
+
+fn router(c: &mut Criterion) {
+    let rt = runtime();
+    let config = Config::default();
+    let users = Arc::new(InMemoryUserRepository::default());
+    rt.block_on(seed(users.as_ref()));
+    let tasks = Arc::new(InMemoryTaskRepository::default());
+    let dyn_users: Arc<dyn UserRepository> = Arc::clone(&users) as _;
+    let dyn_tasks: Arc<dyn TaskRepository> = Arc::clone(&tasks) as _;
+    let unit_of_work = Arc::new(InMemoryUnitOfWork::new(Arc::clone(&users), Arc::clone(&tasks)));
+    let boxed_state =
+        state(&config, &dyn_users, &dyn_tasks, Arc::clone(&tasks) as _, unit_of_work.clone());
+    let boxed = build_router(&boxed_state, &config).expect("boxed router");
+    let generic_state = state(&config, &users, &tasks, Arc::clone(&tasks) as _, unit_of_work);
+    let generic = build_router(&generic_state, &config).expect("generic router");
+    let uri = format!("/users/{USER}");
+
+    let mut group = c.benchmark_group("get_user_request");
+    group.bench_function("boxed", |b| {
+        b.to_async(&rt).iter(|| async { black_box(get(&boxed, &uri).await) });
+    });
+    group.bench_function("generic", |b| {
+        b.to_async(&rt).iter(|| async { black_box(get(&generic, &uri).await) });
+    });
+    group.finish();
+}
+
+async fn get(app: &Router, uri: &str) -> axum::body::Bytes {
+    let request = Request::get(uri).body(Body::empty()).expect("request");
+    let response = app.clone().oneshot(request).await.expect("infallible router");
+    assert!(response.status().is_success(), "GET {uri}: {}", response.status());
+    axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("body")
+}
+
+criterion_group!(benches, use_case, router);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+//! Embeds the repeatable migrations in `migrations/repeatable/` and compiles the gRPC
+//! service definitions in `proto/` when the `grpc` feature is enabled
+
+use std::fmt::Write;
+use std::path::Path;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    embed_repeatable_migrations()?;
+    #[cfg(feature = "grpc")]
+    compile_protos()?;
+    Ok(())
+}
+
+/// Write `repeatable_migrations.rs` to `OUT_DIR`: a slice of every `.sql` file in
+/// `migrations/repeatable/` as `(file name, contents)`, in file name order
+fn embed_repeatable_migrations() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = Path::new(&std::env::var("CARGO_MANIFEST_DIR")?).join("migrations/repeatable");
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "sql") {
+            println!("cargo:rerun-if-changed={}", path.display());
+            files.push(path);
+        }
+    }
+    files.sort();
+    let mut entries = String::new();
+    for path in &files {
+        let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        writeln!(entries, "    ({name:?}, include_str!({:?})),", path.display().to_string())?;
+    }
+    let out = Path::new(&std::env::var("OUT_DIR")?).join("repeatable_migrations.rs");
+    std::fs::write(out, format!("&[\n{entries}]\n"))?;
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+fn compile_protos() -> Result<(), Box<dyn std::error::Error>> {
+    // The vendored protoc avoids requiring a system installation
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure().compile_protos_with_config(
+        config,
+        &["proto/user.proto", "proto/task.proto"],
+        &["proto"],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,250 @@
+//! Adding a feature from outside the crate.
+//!
+//! Defines a minimal "notes" feature (entity, repository port, use cases and routes)
+//! and serves it next to the built-in features through
+//! [`build_router_with`], backed by in-memory repositories.
+//!
+//! ```bash
+//! cargo run --example custom_feature
+//! curl -X POST http://localhost:3000/notes \
+//!   -H "Content-Type: application/json" -d '{"text": "Remember the milk"}'
+//! curl http://localhost:3000/notes
+//! ```
+
+use axum_ddd_template::app::{build_router_with, AppState, InMemoryRepositories};
+use axum_ddd_template::shared::infrastructure::{config::Config, feature::FeatureRegistry};
+use std::sync::Arc;
+use tracing::info;
+
+/// The notes feature; a real feature would split these layers into modules
+mod notes {
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use axum_ddd_template::shared::domain::{DomainError, Entity};
+    use axum_ddd_template::shared::infrastructure::feature::{ExpectedRoute, FeatureRouter};
+    use axum_ddd_template::shared::infrastructure::http::{ApiError, ApiJson};
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    /// Feature name, used in registration errors
+    pub const NAME: &str = "note";
+
+    // --- Domain ---
+
+    axum_ddd_template::string_id!(NoteId, "Note");
+
+    /// A short text note
+    #[derive(Debug, Clone)]
+    pub struct Note {
+        id: NoteId,
+        text: String,
+    }
+
+    impl Note {
+        /// Create a note; the text must not be blank
+        pub fn new(text: &str) -> Result<Self, DomainError> {
+            let text = text.trim();
+            if text.is_empty() {
+                return Err(DomainError::Validation("Text cannot be empty".into()));
+            }
+            Ok(Self { id: NoteId::generate(), text: text.to_owned() })
+        }
+    }
+
+    impl Entity for Note {
+        type Id = NoteId;
+
+        fn id(&self) -> &Self::Id {
+            &self.id
+        }
+    }
+
+    /// Repository port for notes
+    #[async_trait::async_trait]
+    pub trait NoteRepository: Send + Sync {
+        /// Store a new note
+        async fn insert(&self, note: &Note) -> Result<(), DomainError>;
+        /// All notes in insertion order
+        async fn find_all(&self) -> Result<Vec<Note>, DomainError>;
+    }
+
+    // --- Infrastructure: repository adapter ---
+
+    /// In-memory notes, in insertion order
+    #[derive(Default)]
+    pub struct InMemoryNoteRepository {
+        notes: RwLock<Vec<Note>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NoteRepository for InMemoryNoteRepository {
+        async fn insert(&self, note: &Note) -> Result<(), DomainError> {
+            self.notes.write().await.push(note.clone());
+            Ok(())
+        }
+
+        async fn find_all(&self) -> Result<Vec<Note>, DomainError> {
+            Ok(self.notes.read().await.clone())
+        }
+    }
+
+    // --- Application ---
+
+    /// Use cases of the notes feature
+    pub struct NoteState {
+        repository: Arc<dyn NoteRepository>,
+    }
+
+    impl NoteState {
+        /// Wire the use cases to `repository`
+        pub fn new(repository: Arc<dyn NoteRepository>) -> Self {
+            Self { repository }
+        }
+
+        async fn create_note(&self, text: &str) -> Result<Note, DomainError> {
+            let note = Note::new(text)?;
+            self.repository.insert(&note).await?;
+            Ok(note)
+        }
+
+        async fn list_notes(&self) -> Result<Vec<Note>, DomainError> {
+            self.repository.find_all().await
+        }
+    }
+
+    // --- Infrastructure: HTTP ---
+
+    /// HTTP response body for a note
+    #[derive(Serialize)]
+    pub struct NoteResponse {
+        id: String,
+        text: String,
+    }
+
+    impl From<Note> for NoteResponse {
+        fn from(note: Note) -> Self {
+            Self { id: note.id.value().to_owned(), text: note.text }
+        }
+    }
+
+    /// HTTP request body for creating a note
+    #[derive(Deserialize)]
+    pub struct CreateNoteRequest {
+        text: String,
+    }
+
+    /// Routes served by [`routes`]
+    const ROUTES: &[ExpectedRoute] = &[ExpectedRoute::post("/"), ExpectedRoute::get("/")];
+
+    /// Notes routes, nested under `/notes`
+    pub fn routes(state: Arc<NoteState>) -> FeatureRouter<()> {
+        let router = Router::new().route("/", post(create_note).get(list_notes));
+        FeatureRouter {
+            name: NAME,
+            prefix: "/notes",
+            router: router.with_state(state),
+            manifest: ROUTES,
+        }
+    }
+
+    async fn create_note(
+        State(state): State<Arc<NoteState>>,
+        ApiJson(body): ApiJson<CreateNoteRequest>,
+    ) -> Result<(StatusCode, Json<NoteResponse>), ApiError> {
+        let note = state.create_note(&body.text).await?;
+        Ok((StatusCode::CREATED, Json(note.into())))
+    }
+
+    async fn list_notes(
+        State(state): State<Arc<NoteState>>,
+    ) -> Result<Json<Vec<NoteResponse>>, ApiError> {
+        let notes = state.list_notes().await?;
+        Ok(Json(notes.into_iter().map(Into::into).collect()))
+    }
+}
+
+/// The full application: built-in features plus notes
+fn app(config: &Config) -> anyhow::Result<axum::Router> {
+    let state = AppState::build(config, &InMemoryRepositories::default())?;
+    let notes = notes::NoteState::new(Arc::new(notes::InMemoryNoteRepository::default()));
+    let registry = FeatureRegistry::default().register(notes::routes(Arc::new(notes)));
+    build_router_with(&state, config, registry)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt().init();
+    let config = Config::default();
+    let listener = tokio::net::TcpListener::bind(config.server_addr).await?;
+    info!("Server running on http://{}", config.server_addr);
+    axum::serve(listener, app(&config)?).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use axum_ddd_template::shared::domain::UserId;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.expect("infallible router");
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("body");
+        (status, serde_json::from_slice(&bytes).expect("JSON"))
+    }
+
+    fn post_note(text: &str) -> Request<Body> {
+        Request::post("/notes")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "text": text }).to_string()))
+            .expect("valid request")
+    }
+
+    #[test]
+    fn note_id_should_behave_like_builtin_ids() {
+        let empty = notes::NoteId::new("").err().map(|e| e.to_string());
+        assert_eq!(empty.as_deref(), Some("Validation error: Note ID cannot be empty"));
+        let builtin = UserId::new("").err().map(|e| e.to_string());
+        assert_eq!(builtin.as_deref(), Some("Validation error: User ID cannot be empty"));
+
+        assert_ne!(notes::NoteId::generate(), notes::NoteId::generate());
+        assert_eq!(notes::NoteId::entity_name(), "Note");
+
+        let id = notes::NoteId::new("n-1").expect("valid id");
+        assert_eq!(id.value(), "n-1");
+        assert_eq!(serde_json::to_value(&id).expect("serialize"), json!("n-1"));
+        let decoded: notes::NoteId = serde_json::from_value(json!("n-1")).expect("deserialize");
+        assert_eq!(decoded, id);
+        let user_id = UserId::from_trusted("n-1".into());
+        assert_eq!(serde_json::to_value(&user_id).expect("serialize"), json!("n-1"));
+    }
+
+    #[tokio::test]
+    async fn notes_should_be_served_next_to_builtin_features() {
+        let app = app(&Config::default()).expect("valid router");
+
+        let (status, created) = send(&app, post_note("  Remember the milk ")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["text"], "Remember the milk");
+
+        let (status, listed) =
+            send(&app, Request::get("/notes").body(Body::empty()).expect("request")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed, json!([created]));
+
+        let (status, error) = send(&app, post_note(" ")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["code"], "VALIDATION_ERROR");
+
+        let (status, _) =
+            send(&app, Request::get("/users").body(Body::empty()).expect("request")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
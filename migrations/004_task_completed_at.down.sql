@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_tasks_completed_at;
+DROP INDEX IF EXISTS idx_tasks_created_at;
+ALTER TABLE tasks DROP COLUMN IF EXISTS completed_at;
@@ -0,0 +1,7 @@
+-- Record when a task was completed; existing completed tasks use their last update
+ALTER TABLE tasks ADD COLUMN completed_at TIMESTAMPTZ;
+UPDATE tasks SET completed_at = updated_at WHERE completed;
+
+-- Support hourly created/completed aggregations
+CREATE INDEX IF NOT EXISTS idx_tasks_created_at ON tasks(created_at);
+CREATE INDEX IF NOT EXISTS idx_tasks_completed_at ON tasks(completed_at);
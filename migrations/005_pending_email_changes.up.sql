@@ -0,0 +1,10 @@
+-- Email changes awaiting confirmation; only a hash of each token is stored
+CREATE TABLE IF NOT EXISTS pending_email_changes (
+    token_hash VARCHAR(64) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    new_email VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    confirmed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_pending_email_changes_user_id ON pending_email_changes(user_id);
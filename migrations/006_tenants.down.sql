@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS idx_tasks_tenant_id;
+ALTER TABLE tasks DROP CONSTRAINT IF EXISTS tasks_tenant_user_fkey;
+ALTER TABLE tasks ADD CONSTRAINT tasks_user_id_fkey
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_tenant_id_key;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_tenant_email_key;
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
+ALTER TABLE tasks DROP COLUMN IF EXISTS tenant_id;
+ALTER TABLE users DROP COLUMN IF EXISTS tenant_id;
@@ -0,0 +1,15 @@
+-- Every user and task belongs to a tenant; existing rows move to the default tenant
+ALTER TABLE users ADD COLUMN tenant_id VARCHAR(64) NOT NULL DEFAULT 'default';
+ALTER TABLE tasks ADD COLUMN tenant_id VARCHAR(64) NOT NULL DEFAULT 'default';
+
+-- Emails are unique per tenant
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+ALTER TABLE users ADD CONSTRAINT users_tenant_email_key UNIQUE (tenant_id, email);
+
+-- A task is owned by a user of its own tenant
+ALTER TABLE users ADD CONSTRAINT users_tenant_id_key UNIQUE (tenant_id, id);
+ALTER TABLE tasks DROP CONSTRAINT IF EXISTS tasks_user_id_fkey;
+ALTER TABLE tasks ADD CONSTRAINT tasks_tenant_user_fkey
+    FOREIGN KEY (tenant_id, user_id) REFERENCES users (tenant_id, id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_tenant_id ON tasks(tenant_id, id);
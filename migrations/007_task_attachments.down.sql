@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS task_attachments;
+DROP FUNCTION IF EXISTS enqueue_attachment_blob_deletion();
+DROP TABLE IF EXISTS blob_deletions;
+ALTER TABLE tasks DROP CONSTRAINT IF EXISTS tasks_tenant_id_key;
@@ -0,0 +1,34 @@
+-- Metadata of files attached to tasks; the bytes live in blob storage
+ALTER TABLE tasks ADD CONSTRAINT tasks_tenant_id_key UNIQUE (tenant_id, id);
+
+CREATE TABLE IF NOT EXISTS task_attachments (
+    id VARCHAR(255) PRIMARY KEY,
+    tenant_id VARCHAR(64) NOT NULL,
+    task_id VARCHAR(255) NOT NULL,
+    filename VARCHAR(255) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    storage_key VARCHAR(512) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT task_attachments_tenant_task_fkey
+        FOREIGN KEY (tenant_id, task_id) REFERENCES tasks (tenant_id, id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_attachments_task_id ON task_attachments(tenant_id, task_id);
+
+-- Blobs of deleted attachments, removed from blob storage by a background job
+CREATE TABLE IF NOT EXISTS blob_deletions (
+    storage_key VARCHAR(512) PRIMARY KEY,
+    enqueued_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Queue the blob of every deleted attachment, also when its task or user is deleted
+CREATE OR REPLACE FUNCTION enqueue_attachment_blob_deletion() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO blob_deletions (storage_key) VALUES (OLD.storage_key) ON CONFLICT DO NOTHING;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER task_attachments_blob_deletion AFTER DELETE ON task_attachments
+    FOR EACH ROW EXECUTE FUNCTION enqueue_attachment_blob_deletion();
@@ -0,0 +1,11 @@
+-- Changes recorded for later review, written in the transaction of the change itself
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    tenant_id VARCHAR(64) NOT NULL,
+    action VARCHAR(64) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    details JSONB NOT NULL DEFAULT 'null',
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_tenant_recorded_at ON audit_log(tenant_id, recorded_at);
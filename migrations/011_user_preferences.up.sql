@@ -0,0 +1,10 @@
+-- Preferences of each user, such as how their task listing is shown by default
+CREATE TABLE IF NOT EXISTS user_preferences (
+    tenant_id VARCHAR(64) NOT NULL,
+    user_id VARCHAR(255) NOT NULL,
+    task_view JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (tenant_id, user_id),
+    CONSTRAINT user_preferences_tenant_user_fkey FOREIGN KEY (tenant_id, user_id)
+        REFERENCES users (tenant_id, id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- Let sync consumers page through the users of a tenant in the order they were updated
+CREATE INDEX IF NOT EXISTS idx_users_tenant_updated_at ON users(tenant_id, updated_at, id);
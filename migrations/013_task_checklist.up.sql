@@ -0,0 +1,2 @@
+-- Checklist items of each task, stored with the task as a JSON array
+ALTER TABLE tasks ADD COLUMN checklist JSONB NOT NULL DEFAULT '[]';
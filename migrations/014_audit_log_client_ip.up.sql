@@ -0,0 +1,2 @@
+-- Address of the client that requested the change, when it came in over HTTP
+ALTER TABLE audit_log ADD COLUMN client_ip INET;
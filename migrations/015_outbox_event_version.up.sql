@@ -0,0 +1,3 @@
+-- Version of the payload's schema, so that events recorded before a new version are
+-- still delivered as the version they were written in
+ALTER TABLE outbox_events ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,4 @@
+-- Back PREVENT_DUPLICATE_OPEN_TASKS=true: one open task per user and title, ignoring case.
+-- Not run by the server; apply it before enabling the setting, once no duplicates are open
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tasks_open_title_unique
+    ON tasks (user_id, lower(title)) WHERE NOT completed;
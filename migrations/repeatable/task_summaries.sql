@@ -0,0 +1,14 @@
+-- Tasks created and completed per tenant and UTC hour, read by GET /stats/tasks
+-- Truncated in UTC explicitly; date_trunc on TIMESTAMPTZ would use the session time zone
+DROP VIEW IF EXISTS task_summaries;
+CREATE VIEW task_summaries AS
+SELECT tenant_id, hour, SUM(created)::BIGINT AS created, SUM(completed)::BIGINT AS completed
+FROM (
+    SELECT tenant_id, date_trunc('hour', created_at AT TIME ZONE 'UTC') AS hour,
+           1 AS created, 0 AS completed
+    FROM tasks
+    UNION ALL
+    SELECT tenant_id, date_trunc('hour', completed_at AT TIME ZONE 'UTC'), 0, 1
+    FROM tasks WHERE completed_at IS NOT NULL
+) events
+GROUP BY tenant_id, hour;
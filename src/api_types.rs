@@ -0,0 +1,540 @@
+//! Request and response bodies of the HTTP API
+//!
+//! Shared by the server handlers and the typed client (`client` feature), so both
+//! sides serialize exactly the same structures.
+//!
+//! Fields are camelCase on the wire (`userId`), as the API guidelines require; query
+//! parameters keep their `snake_case` names. Clients of earlier releases get the
+//! `snake_case` bodies they expect through [`casing`](crate::shared::infrastructure::casing)
+//! until the cutover completes.
+
+use crate::shared::domain::timestamp;
+use serde::{Deserialize, Serialize};
+
+/// HTTP response body for a user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserResponse {
+    /// User ID
+    pub id: String,
+    /// User name
+    pub name: String,
+    /// Email address
+    pub email: String,
+    /// Tasks owned by the user, present only when requested via `?embed=task_count`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_count: Option<u64>,
+}
+
+/// HTTP response body of `GET /users?updated_since=`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSyncResponse {
+    /// Users updated since the watermark, ordered by last update then ID
+    pub items: Vec<UserResponse>,
+    /// `updated_since` of the next call
+    pub watermark: String,
+}
+
+/// HTTP request body for creating a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateUserRequest {
+    /// User name
+    pub name: String,
+    /// Email address
+    pub email: String,
+}
+
+/// HTTP request body for updating a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserRequest {
+    /// New user name
+    pub name: String,
+    /// New email address
+    pub email: String,
+}
+
+/// HTTP request body of `POST /users/{id}/email-change`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailChangeRequest {
+    /// Address to change to once confirmed
+    pub new_email: String,
+}
+
+/// HTTP response body of `POST /users/{id}/email-change`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailChangeResponse {
+    /// End of the confirmation window (RFC 3339)
+    pub expires_at: String,
+    /// Confirmation token, only returned with `EMAIL_CHANGE_TOKEN_IN_RESPONSE` (dev mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// HTTP request body of `POST /users/email-change/confirm`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmEmailChangeRequest {
+    /// Token issued by the email change request
+    pub token: String,
+}
+
+/// HTTP response body of a dry-run deletion (`?dry_run=true`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResponse {
+    /// Always `true`; nothing was deleted
+    pub dry_run: bool,
+    /// Entities the same request without `dry_run` would delete
+    pub would_delete: DeletionCounts,
+}
+
+/// Number of deleted entities per kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletionCounts {
+    /// Users
+    pub users: u64,
+    /// Tasks
+    pub tasks: u64,
+}
+
+/// HTTP response body for a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskResponse {
+    /// Task ID
+    pub id: String,
+    /// Owning user ID
+    pub user_id: String,
+    /// Normalized title
+    pub title: String,
+    /// Free-form description
+    pub description: String,
+    /// Whether the task is completed
+    pub completed: bool,
+    /// Last persisted modification time
+    #[serde(default, serialize_with = "timestamp::serialize_option")]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Owning user, present only when requested via `?embed=user`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<TaskOwnerResponse>,
+    /// Description rendered as sanitized HTML, present only when requested via
+    /// `?embed=description_html`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_html: Option<String>,
+    /// Checklist items, in the order they were added
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItemResponse>,
+}
+
+/// Item of a task's checklist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistItemResponse {
+    /// Item ID
+    pub id: String,
+    /// What is to be done
+    pub text: String,
+    /// Whether the item is checked
+    pub done: bool,
+}
+
+/// HTTP request body of `POST /tasks/{id}/checklist`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddChecklistItemRequest {
+    /// What is to be done, 1 to 200 characters
+    pub text: String,
+}
+
+/// HTTP request body of `PATCH /tasks/{id}/checklist/{item_id}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateChecklistItemRequest {
+    /// Whether the item is checked
+    pub done: bool,
+}
+
+/// Embedded owner of a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskOwnerResponse {
+    /// User ID
+    pub id: String,
+    /// User name
+    pub name: String,
+}
+
+/// HTTP request body for creating a task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTaskRequest {
+    /// Owning user ID
+    pub user_id: String,
+    /// Title, normalized before validation
+    pub title: String,
+    /// Free-form description
+    pub description: String,
+    /// Tasks are created open: `true` is rejected, as only imports create completed
+    /// tasks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<bool>,
+}
+
+/// HTTP request body of `PUT /tasks/{id}`, creating or updating the task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertTaskRequest {
+    /// Owning user ID; must match the owner of an existing task
+    pub user_id: String,
+    /// Title, normalized before validation
+    pub title: String,
+    /// Free-form description
+    pub description: String,
+}
+
+/// HTTP request body of `POST /tasks/{id}/attachments`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAttachmentRequest {
+    /// File name shown to users
+    pub filename: String,
+    /// Media type, one of the server's `ATTACHMENT_CONTENT_TYPES`
+    pub content_type: String,
+    /// Size of the file in bytes, at most the server's `ATTACHMENT_MAX_SIZE_BYTES`
+    pub size: u64,
+}
+
+/// HTTP response body for an attachment of a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentResponse {
+    /// Attachment ID
+    pub id: String,
+    /// Task the file is attached to
+    pub task_id: String,
+    /// File name shown to users
+    pub filename: String,
+    /// Media type
+    pub content_type: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Creation time
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Short-lived URL to download the file, present when listing attachments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+/// HTTP response body of `POST /tasks/{id}/attachments`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentUploadResponse {
+    /// The registered attachment
+    pub attachment: AttachmentResponse,
+    /// Short-lived URL to `PUT` the file to, with the declared `Content-Type`
+    pub upload_url: String,
+    /// End of the validity of `upload_url`
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub upload_expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Query parameters of `GET /tasks`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskQuery {
+    /// Filter by user ID (optional; omit to list all tasks)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Only completed (`true`) or open (`false`) tasks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<bool>,
+    /// Only tasks whose title or description contains this text (at least 2
+    /// characters), ignoring case
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// Comma-separated relations to embed (`user`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embed: Option<String>,
+    /// Comma-separated fields to return; `id` is always included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<String>,
+    /// Tasks per page, at most the server's `MAX_PAGE_SIZE` (also the default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Tasks to skip, at most the server's `MAX_OFFSET`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+}
+
+/// Entry of `GET /admin/overview`: a user with their most recent tasks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOverviewResponse {
+    /// The user
+    pub user: UserResponse,
+    /// The user's most recently created tasks, newest first
+    pub recent_tasks: Vec<TaskResponse>,
+}
+
+/// HTTP request body of `POST /users/with-task`, creating a user with their first task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardUserRequest {
+    /// User name
+    pub name: String,
+    /// Email address
+    pub email: String,
+    /// The user's first task
+    pub initial_task: InitialTaskRequest,
+}
+
+/// First task of a user created through `POST /users/with-task`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitialTaskRequest {
+    /// Title, normalized before validation
+    pub title: String,
+    /// Free-form description
+    pub description: String,
+}
+
+/// HTTP response body of `POST /users/with-task`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardedUserResponse {
+    /// The new user
+    pub user: UserResponse,
+    /// The user's first task
+    pub task: TaskResponse,
+}
+
+/// HTTP request body of `POST /users/{id}/tasks/reassign`, moving the user's tasks
+/// to another user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReassignTasksRequest {
+    /// ID of the user receiving the tasks
+    pub to_user_id: String,
+    /// Move open tasks only (the default), leaving completed ones with their owner
+    #[serde(default = "default_only_open")]
+    pub only_open: bool,
+}
+
+fn default_only_open() -> bool {
+    true
+}
+
+/// HTTP response body of `POST /users/{id}/tasks/reassign`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReassignTasksResponse {
+    /// Number of tasks moved
+    pub reassigned: u64,
+}
+
+/// Query parameters of `GET /users/{id}/tasks`; `completed`, `sort` and `limit` take
+/// the user's task view preferences when unset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserTaskQuery {
+    /// Only completed (`true`) or open (`false`) tasks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<bool>,
+    /// Only tasks whose title or description contains this text (at least 2
+    /// characters), ignoring case
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// Order: `id`, `title` or `recently_updated`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Comma-separated fields to return; `id` is always included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<String>,
+    /// Tasks per page, at most the server's `MAX_PAGE_SIZE`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Tasks to skip, at most the server's `MAX_OFFSET`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+}
+
+/// HTTP request body of `PUT /users/{id}/preferences`, replacing the user's task view
+/// preferences; unset fields take their defaults and unknown fields are rejected
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TaskViewPreferencesRequest {
+    /// Order of the user's task listing: `id` (the default), `title` or
+    /// `recently_updated`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Whether completed tasks are listed (the default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_completed: Option<bool>,
+    /// Tasks per page, 1 to 100; the server's default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u32>,
+}
+
+/// HTTP response body of `GET` and `PUT /users/{id}/preferences`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskViewPreferencesResponse {
+    /// Order of the user's task listing
+    pub sort: String,
+    /// Whether completed tasks are listed
+    pub show_completed: bool,
+    /// Tasks per page; `null` for the server's default
+    pub page_size: Option<u32>,
+}
+
+/// HTTP response body of `POST /tasks/import`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportTasksResponse {
+    /// Number of tasks created
+    pub imported: u64,
+    /// Rows not imported, by line
+    pub failed: Vec<ImportFailureResponse>,
+}
+
+/// A row of `POST /tasks/import` that was not imported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFailureResponse {
+    /// Line of the file the row starts on, from 1 (the header)
+    pub line: u64,
+    /// Why the row was not imported
+    pub reason: String,
+}
+
+/// HTTP response body of `GET /stats/tasks`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatsResponse {
+    /// Window covered, in hours (e.g. `24h`)
+    pub window: String,
+    /// One bucket per UTC hour of the window, oldest first
+    pub buckets: Vec<TaskStatsBucket>,
+}
+
+/// Task activity within one UTC hour
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatsBucket {
+    /// Start of the hour
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub hour: chrono::DateTime<chrono::Utc>,
+    /// Tasks created within the hour
+    pub created: u64,
+    /// Tasks completed within the hour
+    pub completed: u64,
+}
+
+/// HTTP request body of `POST /users/{id}/tokens`, issuing an API token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenRequest {
+    /// Name of the token, e.g. the service it is for
+    pub name: String,
+    /// End of the validity of the token (RFC 3339); valid until revoked when omitted
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "timestamp::serialize_option"
+    )]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// HTTP response body for an API token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenResponse {
+    /// Token ID
+    pub id: String,
+    /// Name of the token
+    pub name: String,
+    /// When the token was issued
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// End of the validity of the token, `null` when it has none
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Last use of the token, to within a minute; `null` before the first
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// When the token was revoked, `null` while it is not
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The token, sent as `Authorization: Bearer <token>`; only returned when it is
+    /// issued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// HTTP request body of `POST /webhooks`, subscribing an endpoint to events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequest {
+    /// `http` or `https` URL events are posted to
+    pub url: String,
+    /// Secret deliveries are signed with, at least 16 characters; generated when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Event types delivered, e.g. `tasks.reassigned`; every event when empty
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+/// HTTP request body of `PUT /webhooks/{id}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWebhookRequest {
+    /// `http` or `https` URL events are posted to
+    pub url: String,
+    /// Event types delivered; every event when empty
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    /// Whether events are delivered; reactivating forgets the failures
+    pub active: bool,
+}
+
+/// HTTP response body for a webhook subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookResponse {
+    /// Subscription ID
+    pub id: String,
+    /// URL events are posted to
+    pub url: String,
+    /// Event types delivered; every event when empty
+    pub event_types: Vec<String>,
+    /// Whether events are delivered
+    pub active: bool,
+    /// Failed deliveries since the last successful one
+    pub consecutive_failures: u32,
+    /// Outcome of the latest delivery attempt, `null` before the first
+    pub last_delivery: Option<WebhookDeliveryResponse>,
+    /// Signing secret, only returned when the subscription is created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+/// Outcome of a delivery attempt to a webhook subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveryResponse {
+    /// When the attempt ended
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub at: chrono::DateTime<chrono::Utc>,
+    /// Whether the endpoint accepted the event with a `2xx` status
+    pub succeeded: bool,
+    /// Status the endpoint answered with; `null` when no response arrived
+    pub status_code: Option<u16>,
+    /// Why the attempt failed; `null` when it succeeded
+    pub error: Option<String>,
+}
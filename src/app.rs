@@ -0,0 +1,1884 @@
+//! Application composition: feature selection, state wiring and router assembly
+
+use crate::features::api_token::application::{
+    CreateApiTokenCommand, CreateApiTokenUseCase, IssuedApiToken,
+};
+use crate::features::api_token::domain::ApiTokenRepository;
+use crate::features::api_token::infrastructure::{
+    self as api_token_infrastructure, http as api_token_http, CachedApiTokenRepository,
+    InMemoryApiTokenRepository, PgApiTokenRepository, TokenAuth,
+};
+use crate::features::api_token::{self, ApiTokenState};
+use crate::features::task::application::complete_task::ATOMIC_COMPLETE;
+use crate::features::task::domain::{
+    AttachmentRepository, BlobStorage, TaskRepository, TaskViewPreferencesRepository, UnitOfWork,
+};
+use crate::features::task::infrastructure::{
+    http as task_http, BlobCleanupJob, CachedTaskRepository, InMemoryAttachmentRepository,
+    InMemoryTaskRepository, InMemoryTaskViewPreferencesRepository, InMemoryUnitOfWork,
+    InstrumentedAttachmentRepository, InstrumentedTaskRepository,
+    InstrumentedTaskViewPreferencesRepository, LocalBlobStorage, MarkdownRenderer,
+    PgAttachmentRepository, PgTaskRepository, PgTaskViewPreferencesRepository, PgUnitOfWork,
+};
+use crate::features::task::{self, AttachmentSettings, OnboardingSettings, TaskState};
+use crate::features::user::domain::{
+    EmailChangeNotifier, EmailChangeRepository, UserDependents, UserRepository,
+};
+use crate::features::user::infrastructure::{
+    http as user_http, InMemoryEmailChangeRepository, InMemoryUserRepository,
+    InstrumentedEmailChangeRepository, InstrumentedUserRepository, PgEmailChangeRepository,
+    PgUserRepository, WebhookEmailChangeNotifier,
+};
+use crate::features::user::{self, EmailChangeSettings, UserState};
+use crate::features::webhook::domain::{EndpointResolver, WebhookDeliveries, WebhookRepository};
+use crate::features::webhook::infrastructure::{
+    http as webhook_http, DnsResolver, HttpWebhookSender, InMemoryWebhookRepository,
+    PgWebhookRepository, WebhookDeliveryJob,
+};
+use crate::features::webhook::{self, DeliverySettings, WebhookState};
+use crate::shared::application::{DetachedWrites, FeatureToggles};
+use crate::shared::domain::{DomainError, EmailSender, TenantId};
+use crate::shared::infrastructure::{
+    admin::{self, AdminState},
+    backpressure,
+    cache_control::{self, CachePolicy},
+    casing::{self, FieldCasing},
+    client_ip,
+    config::Config,
+    email::ConsoleEmailSender,
+    event_schema,
+    feature::{self, FeatureRegistry, ManifestRoute},
+    feature_toggle,
+    http::{self, health_check, health_details, RuntimeInfo},
+    http_client::{HttpClientConfig, ReqwestHttp},
+    instrumentation::{self, UseCaseBudgets},
+    jobs::{self, JobRunner, JobStatuses},
+    log_level::{self, LogLevel},
+    maintenance::{self, CleanupTask, MaintenanceJob, MaintenanceService, PgAuditLogCleanup},
+    rate_limit::{self, RateLimiter},
+    request_context::{self, ContextSource, RequestContext},
+    tenant::{self, TenantPolicy},
+};
+use axum::{extract::DefaultBodyLimit, middleware, routing::get, Router};
+use metrics_exporter_prometheus::PrometheusHandle;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Instant;
+use tower::ServiceBuilder;
+use tower_http::{
+    decompression::RequestDecompressionLayer, timeout::TimeoutLayer, trace::TraceLayer,
+};
+
+/// Request `Content-Encoding`s decoded when `ACCEPT_COMPRESSED_REQUESTS` is on
+const REQUEST_ENCODINGS: &[&str] = &["gzip"];
+
+/// Most API tokens kept in the token cache (`API_TOKEN_CACHE_TTL_SECS`)
+const API_TOKEN_CACHE_CAPACITY: u64 = 10_000;
+
+/// Every feature known to the application with the features it depends on
+const FEATURES: &[(&str, &[&str])] = &[
+    (user::NAME, user::DEPENDS_ON),
+    (task::NAME, task::DEPENDS_ON),
+    (webhook::NAME, webhook::DEPENDS_ON),
+    (api_token::NAME, api_token::DEPENDS_ON),
+];
+
+/// Source of repositories; only called for features that are enabled
+pub trait RepositoryProvider {
+    /// Repository backing the user feature
+    fn user_repository(&self) -> Arc<dyn UserRepository>;
+    /// Repository of pending email changes, part of the user feature
+    fn email_change_repository(&self) -> Arc<dyn EmailChangeRepository>;
+    /// Repository backing the task feature
+    fn task_repository(&self) -> Arc<dyn TaskRepository>;
+    /// Repository of task attachments, part of the task feature
+    fn attachment_repository(&self) -> Arc<dyn AttachmentRepository>;
+    /// Repository of the task view preferences of users, part of the task feature
+    fn task_view_preferences_repository(&self) -> Arc<dyn TaskViewPreferencesRepository>;
+    /// Unit of work writing users and tasks together, part of the task feature
+    fn unit_of_work(&self) -> Arc<dyn UnitOfWork>;
+    /// Repository of webhook subscriptions, backing the webhook feature
+    fn webhook_repository(&self) -> Arc<dyn WebhookRepository>;
+    /// Outbox events on their way to webhook subscriptions, part of the webhook feature
+    fn webhook_deliveries(&self) -> Arc<dyn WebhookDeliveries>;
+    /// Repository of API tokens, backing the API token feature
+    fn api_token_repository(&self) -> Arc<dyn ApiTokenRepository>;
+    /// Tasks cleaning up the stale data of the enabled `features`; none by default
+    fn cleanup_tasks(&self, _features: &[&str]) -> Vec<Arc<dyn CleanupTask>> {
+        Vec::new()
+    }
+}
+
+/// `PostgreSQL`-backed repositories sharing one pool
+pub struct PgRepositories {
+    pool: PgPool,
+}
+
+impl PgRepositories {
+    /// Create a provider handing out repositories on `pool`
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl RepositoryProvider for PgRepositories {
+    fn user_repository(&self) -> Arc<dyn UserRepository> {
+        Arc::new(PgUserRepository::new(self.pool.clone()))
+    }
+
+    fn email_change_repository(&self) -> Arc<dyn EmailChangeRepository> {
+        Arc::new(PgEmailChangeRepository::new(self.pool.clone()))
+    }
+
+    fn task_repository(&self) -> Arc<dyn TaskRepository> {
+        Arc::new(PgTaskRepository::new(self.pool.clone()))
+    }
+
+    fn attachment_repository(&self) -> Arc<dyn AttachmentRepository> {
+        Arc::new(PgAttachmentRepository::new(self.pool.clone()))
+    }
+
+    fn task_view_preferences_repository(&self) -> Arc<dyn TaskViewPreferencesRepository> {
+        Arc::new(PgTaskViewPreferencesRepository::new(self.pool.clone()))
+    }
+
+    fn unit_of_work(&self) -> Arc<dyn UnitOfWork> {
+        Arc::new(PgUnitOfWork::new(self.pool.clone()))
+    }
+
+    fn webhook_repository(&self) -> Arc<dyn WebhookRepository> {
+        Arc::new(PgWebhookRepository::new(self.pool.clone()))
+    }
+
+    fn webhook_deliveries(&self) -> Arc<dyn WebhookDeliveries> {
+        Arc::new(PgWebhookRepository::new(self.pool.clone()))
+    }
+
+    fn api_token_repository(&self) -> Arc<dyn ApiTokenRepository> {
+        Arc::new(PgApiTokenRepository::new(self.pool.clone()))
+    }
+
+    fn cleanup_tasks(&self, features: &[&str]) -> Vec<Arc<dyn CleanupTask>> {
+        // Every feature may write to the audit log
+        let mut tasks: Vec<Arc<dyn CleanupTask>> =
+            vec![Arc::new(PgAuditLogCleanup::new(self.pool.clone()))];
+        if features.contains(&user::NAME) {
+            tasks.push(Arc::new(PgEmailChangeRepository::new(self.pool.clone())));
+        }
+        if features.contains(&webhook::NAME) {
+            tasks.push(Arc::new(PgWebhookRepository::new(self.pool.clone())));
+        }
+        tasks
+    }
+}
+
+/// In-memory repositories for tests and examples, empty when created
+///
+/// Every call hands out the same instance, so state is shared across features; the
+/// events units of work record are the ones the webhooks deliver.
+#[derive(Default)]
+pub struct InMemoryRepositories {
+    users: Arc<InMemoryUserRepository>,
+    email_changes: Arc<InMemoryEmailChangeRepository>,
+    tasks: Arc<InMemoryTaskRepository>,
+    attachments: Arc<InMemoryAttachmentRepository>,
+    task_view_preferences: Arc<InMemoryTaskViewPreferencesRepository>,
+    webhooks: Arc<InMemoryWebhookRepository>,
+    api_tokens: Arc<InMemoryApiTokenRepository>,
+}
+
+impl RepositoryProvider for InMemoryRepositories {
+    fn user_repository(&self) -> Arc<dyn UserRepository> {
+        Arc::clone(&self.users) as _
+    }
+
+    fn email_change_repository(&self) -> Arc<dyn EmailChangeRepository> {
+        Arc::clone(&self.email_changes) as _
+    }
+
+    fn task_repository(&self) -> Arc<dyn TaskRepository> {
+        Arc::clone(&self.tasks) as _
+    }
+
+    fn attachment_repository(&self) -> Arc<dyn AttachmentRepository> {
+        Arc::clone(&self.attachments) as _
+    }
+
+    fn task_view_preferences_repository(&self) -> Arc<dyn TaskViewPreferencesRepository> {
+        Arc::clone(&self.task_view_preferences) as _
+    }
+
+    fn unit_of_work(&self) -> Arc<dyn UnitOfWork> {
+        let unit_of_work =
+            InMemoryUnitOfWork::new(Arc::clone(&self.users), Arc::clone(&self.tasks));
+        Arc::new(unit_of_work.with_outbox(Arc::clone(self.webhooks.outbox())))
+    }
+
+    fn webhook_repository(&self) -> Arc<dyn WebhookRepository> {
+        Arc::clone(&self.webhooks) as _
+    }
+
+    fn webhook_deliveries(&self) -> Arc<dyn WebhookDeliveries> {
+        Arc::clone(&self.webhooks) as _
+    }
+
+    fn api_token_repository(&self) -> Arc<dyn ApiTokenRepository> {
+        Arc::clone(&self.api_tokens) as _
+    }
+
+    /// The in-memory audit trails record no time, so they are never cleaned up
+    fn cleanup_tasks(&self, features: &[&str]) -> Vec<Arc<dyn CleanupTask>> {
+        let mut tasks: Vec<Arc<dyn CleanupTask>> = Vec::new();
+        if features.contains(&user::NAME) {
+            tasks.push(Arc::clone(&self.email_changes) as _);
+        }
+        if features.contains(&webhook::NAME) {
+            tasks.push(Arc::clone(&self.webhooks) as _);
+        }
+        tasks
+    }
+}
+
+/// Application state; a feature's state is `None` when the feature is disabled
+///
+/// Generic over the user and task repositories like the feature states: the
+/// default, built by [`AppState::build`], holds them as `Arc<dyn ..>`, while
+/// [`AppState::from_states`] takes states wired to concrete repositories.
+pub struct AppState<U: ?Sized = dyn UserRepository, T: ?Sized = dyn TaskRepository> {
+    pub(crate) user: Option<Arc<UserState<U>>>,
+    pub(crate) task: Option<Arc<TaskState<T, U>>>,
+    pub(crate) webhook: Option<Arc<WebhookState>>,
+    pub(crate) api_token: Option<Arc<ApiTokenState>>,
+    pub(crate) jobs: JobStatuses,
+    pub(crate) blob_cleanup: Option<BlobCleanupJob>,
+    /// Cleanup of stale data, served at `POST /admin/maintenance/cleanup`
+    pub(crate) maintenance: Arc<MaintenanceService>,
+    pub(crate) tenant_policy: TenantPolicy,
+    /// Budgets the use cases called by the handlers are measured against
+    pub(crate) budgets: Arc<UseCaseBudgets>,
+    /// Rollouts of new code paths, served at `/admin/flags`
+    pub(crate) feature_toggles: Arc<FeatureToggles>,
+    /// Filter of the global subscriber, served at `/admin/log-level` when attached
+    pub(crate) log_level: Option<Arc<LogLevel>>,
+    /// When the state was built, reported as the uptime by `GET /health/details`
+    pub(crate) started_at: Instant,
+    /// Writes of the use cases that outlive dropped requests, drained at shutdown
+    pub(crate) detached_writes: DetachedWrites,
+}
+
+impl AppState {
+    /// Wire the state of every enabled feature, constructing only the repositories they need
+    ///
+    /// With `METRICS_DB` every repository is wrapped in its instrumented decorator.
+    ///
+    /// # Errors
+    /// Fails when the feature configuration is invalid (see [`enabled_features`]), API
+    /// tokens are required without the `api_token` feature, the outbound HTTP client
+    /// for `EMAIL_CHANGE_WEBHOOK_URL` or the webhooks cannot be built or the blob
+    /// storage is misconfigured, or `FEATURE_FLAGS` names an unknown flag.
+    pub fn build(config: &Config, repositories: &dyn RepositoryProvider) -> anyhow::Result<Self> {
+        let enabled = enabled_features(config)?;
+        let feature_toggles = Arc::new(feature_toggles(config)?);
+        let detached_writes =
+            DetachedWrites::default().propagating(instrumentation::in_current_request);
+        if config.api_token_required && !enabled.contains(&api_token::NAME) {
+            anyhow::bail!("API_TOKEN_REQUIRED requires the '{}' feature", api_token::NAME);
+        }
+        let maintenance = maintenance_service(config, repositories, &enabled);
+        let webhook = enabled.contains(&webhook::NAME).then(|| webhook_state(config, repositories));
+        let webhook = webhook.transpose()?.map(Arc::new);
+        // Every other feature depends on users, so only build the repository when needed
+        let Some(mut user_repository) =
+            enabled.contains(&user::NAME).then(|| repositories.user_repository())
+        else {
+            return Ok(Self {
+                user: None,
+                task: None,
+                webhook,
+                api_token: None,
+                jobs: JobStatuses::default(),
+                blob_cleanup: None,
+                maintenance,
+                tenant_policy: config.tenant_policy(),
+                budgets: Arc::new(UseCaseBudgets::from_config(config)),
+                feature_toggles,
+                log_level: None,
+                started_at: Instant::now(),
+                detached_writes,
+            });
+        };
+        let mut email_changes = repositories.email_change_repository();
+        let mut tasks = enabled.contains(&task::NAME).then(|| repositories.task_repository());
+        let mut attachments = tasks.as_ref().map(|_| repositories.attachment_repository());
+        if config.metrics_db {
+            user_repository = Arc::new(InstrumentedUserRepository::new(user_repository));
+            email_changes = Arc::new(InstrumentedEmailChangeRepository::new(email_changes));
+            tasks = tasks.map(|tasks| {
+                Arc::new(InstrumentedTaskRepository::new(tasks)) as Arc<dyn TaskRepository>
+            });
+            attachments = attachments.map(|attachments| {
+                Arc::new(InstrumentedAttachmentRepository::new(attachments)) as _
+            });
+        }
+        // Outside the instrumentation, which then only times the calls the cache misses
+        if let Some(ttl) = config.task_cache_ttl() {
+            tasks = tasks.map(|tasks| {
+                let mut cached = CachedTaskRepository::new(tasks, ttl, config.task_cache_capacity);
+                if let Some(negative_ttl) = config.task_cache_negative_ttl() {
+                    cached = cached.with_negative_ttl(negative_ttl);
+                }
+                Arc::new(cached) as Arc<dyn TaskRepository>
+            });
+        }
+        let attachments = match attachments {
+            Some(repository) => {
+                Some(AttachmentSettings { repository, storage: blob_storage(config)? })
+            }
+            None => None,
+        };
+        let blob_cleanup = attachments.as_ref().map(blob_cleanup_job);
+        let dependents = tasks.clone().map(|tasks| tasks as Arc<dyn UserDependents>);
+        let email_sender = email_sender(config)?;
+        let (api_token, token_cache) = if enabled.contains(&api_token::NAME) {
+            let (state, cache) = api_token_state(config, repositories, &user_repository);
+            (Some(Arc::new(state)), cache)
+        } else {
+            (None, None)
+        };
+        let deps =
+            UserDeps { repository: &user_repository, dependents, email_changes, token_cache };
+        let user = user_state(config, deps, Arc::clone(&email_sender), &detached_writes)?;
+        Ok(Self {
+            user: Some(Arc::new(user)),
+            task: tasks.zip(attachments).map(|(tasks, attachments)| {
+                let renderer = Arc::new(MarkdownRenderer);
+                let onboarding =
+                    OnboardingSettings { unit_of_work: repositories.unit_of_work(), email_sender };
+                Arc::new(TaskState::new(
+                    config,
+                    &tasks,
+                    &user_repository,
+                    renderer,
+                    attachments,
+                    task_view_preferences(config, repositories),
+                    onboarding,
+                    &feature_toggles,
+                    &detached_writes,
+                ))
+            }),
+            webhook,
+            api_token,
+            jobs: JobStatuses::default(),
+            blob_cleanup,
+            maintenance,
+            tenant_policy: config.tenant_policy(),
+            budgets: Arc::new(UseCaseBudgets::from_config(config)),
+            feature_toggles,
+            log_level: None,
+            started_at: Instant::now(),
+            detached_writes,
+        })
+    }
+}
+
+impl<U: UserRepository + ?Sized, T: TaskRepository + ?Sized> AppState<U, T> {
+    /// Application state of feature states wired by hand, e.g. to concrete repositories
+    /// so that their calls are dispatched statically; `None` disables a feature
+    ///
+    /// No background job or cleanup task is registered: register a [`BlobCleanupJob`]
+    /// on the [`Self::job_runner`] when attachments are served. The feature flags are
+    /// those of the task state, and the detached writes drained at shutdown those of
+    /// the task state, else of the user state: wire both to the same ones.
+    #[must_use]
+    pub fn from_states(
+        config: &Config,
+        user: Option<Arc<UserState<U>>>,
+        task: Option<Arc<TaskState<T, U>>>,
+    ) -> Self {
+        let feature_toggles =
+            task.as_ref().map_or_else(Arc::default, |task| Arc::clone(&task.feature_toggles));
+        let detached_writes = task
+            .as_ref()
+            .map(|task| task.detached_writes.clone())
+            .or_else(|| user.as_ref().map(|user| user.detached_writes.clone()))
+            .unwrap_or_default();
+        Self {
+            user,
+            task,
+            webhook: None,
+            api_token: None,
+            jobs: JobStatuses::default(),
+            blob_cleanup: None,
+            maintenance: Arc::default(),
+            tenant_policy: config.tenant_policy(),
+            budgets: Arc::new(UseCaseBudgets::from_config(config)),
+            feature_toggles,
+            log_level: None,
+            started_at: Instant::now(),
+            detached_writes,
+        }
+    }
+
+    /// Serve `/admin/log-level` on the admin router, changing the filter of `log_level`
+    #[must_use]
+    pub fn with_log_level(self, log_level: LogLevel) -> Self {
+        Self { log_level: Some(Arc::new(log_level)), ..self }
+    }
+
+    /// Runner of the background jobs of the enabled features, recording into
+    /// [`Self::jobs`]; register further jobs before starting it
+    #[must_use]
+    pub fn job_runner(&self) -> JobRunner {
+        let mut runner = JobRunner::new(self.jobs.clone());
+        if let Some(job) = &self.blob_cleanup {
+            runner = runner.register(job.clone());
+        }
+        if let Some(webhook) = &self.webhook {
+            runner = runner.register(WebhookDeliveryJob::new(Arc::clone(&webhook.deliver_events)));
+        }
+        let maintenance = &self.maintenance;
+        if let Some(interval) = maintenance.policy().interval.filter(|_| !maintenance.is_empty()) {
+            runner = runner.register(MaintenanceJob::new(Arc::clone(maintenance), interval));
+        }
+        runner
+    }
+
+    /// Status of background jobs, served at `GET /internal/jobs` on the admin
+    /// listener; hand it to any other [`JobRunner`] running them
+    #[must_use]
+    pub fn jobs(&self) -> &JobStatuses {
+        &self.jobs
+    }
+
+    /// Writes the use cases run detached from their requests; wait for them with
+    /// [`DetachedWrites::drained`] once the servers stopped taking requests
+    #[must_use]
+    pub fn detached_writes(&self) -> &DetachedWrites {
+        &self.detached_writes
+    }
+}
+
+/// Email sender selected by `SMTP_URL`: the log when empty, otherwise the SMTP relay
+/// (`smtp` feature), retrying transient failures
+fn email_sender(config: &Config) -> anyhow::Result<Arc<dyn EmailSender>> {
+    if config.smtp_url.is_empty() {
+        return Ok(Arc::new(ConsoleEmailSender));
+    }
+    #[cfg(feature = "smtp")]
+    {
+        use crate::shared::infrastructure::email::{RetryingEmailSender, SmtpEmailSender};
+        use crate::shared::infrastructure::http_client::RetryPolicy;
+        let smtp = Arc::new(SmtpEmailSender::new(&config.smtp_url, &config.smtp_from)?);
+        Ok(Arc::new(RetryingEmailSender::new(smtp, RetryPolicy::default())))
+    }
+    #[cfg(not(feature = "smtp"))]
+    anyhow::bail!("SMTP_URL requires building with the smtp feature")
+}
+
+/// What the user state is wired to, besides the configuration
+struct UserDeps<'a> {
+    repository: &'a Arc<dyn UserRepository>,
+    /// Counts the tasks of users, with the task feature
+    dependents: Option<Arc<dyn UserDependents>>,
+    email_changes: Arc<dyn EmailChangeRepository>,
+    /// Cache of API tokens, which deleting a user cascades to
+    token_cache: Option<Arc<dyn UserDependents>>,
+}
+
+/// User state wired to `deps`, sending new users email through `email_sender` and
+/// running deletions on `detached_writes`
+fn user_state(
+    config: &Config,
+    deps: UserDeps<'_>,
+    email_sender: Arc<dyn EmailSender>,
+    detached_writes: &DetachedWrites,
+) -> anyhow::Result<UserState> {
+    let email_changes = EmailChangeSettings {
+        repository: deps.email_changes,
+        notifier: email_change_notifier(config)?,
+        token_ttl: config.email_change_token_ttl(),
+        token_in_response: config.email_change_token_in_response,
+    };
+    let mut user = UserState::new(
+        deps.repository,
+        deps.dependents,
+        email_changes,
+        config.page_limits(),
+        email_sender,
+        detached_writes,
+    );
+    // The cache would otherwise still accept the tokens of deleted users
+    if let Some(cache) = deps.token_cache {
+        user.delete_user = user.delete_user.also_forgetting(cache);
+    }
+    Ok(user)
+}
+
+/// Notifier posting email change tokens to `EMAIL_CHANGE_WEBHOOK_URL`, if set
+fn email_change_notifier(
+    config: &Config,
+) -> anyhow::Result<Option<Arc<dyn EmailChangeNotifier>>> {
+    if config.email_change_webhook_url.is_empty() {
+        return Ok(None);
+    }
+    let http = Arc::new(ReqwestHttp::new(&HttpClientConfig::default())?);
+    let url = config.email_change_webhook_url.clone();
+    Ok(Some(Arc::new(WebhookEmailChangeNotifier::new(http, url))))
+}
+
+/// API token state issuing tokens to `users`, looking them up through the token
+/// cache unless `API_TOKEN_CACHE_TTL_SECS` is 0, with the cache, if any, to tell of
+/// user deletions
+fn api_token_state(
+    config: &Config,
+    repositories: &dyn RepositoryProvider,
+    users: &Arc<dyn UserRepository>,
+) -> (ApiTokenState, Option<Arc<dyn UserDependents>>) {
+    let tokens = repositories.api_token_repository();
+    let Some(ttl) = config.api_token_cache_ttl() else {
+        return (ApiTokenState::new(users, &tokens), None);
+    };
+    let cached = Arc::new(CachedApiTokenRepository::new(tokens, ttl, API_TOKEN_CACHE_CAPACITY));
+    let tokens: Arc<dyn ApiTokenRepository> = Arc::clone(&cached) as _;
+    (ApiTokenState::new(users, &tokens), Some(cached))
+}
+
+/// Issue an API token named `name` to the user of `tenant` with `user_id`, for the
+/// `issue-token` command: the API only issues tokens to users holding one already
+///
+/// # Errors
+/// As [`CreateApiTokenUseCase::execute`].
+pub async fn issue_api_token(
+    repositories: &dyn RepositoryProvider,
+    tenant: &TenantId,
+    user_id: &str,
+    name: &str,
+) -> Result<IssuedApiToken, DomainError> {
+    let create = CreateApiTokenUseCase::new(
+        repositories.user_repository(),
+        repositories.api_token_repository(),
+    );
+    let command = CreateApiTokenCommand { name: name.to_owned(), expires_at: None };
+    create.execute(tenant, user_id, command).await
+}
+
+/// Repository of task view preferences, instrumented with `METRICS_DB`
+fn task_view_preferences(
+    config: &Config,
+    repositories: &dyn RepositoryProvider,
+) -> Arc<dyn TaskViewPreferencesRepository> {
+    let preferences = repositories.task_view_preferences_repository();
+    if config.metrics_db {
+        return Arc::new(InstrumentedTaskViewPreferencesRepository::new(preferences));
+    }
+    preferences
+}
+
+/// Job deleting the blobs of deleted attachments from their storage
+fn blob_cleanup_job(attachments: &AttachmentSettings) -> BlobCleanupJob {
+    BlobCleanupJob::new(Arc::clone(&attachments.repository), Arc::clone(&attachments.storage))
+}
+
+/// Feature flags of the use cases, with the percentage each is rolled out to unless
+/// `FEATURE_FLAGS` says otherwise
+const FEATURE_FLAGS: &[(&str, u8)] = &[(ATOMIC_COMPLETE, 100)];
+
+/// Toggles of the [`FEATURE_FLAGS`], rolled out as configured
+///
+/// # Errors
+/// Fails when `FEATURE_FLAGS` names an unknown flag.
+pub fn feature_toggles(config: &Config) -> anyhow::Result<FeatureToggles> {
+    let mut flags = FEATURE_FLAGS.to_vec();
+    for (name, percentage) in &config.feature_flags {
+        let Some(flag) = flags.iter_mut().find(|(known, _)| known == name) else {
+            let known: Vec<_> = FEATURE_FLAGS.iter().map(|&(known, _)| known).collect();
+            anyhow::bail!(
+                "Unknown feature flag '{name}' in FEATURE_FLAGS; known flags: {}",
+                known.join(", ")
+            );
+        };
+        flag.1 = *percentage;
+    }
+    Ok(FeatureToggles::new(&flags))
+}
+
+/// Maintenance service running the cleanup tasks of the `enabled` features
+fn maintenance_service(
+    config: &Config,
+    repositories: &dyn RepositoryProvider,
+    enabled: &[&str],
+) -> Arc<MaintenanceService> {
+    let tasks = repositories.cleanup_tasks(enabled);
+    Arc::new(MaintenanceService::new(config.cleanup_policy()).register_all(tasks))
+}
+
+/// Webhook state signing and posting events with a client of its own, which only
+/// connects to public addresses
+fn webhook_state(
+    config: &Config,
+    repositories: &dyn RepositoryProvider,
+) -> anyhow::Result<WebhookState> {
+    let dns = Arc::new(DnsResolver);
+    let http = ReqwestHttp::with_resolver(&HttpClientConfig::default(), Arc::clone(&dns))?;
+    let http = Arc::new(http);
+    let resolver: Arc<dyn EndpointResolver> = dns;
+    let delivery = DeliverySettings {
+        deliveries: repositories.webhook_deliveries(),
+        sender: Arc::new(HttpWebhookSender::new(http, Arc::clone(&resolver))),
+        policy: config.webhook_delivery_policy(),
+    };
+    Ok(WebhookState::new(&repositories.webhook_repository(), &resolver, delivery))
+}
+
+/// Blob storage of task attachments selected by `BLOB_STORAGE`
+fn blob_storage(config: &Config) -> anyhow::Result<Arc<dyn BlobStorage>> {
+    match config.blob_storage.as_str() {
+        "local" => Ok(Arc::new(LocalBlobStorage::new(&config.blob_local_dir, config.blob_url_ttl())?)),
+        #[cfg(feature = "s3")]
+        "s3" => {
+            use crate::features::task::infrastructure::{S3BlobStorage, S3Settings};
+            Ok(Arc::new(S3BlobStorage::new(S3Settings {
+                endpoint: config.s3_endpoint.clone(),
+                bucket: config.s3_bucket.clone(),
+                region: config.s3_region.clone(),
+                access_key_id: config.s3_access_key_id.clone(),
+                secret_access_key: config.s3_secret_access_key.clone(),
+                path_style: config.s3_path_style,
+                url_ttl: config.blob_url_ttl(),
+            })?))
+        }
+        #[cfg(not(feature = "s3"))]
+        "s3" => anyhow::bail!("BLOB_STORAGE=s3 requires building with the s3 feature"),
+        other => anyhow::bail!("Unknown BLOB_STORAGE '{other}'; expected local or s3"),
+    }
+}
+
+/// Resolve the enabled features: `ENABLED_FEATURES` (all when empty) minus `DISABLED_FEATURES`.
+///
+/// # Errors
+/// Fails on unknown feature names, or when an enabled feature depends on a disabled one.
+pub fn enabled_features(config: &Config) -> anyhow::Result<Vec<&'static str>> {
+    let known = || FEATURES.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ");
+    for (key, names) in [
+        ("ENABLED_FEATURES", &config.enabled_features),
+        ("DISABLED_FEATURES", &config.disabled_features),
+    ] {
+        if let Some(unknown) = names.iter().find(|n| !FEATURES.iter().any(|(name, _)| name == n)) {
+            anyhow::bail!("Unknown feature '{unknown}' in {key}; known features: {}", known());
+        }
+    }
+
+    let enabled: Vec<&'static str> = FEATURES
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| {
+            (config.enabled_features.is_empty()
+                || config.enabled_features.iter().any(|n| n == name))
+                && !config.disabled_features.iter().any(|n| n == name)
+        })
+        .collect();
+
+    for (name, depends_on) in FEATURES.iter().filter(|(name, _)| enabled.contains(name)) {
+        if let Some(missing) = depends_on.iter().find(|dep| !enabled.contains(dep)) {
+            anyhow::bail!("Feature '{name}' depends on feature '{missing}', which is disabled");
+        }
+    }
+    Ok(enabled)
+}
+
+/// Build the application router with the routes of every enabled feature and middleware
+///
+/// The GraphQL endpoint resolves through the boxed feature states, so it is only
+/// served for a state built by [`AppState::build`] (or wired to `Arc<dyn ..>`
+/// repositories by hand).
+///
+/// # Errors
+/// Fails when feature route registrations conflict, or a security header or
+/// `PUBLIC_BASE_URL` is invalid.
+pub fn build_router<U, T>(state: &AppState<U, T>, config: &Config) -> anyhow::Result<Router>
+where
+    U: UserRepository + ?Sized + 'static,
+    T: TaskRepository + ?Sized + 'static,
+{
+    build_router_with(state, config, FeatureRegistry::default())
+}
+
+/// Like [`build_router`], first checking in debug builds that every enabled feature
+/// serves the routes its manifest declares
+///
+/// Run before binding the listener, so a route lost to a wrong path or a missing
+/// state type fails startup naming its feature; see [`FeatureRegistry::check`].
+///
+/// # Errors
+/// Fails when a declared route is duplicated or not routed, and as [`build_router`].
+pub async fn build_router_checked<U, T>(
+    state: &AppState<U, T>,
+    config: &Config,
+) -> anyhow::Result<Router>
+where
+    U: UserRepository + ?Sized + 'static,
+    T: TaskRepository + ?Sized + 'static,
+{
+    if cfg!(debug_assertions) {
+        register_features(state, FeatureRegistry::default()).check().await?;
+    }
+    build_router(state, config)
+}
+
+/// Like [`build_router`], additionally serving the features already registered in `registry`
+///
+/// This is the extension point for features defined outside this crate: they are
+/// nested and conflict-checked together with the built-in ones and share the middleware.
+/// See `examples/custom_feature.rs` for a complete feature.
+///
+/// ```
+/// use axum::{routing::get, Router};
+/// use axum_ddd_template::app::{build_router_with, AppState, InMemoryRepositories};
+/// use axum_ddd_template::shared::infrastructure::config::Config;
+/// use axum_ddd_template::shared::infrastructure::feature::{
+///     ExpectedRoute, FeatureRegistry, FeatureRouter,
+/// };
+///
+/// const NOTE_ROUTES: &[ExpectedRoute] = &[ExpectedRoute::get("/")];
+///
+/// let config = Config::default();
+/// let state = AppState::build(&config, &InMemoryRepositories::default())?;
+/// let notes = FeatureRouter {
+///     name: "note",
+///     prefix: "/notes",
+///     router: Router::new().route("/", get(|| async { "[]" })),
+///     manifest: NOTE_ROUTES,
+/// };
+/// let app = build_router_with(&state, &config, FeatureRegistry::default().register(notes))?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// # Errors
+/// Fails when feature route registrations conflict, or a security header or
+/// `PUBLIC_BASE_URL` is invalid.
+pub fn build_router_with<U, T>(
+    state: &AppState<U, T>,
+    config: &Config,
+    registry: FeatureRegistry<()>,
+) -> anyhow::Result<Router>
+where
+    U: UserRepository + ?Sized + 'static,
+    T: TaskRepository + ?Sized + 'static,
+{
+    let limiter = config.rate_limit_policy().map(|policy| Arc::new(RateLimiter::new(policy)));
+    let api = feature_routes(state, config, registry, limiter.as_ref())?;
+    let mut probes = Router::new().route("/health", get(health_check));
+    if config.health_details {
+        let features = [
+            (user::NAME, state.user.is_some()),
+            (task::NAME, state.task.is_some()),
+            (webhook::NAME, state.webhook.is_some()),
+            (api_token::NAME, state.api_token.is_some()),
+        ];
+        let info = RuntimeInfo {
+            started_at: state.started_at,
+            features: features.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect(),
+        };
+        probes = probes.route("/health/details", get(health_details).with_state(info));
+    }
+    let mut router = Router::new().merge(event_schema::routes()).merge(api);
+    // The dashboard lists users and tasks, so it needs both features
+    #[cfg(feature = "dashboard")]
+    if config.dashboard_enabled && state.user.is_some() && state.task.is_some() {
+        router = router.merge(crate::dashboard::routes());
+    }
+    #[cfg(all(feature = "dev-tools", debug_assertions))]
+    if config.dev_tools_enabled {
+        router = router.merge(crate::dev_tools::routes());
+    }
+    #[cfg(all(feature = "dev-tools", not(debug_assertions)))]
+    if config.dev_tools_enabled {
+        tracing::warn!("DEV_TOOLS_ENABLED is ignored: release builds leave out the dev tools");
+    }
+    let source = ContextSource {
+        base: RequestContext::from_base_url(&config.public_base_url)?,
+        trust_proxy_headers: config.trust_proxy_headers,
+    };
+    router = request_layers(router, state, config, &source);
+    // Outside authentication and the other middleware, so their errors report the quota too
+    if let Some(limiter) = limiter {
+        router = router.layer(middleware::from_fn_with_state(limiter, rate_limit::rate_limit));
+    }
+    // Probes are left out of the rate limit as they are of the backpressure limit, so
+    // that they keep answering clients that ran out of requests
+    router = router.merge(request_layers(probes, state, config, &source));
+    // Outside the rate limit, which counts requests by client IP
+    let proxies = Arc::new(config.trusted_proxies.clone());
+    router = router.layer(middleware::from_fn_with_state(proxies, client_ip::capture_client_ip));
+    // Outside the extractors and the other middleware, so their errors are negotiated too
+    let casing = if config.legacy_snake_case { FieldCasing::Snake } else { FieldCasing::Camel };
+    router = router.layer(middleware::from_fn_with_state(casing, casing::negotiate_casing));
+    #[cfg(feature = "msgpack")]
+    {
+        router = router.layer(middleware::from_fn(http::negotiate_format));
+    }
+    let security_headers = http::SecurityHeaders::from_config(config)?;
+    Ok(router.layer(
+        ServiceBuilder::new()
+            .layer(middleware::from_fn_with_state(security_headers, http::security_headers))
+            .layer(TraceLayer::new_for_http().make_span_with(http::request_span))
+            .layer(TimeoutLayer::with_status_code(
+                axum::http::StatusCode::SERVICE_UNAVAILABLE,
+                config.request_timeout(),
+            ))
+            .layer(middleware::from_fn_with_state(
+                config.request_timeout(),
+                http::propagate_deadline,
+            )),
+    ))
+}
+
+/// `router` wrapped in the middleware inside the rate limit, from the tenant policy out
+/// to the request context of `source`
+fn request_layers<U, T>(
+    mut router: Router,
+    state: &AppState<U, T>,
+    config: &Config,
+    source: &ContextSource,
+) -> Router
+where
+    U: UserRepository + ?Sized + 'static,
+    T: TaskRepository + ?Sized + 'static,
+{
+    router = router
+        .layer(middleware::from_fn_with_state(state.tenant_policy, tenant::capture_tenant_policy));
+    router = router.layer(middleware::from_fn(http::record_error_outcome));
+    router = router.layer(middleware::from_fn_with_state(
+        Arc::clone(&state.budgets),
+        instrumentation::scope_budgets,
+    ));
+    if config.metrics_db {
+        router = router.layer(middleware::from_fn_with_state(
+            config.warn_query_count,
+            instrumentation::count_queries,
+        ));
+    }
+    if config.legacy_validation_status {
+        router = router.layer(middleware::from_fn(http::legacy_validation_status));
+    }
+    if config.assume_json_content_type {
+        router = router.layer(middleware::from_fn(http::assume_json_content_type));
+    }
+    let retry_after = config.busy_retry_after_secs;
+    router = router.layer(middleware::from_fn_with_state(retry_after, http::busy_retry_after));
+    router = router.layer(middleware::from_fn_with_state(
+        CachePolicy::from_config(config),
+        cache_control::cache_control,
+    ));
+    // The body limit is enforced while extractors read the (decompressed) body, so a
+    // small compressed payload cannot expand past it
+    router = router.layer(DefaultBodyLimit::max(config.max_request_body_bytes));
+    let encodings = if config.accept_compressed_requests {
+        router = router.layer(RequestDecompressionLayer::new().gzip(true));
+        REQUEST_ENCODINGS
+    } else {
+        &[]
+    };
+    router = router.layer(middleware::from_fn_with_state(
+        encodings,
+        http::reject_unsupported_encoding,
+    ));
+    router.layer(middleware::from_fn_with_state(
+        source.clone(),
+        request_context::capture_request_context,
+    ))
+}
+
+/// `registry` with the routers of every enabled feature registered
+fn register_features<U, T>(
+    state: &AppState<U, T>,
+    mut registry: FeatureRegistry<()>,
+) -> FeatureRegistry<()>
+where
+    U: UserRepository + ?Sized + 'static,
+    T: TaskRepository + ?Sized + 'static,
+{
+    if let Some(user) = &state.user {
+        registry = registry.register(user_http::routes(Arc::clone(user)));
+    }
+    if let Some(task) = &state.task {
+        registry = registry
+            .register(task_http::routes(Arc::clone(task)))
+            .register(task_http::stats_routes(Arc::clone(task)))
+            .register(task_http::overview_routes(Arc::clone(task)))
+            .register(task_http::onboarding_routes(Arc::clone(task)))
+            .register(task_http::user_task_routes(Arc::clone(task)))
+            .register(task_http::preference_routes(Arc::clone(task)));
+    }
+    if let Some(webhook) = &state.webhook {
+        registry = registry.register(webhook_http::routes(Arc::clone(webhook)));
+    }
+    if let Some(api_token) = &state.api_token {
+        registry = registry.register(api_token_http::routes(Arc::clone(api_token)));
+    }
+    registry
+}
+
+/// Routes of every enabled feature besides those of `registry`, and the GraphQL
+/// endpoint; requests to them are authenticated by API token when that feature is on,
+/// and those authenticated counted by token in `limiter`
+fn feature_routes<U, T>(
+    state: &AppState<U, T>,
+    config: &Config,
+    registry: FeatureRegistry<()>,
+    limiter: Option<&Arc<RateLimiter>>,
+) -> anyhow::Result<Router>
+where
+    U: UserRepository + ?Sized + 'static,
+    T: TaskRepository + ?Sized + 'static,
+{
+    let mut api = register_features(state, registry).build()?;
+    #[cfg(feature = "graphql")]
+    if let (Some(user), Some(task)) = (boxed(state.user.as_ref()), boxed(state.task.as_ref())) {
+        api = api.merge(crate::graphql::routes(user, task));
+    }
+    // Only the API: health checks and the dashboard's static pages stay open
+    if let Some(api_token) = &state.api_token {
+        let auth = TokenAuth {
+            authenticate: Arc::clone(&api_token.authenticate),
+            required: config.api_token_required,
+        };
+        // Inside authentication, so that only verified tokens open buckets of their own
+        if let Some(limiter) = limiter {
+            let count = rate_limit::rate_limit_authenticated;
+            api = api.layer(middleware::from_fn_with_state(Arc::clone(limiter), count));
+        }
+        api = api
+            .layer(middleware::from_fn_with_state(auth, api_token_infrastructure::authenticate));
+    }
+    // Outside authentication, which looks tokens up, and again only the API so that
+    // probes keep answering during overload
+    if config.max_in_flight_requests > 0 {
+        api = backpressure::limit_in_flight(api, config.max_in_flight_requests);
+    }
+    Ok(api)
+}
+
+/// `state` as the boxed state `D`, if that is what it is
+#[cfg(feature = "graphql")]
+fn boxed<S, D>(state: Option<&Arc<S>>) -> Option<Arc<D>>
+where
+    S: Send + Sync + 'static,
+    D: Send + Sync + 'static,
+{
+    let state: Arc<dyn std::any::Any + Send + Sync> = state?.clone();
+    state.downcast().ok()
+}
+
+/// Build the router of the admin listener (`ADMIN_PORT`): `/metrics` rendered by
+/// `metrics`, `/ready` (checking `pool` when given), the `/internal/*` and the
+/// `/admin/*` routes, `/admin/log-level` only when the state has a log level attached
+pub fn build_admin_router(
+    state: &AppState,
+    metrics: PrometheusHandle,
+    pool: Option<PgPool>,
+) -> Router {
+    let manifest: Arc<[ManifestRoute]> =
+        register_features(state, FeatureRegistry::default()).manifest().into();
+    let internal = Router::new()
+        .route("/internal/jobs", get(jobs::job_statuses))
+        .with_state(state.jobs.clone())
+        .route("/internal/routes", get(feature::route_manifest).with_state(manifest));
+    admin::routes(AdminState::new(metrics, pool))
+        .merge(internal)
+        .merge(maintenance::routes(Arc::clone(&state.maintenance)))
+        .merge(feature_toggle::routes(Arc::clone(&state.feature_toggles)))
+        .merge(state.log_level.clone().map(log_level::routes).unwrap_or_default())
+        .layer(TraceLayer::new_for_http().make_span_with(http::request_span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{in_memory_app_with, send};
+    use axum::http::{Method, StatusCode};
+    use std::time::Duration;
+
+    fn config(enabled: &[&str], disabled: &[&str]) -> Config {
+        let mut config = Config::default();
+        config.enabled_features = enabled.iter().map(|&s| s.to_owned()).collect();
+        config.disabled_features = disabled.iter().map(|&s| s.to_owned()).collect();
+        config
+    }
+
+    async fn statuses(config: &Config) -> (StatusCode, StatusCode) {
+        let app = in_memory_app_with(config);
+        let (users, _) = send(&app, Method::GET, "/users", None).await;
+        let (tasks, _) = send(&app, Method::GET, "/tasks", None).await;
+        (users, tasks)
+    }
+
+    #[tokio::test]
+    async fn all_features_should_be_enabled_by_default() {
+        assert_eq!(statuses(&Config::default()).await, (StatusCode::OK, StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn user_only_should_serve_users_and_404_tasks() {
+        let expected = (StatusCode::OK, StatusCode::NOT_FOUND);
+        assert_eq!(statuses(&config(&["user"], &[])).await, expected);
+        assert_eq!(statuses(&config(&[], &["task"])).await, expected);
+    }
+
+    #[tokio::test]
+    async fn no_features_should_404_everything_but_health() {
+        let config = config(&[], &["task", "api_token", "user"]);
+        assert_eq!(statuses(&config).await, (StatusCode::NOT_FOUND, StatusCode::NOT_FOUND));
+        let (status, _) = send(&in_memory_app_with(&config), Method::GET, "/health", None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn health_details_should_report_version_uptime_and_features() {
+        let config = config(&["user"], &[]);
+        let mut state =
+            AppState::build(&config, &InMemoryRepositories::default()).expect("valid state");
+        state.started_at =
+            Instant::now().checked_sub(Duration::from_secs(90)).expect("process runs long enough");
+        let app = build_router(&state, &config).expect("valid router");
+
+        let (status, health) = send(&app, Method::GET, "/health", None).await;
+        assert_eq!((status, health), (StatusCode::OK, serde_json::json!({"status": "ok"})));
+        let (status, details) = send(&app, Method::GET, "/health/details", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(details["status"], "ok");
+        assert_eq!(details["version"], env!("CARGO_PKG_VERSION"));
+        assert!(details["uptimeSeconds"].as_u64().is_some_and(|secs| secs >= 90), "{details}");
+        assert_eq!(details["features"], serde_json::json!(["user"]));
+        assert!(details["cargoFeatures"].is_array());
+    }
+
+    #[tokio::test]
+    async fn health_details_should_404_when_turned_off() {
+        let mut config = Config::default();
+        config.health_details = false;
+        let app = in_memory_app_with(&config);
+        let (status, _) = send(&app, Method::GET, "/health/details", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(send(&app, Method::GET, "/health", None).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn from_states_should_serve_features_wired_to_concrete_repositories() {
+        let config = Config::default();
+        let users = Arc::new(InMemoryUserRepository::default());
+        let email_changes = EmailChangeSettings {
+            repository: Arc::new(InMemoryEmailChangeRepository::default()),
+            notifier: None,
+            token_ttl: config.email_change_token_ttl(),
+            token_in_response: false,
+        };
+        let sender = Arc::new(ConsoleEmailSender);
+        let detached = DetachedWrites::default();
+        let limits = config.page_limits();
+        let user = UserState::new(&users, None, email_changes, limits, sender, &detached);
+        let state: AppState<InMemoryUserRepository, InMemoryTaskRepository> =
+            AppState::from_states(&config, Some(Arc::new(user)), None);
+        let app = build_router(&state, &config).expect("valid router");
+
+        let body = serde_json::json!({"name": "Alice", "email": "alice@example.com"});
+        let (status, created) = send(&app, Method::POST, "/users", Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uri = format!("/users/{}", created["id"].as_str().unwrap_or_default());
+        let (status, found) = send(&app, Method::GET, &uri, None).await;
+        assert_eq!((status, &found["name"]), (StatusCode::OK, &serde_json::json!("Alice")));
+        let (status, _) = send(&app, Method::GET, "/tasks", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn task_without_user_should_fail_with_dependency_error() {
+        for config in [config(&["task"], &[]), config(&[], &["user"])] {
+            let message = AppState::build(&config, &InMemoryRepositories::default())
+                .err()
+                .map(|e| e.to_string())
+                .unwrap_or_default();
+            assert_eq!(message, "Feature 'task' depends on feature 'user', which is disabled");
+        }
+    }
+
+    #[test]
+    fn unknown_feature_name_should_fail() {
+        let message = enabled_features(&config(&["tsk"], &[]))
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert_eq!(
+            message,
+            "Unknown feature 'tsk' in ENABLED_FEATURES; \
+             known features: user, task, webhook, api_token"
+        );
+    }
+
+    #[test]
+    fn disabled_feature_should_not_construct_its_repository() {
+        struct UserOnly;
+        impl RepositoryProvider for UserOnly {
+            fn user_repository(&self) -> Arc<dyn UserRepository> {
+                InMemoryRepositories::default().user_repository()
+            }
+            fn email_change_repository(&self) -> Arc<dyn EmailChangeRepository> {
+                InMemoryRepositories::default().email_change_repository()
+            }
+            fn task_repository(&self) -> Arc<dyn TaskRepository> {
+                unreachable!("task repository must not be constructed when the feature is disabled")
+            }
+            fn attachment_repository(&self) -> Arc<dyn AttachmentRepository> {
+                unreachable!("attachments must not be constructed when the task feature is disabled")
+            }
+            fn task_view_preferences_repository(&self) -> Arc<dyn TaskViewPreferencesRepository> {
+                unreachable!("preferences must not be constructed when the task feature is disabled")
+            }
+            fn unit_of_work(&self) -> Arc<dyn UnitOfWork> {
+                unreachable!("the unit of work must not be constructed when tasks are disabled")
+            }
+            fn webhook_repository(&self) -> Arc<dyn WebhookRepository> {
+                unreachable!("webhooks must not be constructed when the feature is disabled")
+            }
+            fn webhook_deliveries(&self) -> Arc<dyn WebhookDeliveries> {
+                unreachable!("webhooks must not be constructed when the feature is disabled")
+            }
+            fn api_token_repository(&self) -> Arc<dyn ApiTokenRepository> {
+                unreachable!("API tokens must not be constructed when the feature is disabled")
+            }
+        }
+        let state = AppState::build(&config(&["user"], &[]), &UserOnly);
+        assert!(state.is_ok_and(|s| s.user.is_some() && s.task.is_none()));
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn build_router_with_should_serve_extra_features_and_detect_conflicts() {
+        use crate::shared::infrastructure::feature::FeatureRouter;
+        let extra = |prefix| FeatureRouter {
+            name: "extra",
+            prefix,
+            router: Router::new().route("/", get(|| async { "ok" })),
+            manifest: &[],
+        };
+        let config = Config::default();
+        let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+
+        let registry = FeatureRegistry::default().register(extra("/extras"));
+        let app = build_router_with(&state, &config, registry).expect("router");
+        let response = tower::ServiceExt::oneshot(
+            app,
+            axum::http::Request::get("/extras").body(axum::body::Body::empty()).expect("request"),
+        )
+        .await
+        .expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let registry = FeatureRegistry::default().register(extra("/users"));
+        let message = build_router_with(&state, &config, registry).err().map(|e| e.to_string());
+        assert_eq!(
+            message.as_deref(),
+            Some("Conflicting feature registration: 'extra' (/users) and 'user' (/users)")
+        );
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn requests_beyond_the_in_flight_limit_should_be_shed_but_not_health_checks() {
+        use crate::shared::infrastructure::feature::FeatureRouter;
+        use tokio::sync::{mpsc, Semaphore};
+        // Requests to /slow report they started, then wait to be released
+        let (started, mut starts) = mpsc::unbounded_channel();
+        let release = Arc::new(Semaphore::new(0));
+        let slow = {
+            let release = Arc::clone(&release);
+            get(move || async move {
+                started.send(()).ok();
+                release.acquire().await.expect("open semaphore").forget();
+                axum::Json("done")
+            })
+        };
+        let router = Router::new().route("/", slow);
+        let slow = FeatureRouter { name: "slow", prefix: "/slow", router, manifest: &[] };
+        let mut config = Config::default();
+        config.max_in_flight_requests = 2;
+        let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+        let registry = FeatureRegistry::default().register(slow);
+        let app = build_router_with(&state, &config, registry).expect("router");
+
+        let in_flight: Vec<_> = (0..2)
+            .map(|_| {
+                let app = app.clone();
+                tokio::spawn(async move { send(&app, Method::GET, "/slow", None).await.0 })
+            })
+            .collect();
+        for _ in 0..2 {
+            starts.recv().await.expect("started");
+        }
+        for uri in ["/slow", "/users"] {
+            let (status, error) = send(&app, Method::GET, uri, None).await;
+            assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{uri}");
+            assert_eq!(error["code"], "SERVICE_BUSY");
+        }
+        assert_eq!(send(&app, Method::GET, "/health", None).await.0, StatusCode::OK);
+
+        release.add_permits(2);
+        for request in in_flight {
+            assert_eq!(request.await.expect("joined"), StatusCode::OK);
+        }
+        assert_eq!(send(&app, Method::GET, "/users", None).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn every_feature_should_serve_the_routes_of_its_manifest() {
+        let config = Config::default();
+        let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+        let checked = build_router_checked(&state, &config).await;
+        assert!(checked.is_ok(), "{:?}", checked.err());
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn internal_routes_should_list_the_routes_of_enabled_features() {
+        use metrics_exporter_prometheus::PrometheusBuilder;
+        let config = config(&[], &["task"]);
+        let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+        let metrics = PrometheusBuilder::new().build_recorder().handle();
+        let app = build_admin_router(&state, metrics, None);
+
+        let (status, body) = send(&app, Method::GET, "/internal/routes", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let routes = body.as_array().expect("array");
+        let confirm = serde_json::json!({
+            "feature": "user", "method": "POST", "path": "/users/email-change/confirm"
+        });
+        assert!(routes.contains(&confirm), "{body}");
+        assert!(routes.iter().all(|route| route["feature"] != "task"), "{body}");
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn internal_jobs_should_report_registered_jobs() {
+        use crate::shared::infrastructure::jobs::{BackgroundJob, JobContext, JobRunner};
+        use metrics_exporter_prometheus::PrometheusBuilder;
+        struct Noop;
+        #[async_trait::async_trait]
+        impl BackgroundJob for Noop {
+            fn name(&self) -> &'static str {
+                "noop"
+            }
+            fn interval(&self) -> Duration {
+                Duration::from_secs(1)
+            }
+            async fn run(&self, _ctx: &JobContext) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        let config = Config::default();
+        let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+        let metrics = PrometheusBuilder::new().build_recorder().handle();
+        let app = build_admin_router(&state, metrics, None);
+        let (status, body) = send(&app, Method::GET, "/internal/jobs", None).await;
+        assert_eq!((status, body), (StatusCode::OK, serde_json::json!({})));
+
+        let _runner = JobRunner::new(state.jobs().clone()).register(Noop);
+        let (_, body) = send(&app, Method::GET, "/internal/jobs", None).await;
+        let expected = serde_json::json!({
+            "noop": {"last_run": null, "last_error": null, "last_duration_ms": null, "runs": 0}
+        });
+        assert_eq!(body, expected);
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn feature_flags_should_start_as_configured_and_change_on_the_admin_listener() {
+        use metrics_exporter_prometheus::PrometheusBuilder;
+        let mut config = Config::default();
+        config.feature_flags = vec![(ATOMIC_COMPLETE.to_owned(), 25)];
+        let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+        let metrics = PrometheusBuilder::new().build_recorder().handle();
+        let app = build_admin_router(&state, metrics, None);
+
+        let (_, body) = send(&app, Method::GET, "/admin/flags", None).await;
+        let flag = serde_json::json!({"name": ATOMIC_COMPLETE, "percentage": 25, "configured": 25});
+        assert_eq!(body, serde_json::json!([flag]));
+        let uri = format!("/admin/flags/{ATOMIC_COMPLETE}");
+        let payload = serde_json::json!({"percentage": 100});
+        assert_eq!(send(&app, Method::PUT, &uri, Some(payload)).await.0, StatusCode::OK);
+        // The task use cases read the same toggles
+        let task = state.task.as_ref().expect("task feature");
+        assert_eq!(task.feature_toggles.flags()[0].percentage, 100);
+
+        config.feature_flags = vec![("missing".to_owned(), 25)];
+        let error = AppState::build(&config, &InMemoryRepositories::default()).err();
+        let message = error.map(|e| e.to_string()).unwrap_or_default();
+        assert!(message.contains("Unknown feature flag 'missing'"), "{message}");
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn maintenance_cleanup_should_delete_the_stale_rows_of_every_feature() {
+        use crate::features::user::domain::PendingEmailChange;
+        use crate::shared::domain::{Email, OutboxEvent, TenantId, UserId};
+        use chrono::{TimeDelta, Utc};
+        use metrics_exporter_prometheus::PrometheusBuilder;
+
+        let repositories = InMemoryRepositories::default();
+        let outbox = repositories.webhooks.outbox();
+        let event = |subject: &str| {
+            let event = OutboxEvent {
+                event_type: "task.created",
+                version: 1,
+                subject: subject.into(),
+                payload: serde_json::Value::Null,
+            };
+            (TenantId::default(), event)
+        };
+        outbox.append([event("t1"), event("t2"), event("t3")]).await;
+        for id in [1, 2] {
+            assert!(outbox.mark_processed(id).await);
+        }
+        for (user, expires_in) in [("u1", -1), ("u2", -2), ("u3", 1)] {
+            let change = PendingEmailChange::new(
+                UserId::from_trusted(user.into()),
+                Email::from_trusted(format!("{user}@example.com")),
+                format!("hash-{user}"),
+                Utc::now() + TimeDelta::hours(expires_in),
+            );
+            repositories.email_changes.save(&change).await.expect("save");
+        }
+        let mut config = Config::default();
+        config.cleanup_retention_days = 0;
+        config.cleanup_batch_size = 1;
+        let state = AppState::build(&config, &repositories).expect("state");
+        let metrics = PrometheusBuilder::new().build_recorder().handle();
+        let app = build_admin_router(&state, metrics, None);
+
+        let (status, body) = send(&app, Method::POST, "/admin/maintenance/cleanup", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = serde_json::json!({
+            "deleted": {"email_changes": 2, "outbox_events": 2},
+            "complete": true
+        });
+        assert_eq!(body, expected);
+        // The pending event and the change still valid stay
+        let (_, body) = send(&app, Method::POST, "/admin/maintenance/cleanup", None).await;
+        assert_eq!(body["deleted"], serde_json::json!({"email_changes": 0, "outbox_events": 0}));
+        assert_eq!(outbox.pending(10).await.len(), 1);
+    }
+
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    fn gzipped_post(
+        uri: &str,
+        encoding: &str,
+        json: &[u8],
+    ) -> axum::http::Request<axum::body::Body> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(json).expect("compress");
+        axum::http::Request::post(uri)
+            .header(axum::http::header::CONTENT_TYPE, "application/json")
+            .header(axum::http::header::CONTENT_ENCODING, encoding)
+            .body(axum::body::Body::from(encoder.finish().expect("compress")))
+            .expect("request")
+    }
+
+    fn compressed_config() -> Config {
+        let mut config = Config::default();
+        config.accept_compressed_requests = true;
+        config
+    }
+
+    #[tokio::test]
+    async fn gzipped_body_should_be_accepted_only_when_enabled() {
+        use crate::test_support::send_request;
+        let user = br#"{"name":"Alice","email":"alice@example.com"}"#;
+
+        let app = in_memory_app_with(&compressed_config());
+        let (status, body) = send_request(&app, gzipped_post("/users", "gzip", user)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["name"], "Alice");
+        let task = format!(r#"{{"userId":{},"title":"Imported","description":""}}"#, body["id"]);
+        let (status, body) =
+            send_request(&app, gzipped_post("/tasks", "gzip", task.as_bytes())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["title"], "Imported");
+
+        let app = in_memory_app_with(&Config::default());
+        let (status, body) = send_request(&app, gzipped_post("/users", "gzip", user)).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "UNSUPPORTED_ENCODING");
+        assert_eq!(
+            body["message"],
+            "Unsupported Content-Encoding 'gzip'; supported encodings: identity"
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_encoding_should_return_415() {
+        use crate::test_support::send_request;
+        let app = in_memory_app_with(&compressed_config());
+        let (status, body) = send_request(&app, gzipped_post("/users", "br", b"{}")).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "UNSUPPORTED_ENCODING");
+        assert_eq!(
+            body["message"],
+            "Unsupported Content-Encoding 'br'; supported encodings: identity, gzip"
+        );
+    }
+
+    #[tokio::test]
+    async fn decompressed_body_over_limit_should_return_413() {
+        use crate::test_support::send_request;
+        let mut config = compressed_config();
+        config.max_request_body_bytes = 1024;
+        let app = in_memory_app_with(&config);
+        // Compresses to well under the limit but expands far past it
+        let padding = " ".repeat(64 * 1024);
+        let json = format!(r#"{{"name":"Alice",{padding}"email":"alice@example.com"}}"#);
+        let request = gzipped_post("/users", "gzip", json.as_bytes());
+        let (status, body) = send_request(&app, request).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["code"], "BODY_TOO_LARGE");
+
+        let (status, body) = send_request(&app, gzipped_post("/users", "gzip", b"{not json")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_BODY");
+    }
+
+    #[tokio::test]
+    async fn unreadable_query_and_path_parameters_should_render_json_errors() {
+        let app = in_memory_app_with(&Config::default());
+        for (uri, parameter) in [
+            // Repeated, as clients serializing arrays send it, where one value is expected
+            ("/tasks?user_id=a&user_id=b", "`user_id`"),
+            ("/tasks?limit=ten", "limit: invalid digit"),
+            ("/users?offset=-1", "offset: invalid digit"),
+        ] {
+            let (status, body) = send(&app, Method::GET, uri, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["code"], "INVALID_QUERY");
+            let message = body["message"].as_str().unwrap_or_default();
+            assert!(message.contains(parameter), "{uri}: {message}");
+        }
+        for uri in ["/tasks/%FF", "/users/%FF"] {
+            let (status, body) = send(&app, Method::GET, uri, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["code"], "INVALID_PATH");
+            assert_eq!(body["message"], "Invalid URL: Invalid UTF-8 in `id`");
+        }
+
+        let (status, tasks) = send(&app, Method::GET, "/tasks?completed=false&limit=1", None).await;
+        assert_eq!((status, tasks), (StatusCode::OK, serde_json::json!([])));
+        let (status, body) = send(&app, Method::GET, "/tasks/missing", None).await;
+        assert_eq!((status, body["code"].as_str()), (StatusCode::NOT_FOUND, Some("NOT_FOUND")));
+    }
+
+    #[test]
+    fn metrics_db_should_time_repository_calls() {
+        use crate::test_support::recorded_repository_calls;
+        let list_users = |metrics_db| {
+            let mut config = Config::default();
+            config.metrics_db = metrics_db;
+            recorded_repository_calls(async move {
+                let (status, _) =
+                    send(&in_memory_app_with(&config), Method::GET, "/users", None).await;
+                assert_eq!(status, StatusCode::OK);
+            })
+        };
+        assert_eq!(list_users(true), ["user.find_page ok: 1"]);
+        assert!(list_users(false).is_empty());
+    }
+
+    #[test]
+    fn task_cache_should_serve_repeated_reads_without_the_repository() {
+        use crate::test_support::recorded_repository_calls;
+        let mut config = Config::default();
+        config.metrics_db = true;
+        config.task_cache_ttl_secs = 60;
+        let calls = recorded_repository_calls(async move {
+            let app = in_memory_app_with(&config);
+            let user = serde_json::json!({ "name": "Alice", "email": "alice@example.com" });
+            let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+            let task = serde_json::json!({ "userId": user["id"], "title": "t", "description": "" });
+            let (_, task) = send(&app, Method::POST, "/tasks", Some(task)).await;
+            let path = format!("/tasks/{}", task["id"].as_str().unwrap_or_default());
+            for _ in 0..2 {
+                let (status, _) = send(&app, Method::GET, &path, None).await;
+                assert_eq!(status, StatusCode::OK);
+            }
+        });
+        assert!(!calls.iter().any(|call| call.starts_with("task.find_by_id")), "{calls:?}");
+    }
+
+    #[test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    fn error_responses_should_be_recorded_on_the_request_span() {
+        use crate::shared::domain::DomainError;
+        use crate::shared::infrastructure::feature::FeatureRouter;
+        use crate::shared::infrastructure::http::ApiError;
+        use crate::test_support::recorded_request_errors;
+
+        let (spans, counters) = recorded_request_errors(async {
+            let failing = FeatureRouter {
+                name: "failing",
+                prefix: "/failing",
+                router: Router::new().route(
+                    "/",
+                    get(|| async {
+                        Err::<(), _>(ApiError::from(DomainError::Infrastructure("boom".into())))
+                    }),
+                ),
+                manifest: &[],
+            };
+            let config = Config::default();
+            let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+            let registry = FeatureRegistry::default().register(failing);
+            let app = build_router_with(&state, &config, registry).expect("router");
+
+            let missing = format!("/tasks/{}", uuid::Uuid::new_v4());
+            let (status, _) = send(&app, Method::GET, &missing, None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            let (status, _) = send(&app, Method::GET, "/failing", None).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            let (status, _) = send(&app, Method::GET, "/health", None).await;
+            assert_eq!(status, StatusCode::OK);
+        });
+
+        assert_eq!(
+            spans,
+            [
+                "entity=Task error.code=NOT_FOUND error.kind=not_found",
+                "error.code=INTERNAL_ERROR error.kind=infrastructure otel.status_code=ERROR",
+                "",
+            ]
+        );
+        assert_eq!(counters, ["INTERNAL_ERROR /failing: 1", "NOT_FOUND /tasks/{id}: 1"]);
+    }
+
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn created_location(app: &Router, forwarded: &[(&str, &str)]) -> String {
+        use tower::ServiceExt;
+        let mut request = axum::http::Request::post("/users")
+            .header(axum::http::header::CONTENT_TYPE, "application/json");
+        for &(name, value) in forwarded {
+            request = request.header(name, value);
+        }
+        let body = r#"{"name":"Alice","email":"alice@example.com"}"#;
+        let request = request.body(axum::body::Body::from(body)).expect("request");
+        let response = app.clone().oneshot(request).await.expect("infallible router");
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let location = response.headers()[axum::http::header::LOCATION].to_str();
+        location.expect("ASCII location").to_owned()
+    }
+
+    #[tokio::test]
+    async fn location_should_follow_deployment_prefix() {
+        let proxied = [
+            ("x-forwarded-proto", "https"),
+            ("x-forwarded-host", "example.com"),
+            ("x-forwarded-prefix", "/todo-api"),
+        ];
+
+        // Unprefixed: absolute path, forwarding headers ignored unless trusted
+        let location = created_location(&in_memory_app_with(&Config::default()), &proxied).await;
+        assert!(location.starts_with("/users/"), "{location}");
+
+        let mut config = Config::default();
+        config.public_base_url = "https://api.example.com/v1/".to_owned();
+        let location = created_location(&in_memory_app_with(&config), &[]).await;
+        assert!(location.starts_with("https://api.example.com/v1/users/"), "{location}");
+
+        config.trust_proxy_headers = true;
+        let app = in_memory_app_with(&config);
+        let location = created_location(&app, &proxied).await;
+        assert!(location.starts_with("https://example.com/todo-api/users/"), "{location}");
+        let path = location.trim_start_matches("https://example.com/todo-api");
+        assert_eq!(send(&app, Method::GET, path, None).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn error_responses_should_report_the_rate_limit_too() {
+        use axum::extract::ConnectInfo;
+        use axum::http::header;
+        use std::net::SocketAddr;
+        use tower::ServiceExt;
+        let mut config = Config::default();
+        config.rate_limit_requests = 2;
+        config.rate_limit_enforce = true;
+        let app = in_memory_app_with(&config);
+        // Tokens made up per request are all counted against the client's address
+        let call = |i: usize| {
+            let request = axum::http::Request::get("/users")
+                .header(header::AUTHORIZATION, format!("Bearer unknown-{i}"))
+                .extension(ConnectInfo(SocketAddr::from(([203, 0, 113, 5], 4711))))
+                .body(axum::body::Body::empty())
+                .expect("request");
+            app.clone().oneshot(request)
+        };
+
+        let mut seen = Vec::new();
+        for i in 0..3 {
+            let response = call(i).await.expect("infallible router");
+            let remaining = response.headers().get("x-ratelimit-remaining").cloned();
+            seen.push((response.status(), remaining.expect("rate limit headers")));
+        }
+        assert_eq!(
+            seen,
+            [
+                (StatusCode::UNAUTHORIZED, 1.into()),
+                (StatusCode::UNAUTHORIZED, 0.into()),
+                (StatusCode::TOO_MANY_REQUESTS, 0.into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn health_checks_should_be_left_out_of_the_rate_limit() {
+        use axum::extract::ConnectInfo;
+        use std::net::SocketAddr;
+        use tower::ServiceExt;
+        let mut config = Config::default();
+        config.rate_limit_requests = 1;
+        config.rate_limit_enforce = true;
+        config.health_details = true;
+        let app = in_memory_app_with(&config);
+        let call = |uri: &str| {
+            let request = axum::http::Request::get(uri)
+                .extension(ConnectInfo(SocketAddr::from(([203, 0, 113, 5], 4711))))
+                .body(axum::body::Body::empty())
+                .expect("request");
+            app.clone().oneshot(request)
+        };
+
+        assert_eq!(call("/users").await.expect("infallible router").status(), StatusCode::OK);
+        let refused = call("/users").await.expect("infallible router");
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        for uri in ["/health", "/health/details"] {
+            let response = call(uri).await.expect("infallible router");
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+            assert!(!response.headers().contains_key("x-ratelimit-remaining"), "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn rate_limits_should_count_authenticated_requests_by_token() {
+        use crate::test_support::{in_memory_app_with_repositories, issue_token};
+        use axum::extract::ConnectInfo;
+        use axum::http::header;
+        use std::net::SocketAddr;
+        use tower::ServiceExt;
+        let mut config = Config::default();
+        config.rate_limit_requests = 5;
+        let (app, repositories) = in_memory_app_with_repositories(&config);
+        let body = serde_json::json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(body)).await;
+        let token = issue_token(&repositories, user["id"].as_str().unwrap_or_default()).await;
+        let remaining = |authorization: Option<&str>| {
+            let mut request = axum::http::Request::get("/users")
+                .extension(ConnectInfo(SocketAddr::from(([203, 0, 113, 5], 4711))));
+            if let Some(authorization) = authorization {
+                request = request.header(header::AUTHORIZATION, authorization);
+            }
+            let request = request.body(axum::body::Body::empty()).expect("request");
+            let response = app.clone().oneshot(request);
+            async {
+                let response = response.await.expect("infallible router");
+                response.headers().get("x-ratelimit-remaining").cloned().expect("headers")
+            }
+        };
+
+        let bearer = format!("Bearer {token}");
+        assert_eq!(remaining(Some(&bearer)).await, "4");
+        assert_eq!(remaining(Some(&bearer)).await, "3");
+        assert_eq!(remaining(None).await, "4");
+        assert_eq!(remaining(Some("Bearer made-up")).await, "3");
+        assert_eq!(remaining(Some(&bearer)).await, "2");
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn rate_limits_should_count_forwarded_clients_of_trusted_proxies_only() {
+        use crate::shared::infrastructure::client_ip::TrustedProxies;
+        use axum::extract::ConnectInfo;
+        use std::net::SocketAddr;
+        use tower::ServiceExt;
+        let mut config = Config::default();
+        config.rate_limit_requests = 5;
+        config.trusted_proxies =
+            TrustedProxies::parse(&["10.0.0.0/8".to_owned()]).expect("valid proxies");
+        let app = in_memory_app_with(&config);
+        let remaining = |peer: [u8; 4], forwarded_for: &'static str| {
+            let request = axum::http::Request::get("/users")
+                .header("x-forwarded-for", forwarded_for)
+                .extension(ConnectInfo(SocketAddr::from((peer, 4711))))
+                .body(axum::body::Body::empty())
+                .expect("request");
+            let response = app.clone().oneshot(request);
+            async {
+                let response = response.await.expect("infallible router");
+                response.headers().get("x-ratelimit-remaining").cloned().expect("headers")
+            }
+        };
+
+        // Each client behind the balancer has a bucket of its own
+        assert_eq!(remaining([10, 0, 0, 1], "203.0.113.5").await, "4");
+        assert_eq!(remaining([10, 0, 0, 2], "203.0.113.5").await, "3");
+        assert_eq!(remaining([10, 0, 0, 1], "198.51.100.9").await, "4");
+        // A client naming another in the header is still counted as itself
+        assert_eq!(remaining([192, 0, 2, 1], "203.0.113.5").await, "4");
+        assert_eq!(remaining([192, 0, 2, 1], "203.0.113.5").await, "3");
+    }
+
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn response_headers(app: &Router, uri: &str) -> axum::http::HeaderMap {
+        use tower::ServiceExt;
+        let request = axum::http::Request::get(uri)
+            .body(axum::body::Body::empty())
+            .expect("request");
+        app.clone().oneshot(request).await.expect("infallible router").headers().clone()
+    }
+
+    #[cfg(not(feature = "dashboard"))]
+    #[tokio::test]
+    async fn dashboard_should_be_absent_without_its_cargo_feature() {
+        let app = in_memory_app_with(&Config::default());
+        let (status, _) = send(&app, Method::GET, "/dashboard", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(not(all(feature = "dev-tools", debug_assertions)))]
+    #[tokio::test]
+    async fn dev_tools_should_be_absent_without_their_feature_or_in_release_builds() {
+        let app = in_memory_app_with(&Config::default());
+        for uri in ["/dev/echo", "/dev/delay/0", "/dev/error/500"] {
+            let (status, _) = send(&app, Method::GET, uri, None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn security_headers_should_be_set_on_success_error_and_unmatched_routes() {
+        let app = in_memory_app_with(&Config::default());
+        for uri in ["/health", "/users", "/users/missing", "/no-such-route"] {
+            let headers = response_headers(&app, uri).await;
+            assert_eq!(headers["x-content-type-options"], "nosniff", "{uri}");
+            assert_eq!(headers["x-frame-options"], "DENY", "{uri}");
+            assert_eq!(headers["referrer-policy"], "no-referrer", "{uri}");
+            assert!(!headers.contains_key("strict-transport-security"), "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn security_headers_should_follow_config() {
+        let mut config = Config::default();
+        config.behind_tls_proxy = true;
+        config.frame_options = "SAMEORIGIN".to_owned();
+        config.referrer_policy = String::new();
+        let headers = response_headers(&in_memory_app_with(&config), "/health").await;
+        assert_eq!(headers["strict-transport-security"], "max-age=31536000; includeSubDomains");
+        assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+        assert!(!headers.contains_key("referrer-policy"));
+
+        config.frame_options = "DENY\n".to_owned();
+        let state = AppState::build(&config, &InMemoryRepositories::default());
+        let message = state
+            .and_then(|state| build_router(&state, &config))
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_default();
+        assert!(message.starts_with("Invalid X_FRAME_OPTIONS=\"DENY\\n\""), "{message}");
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn create_task_for_missing_user_should_report_user_not_found(pool: sqlx::PgPool) {
+        let config = Config::default();
+        let state = AppState::build(&config, &PgRepositories::new(pool)).expect("state");
+        let app = build_router(&state, &config).expect("router");
+        let body =
+            serde_json::json!({ "userId": "no-such-user", "title": "t", "description": "" });
+        let (status, json) = send(&app, Method::POST, "/tasks", Some(body)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["message"], "Not found: User not found");
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn exhausted_pool_should_return_503_with_retry_after(
+        options: sqlx::postgres::PgPoolOptions,
+        connect: sqlx::postgres::PgConnectOptions,
+    ) {
+        use crate::shared::infrastructure::database::pool_exhausted_total;
+        let pool = options
+            .max_connections(1)
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_with(connect)
+            .await
+            .expect("pool");
+        let config = Config::default();
+        let state = AppState::build(&config, &PgRepositories::new(pool.clone())).expect("state");
+        let app = build_router(&state, &config).expect("router");
+
+        let held = pool.begin().await.expect("hold the only connection");
+        let before = pool_exhausted_total();
+        let response = tower::ServiceExt::oneshot(
+            app,
+            axum::http::Request::get("/users").body(axum::body::Body::empty()).expect("request"),
+        )
+        .await
+        .expect("response");
+        drop(held);
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let retry_after = response.headers().get(axum::http::header::RETRY_AFTER);
+        assert_eq!(retry_after.and_then(|v| v.to_str().ok()), Some("5"));
+        assert!(pool_exhausted_total() > before);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn instances_in_different_schemas_should_not_see_each_others_data(
+        options: sqlx::postgres::PgPoolOptions,
+        connect: sqlx::postgres::PgConnectOptions,
+    ) {
+        use crate::shared::infrastructure::database::{run_migrations, with_schema, MigrationRetry};
+        let mut apps = Vec::new();
+        for schema in ["tenant_a", "tenant_b"] {
+            let pool = with_schema(options.clone(), schema)
+                .connect_with(connect.clone())
+                .await
+                .expect("pool");
+            let config = Config::default();
+            let retry = MigrationRetry::from_config(&config);
+            run_migrations(&pool, schema, retry).await.expect("migrations");
+            let state = AppState::build(&config, &PgRepositories::new(pool)).expect("state");
+            apps.push(build_router(&state, &config).expect("router"));
+        }
+        let user = serde_json::json!({"name": "Alice", "email": "alice@example.com"});
+        let (status, created) = send(&apps[0], Method::POST, "/users", Some(user.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (_, listed) = send(&apps[1], Method::GET, "/users", None).await;
+        assert_eq!(listed, serde_json::json!([]));
+        let uri = format!("/users/{}", created["id"].as_str().unwrap_or_default());
+        let (status, _) = send(&apps[1], Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        // The same email is free in the other schema
+        let (status, _) = send(&apps[1], Method::POST, "/users", Some(user)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (_, listed) = send(&apps[0], Method::GET, "/users", None).await;
+        assert_eq!(listed.as_array().map(Vec::len), Some(1));
+    }
+}
@@ -0,0 +1,261 @@
+//! Generates the skeleton of a new feature
+//!
+//! ```bash
+//! cargo run --bin scaffold -- project            # plural "projects"
+//! cargo run --bin scaffold -- category categories
+//! ```
+//!
+//! Writes `src/features/<name>/` (entity and ID, repository port, in-memory and
+//! `PostgreSQL` repositories, CRUD use cases, state and routes) and the next numbered
+//! migration creating its table, then prints the lines wiring the feature into
+//! `src/features/mod.rs` and `src/app.rs`. Run it from the crate root; it never
+//! overwrites an existing feature.
+
+use anyhow::{bail, Context};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Templates by the path they are written to, relative to the feature directory
+const FEATURE_FILES: &[(&str, &str)] = &[
+    ("mod.rs", include_str!("templates/mod.rs.tmpl")),
+    ("state.rs", include_str!("templates/state.rs.tmpl")),
+    ("domain/mod.rs", include_str!("templates/domain_mod.rs.tmpl")),
+    ("domain/entity.rs", include_str!("templates/entity.rs.tmpl")),
+    ("domain/repository.rs", include_str!("templates/repository.rs.tmpl")),
+    ("application/mod.rs", include_str!("templates/application_mod.rs.tmpl")),
+    ("application/create___name__.rs", include_str!("templates/create.rs.tmpl")),
+    ("application/get___name__.rs", include_str!("templates/get.rs.tmpl")),
+    ("application/update___name__.rs", include_str!("templates/update.rs.tmpl")),
+    ("application/delete___name__.rs", include_str!("templates/delete.rs.tmpl")),
+    ("infrastructure/mod.rs", include_str!("templates/infrastructure_mod.rs.tmpl")),
+    (
+        "infrastructure/in_memory_repository.rs",
+        include_str!("templates/in_memory_repository.rs.tmpl"),
+    ),
+    ("infrastructure/pg_repository.rs", include_str!("templates/pg_repository.rs.tmpl")),
+    ("infrastructure/http.rs", include_str!("templates/http.rs.tmpl")),
+];
+
+const MIGRATION_UP: &str = include_str!("templates/up.sql.tmpl");
+const MIGRATION_DOWN: &str = include_str!("templates/down.sql.tmpl");
+const WIRING: &str = include_str!("templates/wiring.txt.tmpl");
+
+/// Spellings of a feature name substituted into the templates
+#[derive(Debug, PartialEq, Eq)]
+struct Names {
+    /// `task_list`
+    snake: String,
+    /// `task_lists`
+    plural: String,
+}
+
+impl Names {
+    /// Validate `name` (snake case) and `plural`, which defaults to `name` + `s`
+    fn new(name: &str, plural: Option<&str>) -> anyhow::Result<Self> {
+        let plural = plural.map_or_else(|| format!("{name}s"), str::to_owned);
+        for word in [name, &plural] {
+            let valid = word.starts_with(|c: char| c.is_ascii_lowercase())
+                && word.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                && !word.ends_with('_')
+                && !word.contains("__");
+            if !valid {
+                bail!("'{word}' is not a snake_case name such as project or task_list");
+            }
+        }
+        if name == plural {
+            bail!("The plural of '{name}' must differ from it");
+        }
+        Ok(Self { snake: name.to_owned(), plural })
+    }
+
+    /// Fill every placeholder of `template`
+    fn render(&self, template: &str) -> String {
+        [
+            ("__Names_label__", capitalized(&label(&self.plural))),
+            ("__names_label__", label(&self.plural)),
+            ("__Label__", capitalized(&label(&self.snake))),
+            ("__label__", label(&self.snake)),
+            ("__Names__", pascal(&self.plural)),
+            ("__names__", self.plural.clone()),
+            ("__Name__", pascal(&self.snake)),
+            ("__name__", self.snake.clone()),
+        ]
+        .iter()
+        .fold(template.to_owned(), |text, (placeholder, value)| text.replace(placeholder, value))
+    }
+}
+
+/// `task_list` as `TaskList`
+fn pascal(snake: &str) -> String {
+    snake.split('_').map(capitalized).collect()
+}
+
+/// `task_list` as `task list`
+fn label(snake: &str) -> String {
+    snake.replace('_', " ")
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_ascii_uppercase().to_string() + chars.as_str()
+    })
+}
+
+/// Number of the migration following the last one in `migrations`, e.g. `008`
+fn next_migration(migrations: &Path) -> anyhow::Result<String> {
+    let mut last = 0;
+    if migrations.exists() {
+        for entry in fs::read_dir(migrations).context("Cannot read the migrations")? {
+            let name = entry?.file_name();
+            let number = name.to_string_lossy().split('_').next().and_then(|n| n.parse().ok());
+            last = last.max(number.unwrap_or(0_u32));
+        }
+    }
+    Ok(format!("{:03}", last + 1))
+}
+
+/// Write the feature `names` under `root` (the crate root), returning the written
+/// paths relative to `root`
+fn generate(root: &Path, names: &Names) -> anyhow::Result<Vec<PathBuf>> {
+    let feature = Path::new("src/features").join(&names.snake);
+    if root.join(&feature).exists() {
+        bail!("{} already exists", feature.display());
+    }
+    let migration = format!("{}_{}", next_migration(&root.join("migrations"))?, names.plural);
+    let files = FEATURE_FILES
+        .iter()
+        .map(|(path, template)| (feature.join(names.render(path)), *template))
+        .chain([
+            (Path::new("migrations").join(format!("{migration}.up.sql")), MIGRATION_UP),
+            (Path::new("migrations").join(format!("{migration}.down.sql")), MIGRATION_DOWN),
+        ]);
+    let mut written = Vec::new();
+    for (path, template) in files {
+        let target = root.join(&path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, names.render(template))
+            .with_context(|| format!("Cannot write {}", target.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (name, plural) = match args.as_slice() {
+        [name] => (name, None),
+        [name, plural] => (name, Some(plural.as_str())),
+        _ => bail!("Usage: scaffold <name> [<plural>], e.g. scaffold project"),
+    };
+    let names = Names::new(name, plural)?;
+    for path in generate(Path::new("."), &names)? {
+        println!("created {}", path.display());
+    }
+    println!("\n{}", names.render(WIRING));
+    Ok(())
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    const GOLDEN: &str = "src/bin/scaffold/golden/project.snap";
+
+    /// Every file generated under `root`, each under a `=== path` header, in path order
+    fn snapshot(root: &Path, mut paths: Vec<PathBuf>) -> String {
+        paths.sort();
+        let mut snapshot = String::new();
+        for path in paths {
+            let text = fs::read_to_string(root.join(&path)).expect("generated file");
+            snapshot.push_str("=== ");
+            snapshot.push_str(&path.to_string_lossy());
+            snapshot.push('\n');
+            snapshot.push_str(&text);
+        }
+        snapshot
+    }
+
+    /// Copy the directory `from` to `to`, recursively
+    fn copy_tree(from: &Path, to: &Path) {
+        fs::create_dir_all(to).expect("directory");
+        for entry in fs::read_dir(from).expect("readable directory") {
+            let entry = entry.expect("directory entry");
+            let target = to.join(entry.file_name());
+            if entry.file_type().expect("file type").is_dir() {
+                copy_tree(&entry.path(), &target);
+            } else {
+                fs::copy(entry.path(), target).expect("copied file");
+            }
+        }
+    }
+
+    #[test]
+    fn names_should_spell_multi_word_names_and_reject_invalid_ones() {
+        let names = Names::new("task_list", None).expect("valid name");
+        assert_eq!(
+            names.render("__Name__ __Names__ __name__ __names__ __Label__ __names_label__"),
+            "TaskList TaskLists task_list task_lists Task list task lists"
+        );
+        let names = Names::new("category", Some("categories")).expect("valid name");
+        assert_eq!(names.render("__Names_label__ in /__names__"), "Categories in /categories");
+        for invalid in ["", "Project", "2fa", "task-list", "task__list", "project_"] {
+            assert!(Names::new(invalid, None).is_err(), "{invalid}");
+        }
+        assert!(Names::new("sheep", Some("sheep")).is_err());
+    }
+
+    #[test]
+    fn generate_should_match_the_golden_snapshot() {
+        let root = std::env::temp_dir().join(format!("scaffold-{}", std::process::id()));
+        fs::create_dir_all(root.join("migrations")).expect("temp dir");
+        fs::write(root.join("migrations/007_task_attachments.up.sql"), "").expect("migration");
+
+        let names = Names::new("project", None).expect("valid name");
+        let paths = generate(&root, &names).expect("generated");
+        let generated = snapshot(&root, paths);
+        let again = generate(&root, &names);
+        fs::remove_dir_all(&root).expect("cleanup");
+
+        assert!(again.is_err(), "an existing feature must not be overwritten");
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(GOLDEN, &generated).expect("golden written");
+        }
+        let golden = fs::read_to_string(GOLDEN).expect("golden snapshot");
+        assert!(
+            generated == golden,
+            "generated files differ from {GOLDEN}; rerun with UPDATE_GOLDEN=1 to accept"
+        );
+    }
+
+    #[test]
+    #[ignore = "runs cargo clippy on a copy of the crate"]
+    fn generated_feature_should_pass_clippy_in_the_crate() {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let root = std::env::temp_dir().join(format!("scaffold-crate-{}", std::process::id()));
+        fs::create_dir_all(&root).expect("temp dir");
+        for file in ["Cargo.toml", "Cargo.lock", "build.rs", "clippy.toml"] {
+            fs::copy(manifest.join(file), root.join(file)).expect("copied file");
+        }
+        for dir in ["src", "migrations", "proto", "assets", "benches", "examples"] {
+            copy_tree(&manifest.join(dir), &root.join(dir));
+        }
+        generate(&root, &Names::new("project", None).expect("valid name")).expect("generated");
+        let features = root.join("src/features/mod.rs");
+        let declared = fs::read_to_string(&features).expect("features module");
+        fs::write(&features, declared + "pub mod project;\n").expect("feature declared");
+
+        // A target directory of its own, as the one of this test is locked while it runs
+        let output = std::process::Command::new(env!("CARGO"))
+            .args(["clippy", "--lib", "--tests", "--", "-D", "warnings"])
+            .current_dir(&root)
+            .env("CARGO_TARGET_DIR", manifest.join("target/scaffold-check"))
+            .output()
+            .expect("cargo runs");
+        fs::remove_dir_all(&root).expect("cleanup");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+}
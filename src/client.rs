@@ -0,0 +1,250 @@
+//! Typed client of the HTTP API (`client` feature)
+//!
+//! Other Rust services call the API through [`ApiClient`] instead of hand-writing
+//! requests. Bodies are the [`api_types`](crate::api_types) the server itself uses,
+//! and error responses are decoded back into the server's `code` and `message`.
+
+use crate::api_types::{
+    CreateTaskRequest, CreateUserRequest, TaskQuery, TaskResponse, UserResponse,
+};
+use crate::shared::infrastructure::http_client::USER_AGENT;
+use crate::shared::infrastructure::tenant::TENANT_HEADER;
+use axum::http::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Failure of an API call
+#[derive(Debug, thiserror::Error)]
+pub enum ApiClientError {
+    /// The server rejected the request with an API error body
+    #[error("{status} {code}: {message}")]
+    Api {
+        /// Response status
+        status: StatusCode,
+        /// Machine-readable error code, e.g. `NOT_FOUND`
+        code: String,
+        /// Human-readable message
+        message: String,
+    },
+    /// The server answered with a non-success status and no API error body
+    #[error("unexpected response status {0}")]
+    Status(StatusCode),
+    /// No response arrived (connection failure, timeout)
+    #[error("request failed: {0}")]
+    Transport(String),
+    /// A success response could not be decoded
+    #[error("invalid response body: {0}")]
+    Decode(String),
+}
+
+/// Where and how [`ApiClient`] reaches the API
+#[derive(Debug, Clone)]
+pub struct ApiClientConfig {
+    /// Base URL of the API, e.g. `http://tasks.internal:3000`
+    pub base_url: String,
+    /// Key sent as `Authorization: Bearer <key>`, when the deployment requires one
+    pub api_key: Option<String>,
+    /// Tenant sent as `x-tenant-id`; without it the server uses its default tenant
+    pub tenant: Option<String>,
+    /// Time allowed for a whole request, response included
+    pub timeout: Duration,
+}
+
+impl ApiClientConfig {
+    /// Configuration for `base_url` without an API key or tenant and a 10 second timeout
+    #[must_use]
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_key: None,
+            tenant: None,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Error body shape of the API (see `ApiError`)
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: String,
+    message: String,
+}
+
+/// Typed client of the HTTP API
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    tenant: Option<String>,
+}
+
+impl ApiClient {
+    /// Build a client from `config`, identifying as [`USER_AGENT`]
+    ///
+    /// # Errors
+    /// Fails when the TLS backend cannot be initialized.
+    pub fn new(config: &ApiClientConfig) -> Result<Self, ApiClientError> {
+        let http = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .user_agent(USER_AGENT)
+            .build()
+            .map_err(|e| ApiClientError::Transport(e.to_string()))?;
+        Ok(Self {
+            http,
+            base_url: config.base_url.trim_end_matches('/').to_owned(),
+            api_key: config.api_key.clone(),
+            tenant: config.tenant.clone(),
+        })
+    }
+
+    /// `POST /users`
+    ///
+    /// # Errors
+    /// See [`ApiClientError`]; e.g. `VALIDATION_ERROR` for an invalid email.
+    pub async fn create_user(
+        &self,
+        request: &CreateUserRequest,
+    ) -> Result<UserResponse, ApiClientError> {
+        self.send(self.http.post(self.url("/users")).json(request)).await
+    }
+
+    /// `GET /users/{id}`
+    ///
+    /// # Errors
+    /// See [`ApiClientError`]; e.g. `NOT_FOUND` for an unknown user.
+    pub async fn get_user(&self, id: &str) -> Result<UserResponse, ApiClientError> {
+        self.send(self.http.get(self.url(&format!("/users/{id}")))).await
+    }
+
+    /// `POST /tasks`; soft warnings in the response are not surfaced
+    ///
+    /// # Errors
+    /// See [`ApiClientError`]; e.g. `NOT_FOUND` when the owning user does not exist.
+    pub async fn create_task(
+        &self,
+        request: &CreateTaskRequest,
+    ) -> Result<TaskResponse, ApiClientError> {
+        self.send(self.http.post(self.url("/tasks")).json(request)).await
+    }
+
+    /// `GET /tasks/{id}`
+    ///
+    /// # Errors
+    /// See [`ApiClientError`]; e.g. `NOT_FOUND` for an unknown task.
+    pub async fn get_task(&self, id: &str) -> Result<TaskResponse, ApiClientError> {
+        self.send(self.http.get(self.url(&format!("/tasks/{id}")))).await
+    }
+
+    /// `GET /tasks`, filtered and embedded as described by `filter`.
+    ///
+    /// A `fields` projection leaves out fields the response type requires and then
+    /// fails to decode, so leave `filter.fields` unset.
+    ///
+    /// # Errors
+    /// See [`ApiClientError`]; e.g. `INVALID_QUERY` for an unsupported embed.
+    pub async fn list_tasks(
+        &self,
+        filter: &TaskQuery,
+    ) -> Result<Vec<TaskResponse>, ApiClientError> {
+        self.send(self.http.get(self.url("/tasks")).query(filter)).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, ApiClientError> {
+        let request = match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        };
+        let request = match &self.tenant {
+            Some(tenant) => request.header(TENANT_HEADER, tenant),
+            None => request,
+        };
+        let response =
+            request.send().await.map_err(|e| ApiClientError::Transport(e.to_string()))?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(|e| ApiClientError::Transport(e.to_string()))?;
+        if status.is_success() {
+            return serde_json::from_slice(&body)
+                .map_err(|e| ApiClientError::Decode(e.to_string()));
+        }
+        Err(match serde_json::from_slice::<ErrorBody>(&body) {
+            Ok(ErrorBody { code, message }) => ApiClientError::Api { status, code, message },
+            Err(_) => ApiClientError::Status(status),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::in_memory_app;
+
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn serve_in_memory() -> ApiClientConfig {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let base_url = format!("http://{}", listener.local_addr().expect("addr"));
+        tokio::spawn(async move { axum::serve(listener, in_memory_app()).await });
+        ApiClientConfig::new(base_url)
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn client_should_round_trip_users_and_tasks() {
+        let config = serve_in_memory().await;
+        let client = ApiClient::new(&config).expect("client");
+        let alice = CreateUserRequest { name: "Alice".into(), email: "alice@example.com".into() };
+        let user = client.create_user(&alice).await.expect("create user");
+        assert_eq!(client.get_user(&user.id).await.expect("get user"), user);
+
+        let request = CreateTaskRequest {
+            user_id: user.id.clone(),
+            title: "  Buy   milk ".into(),
+            description: String::new(),
+            completed: None,
+        };
+        let task = client.create_task(&request).await.expect("create task");
+        assert_eq!(task.title, "Buy milk");
+        assert_eq!(client.get_task(&task.id).await.expect("get task"), task);
+
+        let filter = TaskQuery { user_id: Some(user.id.clone()), ..TaskQuery::default() };
+        assert_eq!(client.list_tasks(&filter).await.expect("list"), vec![task.clone()]);
+        let filter = TaskQuery { embed: Some("user".into()), ..TaskQuery::default() };
+        let embedded = client.list_tasks(&filter).await.expect("list embedded");
+        assert_eq!(embedded[0].user.as_ref().map(|u| u.name.as_str()), Some("Alice"));
+
+        let acme = ApiClientConfig { tenant: Some("acme".into()), ..config };
+        let acme = ApiClient::new(&acme).expect("acme client");
+        assert!(matches!(acme.get_user(&user.id).await, Err(ApiClientError::Api { .. })));
+        assert_eq!(acme.list_tasks(&TaskQuery::default()).await.expect("acme list"), vec![]);
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn client_should_decode_api_errors() {
+        let client = ApiClient::new(&serve_in_memory().await).expect("client");
+        let error = client.get_task("missing").await.err();
+        assert!(
+            matches!(
+                &error,
+                Some(ApiClientError::Api { status: StatusCode::NOT_FOUND, code, .. })
+                    if code == "NOT_FOUND"
+            ),
+            "{error:?}"
+        );
+
+        let invalid = CreateUserRequest { name: "Bob".into(), email: "bob".into() };
+        let error = client.create_user(&invalid).await.err();
+        assert!(
+            matches!(&error, Some(ApiClientError::Api { code, .. }) if code == "VALIDATION_ERROR"),
+            "{error:?}"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! In-memory message queue, for tests and for driving [`consume`](super::consume)
+//! without a broker
+
+use super::{Message, MessageConsumer};
+use crate::shared::domain::DomainError;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+
+/// Queue holding its messages in memory, recording how each was settled
+#[derive(Debug, Default)]
+pub struct InMemoryQueue {
+    pending: VecDeque<Message>,
+    next_tag: u64,
+    /// Keep waiting for messages once the queue is empty instead of closing it
+    open: bool,
+    /// Messages acknowledged, in order
+    pub acked: Vec<Message>,
+    /// Messages dead-lettered, with the code and reason of their last failure
+    pub dead: Vec<(Message, String, String)>,
+}
+
+impl InMemoryQueue {
+    /// Wait for messages forever once empty, as a broker does, rather than closing
+    #[must_use]
+    pub fn open(self) -> Self {
+        Self { open: true, ..self }
+    }
+
+    /// Add a message with `body` for `tenant` at the back of the queue
+    pub fn push(&mut self, body: Vec<u8>, tenant: Option<&str>) {
+        self.next_tag += 1;
+        let tenant = tenant.map(str::to_owned);
+        self.pending.push_back(Message { tag: self.next_tag, body, tenant, attempts: 0 });
+    }
+
+    /// Number of messages waiting to be received
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[async_trait]
+impl MessageConsumer for InMemoryQueue {
+    async fn next(&mut self) -> Result<Option<Message>, DomainError> {
+        match self.pending.pop_front() {
+            Some(message) => Ok(Some(message)),
+            None if self.open => std::future::pending().await,
+            None => Ok(None),
+        }
+    }
+
+    async fn ack(&mut self, message: &Message) -> Result<(), DomainError> {
+        self.acked.push(message.clone());
+        Ok(())
+    }
+
+    async fn retry(&mut self, message: &Message) -> Result<(), DomainError> {
+        let attempts = message.attempts + 1;
+        self.pending.push_back(Message { attempts, ..message.clone() });
+        Ok(())
+    }
+
+    async fn dead_letter(
+        &mut self,
+        message: &Message,
+        code: &str,
+        reason: &str,
+    ) -> Result<(), DomainError> {
+        self.dead.push((message.clone(), code.to_owned(), reason.to_owned()));
+        Ok(())
+    }
+}
@@ -0,0 +1,455 @@
+//! Use cases driven by messages from a queue (`consumers` cargo feature)
+//!
+//! A [`MessageConsumer`] receives messages from a broker, and [`consume`] hands each
+//! to a [`MessageHandler`] running a use case. A success is acknowledged; a
+//! transient failure (see [`is_transient`]) puts the message back on its queue until
+//! it has been attempted [`RetryPolicy::max_attempts`] times, and any other failure
+//! moves it to the dead-letter queue at once, with the error code clients would get
+//! over HTTP. The tenant of a message comes from its `x-tenant-id` header, as over
+//! HTTP.
+//!
+//! [`spawn`] runs the consumer apart from the HTTP server: a lost broker connection
+//! is logged and reopened with a backoff instead of stopping the process.
+//!
+//! Delivery is at least once: a message handled but not yet acknowledged when the
+//! process stops is handled again, which creates its task twice.
+
+pub mod amqp;
+pub mod in_memory;
+
+pub use amqp::{AmqpConsumer, AmqpSettings};
+pub use in_memory::InMemoryQueue;
+
+use crate::api_types::CreateTaskRequest;
+use crate::app::AppState;
+use crate::features::task::application::CreateTaskCommand;
+use crate::features::task::TaskState;
+use crate::shared::application::Validated;
+use crate::shared::domain::{DomainError, Entity, ErrorStatus, TenantId};
+use crate::shared::infrastructure::config::Config;
+use crate::shared::infrastructure::http::ApiError;
+use crate::shared::infrastructure::tenant::TenantPolicy;
+use async_trait::async_trait;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// A message received from a queue
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Message {
+    /// Identifies the message to the consumer that received it
+    pub tag: u64,
+    /// JSON body
+    pub body: Vec<u8>,
+    /// Value of the `x-tenant-id` header
+    pub tenant: Option<String>,
+    /// Failed attempts at handling the message so far
+    pub attempts: u32,
+}
+
+/// Receives messages from a queue and settles them once they are handled
+#[async_trait]
+pub trait MessageConsumer: Send {
+    /// Wait for the next message; `None` once the queue is closed
+    async fn next(&mut self) -> Result<Option<Message>, DomainError>;
+
+    /// Settle `message` as handled
+    async fn ack(&mut self, message: &Message) -> Result<(), DomainError>;
+
+    /// Put `message` back on its queue with one more failed attempt counted
+    async fn retry(&mut self, message: &Message) -> Result<(), DomainError>;
+
+    /// Move `message` to the dead-letter queue with the `code` and `reason` of its
+    /// last failure
+    async fn dead_letter(
+        &mut self,
+        message: &Message,
+        code: &str,
+        reason: &str,
+    ) -> Result<(), DomainError>;
+}
+
+/// Runs a use case with the body of a message
+#[async_trait]
+pub trait MessageHandler: Send + Sync {
+    /// Handle `body`, sent for `tenant`
+    async fn handle(&self, tenant: &TenantId, body: &[u8]) -> Result<(), DomainError>;
+}
+
+/// Creates a task from a message with the JSON body of `POST /tasks`
+pub struct CreateTaskHandler(pub Arc<TaskState>);
+
+#[async_trait]
+impl MessageHandler for CreateTaskHandler {
+    async fn handle(&self, tenant: &TenantId, body: &[u8]) -> Result<(), DomainError> {
+        let request: CreateTaskRequest = serde_json::from_slice(body)
+            .map_err(|e| DomainError::Validation(format!("Invalid message body: {e}")))?;
+        if request.completed == Some(true) {
+            let reason = "cannot be set on create; import completed tasks instead";
+            return Err(DomainError::validation("Completed", reason));
+        }
+        let command = Validated::new(CreateTaskCommand {
+            user_id: request.user_id,
+            title: request.title,
+            description: request.description,
+        })?;
+        let (task, _) = self.0.create_task.execute(tenant, command).await?;
+        tracing::debug!(task_id = task.id().value(), "Task created from a message");
+        Ok(())
+    }
+}
+
+/// How often a message failing transiently is attempted
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Attempts before the message is dead-lettered, the first included; at least 1
+    pub max_attempts: u32,
+}
+
+/// Whether `error` may not happen again, so the message is worth retrying
+#[must_use]
+pub fn is_transient(error: &DomainError) -> bool {
+    match error {
+        DomainError::Unavailable(_)
+        | DomainError::Infrastructure(_)
+        | DomainError::Unexpected(_) => true,
+        DomainError::Custom { status_hint, .. } => *status_hint == ErrorStatus::Unavailable,
+        _ => false,
+    }
+}
+
+/// Handle the messages of `consumer` with `handler` until the queue closes or
+/// `shutdown` resolves, finishing the message in hand first
+///
+/// # Errors
+/// Those of `consumer`; failures of `handler` settle the message instead.
+pub async fn consume(
+    consumer: &mut dyn MessageConsumer,
+    handler: &dyn MessageHandler,
+    retry: RetryPolicy,
+    tenants: TenantPolicy,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), DomainError> {
+    let mut shutdown = pin!(shutdown);
+    loop {
+        let message = tokio::select! {
+            () = &mut shutdown => return Ok(()),
+            message = consumer.next() => message?,
+        };
+        let Some(message) = message else {
+            return Ok(());
+        };
+        let tenant = message.tenant.as_deref().map(str::as_bytes);
+        let outcome = match tenants.resolve(tenant) {
+            Ok(tenant) => handler.handle(&tenant, &message.body).await,
+            Err(e) => Err(e),
+        };
+        let attempts = message.attempts + 1;
+        match outcome {
+            Ok(()) => consumer.ack(&message).await?,
+            Err(e) if is_transient(&e) && attempts < retry.max_attempts => {
+                tracing::warn!(error = %e, attempts, "Message failed; retrying");
+                consumer.retry(&message).await?;
+            }
+            Err(e) => {
+                let reason = e.to_string();
+                let code = ApiError::from(e).code;
+                tracing::warn!(code, reason, attempts, "Message failed; dead-lettering");
+                consumer.dead_letter(&message, code, &reason).await?;
+            }
+        }
+    }
+}
+
+/// Delays between attempts at reaching the broker after a failure
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    /// Delay after the first failure, doubled after each further one
+    pub initial: Duration,
+    /// Longest delay
+    pub max: Duration,
+}
+
+/// Delays of the consumer [`spawn`] starts
+const BACKOFF: Backoff =
+    Backoff { initial: Duration::from_secs(1), max: Duration::from_secs(30) };
+
+/// Handle the messages of the consumers `connect` opens with `handler` until a queue
+/// closes or `shutdown` resolves
+///
+/// A failure to connect or to consume is logged, and a new consumer is opened after a
+/// delay following `backoff`, which starts over once a consumer is connected.
+pub async fn consume_reconnecting<C, F>(
+    mut connect: impl FnMut() -> F,
+    handler: &dyn MessageHandler,
+    retry: RetryPolicy,
+    tenants: TenantPolicy,
+    backoff: Backoff,
+    shutdown: impl Future<Output = ()>,
+) where
+    C: MessageConsumer,
+    F: Future<Output = Result<C, DomainError>>,
+{
+    let mut shutdown = pin!(shutdown);
+    let mut delay = backoff.initial;
+    loop {
+        let connected = tokio::select! {
+            () = &mut shutdown => return,
+            connected = connect() => connected,
+        };
+        let error = match connected {
+            Ok(mut consumer) => {
+                delay = backoff.initial;
+                match consume(&mut consumer, handler, retry, tenants, shutdown.as_mut()).await {
+                    Ok(()) => return,
+                    Err(e) => e,
+                }
+            }
+            Err(e) => e,
+        };
+        tracing::error!(error = %error, retry_in = ?delay, "Queue consumer failed");
+        tokio::select! {
+            () = &mut shutdown => return,
+            () = tokio::time::sleep(delay) => {}
+        }
+        delay = (delay * 2).min(backoff.max);
+    }
+}
+
+/// Create tasks from the messages of `AMQP_QUEUE` on a task of their own until
+/// `shutdown` resolves; `None` when `AMQP_URL` is empty
+///
+/// The consumer never stops the HTTP server: losing the broker is logged and followed
+/// by reconnections, backing off up to 30 seconds apart.
+///
+/// # Errors
+/// Fails when `AMQP_URL` is invalid or set without the `task` feature.
+pub fn spawn(
+    state: &AppState,
+    config: &Config,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<Option<JoinHandle<()>>> {
+    if config.amqp_url.is_empty() {
+        return Ok(None);
+    }
+    let Some(task) = state.task.as_ref() else {
+        anyhow::bail!("AMQP_URL requires the 'task' feature");
+    };
+    let settings = AmqpSettings::from_config(config)?;
+    let handler = CreateTaskHandler(Arc::clone(task));
+    let retry = RetryPolicy { max_attempts: config.amqp_max_attempts.max(1) };
+    let tenants = state.tenant_policy;
+    Ok(Some(tokio::spawn(async move {
+        let settings = &settings;
+        let connect = move || async move {
+            let consumer = AmqpConsumer::connect(settings).await?;
+            tracing::info!(queue = settings.queue, "Consuming task creations");
+            Ok(consumer)
+        };
+        consume_reconnecting(connect, &handler, retry, tenants, BACKOFF, shutdown).await;
+    })))
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::app::InMemoryRepositories;
+    use crate::features::task::application::TaskListQuery;
+    use crate::shared::application::PageRequest;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tokio::sync::Notify;
+
+    const RETRY: RetryPolicy = RetryPolicy { max_attempts: 3 };
+
+    fn task_handler() -> (Arc<TaskState>, CreateTaskHandler) {
+        let state = AppState::build(&Config::default(), &InMemoryRepositories::default())
+            .expect("valid state");
+        let task = Arc::clone(state.task.as_ref().expect("task feature"));
+        (Arc::clone(&task), CreateTaskHandler(task))
+    }
+
+    fn body(title: &str) -> Vec<u8> {
+        let request = json!({"userId": "user-1", "title": title, "description": ""});
+        serde_json::to_vec(&request).expect("json")
+    }
+
+    /// Fails with `Unavailable` the first `failures` times
+    struct Flaky {
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl MessageHandler for Flaky {
+        async fn handle(&self, _: &TenantId, _: &[u8]) -> Result<(), DomainError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(DomainError::Unavailable("broker test".into()));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn consume_should_create_tasks_and_ack_their_messages() {
+        let (state, handler) = task_handler();
+        let mut queue = InMemoryQueue::default();
+        queue.push(body("Buy milk"), None);
+        queue.push(body("Walk dog"), Some("acme"));
+
+        let tenants = TenantPolicy::default();
+        consume(&mut queue, &handler, RETRY, tenants, std::future::pending()).await.expect("ok");
+
+        assert_eq!(queue.acked.len(), 2);
+        assert!(queue.dead.is_empty());
+        for tenant in [TenantId::default(), TenantId::new("acme").expect("tenant")] {
+            let query = Validated::new(TaskListQuery::default()).expect("valid query");
+            let tasks = state.list_tasks.execute(&tenant, query, PageRequest::default()).await;
+            let tasks = tasks.expect("tasks");
+            assert_eq!(tasks.len(), 1, "{tenant:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn consume_should_dead_letter_invalid_messages_at_once() {
+        let (_, handler) = task_handler();
+        let mut queue = InMemoryQueue::default();
+        queue.push(body("   "), None);
+        queue.push(b"not json".to_vec(), None);
+        queue.push(body("Buy milk"), Some("not a tenant!"));
+
+        let tenants = TenantPolicy::default();
+        consume(&mut queue, &handler, RETRY, tenants, std::future::pending()).await.expect("ok");
+
+        assert!(queue.acked.is_empty());
+        let dead = queue.dead.iter().map(|(m, code, _)| (m.attempts, code.as_str()));
+        assert_eq!(dead.collect::<Vec<_>>(), [(0, "VALIDATION_ERROR"); 3]);
+        assert!(queue.dead[1].2.starts_with("Validation error: Invalid message body"));
+    }
+
+    #[tokio::test]
+    async fn consume_should_retry_transient_failures_up_to_the_attempts() {
+        let mut queue = InMemoryQueue::default();
+        queue.push(b"{}".to_vec(), None);
+        let recovering = Flaky { failures: 2, calls: AtomicU32::new(0) };
+        let tenants = TenantPolicy::default();
+        consume(&mut queue, &recovering, RETRY, tenants, std::future::pending()).await.expect("ok");
+        assert_eq!(queue.acked.iter().map(|m| m.attempts).collect::<Vec<_>>(), [2]);
+
+        let mut queue = InMemoryQueue::default();
+        queue.push(b"{}".to_vec(), None);
+        let failing = Flaky { failures: u32::MAX, calls: AtomicU32::new(0) };
+        consume(&mut queue, &failing, RETRY, tenants, std::future::pending()).await.expect("ok");
+        assert_eq!(failing.calls.load(Ordering::SeqCst), 3);
+        let (message, code, _) = queue.dead.first().expect("dead-lettered");
+        assert_eq!((message.attempts, code.as_str()), (2, "SERVICE_BUSY"));
+    }
+
+    #[tokio::test]
+    async fn consume_should_stop_on_shutdown() {
+        let (_, handler) = task_handler();
+        let mut queue = InMemoryQueue::default().open();
+        queue.push(body("Buy milk"), None);
+
+        let tenants = TenantPolicy::default();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let consumer = consume(&mut queue, &handler, RETRY, tenants, async {
+            stopped.await.ok();
+        });
+        stop.send(()).expect("consumer waiting");
+        consumer.await.expect("stopped");
+        // A message already waiting may be handled or left, but never half handled
+        assert_eq!(queue.acked.len() + queue.pending(), 1);
+    }
+
+    /// Connector failing `failures` times, counting and signalling each attempt, then
+    /// opening `queue`
+    fn connector(
+        failures: u32,
+        queue: InMemoryQueue,
+        attempts: Arc<(AtomicU32, Notify)>,
+    ) -> impl FnMut() -> std::future::Ready<Result<InMemoryQueue, DomainError>> {
+        let mut queue = Some(queue);
+        move || {
+            let (count, attempted) = &*attempts;
+            let failed = count.fetch_add(1, Ordering::SeqCst) < failures;
+            attempted.notify_one();
+            std::future::ready(match queue.take_if(|_| !failed) {
+                Some(queue) => Ok(queue),
+                None => Err(DomainError::Unavailable("broker down".into())),
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn consume_reconnecting_should_back_off_until_it_connects() {
+        let handler = Flaky { failures: 0, calls: AtomicU32::new(0) };
+        let mut queue = InMemoryQueue::default();
+        queue.push(b"{}".to_vec(), None);
+        let attempts = Arc::new((AtomicU32::new(0), Notify::new()));
+        let connect = connector(3, queue, Arc::clone(&attempts));
+        let backoff = Backoff { initial: Duration::from_secs(1), max: Duration::from_secs(3) };
+        let tenants = TenantPolicy::default();
+
+        let started = tokio::time::Instant::now();
+        let shutdown = std::future::pending();
+        consume_reconnecting(connect, &handler, RETRY, tenants, backoff, shutdown).await;
+        assert_eq!(attempts.0.load(Ordering::SeqCst), 4);
+        assert_eq!(handler.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(started.elapsed(), Duration::from_secs(1 + 2 + 3));
+    }
+
+    #[tokio::test]
+    async fn a_failing_consumer_should_not_drop_in_flight_requests() {
+        let attempts = Arc::new((AtomicU32::new(0), Notify::new()));
+        // A request still running once the consumer has failed twice
+        let waiting = Arc::clone(&attempts);
+        let app = axum::Router::new().route(
+            "/slow",
+            axum::routing::get(|| async move {
+                waiting.1.notified().await;
+                waiting.1.notified().await;
+                "done"
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("http://{}/slow", listener.local_addr().expect("addr"));
+        let server = tokio::spawn(async { axum::serve(listener, app).await });
+
+        let (_, handler) = task_handler();
+        let connect = connector(u32::MAX, InMemoryQueue::default(), Arc::clone(&attempts));
+        let backoff = Backoff { initial: Duration::from_millis(1), max: Duration::from_millis(1) };
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let consumer = tokio::spawn(async move {
+            let shutdown = async {
+                stopped.await.ok();
+            };
+            let tenants = TenantPolicy::default();
+            consume_reconnecting(connect, &handler, RETRY, tenants, backoff, shutdown).await;
+        });
+
+        let response = reqwest::get(url).await.expect("response");
+        assert_eq!(response.text().await.expect("body"), "done");
+        assert!(attempts.0.load(Ordering::SeqCst) >= 2);
+        assert!(!server.is_finished());
+        stop.send(()).expect("consumer running");
+        consumer.await.expect("consumer stopped");
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn spawn_should_require_the_task_feature() {
+        let mut config = Config::default();
+        config.amqp_url = "amqp://localhost".into();
+        config.disabled_features = vec!["task".into()];
+        let state =
+            AppState::build(&config, &InMemoryRepositories::default()).expect("valid state");
+        let error = spawn(&state, &config, std::future::pending()).expect_err("no task");
+        assert_eq!(error.to_string(), "AMQP_URL requires the 'task' feature");
+        let consumer = spawn(&state, &Config::default(), std::future::pending());
+        assert!(consumer.expect("no AMQP_URL").is_none());
+    }
+}
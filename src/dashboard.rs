@@ -0,0 +1,153 @@
+//! Embedded admin dashboard for demos (`dashboard` cargo feature)
+//!
+//! A static page under `assets/dashboard` compiled into the binary. It only calls
+//! the JSON API, so it holds no logic of its own. Its assets are revalidated on
+//! every load through an `ETag`, and the page is locked down by a
+//! `Content-Security-Policy` allowing nothing but same-origin scripts, styles and
+//! API calls.
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// Policy of every dashboard response; inline scripts and styles are refused
+pub const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; script-src 'self'; \
+    style-src 'self'; connect-src 'self'; base-uri 'none'; form-action 'none'; \
+    frame-ancestors 'none'";
+
+/// An embedded file with its content type
+#[derive(Clone, Copy)]
+struct Asset {
+    content_type: &'static str,
+    body: &'static str,
+}
+
+const INDEX: Asset = Asset {
+    content_type: "text/html; charset=utf-8",
+    body: include_str!("../assets/dashboard/index.html"),
+};
+const SCRIPT: Asset = Asset {
+    content_type: "text/javascript; charset=utf-8",
+    body: include_str!("../assets/dashboard/app.js"),
+};
+const STYLE: Asset = Asset {
+    content_type: "text/css; charset=utf-8",
+    body: include_str!("../assets/dashboard/style.css"),
+};
+
+impl Asset {
+    /// Strong entity tag of the body, so a new build invalidates cached copies
+    fn etag(self) -> String {
+        let digest = Sha256::digest(self.body.as_bytes());
+        let mut tag = digest.iter().take(8).fold(String::from("\""), |mut tag, byte| {
+            let _ = write!(tag, "{byte:02x}");
+            tag
+        });
+        tag.push('"');
+        tag
+    }
+}
+
+/// Serve `asset`, or `304 Not Modified` when `If-None-Match` carries its tag
+async fn serve(State(asset): State<Asset>, headers: HeaderMap) -> Response {
+    let etag = asset.etag();
+    let cached = headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || tag.trim() == etag);
+    let mut response = if cached {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        ([(header::CONTENT_TYPE, asset.content_type)], asset.body).into_response()
+    };
+    let headers = response.headers_mut();
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    headers
+        .insert(header::CONTENT_SECURITY_POLICY, HeaderValue::from_static(CONTENT_SECURITY_POLICY));
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, etag);
+    }
+    response
+}
+
+/// Routes serving the dashboard at `GET /dashboard`
+pub fn routes() -> Router {
+    Router::new()
+        .route("/dashboard", get(serve).with_state(INDEX))
+        .route("/dashboard/app.js", get(serve).with_state(SCRIPT))
+        .route("/dashboard/style.css", get(serve).with_state(STYLE))
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::shared::infrastructure::config::Config;
+    use crate::test_support::in_memory_app_with;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn get(app: &Router, uri: &str, if_none_match: Option<&str>) -> Response {
+        let mut request = Request::get(uri);
+        if let Some(tag) = if_none_match {
+            request = request.header(header::IF_NONE_MATCH, tag);
+        }
+        let request = request.body(Body::empty()).expect("request");
+        app.clone().oneshot(request).await.expect("response")
+    }
+
+    fn header_of<'a>(response: &'a Response, name: &header::HeaderName) -> &'a str {
+        response.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn dashboard_should_serve_assets_with_their_types_and_policy() {
+        let app = in_memory_app_with(&Config::default());
+        for (uri, content_type) in [
+            ("/dashboard", "text/html; charset=utf-8"),
+            ("/dashboard/app.js", "text/javascript; charset=utf-8"),
+            ("/dashboard/style.css", "text/css; charset=utf-8"),
+        ] {
+            let response = get(&app, uri, None).await;
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+            assert_eq!(header_of(&response, &header::CONTENT_TYPE), content_type);
+            assert_eq!(header_of(&response, &header::CACHE_CONTROL), "no-cache");
+            let policy = header_of(&response, &header::CONTENT_SECURITY_POLICY);
+            assert_eq!(policy, CONTENT_SECURITY_POLICY);
+        }
+
+        let body = get(&app, "/dashboard", None).await.into_body();
+        let html = axum::body::to_bytes(body, usize::MAX).await.expect("body");
+        assert!(String::from_utf8_lossy(&html).contains("<script src=\"/dashboard/app.js\""));
+    }
+
+    #[tokio::test]
+    async fn dashboard_should_revalidate_with_etag() {
+        let app = in_memory_app_with(&Config::default());
+        let etag = header_of(&get(&app, "/dashboard", None).await, &header::ETAG).to_owned();
+        assert!(etag.starts_with('"'));
+
+        let cached = get(&app, "/dashboard", Some(&format!("\"stale\", {etag}"))).await;
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(header_of(&cached, &header::ETAG), etag);
+        let stale = get(&app, "/dashboard", Some("\"stale\"")).await;
+        assert_eq!(stale.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn dashboard_should_be_absent_when_disabled() {
+        let mut config = Config::default();
+        config.dashboard_enabled = false;
+        let response = get(&in_memory_app_with(&config), "/dashboard", None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
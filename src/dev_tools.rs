@@ -0,0 +1,153 @@
+//! Endpoints for exercising clients against predictable responses (`dev-tools`
+//! cargo feature, debug builds only)
+//!
+//! Served under `/dev` when `DEV_TOOLS_ENABLED` is on: `/dev/echo` answers with the
+//! method, headers and body of the request, `/dev/delay/{ms}` answers `200` after
+//! sleeping that long (at most [`MAX_DELAY`]), and `/dev/error/{status}` answers the
+//! error envelope with that status and the code `SIMULATED`. They touch no data, and
+//! go through the same middleware as the API, timeouts and tracing included.
+
+use crate::shared::infrastructure::http::{json_response, ApiError, ApiPath};
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, Method, StatusCode},
+    response::Response,
+    routing::{any, get},
+    Router,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Longest delay `/dev/delay/{ms}` sleeps; longer ones are cut to it
+pub const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Body of `/dev/echo`
+#[derive(Debug, Serialize)]
+struct Echo {
+    method: String,
+    /// Values of each header, in the order they were sent
+    headers: BTreeMap<String, Vec<String>>,
+    /// The body, with invalid UTF-8 replaced
+    body: String,
+}
+
+/// Body of `/dev/delay/{ms}`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Delayed {
+    delayed_ms: u64,
+}
+
+/// `/dev/echo`, `/dev/delay/{ms}` and `/dev/error/{status}`
+pub fn routes() -> Router {
+    Router::new()
+        .route("/dev/echo", any(echo))
+        .route("/dev/delay/{ms}", get(delay))
+        .route("/dev/error/{status}", get(error))
+}
+
+/// `/dev/echo`: the method, headers and body of the request, with any method
+async fn echo(method: Method, headers: HeaderMap, body: Bytes) -> Response {
+    let mut echoed: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, value) in &headers {
+        let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+        echoed.entry(name.as_str().to_owned()).or_default().push(value);
+    }
+    let body = String::from_utf8_lossy(&body).into_owned();
+    json_response(StatusCode::OK, &Echo { method: method.to_string(), headers: echoed, body })
+}
+
+/// `GET /dev/delay/{ms}`: `200` after `ms` milliseconds, at most [`MAX_DELAY`]
+async fn delay(ApiPath(ms): ApiPath<u64>) -> Response {
+    let delay = Duration::from_millis(ms).min(MAX_DELAY);
+    tokio::time::sleep(delay).await;
+    let delayed_ms = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+    json_response(StatusCode::OK, &Delayed { delayed_ms })
+}
+
+/// `GET /dev/error/{status}`: the error envelope with `status` and the code
+/// `SIMULATED`; `400 INVALID_PATH` unless `status` is a 4xx or 5xx status
+async fn error(ApiPath(status): ApiPath<u16>) -> ApiError {
+    match StatusCode::from_u16(status) {
+        Ok(status) if status.is_client_error() || status.is_server_error() => {
+            ApiError::new(status, "SIMULATED", format!("Simulated {status} error"))
+        }
+        _ => ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "INVALID_PATH",
+            format!("Status must be a 4xx or 5xx status code, not {status}"),
+        ),
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::shared::infrastructure::config::Config;
+    use crate::test_support::{in_memory_app_with, send, send_request};
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::json;
+
+    fn app() -> axum::Router {
+        let mut config = Config::default();
+        config.dev_tools_enabled = true;
+        in_memory_app_with(&config)
+    }
+
+    #[tokio::test]
+    async fn echo_should_return_the_method_headers_and_body() {
+        let request = Request::post("/dev/echo")
+            .header("x-probe", "one")
+            .header("x-probe", "two")
+            .body(Body::from("plain body"))
+            .expect("request");
+        let (status, echo) = send_request(&app(), request).await;
+        assert_eq!((status, &echo["method"]), (StatusCode::OK, &json!("POST")));
+        assert_eq!(echo["headers"]["x-probe"], json!(["one", "two"]));
+        assert_eq!(echo["body"], "plain body");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn delay_should_sleep_at_most_thirty_seconds() {
+        let started = tokio::time::Instant::now();
+        let (status, body) = send(&app(), Method::GET, "/dev/delay/250", None).await;
+        assert_eq!((status, body), (StatusCode::OK, json!({"delayedMs": 250})));
+        assert_eq!(started.elapsed().as_millis(), 250);
+
+        let started = tokio::time::Instant::now();
+        let response = delay(ApiPath(90_000)).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("body");
+        let delayed: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert_eq!(delayed, json!({"delayedMs": 30_000}));
+        assert_eq!(started.elapsed(), MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn error_should_return_the_envelope_with_the_status() {
+        let app = app();
+        let (status, body) = send(&app, Method::GET, "/dev/error/418", None).await;
+        assert_eq!((status, &body["code"]), (StatusCode::IM_A_TEAPOT, &json!("SIMULATED")));
+        assert_eq!(body["message"], "Simulated 418 I'm a teapot error");
+        let (status, body) = send(&app, Method::GET, "/dev/error/503", None).await;
+        let unavailable = (StatusCode::SERVICE_UNAVAILABLE, &json!("SIMULATED"));
+        assert_eq!((status, &body["code"]), unavailable);
+
+        for uri in ["/dev/error/200", "/dev/error/teapot"] {
+            let (status, body) = send(&app, Method::GET, uri, None).await;
+            let invalid = (StatusCode::BAD_REQUEST, &json!("INVALID_PATH"));
+            assert_eq!((status, &body["code"]), invalid, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn dev_tools_should_be_absent_unless_enabled() {
+        let app = in_memory_app_with(&Config::default());
+        for uri in ["/dev/echo", "/dev/delay/0", "/dev/error/500"] {
+            let (status, _) = send(&app, Method::GET, uri, None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        }
+    }
+}
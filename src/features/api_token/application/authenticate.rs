@@ -0,0 +1,197 @@
+//! Authenticate API token use case
+
+use crate::features::api_token::domain::{
+    hash_token, ApiToken, ApiTokenRepository, TOKEN_PREFIX,
+};
+use crate::shared::domain::{DomainError, Entity, TenantId};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// Use case for authenticating a request by the API token it presents
+pub struct AuthenticateApiTokenUseCase {
+    repository: Arc<dyn ApiTokenRepository>,
+}
+
+impl AuthenticateApiTokenUseCase {
+    /// Create a new use case instance looking tokens up in `repository`, which
+    /// should be cached: every authenticated request looks its token up
+    pub fn new(repository: Arc<dyn ApiTokenRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// The usable token of `tenant` whose plaintext is `plaintext`, or `None` when
+    /// there is none or it expired or was revoked
+    ///
+    /// The use is recorded unless one was less than
+    /// [`LAST_USED_RESOLUTION`](crate::features::api_token::domain::LAST_USED_RESOLUTION)
+    /// ago; failing to record it does not fail the authentication.
+    ///
+    /// # Errors
+    /// Repository failures looking the token up.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        plaintext: &str,
+    ) -> Result<Option<ApiToken>, DomainError> {
+        // Not one of ours, so not worth a lookup
+        if !plaintext.starts_with(TOKEN_PREFIX) {
+            return Ok(None);
+        }
+        let Some(mut token) = self.repository.find_by_hash(tenant, &hash_token(plaintext)).await?
+        else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        if !token.is_usable(now) {
+            return Ok(None);
+        }
+        if token.needs_touch(now) {
+            match self.repository.touch(tenant, &token, now).await {
+                Ok(()) => token.touch(now),
+                Err(e) => tracing::warn!(
+                    token_id = token.id().value(),
+                    error = %e,
+                    "Failed to record the use of an API token"
+                ),
+            }
+        }
+        Ok(Some(token))
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::api_token::domain::{generate_token, ApiTokenId, LAST_USED_RESOLUTION};
+    use crate::features::api_token::infrastructure::InMemoryApiTokenRepository;
+    use crate::shared::domain::UserId;
+    use chrono::{DateTime, TimeDelta};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Repository counting the uses it records
+    #[derive(Default)]
+    struct CountingTouches {
+        inner: InMemoryApiTokenRepository,
+        touches: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl ApiTokenRepository for CountingTouches {
+        async fn find_by_user(
+            &self,
+            tenant: &TenantId,
+            user_id: &UserId,
+        ) -> Result<Vec<ApiToken>, DomainError> {
+            self.inner.find_by_user(tenant, user_id).await
+        }
+
+        async fn find_by_hash(
+            &self,
+            tenant: &TenantId,
+            token_hash: &str,
+        ) -> Result<Option<ApiToken>, DomainError> {
+            self.inner.find_by_hash(tenant, token_hash).await
+        }
+
+        async fn insert(&self, tenant: &TenantId, token: &ApiToken) -> Result<(), DomainError> {
+            self.inner.insert(tenant, token).await
+        }
+
+        async fn revoke(
+            &self,
+            tenant: &TenantId,
+            user_id: &UserId,
+            id: &ApiTokenId,
+            at: DateTime<Utc>,
+        ) -> Result<bool, DomainError> {
+            self.inner.revoke(tenant, user_id, id, at).await
+        }
+
+        async fn touch(
+            &self,
+            tenant: &TenantId,
+            token: &ApiToken,
+            at: DateTime<Utc>,
+        ) -> Result<(), DomainError> {
+            self.touches.fetch_add(1, Ordering::Relaxed);
+            self.inner.touch(tenant, token, at).await
+        }
+    }
+
+    struct Fixture {
+        repository: Arc<CountingTouches>,
+        use_case: AuthenticateApiTokenUseCase,
+        tenant: TenantId,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let repository = Arc::new(CountingTouches::default());
+            let use_case = AuthenticateApiTokenUseCase::new(Arc::clone(&repository) as _);
+            Self { repository, use_case, tenant: TenantId::default() }
+        }
+
+        /// Store a token issued a day ago and valid until `expires_at`, with its plaintext
+        async fn issue(&self, expires_at: Option<DateTime<Utc>>) -> (ApiToken, String) {
+            let plaintext = generate_token();
+            let token = ApiToken::reconstitute(
+                ApiTokenId::generate(),
+                UserId::generate(),
+                "ci".into(),
+                hash_token(&plaintext),
+                Utc::now() - TimeDelta::days(1),
+                expires_at,
+                None,
+                None,
+            );
+            self.repository.insert(&self.tenant, &token).await.expect("insert");
+            (token, plaintext)
+        }
+
+        async fn authenticate(&self, plaintext: &str) -> Option<ApiToken> {
+            self.use_case.execute(&self.tenant, plaintext).await.expect("looked up")
+        }
+
+        fn touches(&self) -> usize {
+            self.repository.touches.load(Ordering::Relaxed)
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_should_find_usable_tokens_recording_a_use_once_per_resolution() {
+        let fixture = Fixture::new();
+        let (token, plaintext) = fixture.issue(None).await;
+
+        let found = fixture.authenticate(&plaintext).await.expect("authenticated");
+        assert_eq!(found.id(), token.id());
+        assert!(found.last_used_at().is_some());
+        assert!(fixture.authenticate(&plaintext).await.is_some());
+        assert_eq!(fixture.touches(), 1, "used again within the resolution");
+
+        // As if the recorded use were older than the resolution
+        let stale = Utc::now() - LAST_USED_RESOLUTION;
+        fixture.repository.inner.touch(&fixture.tenant, &token, stale).await.expect("touch");
+        assert!(fixture.authenticate(&plaintext).await.is_some());
+        assert_eq!(fixture.touches(), 2);
+    }
+
+    #[tokio::test]
+    async fn execute_should_reject_unknown_expired_revoked_and_foreign_tokens() {
+        let fixture = Fixture::new();
+        let (_, expired) = fixture.issue(Some(Utc::now() - TimeDelta::seconds(1))).await;
+        let (revoked, revoked_plaintext) = fixture.issue(None).await;
+        let (tenant, user_id) = (&fixture.tenant, revoked.user_id());
+        let result = fixture.repository.revoke(tenant, user_id, revoked.id(), Utc::now()).await;
+        assert!(result.expect("revoked"));
+        let (_, plaintext) = fixture.issue(None).await;
+
+        for unusable in [&generate_token(), "not-a-token", &expired, &revoked_plaintext] {
+            assert!(fixture.authenticate(unusable).await.is_none(), "{unusable}");
+        }
+        let other = TenantId::new("other").expect("valid tenant");
+        let found = fixture.use_case.execute(&other, &plaintext).await.expect("looked up");
+        assert!(found.is_none(), "tokens authenticate within their tenant");
+        assert_eq!(fixture.touches(), 0);
+    }
+}
@@ -0,0 +1,100 @@
+//! Create API token use case
+
+use crate::features::api_token::domain::{
+    generate_token, hash_token, ApiToken, ApiTokenId, ApiTokenRepository,
+};
+use crate::features::user::domain::UserRepository;
+use crate::shared::domain::{DomainError, TenantId, UserId};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// Command to issue an API token to a user
+#[derive(Debug)]
+pub struct CreateApiTokenCommand {
+    /// Name of the token, e.g. the service it is for
+    pub name: String,
+    /// End of the validity of the token; valid until revoked when `None`
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A newly issued token with its plaintext, which is not stored
+#[derive(Debug)]
+pub struct IssuedApiToken {
+    /// The token, as stored
+    pub token: ApiToken,
+    /// Plaintext of the token, to hand to the user this once
+    pub plaintext: String,
+}
+
+/// Use case for issuing an API token to a user
+pub struct CreateApiTokenUseCase {
+    users: Arc<dyn UserRepository>,
+    repository: Arc<dyn ApiTokenRepository>,
+}
+
+impl CreateApiTokenUseCase {
+    /// Create a new use case instance issuing tokens to the users of `users`
+    pub fn new(users: Arc<dyn UserRepository>, repository: Arc<dyn ApiTokenRepository>) -> Self {
+        Self { users, repository }
+    }
+
+    /// Issue a token with a generated ID to the user of `tenant` with `user_id`
+    ///
+    /// # Errors
+    /// `Validation` for an empty user ID, a name or expiry the token rejects,
+    /// `NotFound` if the user doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        user_id: &str,
+        command: CreateApiTokenCommand,
+    ) -> Result<IssuedApiToken, DomainError> {
+        let user_id = UserId::new(user_id)?;
+        let plaintext = generate_token();
+        let token = ApiToken::new(
+            ApiTokenId::generate(),
+            user_id,
+            &command.name,
+            hash_token(&plaintext),
+            Utc::now(),
+            command.expires_at,
+        )?;
+        if self.users.find_by_id(tenant, token.user_id()).await?.is_none() {
+            return Err(DomainError::not_found(UserId::entity_name()));
+        }
+        self.repository.insert(tenant, &token).await?;
+        Ok(IssuedApiToken { token, plaintext })
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::api_token::infrastructure::InMemoryApiTokenRepository;
+    use crate::features::user::domain::User;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::Entity;
+
+    #[tokio::test]
+    async fn execute_should_store_only_the_hash_of_the_issued_token() {
+        let tenant = TenantId::default();
+        let users = Arc::new(InMemoryUserRepository::default());
+        let alice = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+            .expect("valid user");
+        users.insert(&tenant, &alice).await.expect("insert user");
+        let tokens = Arc::new(InMemoryApiTokenRepository::default());
+        let use_case = CreateApiTokenUseCase::new(users, Arc::clone(&tokens) as _);
+        let command = || CreateApiTokenCommand { name: "ci".into(), expires_at: None };
+
+        let issued = use_case.execute(&tenant, alice.id().value(), command()).await;
+        let issued = issued.expect("issued");
+        let stored = tokens.find_by_user(&tenant, alice.id()).await.expect("tokens");
+        assert_eq!(stored, std::slice::from_ref(&issued.token));
+        assert_eq!(stored[0].token_hash(), hash_token(&issued.plaintext));
+        assert_ne!(stored[0].token_hash(), issued.plaintext);
+
+        let result = use_case.execute(&tenant, "nobody", command()).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))), "{result:?}");
+    }
+}
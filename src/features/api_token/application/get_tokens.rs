@@ -0,0 +1,38 @@
+//! List API tokens use case
+
+use crate::features::api_token::domain::{ApiToken, ApiTokenRepository};
+use crate::features::user::domain::UserRepository;
+use crate::shared::domain::{DomainError, TenantId, UserId};
+use std::sync::Arc;
+
+/// Use case for listing the API tokens of a user
+pub struct ListApiTokensUseCase {
+    users: Arc<dyn UserRepository>,
+    repository: Arc<dyn ApiTokenRepository>,
+}
+
+impl ListApiTokensUseCase {
+    /// Create a new use case instance
+    pub fn new(users: Arc<dyn UserRepository>, repository: Arc<dyn ApiTokenRepository>) -> Self {
+        Self { users, repository }
+    }
+
+    /// List every token of the user of `tenant` with `user_id`, oldest first, revoked
+    /// and expired ones included; users have few of them
+    ///
+    /// # Errors
+    /// `Validation` for an empty user ID, `NotFound` if the user doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        user_id: &str,
+    ) -> Result<Vec<ApiToken>, DomainError> {
+        let user_id = UserId::new(user_id)?;
+        let tokens = self.repository.find_by_user(tenant, &user_id).await?;
+        // Only a user without tokens may not exist at all
+        if tokens.is_empty() && self.users.find_by_id(tenant, &user_id).await?.is_none() {
+            return Err(DomainError::not_found(UserId::entity_name()));
+        }
+        Ok(tokens)
+    }
+}
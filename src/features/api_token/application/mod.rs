@@ -0,0 +1,11 @@
+//! API token application layer
+
+pub mod authenticate;
+pub mod create_token;
+pub mod get_tokens;
+pub mod revoke_token;
+
+pub use authenticate::AuthenticateApiTokenUseCase;
+pub use create_token::{CreateApiTokenCommand, CreateApiTokenUseCase, IssuedApiToken};
+pub use get_tokens::ListApiTokensUseCase;
+pub use revoke_token::RevokeApiTokenUseCase;
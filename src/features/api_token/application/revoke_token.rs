@@ -0,0 +1,37 @@
+//! Revoke API token use case
+
+use crate::features::api_token::domain::{ApiTokenId, ApiTokenRepository};
+use crate::shared::domain::{DomainError, TenantId, UserId};
+use chrono::Utc;
+use std::sync::Arc;
+
+/// Use case for revoking an API token
+pub struct RevokeApiTokenUseCase {
+    repository: Arc<dyn ApiTokenRepository>,
+}
+
+impl RevokeApiTokenUseCase {
+    /// Create a new use case instance
+    pub fn new(repository: Arc<dyn ApiTokenRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Revoke the token `id` of the user of `tenant` with `user_id`, so that it no
+    /// longer authenticates requests; revoking it again changes nothing
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the user has no such token.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        user_id: &str,
+        id: &str,
+    ) -> Result<(), DomainError> {
+        let (user_id, id) = (UserId::new(user_id)?, ApiTokenId::new(id)?);
+        if self.repository.revoke(tenant, &user_id, &id, Utc::now()).await? {
+            Ok(())
+        } else {
+            Err(DomainError::not_found(ApiTokenId::entity_name()))
+        }
+    }
+}
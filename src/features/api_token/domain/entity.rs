@@ -0,0 +1,221 @@
+//! API token domain
+//!
+//! A token is shown once, when it is issued; only its hash is stored, and requests
+//! are authenticated by looking the hash of the token they present up.
+
+use super::value_objects::ApiTokenId;
+use crate::shared::domain::{DomainError, Entity, UserId, CANNOT_BE_EMPTY};
+use chrono::{DateTime, TimeDelta, Utc};
+
+pub use crate::features::user::domain::email_change::hash_token;
+
+/// Prefix of every token, telling API tokens apart from other secrets
+pub const TOKEN_PREFIX: &str = "uat_";
+
+/// Longest name a token may have, in characters
+pub const MAX_NAME_LEN: usize = 100;
+
+/// How stale the recorded last use of a token may be: a token used again within it
+/// is not written again
+pub const LAST_USED_RESOLUTION: TimeDelta = TimeDelta::minutes(1);
+
+/// Issue a new token: [`TOKEN_PREFIX`] and 64 hex characters from two random UUIDs
+#[must_use]
+pub fn generate_token() -> String {
+    let (a, b) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    format!("{TOKEN_PREFIX}{}{}", a.simple(), b.simple())
+}
+
+/// API token aggregate root: programmatic access as a user, until it expires or is
+/// revoked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiToken {
+    id: ApiTokenId,
+    user_id: UserId,
+    name: String,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    /// Issue a token of `user_id` named `name`, created at `now` and valid until
+    /// `expires_at`, forever when `None`; `token_hash` is the [`hash_token`] of the
+    /// token handed to the user
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the name is empty or longer than
+    /// [`MAX_NAME_LEN`], or `expires_at` is not after `now`.
+    pub fn new(
+        id: ApiTokenId,
+        user_id: UserId,
+        name: &str,
+        token_hash: String,
+        now: DateTime<Utc>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Self, DomainError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(DomainError::validation("Name", CANNOT_BE_EMPTY));
+        }
+        if name.chars().count() > MAX_NAME_LEN {
+            let reason = format!("must have at most {MAX_NAME_LEN} characters");
+            return Err(DomainError::validation("Name", reason));
+        }
+        if expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(DomainError::validation("Expiry", "must be in the future"));
+        }
+        Ok(Self {
+            id,
+            user_id,
+            name: name.to_owned(),
+            token_hash,
+            created_at: now,
+            expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        })
+    }
+
+    /// User the token grants access as
+    #[must_use]
+    pub fn user_id(&self) -> &UserId {
+        &self.user_id
+    }
+
+    /// Name given by the user, e.g. the service the token is for
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Hash of the token (see [`hash_token`])
+    #[must_use]
+    pub fn token_hash(&self) -> &str {
+        &self.token_hash
+    }
+
+    /// When the token was issued
+    #[must_use]
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// End of the validity of the token, if it has one
+    #[must_use]
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at
+    }
+
+    /// When the token was last used, to within [`LAST_USED_RESOLUTION`]
+    #[must_use]
+    pub fn last_used_at(&self) -> Option<DateTime<Utc>> {
+        self.last_used_at
+    }
+
+    /// When the token was revoked, once it was
+    #[must_use]
+    pub fn revoked_at(&self) -> Option<DateTime<Utc>> {
+        self.revoked_at
+    }
+
+    /// Whether the token authenticates requests at `now`: neither revoked nor expired
+    #[must_use]
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+
+    /// Whether a use at `now` should be recorded, as the recorded one is older than
+    /// [`LAST_USED_RESOLUTION`]
+    #[must_use]
+    pub fn needs_touch(&self, now: DateTime<Utc>) -> bool {
+        self.last_used_at.is_none_or(|last_used_at| now - last_used_at >= LAST_USED_RESOLUTION)
+    }
+
+    /// Record a use of the token at `now`
+    pub fn touch(&mut self, now: DateTime<Utc>) {
+        self.last_used_at = Some(now);
+    }
+
+    /// Reconstitute a token from persistence (bypasses business rules)
+    #[must_use]
+    #[expect(clippy::too_many_arguments, reason = "one argument per stored column")]
+    pub fn reconstitute(
+        id: ApiTokenId,
+        user_id: UserId,
+        name: String,
+        token_hash: String,
+        created_at: DateTime<Utc>,
+        expires_at: Option<DateTime<Utc>>,
+        last_used_at: Option<DateTime<Utc>>,
+        revoked_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self { id, user_id, name, token_hash, created_at, expires_at, last_used_at, revoked_at }
+    }
+}
+
+impl Entity for ApiToken {
+    type Id = ApiTokenId;
+
+    fn id(&self) -> &Self::Id {
+        &self.id
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    fn token(expires_at: Option<DateTime<Utc>>) -> ApiToken {
+        let (hash, now) = (hash_token(&generate_token()), Utc::now());
+        ApiToken::new(ApiTokenId::generate(), UserId::generate(), " ci ", hash, now, expires_at)
+            .expect("valid token")
+    }
+
+    #[test]
+    fn generate_token_should_be_prefixed_random_hex() {
+        let (a, b) = (generate_token(), generate_token());
+        let hex = a.strip_prefix(TOKEN_PREFIX).unwrap_or_default();
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()), "{a}");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn new_should_trim_the_name_and_reject_invalid_names_and_past_expiries() {
+        assert_eq!(token(None).name(), "ci");
+        let now = Utc::now();
+        let new = |name: &str, expires_at| {
+            let id = ApiTokenId::generate();
+            ApiToken::new(id, UserId::generate(), name, hash_token("t"), now, expires_at)
+        };
+        for result in [new(" ", None), new(&"x".repeat(101), None), new("ci", Some(now))] {
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{result:?}");
+        }
+    }
+
+    #[test]
+    fn tokens_should_be_unusable_once_expired_or_revoked() {
+        let now = Utc::now();
+        let expiring = token(Some(now + TimeDelta::hours(1)));
+        assert!(expiring.is_usable(now));
+        assert!(!expiring.is_usable(now + TimeDelta::hours(1)));
+        assert!(token(None).is_usable(now + TimeDelta::days(3650)));
+
+        let revoked = ApiToken { revoked_at: Some(now), ..token(None) };
+        assert!(!revoked.is_usable(now));
+    }
+
+    #[test]
+    fn uses_should_be_recorded_at_most_once_per_resolution() {
+        let now = Utc::now();
+        let mut token = token(None);
+        assert!(token.needs_touch(now));
+        token.touch(now);
+        assert!(!token.needs_touch(now + TimeDelta::seconds(59)));
+        assert!(token.needs_touch(now + LAST_USED_RESOLUTION));
+    }
+}
@@ -0,0 +1,9 @@
+//! API token domain layer
+
+pub mod entity;
+pub mod repository;
+pub mod value_objects;
+
+pub use entity::{generate_token, hash_token, ApiToken, LAST_USED_RESOLUTION, TOKEN_PREFIX};
+pub use repository::ApiTokenRepository;
+pub use value_objects::ApiTokenId;
@@ -0,0 +1,44 @@
+//! API token repository port
+
+use super::entity::ApiToken;
+use super::value_objects::ApiTokenId;
+use crate::shared::domain::{DomainError, TenantId, UserId};
+use chrono::{DateTime, Utc};
+
+/// Repository for API token aggregate
+///
+/// Every method is scoped to `tenant`: tokens of other tenants are neither found
+/// nor changed.
+#[async_trait::async_trait]
+pub trait ApiTokenRepository: Send + Sync {
+    /// Find every token of the user, revoked and expired ones included, oldest first
+    async fn find_by_user(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<ApiToken>, DomainError>;
+    /// Find the token whose plaintext hashes to `token_hash`, usable or not
+    async fn find_by_hash(
+        &self,
+        tenant: &TenantId,
+        token_hash: &str,
+    ) -> Result<Option<ApiToken>, DomainError>;
+    /// Insert a new token (fails if the ID or hash exists)
+    async fn insert(&self, tenant: &TenantId, token: &ApiToken) -> Result<(), DomainError>;
+    /// Revoke the token `id` of the user at `at`, keeping the time of an earlier
+    /// revocation; returns false if the user has no such token
+    async fn revoke(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        id: &ApiTokenId,
+        at: DateTime<Utc>,
+    ) -> Result<bool, DomainError>;
+    /// Record `at` as the last use of `token`
+    async fn touch(
+        &self,
+        tenant: &TenantId,
+        token: &ApiToken,
+        at: DateTime<Utc>,
+    ) -> Result<(), DomainError>;
+}
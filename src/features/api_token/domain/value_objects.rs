@@ -0,0 +1,3 @@
+//! API token value objects
+
+crate::string_id!(ApiTokenId, "API token");
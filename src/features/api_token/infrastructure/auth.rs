@@ -0,0 +1,211 @@
+//! Authentication of requests by the API token of `Authorization: Bearer <token>`
+
+use crate::features::api_token::application::AuthenticateApiTokenUseCase;
+use crate::features::api_token::domain::ApiTokenId;
+use crate::shared::domain::{Entity, UserId};
+use crate::shared::infrastructure::http::ApiError;
+use crate::shared::infrastructure::instrumentation::budgeted;
+use crate::shared::infrastructure::tenant::TenantContext;
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// User a request authenticated as, in the extensions of requests presenting a
+/// usable token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authenticated {
+    /// User the token was issued to
+    pub user_id: UserId,
+    /// Token the request presented
+    pub token_id: ApiTokenId,
+}
+
+/// Handlers taking the caller answer requests that [`authenticate`] let through
+/// without a token with `401`, as it does when tokens are required
+impl<S: Send + Sync> FromRequestParts<S> for Authenticated {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Self>()
+            .cloned()
+            .ok_or_else(|| unauthorized("An API token is required: Authorization: Bearer <token>"))
+    }
+}
+
+/// How [`authenticate`] treats requests
+#[derive(Clone)]
+pub struct TokenAuth {
+    /// Looks presented tokens up
+    pub authenticate: Arc<AuthenticateApiTokenUseCase>,
+    /// Reject requests presenting no token instead of letting them through
+    pub required: bool,
+}
+
+/// Middleware authenticating requests that present an API token
+///
+/// A usable token of the request's tenant stores [`Authenticated`] in the request
+/// extensions. A request without an `Authorization` header passes through
+/// unauthenticated, unless a token is required.
+///
+/// # Errors
+/// `401 UNAUTHORIZED`, with `WWW-Authenticate: Bearer`, for a missing token when
+/// one is required, another scheme than `Bearer`, or a token that is unknown,
+/// expired or revoked.
+pub async fn authenticate(State(auth): State<TokenAuth>, request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let Some(authorization) = parts.headers.get(header::AUTHORIZATION) else {
+        if auth.required {
+            return unauthorized("An API token is required: Authorization: Bearer <token>");
+        }
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+    let Some(token) = bearer_token(authorization) else {
+        return unauthorized("Authorization must be a Bearer token");
+    };
+    let tenant = match TenantContext::from_request_parts(&mut parts, &()).await {
+        Ok(TenantContext(tenant)) => tenant,
+        Err(e) => return e.into_response(),
+    };
+    match budgeted("authenticate_api_token", auth.authenticate.execute(&tenant, &token)).await {
+        Ok(Some(token)) => {
+            let (user_id, token_id) = (token.user_id().clone(), token.id().clone());
+            parts.extensions.insert(Authenticated { user_id, token_id });
+            next.run(Request::from_parts(parts, body)).await
+        }
+        Ok(None) => unauthorized("API token is invalid, expired or revoked"),
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// The token of an `Authorization: Bearer <token>` value, whatever the case of the
+/// scheme
+fn bearer_token(authorization: &HeaderValue) -> Option<String> {
+    let (scheme, token) = authorization.to_str().ok()?.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then(|| token.to_owned())
+}
+
+fn unauthorized(message: &str) -> Response {
+    let mut response =
+        ApiError::new(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", message).into_response();
+    response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::api_token::domain::{
+        generate_token, hash_token, ApiToken, ApiTokenRepository,
+    };
+    use crate::features::api_token::infrastructure::InMemoryApiTokenRepository;
+    use crate::shared::domain::TenantId;
+    use crate::shared::infrastructure::config::Config;
+    use crate::test_support::{
+        in_memory_app_with, in_memory_app_with_repositories, issue_token, send, send_request,
+        send_with_token,
+    };
+    use axum::body::Body;
+    use axum::http::Method;
+    use axum::{middleware, routing::get, Extension, Json, Router};
+    use chrono::Utc;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    /// Router echoing the user each request authenticated as, behind the middleware
+    async fn echo(required: bool) -> (Router, ApiToken, String) {
+        let repository = Arc::new(InMemoryApiTokenRepository::default());
+        let plaintext = generate_token();
+        let (id, user_id) = (ApiTokenId::generate(), UserId::generate());
+        let token = ApiToken::new(id, user_id, "ci", hash_token(&plaintext), Utc::now(), None)
+            .expect("valid token");
+        repository.insert(&TenantId::default(), &token).await.expect("insert");
+        let auth = TokenAuth {
+            authenticate: Arc::new(AuthenticateApiTokenUseCase::new(repository)),
+            required,
+        };
+        let handler = |user: Option<Extension<Authenticated>>| async move {
+            Json(json!(user.map(|Extension(user)| user.user_id.value().to_owned())))
+        };
+        let router = Router::new()
+            .route("/", get(handler))
+            .layer(middleware::from_fn_with_state(auth, authenticate));
+        (router, token, plaintext)
+    }
+
+    fn request(authorization: Option<&str>) -> axum::http::Request<Body> {
+        let mut request = axum::http::Request::get("/");
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        request.body(Body::empty()).expect("valid request")
+    }
+
+    #[tokio::test]
+    async fn usable_tokens_should_authenticate_as_their_user() {
+        let (router, token, plaintext) = echo(false).await;
+        for authorization in [format!("Bearer {plaintext}"), format!("bearer  {plaintext}")] {
+            let (status, user) = send_request(&router, request(Some(&authorization))).await;
+            assert_eq!((status, user), (StatusCode::OK, json!(token.user_id().value())));
+        }
+        let (status, user) = send_request(&router, request(None)).await;
+        assert_eq!((status, user), (StatusCode::OK, Value::Null), "anonymous");
+    }
+
+    #[tokio::test]
+    async fn other_schemes_unknown_tokens_and_missing_required_ones_should_be_401() {
+        let (router, _, plaintext) = echo(true).await;
+        let unknown = format!("Bearer {}", generate_token());
+        let basic = format!("Basic {plaintext}");
+        for authorization in [None, Some("Bearer"), Some(basic.as_str()), Some(unknown.as_str())] {
+            let response = router.clone().oneshot(request(authorization)).await.expect("response");
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{authorization:?}");
+            let challenge = response.headers().get(header::WWW_AUTHENTICATE);
+            assert_eq!(challenge.and_then(|v| v.to_str().ok()), Some("Bearer"));
+        }
+        let (status, error) = send_request(&router, request(Some(&unknown))).await;
+        assert_eq!((status, &error["code"]), (StatusCode::UNAUTHORIZED, &json!("UNAUTHORIZED")));
+    }
+
+    #[tokio::test]
+    async fn required_tokens_should_leave_health_checks_open() {
+        let mut config = Config::default();
+        config.api_token_required = true;
+        let app = in_memory_app_with(&config);
+        let (status, error) = send(&app, Method::GET, "/users", None).await;
+        assert_eq!((status, &error["code"]), (StatusCode::UNAUTHORIZED, &json!("UNAUTHORIZED")));
+        assert_eq!(send(&app, Method::GET, "/health", None).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn revoked_tokens_should_be_rejected_at_once_despite_the_cache() {
+        let (app, repositories) = in_memory_app_with_repositories(&Config::default());
+        let body = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(body)).await;
+        let user_id = user["id"].as_str().unwrap_or_default();
+        let token = issue_token(&repositories, user_id).await;
+        let uri = format!("/users/{user_id}/tokens");
+        let (_, issued) =
+            send_with_token(&app, &token, Method::POST, &uri, Some(json!({"name": "ci"}))).await;
+        let issued_token = issued["token"].as_str().unwrap_or_default();
+        assert_eq!(
+            send_with_token(&app, issued_token, Method::GET, "/users", None).await.0,
+            StatusCode::OK
+        );
+        let (_, listed) = send_with_token(&app, &token, Method::GET, &uri, None).await;
+        assert!(listed[1]["lastUsedAt"].is_string(), "{listed}");
+
+        let token_uri = format!("{uri}/{}", issued["id"].as_str().unwrap_or_default());
+        let (status, _) = send_with_token(&app, &token, Method::DELETE, &token_uri, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send_with_token(&app, issued_token, Method::GET, "/users", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
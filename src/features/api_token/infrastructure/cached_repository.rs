@@ -0,0 +1,295 @@
+//! API token repository decorator caching lookups by hash
+
+use crate::features::api_token::domain::{ApiToken, ApiTokenId, ApiTokenRepository};
+use crate::features::user::domain::UserDependents;
+use crate::shared::domain::{DomainError, TenantId, UserId};
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use moka::ops::compute::Op;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Serves `find_by_hash`, the lookup of every authenticated request, from a cache
+/// of the tokens recently found
+///
+/// Revocations go through to the wrapped repository, then drop every cached token,
+/// and a lookup that overlaps one is not cached, so this process never accepts a
+/// token it revoked. Recorded uses update the cached token, so that it is not
+/// recorded again for as long as it stays cached. As [`UserDependents`], it drops
+/// the tokens of users this process deletes the same way.
+///
+/// Only this process's revocations and deletions invalidate: with several
+/// instances, a token revoked elsewhere, or whose user was deleted elsewhere, is
+/// accepted for up to the TTL.
+pub struct CachedApiTokenRepository {
+    inner: Arc<dyn ApiTokenRepository>,
+    cache: Cache<(TenantId, String), ApiToken>,
+    /// Incremented by every revocation, so lookups can tell they overlapped one
+    revocations: AtomicU64,
+}
+
+impl CachedApiTokenRepository {
+    /// Wrap `inner`, caching up to `capacity` tokens for `ttl` each
+    #[must_use]
+    pub fn new(inner: Arc<dyn ApiTokenRepository>, ttl: Duration, capacity: u64) -> Self {
+        let cache = Cache::builder()
+            .time_to_live(ttl)
+            .max_capacity(capacity)
+            .support_invalidation_closures()
+            .build();
+        Self { inner, cache, revocations: AtomicU64::new(0) }
+    }
+
+    /// Number of revocations so far, compared before and after a call to `inner`
+    fn epoch(&self) -> u64 {
+        self.revocations.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiTokenRepository for CachedApiTokenRepository {
+    async fn find_by_user(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<ApiToken>, DomainError> {
+        self.inner.find_by_user(tenant, user_id).await
+    }
+
+    async fn find_by_hash(
+        &self,
+        tenant: &TenantId,
+        token_hash: &str,
+    ) -> Result<Option<ApiToken>, DomainError> {
+        let key = (tenant.clone(), token_hash.to_owned());
+        if let Some(token) = self.cache.get(&key).await {
+            return Ok(Some(token));
+        }
+        let epoch = self.epoch();
+        let token = self.inner.find_by_hash(tenant, token_hash).await?;
+        if let Some(token) = &token {
+            self.cache.insert(key.clone(), token.clone()).await;
+            // A revocation between the lookup and the insert found nothing to drop
+            if self.epoch() != epoch {
+                self.cache.invalidate(&key).await;
+            }
+        }
+        Ok(token)
+    }
+
+    async fn insert(&self, tenant: &TenantId, token: &ApiToken) -> Result<(), DomainError> {
+        self.inner.insert(tenant, token).await
+    }
+
+    async fn revoke(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        id: &ApiTokenId,
+        at: DateTime<Utc>,
+    ) -> Result<bool, DomainError> {
+        // Whatever the outcome, as the token may be revoked even if the call failed
+        let revoked = self.inner.revoke(tenant, user_id, id, at).await;
+        // Only the hash keys the cache; revocations are rare enough to drop every token
+        self.revocations.fetch_add(1, Ordering::SeqCst);
+        self.cache.invalidate_all();
+        revoked
+    }
+
+    async fn touch(
+        &self,
+        tenant: &TenantId,
+        token: &ApiToken,
+        at: DateTime<Utc>,
+    ) -> Result<(), DomainError> {
+        let epoch = self.epoch();
+        self.inner.touch(tenant, token, at).await?;
+        let key = (tenant.clone(), token.token_hash().to_owned());
+        self.cache
+            .entry(key.clone())
+            .and_compute_with(|cached| async move {
+                match cached {
+                    Some(cached) => {
+                        let mut token = cached.into_value();
+                        token.touch(at);
+                        Op::Put(token)
+                    }
+                    None => Op::Nop,
+                }
+            })
+            .await;
+        // The token put back may be one a revocation meanwhile dropped
+        if self.epoch() != epoch {
+            self.cache.invalidate(&key).await;
+        }
+        Ok(())
+    }
+}
+
+/// The tokens of a user, which deleting the user cascades to
+#[async_trait::async_trait]
+impl UserDependents for CachedApiTokenRepository {
+    async fn count_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<u64, DomainError> {
+        Ok(self.inner.find_by_user(tenant, user_id).await?.len() as u64)
+    }
+
+    async fn count_by_user_ids(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+    ) -> Result<HashMap<UserId, u64>, DomainError> {
+        let mut counts = HashMap::new();
+        for user_id in user_ids {
+            let count = self.count_by_user_id(tenant, user_id).await?;
+            if count > 0 {
+                counts.insert(user_id.clone(), count);
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn user_deleted(&self, tenant: &TenantId, user_id: &UserId) -> Result<(), DomainError> {
+        self.revocations.fetch_add(1, Ordering::SeqCst);
+        let (owner_tenant, owner) = (tenant.clone(), user_id.clone());
+        self.cache
+            .invalidate_entries_if(move |(t, _), token| {
+                *t == owner_tenant && *token.user_id() == owner
+            })
+            .map(|_| ())
+            .map_err(|e| DomainError::Infrastructure(format!("API token cache: {e}")))
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::api_token::domain::{generate_token, hash_token};
+    use crate::features::api_token::infrastructure::InMemoryApiTokenRepository;
+    use crate::shared::domain::Entity;
+
+    const TTL: Duration = Duration::from_mins(1);
+
+    /// Repository counting the lookups by hash reaching it
+    #[derive(Default)]
+    struct CountingLookups {
+        inner: InMemoryApiTokenRepository,
+        lookups: AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl ApiTokenRepository for CountingLookups {
+        async fn find_by_user(
+            &self,
+            tenant: &TenantId,
+            user_id: &UserId,
+        ) -> Result<Vec<ApiToken>, DomainError> {
+            self.inner.find_by_user(tenant, user_id).await
+        }
+
+        async fn find_by_hash(
+            &self,
+            tenant: &TenantId,
+            token_hash: &str,
+        ) -> Result<Option<ApiToken>, DomainError> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+            self.inner.find_by_hash(tenant, token_hash).await
+        }
+
+        async fn insert(&self, tenant: &TenantId, token: &ApiToken) -> Result<(), DomainError> {
+            self.inner.insert(tenant, token).await
+        }
+
+        async fn revoke(
+            &self,
+            tenant: &TenantId,
+            user_id: &UserId,
+            id: &ApiTokenId,
+            at: DateTime<Utc>,
+        ) -> Result<bool, DomainError> {
+            self.inner.revoke(tenant, user_id, id, at).await
+        }
+
+        async fn touch(
+            &self,
+            tenant: &TenantId,
+            token: &ApiToken,
+            at: DateTime<Utc>,
+        ) -> Result<(), DomainError> {
+            self.inner.touch(tenant, token, at).await
+        }
+    }
+
+    struct Fixture {
+        inner: Arc<CountingLookups>,
+        cached: CachedApiTokenRepository,
+        tenant: TenantId,
+        token: ApiToken,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let inner = Arc::new(CountingLookups::default());
+            let cached = CachedApiTokenRepository::new(Arc::clone(&inner) as _, TTL, 100);
+            let (hash, now) = (hash_token(&generate_token()), Utc::now());
+            let token =
+                ApiToken::new(ApiTokenId::generate(), UserId::generate(), "ci", hash, now, None)
+                    .expect("valid token");
+            let tenant = TenantId::default();
+            cached.insert(&tenant, &token).await.expect("insert");
+            Self { inner, cached, tenant, token }
+        }
+
+        async fn find(&self) -> Option<ApiToken> {
+            let found = self.cached.find_by_hash(&self.tenant, self.token.token_hash()).await;
+            found.expect("looked up")
+        }
+
+        fn lookups(&self) -> u64 {
+            self.inner.lookups.load(Ordering::Relaxed)
+        }
+    }
+
+    #[tokio::test]
+    async fn find_by_hash_should_be_served_from_the_cache_with_recorded_uses() {
+        let fixture = Fixture::new().await;
+        assert_eq!(fixture.find().await.as_ref(), Some(&fixture.token));
+        let used = Utc::now();
+        fixture.cached.touch(&fixture.tenant, &fixture.token, used).await.expect("touch");
+
+        let found = fixture.find().await.expect("found");
+        assert_eq!((found.last_used_at(), fixture.lookups()), (Some(used), 1));
+    }
+
+    #[tokio::test]
+    async fn revoke_should_drop_cached_tokens() {
+        let fixture = Fixture::new().await;
+        assert!(fixture.find().await.is_some_and(|token| token.revoked_at().is_none()));
+
+        let (tenant, token) = (&fixture.tenant, &fixture.token);
+        let revoked = fixture.cached.revoke(tenant, token.user_id(), token.id(), Utc::now()).await;
+        assert!(revoked.expect("revoked"));
+        assert!(fixture.find().await.is_some_and(|token| token.revoked_at().is_some()));
+        assert_eq!(fixture.lookups(), 2);
+    }
+
+    #[tokio::test]
+    async fn user_deleted_should_drop_the_cached_tokens_of_the_user() {
+        let fixture = Fixture::new().await;
+        assert!(fixture.find().await.is_some());
+
+        let (tenant, token) = (&fixture.tenant, &fixture.token);
+        fixture.cached.user_deleted(tenant, &UserId::generate()).await.expect("other user");
+        assert!(fixture.find().await.is_some());
+        assert_eq!(fixture.lookups(), 1);
+        fixture.cached.user_deleted(tenant, token.user_id()).await.expect("user deleted");
+        assert!(fixture.find().await.is_some());
+        assert_eq!(fixture.lookups(), 2);
+    }
+}
@@ -0,0 +1,224 @@
+//! API token HTTP handlers
+
+pub use crate::api_types::{ApiTokenResponse, CreateApiTokenRequest};
+use crate::features::api_token::application::CreateApiTokenCommand;
+use crate::features::api_token::domain::ApiToken;
+use crate::features::api_token::infrastructure::Authenticated;
+use crate::features::api_token::{ApiTokenState, NAME};
+use crate::shared::domain::entity::Entity;
+use crate::shared::infrastructure::feature::{ExpectedRoute, FeatureRouter};
+use crate::shared::infrastructure::http::{ApiError, ApiPath, Negotiated};
+use crate::shared::infrastructure::instrumentation::budgeted;
+use crate::shared::infrastructure::tenant::TenantContext;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Response,
+    routing::{delete, get},
+    Router,
+};
+use std::sync::Arc;
+
+type ApiResult<T> = Result<T, ApiError>;
+
+impl From<ApiToken> for ApiTokenResponse {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            id: token.id().value().to_owned(),
+            name: token.name().to_owned(),
+            created_at: token.created_at(),
+            expires_at: token.expires_at(),
+            last_used_at: token.last_used_at(),
+            revoked_at: token.revoked_at(),
+            token: None,
+        }
+    }
+}
+
+/// Routes served by [`routes`]
+pub const ROUTES: &[ExpectedRoute] = &[
+    ExpectedRoute::get("/"),
+    ExpectedRoute::post("/"),
+    ExpectedRoute::delete("/{token_id}"),
+];
+
+/// API token routes, nested under `/users/{id}/tokens`
+///
+/// Only the user the path names may manage their tokens: requests must be
+/// authenticated as them, by a token of theirs.
+pub fn routes(state: Arc<ApiTokenState>) -> FeatureRouter<()> {
+    let router = Router::new()
+        .route("/", get(list_tokens).post(create_token))
+        .route("/{token_id}", delete(revoke_token));
+    FeatureRouter {
+        name: NAME,
+        prefix: "/users/{id}/tokens",
+        router: router.with_state(state),
+        manifest: ROUTES,
+    }
+}
+
+/// Reject callers authenticated as another user than `user_id`, the owner of the
+/// tokens of the path
+fn authorize(caller: &Authenticated, user_id: &str) -> ApiResult<()> {
+    if caller.user_id.value() == user_id {
+        return Ok(());
+    }
+    Err(ApiError::new(StatusCode::FORBIDDEN, "FORBIDDEN", "Only the user may manage their tokens"))
+}
+
+/// Issue an API token to a user, returning the token this once
+async fn create_token(
+    State(state): State<Arc<ApiTokenState>>,
+    TenantContext(tenant): TenantContext,
+    caller: Authenticated,
+    ApiPath(user_id): ApiPath<String>,
+    Negotiated(body): Negotiated<CreateApiTokenRequest>,
+) -> ApiResult<Response> {
+    authorize(&caller, &user_id)?;
+    let command = CreateApiTokenCommand { name: body.name, expires_at: body.expires_at };
+    let create = state.create_token.execute(&tenant, &user_id, command);
+    let issued = budgeted("create_api_token", create).await.map_err(ApiError::from)?;
+    let response = ApiTokenResponse { token: Some(issued.plaintext), ..issued.token.into() };
+    Ok(Negotiated(response).with_status(StatusCode::CREATED))
+}
+
+/// List the API tokens of a user, oldest first, without the tokens themselves
+async fn list_tokens(
+    State(state): State<Arc<ApiTokenState>>,
+    TenantContext(tenant): TenantContext,
+    caller: Authenticated,
+    ApiPath(user_id): ApiPath<String>,
+) -> ApiResult<Negotiated<Vec<ApiTokenResponse>>> {
+    authorize(&caller, &user_id)?;
+    let tokens = budgeted("list_api_tokens", state.list_tokens.execute(&tenant, &user_id))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Negotiated(tokens.into_iter().map(Into::into).collect()))
+}
+
+/// Revoke an API token of a user, which then no longer authenticates requests
+async fn revoke_token(
+    State(state): State<Arc<ApiTokenState>>,
+    TenantContext(tenant): TenantContext,
+    caller: Authenticated,
+    ApiPath((user_id, token_id)): ApiPath<(String, String)>,
+) -> ApiResult<StatusCode> {
+    authorize(&caller, &user_id)?;
+    budgeted("revoke_api_token", state.revoke_token.execute(&tenant, &user_id, &token_id))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::InMemoryRepositories;
+    use crate::shared::infrastructure::config::Config;
+    use crate::test_support::{in_memory_app_with_repositories, issue_token, send, send_with_token};
+    use axum::http::{Method, StatusCode};
+    use axum::Router;
+    use serde_json::{json, Value};
+
+    /// Create a user through the API and issue them a token, returning both
+    async fn create_user(
+        app: &Router,
+        repositories: &InMemoryRepositories,
+        name: &str,
+    ) -> (String, String) {
+        let email = format!("{}@example.com", name.to_lowercase());
+        let body = json!({"name": name, "email": email});
+        let (status, user) = send(app, Method::POST, "/users", Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = user["id"].as_str().unwrap_or_default().to_owned();
+        let token = issue_token(repositories, &id).await;
+        (id, token)
+    }
+
+    #[tokio::test]
+    async fn tokens_should_be_issued_once_listed_without_secrets_and_revoked() {
+        let (app, repositories) = in_memory_app_with_repositories(&Config::default());
+        let (id, token) = create_user(&app, &repositories, "Alice").await;
+        let uri = format!("/users/{id}/tokens");
+        let body = json!({"name": "ci", "expiresAt": "2999-01-01T09:00:00+09:00"});
+        let (status, issued) = send_with_token(&app, &token, Method::POST, &uri, Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(issued["token"].as_str().is_some_and(|t| t.starts_with("uat_")), "{issued}");
+        assert_eq!(issued["expiresAt"], "2999-01-01T00:00:00.000Z");
+        assert_eq!((&issued["lastUsedAt"], &issued["revokedAt"]), (&Value::Null, &Value::Null));
+
+        let (status, listed) = send_with_token(&app, &token, Method::GET, &uri, None).await;
+        assert_eq!((status, listed.as_array().map(Vec::len)), (StatusCode::OK, Some(2)));
+        assert!(listed[1].get("token").is_none(), "{listed}");
+        assert_eq!(listed[1]["id"], issued["id"]);
+
+        let token_uri = format!("{uri}/{}", issued["id"].as_str().unwrap_or_default());
+        for _ in 0..2 {
+            let (status, _) = send_with_token(&app, &token, Method::DELETE, &token_uri, None).await;
+            assert_eq!(status, StatusCode::NO_CONTENT);
+        }
+        let (_, listed) = send_with_token(&app, &token, Method::GET, &uri, None).await;
+        assert!(listed[1]["revokedAt"].is_string(), "{listed}");
+        let unknown = format!("{uri}/unknown");
+        let (status, _) = send_with_token(&app, &token, Method::DELETE, &unknown, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn tokens_should_reject_invalid_names_or_expiries() {
+        let (app, repositories) = in_memory_app_with_repositories(&Config::default());
+        let (id, token) = create_user(&app, &repositories, "Alice").await;
+        let uri = format!("/users/{id}/tokens");
+        let expired = json!({"name": "ci", "expiresAt": "2000-01-01T00:00:00Z"});
+        for body in [json!({"name": " "}), expired] {
+            let (status, error) =
+                send_with_token(&app, &token, Method::POST, &uri, Some(body)).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(error["code"], "VALIDATION_ERROR");
+        }
+    }
+
+    #[tokio::test]
+    async fn tokens_of_other_users_should_be_forbidden() {
+        let (app, repositories) = in_memory_app_with_repositories(&Config::default());
+        let (alice, alice_token) = create_user(&app, &repositories, "Alice").await;
+        let (_, bob_token) = create_user(&app, &repositories, "Bob").await;
+        let uri = format!("/users/{alice}/tokens");
+        let (_, listed) = send_with_token(&app, &alice_token, Method::GET, &uri, None).await;
+        let token_uri = format!("{uri}/{}", listed[0]["id"].as_str().unwrap_or_default());
+
+        let requests = [
+            (Method::POST, uri.as_str(), Some(json!({"name": "ci"}))),
+            (Method::GET, uri.as_str(), None),
+            (Method::DELETE, token_uri.as_str(), None),
+            (Method::GET, "/users/nobody/tokens", None),
+        ];
+        for (method, uri, body) in requests {
+            let (status, error) = send_with_token(&app, &bob_token, method, uri, body).await;
+            assert_eq!((status, &error["code"]), (StatusCode::FORBIDDEN, &json!("FORBIDDEN")));
+        }
+        let (_, listed) = send_with_token(&app, &alice_token, Method::GET, &uri, None).await;
+        assert_eq!(listed.as_array().map(Vec::len), Some(1));
+        assert_eq!(listed[0]["revokedAt"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn anonymous_requests_should_be_unauthorized() {
+        let (app, repositories) = in_memory_app_with_repositories(&Config::default());
+        let (alice, _) = create_user(&app, &repositories, "Alice").await;
+        let uri = format!("/users/{alice}/tokens");
+        let token_uri = format!("{uri}/some-token");
+        let requests = [
+            (Method::POST, uri.as_str(), Some(json!({"name": "ci"}))),
+            (Method::GET, uri.as_str(), None),
+            (Method::DELETE, token_uri.as_str(), None),
+        ];
+        for (method, uri, body) in requests {
+            let (status, error) = send(&app, method, uri, body).await;
+            assert_eq!(
+                (status, &error["code"]),
+                (StatusCode::UNAUTHORIZED, &json!("UNAUTHORIZED"))
+            );
+        }
+    }
+}
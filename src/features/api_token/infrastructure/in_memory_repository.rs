@@ -0,0 +1,88 @@
+//! In-memory API token repository implementation for tests and examples
+
+use crate::features::api_token::domain::{ApiToken, ApiTokenId, ApiTokenRepository};
+use crate::shared::domain::{DomainError, Entity, TenantId, UserId};
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+
+/// In-memory implementation of API token repository
+#[derive(Default)]
+pub struct InMemoryApiTokenRepository {
+    /// Tokens with their tenant, oldest first
+    tokens: RwLock<Vec<(TenantId, ApiToken)>>,
+}
+
+#[async_trait::async_trait]
+impl ApiTokenRepository for InMemoryApiTokenRepository {
+    async fn find_by_user(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<ApiToken>, DomainError> {
+        let tokens = self.tokens.read().await;
+        let found = tokens.iter().filter(|(t, token)| t == tenant && token.user_id() == user_id);
+        Ok(found.map(|(_, token)| token.clone()).collect())
+    }
+
+    async fn find_by_hash(
+        &self,
+        tenant: &TenantId,
+        token_hash: &str,
+    ) -> Result<Option<ApiToken>, DomainError> {
+        let tokens = self.tokens.read().await;
+        let mut found =
+            tokens.iter().filter(|(t, token)| t == tenant && token.token_hash() == token_hash);
+        Ok(found.next().map(|(_, token)| token.clone()))
+    }
+
+    async fn insert(&self, tenant: &TenantId, token: &ApiToken) -> Result<(), DomainError> {
+        let mut tokens = self.tokens.write().await;
+        if tokens.iter().any(|(_, t)| t.id() == token.id() || t.token_hash() == token.token_hash())
+        {
+            return Err(DomainError::already_exists(ApiTokenId::entity_name(), "ID"));
+        }
+        tokens.push((tenant.clone(), token.clone()));
+        Ok(())
+    }
+
+    async fn revoke(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        id: &ApiTokenId,
+        at: DateTime<Utc>,
+    ) -> Result<bool, DomainError> {
+        let mut tokens = self.tokens.write().await;
+        let Some((_, token)) = tokens
+            .iter_mut()
+            .find(|(t, token)| t == tenant && token.user_id() == user_id && token.id() == id)
+        else {
+            return Ok(false);
+        };
+        *token = ApiToken::reconstitute(
+            token.id().clone(),
+            token.user_id().clone(),
+            token.name().to_owned(),
+            token.token_hash().to_owned(),
+            token.created_at(),
+            token.expires_at(),
+            token.last_used_at(),
+            token.revoked_at().or(Some(at)),
+        );
+        Ok(true)
+    }
+
+    async fn touch(
+        &self,
+        tenant: &TenantId,
+        token: &ApiToken,
+        at: DateTime<Utc>,
+    ) -> Result<(), DomainError> {
+        let mut tokens = self.tokens.write().await;
+        let stored = tokens.iter_mut().find(|(t, stored)| t == tenant && stored.id() == token.id());
+        if let Some((_, stored)) = stored {
+            stored.touch(at);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+//! API token infrastructure layer
+
+pub mod auth;
+pub mod cached_repository;
+pub mod http;
+pub mod in_memory_repository;
+pub mod pg_repository;
+
+pub use auth::{authenticate, Authenticated, TokenAuth};
+pub use cached_repository::CachedApiTokenRepository;
+pub use in_memory_repository::InMemoryApiTokenRepository;
+pub use pg_repository::PgApiTokenRepository;
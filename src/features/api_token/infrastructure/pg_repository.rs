@@ -0,0 +1,217 @@
+//! `PostgreSQL` API token repository implementation
+
+use crate::features::api_token::domain::{ApiToken, ApiTokenId, ApiTokenRepository};
+use crate::shared::domain::{DomainError, Entity, TenantId, UserId};
+use crate::shared::infrastructure::database::{acquire, run_query};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+/// Columns of [`TokenRow`]
+const TOKEN_COLUMNS: &str =
+    "id, user_id, name, token_hash, created_at, expires_at, last_used_at, revoked_at";
+
+/// `PostgreSQL` implementation of API token repository
+#[derive(Clone)]
+pub struct PgApiTokenRepository {
+    pool: PgPool,
+}
+
+impl PgApiTokenRepository {
+    /// Create a new `PostgreSQL` API token repository
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiTokenRepository for PgApiTokenRepository {
+    async fn find_by_user(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<ApiToken>, DomainError> {
+        let sql = format!(
+            "SELECT {TOKEN_COLUMNS} FROM api_tokens \
+             WHERE tenant_id = $1 AND user_id = $2 ORDER BY created_at, id"
+        );
+        let query = sqlx::query_as::<_, TokenRow>(&sql).bind(tenant.value()).bind(user_id.value());
+        let mut conn = acquire(&self.pool, "find_by_user", "api token").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_by_user", "api token").await?;
+        Ok(rows.into_iter().map(TokenRow::into_domain).collect())
+    }
+
+    async fn find_by_hash(
+        &self,
+        tenant: &TenantId,
+        token_hash: &str,
+    ) -> Result<Option<ApiToken>, DomainError> {
+        let sql = format!(
+            "SELECT {TOKEN_COLUMNS} FROM api_tokens WHERE tenant_id = $1 AND token_hash = $2"
+        );
+        let query = sqlx::query_as::<_, TokenRow>(&sql).bind(tenant.value()).bind(token_hash);
+        let mut conn = acquire(&self.pool, "find", "api token").await?;
+        let row = run_query(query.fetch_optional(&mut *conn), "find", "api token").await?;
+        Ok(row.map(TokenRow::into_domain))
+    }
+
+    async fn insert(&self, tenant: &TenantId, token: &ApiToken) -> Result<(), DomainError> {
+        let query = sqlx::query(
+            "INSERT INTO api_tokens (id, tenant_id, user_id, name, token_hash, created_at, \
+             expires_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(token.id().value())
+        .bind(tenant.value())
+        .bind(token.user_id().value())
+        .bind(token.name())
+        .bind(token.token_hash())
+        .bind(token.created_at())
+        .bind(token.expires_at());
+        let mut conn = acquire(&self.pool, "insert", "api token").await?;
+        run_query(query.execute(&mut *conn), "insert", "api token").await?;
+        Ok(())
+    }
+
+    async fn revoke(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        id: &ApiTokenId,
+        at: DateTime<Utc>,
+    ) -> Result<bool, DomainError> {
+        let query = sqlx::query(
+            "UPDATE api_tokens SET revoked_at = COALESCE(revoked_at, $1) \
+             WHERE tenant_id = $2 AND user_id = $3 AND id = $4",
+        )
+        .bind(at)
+        .bind(tenant.value())
+        .bind(user_id.value())
+        .bind(id.value());
+        let mut conn = acquire(&self.pool, "revoke", "api token").await?;
+        let result = run_query(query.execute(&mut *conn), "revoke", "api token").await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn touch(
+        &self,
+        tenant: &TenantId,
+        token: &ApiToken,
+        at: DateTime<Utc>,
+    ) -> Result<(), DomainError> {
+        // Never moves the last use back, whichever of two concurrent uses writes last
+        let query = sqlx::query(
+            "UPDATE api_tokens SET last_used_at = GREATEST(last_used_at, $1) \
+             WHERE tenant_id = $2 AND id = $3",
+        )
+        .bind(at)
+        .bind(tenant.value())
+        .bind(token.id().value());
+        let mut conn = acquire(&self.pool, "touch", "api token").await?;
+        run_query(query.execute(&mut *conn), "touch", "api token").await?;
+        Ok(())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TokenRow {
+    id: String,
+    user_id: String,
+    name: String,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl TokenRow {
+    fn into_domain(self) -> ApiToken {
+        ApiToken::reconstitute(
+            ApiTokenId::from_trusted(self.id),
+            UserId::from_trusted(self.user_id),
+            self.name,
+            self.token_hash,
+            self.created_at,
+            self.expires_at,
+            self.last_used_at,
+            self.revoked_at,
+        )
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::api_token::domain::{generate_token, hash_token};
+    use crate::features::user::domain::{User, UserRepository};
+    use crate::features::user::infrastructure::PgUserRepository;
+    use chrono::TimeDelta;
+
+    async fn user(pool: &PgPool, tenant: &TenantId) -> User {
+        let user = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+            .expect("valid user");
+        PgUserRepository::new(pool.clone()).insert(tenant, &user).await.expect("insert user");
+        user
+    }
+
+    fn token(user: &User, expires_at: Option<DateTime<Utc>>) -> ApiToken {
+        let (hash, now) = (hash_token(&generate_token()), Utc::now());
+        ApiToken::new(ApiTokenId::generate(), user.id().clone(), "ci", hash, now, expires_at)
+            .expect("valid token")
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn tokens_should_be_found_by_hash_touched_and_revoked_once(pool: PgPool) {
+        let tenant = TenantId::default();
+        let repo = PgApiTokenRepository::new(pool.clone());
+        let alice = user(&pool, &tenant).await;
+        let expiry = Utc::now() + TimeDelta::days(30);
+        let (first, second) = (token(&alice, Some(expiry)), token(&alice, None));
+        repo.insert(&tenant, &first).await.expect("insert");
+        repo.insert(&tenant, &second).await.expect("insert");
+        let duplicate = repo.insert(&tenant, &first).await;
+        assert!(duplicate.is_err(), "{duplicate:?}");
+
+        let found = repo.find_by_hash(&tenant, first.token_hash()).await.expect("find");
+        let found = found.expect("found");
+        assert_eq!((found.id(), found.name()), (first.id(), "ci"));
+        let expires_at = found.expires_at().expect("expiry");
+        assert!((expires_at - expiry).abs() < TimeDelta::milliseconds(1), "{expires_at}");
+        let other = TenantId::new("other").expect("valid tenant");
+        assert!(repo.find_by_hash(&other, first.token_hash()).await.expect("find").is_none());
+
+        let used = Utc::now();
+        repo.touch(&tenant, &first, used).await.expect("touch");
+        repo.touch(&tenant, &first, used - TimeDelta::minutes(5)).await.expect("touch");
+        let revoked_at = used + TimeDelta::seconds(1);
+        assert!(repo.revoke(&tenant, alice.id(), first.id(), revoked_at).await.expect("revoke"));
+        let later = revoked_at + TimeDelta::seconds(1);
+        assert!(repo.revoke(&tenant, alice.id(), first.id(), later).await.expect("revoke"));
+        let stranger = UserId::generate();
+        assert!(!repo.revoke(&tenant, &stranger, second.id(), later).await.expect("revoke"));
+
+        let tokens = repo.find_by_user(&tenant, alice.id()).await.expect("tokens");
+        let ids: Vec<_> = tokens.iter().map(ApiToken::id).collect();
+        assert_eq!(ids, [first.id(), second.id()]);
+        let last_used_at = tokens[0].last_used_at().expect("used");
+        assert!((last_used_at - used).abs() < TimeDelta::milliseconds(1), "not moved back");
+        let revoked = tokens[0].revoked_at().expect("revoked");
+        assert!((revoked - revoked_at).abs() < TimeDelta::milliseconds(1), "first revocation");
+        assert_eq!((tokens[1].last_used_at(), tokens[1].revoked_at()), (None, None));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn tokens_should_be_deleted_with_their_user(pool: PgPool) {
+        let tenant = TenantId::default();
+        let repo = PgApiTokenRepository::new(pool.clone());
+        let alice = user(&pool, &tenant).await;
+        repo.insert(&tenant, &token(&alice, None)).await.expect("insert");
+
+        let users = PgUserRepository::new(pool.clone());
+        assert!(users.delete(&tenant, alice.id()).await.expect("delete user"));
+        assert!(repo.find_by_user(&tenant, alice.id()).await.expect("tokens").is_empty());
+    }
+}
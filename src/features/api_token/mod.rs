@@ -0,0 +1,14 @@
+//! API token feature module: long-lived tokens authenticating programmatic access as
+//! a user
+
+pub mod application;
+pub mod domain;
+pub mod infrastructure;
+pub mod state;
+
+pub use state::ApiTokenState;
+
+/// Feature name used in `ENABLED_FEATURES` / `DISABLED_FEATURES`
+pub const NAME: &str = "api_token";
+/// Features that must be enabled for this one to work (tokens are issued to users)
+pub const DEPENDS_ON: &[&str] = &[super::user::NAME];
@@ -0,0 +1,34 @@
+//! API token feature state shared across handlers and the authentication middleware
+
+use crate::features::api_token::application::{
+    AuthenticateApiTokenUseCase, CreateApiTokenUseCase, ListApiTokensUseCase,
+    RevokeApiTokenUseCase,
+};
+use crate::features::api_token::domain::ApiTokenRepository;
+use crate::features::user::domain::UserRepository;
+use std::sync::Arc;
+
+/// Use cases of the API token feature
+pub struct ApiTokenState {
+    pub(crate) create_token: CreateApiTokenUseCase,
+    pub(crate) list_tokens: ListApiTokensUseCase,
+    pub(crate) revoke_token: RevokeApiTokenUseCase,
+    pub(crate) authenticate: Arc<AuthenticateApiTokenUseCase>,
+}
+
+impl ApiTokenState {
+    /// Wire every API token use case to `repository`, issuing tokens to the users of
+    /// `users`
+    #[must_use]
+    pub fn new(
+        users: &Arc<dyn UserRepository>,
+        repository: &Arc<dyn ApiTokenRepository>,
+    ) -> Self {
+        Self {
+            create_token: CreateApiTokenUseCase::new(Arc::clone(users), Arc::clone(repository)),
+            list_tokens: ListApiTokensUseCase::new(Arc::clone(users), Arc::clone(repository)),
+            revoke_token: RevokeApiTokenUseCase::new(Arc::clone(repository)),
+            authenticate: Arc::new(AuthenticateApiTokenUseCase::new(Arc::clone(repository))),
+        }
+    }
+}
@@ -1,4 +1,6 @@
 //! Features module - each feature is self-contained with domain/application/infrastructure
 
+pub mod api_token;
 pub mod task;
 pub mod user;
+pub mod webhook;
@@ -0,0 +1,169 @@
+//! Task attachment use cases
+//!
+//! Clients upload and download the bytes directly to and from blob storage through
+//! presigned URLs; these use cases only manage the metadata and issue the URLs.
+
+use crate::features::task::domain::attachment::check_filename;
+use crate::features::task::domain::{
+    Attachment, AttachmentId, AttachmentRepository, AttachmentRules, BlobStorage, PresignedUrl,
+    TaskId, TaskRepository,
+};
+use crate::shared::application::{Validate, Validated, ValidationErrors};
+use crate::shared::domain::{validation_message, DomainError, TenantId, CANNOT_BE_EMPTY};
+use std::sync::Arc;
+
+/// Command to attach a file to a task
+#[derive(Debug)]
+pub struct CreateAttachmentCommand {
+    /// Name of the file
+    pub filename: String,
+    /// Media type the file is uploaded with
+    pub content_type: String,
+    /// Size of the file in bytes
+    pub size: u64,
+}
+
+impl Validate for CreateAttachmentCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check("filename", check_filename(&self.filename));
+        if self.content_type.trim().is_empty() {
+            errors.add("content_type", validation_message("Content type", CANNOT_BE_EMPTY));
+        }
+        errors.into_result()
+    }
+}
+
+/// Fail with `NotFound` unless the task exists in `tenant`
+async fn ensure_task_exists<T: TaskRepository + ?Sized>(
+    tasks: &T,
+    tenant: &TenantId,
+    id: &TaskId,
+) -> Result<(), DomainError> {
+    match tasks.find_by_id(tenant, id).await? {
+        Some(_) => Ok(()),
+        None => Err(DomainError::not_found(TaskId::entity_name())),
+    }
+}
+
+/// Use case for attaching a file to a task
+pub struct CreateAttachmentUseCase<T: ?Sized = dyn TaskRepository> {
+    tasks: Arc<T>,
+    attachments: Arc<dyn AttachmentRepository>,
+    storage: Arc<dyn BlobStorage>,
+    rules: AttachmentRules,
+}
+
+impl<T: TaskRepository + ?Sized> CreateAttachmentUseCase<T> {
+    /// Create a new use case instance accepting files within `rules`
+    pub fn new(
+        tasks: Arc<T>,
+        attachments: Arc<dyn AttachmentRepository>,
+        storage: Arc<dyn BlobStorage>,
+        rules: AttachmentRules,
+    ) -> Self {
+        Self { tasks, attachments, storage, rules }
+    }
+
+    /// Record an attachment of the task of `tenant` with `task_id` and issue the URL
+    /// the client uploads the file to
+    ///
+    /// The URL is issued before the metadata is stored, so a failure leaves nothing
+    /// behind.
+    ///
+    /// # Errors
+    /// `NotFound` for an unknown task, `Validation` for a file outside the configured
+    /// rules.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        task_id: &str,
+        command: Validated<CreateAttachmentCommand>,
+    ) -> Result<(Attachment, PresignedUrl), DomainError> {
+        let task_id = TaskId::new(task_id)?;
+        ensure_task_exists(&*self.tasks, tenant, &task_id).await?;
+        let command = command.into_inner();
+        let attachment = Attachment::new(
+            tenant,
+            task_id,
+            &command.filename,
+            &command.content_type,
+            command.size,
+            &self.rules,
+        )?;
+        let upload = self
+            .storage
+            .issue_upload_url(attachment.storage_key(), attachment.content_type())
+            .await?;
+        self.attachments.insert(tenant, &attachment).await?;
+        Ok((attachment, upload))
+    }
+}
+
+/// Use case for listing the attachments of a task
+pub struct ListAttachmentsUseCase<T: ?Sized = dyn TaskRepository> {
+    tasks: Arc<T>,
+    attachments: Arc<dyn AttachmentRepository>,
+    storage: Arc<dyn BlobStorage>,
+}
+
+impl<T: TaskRepository + ?Sized> ListAttachmentsUseCase<T> {
+    /// Create a new use case instance
+    pub fn new(
+        tasks: Arc<T>,
+        attachments: Arc<dyn AttachmentRepository>,
+        storage: Arc<dyn BlobStorage>,
+    ) -> Self {
+        Self { tasks, attachments, storage }
+    }
+
+    /// The attachments of the task of `tenant` with `task_id`, oldest first, each
+    /// with a URL to download it
+    ///
+    /// # Errors
+    /// `NotFound` for an unknown task.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        task_id: &str,
+    ) -> Result<Vec<(Attachment, PresignedUrl)>, DomainError> {
+        let task_id = TaskId::new(task_id)?;
+        ensure_task_exists(&*self.tasks, tenant, &task_id).await?;
+        let mut listed = Vec::new();
+        for attachment in self.attachments.find_by_task(tenant, &task_id).await? {
+            let download = self.storage.issue_download_url(attachment.storage_key()).await?;
+            listed.push((attachment, download));
+        }
+        Ok(listed)
+    }
+}
+
+/// Use case for deleting an attachment
+pub struct DeleteAttachmentUseCase {
+    attachments: Arc<dyn AttachmentRepository>,
+}
+
+impl DeleteAttachmentUseCase {
+    /// Create a new use case instance
+    pub fn new(attachments: Arc<dyn AttachmentRepository>) -> Self {
+        Self { attachments }
+    }
+
+    /// Delete the attachment `id` of the task of `tenant` with `task_id`; its blob is
+    /// queued and deleted from storage in the background
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the task has no such attachment.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        task_id: &str,
+        id: &str,
+    ) -> Result<(), DomainError> {
+        let (task_id, id) = (TaskId::new(task_id)?, AttachmentId::new(id)?);
+        if !self.attachments.delete(tenant, &task_id, &id).await? {
+            return Err(DomainError::not_found(AttachmentId::entity_name()));
+        }
+        Ok(())
+    }
+}
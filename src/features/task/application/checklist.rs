@@ -0,0 +1,174 @@
+//! Task checklist use cases
+//!
+//! Each loads the task, changes its checklist through the aggregate, which keeps the
+//! checklist rules, and writes the task back.
+
+use crate::features::task::domain::checklist::checked_text;
+use crate::features::task::domain::{
+    ChecklistItem, ChecklistItemId, Task, TaskId, TaskRepository,
+};
+use crate::shared::application::{Validate, Validated, ValidationErrors};
+use crate::shared::domain::{DomainError, TenantId};
+use std::sync::Arc;
+
+/// Command to add an item to a task's checklist
+#[derive(Debug)]
+pub struct AddChecklistItemCommand {
+    /// What is to be done
+    pub text: String,
+}
+
+impl Validate for AddChecklistItemCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check("text", checked_text(&self.text));
+        errors.into_result()
+    }
+}
+
+/// The task of `tenant` with `task_id` after `change`, as persisted
+async fn change_checklist<T, R>(
+    tasks: &T,
+    tenant: &TenantId,
+    task_id: &str,
+    change: impl FnOnce(&mut Task) -> Result<R, DomainError>,
+) -> Result<(Task, R), DomainError>
+where
+    T: TaskRepository + ?Sized,
+{
+    let task_id = TaskId::new(task_id)?;
+    let mut task = tasks
+        .find_by_id(tenant, &task_id)
+        .await?
+        .ok_or_else(|| DomainError::not_found(TaskId::entity_name()))?;
+    let changed = change(&mut task)?;
+    Ok((tasks.update(tenant, &task).await?, changed))
+}
+
+/// Use case for adding an item to a task's checklist
+pub struct AddChecklistItemUseCase<T: ?Sized = dyn TaskRepository> {
+    tasks: Arc<T>,
+}
+
+impl<T: TaskRepository + ?Sized> AddChecklistItemUseCase<T> {
+    /// Create a new use case instance
+    pub fn new(tasks: Arc<T>) -> Self {
+        Self { tasks }
+    }
+
+    /// Append an unchecked item to the checklist of the task of `tenant` with
+    /// `task_id`, returning the task as persisted and the new item
+    ///
+    /// # Errors
+    /// `NotFound` for an unknown task, `Validation` once the checklist is full.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        task_id: &str,
+        command: Validated<AddChecklistItemCommand>,
+    ) -> Result<(Task, ChecklistItem), DomainError> {
+        let text = command.into_inner().text;
+        change_checklist(&*self.tasks, tenant, task_id, |task| task.add_item(&text)).await
+    }
+}
+
+/// Use case for checking or unchecking an item of a task's checklist
+pub struct ToggleChecklistItemUseCase<T: ?Sized = dyn TaskRepository> {
+    tasks: Arc<T>,
+}
+
+impl<T: TaskRepository + ?Sized> ToggleChecklistItemUseCase<T> {
+    /// Create a new use case instance
+    pub fn new(tasks: Arc<T>) -> Self {
+        Self { tasks }
+    }
+
+    /// Check (`done`) or uncheck the item `item_id` of the task of `tenant` with
+    /// `task_id`, returning the task as persisted
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` for an unknown task or item.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        task_id: &str,
+        item_id: &str,
+        done: bool,
+    ) -> Result<Task, DomainError> {
+        let item_id = ChecklistItemId::new(item_id)?;
+        let toggle = |task: &mut Task| task.toggle_item(&item_id, done).map(|_| ());
+        Ok(change_checklist(&*self.tasks, tenant, task_id, toggle).await?.0)
+    }
+}
+
+/// Use case for removing an item from a task's checklist
+pub struct RemoveChecklistItemUseCase<T: ?Sized = dyn TaskRepository> {
+    tasks: Arc<T>,
+}
+
+impl<T: TaskRepository + ?Sized> RemoveChecklistItemUseCase<T> {
+    /// Create a new use case instance
+    pub fn new(tasks: Arc<T>) -> Self {
+        Self { tasks }
+    }
+
+    /// Remove the item `item_id` of the task of `tenant` with `task_id`, returning
+    /// the task as persisted
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` for an unknown task or item.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        task_id: &str,
+        item_id: &str,
+    ) -> Result<Task, DomainError> {
+        let item_id = ChecklistItemId::new(item_id)?;
+        let remove = |task: &mut Task| task.remove_item(&item_id);
+        Ok(change_checklist(&*self.tasks, tenant, task_id, remove).await?.0)
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::shared::domain::{Entity, UserId};
+
+    #[tokio::test]
+    async fn checklist_changes_should_be_persisted_with_the_task() {
+        let tenant = TenantId::default();
+        let tasks = Arc::new(InMemoryTaskRepository::default());
+        let task = Task::new(TaskId::generate(), UserId::generate(), "Pack", String::new())
+            .expect("valid task");
+        tasks.insert(&tenant, &task).await.expect("insert");
+        let id = task.id().value();
+
+        let command = Validated::new(AddChecklistItemCommand { text: " Socks ".into() })
+            .expect("valid command");
+        let add = AddChecklistItemUseCase::new(Arc::clone(&tasks));
+        let (_, item) = add.execute(&tenant, id, command).await.expect("added");
+        let toggle = ToggleChecklistItemUseCase::new(Arc::clone(&tasks));
+        toggle.execute(&tenant, id, item.id().value(), true).await.expect("toggled");
+        let stored = tasks.find_by_id(&tenant, task.id()).await.expect("query").expect("task");
+        let items: Vec<_> = stored.checklist().iter().map(|i| (i.text(), i.is_done())).collect();
+        assert_eq!(items, [("Socks", true)]);
+
+        let remove = RemoveChecklistItemUseCase::new(Arc::clone(&tasks));
+        let removed = remove.execute(&tenant, id, item.id().value()).await.expect("removed");
+        assert!(removed.checklist().is_empty());
+        let again = remove.execute(&tenant, id, item.id().value()).await;
+        assert!(matches!(again, Err(DomainError::NotFound(_))), "{again:?}");
+        let missing = toggle.execute(&tenant, "missing", item.id().value(), true).await;
+        assert!(matches!(missing, Err(DomainError::NotFound(_))), "{missing:?}");
+    }
+
+    #[test]
+    fn add_commands_should_need_text_of_valid_length() {
+        for text in ["", "  ", &"x".repeat(201)] {
+            let command = AddChecklistItemCommand { text: text.to_owned() };
+            assert!(Validated::new(command).is_err(), "{text:?}");
+        }
+    }
+}
@@ -1,31 +1,185 @@
 //! Complete task use case
 
-use crate::features::task::domain::{Task, TaskId, TaskRepository};
-use crate::shared::domain::DomainError;
+use crate::features::task::domain::entity::ALREADY_COMPLETED;
+use crate::features::task::domain::{
+    CompleteOutcome, Task, TaskId, TaskRepository, UnitOfWork,
+};
+use crate::shared::application::FeatureToggles;
+use crate::shared::domain::{DomainError, DomainWarning, TenantId};
 use std::sync::Arc;
 
+/// Feature flag of the atomic completion; tasks outside its rollout are completed by
+/// reading, changing and writing them back
+pub const ATOMIC_COMPLETE: &str = "atomic_complete";
+
 /// Use case for completing a task
-pub struct CompleteTaskUseCase {
-    repository: Arc<dyn TaskRepository>,
+pub struct CompleteTaskUseCase<T: ?Sized = dyn TaskRepository> {
+    repository: Arc<T>,
+    unit_of_work: Arc<dyn UnitOfWork>,
+    require_checked_checklist: bool,
+    toggles: Arc<FeatureToggles>,
 }
 
-impl CompleteTaskUseCase {
-    /// Create a new use case instance
-    pub fn new(repository: Arc<dyn TaskRepository>) -> Self {
-        Self { repository }
+impl<T: TaskRepository + ?Sized> CompleteTaskUseCase<T> {
+    /// Create a new use case instance, completing tasks atomically as far as
+    /// [`ATOMIC_COMPLETE`] is rolled out in `toggles` and through `unit_of_work`
+    /// otherwise
+    ///
+    /// When `require_checked_checklist` is set, a task with unchecked checklist items
+    /// cannot be completed; otherwise it is completed with a warning.
+    pub fn new(
+        repository: Arc<T>,
+        unit_of_work: Arc<dyn UnitOfWork>,
+        require_checked_checklist: bool,
+        toggles: Arc<FeatureToggles>,
+    ) -> Self {
+        Self { repository, unit_of_work, require_checked_checklist, toggles }
     }
 
-    pub async fn execute(&self, id: &str) -> Result<Task, DomainError> {
+    /// Complete the task of `tenant` with `id`
+    ///
+    /// Returns the task as persisted, including the database-assigned `updated_at`,
+    /// and the soft rules completing it broke
+    ///
+    /// Within the rollout of [`ATOMIC_COMPLETE`], completion is a single conditional
+    /// write, so of concurrent completes of the same task exactly one succeeds.
+    /// Outside it, or when checklist items must be checked first, the task is read,
+    /// completed and written back in a transaction holding its row lock, which has
+    /// the same effect and keeps the checklist from changing in between.
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the task doesn't exist and
+    /// `Conflict` if it is already completed, or has unchecked checklist items while
+    /// they are required to be checked.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+    ) -> Result<(Task, Vec<DomainWarning>), DomainError> {
         let task_id = TaskId::new(id)?;
+        // The conditional write cannot check the checklist, which the lock keeps as read
+        if self.require_checked_checklist
+            || !self.toggles.is_enabled(ATOMIC_COMPLETE, task_id.value())
+        {
+            return self.read_modify_write(tenant, &task_id).await;
+        }
+        match self.repository.complete_if_open(tenant, &task_id).await? {
+            CompleteOutcome::Completed(task) => {
+                let warnings = task.completion_warnings(false)?;
+                Ok((task, warnings))
+            }
+            CompleteOutcome::AlreadyCompleted => {
+                Err(DomainError::Conflict(ALREADY_COMPLETED.into()))
+            }
+            CompleteOutcome::NotFound => Err(DomainError::not_found(TaskId::entity_name())),
+        }
+    }
 
-        let mut task = self
-            .repository
-            .find_by_id(&task_id)
+    /// Complete the task by reading it, completing the aggregate and writing it back,
+    /// with the task locked from the read until the write
+    async fn read_modify_write(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<(Task, Vec<DomainWarning>), DomainError> {
+        // Dropping the transaction on an early return releases the lock
+        let mut transaction = self.unit_of_work.begin().await?;
+        let mut task = transaction
+            .find_task_for_update(tenant, task_id)
             .await?
-            .ok_or_else(|| DomainError::NotFound(format!("{} not found", TaskId::entity_name())))?;
-
+            .ok_or_else(|| DomainError::not_found(TaskId::entity_name()))?;
         task.complete()?;
-        self.repository.update(&task).await?;
-        Ok(task)
+        let warnings = task.completion_warnings(self.require_checked_checklist)?;
+        let task = transaction.update_task(tenant, &task).await?;
+        transaction.commit().await?;
+        // The unit of work bypassed the repository, which may cache the task
+        self.repository.task_updated(tenant, task_id).await?;
+        Ok((task, warnings))
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::UNCHECKED_CHECKLIST_ITEMS;
+    use crate::features::task::infrastructure::{InMemoryTaskRepository, InMemoryUnitOfWork};
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::{Entity, UserId};
+
+    /// Use case completing tasks of `repository` atomically for `percentage` percent
+    fn use_case(
+        repository: &Arc<InMemoryTaskRepository>,
+        require_checked_checklist: bool,
+        percentage: u8,
+    ) -> CompleteTaskUseCase<InMemoryTaskRepository> {
+        let toggles = Arc::new(FeatureToggles::new(&[(ATOMIC_COMPLETE, percentage)]));
+        let users = Arc::new(InMemoryUserRepository::default());
+        let unit_of_work = Arc::new(InMemoryUnitOfWork::new(users, Arc::clone(repository)));
+        let strict = require_checked_checklist;
+        CompleteTaskUseCase::new(Arc::clone(repository), unit_of_work, strict, toggles)
+    }
+
+    #[tokio::test]
+    async fn concurrent_completes_should_succeed_exactly_once() {
+        // The read-modify-write path locks the task, to the same effect
+        for percentage in [0, 100] {
+            let tenant = TenantId::default();
+            let repository = Arc::new(InMemoryTaskRepository::default());
+            let task =
+                Task::new(TaskId::generate(), UserId::generate(), "Buy milk", String::new())
+                    .expect("valid task");
+            repository.insert(&tenant, &task).await.expect("insert");
+            let use_case = use_case(&repository, false, percentage);
+
+            let id = task.id().value();
+            let (first, second) =
+                tokio::join!(use_case.execute(&tenant, id), use_case.execute(&tenant, id));
+            let results = [first, second];
+            assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1, "{percentage}%");
+            assert!(results.iter().any(|r| matches!(r, Err(DomainError::Conflict(_)))));
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_should_return_not_found_for_missing_task() {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        for percentage in [0, 100] {
+            let result = use_case(&repository, false, percentage).execute(&tenant, "missing").await;
+            assert!(matches!(result, Err(DomainError::NotFound(_))), "{percentage}%");
+        }
+    }
+
+    #[tokio::test]
+    async fn unchecked_checklist_items_should_warn_or_block_completion() {
+        // The read-modify-write path keeps the rules of the atomic one
+        for percentage in [0, 100] {
+            let tenant = TenantId::default();
+            let repository = Arc::new(InMemoryTaskRepository::default());
+            let mut task = Task::new(TaskId::generate(), UserId::generate(), "Pack", String::new())
+                .expect("valid task");
+            task.add_item("Socks").expect("valid item");
+            repository.insert(&tenant, &task).await.expect("insert");
+            let id = task.id().value();
+
+            let strict = use_case(&repository, true, percentage);
+            let blocked = strict.execute(&tenant, id).await;
+            assert!(matches!(blocked, Err(DomainError::Conflict(_))), "{blocked:?}");
+            let stored = repository.find_by_id(&tenant, task.id()).await.expect("query");
+            assert!(stored.is_some_and(|t| !t.is_completed()));
+
+            let lenient = use_case(&repository, false, percentage);
+            let (completed, warnings) = lenient.execute(&tenant, id).await.expect("completed");
+            assert!(completed.is_completed());
+            let stored = repository.find_by_id(&tenant, task.id()).await.expect("query");
+            assert!(stored.is_some_and(|t| t.is_completed()));
+            let codes: Vec<_> = warnings.iter().map(|w| w.code).collect();
+            assert_eq!(codes, [UNCHECKED_CHECKLIST_ITEMS]);
+            let again = strict.execute(&tenant, id).await;
+            assert!(matches!(again, Err(DomainError::Conflict(_))), "{again:?}");
+            let again = lenient.execute(&tenant, id).await;
+            assert!(matches!(again, Err(DomainError::Conflict(m)) if m == ALREADY_COMPLETED));
+        }
     }
 }
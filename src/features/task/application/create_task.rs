@@ -1,7 +1,11 @@
 //! Create task use case
 
+use crate::features::task::domain::entity::normalize_title;
 use crate::features::task::domain::{Task, TaskId, TaskRepository};
-use crate::shared::domain::{DomainError, UserId};
+use crate::shared::application::{Validate, Validated, ValidationErrors};
+use crate::shared::domain::{
+    validation_message, DomainError, DomainWarning, TenantId, UserId, CANNOT_BE_EMPTY,
+};
 use std::sync::Arc;
 
 /// Command to create a new task
@@ -15,23 +19,143 @@ pub struct CreateTaskCommand {
     pub description: String,
 }
 
+impl Validate for CreateTaskCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check("user_id", UserId::new(&self.user_id));
+        if normalize_title(&self.title).is_empty() {
+            errors.add("title", validation_message("Title", CANNOT_BE_EMPTY));
+        }
+        errors.into_result()
+    }
+}
+
 /// Use case for creating a task
-pub struct CreateTaskUseCase {
-    task_repository: Arc<dyn TaskRepository>,
+pub struct CreateTaskUseCase<T: ?Sized = dyn TaskRepository> {
+    task_repository: Arc<T>,
+    prevent_duplicate_open_tasks: bool,
 }
 
-impl CreateTaskUseCase {
+impl<T: TaskRepository + ?Sized> CreateTaskUseCase<T> {
     /// Create a new use case instance
-    pub fn new(task_repository: Arc<dyn TaskRepository>) -> Self {
-        Self { task_repository }
+    ///
+    /// When `prevent_duplicate_open_tasks` is set, creating a task whose
+    /// normalized title matches one of the user's open tasks is rejected.
+    pub fn new(task_repository: Arc<T>, prevent_duplicate_open_tasks: bool) -> Self {
+        Self { task_repository, prevent_duplicate_open_tasks }
+    }
+
+    /// Create a task of `tenant` with a generated ID
+    ///
+    /// User existence is enforced by the database FK constraint, which includes the
+    /// tenant. If the user doesn't exist in the tenant, the insert will fail with
+    /// `DomainError::NotFound`.
+    ///
+    /// Returns the persisted task with the soft-rule warnings its input produced;
+    /// nothing is written when a hard rule fails.
+    ///
+    /// # Errors
+    /// `NotFound` for an unknown user and `AlreadyExists` for a duplicate open task
+    /// when duplicates are prevented.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        command: Validated<CreateTaskCommand>,
+    ) -> Result<(Task, Vec<DomainWarning>), DomainError> {
+        let command = command.into_inner();
+        let user_id = UserId::from_trusted(command.user_id);
+        let (task, warnings) = Task::new_with_warnings(
+            TaskId::generate(),
+            user_id,
+            &command.title,
+            command.description,
+        )?;
+
+        if self.prevent_duplicate_open_tasks
+            && let Some(existing) = self
+                .task_repository
+                .exists_open_with_title(tenant, task.user_id(), task.title())
+                .await?
+        {
+            return Err(DomainError::already_exists_as("Open task", "title", existing.value()));
+        }
+
+        Ok((self.task_repository.insert(tenant, &task).await?, warnings))
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::shared::domain::Entity;
+
+    fn command(title: &str) -> Validated<CreateTaskCommand> {
+        validated("user1", title)
+    }
+
+    fn validated(user_id: &str, title: &str) -> Validated<CreateTaskCommand> {
+        let command = CreateTaskCommand {
+            user_id: user_id.into(),
+            title: title.into(),
+            description: String::new(),
+        };
+        Validated::new(command).expect("valid command")
+    }
+
+    #[test]
+    fn validate_should_report_every_invalid_field() {
+        let command = CreateTaskCommand {
+            user_id: String::new(),
+            title: " \t".into(),
+            description: String::new(),
+        };
+        let errors = command.validate().err().unwrap_or_default();
+        let fields: Vec<_> = errors.fields().iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["user_id", "title"]);
+        assert_eq!(errors.to_string(), "User ID cannot be empty; Title cannot be empty");
+    }
+
+    #[tokio::test]
+    async fn execute_should_allow_duplicates_when_policy_is_off() {
+        let tenant = TenantId::default();
+        let use_case = CreateTaskUseCase::new(Arc::new(InMemoryTaskRepository::default()), false);
+        use_case.execute(&tenant, command("Buy milk")).await.expect("first create");
+        assert!(use_case.execute(&tenant, command("Buy milk")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn execute_should_reject_duplicate_open_task_with_existing_id() {
+        let tenant = TenantId::default();
+        let use_case = CreateTaskUseCase::new(Arc::new(InMemoryTaskRepository::default()), true);
+        let (existing, _) =
+            use_case.execute(&tenant, command("Buy milk")).await.expect("first create");
+
+        let result = use_case.execute(&tenant, command("  buy   MILK ")).await;
+        assert!(
+            matches!(result, Err(DomainError::AlreadyExists(msg)) if msg.contains(existing.id().value()))
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_should_not_be_blocked_by_completed_task() {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        let use_case = CreateTaskUseCase::new(Arc::clone(&repository) as _, true);
+        let (mut existing, _) =
+            use_case.execute(&tenant, command("Buy milk")).await.expect("first create");
+        existing.complete().expect("complete");
+        repository.update(&tenant, &existing).await.expect("update");
+
+        assert!(use_case.execute(&tenant, command("Buy milk")).await.is_ok());
     }
 
-    /// User existence is enforced by the database FK constraint.
-    /// If the user doesn't exist, the insert will fail with `DomainError::NotFound`.
-    pub async fn execute(&self, command: CreateTaskCommand) -> Result<Task, DomainError> {
-        let user_id = UserId::new(&command.user_id)?;
-        let task = Task::new(TaskId::generate(), user_id, command.title, command.description)?;
-        self.task_repository.insert(&task).await?;
-        Ok(task)
+    #[tokio::test]
+    async fn execute_should_scope_duplicates_per_user() {
+        let tenant = TenantId::default();
+        let use_case = CreateTaskUseCase::new(Arc::new(InMemoryTaskRepository::default()), true);
+        use_case.execute(&tenant, command("Buy milk")).await.expect("first create");
+        assert!(use_case.execute(&tenant, validated("user2", "Buy milk")).await.is_ok());
     }
 }
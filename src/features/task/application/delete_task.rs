@@ -1,27 +1,73 @@
 //! Delete task use case
 
-use crate::features::task::domain::{TaskId, TaskRepository};
-use crate::shared::domain::DomainError;
+use crate::features::task::domain::{AttachmentRepository, Task, TaskId, TaskRepository};
+use crate::shared::application::DetachedWrites;
+use crate::shared::domain::{DomainError, TenantId};
 use std::sync::Arc;
 
 /// Use case for deleting a task
-pub struct DeleteTaskUseCase {
-    repository: Arc<dyn TaskRepository>,
+pub struct DeleteTaskUseCase<T: ?Sized = dyn TaskRepository> {
+    repository: Arc<T>,
+    attachments: Arc<dyn AttachmentRepository>,
+    detached: DetachedWrites,
 }
 
-impl DeleteTaskUseCase {
-    /// Create a new use case instance
-    pub fn new(repository: Arc<dyn TaskRepository>) -> Self {
-        Self { repository }
+impl<T: TaskRepository + ?Sized + 'static> DeleteTaskUseCase<T> {
+    /// Create a new use case instance, running its deletions on `detached`
+    pub fn new(
+        repository: Arc<T>,
+        attachments: Arc<dyn AttachmentRepository>,
+        detached: DetachedWrites,
+    ) -> Self {
+        Self { repository, attachments, detached }
     }
 
-    pub async fn execute(&self, id: &str) -> Result<(), DomainError> {
+    /// Delete the task of `tenant` with `id` along with its attachments, whose blobs
+    /// are queued for deletion from storage
+    ///
+    /// `PostgreSQL` already cascades the attachments with the task; deleting them
+    /// explicitly keeps every repository implementation consistent. Both deletions
+    /// run detached, so a dropped request cannot leave the attachments behind.
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the task doesn't exist.
+    pub async fn execute(&self, tenant: &TenantId, id: &str) -> Result<(), DomainError> {
         let task_id = TaskId::new(id)?;
 
-        if !self.repository.delete(&task_id).await? {
-            return Err(DomainError::NotFound("Task not found".into()));
-        }
+        let (tasks, attachments) = (Arc::clone(&self.repository), Arc::clone(&self.attachments));
+        let tenant = tenant.clone();
+        self.detached.run(async move {
+            if !tasks.delete(&tenant, &task_id).await? {
+                return Err(DomainError::not_found(TaskId::entity_name()));
+            }
+            attachments.delete_by_task(&tenant, &task_id).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// [`execute`](Self::execute), also returning the task as it was deleted
+    ///
+    /// The task is read by the deletion itself, so no concurrent update can slip in
+    /// between.
+    ///
+    /// # Errors
+    /// As [`execute`](Self::execute).
+    pub async fn execute_returning(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+    ) -> Result<Task, DomainError> {
+        let task_id = TaskId::new(id)?;
 
-        Ok(())
+        let (tasks, attachments) = (Arc::clone(&self.repository), Arc::clone(&self.attachments));
+        let tenant = tenant.clone();
+        self.detached.run(async move {
+            let task = tasks.delete_returning(&tenant, &task_id).await?;
+            let task = task.ok_or_else(|| DomainError::not_found(TaskId::entity_name()))?;
+            attachments.delete_by_task(&tenant, &task_id).await?;
+            Ok(task)
+        })
+        .await
     }
 }
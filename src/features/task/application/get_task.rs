@@ -1,48 +1,265 @@
 //! Get task use case
 
-use crate::features::task::domain::{Task, TaskId, TaskRepository};
-use crate::shared::domain::{DomainError, UserId};
+use crate::features::task::domain::{Task, TaskFilter, TaskId, TaskRepository, TaskSort};
+use crate::shared::application::{PageLimits, PageRequest, Validate, Validated, ValidationErrors};
+use crate::shared::domain::{DomainError, TenantId, UserId};
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
 use std::sync::Arc;
 
+/// Fewest characters of a `q` search, so a search cannot match nearly everything
+pub const MIN_SEARCH_LENGTH: usize = 2;
+
+/// Filters of a task listing; every filter that is set must match
+#[derive(Debug, Default)]
+pub struct TaskListQuery {
+    /// Only tasks of this user
+    pub user_id: Option<String>,
+    /// Only completed (`true`) or open (`false`) tasks
+    pub completed: Option<bool>,
+    /// Only tasks whose title or description contains this text, ignoring case
+    pub q: Option<String>,
+}
+
+impl TaskListQuery {
+    /// All tasks of `user_id`
+    #[must_use]
+    pub fn for_user(user_id: impl Into<String>) -> Self {
+        Self { user_id: Some(user_id.into()), ..Self::default() }
+    }
+
+    /// The repository filter of a validated query
+    pub(super) fn into_filter(self) -> TaskFilter {
+        let search = search_text(self.q.as_deref());
+        TaskFilter {
+            user_id: self.user_id.map(UserId::from_trusted),
+            completed: self.completed,
+            search,
+        }
+    }
+}
+
+impl Validate for TaskListQuery {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if let Some(user_id) = &self.user_id {
+            errors.check("user_id", UserId::new(user_id));
+        }
+        check_search(&mut errors, self.q.as_deref());
+        errors.into_result()
+    }
+}
+
+/// The search text of `q` without surrounding whitespace, lowercased
+fn search_text(q: Option<&str>) -> Option<String> {
+    q.map(|q| q.trim().to_lowercase())
+}
+
+/// Record in `errors` a `q` search shorter than [`MIN_SEARCH_LENGTH`]
+pub(super) fn check_search(errors: &mut ValidationErrors, q: Option<&str>) {
+    if search_text(q).is_some_and(|q| q.chars().count() < MIN_SEARCH_LENGTH) {
+        errors.add("q", format!("Search text must be at least {MIN_SEARCH_LENGTH} characters"));
+    }
+}
+
 /// Use case for getting a task by ID
-pub struct GetTaskUseCase {
-    repository: Arc<dyn TaskRepository>,
+pub struct GetTaskUseCase<T: ?Sized = dyn TaskRepository> {
+    repository: Arc<T>,
 }
 
-impl GetTaskUseCase {
+impl<T: TaskRepository + ?Sized> GetTaskUseCase<T> {
     /// Create a new use case instance
-    pub fn new(repository: Arc<dyn TaskRepository>) -> Self {
+    pub fn new(repository: Arc<T>) -> Self {
         Self { repository }
     }
 
-    pub async fn execute(&self, id: &str) -> Result<Task, DomainError> {
+    /// Get the task of `tenant` with `id`
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the task doesn't exist, which
+    /// includes tasks of other tenants.
+    pub async fn execute(&self, tenant: &TenantId, id: &str) -> Result<Task, DomainError> {
         let task_id = TaskId::new(id)?;
         self.repository
-            .find_by_id(&task_id)
+            .find_by_id(tenant, &task_id)
             .await?
-            .ok_or_else(|| DomainError::NotFound(format!("{} not found", TaskId::entity_name())))
+            .ok_or_else(|| DomainError::not_found(TaskId::entity_name()))
     }
 }
 
-/// Use case for listing tasks, optionally filtered by user ID
-pub struct ListTasksUseCase {
-    repository: Arc<dyn TaskRepository>,
+/// Use case for listing tasks matching a [`TaskListQuery`], one page at a time
+pub struct ListTasksUseCase<T: ?Sized = dyn TaskRepository> {
+    repository: Arc<T>,
+    limits: PageLimits,
 }
 
-impl ListTasksUseCase {
-    /// Create a new use case instance
-    pub fn new(repository: Arc<dyn TaskRepository>) -> Self {
-        Self { repository }
+impl<T: TaskRepository + ?Sized> ListTasksUseCase<T> {
+    /// Create a new use case instance returning pages within `limits`
+    pub fn new(repository: Arc<T>, limits: PageLimits) -> Self {
+        Self { repository, limits }
+    }
+
+    /// Bounds of the pages listed
+    #[must_use]
+    pub fn limits(&self) -> PageLimits {
+        self.limits
     }
 
-    /// Pass `Some(user_id)` to filter by user, `None` to list all
-    pub async fn execute(&self, user_id: Option<&str>) -> Result<Vec<Task>, DomainError> {
-        match user_id {
-            Some(id) => {
-                let uid = UserId::new(id)?;
-                self.repository.find_by_user_id(&uid).await
+    /// List the requested page of the tasks of `tenant` matching every filter of
+    /// `query`, ordered by ID
+    ///
+    /// # Errors
+    /// `Validation` for a page outside the limits; repository failures.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        query: Validated<TaskListQuery>,
+        page: PageRequest,
+    ) -> Result<Vec<Task>, DomainError> {
+        let page = self.limits.resolve(page)?;
+        let filter = query.into_inner().into_filter();
+        self.repository.find_page(tenant, &filter, TaskSort::Id, page).await
+    }
+
+    /// Stream every task of `tenant` matching `query`, ordered by ID, without a page
+    /// limit; tasks are read as the stream is polled, so it can outlive the request's
+    /// handler as a response body
+    ///
+    /// A repository failure ends the stream with its error.
+    #[must_use]
+    pub fn stream(
+        &self,
+        tenant: TenantId,
+        query: Validated<TaskListQuery>,
+    ) -> BoxStream<'static, Result<Task, DomainError>>
+    where
+        T: 'static,
+    {
+        let repository = Arc::clone(&self.repository);
+        let filter = query.into_inner().into_filter();
+        Box::pin(async_stream::stream! {
+            let mut tasks = repository.stream_filtered(&tenant, &filter);
+            while let Some(task) = tasks.next().await {
+                yield task;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::shared::domain::Entity;
+
+    fn invalid_fields(query: TaskListQuery) -> Vec<&'static str> {
+        let errors = Validated::new(query).expect_err("invalid query");
+        errors.fields().iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn validate_should_report_every_invalid_filter() {
+        let query = TaskListQuery {
+            user_id: Some(String::new()),
+            q: Some(" a ".into()),
+            ..TaskListQuery::default()
+        };
+        assert_eq!(invalid_fields(query), ["user_id", "q"]);
+        let query = TaskListQuery { q: Some("ab".into()), ..TaskListQuery::for_user("u1") };
+        assert!(Validated::new(query).is_ok());
+        assert!(Validated::new(TaskListQuery::default()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn execute_should_apply_every_filter() {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        let (alice, bob) = (UserId::generate(), UserId::generate());
+        for (user, title, description, done) in [
+            (&alice, "Buy milk", "", true),
+            (&alice, "Buy bread", "from the MILL", false),
+            (&alice, "Walk", "", false),
+            (&bob, "Buy milk", "", false),
+        ] {
+            let mut task = Task::new(TaskId::generate(), user.clone(), title, description.into())
+                .expect("valid task");
+            if done {
+                task.complete().expect("complete");
             }
-            None => self.repository.find_all().await,
+            repository.insert(&tenant, &task).await.expect("insert");
         }
+        let use_case = ListTasksUseCase::new(repository, PageLimits::default());
+        let titles = |query: TaskListQuery| async {
+            let query = Validated::new(query).expect("valid");
+            let tasks = use_case.execute(&tenant, query, PageRequest::default()).await;
+            let mut titles: Vec<String> =
+                tasks.expect("list").iter().map(|t| t.title().to_owned()).collect();
+            titles.sort();
+            titles
+        };
+
+        assert_eq!(titles(TaskListQuery::default()).await.len(), 4);
+        let open =
+            TaskListQuery { completed: Some(false), ..TaskListQuery::for_user(alice.value()) };
+        assert_eq!(titles(open).await, ["Buy bread", "Walk"]);
+        let search =
+            TaskListQuery { q: Some(" MIL ".into()), ..TaskListQuery::for_user(alice.value()) };
+        assert_eq!(titles(search).await, ["Buy bread", "Buy milk"]);
+        let all = TaskListQuery {
+            user_id: Some(bob.value().into()),
+            completed: Some(true),
+            q: Some("milk".into()),
+        };
+        assert!(titles(all).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn execute_should_page_within_the_limits() {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        for id in ["a", "b", "c"] {
+            let id = TaskId::new(id).expect("id");
+            let task = Task::new(id, UserId::generate(), "Task", String::new()).expect("valid task");
+            repository.insert(&tenant, &task).await.expect("insert");
+        }
+        let limits = PageLimits { max_page_size: 2, max_offset: 2 };
+        let use_case = ListTasksUseCase::new(repository, limits);
+        let ids = async |limit, offset| {
+            let query = Validated::new(TaskListQuery::default()).expect("valid");
+            let tasks = use_case.execute(&tenant, query, PageRequest { limit, offset }).await?;
+            let ids: Vec<String> = tasks.iter().map(|t| t.id().value().to_owned()).collect();
+            Ok::<_, DomainError>(ids)
+        };
+
+        assert_eq!(ids(None, None).await.expect("list"), ["a", "b"]);
+        assert_eq!(ids(Some(1), Some(2)).await.expect("list"), ["c"]);
+        for (limit, offset) in [(Some(3), None), (Some(0), None), (None, Some(3))] {
+            let result = ids(limit, offset).await;
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{result:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_should_yield_every_matching_task_beyond_the_page_limit() {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        for (id, done) in [("a", false), ("b", true), ("c", false), ("d", false)] {
+            let id = TaskId::new(id).expect("id");
+            let mut task =
+                Task::new(id, UserId::generate(), "Task", String::new()).expect("valid task");
+            if done {
+                task.complete().expect("complete");
+            }
+            repository.insert(&tenant, &task).await.expect("insert");
+        }
+        let limits = PageLimits { max_page_size: 2, max_offset: 2 };
+        let use_case = ListTasksUseCase::new(repository, limits);
+
+        let query = TaskListQuery { completed: Some(false), ..TaskListQuery::default() };
+        let tasks = use_case.stream(tenant, Validated::new(query).expect("valid"));
+        let ids: Vec<String> =
+            tasks.map(|t| t.expect("task").id().value().to_owned()).collect().await;
+        assert_eq!(ids, ["a", "c", "d"]);
     }
 }
@@ -0,0 +1,458 @@
+//! Import tasks use case: many tasks from the rows of an uploaded file, in batches
+
+use crate::features::task::domain::{Task, TaskId, TaskRepository, UnitOfWork};
+use crate::features::user::domain::UserRepository;
+use crate::shared::domain::{DomainError, Entity, TenantId, UserId};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Rows written per transaction
+pub const IMPORT_BATCH_SIZE: usize = 100;
+
+/// Reason of a row giving a due date, which tasks do not have
+pub const NO_DUE_DATE: &str = "Tasks have no due date; leave due_date empty";
+
+/// How large an import may be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportLimits {
+    /// Most rows an import may have, the header excluded
+    pub max_rows: usize,
+    /// Most bytes the uploaded file may have
+    pub max_bytes: usize,
+}
+
+/// A task as read from one row of an imported file
+#[derive(Debug, Clone, Default)]
+pub struct ImportRow {
+    /// Line of the file the row starts on, from 1
+    pub line: u64,
+    /// ID of the task's user
+    pub user_id: String,
+    /// Task title
+    pub title: String,
+    /// Task description
+    pub description: String,
+    /// Due date; tasks have none, so anything but empty fails the row
+    pub due_date: String,
+    /// When the task was completed in the system it is imported from; `None` for an
+    /// open task
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// A row that was not imported, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportFailure {
+    /// Line of the file the row starts on, from 1
+    pub line: u64,
+    /// Why the row was not imported
+    pub reason: String,
+}
+
+/// Outcome of an import
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// Number of tasks written
+    pub imported: u64,
+    /// Rows not imported, by line
+    pub failed: Vec<ImportFailure>,
+}
+
+/// Open tasks of the file so far by user ID and lowercase title, with the line each
+/// starts on
+type OpenTitles = HashMap<(String, String), u64>;
+
+/// Use case importing tasks for existing users, e.g. from a spreadsheet
+pub struct ImportTasksUseCase<T: ?Sized = dyn TaskRepository, U: ?Sized = dyn UserRepository> {
+    task_repository: Arc<T>,
+    user_repository: Arc<U>,
+    unit_of_work: Arc<dyn UnitOfWork>,
+    limits: ImportLimits,
+    prevent_duplicate_open_tasks: bool,
+}
+
+impl<T: TaskRepository + ?Sized, U: UserRepository + ?Sized> ImportTasksUseCase<T, U> {
+    /// Create a new use case instance writing through `unit_of_work`
+    ///
+    /// When `prevent_duplicate_open_tasks` is set, an open row whose title matches an
+    /// open task of its user, stored or on an earlier row, fails.
+    pub fn new(
+        task_repository: Arc<T>,
+        user_repository: Arc<U>,
+        unit_of_work: Arc<dyn UnitOfWork>,
+        limits: ImportLimits,
+        prevent_duplicate_open_tasks: bool,
+    ) -> Self {
+        Self {
+            task_repository,
+            user_repository,
+            unit_of_work,
+            limits,
+            prevent_duplicate_open_tasks,
+        }
+    }
+
+    /// Limits adapters enforce while reading the file
+    #[must_use]
+    pub fn limits(&self) -> ImportLimits {
+        self.limits
+    }
+
+    /// Create a task of `tenant`, with a generated ID, for each of `rows`
+    ///
+    /// Each row is checked as `POST /tasks` checks a task, duplicate open titles
+    /// included, and its user must exist; a row completed in the future fails.
+    /// `rows` already failed by the reader are reported as they are. Valid rows are
+    /// written [`IMPORT_BATCH_SIZE`] at a time, one transaction per batch,
+    /// so the other rows are imported whatever fails. With `all_or_nothing`, nothing
+    /// is written if any row fails, and otherwise every row in one transaction.
+    ///
+    /// # Errors
+    /// `Validation` if there are more rows than [`ImportLimits::max_rows`], before
+    /// anything is written. A failed write aborts the import with its error; without
+    /// `all_or_nothing`, the batches committed before it stay written.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        rows: impl IntoIterator<Item = Result<ImportRow, ImportFailure>>,
+        all_or_nothing: bool,
+    ) -> Result<ImportSummary, DomainError> {
+        let max_rows = self.limits.max_rows;
+        let rows: Vec<_> = rows.into_iter().take(max_rows.saturating_add(1)).collect();
+        if rows.len() > max_rows {
+            let reason = format!("must have at most {max_rows} rows");
+            return Err(DomainError::validation("Import", reason));
+        }
+
+        let now = Utc::now();
+        let mut summary = ImportSummary::default();
+        let mut checked = Vec::new();
+        let mut open_titles = OpenTitles::new();
+        for batch in rows.chunks(IMPORT_BATCH_SIZE) {
+            let mut tasks = self.checked(tenant, batch, now, &mut summary.failed).await?;
+            if self.prevent_duplicate_open_tasks {
+                tasks = self.unique(tenant, tasks, &mut open_titles, &mut summary.failed).await?;
+            }
+            let tasks: Vec<Task> = tasks.into_iter().map(|(_, task)| task).collect();
+            if all_or_nothing {
+                checked.extend(tasks);
+            } else if !tasks.is_empty() {
+                self.insert_all(tenant, &tasks).await?;
+                summary.imported += u64::try_from(tasks.len()).unwrap_or_default();
+            }
+        }
+        // Every row is checked before the single transaction begins
+        if all_or_nothing && summary.failed.is_empty() {
+            self.insert_all(tenant, &checked).await?;
+            summary.imported = u64::try_from(checked.len()).unwrap_or_default();
+        }
+        summary.failed.sort_by_key(|failure| failure.line);
+        Ok(summary)
+    }
+
+    /// Write `tasks` in one transaction
+    async fn insert_all(&self, tenant: &TenantId, tasks: &[Task]) -> Result<(), DomainError> {
+        // An early return drops the transaction, rolling back what it wrote
+        let mut transaction = self.unit_of_work.begin().await?;
+        for task in tasks {
+            transaction.insert_task(tenant, task).await?;
+        }
+        transaction.commit().await
+    }
+
+    /// The tasks of the valid rows of `batch` as of `now` with their lines, recording
+    /// the others in `failed`
+    async fn checked(
+        &self,
+        tenant: &TenantId,
+        batch: &[Result<ImportRow, ImportFailure>],
+        now: DateTime<Utc>,
+        failed: &mut Vec<ImportFailure>,
+    ) -> Result<Vec<(u64, Task)>, DomainError> {
+        let mut tasks = Vec::with_capacity(batch.len());
+        for row in batch {
+            match row.as_ref().map_err(Clone::clone).and_then(|row| task_of(row, now)) {
+                Ok(task) => tasks.push(task),
+                Err(failure) => failed.push(failure),
+            }
+        }
+
+        let mut owners: Vec<UserId> =
+            tasks.iter().map(|(_, task)| task.user_id().clone()).collect();
+        owners.sort_unstable_by(|a, b| a.value().cmp(b.value()));
+        owners.dedup();
+        let found = self.user_repository.find_by_ids(tenant, &owners).await?;
+        let found: HashSet<&str> = found.iter().map(|user| user.id().value()).collect();
+        Ok(tasks
+            .into_iter()
+            .filter_map(|(line, task)| {
+                if found.contains(task.user_id().value()) {
+                    return Some((line, task));
+                }
+                let reason = DomainError::not_found(UserId::entity_name()).to_string();
+                failed.push(ImportFailure { line, reason });
+                None
+            })
+            .collect())
+    }
+
+    /// The open tasks of `tasks` whose title matches no open task of their user,
+    /// stored or in `open_titles`, and the completed ones, recording the others in
+    /// `failed`; adds the open tasks kept to `open_titles`
+    async fn unique(
+        &self,
+        tenant: &TenantId,
+        tasks: Vec<(u64, Task)>,
+        open_titles: &mut OpenTitles,
+        failed: &mut Vec<ImportFailure>,
+    ) -> Result<Vec<(u64, Task)>, DomainError> {
+        let mut unique = Vec::with_capacity(tasks.len());
+        for (line, task) in tasks {
+            if task.is_completed() {
+                unique.push((line, task));
+                continue;
+            }
+            let key = (task.user_id().value().to_owned(), task.title().to_lowercase());
+            let existing = match open_titles.get(&key) {
+                Some(earlier) => Some(format!("line {earlier}")),
+                None => self
+                    .task_repository
+                    .exists_open_with_title(tenant, task.user_id(), task.title())
+                    .await?
+                    .map(|id| id.value().to_owned()),
+            };
+            if let Some(existing) = existing {
+                let reason = DomainError::already_exists_as("Open task", "title", &existing);
+                failed.push(ImportFailure { line, reason: reason.to_string() });
+                continue;
+            }
+            open_titles.insert(key, line);
+            unique.push((line, task));
+        }
+        Ok(unique)
+    }
+}
+
+/// The task of `row` as of `now`, with the row's line, or why it is invalid
+fn task_of(row: &ImportRow, now: DateTime<Utc>) -> Result<(u64, Task), ImportFailure> {
+    let failure = |reason: String| ImportFailure { line: row.line, reason };
+    if !row.due_date.trim().is_empty() {
+        return Err(failure(NO_DUE_DATE.to_owned()));
+    }
+    UserId::new(row.user_id.trim())
+        .and_then(|user_id| {
+            let (id, description) = (TaskId::generate(), row.description.clone());
+            match row.completed_at {
+                Some(at) => Task::new_completed(id, user_id, &row.title, description, at, now),
+                None => Task::new(id, user_id, &row.title, description),
+            }
+        })
+        .map(|task| (row.line, task))
+        .map_err(|e| failure(e.to_string()))
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::{TaskRepository, Transaction};
+    use crate::features::task::infrastructure::{InMemoryTaskRepository, InMemoryUnitOfWork};
+    use crate::features::user::domain::User;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const LIMITS: ImportLimits = ImportLimits { max_rows: 1_000, max_bytes: 1 << 20 };
+
+    /// Unit of work counting the transactions it began
+    struct CountingUnitOfWork {
+        inner: InMemoryUnitOfWork,
+        begun: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl UnitOfWork for CountingUnitOfWork {
+        async fn begin(&self) -> Result<Box<dyn Transaction + '_>, DomainError> {
+            self.begun.fetch_add(1, Ordering::Relaxed);
+            self.inner.begin().await
+        }
+    }
+
+    struct Fixture {
+        tasks: Arc<InMemoryTaskRepository>,
+        unit_of_work: Arc<CountingUnitOfWork>,
+        use_case: ImportTasksUseCase,
+        alice: User,
+    }
+
+    impl Fixture {
+        async fn new(limits: ImportLimits) -> Self {
+            Self::with(limits, false).await
+        }
+
+        /// A fixture whose use case fails rows duplicating an open title
+        async fn preventing_duplicates() -> Self {
+            Self::with(LIMITS, true).await
+        }
+
+        async fn with(limits: ImportLimits, prevent_duplicate_open_tasks: bool) -> Self {
+            let users = Arc::new(InMemoryUserRepository::default());
+            let tasks = Arc::new(InMemoryTaskRepository::default());
+            let alice = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+                .expect("valid user");
+            users.insert(&TenantId::default(), &alice).await.expect("insert user");
+            let unit_of_work = Arc::new(CountingUnitOfWork {
+                inner: InMemoryUnitOfWork::new(Arc::clone(&users), Arc::clone(&tasks)),
+                begun: AtomicUsize::new(0),
+            });
+            let use_case = ImportTasksUseCase::new(
+                Arc::clone(&tasks) as _,
+                users as _,
+                Arc::clone(&unit_of_work) as _,
+                limits,
+                prevent_duplicate_open_tasks,
+            );
+            Self { tasks, unit_of_work, use_case, alice }
+        }
+
+        /// A valid row of Alice's on `line`
+        fn row(&self, line: u64) -> ImportRow {
+            ImportRow {
+                line,
+                user_id: self.alice.id().value().to_owned(),
+                title: format!("Task {line}"),
+                ..ImportRow::default()
+            }
+        }
+
+        /// Rows 2 to 5: valid but for an unknown user on line 3 and a due date on line 5
+        fn rows(&self) -> Vec<Result<ImportRow, ImportFailure>> {
+            let mut rows: Vec<_> = (2..=5).map(|line| self.row(line)).collect();
+            rows[1].user_id = "alice@example".into();
+            rows[3].due_date = "2026-12-31".into();
+            rows.into_iter().map(Ok).collect()
+        }
+
+        async fn stored(&self) -> usize {
+            let tasks = self.tasks.find_all_unbounded(&TenantId::default()).await;
+            tasks.expect("tasks").len()
+        }
+
+        fn begun(&self) -> usize {
+            self.unit_of_work.begun.load(Ordering::Relaxed)
+        }
+    }
+
+    fn lines(summary: &ImportSummary) -> Vec<u64> {
+        summary.failed.iter().map(|failure| failure.line).collect()
+    }
+
+    #[tokio::test]
+    async fn execute_should_import_the_valid_rows_and_report_the_others() {
+        let fixture = Fixture::new(LIMITS).await;
+        let mut rows = fixture.rows();
+        rows.push(Err(ImportFailure { line: 6, reason: "unreadable".into() }));
+
+        let summary = fixture.use_case.execute(&TenantId::default(), rows, false).await;
+        let summary = summary.expect("imported");
+        assert_eq!((summary.imported, lines(&summary)), (2, vec![3, 5, 6]));
+        assert_eq!(summary.failed[0].reason, "Not found: User not found");
+        assert_eq!(summary.failed[1].reason, NO_DUE_DATE);
+        assert_eq!(fixture.stored().await, 2);
+    }
+
+    #[tokio::test]
+    async fn execute_should_import_nothing_when_all_or_nothing_and_a_row_fails() {
+        let fixture = Fixture::new(LIMITS).await;
+
+        let summary = fixture.use_case.execute(&TenantId::default(), fixture.rows(), true).await;
+        let summary = summary.expect("checked");
+        assert_eq!((summary.imported, lines(&summary)), (0, vec![3, 5]));
+        assert_eq!(fixture.stored().await, 0);
+    }
+
+    #[tokio::test]
+    async fn execute_should_write_a_transaction_per_batch_unless_all_or_nothing() {
+        let fixture = Fixture::new(LIMITS).await;
+        let rows = || (0..250).map(|line| Ok(fixture.row(line + 2)));
+        let tenant = TenantId::default();
+
+        let summary = fixture.use_case.execute(&tenant, rows(), false).await.expect("imported");
+        assert_eq!((summary.imported, fixture.begun()), (250, 3));
+        let summary = fixture.use_case.execute(&tenant, rows(), true).await.expect("imported");
+        assert_eq!((summary.imported, fixture.begun()), (250, 4));
+        assert_eq!(fixture.stored().await, 500);
+    }
+
+    #[tokio::test]
+    async fn execute_should_import_completed_rows_completed_unless_in_the_future() {
+        let fixture = Fixture::new(LIMITS).await;
+        let now = Utc::now();
+        let mut rows: Vec<_> = (2..=4).map(|line| fixture.row(line)).collect();
+        rows[0].completed_at = Some(now - chrono::TimeDelta::days(30));
+        rows[1].completed_at = Some(now + chrono::TimeDelta::days(1));
+
+        let rows = rows.into_iter().map(Ok);
+        let summary = fixture.use_case.execute(&TenantId::default(), rows, false).await;
+        let summary = summary.expect("imported");
+        assert_eq!((summary.imported, lines(&summary)), (2, vec![3]));
+        let reason = "Validation error: Completion time cannot be in the future";
+        assert_eq!(summary.failed[0].reason, reason);
+        let tasks = fixture.tasks.find_all_unbounded(&TenantId::default()).await.expect("tasks");
+        let mut completed: Vec<_> = tasks.iter().map(|t| (t.title(), t.is_completed())).collect();
+        completed.sort_unstable();
+        assert_eq!(completed, [("Task 2", true), ("Task 4", false)]);
+    }
+
+    #[tokio::test]
+    async fn execute_should_fail_rows_duplicating_a_stored_open_title_when_prevented() {
+        let fixture = Fixture::preventing_duplicates().await;
+        let tenant = TenantId::default();
+        let alice = fixture.alice.id().clone();
+        let stored =
+            Task::new(TaskId::generate(), alice, "Task 2", String::new()).expect("valid task");
+        fixture.tasks.insert(&tenant, &stored).await.expect("insert");
+        let mut rows: Vec<_> = (2..=4).map(|line| fixture.row(line)).collect();
+        rows[1].title = "TASK 2".into();
+        rows[2].title = "Task 2".into();
+        rows[2].completed_at = Some(Utc::now() - chrono::TimeDelta::days(1));
+
+        let summary = fixture.use_case.execute(&tenant, rows.into_iter().map(Ok), false).await;
+        let summary = summary.expect("imported");
+        assert_eq!((summary.imported, lines(&summary)), (1, vec![2, 3]));
+        let reason = format!(
+            "Already exists: Open task with this title already exists: {}",
+            stored.id().value()
+        );
+        assert_eq!(summary.failed[0].reason, reason);
+        assert_eq!(fixture.stored().await, 2, "the completed row is no duplicate");
+    }
+
+    #[tokio::test]
+    async fn execute_should_fail_rows_duplicating_an_earlier_open_row_when_prevented() {
+        let fixture = Fixture::preventing_duplicates().await;
+        let tenant = TenantId::default();
+        // Line 250 lands in the batch after line 2's
+        let mut rows: Vec<_> = (2..=250).map(|line| fixture.row(line)).collect();
+        rows[248].title = "task 2".into();
+
+        for all_or_nothing in [true, false] {
+            let rows = rows.iter().cloned().map(Ok);
+            let summary = fixture.use_case.execute(&tenant, rows, all_or_nothing).await;
+            let summary = summary.expect("checked");
+            assert_eq!(lines(&summary), vec![250], "all_or_nothing: {all_or_nothing}");
+            let reason = "Already exists: Open task with this title already exists: line 2";
+            assert_eq!(summary.failed[0].reason, reason);
+        }
+        assert_eq!(fixture.stored().await, 248);
+    }
+
+    #[tokio::test]
+    async fn execute_should_reject_more_rows_than_the_limit_before_writing() {
+        let fixture = Fixture::new(ImportLimits { max_rows: 2, ..LIMITS }).await;
+        let rows = (2..=4).map(|line| Ok(fixture.row(line)));
+
+        let result = fixture.use_case.execute(&TenantId::default(), rows, false).await;
+        assert!(matches!(result, Err(DomainError::Validation(m)) if m.contains("at most 2 rows")));
+        assert_eq!((fixture.stored().await, fixture.begun()), (0, 0));
+    }
+}
@@ -0,0 +1,182 @@
+//! List tasks with their owners embedded (read model spanning task and user)
+
+use crate::features::task::application::{ListTasksUseCase, TaskListQuery};
+use crate::features::task::domain::{Task, TaskRepository};
+use crate::features::user::application::GetUsersByIdsUseCase;
+use crate::features::user::domain::{User, UserRepository};
+use crate::shared::application::{PageLimits, PageRequest, Validated};
+use crate::shared::domain::{DomainError, Entity, TenantId, UserId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A task together with the user owning it
+#[derive(Debug)]
+pub struct TaskWithOwner {
+    /// The task
+    pub task: Task,
+    /// The owning user; `None` only if the user vanished between the two queries
+    pub owner: Option<User>,
+}
+
+/// Read-model service listing tasks with their owners, loading all owners in one query
+pub struct ListTasksWithOwnersUseCase<T: ?Sized = dyn TaskRepository, U: ?Sized = dyn UserRepository> {
+    list_tasks: ListTasksUseCase<T>,
+    get_users_by_ids: GetUsersByIdsUseCase<U>,
+}
+
+impl<T: TaskRepository + ?Sized, U: UserRepository + ?Sized> ListTasksWithOwnersUseCase<T, U> {
+    /// Create a new use case instance returning pages within `limits`
+    pub fn new(
+        task_repository: Arc<T>,
+        user_repository: Arc<U>,
+        limits: PageLimits,
+    ) -> Self {
+        Self {
+            list_tasks: ListTasksUseCase::new(task_repository, limits),
+            get_users_by_ids: GetUsersByIdsUseCase::new(user_repository),
+        }
+    }
+
+    /// List the requested page of the tasks of `tenant` matching `query` with their
+    /// owners
+    ///
+    /// # Errors
+    /// `Validation` for a page outside the limits; repository failures.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        query: Validated<TaskListQuery>,
+        page: PageRequest,
+    ) -> Result<Vec<TaskWithOwner>, DomainError> {
+        let tasks = self.list_tasks.execute(tenant, query, page).await?;
+
+        let mut owner_ids: Vec<String> =
+            tasks.iter().map(|t| t.user_id().value().to_owned()).collect();
+        owner_ids.sort();
+        owner_ids.dedup();
+        let owners: HashMap<UserId, User> = self
+            .get_users_by_ids
+            .execute(tenant, &owner_ids)
+            .await?
+            .into_iter()
+            .map(|u| (u.id().clone(), u))
+            .collect();
+
+        Ok(tasks
+            .into_iter()
+            .map(|task| {
+                let owner = owners.get(task.user_id()).cloned();
+                TaskWithOwner { task, owner }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::TaskId;
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::features::user::domain::{SyncWatermark, UserTransaction};
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::Page;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts `find_by_ids` calls on top of an in-memory repository
+    #[derive(Default)]
+    struct CountingUserRepository {
+        inner: InMemoryUserRepository,
+        batch_calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl UserRepository for CountingUserRepository {
+        async fn find_by_id(
+            &self,
+            tenant: &TenantId,
+            id: &UserId,
+        ) -> Result<Option<User>, DomainError> {
+            self.inner.find_by_id(tenant, id).await
+        }
+        async fn find_by_ids(
+            &self,
+            tenant: &TenantId,
+            ids: &[UserId],
+        ) -> Result<Vec<User>, DomainError> {
+            self.batch_calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.find_by_ids(tenant, ids).await
+        }
+        async fn find_page(
+            &self,
+            tenant: &TenantId,
+            email_domain: Option<&str>,
+            page: Page,
+        ) -> Result<Vec<User>, DomainError> {
+            self.inner.find_page(tenant, email_domain, page).await
+        }
+        async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<User>, DomainError> {
+            self.inner.find_all_unbounded(tenant).await
+        }
+        async fn find_updated_since(
+            &self,
+            tenant: &TenantId,
+            since: &SyncWatermark,
+            limit: u32,
+        ) -> Result<Vec<User>, DomainError> {
+            self.inner.find_updated_since(tenant, since, limit).await
+        }
+        async fn insert(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+            self.inner.insert(tenant, user).await
+        }
+        async fn update(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+            self.inner.update(tenant, user).await
+        }
+        async fn delete(&self, tenant: &TenantId, id: &UserId) -> Result<bool, DomainError> {
+            self.inner.delete(tenant, id).await
+        }
+        async fn delete_returning(
+            &self,
+            tenant: &TenantId,
+            id: &UserId,
+        ) -> Result<Option<User>, DomainError> {
+            self.inner.delete_returning(tenant, id).await
+        }
+        async fn begin(&self) -> Result<Box<dyn UserTransaction + '_>, DomainError> {
+            self.inner.begin().await
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_should_embed_owners_with_a_single_batch_lookup() {
+        let users = Arc::new(CountingUserRepository::default());
+        let tasks = Arc::new(InMemoryTaskRepository::default());
+        let tenant = TenantId::default();
+        let mut expected = Vec::new();
+        for name in ["alice", "bob"] {
+            let user =
+                User::new(UserId::generate(), name.to_owned(), &format!("{name}@example.com"))
+                    .expect("valid user");
+            users.insert(&tenant, &user).await.expect("insert user");
+            for title in ["one", "two"] {
+                let task = Task::new(TaskId::generate(), user.id().clone(), title, String::new())
+                    .expect("valid task");
+                tasks.insert(&tenant, &task).await.expect("insert task");
+                expected.push((task.id().clone(), name));
+            }
+        }
+
+        let use_case =
+            ListTasksWithOwnersUseCase::new(tasks, Arc::clone(&users) as _, PageLimits::default());
+        let query = Validated::new(TaskListQuery::default()).expect("valid query");
+        let result = use_case.execute(&tenant, query, PageRequest::default()).await.expect("list");
+
+        assert_eq!(users.batch_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(result.len(), expected.len());
+        for item in result {
+            let (_, name) =
+                expected.iter().find(|(id, _)| id == item.task.id()).expect("known task");
+            assert_eq!(item.owner.map(|u| u.name().to_owned()).as_deref(), Some(*name));
+        }
+    }
+}
@@ -1,11 +1,48 @@
 //! Task application layer
 
+pub mod attachments;
+pub mod checklist;
 pub mod complete_task;
 pub mod create_task;
 pub mod delete_task;
 pub mod get_task;
+pub mod import_tasks;
+pub mod list_tasks_with_owners;
+pub mod onboard_user;
+pub mod reassign_tasks;
+pub mod render_description;
+pub mod task_stats;
+pub mod task_view_preferences;
+pub mod upsert_task;
+pub mod user_overview;
 
+pub use attachments::{
+    CreateAttachmentCommand, CreateAttachmentUseCase, DeleteAttachmentUseCase,
+    ListAttachmentsUseCase,
+};
+pub use checklist::{
+    AddChecklistItemCommand, AddChecklistItemUseCase, RemoveChecklistItemUseCase,
+    ToggleChecklistItemUseCase,
+};
 pub use complete_task::CompleteTaskUseCase;
 pub use create_task::{CreateTaskCommand, CreateTaskUseCase};
 pub use delete_task::DeleteTaskUseCase;
-pub use get_task::{GetTaskUseCase, ListTasksUseCase};
+pub use get_task::{GetTaskUseCase, ListTasksUseCase, TaskListQuery, MIN_SEARCH_LENGTH};
+pub use import_tasks::{
+    ImportFailure, ImportLimits, ImportRow, ImportSummary, ImportTasksUseCase, IMPORT_BATCH_SIZE,
+};
+pub use list_tasks_with_owners::{ListTasksWithOwnersUseCase, TaskWithOwner};
+pub use onboard_user::{OnboardUserCommand, OnboardUserUseCase, OnboardedUser, USER_ONBOARDED};
+pub use reassign_tasks::{ReassignTasksCommand, ReassignTasksUseCase, TASKS_REASSIGNED};
+pub use render_description::{
+    DescriptionRenderer, RenderTaskDescriptionUseCase, MAX_RENDERED_DESCRIPTION_LEN,
+};
+pub use task_stats::{TaskStatsQuery, STATS_CACHE_TTL};
+pub use task_view_preferences::{
+    GetTaskViewPreferencesUseCase, ListUserTasksUseCase, SetTaskViewPreferencesCommand,
+    SetTaskViewPreferencesUseCase, UserTaskListQuery,
+};
+pub use upsert_task::{UpsertTaskCommand, UpsertTaskUseCase};
+pub use user_overview::{
+    UserOverviewQuery, UserWithRecentTasks, DEFAULT_RECENT_TASKS, MAX_RECENT_TASKS,
+};
@@ -0,0 +1,359 @@
+//! Onboard user use case: a user and their first task, created atomically
+
+use crate::features::task::domain::entity::normalize_title;
+use crate::features::task::domain::{Task, TaskId, UnitOfWork};
+use crate::features::user::application::create_user::{check_profile, welcome};
+use crate::features::user::domain::User;
+use crate::shared::application::{Validate, Validated, ValidationErrors};
+use crate::shared::domain::{
+    validation_message, AuditEntry, DomainError, DomainWarning, EmailSender, Entity,
+    OutboxEvent, TenantId, UserId, CANNOT_BE_EMPTY,
+};
+use crate::shared::events::{EventPayload, UserOnboardedV1};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Audit action and event type of an onboarding; the subject is the new user
+pub const USER_ONBOARDED: &str = UserOnboardedV1::SCHEMA.event_type;
+
+/// Command to create a user together with their first task
+#[derive(Debug)]
+pub struct OnboardUserCommand {
+    /// User name
+    pub name: String,
+    /// User email
+    pub email: String,
+    /// Title of the user's first task
+    pub task_title: String,
+    /// Description of the user's first task
+    pub task_description: String,
+    /// Address of the client requesting it, recorded in the audit trail
+    pub client_ip: Option<IpAddr>,
+}
+
+impl Validate for OnboardUserCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        check_profile(&mut errors, &self.name, &self.email);
+        if normalize_title(&self.task_title).is_empty() {
+            errors.add("initial_task.title", validation_message("Title", CANNOT_BE_EMPTY));
+        }
+        errors.into_result()
+    }
+}
+
+/// A user created with their first task
+#[derive(Debug)]
+pub struct OnboardedUser {
+    /// The new user
+    pub user: User,
+    /// The user's first task, as persisted
+    pub task: Task,
+    /// Soft rules broken by the task's input
+    pub warnings: Vec<DomainWarning>,
+}
+
+/// Use case creating a user and their first task in one unit of work, so that no
+/// user is left without it
+///
+/// ```
+/// use axum_ddd_template::features::task::application::{
+///     OnboardUserCommand, OnboardUserUseCase,
+/// };
+/// use axum_ddd_template::features::task::infrastructure::{
+///     InMemoryTaskRepository, InMemoryUnitOfWork,
+/// };
+/// use axum_ddd_template::features::user::infrastructure::InMemoryUserRepository;
+/// use axum_ddd_template::shared::application::Validated;
+/// use axum_ddd_template::shared::domain::{DomainError, Entity, TenantId};
+/// use axum_ddd_template::shared::infrastructure::email::ConsoleEmailSender;
+/// use std::sync::Arc;
+///
+/// let users = Arc::new(InMemoryUserRepository::default());
+/// let tasks = Arc::new(InMemoryTaskRepository::default());
+/// let unit_of_work = Arc::new(InMemoryUnitOfWork::new(users, tasks));
+/// let onboard = OnboardUserUseCase::new(unit_of_work, Arc::new(ConsoleEmailSender));
+/// let command = Validated::new(OnboardUserCommand {
+///     name: "Alice".into(),
+///     email: "alice@example.com".into(),
+///     task_title: "Getting started".into(),
+///     task_description: String::new(),
+///     client_ip: None,
+/// })
+/// .map_err(DomainError::from)?;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+/// let onboarded = runtime.block_on(onboard.execute(&TenantId::default(), command))?;
+/// assert_eq!(onboarded.task.user_id(), onboarded.user.id());
+/// # anyhow::Ok(())
+/// ```
+pub struct OnboardUserUseCase {
+    unit_of_work: Arc<dyn UnitOfWork>,
+    email_sender: Arc<dyn EmailSender>,
+}
+
+impl OnboardUserUseCase {
+    /// Create a new use case instance writing through `unit_of_work` and welcoming
+    /// new users through `email_sender`
+    pub fn new(unit_of_work: Arc<dyn UnitOfWork>, email_sender: Arc<dyn EmailSender>) -> Self {
+        Self { unit_of_work, email_sender }
+    }
+
+    /// Create a user of `tenant` and their first task, both with generated IDs, then
+    /// welcome the user by email as [`CreateUserUseCase`] does
+    ///
+    /// Both aggregates are built before anything is written, and written in one
+    /// transaction: when either breaks a rule or fails to be written, neither exists.
+    /// The transaction records both, as snapshots, in a [`USER_ONBOARDED`] audit entry
+    /// and raises the event of that type.
+    ///
+    /// [`CreateUserUseCase`]: crate::features::user::application::CreateUserUseCase
+    ///
+    /// # Errors
+    /// `AlreadyExists` if the email is taken within the tenant.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        command: Validated<OnboardUserCommand>,
+    ) -> Result<OnboardedUser, DomainError> {
+        let command = command.into_inner();
+        let user = User::new(UserId::generate(), command.name, &command.email)?;
+        let (task, warnings) = Task::new_with_warnings(
+            TaskId::generate(),
+            user.id().clone(),
+            &command.task_title,
+            command.task_description,
+        )?;
+
+        // An early return drops the transaction, rolling back what it wrote
+        let mut transaction = self.unit_of_work.begin().await?;
+        transaction.insert_user(tenant, &user).await?;
+        let task = transaction.insert_task(tenant, &task).await?;
+        let entry = AuditEntry {
+            action: USER_ONBOARDED,
+            subject: user.id().value().to_owned(),
+            details: serde_json::json!({
+                "user": user.to_snapshot(),
+                "task": task.to_snapshot(),
+            }),
+            client_ip: command.client_ip,
+        };
+        transaction.record_audit(tenant, &entry).await?;
+        let payload = UserOnboardedV1 {
+            name: user.name().to_owned(),
+            email: user.email().value().to_owned(),
+            task_id: task.id().value().to_owned(),
+        };
+        let event = OutboxEvent::new(entry.subject, &payload)?;
+        transaction.record_event(tenant, &event).await?;
+        transaction.commit().await?;
+
+        welcome(&self.email_sender, &user);
+        Ok(OnboardedUser { user, task, warnings })
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::{TaskRepository, Transaction};
+    use crate::shared::domain::AuditEntry;
+    use crate::features::task::infrastructure::{InMemoryTaskRepository, InMemoryUnitOfWork};
+    use crate::features::user::application::create_user::WELCOME_SUBJECT;
+    use crate::features::user::domain::UserRepository;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::testing::RecordingEmailSender;
+
+    /// Unit of work whose task inserts fail after the user insert succeeded
+    struct FailingTaskInserts(InMemoryUnitOfWork);
+
+    #[async_trait::async_trait]
+    impl UnitOfWork for FailingTaskInserts {
+        async fn begin(&self) -> Result<Box<dyn Transaction + '_>, DomainError> {
+            Ok(Box::new(FailingTaskInsert(self.0.begin().await?)))
+        }
+    }
+
+    struct FailingTaskInsert<'a>(Box<dyn Transaction + 'a>);
+
+    #[async_trait::async_trait]
+    impl Transaction for FailingTaskInsert<'_> {
+        async fn insert_user(
+            &mut self,
+            tenant: &TenantId,
+            user: &User,
+        ) -> Result<(), DomainError> {
+            self.0.insert_user(tenant, user).await
+        }
+
+        async fn find_task_for_update(
+            &mut self,
+            tenant: &TenantId,
+            id: &TaskId,
+        ) -> Result<Option<Task>, DomainError> {
+            self.0.find_task_for_update(tenant, id).await
+        }
+
+        async fn update_task(
+            &mut self,
+            tenant: &TenantId,
+            task: &Task,
+        ) -> Result<Task, DomainError> {
+            self.0.update_task(tenant, task).await
+        }
+
+        async fn insert_task(&mut self, _: &TenantId, _: &Task) -> Result<Task, DomainError> {
+            Err(DomainError::Infrastructure("disk full".into()))
+        }
+
+        async fn reassign_tasks(
+            &mut self,
+            tenant: &TenantId,
+            from: &UserId,
+            to: &UserId,
+            only_open: bool,
+        ) -> Result<u64, DomainError> {
+            self.0.reassign_tasks(tenant, from, to, only_open).await
+        }
+
+        async fn record_audit(
+            &mut self,
+            tenant: &TenantId,
+            entry: &AuditEntry,
+        ) -> Result<(), DomainError> {
+            self.0.record_audit(tenant, entry).await
+        }
+
+        async fn record_event(
+            &mut self,
+            tenant: &TenantId,
+            event: &OutboxEvent,
+        ) -> Result<(), DomainError> {
+            self.0.record_event(tenant, event).await
+        }
+
+        async fn commit(self: Box<Self>) -> Result<(), DomainError> {
+            self.0.commit().await
+        }
+    }
+
+    fn command(email: &str, task_title: &str) -> OnboardUserCommand {
+        OnboardUserCommand {
+            name: "Alice".into(),
+            email: email.into(),
+            task_title: task_title.into(),
+            task_description: String::new(),
+            client_ip: None,
+        }
+    }
+
+    struct Fixture {
+        users: Arc<InMemoryUserRepository>,
+        tasks: Arc<InMemoryTaskRepository>,
+        sender: Arc<RecordingEmailSender>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                users: Arc::new(InMemoryUserRepository::default()),
+                tasks: Arc::new(InMemoryTaskRepository::default()),
+                sender: Arc::new(RecordingEmailSender::default()),
+            }
+        }
+
+        fn unit_of_work(&self) -> InMemoryUnitOfWork {
+            InMemoryUnitOfWork::new(Arc::clone(&self.users), Arc::clone(&self.tasks))
+        }
+
+        fn use_case(&self, unit_of_work: impl UnitOfWork + 'static) -> OnboardUserUseCase {
+            OnboardUserUseCase::new(Arc::new(unit_of_work), Arc::clone(&self.sender) as _)
+        }
+
+        async fn counts(&self) -> (usize, usize) {
+            let tenant = TenantId::default();
+            let users = self.users.find_all_unbounded(&tenant).await.expect("users");
+            let tasks = self.tasks.find_all_unbounded(&tenant).await.expect("tasks");
+            (users.len(), tasks.len())
+        }
+    }
+
+    #[test]
+    fn validate_should_report_every_invalid_field_of_both_aggregates() {
+        let mut invalid = command("invalid", " ");
+        invalid.name = String::new();
+        let errors = invalid.validate().err().unwrap_or_default();
+        let fields: Vec<_> = errors.fields().iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["name", "email", "initial_task.title"]);
+        assert!(command("alice@example.com", "Getting started").validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn execute_should_create_the_user_with_their_task_and_welcome_them() {
+        let fixture = Fixture::new();
+        let use_case = fixture.use_case(fixture.unit_of_work());
+        let command = Validated::new(command("alice@example.com", "Getting started"))
+            .expect("valid command");
+
+        let onboarded = use_case.execute(&TenantId::default(), command).await.expect("onboarded");
+        assert_eq!(onboarded.task.user_id(), onboarded.user.id());
+        assert!(onboarded.task.updated_at().is_some(), "returned as persisted");
+        assert_eq!(fixture.counts().await, (1, 1));
+        let sent = fixture.sender.wait_for(1).await;
+        assert_eq!(sent[0].subject, WELCOME_SUBJECT);
+    }
+
+    #[tokio::test]
+    async fn execute_should_audit_snapshots_of_both_aggregates() {
+        let fixture = Fixture::new();
+        let unit_of_work = Arc::new(fixture.unit_of_work());
+        let use_case = OnboardUserUseCase::new(
+            Arc::clone(&unit_of_work) as _,
+            Arc::clone(&fixture.sender) as _,
+        );
+        let mut command = command("alice@example.com", "Getting started");
+        command.client_ip = Some([203, 0, 113, 5].into());
+        let tenant = TenantId::default();
+
+        let onboarded = use_case.execute(&tenant, Validated::new(command).expect("valid command"));
+        let onboarded = onboarded.await.expect("onboarded");
+        let trail = unit_of_work.audit_trail(&tenant).await;
+        let [entry] = trail.as_slice() else { panic!("one audit entry: {trail:?}") };
+        assert_eq!(entry.action, USER_ONBOARDED);
+        assert_eq!(entry.subject, onboarded.user.id().value());
+        assert_eq!(entry.client_ip, Some([203, 0, 113, 5].into()));
+        let user = User::from_snapshot(entry.details["user"].clone()).expect("user snapshot");
+        assert_eq!(user.email(), onboarded.user.email());
+        let task = Task::from_snapshot(entry.details["task"].clone()).expect("task snapshot");
+        assert_eq!(task.id(), onboarded.task.id());
+    }
+
+    #[tokio::test]
+    async fn execute_should_roll_back_the_user_when_the_task_insert_fails() {
+        let fixture = Fixture::new();
+        let use_case = fixture.use_case(FailingTaskInserts(fixture.unit_of_work()));
+        let command = Validated::new(command("alice@example.com", "Getting started"))
+            .expect("valid command");
+
+        let result = use_case.execute(&TenantId::default(), command).await;
+        assert!(matches!(result, Err(DomainError::Infrastructure(_))), "{result:?}");
+        assert_eq!(fixture.counts().await, (0, 0));
+        tokio::task::yield_now().await;
+        assert_eq!(fixture.sender.attempts(), 0, "no welcome without an account");
+    }
+
+    #[tokio::test]
+    async fn execute_should_write_nothing_when_the_email_is_taken() {
+        let fixture = Fixture::new();
+        let use_case = fixture.use_case(fixture.unit_of_work());
+        let tenant = TenantId::default();
+        let first = Validated::new(command("alice@example.com", "First")).expect("valid command");
+        use_case.execute(&tenant, first).await.expect("onboarded");
+
+        let again = Validated::new(command("alice@example.com", "Again")).expect("valid command");
+        let result = use_case.execute(&tenant, again).await;
+        assert!(matches!(result, Err(DomainError::AlreadyExists(_))), "{result:?}");
+        assert_eq!(fixture.counts().await, (1, 1));
+    }
+}
@@ -0,0 +1,334 @@
+//! Reassign tasks use case: moving the tasks of a user to another user
+
+use crate::features::task::domain::{TaskRepository, UnitOfWork};
+use crate::features::user::domain::UserRepository;
+use crate::shared::application::{Validate, Validated, ValidationErrors};
+use crate::shared::domain::{
+    validation_message, AuditEntry, DomainError, OutboxEvent, TenantId, UserId,
+};
+use crate::shared::events::{EventPayload, TasksReassignedV1};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Audit action and event type of a reassignment; the subject is the user the tasks
+/// were taken from
+pub const TASKS_REASSIGNED: &str = TasksReassignedV1::SCHEMA.event_type;
+
+/// Command to move the tasks of one user to another
+#[derive(Debug)]
+pub struct ReassignTasksCommand {
+    /// ID of the user whose tasks move
+    pub from_user_id: String,
+    /// ID of the user receiving them
+    pub to_user_id: String,
+    /// Move open tasks only, leaving completed ones with their owner
+    pub only_open: bool,
+    /// Address of the client requesting it, recorded in the audit trail
+    pub client_ip: Option<IpAddr>,
+}
+
+impl Validate for ReassignTasksCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check("to_user_id", UserId::new(&self.to_user_id));
+        if self.to_user_id == self.from_user_id {
+            let reason = "must differ from the user whose tasks are reassigned";
+            errors.add("to_user_id", validation_message("Target user", reason));
+        }
+        errors.into_result()
+    }
+}
+
+/// Use case moving the tasks of a user to another, e.g. when someone leaves the team
+pub struct ReassignTasksUseCase<T: ?Sized = dyn TaskRepository, U: ?Sized = dyn UserRepository> {
+    task_repository: Arc<T>,
+    user_repository: Arc<U>,
+    unit_of_work: Arc<dyn UnitOfWork>,
+}
+
+impl<T: TaskRepository + ?Sized, U: UserRepository + ?Sized> ReassignTasksUseCase<T, U> {
+    /// Create a new use case instance reassigning through `unit_of_work`, then
+    /// letting `task_repository` forget the tasks that moved
+    pub fn new(
+        task_repository: Arc<T>,
+        user_repository: Arc<U>,
+        unit_of_work: Arc<dyn UnitOfWork>,
+    ) -> Self {
+        Self { task_repository, user_repository, unit_of_work }
+    }
+
+    /// Move the tasks of `tenant` owned by the command's source user to its target
+    /// user, returning how many moved
+    ///
+    /// The tasks move in a single write, recorded in the audit trail as
+    /// [`TASKS_REASSIGNED`] in the same transaction, which also raises the event of
+    /// that type.
+    ///
+    /// # Errors
+    /// `NotFound` if either user doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        command: Validated<ReassignTasksCommand>,
+    ) -> Result<u64, DomainError> {
+        let command = command.into_inner();
+        let from = UserId::new(&command.from_user_id)?;
+        let to = UserId::new(&command.to_user_id)?;
+        for id in [&from, &to] {
+            if self.user_repository.find_by_id(tenant, id).await?.is_none() {
+                return Err(DomainError::not_found(UserId::entity_name()));
+            }
+        }
+
+        let mut transaction = self.unit_of_work.begin().await?;
+        let reassigned = transaction.reassign_tasks(tenant, &from, &to, command.only_open).await?;
+        let entry = AuditEntry {
+            action: TASKS_REASSIGNED,
+            subject: from.value().to_owned(),
+            details: serde_json::json!({
+                "to_user_id": to.value(),
+                "only_open": command.only_open,
+                "reassigned": reassigned,
+            }),
+            client_ip: command.client_ip,
+        };
+        transaction.record_audit(tenant, &entry).await?;
+        let payload = TasksReassignedV1 {
+            to_user_id: to.value().to_owned(),
+            only_open: command.only_open,
+            reassigned,
+        };
+        let event = OutboxEvent::new(entry.subject, &payload)?;
+        transaction.record_event(tenant, &event).await?;
+        transaction.commit().await?;
+        // The unit of work bypassed the task repository, which may cache the tasks
+        self.task_repository.tasks_reassigned(tenant, &from).await?;
+        Ok(reassigned)
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::{Task, TaskId, TaskRepository, Transaction};
+    use crate::features::task::infrastructure::{
+        CachedTaskRepository, InMemoryTaskRepository, InMemoryUnitOfWork,
+    };
+    use crate::features::user::domain::User;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::Entity;
+
+    /// Unit of work whose audit writes fail after the tasks moved
+    struct FailingAudit(InMemoryUnitOfWork);
+
+    #[async_trait::async_trait]
+    impl UnitOfWork for FailingAudit {
+        async fn begin(&self) -> Result<Box<dyn Transaction + '_>, DomainError> {
+            Ok(Box::new(FailingAuditWrite(self.0.begin().await?)))
+        }
+    }
+
+    struct FailingAuditWrite<'a>(Box<dyn Transaction + 'a>);
+
+    #[async_trait::async_trait]
+    impl Transaction for FailingAuditWrite<'_> {
+        async fn insert_user(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+            self.0.insert_user(tenant, user).await
+        }
+
+        async fn find_task_for_update(
+            &mut self,
+            tenant: &TenantId,
+            id: &TaskId,
+        ) -> Result<Option<Task>, DomainError> {
+            self.0.find_task_for_update(tenant, id).await
+        }
+
+        async fn update_task(
+            &mut self,
+            tenant: &TenantId,
+            task: &Task,
+        ) -> Result<Task, DomainError> {
+            self.0.update_task(tenant, task).await
+        }
+
+        async fn insert_task(
+            &mut self,
+            tenant: &TenantId,
+            task: &Task,
+        ) -> Result<Task, DomainError> {
+            self.0.insert_task(tenant, task).await
+        }
+
+        async fn reassign_tasks(
+            &mut self,
+            tenant: &TenantId,
+            from: &UserId,
+            to: &UserId,
+            only_open: bool,
+        ) -> Result<u64, DomainError> {
+            self.0.reassign_tasks(tenant, from, to, only_open).await
+        }
+
+        async fn record_audit(&mut self, _: &TenantId, _: &AuditEntry) -> Result<(), DomainError> {
+            Err(DomainError::Infrastructure("disk full".into()))
+        }
+
+        async fn record_event(
+            &mut self,
+            tenant: &TenantId,
+            event: &OutboxEvent,
+        ) -> Result<(), DomainError> {
+            self.0.record_event(tenant, event).await
+        }
+
+        async fn commit(self: Box<Self>) -> Result<(), DomainError> {
+            self.0.commit().await
+        }
+    }
+
+    struct Fixture {
+        users: Arc<InMemoryUserRepository>,
+        tasks: Arc<InMemoryTaskRepository>,
+        alice: User,
+        bob: User,
+    }
+
+    impl Fixture {
+        /// Alice with two open tasks and a completed one, Bob without tasks
+        async fn new() -> Self {
+            let tenant = TenantId::default();
+            let users = Arc::new(InMemoryUserRepository::default());
+            let tasks = Arc::new(InMemoryTaskRepository::default());
+            let user = |name: &str, email: &str| {
+                User::new(UserId::generate(), name.into(), email).expect("valid user")
+            };
+            let (alice, bob) = (user("Alice", "alice@example.com"), user("Bob", "bob@example.com"));
+            for user in [&alice, &bob] {
+                users.insert(&tenant, user).await.expect("insert user");
+            }
+            for (title, completed) in [("Write", false), ("Review", false), ("Plan", true)] {
+                let owner = alice.id().clone();
+                let mut task = Task::new(TaskId::generate(), owner, title, String::new())
+                    .expect("valid task");
+                if completed {
+                    task.complete().expect("open task");
+                }
+                tasks.insert(&tenant, &task).await.expect("insert task");
+            }
+            Self { users, tasks, alice, bob }
+        }
+
+        fn unit_of_work(&self) -> InMemoryUnitOfWork {
+            InMemoryUnitOfWork::new(Arc::clone(&self.users), Arc::clone(&self.tasks))
+        }
+
+        fn use_case(&self, unit_of_work: Arc<dyn UnitOfWork>) -> ReassignTasksUseCase {
+            ReassignTasksUseCase::new(
+                Arc::clone(&self.tasks) as _,
+                Arc::clone(&self.users) as _,
+                unit_of_work,
+            )
+        }
+
+        fn command(&self, to: &User, only_open: bool) -> Validated<ReassignTasksCommand> {
+            Validated::new(ReassignTasksCommand {
+                from_user_id: self.alice.id().value().to_owned(),
+                to_user_id: to.id().value().to_owned(),
+                only_open,
+                client_ip: Some([198, 51, 100, 4].into()),
+            })
+            .expect("valid command")
+        }
+
+        /// Number of tasks of Alice and of Bob
+        async fn counts(&self) -> (usize, usize) {
+            let tenant = TenantId::default();
+            let alice = self.tasks.find_by_user_id(&tenant, self.alice.id()).await;
+            let bob = self.tasks.find_by_user_id(&tenant, self.bob.id()).await;
+            (alice.expect("tasks").len(), bob.expect("tasks").len())
+        }
+    }
+
+    #[test]
+    fn validate_should_reject_reassigning_to_the_same_user() {
+        let command = |to: &str| ReassignTasksCommand {
+            from_user_id: "alice".into(),
+            to_user_id: to.into(),
+            only_open: true,
+            client_ip: None,
+        };
+        let errors = command("alice").validate().err().unwrap_or_default();
+        assert_eq!(errors.fields()[0].field, "to_user_id");
+        assert!(command("").validate().is_err());
+        assert!(command("bob").validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn execute_should_move_open_tasks_and_audit_it() {
+        let fixture = Fixture::new().await;
+        let unit_of_work = Arc::new(fixture.unit_of_work());
+        let use_case = fixture.use_case(Arc::clone(&unit_of_work) as _);
+        let tenant = TenantId::default();
+
+        let moved = use_case.execute(&tenant, fixture.command(&fixture.bob, true)).await;
+        assert_eq!(moved.expect("reassigned"), 2);
+        assert_eq!(fixture.counts().await, (1, 2));
+        let trail = unit_of_work.audit_trail(&tenant).await;
+        assert_eq!(trail.len(), 1);
+        assert_eq!(trail[0].action, TASKS_REASSIGNED);
+        assert_eq!(trail[0].subject, fixture.alice.id().value());
+        assert_eq!(trail[0].details["reassigned"], 2);
+        assert_eq!(trail[0].client_ip, Some([198, 51, 100, 4].into()));
+
+        let moved = use_case.execute(&tenant, fixture.command(&fixture.bob, false)).await;
+        assert_eq!(moved.expect("reassigned"), 1, "the completed task is left");
+        assert_eq!(fixture.counts().await, (0, 3));
+    }
+
+    #[tokio::test]
+    async fn execute_should_drop_the_moved_tasks_from_the_cache() {
+        let fixture = Fixture::new().await;
+        let tenant = TenantId::default();
+        let ttl = std::time::Duration::from_mins(1);
+        let cached = Arc::new(CachedTaskRepository::new(Arc::clone(&fixture.tasks) as _, ttl, 100));
+        let tasks = fixture.tasks.find_by_user_id(&tenant, fixture.alice.id()).await;
+        let task = tasks.expect("tasks").remove(0);
+        cached.find_by_id(&tenant, task.id()).await.expect("cached");
+
+        let use_case = ReassignTasksUseCase::new(
+            Arc::clone(&cached) as Arc<dyn TaskRepository>,
+            Arc::clone(&fixture.users) as _,
+            Arc::new(fixture.unit_of_work()),
+        );
+        use_case.execute(&tenant, fixture.command(&fixture.bob, false)).await.expect("moved");
+        let task = cached.find_by_id(&tenant, task.id()).await.expect("found").expect("exists");
+        assert_eq!(task.user_id(), fixture.bob.id());
+    }
+
+    #[tokio::test]
+    async fn execute_should_reject_a_missing_target() {
+        let fixture = Fixture::new().await;
+        let unit_of_work = Arc::new(fixture.unit_of_work());
+        let use_case = fixture.use_case(Arc::clone(&unit_of_work) as _);
+        let stranger = User::new(UserId::generate(), "Carol".into(), "carol@example.com")
+            .expect("valid user");
+
+        let result = use_case.execute(&TenantId::default(), fixture.command(&stranger, true)).await;
+        assert!(matches!(result, Err(DomainError::NotFound(m)) if m == "User not found"));
+        assert_eq!(fixture.counts().await, (3, 0));
+        assert!(unit_of_work.audit_trail(&TenantId::default()).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn execute_should_move_nothing_when_the_audit_write_fails() {
+        let fixture = Fixture::new().await;
+        let use_case = fixture.use_case(Arc::new(FailingAudit(fixture.unit_of_work())));
+
+        let command = fixture.command(&fixture.bob, false);
+        let result = use_case.execute(&TenantId::default(), command).await;
+        assert!(matches!(result, Err(DomainError::Infrastructure(_))), "{result:?}");
+        assert_eq!(fixture.counts().await, (3, 0));
+    }
+}
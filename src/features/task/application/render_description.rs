@@ -0,0 +1,149 @@
+//! Render a task description as sanitized HTML
+
+use crate::features::task::application::GetTaskUseCase;
+use crate::features::task::domain::{Task, TaskId, TaskRepository};
+use crate::shared::domain::{DomainError, Entity, TenantId};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Port turning a Markdown description into HTML that is safe to embed in a page
+pub trait DescriptionRenderer: Send + Sync {
+    /// Render `markdown`; the output must not contain scripts, iframes or event handlers
+    fn render(&self, markdown: &str) -> String;
+}
+
+/// Longest description (in bytes) that will be rendered
+pub const MAX_RENDERED_DESCRIPTION_LEN: usize = 64 * 1024;
+
+/// Number of rendered descriptions kept before the cache is reset
+const CACHE_CAPACITY: usize = 1024;
+
+/// Rendered HTML per task, tagged with the `updated_at` it was rendered from
+type RenderCache = HashMap<TaskId, (DateTime<Utc>, Arc<str>)>;
+
+/// Use case for getting a task together with its rendered description
+///
+/// Rendered output is cached per task and reused while the task's `updated_at`
+/// is unchanged, so repeated reads don't re-render.
+pub struct RenderTaskDescriptionUseCase<T: ?Sized = dyn TaskRepository> {
+    get_task: GetTaskUseCase<T>,
+    renderer: Arc<dyn DescriptionRenderer>,
+    cache: Mutex<RenderCache>,
+}
+
+impl<T: TaskRepository + ?Sized> RenderTaskDescriptionUseCase<T> {
+    /// Create a new use case instance
+    pub fn new(
+        repository: Arc<T>,
+        renderer: Arc<dyn DescriptionRenderer>,
+    ) -> Self {
+        Self { get_task: GetTaskUseCase::new(repository), renderer, cache: Mutex::default() }
+    }
+
+    /// Get the task of `tenant` with `id` and its description rendered as sanitized
+    /// HTML
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID or a description longer than
+    /// [`MAX_RENDERED_DESCRIPTION_LEN`], `NotFound` if the task doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+    ) -> Result<(Task, Arc<str>), DomainError> {
+        let task = self.get_task.execute(tenant, id).await?;
+        if task.description().len() > MAX_RENDERED_DESCRIPTION_LEN {
+            return Err(DomainError::Validation(format!(
+                "Description exceeds {MAX_RENDERED_DESCRIPTION_LEN} bytes and cannot be rendered"
+            )));
+        }
+        // Not yet persisted tasks have no version to cache against
+        let Some(version) = task.updated_at() else {
+            let html = self.renderer.render(task.description()).into();
+            return Ok((task, html));
+        };
+
+        let cached = self.cache().get(task.id()).filter(|(at, _)| *at == version).cloned();
+        if let Some((_, html)) = cached {
+            return Ok((task, html));
+        }
+        let html: Arc<str> = self.renderer.render(task.description()).into();
+        let mut cache = self.cache();
+        if cache.len() >= CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(task.id().clone(), (version, Arc::clone(&html)));
+        Ok((task, html))
+    }
+
+    fn cache(&self) -> MutexGuard<'_, RenderCache> {
+        // A poisoned cache only holds fully inserted entries, so it is still usable
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::shared::domain::UserId;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Wraps the description in `<p>` and counts calls
+    #[derive(Default)]
+    struct CountingRenderer {
+        calls: AtomicUsize,
+    }
+
+    impl DescriptionRenderer for CountingRenderer {
+        fn render(&self, markdown: &str) -> String {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            format!("<p>{markdown}</p>")
+        }
+    }
+
+    async fn setup(description: String) -> (Arc<InMemoryTaskRepository>, Task) {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        let task = Task::new(TaskId::generate(), UserId::generate(), "title", description)
+            .expect("valid task");
+        let task = repository.insert(&tenant, &task).await.expect("insert");
+        (repository, task)
+    }
+
+    #[tokio::test]
+    async fn execute_should_reuse_rendering_until_task_changes() {
+        let tenant = TenantId::default();
+        let (repository, mut task) = setup("hello".into()).await;
+        let renderer = Arc::new(CountingRenderer::default());
+        let use_case = RenderTaskDescriptionUseCase::new(
+            Arc::clone(&repository) as _,
+            Arc::clone(&renderer) as _,
+        );
+
+        for _ in 0..3 {
+            let (_, html) = use_case.execute(&tenant, task.id().value()).await.expect("render");
+            assert_eq!(&*html, "<p>hello</p>");
+        }
+        assert_eq!(renderer.calls.load(Ordering::SeqCst), 1);
+
+        task.complete().expect("complete");
+        repository.update(&tenant, &task).await.expect("update");
+        use_case.execute(&tenant, task.id().value()).await.expect("render");
+        assert_eq!(renderer.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn execute_should_reject_overlong_descriptions() {
+        let tenant = TenantId::default();
+        let (repository, task) = setup("x".repeat(MAX_RENDERED_DESCRIPTION_LEN + 1)).await;
+        let renderer = Arc::new(CountingRenderer::default());
+        let use_case = RenderTaskDescriptionUseCase::new(repository, Arc::clone(&renderer) as _);
+
+        let result = use_case.execute(&tenant, task.id().value()).await;
+        assert!(matches!(result, Err(DomainError::Validation(_))));
+        assert_eq!(renderer.calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,136 @@
+//! Task activity statistics use case
+
+use crate::features::task::domain::{HourlyTaskCounts, StatsWindow, TaskRepository};
+use crate::shared::domain::{DomainError, TenantId};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How long computed statistics are served before the repository is queried again
+pub const STATS_CACHE_TTL: Duration = Duration::from_mins(1);
+
+type Cached = (Instant, Arc<Vec<HourlyTaskCounts>>);
+
+/// Use case counting created and completed tasks per hour over a window.
+///
+/// Results are cached per tenant and window for [`STATS_CACHE_TTL`], so frequent
+/// polling costs one aggregation query per tenant, window and minute.
+pub struct TaskStatsQuery<T: ?Sized = dyn TaskRepository> {
+    repository: Arc<T>,
+    cache: Mutex<HashMap<(TenantId, StatsWindow), Cached>>,
+}
+
+impl<T: TaskRepository + ?Sized> TaskStatsQuery<T> {
+    /// Create a new use case instance
+    pub fn new(repository: Arc<T>) -> Self {
+        Self { repository, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// One bucket per UTC hour of `window`, oldest first, ending with the current hour;
+    /// hours without activity have zero counts. Only tasks of `tenant` are counted.
+    ///
+    /// # Errors
+    /// Repository errors.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        window: StatsWindow,
+    ) -> Result<Arc<Vec<HourlyTaskCounts>>, DomainError> {
+        self.execute_at(tenant, window, Utc::now()).await
+    }
+
+    async fn execute_at(
+        &self,
+        tenant: &TenantId,
+        window: StatsWindow,
+        now: DateTime<Utc>,
+    ) -> Result<Arc<Vec<HourlyTaskCounts>>, DomainError> {
+        let key = (tenant.clone(), window);
+        if let Some(cached) = self.cached(&key) {
+            return Ok(cached);
+        }
+        let current_hour = now.duration_trunc(TimeDelta::hours(1)).unwrap_or(now);
+        let since = current_hour - TimeDelta::hours(i64::from(window.hours()) - 1);
+        let counts: HashMap<_, _> = self
+            .repository
+            .hourly_counts(tenant, since)
+            .await?
+            .into_iter()
+            .map(|counts| (counts.hour, counts))
+            .collect();
+        let buckets: Vec<_> = (0..window.hours())
+            .map(|offset| since + TimeDelta::hours(i64::from(offset)))
+            .map(|hour| {
+                counts.get(&hour).copied().unwrap_or(HourlyTaskCounts {
+                    hour,
+                    created: 0,
+                    completed: 0,
+                })
+            })
+            .collect();
+        let buckets = Arc::new(buckets);
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(key, (Instant::now(), Arc::clone(&buckets)));
+        }
+        Ok(buckets)
+    }
+
+    fn cached(&self, key: &(TenantId, StatsWindow)) -> Option<Arc<Vec<HourlyTaskCounts>>> {
+        let cache = self.cache.lock().ok()?;
+        let (computed_at, buckets) = cache.get(key)?;
+        (computed_at.elapsed() < STATS_CACHE_TTL).then(|| Arc::clone(buckets))
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::{Task, TaskId};
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::shared::domain::UserId;
+
+    async fn create_task(repository: &InMemoryTaskRepository, tenant: &TenantId, completed: bool) {
+        let user_id = UserId::new("user1").expect("valid user id");
+        let mut task = Task::new(TaskId::generate(), user_id, "Task", String::new()).expect("task");
+        if completed {
+            task.complete().expect("complete");
+        }
+        repository.insert(tenant, &task).await.expect("insert");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn execute_should_fill_every_hour_and_cache_for_ttl() {
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        let tenant = TenantId::default();
+        create_task(&repository, &tenant, false).await;
+        create_task(&repository, &tenant, true).await;
+        let stats = TaskStatsQuery::new(Arc::clone(&repository) as _);
+        let window = StatsWindow::parse("6h").expect("window");
+
+        let now = Utc::now();
+        let buckets = stats.execute_at(&tenant, window, now).await.expect("stats");
+        assert_eq!(buckets.len(), 6);
+        let current_hour = now.duration_trunc(TimeDelta::hours(1)).expect("trunc");
+        assert_eq!(buckets.first().map(|b| b.hour), Some(current_hour - TimeDelta::hours(5)));
+        assert_eq!(buckets.last().map(|b| b.hour), Some(current_hour));
+        let totals = |buckets: &[HourlyTaskCounts]| {
+            buckets.iter().fold((0, 0), |(c, d), b| (c + b.created, d + b.completed))
+        };
+        assert_eq!(totals(&buckets), (2, 1));
+
+        create_task(&repository, &tenant, false).await;
+        let cached = stats.execute_at(&tenant, window, now).await.expect("stats");
+        assert_eq!(totals(&cached), (2, 1));
+        // Other tenants are counted and cached on their own
+        let other = TenantId::new("other").expect("tenant");
+        let empty = stats.execute_at(&other, window, now).await.expect("stats");
+        assert_eq!(totals(&empty), (0, 0));
+
+        tokio::time::advance(STATS_CACHE_TTL).await;
+        let refreshed = stats.execute_at(&tenant, window, now).await.expect("stats");
+        assert_eq!(totals(&refreshed), (3, 1));
+    }
+}
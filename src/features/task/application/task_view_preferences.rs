@@ -0,0 +1,301 @@
+//! Task view preferences use cases: how a user's task listing is shown by default,
+//! and listing a user's tasks accordingly
+
+use super::get_task::{check_search, TaskListQuery};
+use crate::features::task::domain::preferences::check_page_size;
+use crate::features::task::domain::{
+    Task, TaskRepository, TaskSort, TaskViewPreferences, TaskViewPreferencesRepository,
+};
+use crate::features::user::domain::UserRepository;
+use crate::shared::application::{PageLimits, PageRequest, Validate, Validated, ValidationErrors};
+use crate::shared::domain::{DomainError, TenantId, UserId};
+use std::sync::Arc;
+
+/// Command replacing the task view preferences of a user; unset fields take the
+/// defaults of [`TaskViewPreferences`]
+#[derive(Debug, Default)]
+pub struct SetTaskViewPreferencesCommand {
+    /// Order of the listing, named as by [`TaskSort::name`]
+    pub sort: Option<String>,
+    /// Whether completed tasks are listed
+    pub show_completed: Option<bool>,
+    /// Tasks per page
+    pub page_size: Option<u32>,
+}
+
+impl Validate for SetTaskViewPreferencesCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if let Some(sort) = &self.sort {
+            errors.check("sort", TaskSort::parse(sort));
+        }
+        errors.check("page_size", check_page_size(self.page_size));
+        errors.into_result()
+    }
+}
+
+/// Filters and order of a user's task listing; `completed` and `sort` take the
+/// user's task view preferences when unset
+#[derive(Debug, Default)]
+pub struct UserTaskListQuery {
+    /// Only completed (`true`) or open (`false`) tasks
+    pub completed: Option<bool>,
+    /// Only tasks whose title or description contains this text, ignoring case
+    pub q: Option<String>,
+    /// Order of the listing, named as by [`TaskSort::name`]
+    pub sort: Option<String>,
+}
+
+impl Validate for UserTaskListQuery {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        check_search(&mut errors, self.q.as_deref());
+        if let Some(sort) = &self.sort {
+            errors.check("sort", TaskSort::parse(sort));
+        }
+        errors.into_result()
+    }
+}
+
+/// The ID of the existing user `id` of `tenant`
+///
+/// # Errors
+/// `Validation` for an invalid ID, `NotFound` if the user doesn't exist.
+async fn existing_user<U: UserRepository + ?Sized>(
+    users: &U,
+    tenant: &TenantId,
+    id: &str,
+) -> Result<UserId, DomainError> {
+    let user_id = UserId::new(id)?;
+    if users.find_by_id(tenant, &user_id).await?.is_none() {
+        return Err(DomainError::not_found(UserId::entity_name()));
+    }
+    Ok(user_id)
+}
+
+/// Use case reading the task view preferences of a user
+pub struct GetTaskViewPreferencesUseCase<U: ?Sized = dyn UserRepository> {
+    user_repository: Arc<U>,
+    preferences: Arc<dyn TaskViewPreferencesRepository>,
+}
+
+impl<U: UserRepository + ?Sized> GetTaskViewPreferencesUseCase<U> {
+    /// Create a new use case instance
+    pub fn new(
+        user_repository: Arc<U>,
+        preferences: Arc<dyn TaskViewPreferencesRepository>,
+    ) -> Self {
+        Self { user_repository, preferences }
+    }
+
+    /// The task view preferences of the user `id` of `tenant`; the defaults if they
+    /// never saved any
+    ///
+    /// # Errors
+    /// `Validation` for an invalid ID, `NotFound` if the user doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+    ) -> Result<TaskViewPreferences, DomainError> {
+        let user_id = existing_user(self.user_repository.as_ref(), tenant, id).await?;
+        Ok(self.preferences.find(tenant, &user_id).await?.unwrap_or_default())
+    }
+}
+
+/// Use case replacing the task view preferences of a user
+pub struct SetTaskViewPreferencesUseCase<U: ?Sized = dyn UserRepository> {
+    user_repository: Arc<U>,
+    preferences: Arc<dyn TaskViewPreferencesRepository>,
+}
+
+impl<U: UserRepository + ?Sized> SetTaskViewPreferencesUseCase<U> {
+    /// Create a new use case instance
+    pub fn new(
+        user_repository: Arc<U>,
+        preferences: Arc<dyn TaskViewPreferencesRepository>,
+    ) -> Self {
+        Self { user_repository, preferences }
+    }
+
+    /// Replace the task view preferences of the user `id` of `tenant`, returning them
+    /// as saved
+    ///
+    /// # Errors
+    /// `Validation` for an invalid ID, `NotFound` if the user doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+        command: Validated<SetTaskViewPreferencesCommand>,
+    ) -> Result<TaskViewPreferences, DomainError> {
+        let user_id = existing_user(self.user_repository.as_ref(), tenant, id).await?;
+        let command = command.into_inner();
+        let defaults = TaskViewPreferences::default();
+        let sort = command.sort.as_deref().map(TaskSort::parse).transpose()?;
+        let preferences = TaskViewPreferences::new(
+            sort.unwrap_or(defaults.sort()),
+            command.show_completed.unwrap_or(defaults.show_completed()),
+            command.page_size,
+        )?;
+        self.preferences.save(tenant, &user_id, &preferences).await?;
+        Ok(preferences)
+    }
+}
+
+/// Use case listing the tasks of a user as their task view preferences say, unless
+/// the request says otherwise
+pub struct ListUserTasksUseCase<T: ?Sized = dyn TaskRepository, U: ?Sized = dyn UserRepository> {
+    repository: Arc<T>,
+    user_repository: Arc<U>,
+    preferences: Arc<dyn TaskViewPreferencesRepository>,
+    limits: PageLimits,
+}
+
+impl<T: TaskRepository + ?Sized, U: UserRepository + ?Sized> ListUserTasksUseCase<T, U> {
+    /// Create a new use case instance returning pages within `limits`
+    pub fn new(
+        repository: Arc<T>,
+        user_repository: Arc<U>,
+        preferences: Arc<dyn TaskViewPreferencesRepository>,
+        limits: PageLimits,
+    ) -> Self {
+        Self { repository, user_repository, preferences, limits }
+    }
+
+    /// Bounds of the pages listed
+    #[must_use]
+    pub fn limits(&self) -> PageLimits {
+        self.limits
+    }
+
+    /// List the requested page of the tasks of the user `id` of `tenant` matching
+    /// `query`
+    ///
+    /// What `query` and `page` leave unset comes from the user's preferences: the
+    /// order, hiding completed tasks, and the page size, capped at the largest page.
+    /// Explicit values always win. Returns the tasks with the page request the
+    /// preferences completed, to link the neighbouring pages with.
+    ///
+    /// # Errors
+    /// `Validation` for an invalid ID or a page outside the limits, `NotFound` if the
+    /// user doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+        query: Validated<UserTaskListQuery>,
+        page: PageRequest,
+    ) -> Result<(Vec<Task>, PageRequest), DomainError> {
+        let user_id = existing_user(self.user_repository.as_ref(), tenant, id).await?;
+        let preferences = self.preferences.find(tenant, &user_id).await?.unwrap_or_default();
+        let query = query.into_inner();
+        let sort = match query.sort.as_deref() {
+            Some(name) => TaskSort::parse(name)?,
+            None => preferences.sort(),
+        };
+        let completed = query.completed.or((!preferences.show_completed()).then_some(false));
+        let preferred = preferences.page_size().map(|size| size.min(self.limits.max_page_size));
+        let page = PageRequest { limit: page.limit.or(preferred), ..page };
+        let filter =
+            TaskListQuery { user_id: Some(user_id.value().to_owned()), completed, q: query.q }
+                .into_filter();
+        let tasks =
+            self.repository.find_page(tenant, &filter, sort, self.limits.resolve(page)?).await?;
+        Ok((tasks, page))
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::TaskId;
+    use crate::features::task::infrastructure::{
+        InMemoryTaskRepository, InMemoryTaskViewPreferencesRepository,
+    };
+    use crate::features::user::domain::User;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::Entity;
+
+    struct Fixture {
+        list: ListUserTasksUseCase,
+        set: SetTaskViewPreferencesUseCase,
+    }
+
+    /// Use cases over user `u1` owning open tasks `b` and `c` and completed task `a`
+    async fn fixture(max_page_size: u32) -> Fixture {
+        let tenant = TenantId::default();
+        let users: Arc<dyn UserRepository> = Arc::new(InMemoryUserRepository::default());
+        let user = User::new(UserId::from_trusted("u1".into()), "Alice".into(), "a@example.com")
+            .expect("valid user");
+        users.insert(&tenant, &user).await.expect("insert user");
+        let tasks: Arc<dyn TaskRepository> = Arc::new(InMemoryTaskRepository::default());
+        let seeded = [("a", "Cook", true), ("b", "bake", false), ("c", "Alter", false)];
+        for (id, title, completed) in seeded {
+            let id = TaskId::from_trusted(id.into());
+            let mut task = Task::new(id, user.id().clone(), title, String::new()).expect("valid");
+            if completed {
+                task.complete().expect("open task");
+            }
+            tasks.insert(&tenant, &task).await.expect("insert task");
+        }
+        let preferences = Arc::new(InMemoryTaskViewPreferencesRepository::default());
+        let limits = PageLimits { max_page_size, max_offset: 100 };
+        Fixture {
+            list: ListUserTasksUseCase::new(tasks, Arc::clone(&users), preferences.clone(), limits),
+            set: SetTaskViewPreferencesUseCase::new(users, preferences),
+        }
+    }
+
+    async fn list(fixture: &Fixture, query: UserTaskListQuery, limit: Option<u32>) -> Vec<String> {
+        let query = Validated::new(query).expect("valid query");
+        let page = PageRequest { limit, offset: None };
+        let (tasks, _) =
+            fixture.list.execute(&TenantId::default(), "u1", query, page).await.expect("list");
+        tasks.iter().map(|t| t.id().value().to_owned()).collect()
+    }
+
+    #[tokio::test]
+    async fn stored_preferences_should_apply_unless_the_query_says_otherwise() {
+        let fixture = fixture(2).await;
+        assert_eq!(list(&fixture, UserTaskListQuery::default(), None).await, ["a", "b"]);
+
+        let command = SetTaskViewPreferencesCommand {
+            sort: Some("title".into()),
+            show_completed: Some(false),
+            page_size: Some(50),
+        };
+        let command = Validated::new(command).expect("valid command");
+        fixture.set.execute(&TenantId::default(), "u1", command).await.expect("save");
+
+        // Title order without the completed task, in pages capped at the largest
+        assert_eq!(list(&fixture, UserTaskListQuery::default(), None).await, ["c", "b"]);
+        let explicit = UserTaskListQuery {
+            completed: Some(true),
+            sort: Some("id".into()),
+            ..UserTaskListQuery::default()
+        };
+        assert_eq!(list(&fixture, explicit, Some(1)).await, ["a"]);
+    }
+
+    #[tokio::test]
+    async fn preferences_of_an_unknown_user_should_not_be_saved() {
+        let fixture = fixture(10).await;
+        let command = Validated::new(SetTaskViewPreferencesCommand::default()).expect("valid");
+        let result = fixture.set.execute(&TenantId::default(), "nobody", command).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))), "{result:?}");
+    }
+
+    #[test]
+    fn commands_should_report_every_invalid_field() {
+        let command = SetTaskViewPreferencesCommand {
+            sort: Some("size".into()),
+            show_completed: None,
+            page_size: Some(0),
+        };
+        let errors = command.validate().expect_err("invalid");
+        let fields: Vec<_> = errors.fields().iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["sort", "page_size"]);
+    }
+}
@@ -0,0 +1,205 @@
+//! Create-or-update task use case for clients that choose task IDs themselves
+
+use crate::features::task::domain::entity::{normalize_title, OWNED_BY_ANOTHER_USER};
+use crate::features::task::domain::{Task, TaskId, TaskRepository, UpsertOutcome};
+use crate::shared::application::{Validate, Validated, ValidationErrors};
+use crate::shared::domain::{
+    validation_message, DomainError, DomainWarning, Entity, TenantId, UserId, CANNOT_BE_EMPTY,
+};
+use std::sync::Arc;
+
+/// Command to create the task with a client-supplied ID, or update it if it exists
+#[derive(Debug)]
+pub struct UpsertTaskCommand {
+    /// Task ID: a lowercase hyphenated UUID
+    pub id: String,
+    /// User ID who owns the task; cannot change for an existing task
+    pub user_id: String,
+    /// Task title
+    pub title: String,
+    /// Task description
+    pub description: String,
+}
+
+/// Whether `id` is a UUID in the lowercase hyphenated form of generated IDs
+fn is_canonical_uuid(id: &str) -> bool {
+    uuid::Uuid::try_parse(id).is_ok_and(|uuid| uuid.hyphenated().to_string() == id)
+}
+
+impl Validate for UpsertTaskCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check("id", TaskId::new(&self.id));
+        if !self.id.is_empty() && !is_canonical_uuid(&self.id) {
+            errors.add("id", "Task ID must be a lowercase hyphenated UUID");
+        }
+        errors.check("user_id", UserId::new(&self.user_id));
+        if normalize_title(&self.title).is_empty() {
+            errors.add("title", validation_message("Title", CANNOT_BE_EMPTY));
+        }
+        errors.into_result()
+    }
+}
+
+/// Use case for creating or updating a task by its client-supplied ID
+pub struct UpsertTaskUseCase<T: ?Sized = dyn TaskRepository> {
+    task_repository: Arc<T>,
+    prevent_duplicate_open_tasks: bool,
+}
+
+impl<T: TaskRepository + ?Sized> UpsertTaskUseCase<T> {
+    /// Create a new use case instance
+    ///
+    /// When `prevent_duplicate_open_tasks` is set, a title matching another open
+    /// task of the user is rejected, as on creation.
+    pub fn new(
+        task_repository: Arc<T>,
+        prevent_duplicate_open_tasks: bool,
+    ) -> Self {
+        Self { task_repository, prevent_duplicate_open_tasks }
+    }
+
+    /// Create the task in `tenant` if no task has the ID, otherwise replace its title
+    /// and description. Repeating the same command leaves the same task.
+    ///
+    /// Returns whether the task was created or updated, with the soft-rule warnings
+    /// its input produced.
+    ///
+    /// # Errors
+    /// `Conflict` if the task belongs to another user or tenant, `NotFound` for an unknown
+    /// user, `AlreadyExists` for a duplicate open task when duplicates are prevented.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        command: Validated<UpsertTaskCommand>,
+    ) -> Result<(UpsertOutcome, Vec<DomainWarning>), DomainError> {
+        let command = command.into_inner();
+        let id = TaskId::from_trusted(command.id);
+        let user_id = UserId::from_trusted(command.user_id);
+        let (task, warnings) = match self.task_repository.find_by_id(tenant, &id).await? {
+            Some(existing) if *existing.user_id() != user_id => {
+                return Err(DomainError::Conflict(OWNED_BY_ANOTHER_USER.into()));
+            }
+            Some(mut existing) => {
+                let warnings = existing.edit(&command.title, command.description)?;
+                (existing, warnings)
+            }
+            None => Task::new_with_warnings(id, user_id, &command.title, command.description)?,
+        };
+
+        if self.prevent_duplicate_open_tasks
+            && let Some(existing) = self
+                .task_repository
+                .exists_open_with_title(tenant, task.user_id(), task.title())
+                .await?
+            && existing != *task.id()
+        {
+            return Err(DomainError::already_exists_as("Open task", "title", existing.value()));
+        }
+
+        Ok((self.task_repository.upsert(tenant, &task).await?, warnings))
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+
+    const ID: &str = "0b6f2a6e-8d1c-4a7e-9f3b-2c5d7e9a1b3c";
+
+    fn command(user_id: &str, title: &str) -> Validated<UpsertTaskCommand> {
+        let command = UpsertTaskCommand {
+            id: ID.into(),
+            user_id: user_id.into(),
+            title: title.into(),
+            description: String::new(),
+        };
+        Validated::new(command).expect("valid command")
+    }
+
+    fn unpack(outcome: UpsertOutcome) -> (bool, Task) {
+        match outcome {
+            UpsertOutcome::Created(task) => (true, task),
+            UpsertOutcome::Updated(task) => (false, task),
+        }
+    }
+
+    #[test]
+    fn validate_should_require_a_canonical_uuid() {
+        let fields = |id: &str| {
+            let command = UpsertTaskCommand {
+                id: id.into(),
+                user_id: "user1".into(),
+                title: "Buy milk".into(),
+                description: String::new(),
+            };
+            let errors = command.validate().err().unwrap_or_default();
+            errors.fields().iter().map(|e| e.message.clone()).collect::<Vec<_>>()
+        };
+        assert!(fields(ID).is_empty());
+        assert_eq!(fields(""), ["Task ID cannot be empty"]);
+        for id in ["task-1", &ID.to_uppercase(), &ID.replace('-', "")] {
+            assert_eq!(fields(id), ["Task ID must be a lowercase hyphenated UUID"], "{id}");
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_should_create_then_update_idempotently() {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        let use_case = UpsertTaskUseCase::new(Arc::clone(&repository) as _, false);
+
+        let (outcome, _) =
+            use_case.execute(&tenant, command("user1", "Buy milk")).await.expect("create");
+        let (created, task) = unpack(outcome);
+        assert!(created);
+        assert_eq!((task.id().value(), task.title()), (ID, "Buy milk"));
+
+        repository.complete_if_open(&tenant, task.id()).await.expect("complete");
+        for _ in 0..2 {
+            let (outcome, _) =
+                use_case.execute(&tenant, command("user1", " Buy  bread ")).await.expect("update");
+            let (created, task) = unpack(outcome);
+            assert!(!created);
+            assert_eq!(task.title(), "Buy bread");
+            assert!(task.is_completed());
+        }
+        assert_eq!(repository.find_all_unbounded(&tenant).await.expect("query").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn execute_should_not_transfer_ownership() {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        let use_case = UpsertTaskUseCase::new(Arc::clone(&repository) as _, false);
+        use_case.execute(&tenant, command("user1", "Buy milk")).await.expect("create");
+
+        let result = use_case.execute(&tenant, command("user2", "Buy milk")).await;
+        assert!(matches!(result, Err(DomainError::Conflict(_))), "{result:?}");
+        let stored = repository.find_all_unbounded(&tenant).await.expect("query");
+        assert_eq!(stored.iter().map(|t| t.user_id().value()).collect::<Vec<_>>(), ["user1"]);
+    }
+
+    #[tokio::test]
+    async fn execute_should_reject_duplicate_of_another_open_task_only() {
+        let tenant = TenantId::default();
+        let repository = Arc::new(InMemoryTaskRepository::default());
+        let use_case = UpsertTaskUseCase::new(Arc::clone(&repository) as _, true);
+        use_case.execute(&tenant, command("user1", "Buy milk")).await.expect("create");
+        // Re-sending the task's own title is not a duplicate
+        use_case.execute(&tenant, command("user1", "buy MILK")).await.expect("update");
+
+        let other = Task::new(
+            TaskId::generate(),
+            UserId::from_trusted("user1".into()),
+            "Call mom",
+            String::new(),
+        )
+        .expect("valid task");
+        repository.insert(&tenant, &other).await.expect("insert");
+        let result = use_case.execute(&tenant, command("user1", "Call mom")).await;
+        assert!(matches!(result, Err(DomainError::AlreadyExists(_))), "{result:?}");
+    }
+}
@@ -0,0 +1,118 @@
+//! Every user with their most recent tasks (read model spanning user and task)
+
+use crate::features::task::domain::{Task, TaskRepository};
+use crate::features::user::domain::{User, UserRepository};
+use crate::shared::application::{PageLimits, PageRequest};
+use crate::shared::domain::{DomainError, Entity, TenantId, UserId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Recent tasks shown per user unless requested otherwise
+pub const DEFAULT_RECENT_TASKS: u32 = 5;
+/// Largest number of recent tasks per user
+pub const MAX_RECENT_TASKS: u32 = 50;
+
+/// A user with their most recently created tasks, newest first
+#[derive(Debug)]
+pub struct UserWithRecentTasks {
+    /// The user
+    pub user: User,
+    /// The user's most recent tasks; empty for a user without tasks
+    pub tasks: Vec<Task>,
+}
+
+/// Read-model service listing users with their recent tasks in two queries
+pub struct UserOverviewQuery<T: ?Sized = dyn TaskRepository, U: ?Sized = dyn UserRepository> {
+    user_repository: Arc<U>,
+    task_repository: Arc<T>,
+    limits: PageLimits,
+}
+
+impl<T: TaskRepository + ?Sized, U: UserRepository + ?Sized> UserOverviewQuery<T, U> {
+    /// Create a new use case instance returning pages of users within `limits`
+    pub fn new(
+        user_repository: Arc<U>,
+        task_repository: Arc<T>,
+        limits: PageLimits,
+    ) -> Self {
+        Self { user_repository, task_repository, limits }
+    }
+
+    /// The requested page of users of `tenant`, ordered by ID, each with up to
+    /// `per_user_limit` of their most recent tasks
+    ///
+    /// # Errors
+    /// `Validation` when `per_user_limit` is outside 1..=[`MAX_RECENT_TASKS`] or the
+    /// page is outside the limits.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        per_user_limit: u32,
+        page: PageRequest,
+    ) -> Result<Vec<UserWithRecentTasks>, DomainError> {
+        if !(1..=MAX_RECENT_TASKS).contains(&per_user_limit) {
+            return Err(DomainError::Validation(format!(
+                "per_user must be between 1 and {MAX_RECENT_TASKS}"
+            )));
+        }
+        let page = self.limits.resolve(page)?;
+        let users = self.user_repository.find_page(tenant, None, page).await?;
+        let user_ids: Vec<UserId> = users.iter().map(|u| u.id().clone()).collect();
+        let mut tasks: HashMap<UserId, Vec<Task>> = HashMap::new();
+        let recent = self.task_repository.find_recent_by_users(tenant, &user_ids, per_user_limit);
+        for task in recent.await? {
+            tasks.entry(task.user_id().clone()).or_default().push(task);
+        }
+        Ok(users
+            .into_iter()
+            .map(|user| {
+                let tasks = tasks.remove(user.id()).unwrap_or_default();
+                UserWithRecentTasks { user, tasks }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::TaskId;
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+
+    #[tokio::test]
+    async fn execute_should_limit_tasks_per_user_and_keep_users_without_tasks() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let tasks = Arc::new(InMemoryTaskRepository::default());
+        let tenant = TenantId::default();
+        for (name, count) in [("alice", 3), ("bob", 0)] {
+            let user = User::new(UserId::generate(), name.into(), &format!("{name}@example.com"))
+                .expect("valid user");
+            users.insert(&tenant, &user).await.expect("insert user");
+            for n in 0..count {
+                let task =
+                    Task::new(TaskId::generate(), user.id().clone(), &n.to_string(), String::new())
+                        .expect("valid task");
+                tasks.insert(&tenant, &task).await.expect("insert task");
+            }
+        }
+        let query = UserOverviewQuery::new(users, tasks, PageLimits::default());
+
+        let overview = query.execute(&tenant, 2, PageRequest::default()).await.expect("overview");
+        let mut summary: Vec<(&str, Vec<&str>)> = overview
+            .iter()
+            .map(|entry| (entry.user.name(), entry.tasks.iter().map(Task::title).collect()))
+            .collect();
+        summary.sort();
+        assert_eq!(summary, [("alice", vec!["2", "1"]), ("bob", vec![])]);
+
+        for invalid in [0, MAX_RECENT_TASKS + 1] {
+            let result = query.execute(&tenant, invalid, PageRequest::default()).await;
+            assert!(matches!(result, Err(DomainError::Validation(_))));
+        }
+        let page = PageRequest { limit: Some(1), offset: Some(1) };
+        let overview = query.execute(&tenant, 2, page).await.expect("overview");
+        assert_eq!(overview.len(), 1);
+    }
+}
@@ -0,0 +1,212 @@
+//! Attachment: metadata of a file attached to a task
+//!
+//! Only metadata is stored here. The bytes live in [`BlobStorage`](super::BlobStorage)
+//! under the attachment's storage key, and clients upload and download them directly
+//! through presigned URLs.
+
+use crate::features::task::domain::value_objects::{AttachmentId, TaskId};
+use crate::shared::domain::{DomainError, Entity, ErrorStatus, TenantId};
+use chrono::{DateTime, Utc};
+
+/// Longest attachment filename accepted, in characters
+pub const MAX_FILENAME_LEN: usize = 255;
+
+/// Code of the refusal of a file above [`AttachmentRules::max_size_bytes`], which is
+/// reported in `details.maxSizeBytes`
+pub const ATTACHMENT_TOO_LARGE: &str = "ATTACHMENT_TOO_LARGE";
+
+/// Which files may be attached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentRules {
+    /// Largest attachment in bytes
+    pub max_size_bytes: u64,
+    /// Accepted media types (`type/subtype`); every other type is rejected
+    pub content_types: Vec<String>,
+}
+
+/// Check a client-supplied filename: 1 to [`MAX_FILENAME_LEN`] characters without
+/// path separators or control characters
+///
+/// # Errors
+/// Returns `DomainError::Validation` naming the broken rule.
+pub fn check_filename(filename: &str) -> Result<(), DomainError> {
+    if filename.trim().is_empty() || filename.chars().count() > MAX_FILENAME_LEN {
+        return Err(DomainError::Validation(format!(
+            "Filename must be between 1 and {MAX_FILENAME_LEN} characters"
+        )));
+    }
+    if filename.chars().any(|c| c == '/' || c == '\\' || c.is_control()) {
+        return Err(DomainError::Validation(
+            "Filename cannot contain path separators or control characters".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Media type of a `Content-Type` value, without parameters and lowercased
+fn essence(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// A file attached to a task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    id: AttachmentId,
+    task_id: TaskId,
+    filename: String,
+    content_type: String,
+    size: u64,
+    storage_key: String,
+    created_at: DateTime<Utc>,
+}
+
+impl Attachment {
+    /// Describe a new file of `size` bytes attached to `task_id` in `tenant`
+    ///
+    /// The storage key is derived from the tenant and the generated ID only, so no
+    /// client input ends up in a blob path. The content type is stored without
+    /// parameters.
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` for an invalid filename or a content type
+    /// outside `rules.content_types`, [`ATTACHMENT_TOO_LARGE`] for a size above
+    /// `rules.max_size_bytes`.
+    pub fn new(
+        tenant: &TenantId,
+        task_id: TaskId,
+        filename: &str,
+        content_type: &str,
+        size: u64,
+        rules: &AttachmentRules,
+    ) -> Result<Self, DomainError> {
+        check_filename(filename)?;
+        let content_type = essence(content_type);
+        if !rules.content_types.iter().any(|allowed| essence(allowed) == content_type) {
+            return Err(DomainError::Validation(format!(
+                "Content type '{content_type}' is not allowed; allowed: {}",
+                rules.content_types.join(", ")
+            )));
+        }
+        if size > rules.max_size_bytes {
+            return Err(DomainError::Custom {
+                code: ATTACHMENT_TOO_LARGE,
+                status_hint: ErrorStatus::Invalid,
+                message: format!("Attachment is larger than {} bytes", rules.max_size_bytes),
+                details: serde_json::json!({ "maxSizeBytes": rules.max_size_bytes }),
+            });
+        }
+        let id = AttachmentId::generate();
+        Ok(Self {
+            storage_key: format!("{}/attachments/{}", tenant.value(), id.value()),
+            id,
+            task_id,
+            filename: filename.to_owned(),
+            content_type,
+            size,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Reconstitute an attachment from persistence (bypasses business rules)
+    #[must_use]
+    pub fn reconstitute(
+        id: AttachmentId,
+        task_id: TaskId,
+        filename: String,
+        content_type: String,
+        size: u64,
+        storage_key: String,
+        created_at: DateTime<Utc>,
+    ) -> Self {
+        Self { id, task_id, filename, content_type, size, storage_key, created_at }
+    }
+
+    /// Task the file is attached to
+    #[must_use]
+    pub fn task_id(&self) -> &TaskId {
+        &self.task_id
+    }
+
+    /// Name of the file as given by the client
+    #[must_use]
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Media type of the file
+    #[must_use]
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    /// Size of the file in bytes, as announced by the client
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Key of the file's blob in [`BlobStorage`](super::BlobStorage)
+    #[must_use]
+    pub fn storage_key(&self) -> &str {
+        &self.storage_key
+    }
+
+    /// When the attachment was created
+    #[must_use]
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+impl Entity for Attachment {
+    type Id = AttachmentId;
+
+    fn id(&self) -> &Self::Id {
+        &self.id
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    fn rules() -> AttachmentRules {
+        AttachmentRules { max_size_bytes: 100, content_types: vec!["image/png".into()] }
+    }
+
+    fn attach(filename: &str, content_type: &str, size: u64) -> Result<Attachment, DomainError> {
+        let task_id = TaskId::from_trusted("t1".into());
+        Attachment::new(&TenantId::default(), task_id, filename, content_type, size, &rules())
+    }
+
+    #[test]
+    fn new_should_normalize_content_type_and_derive_storage_key() {
+        let attachment =
+            attach("photo.png", "Image/PNG; charset=binary", 100).expect("valid attachment");
+        assert_eq!(attachment.content_type(), "image/png");
+        let expected = format!("default/attachments/{}", attachment.id().value());
+        assert_eq!(attachment.storage_key(), expected);
+    }
+
+    #[test]
+    fn new_should_enforce_filename_type_and_size_rules() {
+        let too_long = "a".repeat(MAX_FILENAME_LEN + 1);
+        for (filename, content_type, size) in [
+            ("", "image/png", 1),
+            (too_long.as_str(), "image/png", 1),
+            ("../photo.png", "image/png", 1),
+            ("photo\n.png", "image/png", 1),
+            ("photo.svg", "image/svg+xml", 1),
+        ] {
+            let result = attach(filename, content_type, size);
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{filename} {size}");
+        }
+        let result = attach("photo.png", "image/png", 101);
+        assert!(
+            matches!(&result, Err(DomainError::Custom { code: ATTACHMENT_TOO_LARGE, details, .. })
+                if details["maxSizeBytes"] == 100),
+            "{result:?}"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Checklist: lightweight sub-items of a task
+//!
+//! Items are part of the [`Task`](super::Task) aggregate rather than entities of their
+//! own: they are changed only through the task's `add_item`, `toggle_item` and
+//! `remove_item`, which keep the rules below, and stored with the task.
+
+use crate::features::task::domain::value_objects::ChecklistItemId;
+use crate::shared::domain::DomainError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Most items a task's checklist can hold
+pub const MAX_CHECKLIST_ITEMS: usize = 50;
+
+/// Longest checklist item text, in characters after trimming
+pub const MAX_CHECKLIST_ITEM_LEN: usize = 200;
+
+/// Code of the warning reported when a task is completed with unchecked items
+pub const UNCHECKED_CHECKLIST_ITEMS: &str = "UNCHECKED_CHECKLIST_ITEMS";
+
+/// An item of a task's checklist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    id: ChecklistItemId,
+    text: String,
+    done: bool,
+}
+
+impl ChecklistItem {
+    /// Unchecked item with a new ID and `text`, trimmed
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` unless the trimmed text has 1 to
+    /// [`MAX_CHECKLIST_ITEM_LEN`] characters.
+    pub(crate) fn new(text: &str) -> Result<Self, DomainError> {
+        Ok(Self { id: ChecklistItemId::generate(), text: checked_text(text)?, done: false })
+    }
+
+    /// Item ID, unique within its checklist
+    #[must_use]
+    pub fn id(&self) -> &ChecklistItemId {
+        &self.id
+    }
+
+    /// What is to be done
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether the item is checked
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub(crate) fn set_done(&mut self, done: bool) {
+        self.done = done;
+    }
+}
+
+/// `text` trimmed, if it has 1 to [`MAX_CHECKLIST_ITEM_LEN`] characters
+pub(crate) fn checked_text(text: &str) -> Result<String, DomainError> {
+    let text = text.trim();
+    if text.is_empty() || text.chars().count() > MAX_CHECKLIST_ITEM_LEN {
+        return Err(DomainError::Validation(format!(
+            "Checklist item text must be between 1 and {MAX_CHECKLIST_ITEM_LEN} characters"
+        )));
+    }
+    Ok(text.to_owned())
+}
+
+/// Check that room is left for another item in `items`
+pub(crate) fn check_room(items: &[ChecklistItem]) -> Result<(), DomainError> {
+    if items.len() >= MAX_CHECKLIST_ITEMS {
+        return Err(DomainError::Validation(format!(
+            "Checklist cannot have more than {MAX_CHECKLIST_ITEMS} items"
+        )));
+    }
+    Ok(())
+}
+
+/// Check that `items`, read from outside the aggregate, keep every checklist rule:
+/// at most [`MAX_CHECKLIST_ITEMS`] items with unique non-empty IDs and valid, trimmed
+/// texts
+pub(crate) fn validate(items: &[ChecklistItem]) -> Result<(), DomainError> {
+    if items.len() > MAX_CHECKLIST_ITEMS {
+        return Err(DomainError::Validation(format!(
+            "Checklist cannot have more than {MAX_CHECKLIST_ITEMS} items"
+        )));
+    }
+    let mut ids = HashSet::new();
+    for item in items {
+        ChecklistItemId::new(item.id.value())?;
+        if !ids.insert(item.id.value()) {
+            return Err(DomainError::Validation(format!(
+                "Checklist item ID '{}' is not unique",
+                item.id.value()
+            )));
+        }
+        if checked_text(&item.text)? != item.text {
+            return Err(DomainError::Validation("Checklist item text must be trimmed".into()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_items_should_be_unchecked_with_trimmed_text_of_valid_length() {
+        let item = ChecklistItem::new("  Buy  milk \n").expect("valid item");
+        assert_eq!((item.text(), item.is_done()), ("Buy  milk", false));
+        assert!(ChecklistItem::new(&"x".repeat(MAX_CHECKLIST_ITEM_LEN)).is_ok());
+        for invalid in [String::new(), " \t ".into(), "x".repeat(MAX_CHECKLIST_ITEM_LEN + 1)] {
+            let result = ChecklistItem::new(&invalid);
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn stored_items_should_round_trip_through_json() {
+        let mut done = ChecklistItem::new("Pack \"bags\" \\ 日本語").expect("valid item");
+        done.set_done(true);
+        let items = vec![ChecklistItem::new("First").expect("valid item"), done];
+        let json = serde_json::to_string(&items).expect("serializable");
+        let read: Vec<ChecklistItem> = serde_json::from_str(&json).expect("readable");
+        assert_eq!(read, items);
+        assert!(validate(&read).is_ok());
+    }
+
+    #[test]
+    fn validate_should_reject_broken_checklists() {
+        let item = |id: &str, text: &str| ChecklistItem {
+            id: ChecklistItemId::from_trusted(id.into()),
+            text: text.into(),
+            done: false,
+        };
+        let too_many: Vec<ChecklistItem> =
+            (0..=MAX_CHECKLIST_ITEMS).map(|n| item(&n.to_string(), "Item")).collect();
+        let invalid = [
+            too_many,
+            vec![item("", "Item")],
+            vec![item("a", "Item"), item("a", "Other")],
+            vec![item("a", "")],
+            vec![item("a", " Untrimmed ")],
+        ];
+        for items in invalid {
+            assert!(matches!(validate(&items), Err(DomainError::Validation(_))), "{items:?}");
+        }
+    }
+}
@@ -1,7 +1,14 @@
 //! Task domain
 
-use crate::features::task::domain::value_objects::TaskId;
-use crate::shared::domain::{DomainError, Entity, UserId};
+use crate::features::task::domain::checklist::{
+    self, ChecklistItem, UNCHECKED_CHECKLIST_ITEMS,
+};
+use crate::features::task::domain::value_objects::{ChecklistItemId, TaskId};
+use crate::shared::domain::{
+    snapshot, DomainError, DomainWarning, Entity, UserId, CANNOT_BE_EMPTY,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 /// Task aggregate root
 #[derive(Debug, Clone)]
@@ -11,32 +18,122 @@ pub struct Task {
     title: String,
     description: String,
     completed: bool,
-    #[expect(dead_code, reason = "persisted for auditing, not yet exposed in API")]
+    completed_at: Option<DateTime<Utc>>,
     updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    checklist: Vec<ChecklistItem>,
+}
+
+/// Title length (in characters, after normalization) above which a task is
+/// still accepted but flagged with a `TITLE_TOO_LONG` warning
+pub const TITLE_WARNING_LEN: usize = 150;
+
+/// Version of the snapshots [`Task::to_snapshot`] writes; bump it when adding a
+/// field, which must have a default
+pub const TASK_SNAPSHOT_VERSION: u32 = 2;
+
+/// Reason a task cannot be created completed in the future
+pub const COMPLETED_IN_THE_FUTURE: &str = "cannot be in the future";
+
+/// Message of the conflict raised when completing a completed task
+pub(crate) const ALREADY_COMPLETED: &str = "Task is already completed";
+
+/// Message of the conflict raised when an upsert names another user's task
+pub(crate) const OWNED_BY_ANOTHER_USER: &str = "Task belongs to another user";
+
+/// Trim a title and collapse internal runs of whitespace (tabs, newlines,
+/// Unicode spaces) into single ASCII spaces.
+pub(crate) fn normalize_title(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Normalize `title`, rejecting an empty one and flagging a long one
+fn checked_title(title: &str) -> Result<(String, Vec<DomainWarning>), DomainError> {
+    let title = normalize_title(title);
+    if title.is_empty() {
+        return Err(DomainError::validation("Title", CANNOT_BE_EMPTY));
+    }
+    let mut warnings = Vec::new();
+    if title.chars().count() > TITLE_WARNING_LEN {
+        warnings.push(DomainWarning {
+            code: "TITLE_TOO_LONG",
+            message: format!("Title is longer than {TITLE_WARNING_LEN} characters"),
+            field: "title",
+        });
+    }
+    Ok((title, warnings))
 }
 
 impl Task {
     /// Create a new task
+    ///
+    /// The title is normalized before the non-empty check, so a
+    /// whitespace-only title is rejected.
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the normalized title is empty.
     pub fn new(
         id: TaskId,
         user_id: UserId,
-        title: String,
+        title: &str,
         description: String,
     ) -> Result<Self, DomainError> {
-        if title.is_empty() {
-            return Err(DomainError::Validation("Title cannot be empty".into()));
-        }
-        Ok(Self {
+        Self::new_with_warnings(id, user_id, title, description).map(|(task, _)| task)
+    }
+
+    /// Create a new task, also reporting the soft rules the input broke
+    ///
+    /// A title longer than [`TITLE_WARNING_LEN`] characters is accepted with a
+    /// `TITLE_TOO_LONG` warning.
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the normalized title is empty.
+    pub fn new_with_warnings(
+        id: TaskId,
+        user_id: UserId,
+        title: &str,
+        description: String,
+    ) -> Result<(Self, Vec<DomainWarning>), DomainError> {
+        let (title, warnings) = checked_title(title)?;
+        let task = Self {
             id,
             user_id,
             title,
             description,
             completed: false,
+            completed_at: None,
             updated_at: None,
-        })
+            checklist: Vec::new(),
+        };
+        Ok((task, warnings))
+    }
+
+    /// Create a task that was completed at `completed_at`, e.g. one imported with
+    /// its history from another system
+    ///
+    /// Tasks created through the API start open; only imports create them
+    /// completed. The title follows the rules of [`Task::new`].
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the normalized title is empty or
+    /// `completed_at` is after `now`.
+    pub fn new_completed(
+        id: TaskId,
+        user_id: UserId,
+        title: &str,
+        description: String,
+        completed_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<Self, DomainError> {
+        if completed_at > now {
+            return Err(DomainError::validation("Completion time", COMPLETED_IN_THE_FUTURE));
+        }
+        let task = Self::new(id, user_id, title, description)?;
+        Ok(Self { completed: true, completed_at: Some(completed_at), ..task })
     }
 
-    /// Reconstitute a task from persistence (bypasses business rules)
+    /// Reconstitute a task from persistence (bypasses business rules and
+    /// title normalization)
+    #[must_use]
     pub fn reconstitute(
         id: TaskId,
         user_id: UserId,
@@ -45,40 +142,221 @@ impl Task {
         completed: bool,
         updated_at: Option<chrono::DateTime<chrono::Utc>>,
     ) -> Self {
-        Self { id, user_id, title, description, completed, updated_at }
+        Self {
+            id,
+            user_id,
+            title,
+            description,
+            completed,
+            completed_at: None,
+            updated_at,
+            checklist: Vec::new(),
+        }
+    }
+
+    /// The reconstituted task with its stored `checklist` (bypasses the checklist rules)
+    #[must_use]
+    pub fn with_checklist(self, checklist: Vec<ChecklistItem>) -> Self {
+        Self { checklist, ..self }
     }
 
     /// Get user ID
+    #[must_use]
     pub fn user_id(&self) -> &UserId {
         &self.user_id
     }
 
     /// Get task title
+    #[must_use]
     pub fn title(&self) -> &str {
         &self.title
     }
 
     /// Get task description
+    #[must_use]
     pub fn description(&self) -> &str {
         &self.description
     }
 
+    /// Get the last persisted modification time (`None` until first persisted)
+    #[must_use]
+    pub fn updated_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.updated_at
+    }
+
     /// Check if task is completed
+    #[must_use]
     pub fn is_completed(&self) -> bool {
         self.completed
     }
 
+    /// When a task created completed by [`Task::new_completed`] was completed; `None`
+    /// for other tasks, whose completion time storage records as it happens
+    #[must_use]
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.completed_at
+    }
+
+    /// Checklist items, in the order they were added
+    #[must_use]
+    pub fn checklist(&self) -> &[ChecklistItem] {
+        &self.checklist
+    }
+
+    /// Append an unchecked item with `text`, trimmed, to the checklist, returning it
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the trimmed text is empty or longer than
+    /// [`MAX_CHECKLIST_ITEM_LEN`](checklist::MAX_CHECKLIST_ITEM_LEN) characters, or the
+    /// checklist already has [`MAX_CHECKLIST_ITEMS`](checklist::MAX_CHECKLIST_ITEMS)
+    /// items; the task is left unchanged.
+    pub fn add_item(&mut self, text: &str) -> Result<ChecklistItem, DomainError> {
+        checklist::check_room(&self.checklist)?;
+        let item = ChecklistItem::new(text)?;
+        self.checklist.push(item.clone());
+        Ok(item)
+    }
+
+    /// Check (`done`) or uncheck the checklist item `id`
+    ///
+    /// # Errors
+    /// Returns `DomainError::NotFound` if the checklist has no such item.
+    pub fn toggle_item(
+        &mut self,
+        id: &ChecklistItemId,
+        done: bool,
+    ) -> Result<&ChecklistItem, DomainError> {
+        let item = self
+            .checklist
+            .iter_mut()
+            .find(|item| item.id() == id)
+            .ok_or_else(|| DomainError::not_found(ChecklistItemId::entity_name()))?;
+        item.set_done(done);
+        Ok(item)
+    }
+
+    /// Remove the checklist item `id`, returning it
+    ///
+    /// # Errors
+    /// Returns `DomainError::NotFound` if the checklist has no such item.
+    pub fn remove_item(&mut self, id: &ChecklistItemId) -> Result<ChecklistItem, DomainError> {
+        let position = self
+            .checklist
+            .iter()
+            .position(|item| item.id() == id)
+            .ok_or_else(|| DomainError::not_found(ChecklistItemId::entity_name()))?;
+        Ok(self.checklist.remove(position))
+    }
+
+    /// Soft rules completing the task breaks: an [`UNCHECKED_CHECKLIST_ITEMS`] warning
+    /// while checklist items are unchecked, or a refusal when `require_checked`
+    ///
+    /// # Errors
+    /// Returns `DomainError::Conflict` for unchecked items when `require_checked`.
+    pub fn completion_warnings(
+        &self,
+        require_checked: bool,
+    ) -> Result<Vec<DomainWarning>, DomainError> {
+        let unchecked = self.checklist.iter().filter(|item| !item.is_done()).count();
+        if unchecked == 0 {
+            return Ok(Vec::new());
+        }
+        let message = format!("Task has {unchecked} unchecked checklist item(s)");
+        if require_checked {
+            return Err(DomainError::Conflict(message));
+        }
+        Ok(vec![DomainWarning { code: UNCHECKED_CHECKLIST_ITEMS, message, field: "checklist" }])
+    }
+
+    /// Replace title and description under the same rules as [`Task::new_with_warnings`],
+    /// returning the soft rules the input broke
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the normalized title is empty; the task is
+    /// left unchanged.
+    pub fn edit(
+        &mut self,
+        title: &str,
+        description: String,
+    ) -> Result<Vec<DomainWarning>, DomainError> {
+        let (title, warnings) = checked_title(title)?;
+        self.title = title;
+        self.description = description;
+        Ok(warnings)
+    }
+
     /// Mark task as completed
     ///
     /// # Errors
-    /// Returns `DomainError::Validation` if the task is already completed.
+    /// Returns `DomainError::Conflict` if the task is already completed.
     pub fn complete(&mut self) -> Result<(), DomainError> {
         if self.completed {
-            return Err(DomainError::Validation("Task is already completed".into()));
+            return Err(DomainError::Conflict(ALREADY_COMPLETED.into()));
         }
         self.completed = true;
         Ok(())
     }
+
+    /// The task as a JSON snapshot with its `schema_version`, as recorded in the audit
+    /// trail of an onboarding
+    #[must_use]
+    pub fn to_snapshot(&self) -> serde_json::Value {
+        let fields = TaskSnapshot {
+            id: self.id.clone(),
+            user_id: self.user_id.clone(),
+            title: self.title.clone(),
+            description: self.description.clone(),
+            completed: self.completed,
+            updated_at: self.updated_at,
+            checklist: self.checklist.clone(),
+        };
+        snapshot::write(&fields, TASK_SNAPSHOT_VERSION)
+    }
+
+    /// Read a task back from a snapshot of any version up to
+    /// [`TASK_SNAPSHOT_VERSION`]
+    ///
+    /// Unlike [`Task::reconstitute`], the IDs must not be empty, the title must not
+    /// be blank and the checklist must keep its rules, as snapshots may come from
+    /// outside, e.g. a data import. The title is kept as it is, so a snapshot reads
+    /// back unchanged. Version 1 snapshots have no checklist.
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the snapshot has an unknown version, lacks
+    /// a field, has an empty ID, a blank title or a broken checklist.
+    pub fn from_snapshot(snapshot: serde_json::Value) -> Result<Self, DomainError> {
+        let fields: TaskSnapshot =
+            snapshot::read(snapshot, TaskId::entity_name(), TASK_SNAPSHOT_VERSION)?;
+        if normalize_title(&fields.title).is_empty() {
+            return Err(DomainError::validation("Title", CANNOT_BE_EMPTY));
+        }
+        checklist::validate(&fields.checklist)?;
+        Ok(Self {
+            id: TaskId::new(fields.id.value())?,
+            user_id: UserId::new(fields.user_id.value())?,
+            title: fields.title,
+            description: fields.description,
+            completed: fields.completed,
+            completed_at: None,
+            updated_at: fields.updated_at,
+            checklist: fields.checklist,
+        })
+    }
+}
+
+/// Fields of a task snapshot
+#[derive(Serialize, Deserialize)]
+struct TaskSnapshot {
+    id: TaskId,
+    user_id: UserId,
+    title: String,
+    description: String,
+    completed: bool,
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
+    /// Added in version 2
+    #[serde(default)]
+    checklist: Vec<ChecklistItem>,
 }
 
 impl Entity for Task {
@@ -93,42 +371,298 @@ impl Entity for Task {
 #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
 mod tests {
     use super::*;
+    use crate::features::task::domain::checklist::{MAX_CHECKLIST_ITEMS, MAX_CHECKLIST_ITEM_LEN};
 
     #[test]
     fn task_new_should_reject_empty_title() {
         let user_id = UserId::new("user1").expect("valid user id");
-        let result = Task::new(TaskId::generate(), user_id, String::new(), String::new());
+        let result = Task::new(TaskId::generate(), user_id, "", String::new());
         assert!(matches!(result, Err(DomainError::Validation(_))));
     }
 
     #[test]
     fn task_new_should_succeed_with_valid_input() {
         let user_id = UserId::new("user1").expect("valid user id");
-        let result = Task::new(TaskId::generate(), user_id, "Buy milk".to_string(), String::new());
+        let result = Task::new(TaskId::generate(), user_id, "Buy milk", String::new());
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn task_new_should_reject_whitespace_only_title() {
+        let user_id = UserId::new("user1").expect("valid user id");
+        let result =
+            Task::new(TaskId::generate(), user_id, " \t\n\u{00A0}\u{3000} ", String::new());
+        assert!(matches!(result, Err(DomainError::Validation(_))));
+    }
+
+    #[test]
+    fn task_new_should_normalize_title_whitespace() {
+        let cases = [
+            ("  Buy milk  ", "Buy milk"),
+            ("Buy  milk", "Buy milk"),
+            ("Buy\tmilk", "Buy milk"),
+            ("\nBuy\r\n\nmilk\n", "Buy milk"),
+            ("Buy\u{00A0}\u{2003}milk\u{3000}", "Buy milk"),
+        ];
+        for (input, expected) in cases {
+            let user_id = UserId::new("user1").expect("valid user id");
+            let task =
+                Task::new(TaskId::generate(), user_id, input, String::new()).expect("valid task");
+            assert_eq!(task.title(), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn task_reconstitute_should_not_normalize_title() {
+        let task = Task::reconstitute(
+            TaskId::generate(),
+            UserId::new("user1").expect("valid user id"),
+            "  Buy  milk ".to_string(),
+            String::new(),
+            false,
+            None,
+        );
+        assert_eq!(task.title(), "  Buy  milk ");
+    }
+
     #[test]
     fn task_complete_should_mark_as_completed() {
         let user_id = UserId::new("user1").expect("valid user id");
-        let mut task = Task::new(TaskId::generate(), user_id, "Buy milk".to_string(), String::new())
-            .expect("valid task");
+        let mut task =
+            Task::new(TaskId::generate(), user_id, "Buy milk", String::new()).expect("valid task");
         assert!(!task.is_completed());
         task.complete().expect("first complete should succeed");
         assert!(task.is_completed());
     }
 
+    #[test]
+    fn task_new_completed_should_be_completed_at_a_past_time_only() {
+        let now = Utc::now();
+        let user_id = UserId::new("user1").expect("valid user id");
+        let new = |title: &str, completed_at| {
+            let id = TaskId::generate();
+            Task::new_completed(id, user_id.clone(), title, String::new(), completed_at, now)
+        };
+
+        let yesterday = now - chrono::TimeDelta::days(1);
+        let mut task = new("  Buy  milk ", yesterday).expect("valid task");
+        assert!(task.is_completed());
+        assert_eq!((task.title(), task.completed_at()), ("Buy milk", Some(yesterday)));
+        assert!(matches!(task.complete(), Err(DomainError::Conflict(_))));
+        assert!(new("Buy milk", now).is_ok());
+
+        let future = new("Buy milk", now + chrono::TimeDelta::seconds(1));
+        let message = "Completion time cannot be in the future";
+        assert!(matches!(future, Err(DomainError::Validation(m)) if m == message));
+        assert!(matches!(new(" ", yesterday), Err(DomainError::Validation(_))));
+        let open = Task::new(TaskId::generate(), user_id.clone(), "Buy milk", String::new());
+        assert_eq!(open.expect("valid task").completed_at(), None);
+    }
+
     #[test]
     fn task_complete_should_reject_already_completed() {
         let user_id = UserId::new("user1").expect("valid user id");
-        let mut task = Task::new(TaskId::generate(), user_id, "Buy milk".to_string(), String::new())
-            .expect("valid task");
+        let mut task =
+            Task::new(TaskId::generate(), user_id, "Buy milk", String::new()).expect("valid task");
         task.complete().expect("first complete should succeed");
-        assert!(matches!(task.complete(), Err(DomainError::Validation(_))));
+        assert!(matches!(task.complete(), Err(DomainError::Conflict(_))));
+    }
+
+    #[test]
+    fn task_edit_should_normalize_and_keep_task_on_empty_title() {
+        let user_id = UserId::new("user1").expect("valid user id");
+        let mut task =
+            Task::new(TaskId::generate(), user_id, "Buy milk", String::new()).expect("valid task");
+        let warnings = task.edit("  Buy  bread ", "Wholegrain".into()).expect("valid edit");
+        assert!(warnings.is_empty());
+        assert_eq!((task.title(), task.description()), ("Buy bread", "Wholegrain"));
+
+        assert!(matches!(task.edit(" ", String::new()), Err(DomainError::Validation(_))));
+        assert_eq!((task.title(), task.description()), ("Buy bread", "Wholegrain"));
     }
 
     #[test]
     fn task_id_new_should_reject_empty() {
         assert!(matches!(TaskId::new(""), Err(DomainError::Validation(_))));
     }
+
+    #[test]
+    fn task_new_with_warnings_should_flag_long_title_only() {
+        let user_id = UserId::new("user1").expect("valid user id");
+        let long = "x".repeat(TITLE_WARNING_LEN + 1);
+        let (task, warnings) =
+            Task::new_with_warnings(TaskId::generate(), user_id.clone(), &long, String::new())
+                .expect("long title is accepted");
+        assert_eq!(task.title(), long);
+        assert_eq!(warnings.iter().map(|w| w.code).collect::<Vec<_>>(), ["TITLE_TOO_LONG"]);
+
+        let at_limit = "x".repeat(TITLE_WARNING_LEN);
+        let (_, warnings) =
+            Task::new_with_warnings(TaskId::generate(), user_id, &at_limit, String::new())
+                .expect("valid task");
+        assert!(warnings.is_empty());
+    }
+
+    /// An open task whose checklist is still empty
+    fn empty_task() -> Task {
+        Task::new(TaskId::generate(), UserId::generate(), "Pack", String::new())
+            .expect("valid task")
+    }
+
+    #[test]
+    fn add_item_should_append_unchecked_trimmed_items() {
+        let mut task = empty_task();
+        let first = task.add_item("  Socks ").expect("valid item");
+        let second = task.add_item("Shoes").expect("valid item");
+        assert_eq!((first.text(), first.is_done()), ("Socks", false));
+        assert_ne!(first.id(), second.id());
+        assert_eq!(task.checklist(), [first, second]);
+    }
+
+    #[test]
+    fn add_item_should_reject_invalid_text_and_keep_the_checklist() {
+        let mut task = empty_task();
+        let too_long = "x".repeat(MAX_CHECKLIST_ITEM_LEN + 1);
+        for text in ["", " \t\n", too_long.as_str()] {
+            assert!(matches!(task.add_item(text), Err(DomainError::Validation(_))), "{text:?}");
+        }
+        assert!(task.checklist().is_empty());
+        task.add_item(&"x".repeat(MAX_CHECKLIST_ITEM_LEN)).expect("longest text");
+    }
+
+    #[test]
+    fn add_item_should_refuse_more_than_the_maximum_items() {
+        let mut task = empty_task();
+        for n in 0..MAX_CHECKLIST_ITEMS {
+            task.add_item(&format!("Item {n}")).expect("room left");
+        }
+        assert!(matches!(task.add_item("One more"), Err(DomainError::Validation(_))));
+        assert_eq!(task.checklist().len(), MAX_CHECKLIST_ITEMS);
+    }
+
+    #[test]
+    fn toggle_item_should_check_and_uncheck_known_items_only() {
+        let mut task = empty_task();
+        let item = task.add_item("Socks").expect("valid item");
+        assert!(task.toggle_item(item.id(), true).expect("known item").is_done());
+        assert!(task.toggle_item(item.id(), true).expect("known item").is_done());
+        assert!(!task.toggle_item(item.id(), false).expect("known item").is_done());
+        let unknown = ChecklistItemId::generate();
+        assert!(matches!(task.toggle_item(&unknown, true), Err(DomainError::NotFound(_))));
+    }
+
+    #[test]
+    fn remove_item_should_remove_known_items_only() {
+        let mut task = empty_task();
+        let socks = task.add_item("Socks").expect("valid item");
+        let shoes = task.add_item("Shoes").expect("valid item");
+        assert_eq!(task.remove_item(socks.id()).expect("known item"), socks);
+        assert_eq!(task.checklist(), [shoes]);
+        assert!(matches!(task.remove_item(socks.id()), Err(DomainError::NotFound(_))));
+    }
+
+    #[test]
+    fn completion_warnings_should_report_or_refuse_unchecked_items() {
+        let mut task = empty_task();
+        assert!(task.completion_warnings(true).expect("no items").is_empty());
+        let item = task.add_item("Socks").expect("valid item");
+        task.add_item("Shoes").expect("valid item");
+
+        let warnings = task.completion_warnings(false).expect("warned only");
+        let codes: Vec<_> = warnings.iter().map(|w| (w.code, w.field)).collect();
+        assert_eq!(codes, [(UNCHECKED_CHECKLIST_ITEMS, "checklist")]);
+        assert!(warnings[0].message.contains('2'), "{}", warnings[0].message);
+        assert!(matches!(task.completion_warnings(true), Err(DomainError::Conflict(_))));
+
+        task.toggle_item(item.id(), true).expect("known item");
+        task.remove_item(&task.checklist()[1].id().clone()).expect("known item");
+        assert!(task.completion_warnings(true).expect("all checked").is_empty());
+    }
+
+    /// Every field of `task` a snapshot keeps
+    fn fields(task: &Task) -> (String, String, String, String, bool, Option<DateTime<Utc>>) {
+        let (id, user_id) = (task.id().value().to_owned(), task.user_id().value().to_owned());
+        let (title, description) = (task.title.clone(), task.description.clone());
+        (id, user_id, title, description, task.completed, task.updated_at)
+    }
+
+    #[test]
+    fn snapshots_should_round_trip() {
+        let titles = ["Buy milk", "  not  normalized\t", "\"Quoted\" \\ escaped", "日本語"];
+        let descriptions = ["", "line\nbreaks\r\n and {\"json\": true}"];
+        let times = [None, DateTime::from_timestamp(1_700_000_000, 456_789)];
+        for (i, title) in titles.iter().enumerate() {
+            for description in descriptions {
+                for (completed, updated_at) in [false, true].into_iter().zip(times) {
+                    let task = Task::reconstitute(
+                        TaskId::from_trusted(format!("task-{i}")),
+                        UserId::from_trusted("user1".into()),
+                        (*title).to_owned(),
+                        description.to_owned(),
+                        completed,
+                        updated_at,
+                    );
+                    let read = Task::from_snapshot(task.to_snapshot()).expect("round trip");
+                    assert_eq!(fields(&read), fields(&task), "{title}");
+                    assert!(read.checklist().is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn snapshots_should_round_trip_the_checklist() {
+        let mut task = Task::new(TaskId::generate(), UserId::generate(), "Pack", String::new())
+            .expect("valid task");
+        let socks = task.add_item("Socks").expect("valid item");
+        task.add_item("\"Quoted\" 日本語").expect("valid item");
+        task.toggle_item(socks.id(), true).expect("known item");
+        let snapshot = task.to_snapshot();
+        assert_eq!(snapshot["schema_version"], 2);
+        let read = Task::from_snapshot(snapshot).expect("round trip");
+        assert_eq!(read.checklist(), task.checklist());
+
+        let mut broken = task.to_snapshot();
+        broken["checklist"][1]["id"] = socks.id().value().into();
+        assert!(matches!(Task::from_snapshot(broken), Err(DomainError::Validation(_))));
+    }
+
+    #[test]
+    fn v1_snapshots_should_still_be_read() {
+        // As written by the first version; fields added since must take defaults
+        let v1 = serde_json::json!({
+            "schema_version": 1,
+            "id": "t1",
+            "user_id": "u1",
+            "title": "Buy milk",
+            "description": "",
+            "completed": true
+        });
+        let task = Task::from_snapshot(v1).expect("v1 snapshot");
+        let expected = ("t1".into(), "u1".into(), "Buy milk".into(), String::new(), true, None);
+        assert_eq!(fields(&task), expected);
+    }
+
+    #[test]
+    fn snapshots_should_need_ids_a_title_and_a_known_version() {
+        let valid = serde_json::json!({
+            "schema_version": 1, "id": "t1", "user_id": "u1", "title": "T",
+            "description": "", "completed": false
+        });
+        let invalid: [(&str, serde_json::Value); 4] = [
+            ("id", "".into()),
+            ("user_id", "".into()),
+            ("title", " \t ".into()),
+            ("schema_version", 3.into()),
+        ];
+        for (field, value) in invalid {
+            let mut snapshot = valid.clone();
+            snapshot[field] = value;
+            let result = Task::from_snapshot(snapshot);
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{field}");
+        }
+        let mut missing = valid;
+        missing.as_object_mut().map(|fields| fields.remove("completed"));
+        assert!(matches!(Task::from_snapshot(missing), Err(DomainError::Validation(_))));
+    }
 }
@@ -1,9 +1,24 @@
 //! Task domain layer
 
+pub mod attachment;
+pub mod checklist;
 pub mod entity;
+pub mod preferences;
 pub mod repository;
+pub mod stats;
+pub mod unit_of_work;
 pub mod value_objects;
 
-pub use entity::Task;
-pub use repository::TaskRepository;
-pub use value_objects::TaskId;
+pub use attachment::{Attachment, AttachmentRules, ATTACHMENT_TOO_LARGE, MAX_FILENAME_LEN};
+pub use checklist::{
+    ChecklistItem, MAX_CHECKLIST_ITEMS, MAX_CHECKLIST_ITEM_LEN, UNCHECKED_CHECKLIST_ITEMS,
+};
+pub use entity::{Task, TASK_SNAPSHOT_VERSION, TITLE_WARNING_LEN};
+pub use preferences::{TaskSort, TaskViewPreferences, MAX_PREFERRED_PAGE_SIZE};
+pub use repository::{
+    AttachmentRepository, BlobStorage, CompleteOutcome, PresignedUrl, TaskFilter, TaskRepository,
+    TaskViewPreferencesRepository, UpsertOutcome,
+};
+pub use stats::{HourlyTaskCounts, StatsWindow};
+pub use unit_of_work::{Transaction, UnitOfWork};
+pub use value_objects::{AttachmentId, ChecklistItemId, TaskId};
@@ -0,0 +1,151 @@
+//! Task view preferences: how a user's task listing is shown by default
+//!
+//! Stored per user, they apply to `GET /users/{id}/tasks` whenever the request leaves
+//! the matching parameter out; explicit parameters always win.
+
+use super::entity::Task;
+use crate::shared::domain::{DomainError, Entity};
+use std::cmp::Ordering;
+
+/// Largest page size a user may prefer; larger pages are still capped by the server's
+/// page limits when the preference applies
+pub const MAX_PREFERRED_PAGE_SIZE: u32 = 100;
+
+/// Order of a task listing; the ID breaks ties, so pages stay stable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TaskSort {
+    /// By ID, the order of every other listing
+    #[default]
+    Id,
+    /// By title, ignoring case
+    Title,
+    /// Most recently updated first
+    RecentlyUpdated,
+}
+
+impl TaskSort {
+    /// Every order, as named by [`TaskSort::name`]
+    pub const ALL: [Self; 3] = [Self::Id, Self::Title, Self::RecentlyUpdated];
+
+    /// Name of the order in requests and responses
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Title => "title",
+            Self::RecentlyUpdated => "recently_updated",
+        }
+    }
+
+    /// The order named `name`
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` listing the known orders for any other name.
+    pub fn parse(name: &str) -> Result<Self, DomainError> {
+        Self::ALL.into_iter().find(|sort| sort.name() == name).ok_or_else(|| {
+            let known: Vec<_> = Self::ALL.iter().map(|sort| sort.name()).collect();
+            DomainError::Validation(format!("Sort must be one of: {}", known.join(", ")))
+        })
+    }
+
+    /// How `a` and `b` compare in this order; titles compare by their lowercase bytes
+    #[must_use]
+    pub fn compare(self, a: &Task, b: &Task) -> Ordering {
+        let by_id = || a.id().value().cmp(b.id().value());
+        match self {
+            Self::Id => by_id(),
+            Self::Title => a.title().to_lowercase().cmp(&b.title().to_lowercase()).then_with(by_id),
+            Self::RecentlyUpdated => b.updated_at().cmp(&a.updated_at()).then_with(by_id),
+        }
+    }
+}
+
+/// How a user's task listing is shown when the request does not say otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskViewPreferences {
+    sort: TaskSort,
+    show_completed: bool,
+    page_size: Option<u32>,
+}
+
+/// Tasks by ID, completed ones included, in pages of the server's default size
+impl Default for TaskViewPreferences {
+    fn default() -> Self {
+        Self { sort: TaskSort::Id, show_completed: true, page_size: None }
+    }
+}
+
+impl TaskViewPreferences {
+    /// Preferences listing tasks in `sort` order, hiding completed ones unless
+    /// `show_completed`, in pages of `page_size` (the server's default when `None`)
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` for a page size outside 1 to
+    /// [`MAX_PREFERRED_PAGE_SIZE`].
+    pub fn new(
+        sort: TaskSort,
+        show_completed: bool,
+        page_size: Option<u32>,
+    ) -> Result<Self, DomainError> {
+        check_page_size(page_size)?;
+        Ok(Self { sort, show_completed, page_size })
+    }
+
+    /// Order of the listing
+    #[must_use]
+    pub fn sort(&self) -> TaskSort {
+        self.sort
+    }
+
+    /// Whether completed tasks are listed
+    #[must_use]
+    pub fn show_completed(&self) -> bool {
+        self.show_completed
+    }
+
+    /// Tasks per page; `None` for the server's default
+    #[must_use]
+    pub fn page_size(&self) -> Option<u32> {
+        self.page_size
+    }
+}
+
+/// Check a preferred page size: unset, or 1 to [`MAX_PREFERRED_PAGE_SIZE`]
+///
+/// # Errors
+/// Returns `DomainError::Validation` for any other size.
+pub fn check_page_size(page_size: Option<u32>) -> Result<(), DomainError> {
+    match page_size {
+        Some(size) if size == 0 || size > MAX_PREFERRED_PAGE_SIZE => {
+            Err(DomainError::Validation(format!(
+                "Page size must be between 1 and {MAX_PREFERRED_PAGE_SIZE}"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_should_parse_its_names_only() {
+        for sort in TaskSort::ALL {
+            assert_eq!(TaskSort::parse(sort.name()).ok(), Some(sort));
+        }
+        let error = TaskSort::parse("priority").err().map(|e| e.to_string()).unwrap_or_default();
+        assert!(error.contains("id, title, recently_updated"), "{error}");
+        assert!(TaskSort::parse("Title").is_err());
+    }
+
+    #[test]
+    fn page_size_should_be_bounded() {
+        let new = |size| TaskViewPreferences::new(TaskSort::Title, false, size);
+        assert!(new(None).is_ok());
+        assert_eq!(new(Some(1)).map(|p| p.page_size()).ok(), Some(Some(1)));
+        assert!(new(Some(MAX_PREFERRED_PAGE_SIZE)).is_ok());
+        assert!(matches!(new(Some(0)), Err(DomainError::Validation(_))));
+        assert!(matches!(new(Some(MAX_PREFERRED_PAGE_SIZE + 1)), Err(DomainError::Validation(_))));
+    }
+}
@@ -1,22 +1,241 @@
 //! Task repository port
 
+use super::attachment::Attachment;
 use super::entity::Task;
-use super::value_objects::TaskId;
-use crate::shared::domain::{DomainError, UserId};
+use super::preferences::{TaskSort, TaskViewPreferences};
+use super::stats::HourlyTaskCounts;
+use super::value_objects::{AttachmentId, TaskId};
+use crate::features::user::domain::UserDependents;
+use crate::shared::domain::{DomainError, Page, TenantId, UserId};
+use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
+
+/// Result of [`TaskRepository::complete_if_open`]
+#[derive(Debug)]
+pub enum CompleteOutcome {
+    /// The task was open and is now completed, as persisted
+    Completed(Task),
+    /// The task exists but was already completed; nothing was written
+    AlreadyCompleted,
+    /// No task with that ID exists
+    NotFound,
+}
+
+/// Filters of [`TaskRepository::find_page`] and [`TaskRepository::stream_filtered`];
+/// every filter that is set must match
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    /// Only tasks of this user
+    pub user_id: Option<UserId>,
+    /// Only completed (`true`) or open (`false`) tasks
+    pub completed: Option<bool>,
+    /// Only tasks whose title or description contains this lowercase text, ignoring case
+    pub search: Option<String>,
+}
+
+impl TaskFilter {
+    /// Whether `task` passes every filter
+    #[must_use]
+    pub fn matches(&self, task: &Task) -> bool {
+        self.user_id.as_ref().is_none_or(|user_id| task.user_id() == user_id)
+            && self.completed.is_none_or(|completed| task.is_completed() == completed)
+            && self.search.as_deref().is_none_or(|q| {
+                task.title().to_lowercase().contains(q)
+                    || task.description().to_lowercase().contains(q)
+            })
+    }
+}
+
+/// Result of [`TaskRepository::upsert`]
+#[derive(Debug)]
+pub enum UpsertOutcome {
+    /// No task with that ID existed; it was inserted
+    Created(Task),
+    /// The task existed and its title and description were replaced
+    Updated(Task),
+}
 
 /// Repository for task aggregate
+///
+/// Tasks are the dependents of their user: [`UserDependents::count_by_user_id`]
+/// counts the tasks a user owns. Every method is scoped to `tenant`: tasks of
+/// other tenants are neither found nor changed, and a task's user must belong to
+/// the task's tenant.
+///
+/// Methods returning several tasks order them by ID unless documented otherwise,
+/// whatever order the tasks were inserted in, so lists and their pages are stable.
 #[async_trait::async_trait]
-pub trait TaskRepository: Send + Sync {
+pub trait TaskRepository: UserDependents {
     /// Find task by ID
-    async fn find_by_id(&self, id: &TaskId) -> Result<Option<Task>, DomainError>;
-    /// Find tasks by user ID
-    async fn find_by_user_id(&self, user_id: &UserId) -> Result<Vec<Task>, DomainError>;
-    /// Find all tasks
-    async fn find_all(&self) -> Result<Vec<Task>, DomainError>;
-    /// Insert a new task (fails if ID already exists or FK violated)
-    async fn insert(&self, task: &Task) -> Result<(), DomainError>;
-    /// Update an existing task
-    async fn update(&self, task: &Task) -> Result<(), DomainError>;
+    async fn find_by_id(&self, tenant: &TenantId, id: &TaskId)
+        -> Result<Option<Task>, DomainError>;
+    /// Find tasks by user ID, ordered by ID
+    async fn find_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<Task>, DomainError>;
+    /// Find the ID of an open task of the user whose (already normalized)
+    /// title matches case-insensitively, if any; the lowest ID if several do
+    async fn exists_open_with_title(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        title: &str,
+    ) -> Result<Option<TaskId>, DomainError>;
+    /// Find the `page` of tasks matching `filter`, in `sort` order
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        filter: &TaskFilter,
+        sort: TaskSort,
+        page: Page,
+    ) -> Result<Vec<Task>, DomainError>;
+    /// Stream the tasks matching `filter`, ordered by ID, as they are read; for
+    /// exports too large to hold in memory
+    ///
+    /// A failure ends the stream with its error.
+    fn stream_filtered<'a>(
+        &'a self,
+        tenant: &'a TenantId,
+        filter: &'a TaskFilter,
+    ) -> BoxStream<'a, Result<Task, DomainError>>;
+    /// Find all tasks, ordered by ID, however many there are; for internal jobs, never
+    /// request handlers
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<Task>, DomainError>;
+    /// Find the `per_user_limit` most recently created tasks of each of `user_ids`,
+    /// newest first per user
+    async fn find_recent_by_users(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+        per_user_limit: u32,
+    ) -> Result<Vec<Task>, DomainError>;
+    /// Insert a new task (fails if ID already exists or FK violated),
+    /// returning the task as persisted
+    async fn insert(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError>;
+    /// Update an existing task, returning the task as persisted
+    /// (fails with `NotFound` if the task no longer exists)
+    async fn update(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError>;
+    /// Insert the task, or replace the title and description of the task with its ID,
+    /// returning the task as persisted. Completion state is never changed.
+    ///
+    /// Fails with `Conflict` if a task with the ID belongs to another user (or
+    /// tenant), and with `NotFound` if the user does not exist in the tenant.
+    async fn upsert(&self, tenant: &TenantId, task: &Task) -> Result<UpsertOutcome, DomainError>;
+    /// Atomically complete the task if it is still open.
+    ///
+    /// Of concurrent calls for the same open task exactly one sees `Completed`.
+    async fn complete_if_open(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<CompleteOutcome, DomainError>;
     /// Delete task by ID, returns true if a row was deleted
-    async fn delete(&self, id: &TaskId) -> Result<bool, DomainError>;
+    async fn delete(&self, tenant: &TenantId, id: &TaskId) -> Result<bool, DomainError>;
+    /// Delete task by ID, returning the task as it was deleted, read in the same
+    /// atomic step so no concurrent write lands in between; `None` if there was none
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError>;
+    /// Tasks created and completed per UTC hour since `since` (an hour boundary),
+    /// oldest first; hours without either are omitted
+    async fn hourly_counts(
+        &self,
+        tenant: &TenantId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<HourlyTaskCounts>, DomainError>;
+    /// Forget any state kept about the tasks of `user_id`, just moved to another user
+    /// through a [`UnitOfWork`](super::UnitOfWork); nothing to do unless they are cached
+    async fn tasks_reassigned(
+        &self,
+        _tenant: &TenantId,
+        _from: &UserId,
+    ) -> Result<(), DomainError> {
+        Ok(())
+    }
+    /// Forget any state kept about task `id`, just updated through a
+    /// [`UnitOfWork`](super::UnitOfWork); nothing to do unless it is cached
+    async fn task_updated(&self, _tenant: &TenantId, _id: &TaskId) -> Result<(), DomainError> {
+        Ok(())
+    }
+}
+
+/// Repository of the task view preferences of users, scoped to `tenant`
+///
+/// Preferences are removed with their user.
+#[async_trait::async_trait]
+pub trait TaskViewPreferencesRepository: Send + Sync {
+    /// Find the preferences of the user; `None` if they never saved any
+    async fn find(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Option<TaskViewPreferences>, DomainError>;
+    /// Save the preferences of the user, replacing any saved before (fails with
+    /// `NotFound` if the user does not exist)
+    async fn save(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        preferences: &TaskViewPreferences,
+    ) -> Result<(), DomainError>;
+}
+
+/// Repository of task attachment metadata
+///
+/// Every attachment that is removed, on its own or with its task, queues its blob
+/// for deletion from [`BlobStorage`] in the same write, so no blob outlives its
+/// metadata for longer than the deletion queue takes to drain. Every method but
+/// the queue's is scoped to `tenant`.
+#[async_trait::async_trait]
+pub trait AttachmentRepository: Send + Sync {
+    /// Insert a new attachment (fails with `NotFound` if its task does not exist)
+    async fn insert(&self, tenant: &TenantId, attachment: &Attachment) -> Result<(), DomainError>;
+    /// Find the attachments of a task, oldest first, then by ID
+    async fn find_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<Vec<Attachment>, DomainError>;
+    /// Delete an attachment of a task and queue its blob, returns true if a row was deleted
+    async fn delete(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+        id: &AttachmentId,
+    ) -> Result<bool, DomainError>;
+    /// Delete every attachment of a task and queue their blobs, returns how many were deleted
+    async fn delete_by_task(&self, tenant: &TenantId, task_id: &TaskId)
+        -> Result<u64, DomainError>;
+    /// Storage keys of up to `limit` queued blobs, oldest first
+    async fn queued_blob_deletions(&self, limit: u32) -> Result<Vec<String>, DomainError>;
+    /// Remove a blob from the deletion queue once it is deleted from storage
+    async fn dequeue_blob_deletion(&self, storage_key: &str) -> Result<(), DomainError>;
+}
+
+/// A URL granting one operation on a blob until it expires
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedUrl {
+    /// The URL; whoever holds it may perform the operation
+    pub url: String,
+    /// When the URL stops working
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Storage of attachment bytes, which clients reach through presigned URLs
+#[async_trait::async_trait]
+pub trait BlobStorage: Send + Sync {
+    /// URL to `PUT` the blob stored under `key`, sent with `content_type`
+    async fn issue_upload_url(
+        &self,
+        key: &str,
+        content_type: &str,
+    ) -> Result<PresignedUrl, DomainError>;
+    /// URL to `GET` the blob stored under `key`
+    async fn issue_download_url(&self, key: &str) -> Result<PresignedUrl, DomainError>;
+    /// Delete the blob stored under `key`; deleting a missing blob succeeds
+    async fn delete(&self, key: &str) -> Result<(), DomainError>;
 }
@@ -0,0 +1,87 @@
+//! Task activity statistics
+
+use crate::shared::domain::DomainError;
+use chrono::{DateTime, Utc};
+
+/// Largest window, in hours (30 days)
+const MAX_WINDOW_HOURS: u32 = 30 * 24;
+
+/// Time window of task statistics, a whole number of hours between 1h and 30d
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StatsWindow {
+    hours: u32,
+}
+
+impl Default for StatsWindow {
+    /// The last 24 hours
+    fn default() -> Self {
+        Self { hours: 24 }
+    }
+}
+
+impl StatsWindow {
+    /// Parse a window such as `6h` or `7d`
+    ///
+    /// # Errors
+    /// `Validation` when the format is not `<n>h` / `<n>d` or the window is
+    /// outside 1h–30d.
+    pub fn parse(value: &str) -> Result<Self, DomainError> {
+        let invalid = || {
+            DomainError::Validation(format!(
+                "window must be between 1h and 30d, as <hours>h or <days>d (got '{value}')"
+            ))
+        };
+        let unit = value.chars().last().ok_or_else(invalid)?;
+        let amount: u32 = value[..value.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+        let hours = match unit {
+            'h' => amount,
+            'd' => amount.checked_mul(24).ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+        if !(1..=MAX_WINDOW_HOURS).contains(&hours) {
+            return Err(invalid());
+        }
+        Ok(Self { hours })
+    }
+
+    /// Length of the window in hours, i.e. its number of hourly buckets
+    #[must_use]
+    pub fn hours(self) -> u32 {
+        self.hours
+    }
+}
+
+/// Number of tasks created and completed within one UTC hour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HourlyTaskCounts {
+    /// Start of the hour (UTC)
+    pub hour: DateTime<Utc>,
+    /// Tasks created within the hour
+    pub created: u64,
+    /// Tasks completed within the hour
+    pub completed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_should_accept_hours_and_days_within_bounds() {
+        assert_eq!(StatsWindow::parse("1h").map(StatsWindow::hours).ok(), Some(1));
+        assert_eq!(StatsWindow::parse("24h").map(StatsWindow::hours).ok(), Some(24));
+        assert_eq!(StatsWindow::parse("30d").map(StatsWindow::hours).ok(), Some(720));
+        assert_eq!(StatsWindow::default().hours(), 24);
+    }
+
+    #[test]
+    fn parse_should_reject_out_of_range_or_malformed_windows() {
+        for value in ["0h", "31d", "721h", "", "h", "24", "1w", "-1h", "1.5h", "99999999999d", "1é"]
+        {
+            assert!(
+                matches!(StatsWindow::parse(value), Err(DomainError::Validation(_))),
+                "{value}"
+            );
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Unit of work port: writes spanning users and tasks, applied together or not at all
+
+use super::entity::Task;
+use super::value_objects::TaskId;
+use crate::features::user::domain::User;
+use crate::shared::domain::{AuditEntry, DomainError, OutboxEvent, TenantId, UserId};
+
+/// Begins transactions writing users and tasks atomically
+///
+/// The repositories write each aggregate on its own; a use case changing several
+/// aggregates at once writes them through a [`Transaction`] instead.
+#[async_trait::async_trait]
+pub trait UnitOfWork: Send + Sync {
+    /// Begin a transaction; nothing it writes is visible to others until it commits
+    async fn begin(&self) -> Result<Box<dyn Transaction + '_>, DomainError>;
+}
+
+/// Writes of one unit of work
+///
+/// Dropping the transaction without committing it rolls back every write it made,
+/// so returning early on an error undoes the writes that succeeded before it. The
+/// writes fail as the repository methods of the same name do.
+///
+/// Rows read for update stay locked until the transaction ends. As with
+/// [`UserTransaction`](crate::features::user::domain::UserTransaction), users are
+/// locked before tasks and rows of one kind in ID order, so that no two
+/// transactions wait on each other.
+#[async_trait::async_trait]
+pub trait Transaction: Send {
+    /// Insert a new user (fails if the ID exists, or the email exists in the tenant)
+    async fn insert_user(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError>;
+    /// Find task by ID, locking it until the transaction ends
+    async fn find_task_for_update(
+        &mut self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError>;
+    /// Update an existing task, returning the task as persisted (fails with
+    /// `NotFound` if the task no longer exists)
+    async fn update_task(&mut self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError>;
+    /// Insert a new task, returning the task as persisted (fails if the ID exists,
+    /// or with `NotFound` if its user does not exist in the tenant, this
+    /// transaction's users included)
+    async fn insert_task(&mut self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError>;
+    /// Move the tasks of user `from` to user `to`, only the open ones when
+    /// `only_open`, returning how many moved (fails with `NotFound` if `to` does not
+    /// exist in the tenant)
+    async fn reassign_tasks(
+        &mut self,
+        tenant: &TenantId,
+        from: &UserId,
+        to: &UserId,
+        only_open: bool,
+    ) -> Result<u64, DomainError>;
+    /// Append `entry` to the audit trail of `tenant`
+    async fn record_audit(
+        &mut self,
+        tenant: &TenantId,
+        entry: &AuditEntry,
+    ) -> Result<(), DomainError>;
+    /// Write `event` to the outbox, published once the transaction commits
+    async fn record_event(
+        &mut self,
+        tenant: &TenantId,
+        event: &OutboxEvent,
+    ) -> Result<(), DomainError>;
+    /// Make every write of the transaction visible at once
+    async fn commit(self: Box<Self>) -> Result<(), DomainError>;
+}
@@ -1,6 +1,5 @@
 //! Task value objects
 
-use crate::shared::domain::value_objects::string_id;
-use serde::{Deserialize, Serialize};
-
-string_id!(TaskId, "Task");
+crate::string_id!(TaskId, "Task");
+crate::string_id!(AttachmentId, "Attachment");
+crate::string_id!(ChecklistItemId, "Checklist item");
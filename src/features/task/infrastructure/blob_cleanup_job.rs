@@ -0,0 +1,129 @@
+//! Background deletion of the blobs of deleted attachments
+
+use crate::features::task::domain::{AttachmentRepository, BlobStorage};
+use crate::shared::infrastructure::jobs::{BackgroundJob, JobContext};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Blobs deleted per batch; a run continues with the next batch while batches are full
+const BATCH_SIZE: u32 = 100;
+
+/// Deletes queued blobs from storage, then removes them from the queue
+///
+/// A blob that cannot be deleted stays queued and is retried on the next run, which
+/// is then recorded as failed.
+#[derive(Clone)]
+pub struct BlobCleanupJob {
+    attachments: Arc<dyn AttachmentRepository>,
+    storage: Arc<dyn BlobStorage>,
+}
+
+impl BlobCleanupJob {
+    /// Drain the deletion queue of `attachments` into `storage`
+    #[must_use]
+    pub fn new(attachments: Arc<dyn AttachmentRepository>, storage: Arc<dyn BlobStorage>) -> Self {
+        Self { attachments, storage }
+    }
+}
+
+#[async_trait::async_trait]
+impl BackgroundJob for BlobCleanupJob {
+    fn name(&self) -> &'static str {
+        "blob_cleanup"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_mins(1)
+    }
+
+    async fn run(&self, ctx: &JobContext) -> anyhow::Result<()> {
+        loop {
+            let keys = self.attachments.queued_blob_deletions(BATCH_SIZE).await?;
+            let mut failed = 0;
+            for key in &keys {
+                match self.storage.delete(key).await {
+                    Ok(()) => self.attachments.dequeue_blob_deletion(key).await?,
+                    Err(e) => {
+                        tracing::warn!("Could not delete blob '{key}': {e}");
+                        failed += 1;
+                    }
+                }
+            }
+            anyhow::ensure!(failed == 0, "{failed} of {} blobs could not be deleted", keys.len());
+            if keys.len() < BATCH_SIZE as usize || ctx.is_shutting_down() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::{Attachment, AttachmentRules, PresignedUrl, TaskId};
+    use crate::features::task::infrastructure::InMemoryAttachmentRepository;
+    use crate::shared::domain::{DomainError, TenantId};
+    use std::sync::Mutex;
+
+    /// Storage recording deletions and failing them for keys in `failing`
+    #[derive(Default)]
+    struct RecordingStorage {
+        deleted: Mutex<Vec<String>>,
+        failing: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BlobStorage for RecordingStorage {
+        async fn issue_upload_url(
+            &self,
+            _key: &str,
+            _content_type: &str,
+        ) -> Result<PresignedUrl, DomainError> {
+            unreachable!("the job only deletes")
+        }
+
+        async fn issue_download_url(&self, _key: &str) -> Result<PresignedUrl, DomainError> {
+            unreachable!("the job only deletes")
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), DomainError> {
+            if self.failing.lock().expect("lock").iter().any(|k| k == key) {
+                return Err(DomainError::Unavailable("storage down".into()));
+            }
+            self.deleted.lock().expect("lock").push(key.to_owned());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn run_should_delete_queued_blobs_and_keep_failed_ones_queued() {
+        let repo = Arc::new(InMemoryAttachmentRepository::default());
+        let storage = Arc::new(RecordingStorage::default());
+        let (tenant, task_id) = (TenantId::default(), TaskId::from_trusted("t1".into()));
+        let rules =
+            AttachmentRules { max_size_bytes: 10, content_types: vec!["text/plain".into()] };
+        let mut keys = Vec::new();
+        for name in ["a.txt", "b.txt"] {
+            let attachment =
+                Attachment::new(&tenant, task_id.clone(), name, "text/plain", 1, &rules)
+                    .expect("valid attachment");
+            repo.insert(&tenant, &attachment).await.expect("insert");
+            keys.push(attachment.storage_key().to_owned());
+        }
+        assert_eq!(repo.delete_by_task(&tenant, &task_id).await.expect("delete"), 2);
+        storage.failing.lock().expect("lock").push(keys[1].clone());
+
+        let job = BlobCleanupJob::new(repo.clone(), storage.clone());
+        let (_shutdown, receiver) = tokio::sync::watch::channel(());
+        let ctx = JobContext::new(receiver);
+        assert!(job.run(&ctx).await.is_err());
+        assert_eq!(*storage.deleted.lock().expect("lock"), [keys[0].clone()]);
+        assert_eq!(repo.queued_blob_deletions(10).await.expect("queue"), [keys[1].clone()]);
+
+        storage.failing.lock().expect("lock").clear();
+        job.run(&ctx).await.expect("run");
+        assert_eq!(*storage.deleted.lock().expect("lock"), keys);
+        assert!(repo.queued_blob_deletions(10).await.expect("queue").is_empty());
+    }
+}
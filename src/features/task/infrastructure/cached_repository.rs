@@ -0,0 +1,470 @@
+//! Task repository decorator caching single-task reads
+
+use crate::features::task::domain::{
+    CompleteOutcome, HourlyTaskCounts, Task, TaskFilter, TaskId, TaskRepository, TaskSort,
+    UpsertOutcome,
+};
+use crate::features::user::domain::UserDependents;
+use crate::shared::domain::{DomainError, Entity, Page, TenantId, UserId};
+use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
+use moka::future::Cache;
+use moka::ops::compute::Op;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Counter of [`CachedTaskRepository::find_by_id`] lookups, labelled with `result`
+/// (`hit`, `negative_hit` for a task known to be missing, or `miss`)
+pub const TASK_CACHE_LOOKUPS_TOTAL: &str = "task_cache_lookups_total";
+
+/// Serves `find_by_id` from a cache of the tasks most recently read or written
+///
+/// Writes go through to the wrapped repository first. The task it returns then
+/// replaces the cached one, unless the cached one has a newer `updated_at`, so a
+/// slow write never overwrites a faster, later one. Deletions and writes whose
+/// outcome is unknown invalidate instead, and a read that overlaps an invalidation
+/// is not cached, so a deleted task is never served.
+///
+/// Only this process's writes invalidate: with several instances, a task changed
+/// elsewhere is served stale for up to the TTL.
+///
+/// With [`Self::with_negative_ttl`], tasks found missing are remembered too, so
+/// repeated lookups of a deleted task stop reaching the wrapped repository. Inserts
+/// and upserts forget the task before returning, whatever their outcome, and a
+/// lookup that overlaps one is not remembered, so a created task is never reported
+/// missing by this process.
+pub struct CachedTaskRepository {
+    inner: Arc<dyn TaskRepository>,
+    cache: Cache<(TenantId, TaskId), Task>,
+    /// Incremented by every invalidation, so reads can tell they overlapped one
+    invalidations: AtomicU64,
+    missing: Option<MissingTasks>,
+}
+
+/// Tasks found missing by recent lookups
+struct MissingTasks {
+    cache: Cache<(TenantId, TaskId), ()>,
+    /// Incremented by every insert and upsert, so lookups can tell they overlapped one
+    creations: AtomicU64,
+}
+
+impl CachedTaskRepository {
+    /// Wrap `inner`, caching up to `capacity` tasks for `ttl` each
+    #[must_use]
+    pub fn new(inner: Arc<dyn TaskRepository>, ttl: Duration, capacity: u64) -> Self {
+        let cache = Cache::builder()
+            .time_to_live(ttl)
+            .max_capacity(capacity)
+            .support_invalidation_closures()
+            .build();
+        Self { inner, cache, invalidations: AtomicU64::new(0), missing: None }
+    }
+
+    /// Also remember tasks found missing, for `ttl` each
+    #[must_use]
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        let capacity = self.cache.policy().max_capacity().unwrap_or(u64::MAX);
+        let cache = Cache::builder().time_to_live(ttl).max_capacity(capacity).build();
+        self.missing = Some(MissingTasks { cache, creations: AtomicU64::new(0) });
+        self
+    }
+
+    /// Number of invalidations so far, to pass to [`Self::store`] after the inner call
+    fn epoch(&self) -> u64 {
+        self.invalidations.load(Ordering::SeqCst)
+    }
+
+    /// Cache `task`, read or written by a call that started at `epoch`, unless a
+    /// newer version of it is cached already; if anything was invalidated meanwhile
+    /// the task may be gone, so its entry is dropped instead
+    async fn store(&self, tenant: &TenantId, task: Task, epoch: u64) {
+        if self.epoch() != epoch {
+            self.invalidate(tenant, task.id()).await;
+            return;
+        }
+        let key = (tenant.clone(), task.id().clone());
+        self.cache
+            .entry(key)
+            .and_compute_with(|cached| async move {
+                match cached {
+                    Some(cached) if cached.value().updated_at() > task.updated_at() => Op::Nop,
+                    _ => Op::Put(task),
+                }
+            })
+            .await;
+    }
+
+    async fn invalidate(&self, tenant: &TenantId, id: &TaskId) {
+        self.invalidations.fetch_add(1, Ordering::SeqCst);
+        self.cache.invalidate(&(tenant.clone(), id.clone())).await;
+    }
+
+    /// Drop the cached tasks of `user_id` in `tenant`, changed by a write that
+    /// bypassed this repository
+    fn forget_tasks_of(&self, tenant: &TenantId, user_id: &UserId) -> Result<(), DomainError> {
+        self.invalidations.fetch_add(1, Ordering::SeqCst);
+        let (owner_tenant, owner) = (tenant.clone(), user_id.clone());
+        self.cache
+            .invalidate_entries_if(move |(t, _), task| {
+                *t == owner_tenant && *task.user_id() == owner
+            })
+            .map(|_| ())
+            .map_err(|e| DomainError::Infrastructure(format!("Task cache: {e}")))
+    }
+
+    /// Number of inserts and upserts so far, to pass to [`Self::store_missing`]
+    fn creations(&self) -> u64 {
+        self.missing.as_ref().map_or(0, |missing| missing.creations.load(Ordering::SeqCst))
+    }
+
+    fn is_missing(&self, key: &(TenantId, TaskId)) -> bool {
+        self.missing.as_ref().is_some_and(|missing| missing.cache.contains_key(key))
+    }
+
+    /// Remember that task `key` was missing for a lookup that started at `creations`,
+    /// unless it may have been created meanwhile
+    async fn store_missing(&self, key: (TenantId, TaskId), creations: u64) {
+        let Some(missing) = &self.missing else { return };
+        if self.creations() != creations {
+            return;
+        }
+        missing.cache.insert(key.clone(), ()).await;
+        // A creation between the check and the insert found no marker to forget
+        if self.creations() != creations {
+            missing.cache.invalidate(&key).await;
+        }
+    }
+
+    /// Forget that task `id` was missing, as it may exist now
+    async fn forget_missing(&self, tenant: &TenantId, id: &TaskId) {
+        if let Some(missing) = &self.missing {
+            missing.creations.fetch_add(1, Ordering::SeqCst);
+            missing.cache.invalidate(&(tenant.clone(), id.clone())).await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskRepository for CachedTaskRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        let key = (tenant.clone(), id.clone());
+        if let Some(task) = self.cache.get(&key).await {
+            metrics::counter!(TASK_CACHE_LOOKUPS_TOTAL, "result" => "hit").increment(1);
+            return Ok(Some(task));
+        }
+        if self.is_missing(&key) {
+            metrics::counter!(TASK_CACHE_LOOKUPS_TOTAL, "result" => "negative_hit").increment(1);
+            return Ok(None);
+        }
+        metrics::counter!(TASK_CACHE_LOOKUPS_TOTAL, "result" => "miss").increment(1);
+        let (epoch, creations) = (self.epoch(), self.creations());
+        let task = self.inner.find_by_id(tenant, id).await?;
+        match &task {
+            Some(task) => self.store(tenant, task.clone(), epoch).await,
+            None => self.store_missing(key, creations).await,
+        }
+        Ok(task)
+    }
+
+    async fn find_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<Task>, DomainError> {
+        self.inner.find_by_user_id(tenant, user_id).await
+    }
+
+    async fn exists_open_with_title(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        title: &str,
+    ) -> Result<Option<TaskId>, DomainError> {
+        self.inner.exists_open_with_title(tenant, user_id, title).await
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        filter: &TaskFilter,
+        sort: TaskSort,
+        page: Page,
+    ) -> Result<Vec<Task>, DomainError> {
+        self.inner.find_page(tenant, filter, sort, page).await
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        tenant: &'a TenantId,
+        filter: &'a TaskFilter,
+    ) -> BoxStream<'a, Result<Task, DomainError>> {
+        self.inner.stream_filtered(tenant, filter)
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<Task>, DomainError> {
+        self.inner.find_all_unbounded(tenant).await
+    }
+
+    async fn find_recent_by_users(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+        per_user_limit: u32,
+    ) -> Result<Vec<Task>, DomainError> {
+        self.inner.find_recent_by_users(tenant, user_ids, per_user_limit).await
+    }
+
+    async fn insert(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        let epoch = self.epoch();
+        let inserted = self.inner.insert(tenant, task).await;
+        self.forget_missing(tenant, task.id()).await;
+        let inserted = inserted?;
+        self.store(tenant, inserted.clone(), epoch).await;
+        Ok(inserted)
+    }
+
+    async fn update(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        let epoch = self.epoch();
+        match self.inner.update(tenant, task).await {
+            Ok(updated) => {
+                self.store(tenant, updated.clone(), epoch).await;
+                Ok(updated)
+            }
+            Err(e) => {
+                self.invalidate(tenant, task.id()).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn upsert(&self, tenant: &TenantId, task: &Task) -> Result<UpsertOutcome, DomainError> {
+        let epoch = self.epoch();
+        let outcome = self.inner.upsert(tenant, task).await;
+        self.forget_missing(tenant, task.id()).await;
+        let outcome = outcome?;
+        let (UpsertOutcome::Created(persisted) | UpsertOutcome::Updated(persisted)) = &outcome;
+        self.store(tenant, persisted.clone(), epoch).await;
+        Ok(outcome)
+    }
+
+    async fn complete_if_open(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<CompleteOutcome, DomainError> {
+        let epoch = self.epoch();
+        let outcome = self.inner.complete_if_open(tenant, id).await?;
+        match &outcome {
+            CompleteOutcome::Completed(task) => self.store(tenant, task.clone(), epoch).await,
+            // A cached open task is out of date, a cached missing one must go
+            CompleteOutcome::AlreadyCompleted | CompleteOutcome::NotFound => {
+                self.invalidate(tenant, id).await;
+            }
+        }
+        Ok(outcome)
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &TaskId) -> Result<bool, DomainError> {
+        let deleted = self.inner.delete(tenant, id).await;
+        self.invalidate(tenant, id).await;
+        deleted
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        let deleted = self.inner.delete_returning(tenant, id).await;
+        self.invalidate(tenant, id).await;
+        deleted
+    }
+
+    async fn hourly_counts(
+        &self,
+        tenant: &TenantId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<HourlyTaskCounts>, DomainError> {
+        self.inner.hourly_counts(tenant, since).await
+    }
+
+    async fn tasks_reassigned(&self, tenant: &TenantId, from: &UserId) -> Result<(), DomainError> {
+        self.forget_tasks_of(tenant, from)?;
+        self.inner.tasks_reassigned(tenant, from).await
+    }
+
+    async fn task_updated(&self, tenant: &TenantId, id: &TaskId) -> Result<(), DomainError> {
+        self.invalidate(tenant, id).await;
+        self.inner.task_updated(tenant, id).await
+    }
+}
+
+#[async_trait::async_trait]
+impl UserDependents for CachedTaskRepository {
+    async fn count_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<u64, DomainError> {
+        self.inner.count_by_user_id(tenant, user_id).await
+    }
+
+    async fn count_by_user_ids(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+    ) -> Result<HashMap<UserId, u64>, DomainError> {
+        self.inner.count_by_user_ids(tenant, user_ids).await
+    }
+
+    async fn user_deleted(&self, tenant: &TenantId, user_id: &UserId) -> Result<(), DomainError> {
+        self.forget_tasks_of(tenant, user_id)?;
+        self.inner.user_deleted(tenant, user_id).await
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::infrastructure::{
+        InMemoryTaskRepository, InstrumentedTaskRepository,
+    };
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::test_support::recorded_repository_calls;
+    use crate::testing::repository_contract;
+
+    const TTL: Duration = Duration::from_mins(1);
+
+    fn task(user: &str) -> Task {
+        let user_id = UserId::new(user).expect("valid user id");
+        Task::new(TaskId::generate(), user_id, "Task", String::new()).expect("valid task")
+    }
+
+    /// Insert `task` behind the cache's back and read it twice through `repo`
+    async fn read_twice(inner: &dyn TaskRepository, repo: &dyn TaskRepository, task: &Task) {
+        let tenant = TenantId::default();
+        inner.insert(&tenant, task).await.expect("insert");
+        for _ in 0..2 {
+            let found = repo.find_by_id(&tenant, task.id()).await.expect("find");
+            assert_eq!(found.as_ref().map(Task::title), Some("Task"));
+        }
+    }
+
+    #[test]
+    fn cache_should_compose_with_instrumentation_in_either_order() {
+        let calls = recorded_repository_calls(async {
+            let inner = Arc::new(InMemoryTaskRepository::default());
+            let instrumented = Arc::new(InstrumentedTaskRepository::new(inner.clone()));
+            let repo = CachedTaskRepository::new(instrumented, TTL, 100);
+            read_twice(inner.as_ref(), &repo, &task("user1")).await;
+        });
+        assert_eq!(calls, ["task.find_by_id ok: 1"], "only the miss reaches the repository");
+
+        let calls = recorded_repository_calls(async {
+            let inner = Arc::new(InMemoryTaskRepository::default());
+            let cached = Arc::new(CachedTaskRepository::new(inner.clone(), TTL, 100));
+            let repo = InstrumentedTaskRepository::new(cached);
+            read_twice(inner.as_ref(), &repo, &task("user1")).await;
+        });
+        assert_eq!(calls, ["task.find_by_id ok: 2"], "every call is timed, hits included");
+    }
+
+    #[test]
+    fn repeated_lookups_of_a_missing_task_should_reach_the_repository_once() {
+        let calls = recorded_repository_calls(async {
+            let inner = Arc::new(InMemoryTaskRepository::default());
+            let instrumented = Arc::new(InstrumentedTaskRepository::new(inner));
+            let repo = CachedTaskRepository::new(instrumented, TTL, 100).with_negative_ttl(TTL);
+            let (tenant, id) = (TenantId::default(), TaskId::generate());
+            let find = || async { repo.find_by_id(&tenant, &id).await.expect("find") };
+
+            assert!(find().await.is_none());
+            let stampede = futures_util::future::join_all((0..100).map(|_| find())).await;
+            assert!(stampede.iter().all(Option::is_none));
+        });
+        assert_eq!(calls, ["task.find_by_id ok: 1"], "only the first lookup misses");
+    }
+
+    #[tokio::test]
+    async fn creating_a_missing_task_should_forget_it_was_missing() {
+        let inner = Arc::new(InMemoryTaskRepository::default());
+        let repo = CachedTaskRepository::new(inner.clone(), TTL, 100).with_negative_ttl(TTL);
+        let tenant = TenantId::default();
+        let (inserted, upserted) = (task("user1"), task("user1"));
+        for task in [&inserted, &upserted] {
+            assert!(repo.find_by_id(&tenant, task.id()).await.expect("find").is_none());
+        }
+        // Created behind the cache's back, the task is still known to be missing
+        inner.insert(&tenant, &upserted).await.expect("insert");
+        assert!(repo.find_by_id(&tenant, upserted.id()).await.expect("find").is_none());
+
+        repo.insert(&tenant, &inserted).await.expect("insert");
+        // As `PUT /tasks/{id}` does with a client-chosen ID
+        repo.upsert(&tenant, &upserted).await.expect("upsert");
+        for task in [&inserted, &upserted] {
+            // Also once the cached task is evicted
+            assert!(!repo.is_missing(&(tenant.clone(), task.id().clone())));
+            assert!(repo.find_by_id(&tenant, task.id()).await.expect("find").is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_should_refresh_the_cached_task() {
+        let inner = Arc::new(InMemoryTaskRepository::default());
+        let repo = CachedTaskRepository::new(inner.clone(), TTL, 100);
+        let tenant = TenantId::default();
+        let mut task = repo.insert(&tenant, &task("user1")).await.expect("insert");
+        task.edit("Renamed", String::new()).expect("valid title");
+        repo.update(&tenant, &task).await.expect("update");
+        repo.complete_if_open(&tenant, task.id()).await.expect("complete");
+
+        let cached = repo.find_by_id(&tenant, task.id()).await.expect("find").expect("task");
+        assert_eq!(cached.title(), "Renamed");
+        assert!(cached.is_completed());
+    }
+
+    #[tokio::test]
+    async fn updates_of_deleted_tasks_should_fail() {
+        let users = InMemoryUserRepository::default();
+        let inner = Arc::new(InMemoryTaskRepository::default());
+        let tasks = CachedTaskRepository::new(inner, TTL, 100);
+        repository_contract::tasks_should_not_update_deleted_rows(&users, &tasks).await;
+    }
+
+    #[tokio::test]
+    async fn deleted_tasks_should_never_be_served() {
+        let inner = Arc::new(InMemoryTaskRepository::default());
+        let repo = CachedTaskRepository::new(inner.clone(), TTL, 100);
+        let tenant = TenantId::default();
+        let find = |id: TaskId| {
+            let (repo, tenant) = (&repo, &tenant);
+            async move { repo.find_by_id(tenant, &id).await.expect("find") }
+        };
+
+        let deleted = repo.insert(&tenant, &task("user1")).await.expect("insert");
+        assert!(repo.delete(&tenant, deleted.id()).await.expect("delete"));
+        assert!(find(deleted.id().clone()).await.is_none());
+
+        // Deleted elsewhere, found missing when completing it
+        let gone = repo.insert(&tenant, &task("user1")).await.expect("insert");
+        inner.delete(&tenant, gone.id()).await.expect("delete");
+        repo.complete_if_open(&tenant, gone.id()).await.expect("complete");
+        assert!(find(gone.id().clone()).await.is_none());
+
+        // Cascade-deleted with their user
+        let owned = repo.insert(&tenant, &task("user1")).await.expect("insert");
+        let other = repo.insert(&tenant, &task("user2")).await.expect("insert");
+        inner.delete(&tenant, owned.id()).await.expect("delete");
+        let user1 = UserId::new("user1").expect("valid user id");
+        repo.user_deleted(&tenant, &user1).await.expect("user deleted");
+        assert!(find(owned.id().clone()).await.is_none());
+        assert!(find(other.id().clone()).await.is_some());
+    }
+}
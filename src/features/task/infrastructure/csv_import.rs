@@ -0,0 +1,125 @@
+//! Reading task imports from CSV files
+
+use crate::features::task::application::{ImportFailure, ImportRow};
+use crate::shared::domain::timestamp;
+use crate::shared::infrastructure::http::ApiError;
+use axum::body::Bytes;
+use axum::http::StatusCode;
+use std::io::Cursor;
+
+/// Media type of CSV files
+pub const TEXT_CSV: &str = "text/csv";
+
+/// Columns every task import names in its header
+const REQUIRED_COLUMNS: [&str; 3] = ["user_id", "title", "description"];
+
+/// Column of the optional due date
+const DUE_DATE_COLUMN: &str = "due_date";
+
+/// Column of the optional completion time
+const COMPLETED_AT_COLUMN: &str = "completed_at";
+
+/// The rows of the CSV file `csv`, read one at a time as they are consumed
+///
+/// The first line names the columns, in any order: `user_id`, `title` and
+/// `description`, and optionally `due_date` and `completed_at`; other columns are
+/// ignored. A row that cannot be read, lacks one of the columns, or has a
+/// `completed_at` that is neither empty nor an RFC 3339 timestamp is failed with its
+/// line.
+///
+/// # Errors
+/// `400 INVALID_BODY` if the header cannot be read or lacks a required column.
+pub fn read_rows(
+    csv: Bytes,
+) -> Result<impl Iterator<Item = Result<ImportRow, ImportFailure>> + Send, ApiError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(Cursor::new(csv));
+    let header = reader.headers().map_err(|e| invalid_csv(&e.to_string()))?;
+    let position = |name: &str| header.iter().position(|column| column.trim() == name);
+    let missing: Vec<_> =
+        REQUIRED_COLUMNS.into_iter().filter(|name| position(name).is_none()).collect();
+    if !missing.is_empty() {
+        return Err(invalid_csv(&format!("CSV header lacks the columns {}", missing.join(", "))));
+    }
+    let [user_id, title, description] = REQUIRED_COLUMNS.map(|name| position(name).unwrap_or(0));
+    let due_date = position(DUE_DATE_COLUMN);
+    let completed_at = position(COMPLETED_AT_COLUMN);
+
+    Ok(reader.into_records().map(move |record| {
+        let record = record.map_err(|e| ImportFailure {
+            line: e.position().map_or(0, csv::Position::line),
+            reason: e.to_string(),
+        })?;
+        let line = record.position().map_or(0, csv::Position::line);
+        let field = |index: usize| {
+            record.get(index).map(str::to_owned).ok_or_else(|| ImportFailure {
+                line,
+                reason: format!("Row has {} columns; the header names more", record.len()),
+            })
+        };
+        let completed_at = completed_at
+            .and_then(|index| record.get(index))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| timestamp::parse(COMPLETED_AT_COLUMN, value))
+            .transpose()
+            .map_err(|e| ImportFailure { line, reason: e.to_string() })?;
+        Ok(ImportRow {
+            line,
+            user_id: field(user_id)?,
+            title: field(title)?,
+            description: field(description)?,
+            due_date: due_date.and_then(|index| record.get(index)).unwrap_or_default().to_owned(),
+            completed_at,
+        })
+    }))
+}
+
+fn invalid_csv(message: &str) -> ApiError {
+    ApiError::new(StatusCode::BAD_REQUEST, "INVALID_BODY", message)
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    fn lines(csv: &'static str) -> Vec<Result<(u64, String), u64>> {
+        let rows = read_rows(Bytes::from_static(csv.as_bytes())).expect("valid header");
+        rows.map(|row| row.map(|r| (r.line, r.title)).map_err(|f| f.line)).collect()
+    }
+
+    #[test]
+    fn rows_should_be_read_by_column_name_with_their_line() {
+        let csv = "title,user_id,description,due_date\n\
+                   Buy milk,u1,,\n\
+                   \"Two\nlines\",u1,x,\n\
+                   Short\n\
+                   Walk,u2,,";
+        let read = |line, title: &str| Ok((line, title.to_owned()));
+        let expected = [read(2, "Buy milk"), read(3, "Two\nlines"), Err(5), read(6, "Walk")];
+        assert_eq!(lines(csv), expected);
+    }
+
+    #[test]
+    fn completion_times_should_be_read_when_given_and_valid() {
+        let csv = "user_id,title,description,completed_at\n\
+                   u1,Open,,\n\
+                   u1,Done,,2026-01-01T09:00:00+09:00\n\
+                   u1,Bad,,yesterday";
+        let rows: Vec<_> = read_rows(Bytes::from_static(csv.as_bytes()))
+            .expect("valid header")
+            .map(|row| row.map(|r| r.completed_at).map_err(|f| (f.line, f.reason)))
+            .collect();
+        let done = "2026-01-01T00:00:00Z".parse().expect("valid timestamp");
+        let reason = "Validation error: completed_at must be an RFC3339 timestamp \
+                      (e.g. 2026-01-01T09:00:00+09:00)";
+        assert_eq!(rows, [Ok(None), Ok(Some(done)), Err((4, reason.to_owned()))]);
+    }
+
+    #[test]
+    fn a_header_without_a_required_column_should_be_rejected() {
+        let result = read_rows(Bytes::from_static(b"user_id,name\nu1,Buy milk\n"));
+        let error = result.err().map(|e| e.message).unwrap_or_default();
+        assert_eq!(error, "CSV header lacks the columns title, description");
+    }
+}
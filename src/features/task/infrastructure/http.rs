@@ -1,31 +1,57 @@
 //! Task HTTP handlers
 
-use crate::features::task::application::CreateTaskCommand;
-use crate::features::task::domain::Task;
+pub use crate::api_types::{
+    AddChecklistItemRequest, AttachmentResponse, AttachmentUploadResponse, ChecklistItemResponse,
+    CreateAttachmentRequest, CreateTaskRequest, ImportFailureResponse, ImportTasksResponse,
+    InitialTaskRequest, OnboardUserRequest, OnboardedUserResponse, ReassignTasksRequest,
+    ReassignTasksResponse, TaskOwnerResponse, TaskQuery, TaskResponse, TaskStatsBucket,
+    TaskStatsResponse, TaskViewPreferencesRequest, TaskViewPreferencesResponse,
+    UpdateChecklistItemRequest, UpsertTaskRequest, UserOverviewResponse, UserTaskQuery,
+};
+use crate::features::task::application::{
+    AddChecklistItemCommand, CreateAttachmentCommand, CreateTaskCommand, ImportSummary,
+    OnboardUserCommand, OnboardedUser, ReassignTasksCommand, SetTaskViewPreferencesCommand,
+    TaskListQuery, TaskWithOwner, UpsertTaskCommand, UserTaskListQuery, DEFAULT_RECENT_TASKS,
+};
+use crate::features::task::application::STATS_CACHE_TTL;
+use crate::features::task::domain::{
+    Attachment, ChecklistItem, StatsWindow, Task, TaskRepository, TaskViewPreferences,
+    UpsertOutcome,
+};
+use crate::features::task::infrastructure::csv_import::{self, TEXT_CSV};
+use crate::features::task::{TaskState, NAME};
+use crate::features::user::domain::UserRepository;
+use crate::shared::application::{PageRequest, Validated};
 use crate::shared::domain::entity::Entity;
-use crate::shared::infrastructure::http::ApiError;
-use crate::AppState;
+use crate::shared::domain::DomainError;
+use crate::shared::infrastructure::cache_control;
+use crate::shared::infrastructure::casing::FieldCasing;
+use crate::shared::infrastructure::client_ip::ClientIp;
+use crate::shared::infrastructure::conditional;
+use crate::shared::infrastructure::feature::{ExpectedRoute, FeatureRouter};
+use crate::shared::infrastructure::fields::{self, FieldSelection};
+use crate::shared::infrastructure::http::{
+    ApiError, ApiPath, ApiQuery, Negotiated, ReturnPreference, WithWarnings,
+};
+use crate::shared::infrastructure::instrumentation::budgeted;
+use crate::shared::infrastructure::page_links::page_links;
+use crate::shared::infrastructure::request_context::{self, RequestContext};
+use crate::shared::infrastructure::tenant::TenantContext;
 use std::sync::Arc;
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    routing::{get, patch, post},
-    Json, Router,
+    body::{Body, Bytes},
+    extract::{OriginalUri, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::map_response,
+    response::{IntoResponse, Response},
+    routing::{delete, get, patch, post},
+    Router,
 };
-use serde::{Deserialize, Serialize};
+use futures_util::StreamExt;
+use serde::Deserialize;
 
 type ApiResult<T> = Result<T, ApiError>;
 
-/// HTTP response body for a task
-#[derive(Serialize)]
-pub struct TaskResponse {
-    pub id: String,
-    pub user_id: String,
-    pub title: String,
-    pub description: String,
-    pub completed: bool,
-}
-
 impl From<Task> for TaskResponse {
     fn from(t: Task) -> Self {
         Self {
@@ -34,82 +60,2085 @@ impl From<Task> for TaskResponse {
             title: t.title().to_owned(),
             description: t.description().to_owned(),
             completed: t.is_completed(),
+            updated_at: t.updated_at(),
+            user: None,
+            description_html: None,
+            checklist: t.checklist().iter().cloned().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ChecklistItem> for ChecklistItemResponse {
+    fn from(item: ChecklistItem) -> Self {
+        Self {
+            id: item.id().value().to_owned(),
+            text: item.text().to_owned(),
+            done: item.is_done(),
+        }
+    }
+}
+
+impl From<TaskWithOwner> for TaskResponse {
+    fn from(TaskWithOwner { task, owner }: TaskWithOwner) -> Self {
+        Self {
+            user: owner.map(|u| TaskOwnerResponse {
+                id: u.id().value().to_owned(),
+                name: u.name().to_owned(),
+            }),
+            ..task.into()
+        }
+    }
+}
+
+impl From<Attachment> for AttachmentResponse {
+    fn from(a: Attachment) -> Self {
+        Self {
+            id: a.id().value().to_owned(),
+            task_id: a.task_id().value().to_owned(),
+            filename: a.filename().to_owned(),
+            content_type: a.content_type().to_owned(),
+            size: a.size(),
+            created_at: a.created_at(),
+            download_url: None,
         }
     }
 }
 
-/// HTTP request body for creating a task
+impl From<TaskViewPreferences> for TaskViewPreferencesResponse {
+    fn from(p: TaskViewPreferences) -> Self {
+        Self {
+            sort: p.sort().name().to_owned(),
+            show_completed: p.show_completed(),
+            page_size: p.page_size(),
+        }
+    }
+}
+
+/// Query parameter for fetching a single task
 #[derive(Deserialize)]
-pub struct CreateTaskRequest {
-    pub user_id: String,
-    pub title: String,
-    pub description: String,
+pub struct GetTaskQuery {
+    /// Comma-separated extras to embed (see [`GET_EMBEDS`])
+    pub embed: Option<String>,
+}
+
+/// Relations that can be embedded in task listings
+pub const LIST_EMBEDS: &[&str] = &["user"];
+
+/// Extras that can be embedded when fetching a single task
+pub const GET_EMBEDS: &[&str] = &["description_html"];
+
+/// Fields that can be selected in task listings with `?fields=`
+pub const LIST_FIELDS: &[&str] =
+    &["id", "userId", "title", "description", "completed", "updatedAt", "user", "checklist"];
+
+/// Routes served by [`routes`]
+pub const ROUTES: &[ExpectedRoute] = &[
+    ExpectedRoute::get("/"),
+    ExpectedRoute::post("/"),
+    ExpectedRoute::get("/export"),
+    ExpectedRoute::post("/import"),
+    ExpectedRoute::get("/{id}"),
+    ExpectedRoute::put("/{id}"),
+    ExpectedRoute::delete("/{id}"),
+    ExpectedRoute::patch("/{id}/complete"),
+    ExpectedRoute::post("/{id}/checklist"),
+    ExpectedRoute::patch("/{id}/checklist/{item_id}"),
+    ExpectedRoute::delete("/{id}/checklist/{item_id}"),
+    ExpectedRoute::get("/{id}/attachments"),
+    ExpectedRoute::post("/{id}/attachments"),
+    ExpectedRoute::delete("/{id}/attachments/{attachment_id}"),
+];
+
+/// Task feature routes, nested under `/tasks`
+pub fn routes<T, U>(state: Arc<TaskState<T, U>>) -> FeatureRouter<()>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized + 'static,
+{
+    let router = Router::new()
+        .route("/", get(list_tasks).layer(map_response(cache_control::list)).post(create_task))
+        .route("/export", get(export_tasks).layer(map_response(cache_control::list)))
+        .route("/import", post(import_tasks))
+        .route(
+            "/{id}",
+            get(get_task)
+                .layer(map_response(cache_control::entity))
+                .put(upsert_task)
+                .delete(delete_task),
+        )
+        .route("/{id}/complete", patch(complete_task))
+        .route("/{id}/checklist", post(add_checklist_item))
+        .route(
+            "/{id}/checklist/{item_id}",
+            patch(toggle_checklist_item).delete(remove_checklist_item),
+        )
+        .route(
+            "/{id}/attachments",
+            get(list_attachments).layer(map_response(cache_control::list)).post(create_attachment),
+        )
+        .route("/{id}/attachments/{attachment_id}", delete(delete_attachment));
+    FeatureRouter {
+        name: NAME,
+        prefix: "/tasks",
+        router: router.with_state(state),
+        manifest: ROUTES,
+    }
+}
+
+/// Routes served by [`overview_routes`]
+pub const OVERVIEW_ROUTES: &[ExpectedRoute] = &[ExpectedRoute::get("/")];
+
+/// Admin overview routes, nested under `/admin/overview`
+pub fn overview_routes<T, U>(state: Arc<TaskState<T, U>>) -> FeatureRouter<()>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized + 'static,
+{
+    let router = Router::new().route("/", get(user_overview));
+    FeatureRouter {
+        name: "admin_overview",
+        prefix: "/admin/overview",
+        router: router.with_state(state),
+        manifest: OVERVIEW_ROUTES,
+    }
+}
+
+/// Routes served by [`stats_routes`]
+pub const STATS_ROUTES: &[ExpectedRoute] = &[ExpectedRoute::get("/")];
+
+/// Task statistics routes, nested under `/stats/tasks`
+pub fn stats_routes<T, U>(state: Arc<TaskState<T, U>>) -> FeatureRouter<()>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized + 'static,
+{
+    let router = Router::new().route("/", get(task_stats));
+    FeatureRouter {
+        name: "task_stats",
+        prefix: "/stats/tasks",
+        router: router.with_state(state),
+        manifest: STATS_ROUTES,
+    }
+}
+
+/// Routes served by [`onboarding_routes`]
+pub const ONBOARDING_ROUTES: &[ExpectedRoute] = &[ExpectedRoute::post("/")];
+
+/// Onboarding routes, nested under `/users/with-task`
+pub fn onboarding_routes<T, U>(state: Arc<TaskState<T, U>>) -> FeatureRouter<()>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized + 'static,
+{
+    let router = Router::new().route("/", post(onboard_user));
+    FeatureRouter {
+        name: "onboarding",
+        prefix: "/users/with-task",
+        router: router.with_state(state),
+        manifest: ONBOARDING_ROUTES,
+    }
+}
+
+/// Routes served by [`user_task_routes`]
+pub const USER_TASK_ROUTES: &[ExpectedRoute] =
+    &[ExpectedRoute::get("/"), ExpectedRoute::post("/reassign")];
+
+/// Routes of the tasks of a user, listing and reassigning them, nested under
+/// `/users/{id}/tasks`
+pub fn user_task_routes<T, U>(state: Arc<TaskState<T, U>>) -> FeatureRouter<()>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized + 'static,
+{
+    let router = Router::new()
+        .route("/", get(list_user_tasks).layer(map_response(cache_control::list)))
+        .route("/reassign", post(reassign_tasks));
+    FeatureRouter {
+        name: "user_tasks",
+        prefix: "/users/{id}/tasks",
+        router: router.with_state(state),
+        manifest: USER_TASK_ROUTES,
+    }
+}
+
+/// Routes served by [`preference_routes`]
+pub const PREFERENCE_ROUTES: &[ExpectedRoute] =
+    &[ExpectedRoute::get("/"), ExpectedRoute::put("/")];
+
+/// Task view preferences routes, nested under `/users/{id}/preferences`
+pub fn preference_routes<T, U>(state: Arc<TaskState<T, U>>) -> FeatureRouter<()>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized + 'static,
+{
+    let router = Router::new().route(
+        "/",
+        get(get_task_view_preferences)
+            .layer(map_response(cache_control::entity))
+            .put(set_task_view_preferences),
+    );
+    FeatureRouter {
+        name: "task_view_preferences",
+        prefix: "/users/{id}/preferences",
+        router: router.with_state(state),
+        manifest: PREFERENCE_ROUTES,
+    }
+}
+
+/// Create a new task, linking to it in `Location`; soft-rule warnings are listed in
+/// `warnings`
+async fn create_task<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    context: RequestContext,
+    Negotiated(body): Negotiated<CreateTaskRequest>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    if body.completed == Some(true) {
+        let reason = "cannot be set on create; import completed tasks instead";
+        return Err(ApiError::from(DomainError::validation("Completed", reason)));
+    }
+    let command = Validated::new(CreateTaskCommand {
+        user_id: body.user_id,
+        title: body.title,
+        description: body.description,
+    })?;
+    let (task, warnings) = budgeted("create_task", state.create_task.execute(&tenant, command))
+        .await
+        .map_err(ApiError::from)?;
+    let location = format!("/tasks/{}", task.id().value());
+    let body = WithWarnings::new(TaskResponse::from(task), warnings);
+    Ok(request_context::created(&context, &location, body))
+}
+
+/// Create a user together with their first task, linking to the user in `Location`;
+/// soft-rule warnings of the task are listed in `warnings`
+async fn onboard_user<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    context: RequestContext,
+    client_ip: Option<ClientIp>,
+    Negotiated(body): Negotiated<OnboardUserRequest>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let command = Validated::new(OnboardUserCommand {
+        name: body.name,
+        email: body.email,
+        task_title: body.initial_task.title,
+        task_description: body.initial_task.description,
+        client_ip: client_ip.map(|ClientIp(ip)| ip),
+    })?;
+    let OnboardedUser { user, task, warnings } =
+        budgeted("onboard_user", state.onboard_user.execute(&tenant, command))
+            .await
+            .map_err(ApiError::from)?;
+    let location = format!("/users/{}", user.id().value());
+    let body = OnboardedUserResponse { user: user.into(), task: task.into() };
+    Ok(request_context::created(&context, &location, WithWarnings::new(body, warnings)))
+}
+
+/// Move the tasks of the user in the path to another user, the open ones only unless
+/// `only_open` is false; reassigning to the same user is a 400 `INVALID_BODY`
+async fn reassign_tasks<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    client_ip: Option<ClientIp>,
+    ApiPath(id): ApiPath<String>,
+    Negotiated(body): Negotiated<ReassignTasksRequest>,
+) -> ApiResult<Negotiated<ReassignTasksResponse>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let command = ReassignTasksCommand {
+        from_user_id: id,
+        to_user_id: body.to_user_id,
+        only_open: body.only_open,
+        client_ip: client_ip.map(|ClientIp(ip)| ip),
+    };
+    let command = Validated::new(command).map_err(|e| ApiError::invalid_body(&e))?;
+    let reassigned = budgeted("reassign_tasks", state.reassign_tasks.execute(&tenant, command))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Negotiated(ReassignTasksResponse { reassigned }))
+}
+
+/// List a page of the tasks of the user in the path, projected with `fields=`; the
+/// order, the completion filter and the page size come from the user's task view
+/// preferences unless `sort=`, `completed=` or `limit=` are given
+async fn list_user_tasks<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    context: RequestContext,
+    OriginalUri(uri): OriginalUri,
+    ApiPath(id): ApiPath<String>,
+    ApiQuery(query): ApiQuery<UserTaskQuery>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let selection = FieldSelection::parse(query.fields.as_deref(), LIST_FIELDS)?;
+    let filters = UserTaskListQuery { completed: query.completed, q: query.q, sort: query.sort };
+    let filters = Validated::new(filters).map_err(|e| ApiError::invalid_query(&e))?;
+    let page = PageRequest { limit: query.limit, offset: query.offset };
+    let list = state.list_user_tasks.execute(&tenant, &id, filters, page);
+    let (tasks, page) = budgeted("list_user_tasks", list).await.map_err(ApiError::from_query)?;
+    let tasks: Vec<TaskResponse> = tasks.into_iter().map(Into::into).collect();
+    let links = page_links(&context, &uri, state.list_user_tasks.limits(), page, tasks.len());
+    let mut response = fields::project_list(tasks, selection.as_ref())?;
+    if let Some(links) = links {
+        response.headers_mut().insert(header::LINK, links);
+    }
+    Ok(response)
+}
+
+/// Task view preferences of the user in the path; the defaults if they saved none
+async fn get_task_view_preferences<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+) -> ApiResult<Negotiated<TaskViewPreferencesResponse>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let get = state.get_task_view_preferences.execute(&tenant, &id);
+    let preferences = budgeted("get_task_view_preferences", get).await.map_err(ApiError::from)?;
+    Ok(Negotiated(preferences.into()))
+}
+
+/// Replace the task view preferences of the user in the path; unknown fields are a
+/// 400 `INVALID_BODY`
+async fn set_task_view_preferences<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    Negotiated(body): Negotiated<TaskViewPreferencesRequest>,
+) -> ApiResult<Negotiated<TaskViewPreferencesResponse>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let command = Validated::new(SetTaskViewPreferencesCommand {
+        sort: body.sort,
+        show_completed: body.show_completed,
+        page_size: body.page_size,
+    })?;
+    let set = state.set_task_view_preferences.execute(&tenant, &id, command);
+    let preferences = budgeted("set_task_view_preferences", set).await.map_err(ApiError::from)?;
+    Ok(Negotiated(preferences.into()))
+}
+
+/// Create the task with the ID from the path (201 with `Location`) or update its title
+/// and description (200); the owner of an existing task cannot change
+async fn upsert_task<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    context: RequestContext,
+    ApiPath(id): ApiPath<String>,
+    Negotiated(body): Negotiated<UpsertTaskRequest>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let command = Validated::new(UpsertTaskCommand {
+        id,
+        user_id: body.user_id,
+        title: body.title,
+        description: body.description,
+    })?;
+    let (outcome, warnings) = budgeted("upsert_task", state.upsert_task.execute(&tenant, command))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(match outcome {
+        UpsertOutcome::Created(task) => {
+            let location = format!("/tasks/{}", task.id().value());
+            let body = WithWarnings::new(TaskResponse::from(task), warnings);
+            request_context::created(&context, &location, body)
+        }
+        UpsertOutcome::Updated(task) => {
+            Negotiated(WithWarnings::new(TaskResponse::from(task), warnings)).into_response()
+        }
+    })
+}
+
+/// Get a task by ID, with the rendered description when `embed=description_html`;
+/// honours `If-Modified-Since`
+async fn get_task<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    ApiQuery(query): ApiQuery<GetTaskQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let (task, description_html) =
+        if fields::embeds(query.embed.as_deref(), GET_EMBEDS, "description_html")? {
+            let (task, html) =
+                budgeted("render_description", state.render_description.execute(&tenant, &id))
+                    .await
+                    .map_err(ApiError::from)?;
+            (task, Some(html.to_string()))
+        } else {
+            (
+                budgeted("get_task", state.get_task.execute(&tenant, &id))
+                    .await
+                    .map_err(ApiError::from)?,
+                None,
+            )
+        };
+    // Stored tasks always have one: the column is NOT NULL
+    let last_modified = task
+        .updated_at()
+        .ok_or_else(|| DomainError::Unexpected(format!("Task {id} has no updated_at")))?;
+    let body = TaskResponse { description_html, ..task.into() };
+    Ok(conditional::respond(&headers, last_modified, Negotiated(body)))
+}
+
+/// List a page of tasks (`limit=`, `offset=`), optionally filtered, embedding owners
+/// with `embed=user` and projected to a subset of fields with `fields=`
+async fn list_tasks<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    context: RequestContext,
+    OriginalUri(uri): OriginalUri,
+    ApiQuery(query): ApiQuery<TaskQuery>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let selection = FieldSelection::parse(query.fields.as_deref(), LIST_FIELDS)?;
+    let embed_user = fields::embeds(query.embed.as_deref(), LIST_EMBEDS, "user")?;
+    let filters = TaskListQuery { user_id: query.user_id, completed: query.completed, q: query.q };
+    let filters = Validated::new(filters).map_err(|e| ApiError::invalid_query(&e))?;
+    let page = PageRequest { limit: query.limit, offset: query.offset };
+    let tasks: Vec<TaskResponse> = if embed_user {
+        let tasks = budgeted(
+            "list_tasks_with_owners",
+            state.list_tasks_with_owners.execute(&tenant, filters, page),
+        )
+        .await
+        .map_err(ApiError::from_query)?;
+        tasks.into_iter().map(Into::into).collect()
+    } else {
+        let tasks = budgeted("list_tasks", state.list_tasks.execute(&tenant, filters, page))
+            .await
+            .map_err(ApiError::from_query)?;
+        tasks.into_iter().map(Into::into).collect()
+    };
+    let links = page_links(&context, &uri, state.list_tasks.limits(), page, tasks.len());
+    let mut response = fields::project_list(tasks, selection.as_ref())?;
+    if let Some(links) = links {
+        response.headers_mut().insert(header::LINK, links);
+    }
+    Ok(response)
 }
 
-/// Query parameter for filtering tasks
+/// Query parameters of `GET /tasks/export`: the filters of `GET /tasks`
 #[derive(Deserialize)]
-pub struct TaskQuery {
-    /// Filter by user ID (optional; omit to list all tasks)
+pub struct ExportTasksQuery {
+    /// Only tasks of this user
     pub user_id: Option<String>,
+    /// Only completed (`true`) or open (`false`) tasks
+    pub completed: Option<bool>,
+    /// Only tasks whose title or description contains this text, ignoring case
+    pub q: Option<String>,
+}
+
+/// Every task matching the filters as one JSON array, ordered by ID and streamed as
+/// tasks are read, so exports of any size take constant memory
+///
+/// Invalid filters are rejected before the response starts. A failure while reading
+/// tasks is logged and aborts the body, so clients see a truncated array rather than
+/// a successful partial export.
+async fn export_tasks<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiQuery(query): ApiQuery<ExportTasksQuery>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized,
+{
+    let filters = TaskListQuery { user_id: query.user_id, completed: query.completed, q: query.q };
+    let filters = Validated::new(filters).map_err(|e| ApiError::invalid_query(&e))?;
+    let mut tasks = state.list_tasks.stream(tenant, filters);
+    let casing = FieldCasing::current();
+    let body = async_stream::stream! {
+        yield Ok(Bytes::from_static(b"["));
+        let mut separator: &[u8] = b"";
+        while let Some(task) = tasks.next().await {
+            let task = match task {
+                Ok(task) => TaskResponse::from(task),
+                Err(e) => {
+                    tracing::error!(error = %e, "Task export aborted");
+                    yield Err(e);
+                    return;
+                }
+            };
+            let mut chunk = separator.to_vec();
+            separator = b",";
+            match casing.write_json(&mut chunk, &task) {
+                Ok(()) => yield Ok(Bytes::from(chunk)),
+                Err(e) => {
+                    tracing::error!(error = %e, "Task export aborted");
+                    yield Err(DomainError::Infrastructure(e.to_string()));
+                    return;
+                }
+            }
+        }
+        yield Ok(Bytes::from_static(b"]"));
+    };
+    Ok(([(header::CONTENT_TYPE, "application/json")], Body::from_stream(body)).into_response())
 }
 
-/// Task feature router
-pub fn router() -> Router<Arc<AppState>> {
-    Router::new()
-        .route("/tasks", post(create_task).get(list_tasks))
-        .route("/tasks/{id}", get(get_task).delete(delete_task))
-        .route("/tasks/{id}/complete", patch(complete_task))
+impl From<ImportSummary> for ImportTasksResponse {
+    fn from(summary: ImportSummary) -> Self {
+        Self {
+            imported: summary.imported,
+            failed: summary
+                .failed
+                .into_iter()
+                .map(|f| ImportFailureResponse { line: f.line, reason: f.reason })
+                .collect(),
+        }
+    }
+}
+
+/// Query parameters of `POST /tasks/import`
+#[derive(Deserialize)]
+pub struct ImportTasksQuery {
+    /// Import nothing if any row fails
+    #[serde(default)]
+    pub all_or_nothing: bool,
 }
 
-/// Create a new task
-pub async fn create_task(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<CreateTaskRequest>,
-) -> ApiResult<(StatusCode, Json<TaskResponse>)> {
-    let task = state
-        .create_task
-        .execute(CreateTaskCommand { user_id: body.user_id, title: body.title, description: body.description })
+/// Create tasks from the `text/csv` body, one per row, listing the rows not imported
+///
+/// The body is read up to the configured size before any row is imported; see
+/// [`csv_import::read_rows`] for the columns.
+async fn import_tasks<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiQuery(query): ApiQuery<ImportTasksQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> ApiResult<Negotiated<ImportTasksResponse>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let media_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(str::trim);
+    if !media_type.is_some_and(|media_type| media_type.eq_ignore_ascii_case(TEXT_CSV)) {
+        let received = headers.get(header::CONTENT_TYPE);
+        return Err(ApiError::unsupported_media_type(&[TEXT_CSV], received));
+    }
+    let max_bytes = state.import_tasks.limits().max_bytes;
+    let mut chunks = body.into_data_stream();
+    let mut csv = Vec::new();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, "INVALID_BODY", e.to_string()))?;
+        if csv.len() + chunk.len() > max_bytes {
+            let message = format!("CSV files are limited to {max_bytes} bytes");
+            return Err(ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, "BODY_TOO_LARGE", message));
+        }
+        csv.extend_from_slice(&chunk);
+    }
+
+    let rows = csv_import::read_rows(Bytes::from(csv))?;
+    let import = state.import_tasks.execute(&tenant, rows, query.all_or_nothing);
+    let summary = budgeted("import_tasks", import).await.map_err(ApiError::from)?;
+    Ok(Negotiated(summary.into()))
+}
+
+/// Query parameters of `GET /stats/tasks`
+#[derive(Deserialize)]
+pub struct TaskStatsParams {
+    /// Window such as `6h` or `7d`, between 1h and 30d; defaults to `24h`
+    pub window: Option<String>,
+}
+
+/// Created and completed tasks per UTC hour over a window; cacheable for
+/// [`STATS_CACHE_TTL`]
+async fn task_stats<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiQuery(query): ApiQuery<TaskStatsParams>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let window = match query.window.as_deref() {
+        Some(window) => StatsWindow::parse(window).map_err(ApiError::from_query)?,
+        None => StatsWindow::default(),
+    };
+    let buckets = budgeted("task_stats", state.task_stats.execute(&tenant, window))
         .await
         .map_err(ApiError::from)?;
-    Ok((StatusCode::CREATED, Json(task.into())))
-}
-
-/// Get a task by ID
-pub async fn get_task(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> ApiResult<Json<TaskResponse>> {
-    let task = state.get_task.execute(&id).await.map_err(ApiError::from)?;
-    Ok(Json(task.into()))
-}
-
-/// List tasks, optionally filtered by `user_id`
-pub async fn list_tasks(
-    State(state): State<Arc<AppState>>,
-    Query(query): Query<TaskQuery>,
-) -> ApiResult<Json<Vec<TaskResponse>>> {
-    let tasks = state
-        .list_tasks
-        .execute(query.user_id.as_deref())
+    let body = TaskStatsResponse {
+        window: format!("{}h", window.hours()),
+        buckets: buckets
+            .iter()
+            .map(|b| TaskStatsBucket { hour: b.hour, created: b.created, completed: b.completed })
+            .collect(),
+    };
+    let cache_control = format!("max-age={}", STATS_CACHE_TTL.as_secs());
+    Ok(([(header::CACHE_CONTROL, cache_control)], Negotiated(body)).into_response())
+}
+
+/// Query parameters of `GET /admin/overview`
+#[derive(Deserialize)]
+pub struct UserOverviewParams {
+    /// Recent tasks per user, between 1 and 50; defaults to [`DEFAULT_RECENT_TASKS`]
+    pub per_user: Option<u32>,
+    /// Users per page; defaults to the largest page allowed
+    pub limit: Option<u32>,
+    /// Users to skip
+    pub offset: Option<u32>,
+}
+
+/// A page of users with their most recent tasks, for the admin overview screen
+async fn user_overview<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiQuery(query): ApiQuery<UserOverviewParams>,
+) -> ApiResult<Negotiated<Vec<UserOverviewResponse>>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let per_user = query.per_user.unwrap_or(DEFAULT_RECENT_TASKS);
+    let page = PageRequest { limit: query.limit, offset: query.offset };
+    let overview = budgeted("user_overview", state.user_overview.execute(&tenant, per_user, page))
+        .await
+        .map_err(ApiError::from_query)?;
+    Ok(Negotiated(
+        overview
+            .into_iter()
+            .map(|entry| UserOverviewResponse {
+                user: entry.user.into(),
+                recent_tasks: entry.tasks.into_iter().map(Into::into).collect(),
+            })
+            .collect(),
+    ))
+}
+
+/// Complete a task, warning about checklist items left unchecked
+async fn complete_task<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+) -> ApiResult<Negotiated<WithWarnings<TaskResponse>>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let (task, warnings) = budgeted("complete_task", state.complete_task.execute(&tenant, &id))
         .await
         .map_err(ApiError::from)?;
-    Ok(Json(tasks.into_iter().map(Into::into).collect()))
+    Ok(Negotiated(WithWarnings::new(TaskResponse::from(task), warnings)))
+}
+
+/// Add an unchecked item to a task's checklist and return the task
+async fn add_checklist_item<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    Negotiated(body): Negotiated<AddChecklistItemRequest>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let command = Validated::new(AddChecklistItemCommand { text: body.text })?;
+    let (task, _) =
+        budgeted("add_checklist_item", state.add_checklist_item.execute(&tenant, &id, command))
+            .await
+            .map_err(ApiError::from)?;
+    Ok(Negotiated(TaskResponse::from(task)).with_status(StatusCode::CREATED))
+}
+
+/// Check or uncheck an item of a task's checklist and return the task
+async fn toggle_checklist_item<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath((id, item_id)): ApiPath<(String, String)>,
+    Negotiated(body): Negotiated<UpdateChecklistItemRequest>,
+) -> ApiResult<Negotiated<TaskResponse>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let toggle = state.toggle_checklist_item.execute(&tenant, &id, &item_id, body.done);
+    let task = budgeted("toggle_checklist_item", toggle).await.map_err(ApiError::from)?;
+    Ok(Negotiated(task.into()))
 }
 
-/// Complete a task
-pub async fn complete_task(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> ApiResult<Json<TaskResponse>> {
-    let task = state.complete_task.execute(&id).await.map_err(ApiError::from)?;
-    Ok(Json(task.into()))
+/// Remove an item from a task's checklist and return the task
+async fn remove_checklist_item<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath((id, item_id)): ApiPath<(String, String)>,
+) -> ApiResult<Negotiated<TaskResponse>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let remove = state.remove_checklist_item.execute(&tenant, &id, &item_id);
+    let task = budgeted("remove_checklist_item", remove).await.map_err(ApiError::from)?;
+    Ok(Negotiated(task.into()))
+}
+
+/// Query parameters of `DELETE /tasks/{id}`
+#[derive(Deserialize)]
+pub struct DeleteTaskQuery {
+    /// `representation` to get the deleted task back (`200` instead of `204`)
+    #[serde(default, rename = "return")]
+    pub return_preference: ReturnPreference,
 }
 
 /// Delete a task by ID
-pub async fn delete_task(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> ApiResult<StatusCode> {
-    state.delete_task.execute(&id).await.map_err(ApiError::from)?;
+async fn delete_task<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    ApiQuery(query): ApiQuery<DeleteTaskQuery>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized,
+{
+    if query.return_preference == ReturnPreference::Representation {
+        let task = budgeted("delete_task", state.delete_task.execute_returning(&tenant, &id))
+            .await
+            .map_err(ApiError::from)?;
+        return Ok(Negotiated(TaskResponse::from(task)).into_response());
+    }
+    budgeted("delete_task", state.delete_task.execute(&tenant, &id))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Register an attachment of a task and return the URL to upload its file to
+async fn create_attachment<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    Negotiated(body): Negotiated<CreateAttachmentRequest>,
+) -> ApiResult<Response>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let command = Validated::new(CreateAttachmentCommand {
+        filename: body.filename,
+        content_type: body.content_type,
+        size: body.size,
+    })?;
+    let (attachment, upload) =
+        budgeted("create_attachment", state.create_attachment.execute(&tenant, &id, command))
+            .await
+            .map_err(ApiError::from)?;
+    let body = AttachmentUploadResponse {
+        attachment: attachment.into(),
+        upload_url: upload.url,
+        upload_expires_at: upload.expires_at,
+    };
+    Ok(Negotiated(body).with_status(StatusCode::CREATED))
+}
+
+/// List the attachments of a task, oldest first, each with a download URL
+async fn list_attachments<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+) -> ApiResult<Negotiated<Vec<AttachmentResponse>>>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    let attachments = budgeted("list_attachments", state.list_attachments.execute(&tenant, &id))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Negotiated(
+        attachments
+            .into_iter()
+            .map(|(attachment, download)| AttachmentResponse {
+                download_url: Some(download.url),
+                ..attachment.into()
+            })
+            .collect(),
+    ))
+}
+
+/// Delete an attachment; its file is deleted in the background
+async fn delete_attachment<T, U>(
+    State(state): State<Arc<TaskState<T, U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath((id, attachment_id)): ApiPath<(String, String)>,
+) -> ApiResult<StatusCode>
+where
+    T: TaskRepository + ?Sized,
+    U: UserRepository + ?Sized,
+{
+    budgeted("delete_attachment", state.delete_attachment.execute(&tenant, &id, &attachment_id))
+        .await
+        .map_err(ApiError::from)?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use crate::features::task::domain::TITLE_WARNING_LEN;
+    use crate::shared::infrastructure::config::Config;
+    use crate::test_support::{
+        get_if_modified_since, in_memory_app, in_memory_app_with, send, send_as, send_request,
+    };
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn create_task_should_store_and_return_normalized_title() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+
+        let (status, created) = send(
+            &app,
+            Method::POST,
+            "/tasks",
+            Some(json!({"userId": user["id"], "title": " \tBuy \n milk\u{00A0} ", "description": ""})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["title"], "Buy milk");
+
+        let uri = format!("/tasks/{}", created["id"].as_str().unwrap_or_default());
+        let (status, fetched) = send(&app, Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched["title"], "Buy milk");
+    }
+
+    #[tokio::test]
+    async fn onboard_user_should_create_the_user_with_their_first_task() {
+        let app = in_memory_app();
+        let payload = json!({
+            "name": "Alice",
+            "email": "alice@example.com",
+            "initialTask": {"title": "Getting  started", "description": "Read the docs"},
+        });
+        let (status, body) = send(&app, Method::POST, "/users/with-task", Some(payload)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["user"]["name"], "Alice");
+        assert_eq!(body["task"]["title"], "Getting started");
+        assert_eq!(body["task"]["userId"], body["user"]["id"]);
+
+        let user_id = body["user"]["id"].as_str().unwrap_or_default();
+        let (status, _) = send(&app, Method::GET, &format!("/users/{user_id}"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, tasks) = send(&app, Method::GET, &format!("/tasks?user_id={user_id}"), None).await;
+        assert_eq!(tasks.as_array().map(Vec::len), Some(1));
+    }
+
+    #[tokio::test]
+    async fn onboard_user_should_create_nothing_when_either_aggregate_is_invalid() {
+        let app = in_memory_app();
+        let payload = json!({
+            "name": "",
+            "email": "alice@example.com",
+            "initialTask": {"title": " ", "description": ""},
+        });
+        let (status, body) = send(&app, Method::POST, "/users/with-task", Some(payload)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let fields = json!([
+            {"field": "name", "message": "Name cannot be empty"},
+            {"field": "initialTask.title", "message": "Title cannot be empty"},
+        ]);
+        assert_eq!(body["details"], json!({"fields": fields}));
+
+        let (_, users) = send(&app, Method::GET, "/users", None).await;
+        assert_eq!(users, json!([]));
+        let (_, tasks) = send(&app, Method::GET, "/tasks", None).await;
+        assert_eq!(tasks, json!([]));
+    }
+
+    #[tokio::test]
+    async fn reassign_tasks_should_move_open_tasks_to_another_user() {
+        let app = in_memory_app();
+        let mut ids = Vec::new();
+        for (name, email) in [("Alice", "alice@example.com"), ("Bob", "bob@example.com")] {
+            let user = json!({"name": name, "email": email});
+            let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+            ids.push(user["id"].as_str().unwrap_or_default().to_owned());
+        }
+        let (alice, bob) = (&ids[0], &ids[1]);
+        for title in ["Write", "Review"] {
+            let task = json!({"userId": alice, "title": title, "description": ""});
+            let (_, task) = send(&app, Method::POST, "/tasks", Some(task)).await;
+            let uri = format!("/tasks/{}/complete", task["id"].as_str().unwrap_or_default());
+            if title == "Review" {
+                send(&app, Method::PATCH, &uri, None).await;
+            }
+        }
+
+        let uri = format!("/users/{alice}/tasks/reassign");
+        let body = json!({"toUserId": bob, "onlyOpen": true});
+        let (status, body) = send(&app, Method::POST, &uri, Some(body)).await;
+        assert_eq!((status, body), (StatusCode::OK, json!({"reassigned": 1})));
+        let (_, tasks) = send(&app, Method::GET, &format!("/tasks?user_id={bob}"), None).await;
+        assert_eq!(tasks[0]["title"], "Write");
+
+        let to_alice = json!({"toUserId": alice});
+        let (status, body) = send(&app, Method::POST, &uri, Some(to_alice)).await;
+        assert_eq!((status, &body["code"]), (StatusCode::BAD_REQUEST, &json!("INVALID_BODY")));
+        assert_eq!(body["details"]["fields"][0]["field"], "toUserId");
+        let to_nobody = json!({"toUserId": "nobody"});
+        let (status, body) = send(&app, Method::POST, &uri, Some(to_nobody)).await;
+        assert_eq!((status, &body["code"]), (StatusCode::NOT_FOUND, &json!("NOT_FOUND")));
+    }
+
+    #[tokio::test]
+    async fn user_tasks_should_follow_stored_preferences_unless_the_query_overrides_them() {
+        use tower::ServiceExt;
+        let app = in_memory_app();
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        let id = user["id"].as_str().unwrap_or_default();
+        for title in ["approve", "Write", "Call"] {
+            let task = json!({"userId": id, "title": title, "description": ""});
+            let (_, task) = send(&app, Method::POST, "/tasks", Some(task)).await;
+            if title == "Call" {
+                let uri = format!("/tasks/{}/complete", task["id"].as_str().unwrap_or_default());
+                send(&app, Method::PATCH, &uri, None).await;
+            }
+        }
+        let preferences = format!("/users/{id}/preferences");
+        let (status, body) = send(&app, Method::GET, &preferences, None).await;
+        let defaults = json!({"sort": "id", "showCompleted": true, "pageSize": null});
+        assert_eq!((status, body), (StatusCode::OK, defaults));
+        let saved = json!({"sort": "title", "showCompleted": false, "pageSize": 1});
+        let (status, body) = send(&app, Method::PUT, &preferences, Some(saved.clone())).await;
+        assert_eq!((status, &body), (StatusCode::OK, &saved));
+        assert_eq!(send(&app, Method::GET, &preferences, None).await.1, saved);
+
+        let titles = |query: &'static str| {
+            let uri = format!("/users/{id}/tasks{query}");
+            let app = &app;
+            async move {
+                let (status, tasks) = send(app, Method::GET, &uri, None).await;
+                assert_eq!(status, StatusCode::OK, "{tasks}");
+                let tasks = tasks.as_array().cloned().unwrap_or_default();
+                tasks.iter().map(|t| t["title"].as_str().unwrap_or_default().to_owned()).collect()
+            }
+        };
+        let first: Vec<String> = titles("").await;
+        assert_eq!(first, ["approve"]);
+        let second: Vec<String> = titles("?offset=1").await;
+        assert_eq!(second, ["Write"]);
+        let explicit: Vec<String> = titles("?sort=recently_updated&completed=true&limit=5").await;
+        assert_eq!(explicit, ["Call"]);
+        let explicit: Vec<String> = titles("?sort=recently_updated&limit=5").await;
+        assert_eq!(explicit, ["Write", "approve"]);
+
+        // Links page by the preferred size
+        let request = Request::get(format!("/users/{id}/tasks")).body(Body::empty());
+        let response = app.clone().oneshot(request.expect("request")).await.expect("infallible");
+        let links = response.headers()[header::LINK].to_str().expect("ASCII links");
+        assert!(links.contains(&format!("</users/{id}/tasks?limit=1&offset=1>; rel=\"next\"")));
+    }
+
+    #[tokio::test]
+    async fn preferences_should_reject_unknown_keys_invalid_values_and_unknown_users() {
+        let app = in_memory_app();
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        let uri = format!("/users/{}/preferences", user["id"].as_str().unwrap_or_default());
+
+        let unknown = json!({"sort": "title", "colour": "red"});
+        let (status, body) = send(&app, Method::PUT, &uri, Some(unknown)).await;
+        assert_eq!((status, &body["code"]), (StatusCode::BAD_REQUEST, &json!("INVALID_BODY")));
+        let invalid = json!({"sort": "size", "pageSize": 0});
+        let (status, body) = send(&app, Method::PUT, &uri, Some(invalid)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+        let fields = &body["details"]["fields"];
+        assert_eq!((&fields[0]["field"], &fields[1]["field"]), (&json!("sort"), &json!("pageSize")));
+        let (_, stored) = send(&app, Method::GET, &uri, None).await;
+        assert_eq!(stored["sort"], "id");
+
+        let (status, _) = send(&app, Method::GET, "/users/nobody/preferences", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(&app, Method::GET, "/users/nobody/tasks", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, body) = send(&app, Method::GET, "/users/nobody/tasks?sort=size", None).await;
+        assert_eq!((status, &body["code"]), (StatusCode::BAD_REQUEST, &json!("INVALID_QUERY")));
+    }
+
+    #[tokio::test]
+    async fn tasks_of_another_tenant_should_be_invisible() {
+        let app = in_memory_app();
+        let acme = Some("acme");
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send_as(&app, acme, Method::POST, "/users", Some(user)).await;
+        let task = json!({"userId": user["id"], "title": "Acme task", "description": ""});
+        let (status, task) = send_as(&app, acme, Method::POST, "/tasks", Some(task)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uri = format!("/tasks/{}", task["id"].as_str().unwrap_or_default());
+
+        let (_, listed) = send(&app, Method::GET, "/tasks", None).await;
+        assert_eq!(listed, json!([]));
+        for (method, uri) in [
+            (Method::GET, uri.clone()),
+            (Method::PATCH, format!("{uri}/complete")),
+            (Method::DELETE, uri.clone()),
+        ] {
+            let (status, body) = send(&app, method, &uri, None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+            assert_eq!(body["code"], "NOT_FOUND");
+        }
+        let (status, fetched) = send_as(&app, acme, Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched["completed"], false);
+    }
+
+    #[tokio::test]
+    async fn create_task_should_reject_whitespace_only_title() {
+        let app = in_memory_app();
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/tasks",
+            Some(json!({"userId": "user1", "title": " \t\n ", "description": ""})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "VALIDATION_ERROR");
+    }
+
+    #[tokio::test]
+    async fn create_task_should_reject_a_completed_flag_without_writing() {
+        let app = in_memory_app();
+        let task = json!({"userId": "user1", "title": "Buy milk", "description": ""});
+        let mut completed = task.clone();
+        completed["completed"] = json!(true);
+        let (status, body) = send(&app, Method::POST, "/tasks", Some(completed)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "VALIDATION_ERROR");
+        let message = "Validation error: Completed cannot be set on create; import completed \
+                       tasks instead";
+        assert_eq!(body["message"], message);
+        let (_, tasks) = send(&app, Method::GET, "/tasks", None).await;
+        assert_eq!(tasks, json!([]));
+
+        let mut open = task;
+        open["completed"] = json!(false);
+        let (status, created) = send(&app, Method::POST, "/tasks", Some(open)).await;
+        assert_eq!((status, &created["completed"]), (StatusCode::CREATED, &json!(false)));
+    }
+
+    #[tokio::test]
+    async fn put_task_should_create_then_update_without_changing_owner() {
+        let app = in_memory_app();
+        let uri = "/tasks/0b6f2a6e-8d1c-4a7e-9f3b-2c5d7e9a1b3c";
+        let body = |user_id: &str, title: &str| {
+            Some(json!({"userId": user_id, "title": title, "description": ""}))
+        };
+
+        let (status, created) = send(&app, Method::PUT, uri, body("user1", "Buy milk")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["id"], "0b6f2a6e-8d1c-4a7e-9f3b-2c5d7e9a1b3c");
+        let (status, updated) = send(&app, Method::PUT, uri, body("user1", " Buy  bread")).await;
+        assert_eq!((status, &updated["title"]), (StatusCode::OK, &json!("Buy bread")));
+        let (status, _) = send(&app, Method::PUT, uri, body("user1", "Buy bread")).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, error) = send(&app, Method::PUT, uri, body("user2", "Mine now")).await;
+        assert_eq!((status, &error["code"]), (StatusCode::CONFLICT, &json!("CONFLICT")));
+        let (_, task) = send(&app, Method::GET, uri, None).await;
+        assert_eq!((&task["userId"], &task["title"]), (&json!("user1"), &json!("Buy bread")));
+
+        let (status, error) = send(&app, Method::PUT, "/tasks/task-1", body("user1", "x")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["details"]["fields"][0]["field"], "id");
+    }
+
+    #[tokio::test]
+    async fn create_task_should_conflict_on_duplicate_open_title_when_enabled() {
+        let mut config = Config::default();
+        config.prevent_duplicate_open_tasks = true;
+        let app = in_memory_app_with(&config);
+        let task = json!({"userId": "user1", "title": "Buy milk", "description": ""});
+        let (status, first) = send(&app, Method::POST, "/tasks", Some(task.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, body) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(body["message"].as_str().unwrap_or_default().contains(
+            first["id"].as_str().unwrap_or_default()
+        ));
+    }
+
+    #[tokio::test]
+    async fn task_routes_should_404_when_feature_is_disabled() {
+        let mut config = Config::default();
+        config.disabled_features = vec!["task".into()];
+        let app = in_memory_app_with(&config);
+
+        let (status, _) = send(&app, Method::GET, "/tasks", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(&app, Method::GET, "/users", None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn complete_task_should_return_persisted_state_with_updated_at() {
+        let app = in_memory_app();
+        let task = json!({"userId": "user1", "title": "Buy milk", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        assert!(created["updatedAt"].is_string());
+
+        let uri = format!("/tasks/{}/complete", created["id"].as_str().unwrap_or_default());
+        let (status, completed) = send(&app, Method::PATCH, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(completed["completed"], true);
+        assert!(completed["updatedAt"].as_str().is_some_and(|at| at.ends_with('Z')));
+    }
+
+    #[tokio::test]
+    async fn checklist_items_should_be_added_toggled_and_removed() {
+        let app = in_memory_app();
+        let task = json!({"userId": "user1", "title": "Pack", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        assert_eq!(created["checklist"], json!([]));
+        let uri = format!("/tasks/{}/checklist", created["id"].as_str().unwrap_or_default());
+
+        let (status, added) =
+            send(&app, Method::POST, &uri, Some(json!({"text": " Socks "}))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let item = &added["checklist"][0];
+        assert_eq!((&item["text"], &item["done"]), (&json!("Socks"), &json!(false)));
+        let item_uri = format!("{uri}/{}", item["id"].as_str().unwrap_or_default());
+
+        let (status, toggled) =
+            send(&app, Method::PATCH, &item_uri, Some(json!({"done": true}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(toggled["checklist"][0]["done"], true);
+        let task_uri = format!("/tasks/{}", created["id"].as_str().unwrap_or_default());
+        let (_, fetched) = send(&app, Method::GET, &task_uri, None).await;
+        assert_eq!(fetched["checklist"], toggled["checklist"]);
+
+        let (status, removed) = send(&app, Method::DELETE, &item_uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(removed["checklist"], json!([]));
+        let (status, _) = send(&app, Method::DELETE, &item_uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn checklist_items_should_need_text_of_valid_length() {
+        let app = in_memory_app();
+        let task = json!({"userId": "user1", "title": "Pack", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}/checklist", created["id"].as_str().unwrap_or_default());
+
+        for text in [String::new(), "x".repeat(201)] {
+            let (status, body) = send(&app, Method::POST, &uri, Some(json!({"text": text}))).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+        }
+        let (status, _) =
+            send(&app, Method::POST, "/tasks/missing/checklist", Some(json!({"text": "x"}))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn complete_task_should_warn_about_unchecked_checklist_items() {
+        let app = in_memory_app();
+        let task = json!({"userId": "user1", "title": "Pack", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}", created["id"].as_str().unwrap_or_default());
+        send(&app, Method::POST, &format!("{uri}/checklist"), Some(json!({"text": "Socks"}))).await;
+
+        let (status, completed) = send(&app, Method::PATCH, &format!("{uri}/complete"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(completed["completed"], true);
+        assert_eq!(completed["warnings"][0]["code"], "UNCHECKED_CHECKLIST_ITEMS");
+        assert_eq!(completed["warnings"][0]["field"], "checklist");
+    }
+
+    #[tokio::test]
+    async fn complete_task_should_conflict_on_unchecked_checklist_items_when_required() {
+        let mut config = Config::default();
+        config.require_checked_checklist = true;
+        let app = in_memory_app_with(&config);
+        let task = json!({"userId": "user1", "title": "Pack", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}", created["id"].as_str().unwrap_or_default());
+        let (_, added) =
+            send(&app, Method::POST, &format!("{uri}/checklist"), Some(json!({"text": "Socks"})))
+                .await;
+
+        let (status, _) = send(&app, Method::PATCH, &format!("{uri}/complete"), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let item = added["checklist"][0]["id"].as_str().unwrap_or_default();
+        let item_uri = format!("{uri}/checklist/{item}");
+        send(&app, Method::PATCH, &item_uri, Some(json!({"done": true}))).await;
+        let (status, completed) = send(&app, Method::PATCH, &format!("{uri}/complete"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(completed.get("warnings").is_none(), "{completed}");
+    }
+
+    #[tokio::test]
+    async fn create_task_should_accept_long_title_with_warning() {
+        let app = in_memory_app();
+        let title = "x".repeat(TITLE_WARNING_LEN + 1);
+        let task = json!({"userId": "user1", "title": title, "description": ""});
+        let (status, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["title"], title);
+        assert_eq!(
+            created["warnings"],
+            json!([{
+                "code": "TITLE_TOO_LONG",
+                "message": format!("Title is longer than {TITLE_WARNING_LEN} characters"),
+                "field": "title",
+            }])
+        );
+
+        let task = json!({"userId": "user1", "title": "Buy milk", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        assert!(created.get("warnings").is_none());
+    }
+
+    #[tokio::test]
+    async fn create_task_hard_error_should_win_over_warning_without_writing() {
+        let app = in_memory_app();
+        let title = "x".repeat(TITLE_WARNING_LEN + 1);
+        let task = json!({"userId": "", "title": title, "description": ""});
+        let (status, body) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "VALIDATION_ERROR");
+        assert!(body.get("warnings").is_none());
+
+        let (_, tasks) = send(&app, Method::GET, "/tasks", None).await;
+        assert_eq!(tasks, json!([]));
+    }
+
+    #[tokio::test]
+    async fn get_task_should_set_last_modified_and_honour_if_modified_since() {
+        let app = in_memory_app();
+        let task = json!({"userId": "user1", "title": "Buy milk", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}", created["id"].as_str().unwrap_or_default());
+
+        let (status, last_modified) = get_if_modified_since(&app, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(last_modified.as_deref().is_some_and(|at| at.ends_with(" GMT")));
+        for uri in [uri.clone(), format!("{uri}?embed=description_html")] {
+            let (status, _) = get_if_modified_since(&app, &uri, last_modified.as_deref()).await;
+            assert_eq!(status, StatusCode::NOT_MODIFIED);
+        }
+        let stale = Some("Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(get_if_modified_since(&app, &uri, stale).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn complete_task_twice_should_return_409() {
+        let app = in_memory_app();
+        let task = json!({"userId": "user1", "title": "Buy milk", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}/complete", created["id"].as_str().unwrap_or_default());
+        assert_eq!(send(&app, Method::PATCH, &uri, None).await.0, StatusCode::OK);
+        let (status, body) = send(&app, Method::PATCH, &uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "CONFLICT");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn complete_task_racing_a_delete_should_return_404() {
+        use crate::testing::{test_app, FakeRepositories};
+        use std::time::Duration;
+
+        let fakes = FakeRepositories::default();
+        let app = test_app(&fakes).expect("valid app");
+        let task = json!({"userId": "user1", "title": "Buy milk", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}", created["id"].as_str().unwrap_or_default());
+        fakes.tasks.slow("complete_if_open", Duration::from_millis(50));
+
+        // The delete lands while the complete waits on the repository
+        let complete = format!("{uri}/complete");
+        let ((status, body), (deleted, _)) = tokio::join!(
+            send(&app, Method::PATCH, &complete, None),
+            async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                send(&app, Method::DELETE, &uri, None).await
+            },
+        );
+        assert_eq!(deleted, StatusCode::NO_CONTENT);
+        assert_eq!((status, &body["code"]), (StatusCode::NOT_FOUND, &json!("NOT_FOUND")));
+        assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn list_tasks_should_embed_user_only_when_requested() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        send(&app, Method::POST, "/tasks", Some(task)).await;
+
+        let (status, plain) = send(&app, Method::GET, "/tasks", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(plain[0].get("user").is_none());
+
+        let (status, embedded) = send(&app, Method::GET, "/tasks?embed=user", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(embedded[0]["user"], json!({"id": user["id"], "name": "Alice"}));
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn list_tasks_should_link_the_neighbouring_pages_keeping_filters() {
+        use tower::ServiceExt;
+        let mut config = Config::default();
+        config.public_base_url = "https://api.example.com/v1".to_owned();
+        let app = in_memory_app_with(&config);
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        for title in ["Buy milk 1", "Buy milk 2", "Buy milk 3", "Buy milk 4", "Buy milk 5", "Done"]
+        {
+            let task = json!({"userId": user["id"], "title": title, "description": ""});
+            let (_, task) = send(&app, Method::POST, "/tasks", Some(task)).await;
+            if title == "Done" {
+                let uri = format!("/tasks/{}/complete", task["id"].as_str().unwrap_or_default());
+                send(&app, Method::PATCH, &uri, None).await;
+            }
+        }
+        // rel => target of each `<target>; rel="rel"` of the `Link` header
+        let links = |offset: u32| {
+            let app = app.clone();
+            async move {
+                let uri = format!("/tasks?completed=false&q=buy%20milk&limit=2&offset={offset}");
+                let request = Request::get(uri).body(Body::empty()).expect("request");
+                let response = app.oneshot(request).await.expect("infallible router");
+                let header = response.headers()[header::LINK].to_str().expect("ASCII links");
+                let mut links: Vec<(String, String)> = header
+                    .split(", ")
+                    .filter_map(|link| {
+                        let (target, rel) = link.split_once("; rel=")?;
+                        let target = target.trim_start_matches('<').trim_end_matches('>');
+                        Some((rel.trim_matches('"').to_owned(), target.to_owned()))
+                    })
+                    .collect();
+                links.sort();
+                links
+            }
+        };
+        let page = |offset: u32| {
+            let filters = "completed=false&q=buy+milk";
+            format!("https://api.example.com/v1/tasks?{filters}&limit=2&offset={offset}")
+        };
+
+        let middle = links(2).await;
+        let expected = [("first", page(0)), ("next", page(4)), ("prev", page(0))];
+        assert_eq!(middle, expected.map(|(rel, target)| (rel.to_owned(), target)));
+        let first = links(0).await;
+        assert_eq!(first, [("first".to_owned(), page(0)), ("next".to_owned(), page(2))]);
+        let last = links(4).await;
+        assert_eq!(last, [("first".to_owned(), page(0)), ("prev".to_owned(), page(2))]);
+    }
+
+    #[test]
+    fn list_tasks_embedding_users_should_load_owners_in_one_query() {
+        use crate::test_support::recorded_request_errors;
+        let mut config = Config::default();
+        config.metrics_db = true;
+        let (spans, _) = recorded_request_errors(async move {
+            let app = in_memory_app_with(&config);
+            for name in ["Alice", "Bob", "Carol"] {
+                let user = json!({"name": name, "email": format!("{name}@example.com")});
+                let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+                for title in ["First", "Second"] {
+                    let task = json!({"userId": user["id"], "title": title, "description": ""});
+                    send(&app, Method::POST, "/tasks", Some(task)).await;
+                }
+            }
+            let (status, tasks) = send(&app, Method::GET, "/tasks?embed=user", None).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(tasks.as_array().map(Vec::len), Some(6));
+        });
+        let listing = spans.last().map(String::as_str).unwrap_or_default();
+        assert!(listing.split(' ').any(|field| field == "db.queries=2"), "{listing}");
+    }
+
+    #[tokio::test]
+    async fn export_tasks_should_stream_every_matching_task_beyond_the_page_size() {
+        use axum::body::{Body, HttpBody};
+        use axum::http::{header, Request};
+        use tower::ServiceExt;
+
+        let mut config = Config::default();
+        config.max_page_size = 2;
+        let app = in_memory_app_with(&config);
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        for title in ["First", "Second", "Third"] {
+            let task = json!({"userId": user["id"], "title": title, "description": ""});
+            send(&app, Method::POST, "/tasks", Some(task)).await;
+        }
+
+        let request = Request::get("/tasks/export?q=ir").body(Body::empty()).expect("request");
+        let response = app.clone().oneshot(request).await.expect("infallible router");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert!(response.headers().get(header::CONTENT_LENGTH).is_none());
+        assert_eq!(response.body().size_hint().exact(), None, "body is not chunked");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("body");
+        let tasks: serde_json::Value = serde_json::from_slice(&body).expect("JSON array");
+        let mut titles: Vec<&str> =
+            tasks.as_array().expect("array").iter().filter_map(|t| t["title"].as_str()).collect();
+        titles.sort_unstable();
+        assert_eq!(titles, ["First", "Third"]);
+
+        let (status, empty) = send(&app, Method::GET, "/tasks/export?completed=true", None).await;
+        assert_eq!((status, empty), (StatusCode::OK, json!([])));
+    }
+
+    #[tokio::test]
+    async fn export_tasks_should_abort_the_body_when_a_task_cannot_be_read() {
+        use crate::shared::domain::DomainError;
+        use crate::testing::{test_app, FakeRepositories};
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let fakes = FakeRepositories::default();
+        let app = test_app(&fakes).expect("valid app");
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        send(&app, Method::POST, "/tasks", Some(task)).await;
+        fakes.tasks.fail("stream_filtered", || DomainError::Infrastructure("disk full".into()));
+
+        let request = Request::get("/tasks/export").body(Body::empty()).expect("request");
+        let response = app.oneshot(request).await.expect("infallible router");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await;
+        assert!(body.is_err(), "a failed export must not end as a valid array: {body:?}");
+    }
+
+    fn csv_import(uri: &str, content_type: &str, csv: String) -> Request<Body> {
+        Request::post(uri)
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(csv))
+            .expect("request")
+    }
+
+    #[tokio::test]
+    async fn import_tasks_should_report_failed_rows_or_import_nothing() {
+        let app = in_memory_app();
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        let id = user["id"].as_str().unwrap_or_default();
+        // Line 3 names the user by email rather than by ID
+        let csv = format!(
+            "user_id,title,description\n{id},Buy milk,\nalice@example.com,Walk,\n{id},Read,A book\n"
+        );
+
+        let request = csv_import("/tasks/import?all_or_nothing=true", "text/csv", csv.clone());
+        let (status, body) = send_request(&app, request).await;
+        assert_eq!((status, &body["imported"]), (StatusCode::OK, &json!(0)));
+        assert_eq!(body["failed"], json!([{"line": 3, "reason": "Not found: User not found"}]));
+        assert_eq!(send(&app, Method::GET, "/tasks", None).await.1, json!([]));
+
+        let request = csv_import("/tasks/import", "text/csv; charset=utf-8", csv);
+        let (status, body) = send_request(&app, request).await;
+        assert_eq!((status, &body["imported"]), (StatusCode::OK, &json!(2)));
+        assert_eq!(body["failed"][0]["line"], 3);
+        let (_, tasks) = send(&app, Method::GET, "/tasks", None).await;
+        let mut titles: Vec<_> =
+            tasks.as_array().into_iter().flatten().filter_map(|t| t["title"].as_str()).collect();
+        titles.sort_unstable();
+        assert_eq!(titles, ["Buy milk", "Read"]);
+    }
+
+    #[tokio::test]
+    async fn import_tasks_should_refuse_other_media_types_bad_headers_and_large_files() {
+        let mut config = Config::default();
+        config.task_import_max_bytes = 64;
+        let app = in_memory_app_with(&config);
+        let header = "user_id,title,description\n".to_owned();
+
+        let request = csv_import("/tasks/import", "application/json", header.clone());
+        let (status, body) = send_request(&app, request).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "UNSUPPORTED_MEDIA_TYPE");
+        assert_eq!(body["message"], "Content-Type must be text/csv; got application/json");
+        let request = csv_import("/tasks/import", "text/csv", "user_id,name\n".into());
+        let (status, body) = send_request(&app, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_BODY");
+        let request = csv_import("/tasks/import", "text/csv", header + &"u1,Task,\n".repeat(8));
+        let (status, body) = send_request(&app, request).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["code"], "BODY_TOO_LARGE");
+    }
+
+    #[tokio::test]
+    async fn list_tasks_should_filter_and_reject_invalid_filters_with_400() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        for title in ["Buy milk", "Walk"] {
+            let task = json!({"userId": user["id"], "title": title, "description": ""});
+            send(&app, Method::POST, "/tasks", Some(task)).await;
+        }
+
+        let (status, body) = send(&app, Method::GET, "/tasks?q=MILK&completed=false", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().map(Vec::len), Some(1));
+        assert_eq!(body[0]["title"], "Buy milk");
+
+        let (status, body) = send(&app, Method::GET, "/tasks?user_id=&q=m", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_QUERY");
+        assert_eq!(body["details"]["fields"][0]["field"], "user_id");
+        assert_eq!(body["details"]["fields"][1]["field"], "q");
+        let message = "Search text must be at least 2 characters";
+        assert_eq!(body["details"]["fields"][1]["message"], message);
+    }
+
+    #[tokio::test]
+    async fn list_tasks_should_page_and_reject_pages_out_of_bounds_with_400() {
+        let app = in_memory_app();
+        for title in ["Buy milk", "Walk"] {
+            let task = json!({"userId": "user1", "title": title, "description": ""});
+            send(&app, Method::POST, "/tasks", Some(task)).await;
+        }
+        let (_, all) = send(&app, Method::GET, "/tasks", None).await;
+        assert_eq!(all.as_array().map(Vec::len), Some(2));
+
+        let (status, page) = send(&app, Method::GET, "/tasks?limit=1&offset=1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page, json!([all[1]]));
+
+        let too_deep = "offset cannot exceed 10000; narrow the filters instead of paging deeper";
+        let rejected = [
+            ("limit=0", "limit must be between 1 and 100"),
+            ("limit=101", "limit must be between 1 and 100"),
+            ("offset=10001", too_deep),
+        ];
+        for (query, message) in rejected {
+            let uri = format!("/tasks?{query}&embed=user");
+            let (status, body) = send(&app, Method::GET, &uri, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{query}");
+            assert_eq!(body["code"], "INVALID_QUERY");
+            assert_eq!(body["message"], message);
+        }
+    }
+
+    #[tokio::test]
+    async fn list_tasks_should_reject_unknown_embed() {
+        let (status, body) = send(&in_memory_app(), Method::GET, "/tasks?embed=owner", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_QUERY");
+        assert_eq!(body["message"], "Unsupported embed 'owner'; supported embeds: user");
+    }
+
+    #[tokio::test]
+    async fn list_tasks_should_project_requested_fields_with_id() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task)).await;
+
+        let (status, body) = send(&app, Method::GET, "/tasks?fields=title,completed", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = json!([{"id": created["id"], "title": "Buy milk", "completed": false}]);
+        assert_eq!(body, expected);
+
+        let uri = "/tasks?embed=user&fields=user";
+        let (status, body) = send(&app, Method::GET, uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let owner = json!({"id": user["id"], "name": "Alice"});
+        assert_eq!(body, json!([{"id": created["id"], "user": owner}]));
+    }
+
+    #[tokio::test]
+    async fn list_fields_should_match_serialized_task_keys() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        send(&app, Method::POST, "/tasks", Some(task)).await;
+
+        let (_, body) = send(&app, Method::GET, "/tasks?embed=user", None).await;
+        let mut keys: Vec<_> = body[0].as_object().into_iter().flat_map(|o| o.keys()).collect();
+        let mut expected: Vec<_> = super::LIST_FIELDS.to_vec();
+        keys.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(keys, expected);
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn snake_case_profile_should_read_and_write_legacy_field_names() {
+        let profiled = |uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header("accept-profile", "snake_case")
+                .body(Body::from(body.to_string()))
+                .expect("request")
+        };
+        let app = in_memory_app();
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+
+        let task = json!({"user_id": user["id"], "title": "Buy milk", "description": ""});
+        let (status, created) = send_request(&app, profiled("/tasks", task)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["user_id"], user["id"]);
+        assert!(created["updated_at"].is_string() && created.get("userId").is_none());
+        let (_, camel) = send(&app, Method::GET, "/tasks?fields=user_id,updated_at", None).await;
+        assert!(camel[0]["userId"].is_string() && camel[0]["updatedAt"].is_string());
+        let export = Request::get("/tasks/export").header("accept-profile", "snake_case");
+        let export = export.body(Body::empty()).expect("request");
+        let (_, exported) = send_request(&app, export).await;
+        assert_eq!(exported[0]["user_id"], user["id"]);
+
+        let invalid = json!({"user_id": "", "title": "Buy bread", "description": ""});
+        let (status, body) = send_request(&app, profiled("/tasks", invalid)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["details"]["fields"][0]["field"], "user_id");
+
+        let mut config = Config::default();
+        config.legacy_snake_case = true;
+        let app = in_memory_app_with(&config);
+        let onboard = json!({
+            "name": "Bob",
+            "email": "bob@example.com",
+            "initial_task": {"title": "Say hi", "description": ""},
+        });
+        let (status, body) = send(&app, Method::POST, "/users/with-task", Some(onboard)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["task"]["user_id"], body["user"]["id"]);
+    }
+
+    #[tokio::test]
+    async fn list_tasks_should_reject_unknown_field_listing_valid_ones() {
+        let (status, body) =
+            send(&in_memory_app(), Method::GET, "/tasks?fields=title,color", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_QUERY");
+        let valid = "id, userId, title, description, completed, updatedAt, user, checklist";
+        assert_eq!(body["message"], format!("Unknown field 'color'; valid fields: {valid}"));
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn task_stats_should_return_hourly_buckets_and_be_cacheable() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        send(&app, Method::POST, "/tasks", Some(task)).await;
+
+        let response = tower::ServiceExt::oneshot(
+            app.clone(),
+            axum::http::Request::get("/stats/tasks?window=2d")
+                .body(axum::body::Body::empty())
+                .expect("request"),
+        )
+        .await
+        .expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let cache_control = response.headers().get(axum::http::header::CACHE_CONTROL);
+        assert_eq!(cache_control.and_then(|v| v.to_str().ok()), Some("max-age=60"));
+
+        let (status, body) = send(&app, Method::GET, "/stats/tasks", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["window"], "24h");
+        let buckets = body["buckets"].as_array().cloned().unwrap_or_default();
+        assert_eq!(buckets.len(), 24);
+        let created: u64 = buckets.iter().filter_map(|b| b["created"].as_u64()).sum();
+        assert_eq!(created, 1);
+        let hour = buckets[0]["hour"].as_str().unwrap_or_default();
+        assert!(hour.ends_with(":00:00.000Z"), "{hour}");
+    }
+
+    #[tokio::test]
+    async fn task_stats_should_reject_windows_outside_bounds() {
+        let app = in_memory_app();
+        for window in ["0h", "31d", "week"] {
+            let uri = format!("/stats/tasks?window={window}");
+            let (status, body) = send(&app, Method::GET, &uri, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{window}");
+            assert_eq!(body["code"], "INVALID_QUERY");
+        }
+    }
+
+    #[tokio::test]
+    async fn get_task_should_embed_sanitized_description_html() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let description = "**Note** <script>alert('xss')</script><img src=x onerror=alert(1)>";
+        let (_, task) = send(
+            &app,
+            Method::POST,
+            "/tasks",
+            Some(json!({"userId": user["id"], "title": "XSS", "description": description})),
+        )
+        .await;
+        let uri = format!("/tasks/{}", task["id"].as_str().unwrap_or_default());
+
+        let (_, plain) = send(&app, Method::GET, &uri, None).await;
+        assert!(plain.get("descriptionHtml").is_none());
+
+        let (status, body) =
+            send(&app, Method::GET, &format!("{uri}?embed=description_html"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["description"], description);
+        let html = body["descriptionHtml"].as_str().unwrap_or_default();
+        assert!(html.contains("<strong>Note</strong>"), "{html}");
+        assert!(!html.contains("<script") && !html.contains("onerror"), "{html}");
+
+        let (status, body) = send(&app, Method::GET, &format!("{uri}?embed=user"), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Unsupported embed 'user'; supported embeds: description_html");
+    }
+
+    #[tokio::test]
+    async fn user_overview_should_list_every_user_with_recent_tasks() {
+        let app = in_memory_app();
+        let mut user_ids = Vec::new();
+        for (name, email) in [("Alice", "alice@example.com"), ("Bob", "bob@example.com")] {
+            let (_, user) =
+                send(&app, Method::POST, "/users", Some(json!({"name": name, "email": email})))
+                    .await;
+            user_ids.push(user["id"].clone());
+        }
+        for title in ["First", "Second", "Third"] {
+            let body = json!({"userId": user_ids[0], "title": title, "description": ""});
+            send(&app, Method::POST, "/tasks", Some(body)).await;
+        }
+
+        let (status, body) = send(&app, Method::GET, "/admin/overview?per_user=2", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let entries = body.as_array().cloned().unwrap_or_default();
+        assert_eq!(entries.len(), 2);
+        let recent = |id: &serde_json::Value| {
+            let entry = entries.iter().find(|e| &e["user"]["id"] == id).cloned();
+            let tasks = entry.and_then(|e| e["recentTasks"].as_array().cloned());
+            tasks.unwrap_or_default().iter().map(|t| t["title"].clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(recent(&user_ids[0]), [json!("Third"), json!("Second")]);
+        assert!(recent(&user_ids[1]).is_empty());
+
+        let (status, body) = send(&app, Method::GET, "/admin/overview?per_user=0", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_QUERY");
+    }
+
+    #[tokio::test]
+    async fn attachments_should_be_created_listed_and_deleted() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let task = json!({"userId": user["id"], "title": "Report", "description": ""});
+        let (_, task) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}/attachments", task["id"].as_str().unwrap_or_default());
+
+        let file = json!({"filename": "scan.pdf", "contentType": "application/pdf", "size": 42});
+        let (status, created) = send(&app, Method::POST, &uri, Some(file)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["attachment"]["filename"], "scan.pdf");
+        assert_eq!(created["attachment"]["taskId"], task["id"]);
+        assert!(created["attachment"].get("downloadUrl").is_none());
+        assert!(created["uploadUrl"].as_str().is_some_and(|url| url.starts_with("file://")));
+
+        let (status, listed) = send(&app, Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed[0]["id"], created["attachment"]["id"]);
+        assert_eq!(listed[0]["size"], 42);
+        assert!(listed[0]["downloadUrl"].is_string());
+
+        let id = created["attachment"]["id"].as_str().unwrap_or_default();
+        let (status, _) = send(&app, Method::DELETE, &format!("{uri}/{id}"), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&app, Method::DELETE, &format!("{uri}/{id}"), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, listed) = send(&app, Method::GET, &uri, None).await;
+        assert_eq!(listed, json!([]));
+    }
+
+    #[tokio::test]
+    async fn create_attachment_should_enforce_configured_rules() {
+        let mut config = Config::default();
+        config.attachment_max_size_bytes = 100;
+        let app = in_memory_app_with(&config);
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let task = json!({"userId": user["id"], "title": "Report", "description": ""});
+        let (_, task) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}/attachments", task["id"].as_str().unwrap_or_default());
+
+        let big = json!({"filename": "big.pdf", "contentType": "application/pdf", "size": 101});
+        let (status, body) = send(&app, Method::POST, &uri, Some(big)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "ATTACHMENT_TOO_LARGE");
+        assert_eq!(body["details"], json!({"maxSizeBytes": 100}));
+        for file in [
+            json!({"filename": "run.sh", "contentType": "text/x-shellscript", "size": 1}),
+            json!({"filename": "../etc/passwd", "contentType": "text/plain", "size": 1}),
+        ] {
+            let (status, body) = send(&app, Method::POST, &uri, Some(file)).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+        }
+        let file = json!({"filename": "a.txt", "contentType": "text/plain", "size": 1});
+        let (status, _) =
+            send(&app, Method::POST, "/tasks/missing/attachments", Some(file)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn delete_task_should_delete_its_attachments() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let task = json!({"userId": user["id"], "title": "Report", "description": ""});
+        let (_, created) = send(&app, Method::POST, "/tasks", Some(task.clone())).await;
+        let task_uri = format!("/tasks/{}", created["id"].as_str().unwrap_or_default());
+        let file = json!({"filename": "a.txt", "contentType": "text/plain", "size": 1});
+        send(&app, Method::POST, &format!("{task_uri}/attachments"), Some(file)).await;
+
+        let (status, _) = send(&app, Method::DELETE, &task_uri, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&app, Method::GET, &format!("{task_uri}/attachments"), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // A task recreated under the same ID starts without attachments
+        let (status, _) = send(&app, Method::PUT, &task_uri, Some(task)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (_, listed) = send(&app, Method::GET, &format!("{task_uri}/attachments"), None).await;
+        assert_eq!(listed, json!([]));
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn delete_task_should_return_the_deleted_task_only_when_asked() {
+        let app = in_memory_app();
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        let (_, first) = send(&app, Method::POST, "/tasks", Some(task.clone())).await;
+        let (_, second) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = |task: &serde_json::Value| format!("/tasks/{}", task["id"].as_str().expect("id"));
+        let completed = send(&app, Method::PATCH, &format!("{}/complete", uri(&first)), None).await;
+
+        let returning = format!("{}?return=representation", uri(&first));
+        let (status, body) = send(&app, Method::DELETE, &returning, None).await;
+        assert_eq!((status, body), (StatusCode::OK, completed.1));
+        assert_eq!(send(&app, Method::GET, &uri(&first), None).await.0, StatusCode::NOT_FOUND);
+        let (status, body) = send(&app, Method::DELETE, &returning, None).await;
+        assert_eq!((status, &body["code"]), (StatusCode::NOT_FOUND, &json!("NOT_FOUND")));
+
+        let minimal = format!("{}?return=minimal", uri(&second));
+        let (status, body) = send(&app, Method::DELETE, &minimal, None).await;
+        assert_eq!((status, body), (StatusCode::NO_CONTENT, serde_json::Value::Null));
+    }
+
+    #[tokio::test]
+    async fn create_task_with_an_unsupported_content_type_should_return_415() {
+        use crate::test_support::send_request;
+        use axum::{body::Body, http::header, http::Request};
+
+        let app = in_memory_app();
+        let request = Request::post("/tasks")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from("Buy milk"))
+            .expect("request");
+        let (status, body) = send_request(&app, request).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "UNSUPPORTED_MEDIA_TYPE");
+    }
+
+    #[cfg(feature = "msgpack")]
+    mod msgpack {
+        use super::*;
+        use crate::shared::infrastructure::http::MSGPACK;
+        use axum::body::Body;
+        use axum::http::{header, HeaderMap, Request};
+        use axum::Router;
+        use serde_json::Value;
+        use tower::ServiceExt;
+
+        /// Send `body` encoded as `content_type`, accepting `accept`; the response body
+        /// is decoded by its `Content-Type`
+        async fn exchange(
+            app: &Router,
+            method: Method,
+            uri: &str,
+            (content_type, body): (&str, Vec<u8>),
+            accept: &str,
+        ) -> (StatusCode, HeaderMap, Value) {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::ACCEPT, accept)
+                .body(Body::from(body))
+                .expect("request");
+            let response = app.clone().oneshot(request).await.expect("infallible router");
+            let (status, headers) = (response.status(), response.headers().clone());
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await;
+            let bytes = bytes.expect("readable body");
+            let body = match headers.get(header::CONTENT_TYPE).map(|v| v.to_str()) {
+                Some(Ok(MSGPACK)) => rmp_serde::from_slice(&bytes).expect("MessagePack"),
+                _ => serde_json::from_slice(&bytes).expect("JSON"),
+            };
+            (status, headers, body)
+        }
+
+        fn msgpack(value: &Value) -> (&'static str, Vec<u8>) {
+            (MSGPACK, rmp_serde::to_vec_named(value).expect("encodable"))
+        }
+
+        fn json_body(value: &Value) -> (&'static str, Vec<u8>) {
+            ("application/json", value.to_string().into_bytes())
+        }
+
+        async fn user_id(app: &Router) -> Value {
+            let user = json!({"name": "Alice", "email": "alice@example.com"});
+            send(app, Method::POST, "/users", Some(user)).await.1["id"].clone()
+        }
+
+        #[tokio::test]
+        async fn create_task_should_round_trip_both_content_types() {
+            let app = in_memory_app();
+            let user_id = user_id(&app).await;
+            let task = json!({"userId": user_id, "title": "Buy milk", "description": ""});
+
+            for (body, accept) in [
+                (msgpack(&task), MSGPACK),
+                (msgpack(&task), "application/json"),
+                (json_body(&task), MSGPACK),
+                (json_body(&task), "application/json"),
+            ] {
+                let request_type = body.0;
+                let (status, headers, created) =
+                    exchange(&app, Method::POST, "/tasks", body, accept).await;
+                let case = format!("{request_type} accepting {accept}");
+                assert_eq!(status, StatusCode::CREATED, "{case}");
+                assert_eq!(headers[header::CONTENT_TYPE], accept, "{case}");
+                let vary: Vec<_> = headers.get_all(header::VARY).iter().collect();
+                assert_eq!(vary, ["accept-profile", "accept"], "{case}");
+                assert_eq!(created["title"], "Buy milk", "{case}");
+
+                let uri = format!("/tasks/{}", created["id"].as_str().expect("task id"));
+                let no_body = ("application/json", Vec::new());
+                let (_, _, fetched) = exchange(&app, Method::GET, &uri, no_body, accept).await;
+                assert_eq!(fetched, created, "{case}");
+            }
+        }
+
+        #[tokio::test]
+        async fn errors_should_be_encoded_in_the_negotiated_format() {
+            let app = in_memory_app();
+            let untitled = json!({"userId": user_id(&app).await, "title": " ", "description": ""});
+
+            let (status, headers, error) =
+                exchange(&app, Method::POST, "/tasks", msgpack(&untitled), MSGPACK).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(headers[header::CONTENT_TYPE], MSGPACK);
+            assert_eq!(error["code"], "VALIDATION_ERROR");
+
+            let text = ("text/plain", b"Buy milk".to_vec());
+            let (status, _, error) = exchange(&app, Method::POST, "/tasks", text, MSGPACK).await;
+            assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            assert_eq!(error["code"], "UNSUPPORTED_MEDIA_TYPE");
+            let garbage = (MSGPACK, vec![0xc1]);
+            let (status, _, error) = exchange(&app, Method::POST, "/tasks", garbage, MSGPACK).await;
+            assert_eq!((status, &error["code"]), (StatusCode::BAD_REQUEST, &json!("INVALID_BODY")));
+
+            // A refused MessagePack falls back to JSON
+            let accept = "application/msgpack;q=0, application/json";
+            let (_, headers, _) =
+                exchange(&app, Method::POST, "/tasks", msgpack(&untitled), accept).await;
+            assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        }
+    }
+}
@@ -0,0 +1,750 @@
+//! In-memory task and attachment repository implementations for tests and examples
+
+use crate::features::task::domain::entity::OWNED_BY_ANOTHER_USER;
+use crate::features::task::domain::{
+    Attachment, AttachmentId, AttachmentRepository, CompleteOutcome, HourlyTaskCounts, Task,
+    TaskFilter, TaskId, TaskRepository, TaskSort, TaskViewPreferences,
+    TaskViewPreferencesRepository, Transaction, UnitOfWork, UpsertOutcome,
+};
+use crate::features::user::domain::{User, UserDependents};
+use crate::features::user::infrastructure::in_memory_repository::{insert_user, StoredUsers};
+use crate::features::user::infrastructure::InMemoryUserRepository;
+use crate::shared::domain::{
+    AuditEntry, DomainError, Entity, OutboxEvent, Page, TenantId, UserId,
+};
+use crate::shared::infrastructure::outbox::InMemoryOutbox;
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use futures_util::stream::BoxStream;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+/// A task with its tenant and the creation and completion time the entity does not carry
+struct Stored {
+    tenant: TenantId,
+    task: Task,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl Stored {
+    fn new(tenant: &TenantId, task: &Task) -> Self {
+        let completed_at =
+            task.is_completed().then(|| task.completed_at().unwrap_or_else(Utc::now));
+        let task = touched(task);
+        Self { tenant: tenant.clone(), task, created_at: Utc::now(), completed_at }
+    }
+}
+
+/// In-memory implementation of task repository, keyed by task ID
+///
+/// IDs are unique across tenants, as the primary key of the `tasks` table.
+#[derive(Default)]
+pub struct InMemoryTaskRepository {
+    tasks: RwLock<BTreeMap<String, Stored>>,
+}
+
+/// Insert `task` into `tasks`, returning it as persisted
+fn insert_task(
+    tasks: &mut BTreeMap<String, Stored>,
+    tenant: &TenantId,
+    task: &Task,
+) -> Result<Task, DomainError> {
+    if tasks.contains_key(task.id().value()) {
+        return Err(DomainError::already_exists(TaskId::entity_name(), "ID"));
+    }
+    let stored = Stored::new(tenant, task);
+    let persisted = stored.task.clone();
+    tasks.insert(task.id().value().to_owned(), stored);
+    Ok(persisted)
+}
+
+/// A stored task with its completion time, kept to undo a write
+type Replaced = (Task, Option<DateTime<Utc>>);
+
+/// Replace `task` in `tasks`, returning it as persisted and the stored task with its
+/// completion time as they were before
+fn update_task(
+    tasks: &mut BTreeMap<String, Stored>,
+    tenant: &TenantId,
+    task: &Task,
+) -> Result<(Task, Replaced), DomainError> {
+    let stored = tasks
+        .get_mut(task.id().value())
+        .filter(|s| &s.tenant == tenant)
+        .ok_or_else(|| DomainError::not_found(TaskId::entity_name()))?;
+    let before = (stored.task.clone(), stored.completed_at);
+    stored.task = touched(task);
+    stored.completed_at =
+        task.is_completed().then(|| stored.completed_at.unwrap_or_else(Utc::now));
+    Ok((stored.task.clone(), before))
+}
+
+impl InMemoryTaskRepository {
+    /// Tasks of `tenant` in ID order
+    async fn of_tenant(&self, tenant: &TenantId) -> Vec<Task> {
+        let tasks = self.tasks.read().await;
+        tasks.values().filter(|s| &s.tenant == tenant).map(|s| s.task.clone()).collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskRepository for InMemoryTaskRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        let tasks = self.tasks.read().await;
+        Ok(tasks.get(id.value()).filter(|s| &s.tenant == tenant).map(|s| s.task.clone()))
+    }
+
+    async fn find_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<Task>, DomainError> {
+        let mut tasks = self.of_tenant(tenant).await;
+        tasks.retain(|t| t.user_id() == user_id);
+        Ok(tasks)
+    }
+
+    async fn exists_open_with_title(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        title: &str,
+    ) -> Result<Option<TaskId>, DomainError> {
+        let title = title.to_lowercase();
+        Ok(self
+            .of_tenant(tenant)
+            .await
+            .into_iter()
+            .find(|t| {
+                t.user_id() == user_id && !t.is_completed() && t.title().to_lowercase() == title
+            })
+            .map(|t| t.id().clone()))
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        filter: &TaskFilter,
+        sort: TaskSort,
+        page: Page,
+    ) -> Result<Vec<Task>, DomainError> {
+        let mut tasks: Vec<_> =
+            self.of_tenant(tenant).await.into_iter().filter(|t| filter.matches(t)).collect();
+        tasks.sort_by(|a, b| sort.compare(a, b));
+        Ok(page.slice(tasks))
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        tenant: &'a TenantId,
+        filter: &'a TaskFilter,
+    ) -> BoxStream<'a, Result<Task, DomainError>> {
+        Box::pin(async_stream::stream! {
+            for task in self.of_tenant(tenant).await {
+                if filter.matches(&task) {
+                    yield Ok(task);
+                }
+            }
+        })
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<Task>, DomainError> {
+        Ok(self.of_tenant(tenant).await)
+    }
+
+    async fn find_recent_by_users(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+        per_user_limit: u32,
+    ) -> Result<Vec<Task>, DomainError> {
+        let tasks = self.tasks.read().await;
+        let limit = usize::try_from(per_user_limit).unwrap_or(usize::MAX);
+        let mut recent = Vec::new();
+        for user_id in user_ids {
+            let mut owned: Vec<_> = tasks
+                .values()
+                .filter(|s| &s.tenant == tenant && s.task.user_id() == user_id)
+                .map(|s| (s.created_at, &s.task))
+                .collect();
+            owned.sort_by(|(a, x), (b, y)| (b, y.id().value()).cmp(&(a, x.id().value())));
+            recent.extend(owned.into_iter().take(limit).map(|(_, t)| t.clone()));
+        }
+        Ok(recent)
+    }
+
+    async fn insert(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        insert_task(&mut *self.tasks.write().await, tenant, task)
+    }
+
+    async fn update(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        update_task(&mut *self.tasks.write().await, tenant, task).map(|(updated, _)| updated)
+    }
+
+    async fn upsert(&self, tenant: &TenantId, task: &Task) -> Result<UpsertOutcome, DomainError> {
+        let mut tasks = self.tasks.write().await;
+        if let Some(stored) = tasks.get_mut(task.id().value()) {
+            if &stored.tenant != tenant || stored.task.user_id() != task.user_id() {
+                return Err(DomainError::Conflict(OWNED_BY_ANOTHER_USER.into()));
+            }
+            let mut updated = stored.task.clone();
+            updated.edit(task.title(), task.description().to_owned())?;
+            stored.task = touched(&updated);
+            return Ok(UpsertOutcome::Updated(stored.task.clone()));
+        }
+        let stored = Stored::new(tenant, task);
+        let persisted = stored.task.clone();
+        tasks.insert(task.id().value().to_owned(), stored);
+        Ok(UpsertOutcome::Created(persisted))
+    }
+
+    async fn complete_if_open(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<CompleteOutcome, DomainError> {
+        let mut tasks = self.tasks.write().await;
+        let Some(stored) = tasks.get_mut(id.value()).filter(|s| &s.tenant == tenant) else {
+            return Ok(CompleteOutcome::NotFound);
+        };
+        if stored.task.is_completed() {
+            return Ok(CompleteOutcome::AlreadyCompleted);
+        }
+        let mut completed = stored.task.clone();
+        completed.complete()?;
+        stored.task = touched(&completed);
+        stored.completed_at = Some(Utc::now());
+        Ok(CompleteOutcome::Completed(stored.task.clone()))
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &TaskId) -> Result<bool, DomainError> {
+        let mut tasks = self.tasks.write().await;
+        if tasks.get(id.value()).is_none_or(|s| &s.tenant != tenant) {
+            return Ok(false);
+        }
+        Ok(tasks.remove(id.value()).is_some())
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        let mut tasks = self.tasks.write().await;
+        if tasks.get(id.value()).is_none_or(|s| &s.tenant != tenant) {
+            return Ok(None);
+        }
+        Ok(tasks.remove(id.value()).map(|stored| stored.task))
+    }
+
+    async fn hourly_counts(
+        &self,
+        tenant: &TenantId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<HourlyTaskCounts>, DomainError> {
+        let mut counts: BTreeMap<DateTime<Utc>, HourlyTaskCounts> = BTreeMap::new();
+        for stored in self.tasks.read().await.values().filter(|s| &s.tenant == tenant) {
+            let events = [(Some(stored.created_at), true), (stored.completed_at, false)];
+            for (at, created) in events.into_iter().filter_map(|(at, c)| Some((at?, c))) {
+                if at < since {
+                    continue;
+                }
+                let hour = at.duration_trunc(TimeDelta::hours(1)).unwrap_or(at);
+                let bucket = counts.entry(hour).or_insert(HourlyTaskCounts {
+                    hour,
+                    created: 0,
+                    completed: 0,
+                });
+                if created {
+                    bucket.created += 1;
+                } else {
+                    bucket.completed += 1;
+                }
+            }
+        }
+        Ok(counts.into_values().collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl UserDependents for InMemoryTaskRepository {
+    async fn count_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<u64, DomainError> {
+        let tasks = self.of_tenant(tenant).await;
+        Ok(tasks.iter().filter(|t| t.user_id() == user_id).count() as u64)
+    }
+
+    async fn count_by_user_ids(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+    ) -> Result<HashMap<UserId, u64>, DomainError> {
+        let mut counts = HashMap::new();
+        for task in self.of_tenant(tenant).await.iter().filter(|t| user_ids.contains(t.user_id())) {
+            *counts.entry(task.user_id().clone()).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+}
+
+/// Unit of work over the in-memory user and task repositories
+///
+/// A transaction holds the write locks of both repositories until it ends, so
+/// transactions run one at a time and other writes wait for them. Its writes are
+/// applied as they are made and undone again unless it commits; its audit entries
+/// and events are only kept once it commits.
+pub struct InMemoryUnitOfWork {
+    users: Arc<InMemoryUserRepository>,
+    tasks: Arc<InMemoryTaskRepository>,
+    audit_trail: RwLock<Vec<(TenantId, AuditEntry)>>,
+    outbox: Arc<InMemoryOutbox>,
+}
+
+impl InMemoryUnitOfWork {
+    /// Write to `users` and `tasks`, the repositories the rest of the application reads
+    #[must_use]
+    pub fn new(users: Arc<InMemoryUserRepository>, tasks: Arc<InMemoryTaskRepository>) -> Self {
+        Self { users, tasks, audit_trail: RwLock::default(), outbox: Arc::default() }
+    }
+
+    /// Append committed events to `outbox`, e.g. the one webhooks are delivered from,
+    /// instead of an outbox of its own
+    #[must_use]
+    pub fn with_outbox(mut self, outbox: Arc<InMemoryOutbox>) -> Self {
+        self.outbox = outbox;
+        self
+    }
+
+    /// Outbox the events of committed transactions are appended to
+    #[must_use]
+    pub fn outbox(&self) -> &Arc<InMemoryOutbox> {
+        &self.outbox
+    }
+
+    /// Audit entries of `tenant` recorded by committed transactions, oldest first
+    pub async fn audit_trail(&self, tenant: &TenantId) -> Vec<AuditEntry> {
+        let trail = self.audit_trail.read().await;
+        trail.iter().filter(|(t, _)| t == tenant).map(|(_, entry)| entry.clone()).collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl UnitOfWork for InMemoryUnitOfWork {
+    async fn begin(&self) -> Result<Box<dyn Transaction + '_>, DomainError> {
+        // Users first, as every transaction does, so that none waits on another
+        let users = self.users.write().await;
+        let tasks = self.tasks.tasks.write().await;
+        Ok(Box::new(InMemoryTransaction {
+            users,
+            tasks,
+            audit_trail: &self.audit_trail,
+            outbox: &self.outbox,
+            inserted_users: Vec::new(),
+            inserted_tasks: Vec::new(),
+            replaced_tasks: Vec::new(),
+            audit_entries: Vec::new(),
+            events: Vec::new(),
+        }))
+    }
+}
+
+/// Transaction of [`InMemoryUnitOfWork`], undoing its writes when dropped uncommitted
+struct InMemoryTransaction<'a> {
+    users: RwLockWriteGuard<'a, StoredUsers>,
+    tasks: RwLockWriteGuard<'a, BTreeMap<String, Stored>>,
+    audit_trail: &'a RwLock<Vec<(TenantId, AuditEntry)>>,
+    outbox: &'a InMemoryOutbox,
+    /// IDs of the users inserted so far
+    inserted_users: Vec<String>,
+    /// IDs of the tasks inserted so far
+    inserted_tasks: Vec<String>,
+    /// Tasks reassigned or updated so far, as they were before, with their completion
+    /// time
+    replaced_tasks: Vec<Replaced>,
+    /// Audit entries to keep on commit
+    audit_entries: Vec<(TenantId, AuditEntry)>,
+    /// Events to append to the outbox on commit
+    events: Vec<(TenantId, OutboxEvent)>,
+}
+
+#[async_trait::async_trait]
+impl Transaction for InMemoryTransaction<'_> {
+    async fn insert_user(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        insert_user(&mut self.users, tenant, user)?;
+        self.inserted_users.push(user.id().value().to_owned());
+        Ok(())
+    }
+
+    async fn find_task_for_update(
+        &mut self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        // Every task is locked for as long as the transaction
+        Ok(self.tasks.get(id.value()).filter(|s| &s.tenant == tenant).map(|s| s.task.clone()))
+    }
+
+    async fn update_task(&mut self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        let (updated, before) = update_task(&mut self.tasks, tenant, task)?;
+        self.replaced_tasks.push(before);
+        Ok(updated)
+    }
+
+    async fn insert_task(&mut self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        // The foreign key of the `tasks` table, which the repository alone cannot check
+        if self.users.get(task.user_id().value()).is_none_or(|(t, _)| t != tenant) {
+            return Err(DomainError::not_found(UserId::entity_name()));
+        }
+        let persisted = insert_task(&mut self.tasks, tenant, task)?;
+        self.inserted_tasks.push(task.id().value().to_owned());
+        Ok(persisted)
+    }
+
+    async fn reassign_tasks(
+        &mut self,
+        tenant: &TenantId,
+        from: &UserId,
+        to: &UserId,
+        only_open: bool,
+    ) -> Result<u64, DomainError> {
+        if self.users.get(to.value()).is_none_or(|(t, _)| t != tenant) {
+            return Err(DomainError::not_found(UserId::entity_name()));
+        }
+        let mut moved = 0;
+        for stored in self.tasks.values_mut() {
+            let task = &stored.task;
+            let reassigned = &stored.tenant == tenant
+                && task.user_id() == from
+                && !(only_open && task.is_completed());
+            if reassigned {
+                self.replaced_tasks.push((task.clone(), stored.completed_at));
+                stored.task = touched(&owned_by(task, to));
+                moved += 1;
+            }
+        }
+        Ok(moved)
+    }
+
+    async fn record_audit(
+        &mut self,
+        tenant: &TenantId,
+        entry: &AuditEntry,
+    ) -> Result<(), DomainError> {
+        self.audit_entries.push((tenant.clone(), entry.clone()));
+        Ok(())
+    }
+
+    async fn record_event(
+        &mut self,
+        tenant: &TenantId,
+        event: &OutboxEvent,
+    ) -> Result<(), DomainError> {
+        self.events.push((tenant.clone(), event.clone()));
+        Ok(())
+    }
+
+    async fn commit(mut self: Box<Self>) -> Result<(), DomainError> {
+        // Locked before anything is kept, so a commit dropped while waiting for the
+        // locks is rolled back whole rather than keeping the writes without the events
+        let mut audit_trail = self.audit_trail.write().await;
+        let mut outbox = self.outbox.appender().await;
+        self.inserted_users.clear();
+        self.inserted_tasks.clear();
+        self.replaced_tasks.clear();
+        audit_trail.append(&mut self.audit_entries);
+        outbox.append(self.events.drain(..));
+        Ok(())
+    }
+}
+
+impl Drop for InMemoryTransaction<'_> {
+    fn drop(&mut self) {
+        for (task, completed_at) in self.replaced_tasks.drain(..).rev() {
+            if let Some(stored) = self.tasks.get_mut(task.id().value()) {
+                stored.task = task;
+                stored.completed_at = completed_at;
+            }
+        }
+        for id in &self.inserted_tasks {
+            self.tasks.remove(id);
+        }
+        for id in &self.inserted_users {
+            self.users.remove(id);
+        }
+    }
+}
+
+/// Copy of `task` owned by `user_id`
+fn owned_by(task: &Task, user_id: &UserId) -> Task {
+    Task::reconstitute(
+        task.id().clone(),
+        user_id.clone(),
+        task.title().to_owned(),
+        task.description().to_owned(),
+        task.is_completed(),
+        task.updated_at(),
+    )
+    .with_checklist(task.checklist().to_vec())
+}
+
+/// Copy of `task` with `updated_at` set to now, as the database default/trigger would
+fn touched(task: &Task) -> Task {
+    Task::reconstitute(
+        task.id().clone(),
+        task.user_id().clone(),
+        task.title().to_owned(),
+        task.description().to_owned(),
+        task.is_completed(),
+        Some(chrono::Utc::now()),
+    )
+    .with_checklist(task.checklist().to_vec())
+}
+
+/// In-memory implementation of attachment repository, keyed by attachment ID
+///
+/// It does not know the tasks, so [`AttachmentRepository::insert`] accepts any task;
+/// the use cases check that the task exists first.
+#[derive(Default)]
+pub struct InMemoryAttachmentRepository {
+    attachments: RwLock<BTreeMap<String, (TenantId, Attachment)>>,
+    blob_deletions: RwLock<Vec<String>>,
+}
+
+impl InMemoryAttachmentRepository {
+    /// Remove the attachments of `tenant` matching `filter`, queueing their blobs
+    async fn remove(&self, tenant: &TenantId, filter: impl Fn(&Attachment) -> bool) -> u64 {
+        let mut attachments = self.attachments.write().await;
+        let mut removed = Vec::new();
+        attachments.retain(|_, (t, a)| {
+            let remove = t == tenant && filter(a);
+            if remove {
+                removed.push(a.storage_key().to_owned());
+            }
+            !remove
+        });
+        let count = removed.len() as u64;
+        self.blob_deletions.write().await.extend(removed);
+        count
+    }
+}
+
+#[async_trait::async_trait]
+impl AttachmentRepository for InMemoryAttachmentRepository {
+    async fn insert(&self, tenant: &TenantId, attachment: &Attachment) -> Result<(), DomainError> {
+        let mut attachments = self.attachments.write().await;
+        if attachments.contains_key(attachment.id().value()) {
+            return Err(DomainError::already_exists(AttachmentId::entity_name(), "ID"));
+        }
+        attachments
+            .insert(attachment.id().value().to_owned(), (tenant.clone(), attachment.clone()));
+        Ok(())
+    }
+
+    async fn find_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<Vec<Attachment>, DomainError> {
+        let attachments = self.attachments.read().await;
+        let mut found: Vec<_> = attachments
+            .values()
+            .filter(|(t, a)| t == tenant && a.task_id() == task_id)
+            .map(|(_, a)| a.clone())
+            .collect();
+        found.sort_by(|a, b| {
+            (a.created_at(), a.id().value()).cmp(&(b.created_at(), b.id().value()))
+        });
+        Ok(found)
+    }
+
+    async fn delete(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+        id: &AttachmentId,
+    ) -> Result<bool, DomainError> {
+        Ok(self.remove(tenant, |a| a.task_id() == task_id && a.id() == id).await > 0)
+    }
+
+    async fn delete_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<u64, DomainError> {
+        Ok(self.remove(tenant, |a| a.task_id() == task_id).await)
+    }
+
+    async fn queued_blob_deletions(&self, limit: u32) -> Result<Vec<String>, DomainError> {
+        let queue = self.blob_deletions.read().await;
+        Ok(queue.iter().take(limit as usize).cloned().collect())
+    }
+
+    async fn dequeue_blob_deletion(&self, storage_key: &str) -> Result<(), DomainError> {
+        self.blob_deletions.write().await.retain(|key| key != storage_key);
+        Ok(())
+    }
+}
+
+/// In-memory implementation of task view preferences repository
+///
+/// It does not know the users, so [`TaskViewPreferencesRepository::save`] accepts any
+/// user; the use cases check that the user exists first.
+#[derive(Default)]
+pub struct InMemoryTaskViewPreferencesRepository {
+    preferences: RwLock<BTreeMap<(TenantId, String), TaskViewPreferences>>,
+}
+
+#[async_trait::async_trait]
+impl TaskViewPreferencesRepository for InMemoryTaskViewPreferencesRepository {
+    async fn find(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Option<TaskViewPreferences>, DomainError> {
+        let key = (tenant.clone(), user_id.value().to_owned());
+        Ok(self.preferences.read().await.get(&key).copied())
+    }
+
+    async fn save(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        preferences: &TaskViewPreferences,
+    ) -> Result<(), DomainError> {
+        let key = (tenant.clone(), user_id.value().to_owned());
+        self.preferences.write().await.insert(key, *preferences);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::user::domain::UserRepository;
+    use crate::testing::{repository_contract, CancellationSafety};
+
+    #[tokio::test]
+    async fn lists_should_be_ordered_by_id() {
+        let users = InMemoryUserRepository::default();
+        let tasks = InMemoryTaskRepository::default();
+        repository_contract::tasks_should_list_in_id_order(&users, &tasks).await;
+    }
+
+    #[tokio::test]
+    async fn updates_of_deleted_tasks_should_fail() {
+        let users = InMemoryUserRepository::default();
+        let tasks = InMemoryTaskRepository::default();
+        repository_contract::tasks_should_not_update_deleted_rows(&users, &tasks).await;
+    }
+
+    #[tokio::test]
+    async fn deletions_should_return_the_deleted_task() {
+        let users = InMemoryUserRepository::default();
+        let tasks = InMemoryTaskRepository::default();
+        repository_contract::tasks_should_return_deleted_rows(&users, &tasks).await;
+    }
+
+    #[tokio::test]
+    async fn pages_should_follow_the_sort_order() {
+        let users = InMemoryUserRepository::default();
+        let tasks = InMemoryTaskRepository::default();
+        repository_contract::tasks_should_page_in_sort_order(&users, &tasks).await;
+    }
+
+    #[tokio::test]
+    async fn checklists_should_be_stored_with_their_task() {
+        let users = InMemoryUserRepository::default();
+        let tasks = InMemoryTaskRepository::default();
+        repository_contract::tasks_should_store_checklists(&users, &tasks).await;
+    }
+
+    #[tokio::test]
+    async fn tasks_should_be_counted_per_requested_owner() {
+        let users = InMemoryUserRepository::default();
+        let tasks = InMemoryTaskRepository::default();
+        repository_contract::tasks_should_count_by_user_ids(&users, &tasks).await;
+    }
+
+    #[tokio::test]
+    async fn tasks_inserted_completed_should_keep_their_completion_time() {
+        let users = InMemoryUserRepository::default();
+        let tasks = InMemoryTaskRepository::default();
+        repository_contract::tasks_should_insert_completed_at_their_time(&users, &tasks).await;
+    }
+
+    #[tokio::test]
+    async fn tasks_read_for_update_should_stay_locked_until_the_transaction_ends() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let tasks = Arc::new(InMemoryTaskRepository::default());
+        let unit_of_work = InMemoryUnitOfWork::new(Arc::clone(&users), Arc::clone(&tasks));
+        repository_contract::tasks_should_lock_rows_read_for_update(&*users, &*tasks, &unit_of_work)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn saved_preferences_should_replace_earlier_ones() {
+        let users = InMemoryUserRepository::default();
+        let preferences = InMemoryTaskViewPreferencesRepository::default();
+        repository_contract::task_view_preferences_should_be_replaced(&users, &preferences).await;
+    }
+
+    #[tokio::test]
+    async fn unit_of_work_should_check_task_owners_and_undo_uncommitted_inserts() {
+        let tenant = TenantId::default();
+        let users = Arc::new(InMemoryUserRepository::default());
+        let tasks = Arc::new(InMemoryTaskRepository::default());
+        let unit_of_work = InMemoryUnitOfWork::new(Arc::clone(&users), Arc::clone(&tasks));
+        let user = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+            .expect("valid user");
+        let task = Task::new(TaskId::generate(), user.id().clone(), "Read", String::new())
+            .expect("valid task");
+
+        let mut transaction = unit_of_work.begin().await.expect("begin");
+        let orphan = transaction.insert_task(&tenant, &task).await;
+        assert!(matches!(orphan, Err(DomainError::NotFound(m)) if m == "User not found"));
+        transaction.insert_user(&tenant, &user).await.expect("insert user");
+        transaction.insert_task(&tenant, &task).await.expect("insert task");
+        drop(transaction);
+        assert!(users.find_all_unbounded(&tenant).await.expect("users").is_empty());
+        assert!(tasks.find_all_unbounded(&tenant).await.expect("tasks").is_empty());
+
+        let mut transaction = unit_of_work.begin().await.expect("begin");
+        transaction.insert_user(&tenant, &user).await.expect("insert user");
+        transaction.insert_task(&tenant, &task).await.expect("insert task");
+        transaction.commit().await.expect("commit");
+        assert_eq!(users.find_all_unbounded(&tenant).await.expect("users").len(), 1);
+        assert_eq!(tasks.find_all_unbounded(&tenant).await.expect("tasks").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn commit_dropped_while_waiting_for_its_locks_should_roll_back_with_its_events() {
+        let tenant = TenantId::default();
+        let users = Arc::new(InMemoryUserRepository::default());
+        let tasks = Arc::new(InMemoryTaskRepository::default());
+        let unit_of_work = InMemoryUnitOfWork::new(Arc::clone(&users), tasks);
+        let user = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+            .expect("valid user");
+        let (subject, payload) = ("u-1".into(), serde_json::json!({"name": "Alice"}));
+        let event = OutboxEvent { event_type: "user.onboarded", version: 1, subject, payload };
+
+        let mut transaction = unit_of_work.begin().await.expect("begin");
+        transaction.insert_user(&tenant, &user).await.expect("insert user");
+        transaction.record_event(&tenant, &event).await.expect("record");
+        let audit_trail = unit_of_work.audit_trail.read().await;
+        let commit = CancellationSafety::after_polls(1).run(transaction.commit()).await;
+        assert!(commit.is_none(), "the commit waits for the audit trail");
+        drop(audit_trail);
+
+        assert!(users.find_all_unbounded(&tenant).await.expect("users").is_empty());
+        assert!(unit_of_work.outbox().events(&tenant).await.is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+//! Task, attachment and task view preferences repository decorators recording call
+//! timings
+
+use crate::features::task::domain::{
+    Attachment, AttachmentId, AttachmentRepository, CompleteOutcome, HourlyTaskCounts, Task,
+    TaskFilter, TaskId, TaskRepository, TaskSort, TaskViewPreferences,
+    TaskViewPreferencesRepository, UpsertOutcome,
+};
+use crate::features::user::domain::UserDependents;
+use crate::shared::domain::{DomainError, Page, TenantId, UserId};
+use crate::shared::infrastructure::instrumentation::{timed, timed_stream};
+use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const ENTITY: &str = "task";
+
+/// Times every call of the wrapped task repository (see
+/// [`instrumentation`](crate::shared::infrastructure::instrumentation) for how it
+/// composes with other decorators)
+pub struct InstrumentedTaskRepository {
+    inner: Arc<dyn TaskRepository>,
+}
+
+impl InstrumentedTaskRepository {
+    /// Wrap `inner`
+    #[must_use]
+    pub fn new(inner: Arc<dyn TaskRepository>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskRepository for InstrumentedTaskRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        timed(ENTITY, "find_by_id", self.inner.find_by_id(tenant, id)).await
+    }
+
+    async fn find_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<Task>, DomainError> {
+        timed(ENTITY, "find_by_user_id", self.inner.find_by_user_id(tenant, user_id)).await
+    }
+
+    async fn exists_open_with_title(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        title: &str,
+    ) -> Result<Option<TaskId>, DomainError> {
+        let call = self.inner.exists_open_with_title(tenant, user_id, title);
+        timed(ENTITY, "exists_open_with_title", call).await
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        filter: &TaskFilter,
+        sort: TaskSort,
+        page: Page,
+    ) -> Result<Vec<Task>, DomainError> {
+        timed(ENTITY, "find_page", self.inner.find_page(tenant, filter, sort, page)).await
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        tenant: &'a TenantId,
+        filter: &'a TaskFilter,
+    ) -> BoxStream<'a, Result<Task, DomainError>> {
+        timed_stream(ENTITY, "stream_filtered", self.inner.stream_filtered(tenant, filter))
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<Task>, DomainError> {
+        timed(ENTITY, "find_all_unbounded", self.inner.find_all_unbounded(tenant)).await
+    }
+
+    async fn find_recent_by_users(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+        per_user_limit: u32,
+    ) -> Result<Vec<Task>, DomainError> {
+        let call = self.inner.find_recent_by_users(tenant, user_ids, per_user_limit);
+        timed(ENTITY, "find_recent_by_users", call).await
+    }
+
+    async fn insert(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        timed(ENTITY, "insert", self.inner.insert(tenant, task)).await
+    }
+
+    async fn update(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        timed(ENTITY, "update", self.inner.update(tenant, task)).await
+    }
+
+    async fn upsert(&self, tenant: &TenantId, task: &Task) -> Result<UpsertOutcome, DomainError> {
+        timed(ENTITY, "upsert", self.inner.upsert(tenant, task)).await
+    }
+
+    async fn complete_if_open(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<CompleteOutcome, DomainError> {
+        timed(ENTITY, "complete_if_open", self.inner.complete_if_open(tenant, id)).await
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &TaskId) -> Result<bool, DomainError> {
+        timed(ENTITY, "delete", self.inner.delete(tenant, id)).await
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        timed(ENTITY, "delete_returning", self.inner.delete_returning(tenant, id)).await
+    }
+
+    async fn hourly_counts(
+        &self,
+        tenant: &TenantId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<HourlyTaskCounts>, DomainError> {
+        timed(ENTITY, "hourly_counts", self.inner.hourly_counts(tenant, since)).await
+    }
+
+    async fn tasks_reassigned(&self, tenant: &TenantId, from: &UserId) -> Result<(), DomainError> {
+        self.inner.tasks_reassigned(tenant, from).await
+    }
+
+    async fn task_updated(&self, tenant: &TenantId, id: &TaskId) -> Result<(), DomainError> {
+        self.inner.task_updated(tenant, id).await
+    }
+}
+
+#[async_trait::async_trait]
+impl UserDependents for InstrumentedTaskRepository {
+    async fn count_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<u64, DomainError> {
+        timed(ENTITY, "count_by_user_id", self.inner.count_by_user_id(tenant, user_id)).await
+    }
+
+    async fn count_by_user_ids(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+    ) -> Result<HashMap<UserId, u64>, DomainError> {
+        timed(ENTITY, "count_by_user_ids", self.inner.count_by_user_ids(tenant, user_ids)).await
+    }
+
+    async fn user_deleted(&self, tenant: &TenantId, user_id: &UserId) -> Result<(), DomainError> {
+        self.inner.user_deleted(tenant, user_id).await
+    }
+}
+
+const ATTACHMENT: &str = "attachment";
+
+/// Times every call of the wrapped attachment repository
+pub struct InstrumentedAttachmentRepository {
+    inner: Arc<dyn AttachmentRepository>,
+}
+
+impl InstrumentedAttachmentRepository {
+    /// Wrap `inner`
+    #[must_use]
+    pub fn new(inner: Arc<dyn AttachmentRepository>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait::async_trait]
+impl AttachmentRepository for InstrumentedAttachmentRepository {
+    async fn insert(&self, tenant: &TenantId, attachment: &Attachment) -> Result<(), DomainError> {
+        timed(ATTACHMENT, "insert", self.inner.insert(tenant, attachment)).await
+    }
+
+    async fn find_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<Vec<Attachment>, DomainError> {
+        timed(ATTACHMENT, "find_by_task", self.inner.find_by_task(tenant, task_id)).await
+    }
+
+    async fn delete(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+        id: &AttachmentId,
+    ) -> Result<bool, DomainError> {
+        timed(ATTACHMENT, "delete", self.inner.delete(tenant, task_id, id)).await
+    }
+
+    async fn delete_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<u64, DomainError> {
+        timed(ATTACHMENT, "delete_by_task", self.inner.delete_by_task(tenant, task_id)).await
+    }
+
+    async fn queued_blob_deletions(&self, limit: u32) -> Result<Vec<String>, DomainError> {
+        timed(ATTACHMENT, "queued_blob_deletions", self.inner.queued_blob_deletions(limit)).await
+    }
+
+    async fn dequeue_blob_deletion(&self, storage_key: &str) -> Result<(), DomainError> {
+        let call = self.inner.dequeue_blob_deletion(storage_key);
+        timed(ATTACHMENT, "dequeue_blob_deletion", call).await
+    }
+}
+
+const PREFERENCES: &str = "preferences";
+
+/// Times every call of the wrapped task view preferences repository
+pub struct InstrumentedTaskViewPreferencesRepository {
+    inner: Arc<dyn TaskViewPreferencesRepository>,
+}
+
+impl InstrumentedTaskViewPreferencesRepository {
+    /// Wrap `inner`
+    #[must_use]
+    pub fn new(inner: Arc<dyn TaskViewPreferencesRepository>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskViewPreferencesRepository for InstrumentedTaskViewPreferencesRepository {
+    async fn find(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Option<TaskViewPreferences>, DomainError> {
+        timed(PREFERENCES, "find", self.inner.find(tenant, user_id)).await
+    }
+
+    async fn save(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        preferences: &TaskViewPreferences,
+    ) -> Result<(), DomainError> {
+        timed(PREFERENCES, "save", self.inner.save(tenant, user_id, preferences)).await
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::shared::domain::Entity;
+    use crate::test_support::recorded_repository_calls;
+
+    #[test]
+    fn calls_should_be_recorded_including_user_dependents() {
+        let calls = recorded_repository_calls(async {
+            let repo = InstrumentedTaskRepository::new(Arc::new(InMemoryTaskRepository::default()));
+            let user_id = UserId::new("user1").expect("valid user id");
+            let task = Task::new(TaskId::generate(), user_id.clone(), "Task", String::new())
+                .expect("valid task");
+            let tenant = TenantId::default();
+            repo.insert(&tenant, &task).await.expect("insert");
+            assert_eq!(repo.count_by_user_id(&tenant, &user_id).await.expect("count"), 1);
+            repo.complete_if_open(&tenant, task.id()).await.expect("complete");
+        });
+        assert_eq!(
+            calls,
+            ["task.complete_if_open ok: 1", "task.count_by_user_id ok: 1", "task.insert ok: 1"]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Blob storage on the local filesystem, for development and single-host setups
+
+use crate::features::task::domain::{BlobStorage, PresignedUrl};
+use crate::shared::domain::DomainError;
+use chrono::{TimeDelta, Utc};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use url::Url;
+
+/// Stores each blob as a file under a root directory
+///
+/// Upload and download URLs are `file://` URLs of that file, so clients must run on
+/// the same host and create missing directories themselves; their expiry is
+/// advisory. Use the `s3` storage wherever clients are remote.
+pub struct LocalBlobStorage {
+    root: PathBuf,
+    url_ttl: Duration,
+}
+
+impl LocalBlobStorage {
+    /// Store blobs under `root`, made absolute against the working directory, and
+    /// report URLs as valid for `url_ttl`
+    ///
+    /// # Errors
+    /// Fails when the working directory cannot be determined.
+    pub fn new(root: impl AsRef<Path>, url_ttl: Duration) -> std::io::Result<Self> {
+        Ok(Self { root: std::path::absolute(root)?, url_ttl })
+    }
+
+    /// Path of the blob under `key`; keys with anything but plain path segments are
+    /// rejected, so no key reaches outside the root
+    fn path_of(&self, key: &str) -> Result<PathBuf, DomainError> {
+        let relative = Path::new(key);
+        if key.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(DomainError::Infrastructure(format!("Invalid storage key '{key}'")));
+        }
+        Ok(self.root.join(relative))
+    }
+
+    fn file_url(&self, key: &str) -> Result<PresignedUrl, DomainError> {
+        let url = Url::from_file_path(self.path_of(key)?)
+            .map_err(|()| DomainError::Infrastructure(format!("No file URL for '{key}'")))?;
+        let ttl = TimeDelta::from_std(self.url_ttl).unwrap_or(TimeDelta::MAX);
+        Ok(PresignedUrl { url: url.into(), expires_at: Utc::now() + ttl })
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobStorage for LocalBlobStorage {
+    async fn issue_upload_url(
+        &self,
+        key: &str,
+        _content_type: &str,
+    ) -> Result<PresignedUrl, DomainError> {
+        self.file_url(key)
+    }
+
+    async fn issue_download_url(&self, key: &str) -> Result<PresignedUrl, DomainError> {
+        self.file_url(key)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DomainError> {
+        match tokio::fs::remove_file(self.path_of(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                tracing::error!("Failed to delete blob '{key}': {e}");
+                Err(DomainError::Infrastructure("Failed to delete blob".into()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn urls_should_name_files_under_the_root_and_delete_should_remove_them() {
+        let root = std::env::temp_dir().join(format!("blobs-{}", uuid::Uuid::new_v4()));
+        let storage = LocalBlobStorage::new(&root, Duration::from_mins(1)).expect("storage");
+        let upload = storage.issue_upload_url("acme/attachments/a1", "image/png").await;
+        let path = Url::parse(&upload.expect("upload URL").url)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+            .expect("file URL");
+        assert_eq!(path, root.join("acme/attachments/a1"));
+
+        std::fs::create_dir_all(root.join("acme/attachments")).expect("create dir");
+        std::fs::write(&path, b"png").expect("write blob");
+        storage.delete("acme/attachments/a1").await.expect("delete");
+        assert!(!path.exists());
+        storage.delete("acme/attachments/a1").await.expect("delete missing blob");
+        std::fs::remove_dir_all(&root).ok();
+
+        for key in ["", "../escape", "/etc/passwd"] {
+            assert!(storage.issue_download_url(key).await.is_err(), "{key}");
+            assert!(storage.delete(key).await.is_err(), "{key}");
+        }
+    }
+}
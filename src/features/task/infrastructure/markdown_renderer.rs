@@ -0,0 +1,44 @@
+//! Markdown description renderer backed by pulldown-cmark and ammonia
+
+use crate::features::task::application::DescriptionRenderer;
+use pulldown_cmark::{html, Options, Parser};
+
+/// Renders `CommonMark` (plus tables and strikethrough) and sanitizes the HTML
+///
+/// Sanitizing uses ammonia's allow-list, which drops `<script>`, `<iframe>`,
+/// event handler attributes and `javascript:` URLs.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MarkdownRenderer;
+
+impl DescriptionRenderer for MarkdownRenderer {
+    fn render(&self, markdown: &str) -> String {
+        let parser =
+            Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH);
+        let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+        html::push_html(&mut unsafe_html, parser);
+        ammonia::clean(&unsafe_html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_should_convert_markdown() {
+        let html = MarkdownRenderer.render("# Title\n\n**bold** and ~~gone~~");
+        assert_eq!(html, "<h1>Title</h1>\n<p><strong>bold</strong> and <del>gone</del></p>\n");
+    }
+
+    #[test]
+    fn render_should_neutralize_xss_payloads() {
+        let markdown = "<script>alert(1)</script>\n\n\
+                        <iframe src=\"https://evil.example\"></iframe>\n\n\
+                        <img src=x onerror=\"alert(1)\">\n\n\
+                        [click](javascript:alert(1))";
+        let html = MarkdownRenderer.render(markdown);
+        for forbidden in ["<script", "<iframe", "onerror", "javascript:"] {
+            assert!(!html.contains(forbidden), "{forbidden} survived in {html}");
+        }
+    }
+}
@@ -1,6 +1,31 @@
 //! Task infrastructure layer
 
+pub mod blob_cleanup_job;
+pub mod cached_repository;
+pub mod csv_import;
 pub mod http;
+pub mod in_memory_repository;
+pub mod instrumented_repository;
+pub mod local_blob_storage;
+pub mod markdown_renderer;
 pub mod repository;
+#[cfg(feature = "s3")]
+pub mod s3_blob_storage;
 
-pub use repository::PgTaskRepository;
+pub use blob_cleanup_job::BlobCleanupJob;
+pub use cached_repository::CachedTaskRepository;
+pub use in_memory_repository::{
+    InMemoryAttachmentRepository, InMemoryTaskRepository, InMemoryTaskViewPreferencesRepository,
+    InMemoryUnitOfWork,
+};
+pub use instrumented_repository::{
+    InstrumentedAttachmentRepository, InstrumentedTaskRepository,
+    InstrumentedTaskViewPreferencesRepository,
+};
+pub use local_blob_storage::LocalBlobStorage;
+pub use markdown_renderer::MarkdownRenderer;
+pub use repository::{
+    PgAttachmentRepository, PgTaskRepository, PgTaskViewPreferencesRepository, PgUnitOfWork,
+};
+#[cfg(feature = "s3")]
+pub use s3_blob_storage::{S3BlobStorage, S3Settings};
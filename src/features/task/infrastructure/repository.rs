@@ -1,9 +1,27 @@
-//! `PostgreSQL` task repository implementation
+//! `PostgreSQL` task, attachment and task view preferences repository implementations
 
-use crate::features::task::domain::{Task, TaskId, TaskRepository};
-use crate::shared::domain::{DomainError, Entity, UserId};
-use crate::shared::infrastructure::database::map_db_error;
-use sqlx::PgPool;
+use crate::features::task::domain::entity::OWNED_BY_ANOTHER_USER;
+use crate::features::task::domain::{
+    Attachment, AttachmentId, AttachmentRepository, CompleteOutcome, HourlyTaskCounts, Task,
+    TaskFilter, TaskId, TaskRepository, TaskSort, TaskViewPreferences,
+    TaskViewPreferencesRepository, Transaction, UnitOfWork, UpsertOutcome,
+};
+use crate::features::user::domain::{User, UserDependents};
+use crate::features::user::infrastructure::pg_repository::insert_query as user_insert_query;
+use crate::shared::domain::{
+    AuditEntry, DomainError, Entity, OutboxEvent, Page, TenantId, UserId,
+};
+use crate::shared::infrastructure::database::{acquire, map_db_error, run_query};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures_util::stream::BoxStream;
+use futures_util::TryStreamExt;
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
+use sqlx::{PgPool, Postgres};
+use std::collections::HashMap;
+
+/// Query returning [`TaskRow`]s, its arguments borrowed for `'a`
+type TaskQuery<'a> = QueryAs<'a, Postgres, TaskRow, PgArguments>;
 
 /// `PostgreSQL` implementation of task repository
 #[derive(Clone)]
@@ -13,6 +31,7 @@ pub struct PgTaskRepository {
 
 impl PgTaskRepository {
     /// Create a new `PostgreSQL` task repository
+    #[must_use]
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
@@ -20,77 +39,482 @@ impl PgTaskRepository {
 
 #[async_trait::async_trait]
 impl TaskRepository for PgTaskRepository {
-    async fn find_by_id(&self, id: &TaskId) -> Result<Option<Task>, DomainError> {
-        Ok(sqlx::query_as::<_, TaskRow>(
-            "SELECT id, user_id, title, description, completed, updated_at FROM tasks WHERE id = $1",
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        let query = sqlx::query_as::<_, TaskRow>(
+            "SELECT id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist FROM tasks \
+             WHERE tenant_id = $1 AND id = $2",
         )
-        .bind(id.value())
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| map_db_error(e, "find", "task"))?
-        .map(TaskRow::into_domain))
+        .bind(tenant.value())
+        .bind(id.value());
+        let mut conn = acquire(&self.pool, "find", "task").await?;
+        let row = run_query(query.fetch_optional(&mut *conn), "find", "task").await?;
+        row.map(TaskRow::into_domain).transpose()
+    }
+
+    async fn find_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<Task>, DomainError> {
+        let query = sqlx::query_as::<_, TaskRow>(
+            "SELECT id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist FROM tasks \
+             WHERE tenant_id = $1 AND user_id = $2 ORDER BY id",
+        )
+        .bind(tenant.value())
+        .bind(user_id.value());
+        let mut conn = acquire(&self.pool, "find_by_user_id", "task").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_by_user_id", "task").await?;
+        rows.into_iter().map(TaskRow::into_domain).collect()
     }
 
-    async fn find_by_user_id(&self, user_id: &UserId) -> Result<Vec<Task>, DomainError> {
-        Ok(sqlx::query_as::<_, TaskRow>(
-            "SELECT id, user_id, title, description, completed, updated_at FROM tasks WHERE user_id = $1",
+    async fn exists_open_with_title(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        title: &str,
+    ) -> Result<Option<TaskId>, DomainError> {
+        let query = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM tasks WHERE tenant_id = $1 AND user_id = $2 \
+             AND lower(title) = lower($3) AND NOT completed ORDER BY id LIMIT 1",
         )
+        .bind(tenant.value())
         .bind(user_id.value())
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| map_db_error(e, "find_by_user_id", "task"))?
-        .into_iter()
-        .map(TaskRow::into_domain)
-        .collect())
+        .bind(title);
+        let mut conn = acquire(&self.pool, "exists_open_with_title", "task").await?;
+        let id = run_query(query.fetch_optional(&mut *conn), "exists_open_with_title", "task").await?;
+        Ok(id.map(TaskId::from_trusted))
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        filter: &TaskFilter,
+        sort: TaskSort,
+        page: Page,
+    ) -> Result<Vec<Task>, DomainError> {
+        let sql = format!(
+            "SELECT id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist FROM tasks \
+             WHERE tenant_id = $6 \
+             AND ($1::TEXT IS NULL OR user_id = $1) \
+             AND ($2::BOOLEAN IS NULL OR completed = $2) \
+             AND ($3::TEXT IS NULL OR strpos(lower(title), $3) > 0 \
+                  OR strpos(lower(description), $3) > 0) \
+             ORDER BY {} LIMIT $4 OFFSET $5",
+            order_by(sort)
+        );
+        let query = sqlx::query_as::<_, TaskRow>(&sql)
+        .bind(filter.user_id.as_ref().map(UserId::value))
+        .bind(filter.completed)
+        .bind(filter.search.as_deref())
+        .bind(i64::from(page.limit))
+        .bind(i64::from(page.offset))
+        .bind(tenant.value());
+        let mut conn = acquire(&self.pool, "find_page", "task").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_page", "task").await?;
+        rows.into_iter().map(TaskRow::into_domain).collect()
     }
 
-    async fn find_all(&self) -> Result<Vec<Task>, DomainError> {
-        Ok(sqlx::query_as::<_, TaskRow>(
-            "SELECT id, user_id, title, description, completed, updated_at FROM tasks",
+    /// Rows are fetched one by one on a connection held until the stream ends or is
+    /// dropped; only acquiring it counts against the request deadline.
+    fn stream_filtered<'a>(
+        &'a self,
+        tenant: &'a TenantId,
+        filter: &'a TaskFilter,
+    ) -> BoxStream<'a, Result<Task, DomainError>> {
+        Box::pin(async_stream::try_stream! {
+            let mut conn = acquire(&self.pool, "stream_filtered", "task").await?;
+            let mut rows = sqlx::query_as::<_, TaskRow>(
+                "SELECT id, user_id, title, description, completed, updated_at, \
+                 checklist::text AS checklist FROM tasks \
+                 WHERE tenant_id = $4 \
+                 AND ($1::TEXT IS NULL OR user_id = $1) \
+                 AND ($2::BOOLEAN IS NULL OR completed = $2) \
+                 AND ($3::TEXT IS NULL OR strpos(lower(title), $3) > 0 \
+                      OR strpos(lower(description), $3) > 0) \
+                 ORDER BY id",
+            )
+            .bind(filter.user_id.as_ref().map(UserId::value))
+            .bind(filter.completed)
+            .bind(filter.search.as_deref())
+            .bind(tenant.value())
+            .fetch(&mut *conn);
+            while let Some(row) =
+                rows.try_next().await.map_err(|e| map_db_error(e, "stream_filtered", "task"))?
+            {
+                yield row.into_domain()?;
+            }
+        })
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<Task>, DomainError> {
+        let query = sqlx::query_as::<_, TaskRow>(
+            "SELECT id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist FROM tasks \
+             WHERE tenant_id = $1 ORDER BY id",
         )
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| map_db_error(e, "find_all", "task"))?
-        .into_iter()
-        .map(TaskRow::into_domain)
-        .collect())
+        .bind(tenant.value());
+        let mut conn = acquire(&self.pool, "find_all", "task").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_all", "task").await?;
+        rows.into_iter().map(TaskRow::into_domain).collect()
     }
 
-    async fn insert(&self, task: &Task) -> Result<(), DomainError> {
-        sqlx::query(
-            "INSERT INTO tasks (id, user_id, title, description) VALUES ($1, $2, $3, $4)",
+    async fn find_recent_by_users(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+        per_user_limit: u32,
+    ) -> Result<Vec<Task>, DomainError> {
+        let user_ids: Vec<&str> = user_ids.iter().map(UserId::value).collect();
+        let query = sqlx::query_as::<_, TaskRow>(
+            "SELECT id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist FROM ( \
+                 SELECT *, ROW_NUMBER() OVER ( \
+                     PARTITION BY user_id ORDER BY created_at DESC, id DESC \
+                 ) AS position \
+                 FROM tasks WHERE tenant_id = $3 AND user_id = ANY($1) \
+             ) ranked \
+             WHERE position <= $2 ORDER BY user_id, position",
+        )
+        .bind(user_ids)
+        .bind(i64::from(per_user_limit))
+        .bind(tenant.value());
+        let mut conn = acquire(&self.pool, "find_recent", "task").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_recent", "task").await?;
+        rows.into_iter().map(TaskRow::into_domain).collect()
+    }
+
+    async fn insert(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        let mut conn = acquire(&self.pool, "insert", "task").await?;
+        let query = insert_query(tenant, task);
+        run_query(query.fetch_one(&mut *conn), "insert", "task").await?.into_domain()
+    }
+
+    async fn update(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        let query = update_query(tenant, task);
+        let mut conn = acquire(&self.pool, "update", "task").await?;
+        run_query(query.fetch_optional(&mut *conn), "update", "task")
+            .await?
+            .ok_or_else(|| DomainError::not_found(TaskId::entity_name()))?
+            .into_domain()
+    }
+
+    async fn upsert(&self, tenant: &TenantId, task: &Task) -> Result<UpsertOutcome, DomainError> {
+        // `xmax` is 0 only for a freshly inserted row; the WHERE clause leaves the task
+        // of another user or tenant untouched and returns no row
+        let query = sqlx::query_as::<_, UpsertedRow>(
+            "INSERT INTO tasks (tenant_id, id, user_id, title, description) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (id) DO UPDATE SET title = EXCLUDED.title, \
+             description = EXCLUDED.description, updated_at = CURRENT_TIMESTAMP \
+             WHERE tasks.tenant_id = EXCLUDED.tenant_id AND tasks.user_id = EXCLUDED.user_id \
+             RETURNING id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist, xmax = 0 AS inserted",
         )
+        .bind(tenant.value())
         .bind(task.id().value())
         .bind(task.user_id().value())
         .bind(task.title())
-        .bind(task.description())
-        .execute(&self.pool)
-        .await
-        .map_err(|e| map_db_error(e, "insert", "task"))?;
+        .bind(task.description());
+        let mut conn = acquire(&self.pool, "upsert", "task").await?;
+        let row = run_query(query.fetch_optional(&mut *conn), "upsert", "task")
+            .await?
+            .ok_or_else(|| DomainError::Conflict(OWNED_BY_ANOTHER_USER.into()))?;
+        let task = row.task.into_domain()?;
+        Ok(if row.inserted { UpsertOutcome::Created(task) } else { UpsertOutcome::Updated(task) })
+    }
+
+    async fn complete_if_open(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<CompleteOutcome, DomainError> {
+        let query = sqlx::query_as::<_, TaskRow>(
+            "UPDATE tasks SET completed = true, updated_at = CURRENT_TIMESTAMP, \
+             completed_at = CURRENT_TIMESTAMP \
+             WHERE tenant_id = $1 AND id = $2 AND completed = false \
+             RETURNING id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist",
+        )
+        .bind(tenant.value())
+        .bind(id.value());
+        let mut conn = acquire(&self.pool, "complete", "task").await?;
+        if let Some(row) = run_query(query.fetch_optional(&mut *conn), "complete", "task").await? {
+            return Ok(CompleteOutcome::Completed(row.into_domain()?));
+        }
+        // Nothing updated: the task is either completed already or gone
+        let query = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM tasks WHERE tenant_id = $1 AND id = $2)",
+        )
+        .bind(tenant.value())
+        .bind(id.value());
+        let exists = run_query(query.fetch_one(&mut *conn), "complete", "task").await?;
+        Ok(if exists { CompleteOutcome::AlreadyCompleted } else { CompleteOutcome::NotFound })
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &TaskId) -> Result<bool, DomainError> {
+        let query = sqlx::query("DELETE FROM tasks WHERE tenant_id = $1 AND id = $2")
+            .bind(tenant.value())
+            .bind(id.value());
+        let mut conn = acquire(&self.pool, "delete", "task").await?;
+        let result = run_query(query.execute(&mut *conn), "delete", "task").await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        let query = sqlx::query_as::<_, TaskRow>(
+            "DELETE FROM tasks WHERE tenant_id = $1 AND id = $2 \
+             RETURNING id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist",
+        )
+        .bind(tenant.value())
+        .bind(id.value());
+        let mut conn = acquire(&self.pool, "delete", "task").await?;
+        let row = run_query(query.fetch_optional(&mut *conn), "delete", "task").await?;
+        row.map(TaskRow::into_domain).transpose()
+    }
+
+    async fn hourly_counts(
+        &self,
+        tenant: &TenantId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<HourlyTaskCounts>, DomainError> {
+        // Buckets of the task_summaries view in migrations/repeatable/, hours in UTC
+        let query = sqlx::query_as::<_, (NaiveDateTime, i64, i64)>(
+            "SELECT hour, created, completed FROM task_summaries \
+             WHERE tenant_id = $2 AND hour >= $1 AT TIME ZONE 'UTC' ORDER BY hour",
+        )
+        .bind(since)
+        .bind(tenant.value());
+        let mut conn = acquire(&self.pool, "count", "task").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "count", "task").await?;
+        Ok(rows
+            .into_iter()
+            .map(|(hour, created, completed)| HourlyTaskCounts {
+                hour: hour.and_utc(),
+                created: u64::try_from(created).unwrap_or_default(),
+                completed: u64::try_from(completed).unwrap_or_default(),
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl UserDependents for PgTaskRepository {
+    async fn count_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<u64, DomainError> {
+        let query = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM tasks WHERE tenant_id = $1 AND user_id = $2",
+        )
+        .bind(tenant.value())
+        .bind(user_id.value());
+        let mut conn = acquire(&self.pool, "count", "task").await?;
+        let count = run_query(query.fetch_one(&mut *conn), "count", "task").await?;
+        Ok(u64::try_from(count).unwrap_or_default())
+    }
+
+    async fn count_by_user_ids(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+    ) -> Result<HashMap<UserId, u64>, DomainError> {
+        let user_ids: Vec<&str> = user_ids.iter().map(UserId::value).collect();
+        let query = sqlx::query_as::<_, (String, i64)>(
+            "SELECT user_id, COUNT(*) FROM tasks WHERE tenant_id = $1 AND user_id = ANY($2) \
+             GROUP BY user_id",
+        )
+        .bind(tenant.value())
+        .bind(user_ids);
+        let mut conn = acquire(&self.pool, "count", "task").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "count", "task").await?;
+        Ok(rows
+            .into_iter()
+            .map(|(user_id, count)| {
+                (UserId::from_trusted(user_id), u64::try_from(count).unwrap_or_default())
+            })
+            .collect())
+    }
+}
+
+/// Query inserting `task` and returning it as persisted, shared with the unit of work
+fn insert_query<'a>(tenant: &'a TenantId, task: &'a Task) -> TaskQuery<'a> {
+    sqlx::query_as::<_, TaskRow>(
+        "INSERT INTO tasks (tenant_id, id, user_id, title, description, checklist, completed, \
+         completed_at) \
+         VALUES ($1, $2, $3, $4, $5, $6::jsonb, $7, \
+         CASE WHEN $7 THEN COALESCE($8, CURRENT_TIMESTAMP) END) \
+         RETURNING id, user_id, title, description, completed, updated_at, \
+         checklist::text AS checklist",
+    )
+    .bind(tenant.value())
+    .bind(task.id().value())
+    .bind(task.user_id().value())
+    .bind(task.title())
+    .bind(task.description())
+    .bind(checklist_json(task))
+    .bind(task.is_completed())
+    .bind(task.completed_at())
+}
+
+/// Query updating `task` and returning it as persisted, or no row if it is gone;
+/// shared with the unit of work
+fn update_query<'a>(tenant: &'a TenantId, task: &'a Task) -> TaskQuery<'a> {
+    sqlx::query_as::<_, TaskRow>(
+        "UPDATE tasks SET title = $1, description = $2, completed = $3, \
+         checklist = $6::jsonb, updated_at = CURRENT_TIMESTAMP, \
+         completed_at = CASE WHEN $3 THEN COALESCE(completed_at, CURRENT_TIMESTAMP) END \
+         WHERE tenant_id = $4 AND id = $5 \
+         RETURNING id, user_id, title, description, completed, updated_at, \
+         checklist::text AS checklist",
+    )
+    .bind(task.title())
+    .bind(task.description())
+    .bind(task.is_completed())
+    .bind(tenant.value())
+    .bind(task.id().value())
+    .bind(checklist_json(task))
+}
+
+/// `task`'s checklist as the JSON stored in `tasks.checklist`
+fn checklist_json(task: &Task) -> String {
+    serde_json::to_string(task.checklist()).unwrap_or_else(|_| "[]".into())
+}
+
+/// `PostgreSQL` unit of work, writing users and tasks in one database transaction
+#[derive(Clone)]
+pub struct PgUnitOfWork {
+    pool: PgPool,
+}
+
+impl PgUnitOfWork {
+    /// Create a unit of work beginning its transactions on `pool`
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl UnitOfWork for PgUnitOfWork {
+    async fn begin(&self) -> Result<Box<dyn Transaction + '_>, DomainError> {
+        let conn = acquire(&self.pool, "begin", "transaction").await?;
+        let tx = run_query(sqlx::Transaction::begin(conn, None), "begin", "transaction").await?;
+        Ok(Box::new(PgTransaction { tx }))
+    }
+}
+
+/// Transaction of [`PgUnitOfWork`]; sqlx rolls it back when dropped uncommitted
+struct PgTransaction {
+    tx: sqlx::Transaction<'static, Postgres>,
+}
+
+#[async_trait::async_trait]
+impl Transaction for PgTransaction {
+    async fn insert_user(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        let query = user_insert_query(tenant, user);
+        run_query(query.execute(&mut *self.tx), "insert", "user").await?;
         Ok(())
     }
 
-    async fn update(&self, task: &Task) -> Result<(), DomainError> {
-        sqlx::query(
-            "UPDATE tasks SET title = $1, description = $2, completed = $3, updated_at = CURRENT_TIMESTAMP WHERE id = $4",
+    async fn find_task_for_update(
+        &mut self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        let query = sqlx::query_as::<_, TaskRow>(
+            "SELECT id, user_id, title, description, completed, updated_at, \
+             checklist::text AS checklist FROM tasks \
+             WHERE tenant_id = $1 AND id = $2 FOR UPDATE",
         )
-        .bind(task.title())
-        .bind(task.description())
-        .bind(task.is_completed())
-        .bind(task.id().value())
-        .execute(&self.pool)
-        .await
-        .map_err(|e| map_db_error(e, "update", "task"))?;
+        .bind(tenant.value())
+        .bind(id.value());
+        let row = run_query(query.fetch_optional(&mut *self.tx), "find", "task").await?;
+        row.map(TaskRow::into_domain).transpose()
+    }
+
+    async fn update_task(&mut self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        let query = update_query(tenant, task);
+        run_query(query.fetch_optional(&mut *self.tx), "update", "task")
+            .await?
+            .ok_or_else(|| DomainError::not_found(TaskId::entity_name()))?
+            .into_domain()
+    }
+
+    async fn insert_task(&mut self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        let query = insert_query(tenant, task);
+        run_query(query.fetch_one(&mut *self.tx), "insert", "task").await?.into_domain()
+    }
+
+    async fn reassign_tasks(
+        &mut self,
+        tenant: &TenantId,
+        from: &UserId,
+        to: &UserId,
+        only_open: bool,
+    ) -> Result<u64, DomainError> {
+        let query = sqlx::query(
+            "UPDATE tasks SET user_id = $1, updated_at = CURRENT_TIMESTAMP \
+             WHERE tenant_id = $2 AND user_id = $3 AND (NOT $4 OR NOT completed)",
+        )
+        .bind(to.value())
+        .bind(tenant.value())
+        .bind(from.value())
+        .bind(only_open);
+        let result = run_query(query.execute(&mut *self.tx), "reassign", "task").await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn record_audit(
+        &mut self,
+        tenant: &TenantId,
+        entry: &AuditEntry,
+    ) -> Result<(), DomainError> {
+        let query = sqlx::query(
+            "INSERT INTO audit_log (tenant_id, action, subject, details, client_ip) \
+             VALUES ($1, $2, $3, $4::jsonb, $5::inet)",
+        )
+        .bind(tenant.value())
+        .bind(entry.action)
+        .bind(&entry.subject)
+        .bind(entry.details.to_string())
+        .bind(entry.client_ip.map(|ip| ip.to_string()));
+        run_query(query.execute(&mut *self.tx), "record", "audit entry").await?;
         Ok(())
     }
 
-    async fn delete(&self, id: &TaskId) -> Result<bool, DomainError> {
-        let result = sqlx::query("DELETE FROM tasks WHERE id = $1")
-            .bind(id.value())
-            .execute(&self.pool)
-            .await
-            .map_err(|e| map_db_error(e, "delete", "task"))?;
-        Ok(result.rows_affected() > 0)
+    async fn record_event(
+        &mut self,
+        tenant: &TenantId,
+        event: &OutboxEvent,
+    ) -> Result<(), DomainError> {
+        let query = sqlx::query(
+            "INSERT INTO outbox_events (tenant_id, event_type, version, subject, payload) \
+             VALUES ($1, $2, $3, $4, $5::jsonb)",
+        )
+        .bind(tenant.value())
+        .bind(event.event_type)
+        .bind(i32::from(event.version))
+        .bind(&event.subject)
+        .bind(event.payload.to_string());
+        run_query(query.execute(&mut *self.tx), "record", "outbox event").await?;
+        Ok(())
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DomainError> {
+        run_query(self.tx.commit(), "commit", "transaction").await
     }
 }
 
@@ -102,17 +526,905 @@ struct TaskRow {
     description: String,
     completed: bool,
     updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    checklist: String,
+}
+
+/// Row returned by [`PgTaskRepository::upsert`]
+#[derive(sqlx::FromRow)]
+struct UpsertedRow {
+    #[sqlx(flatten)]
+    task: TaskRow,
+    inserted: bool,
 }
 
 impl TaskRow {
-    fn into_domain(self) -> Task {
-        Task::reconstitute(
+    /// The stored task; failing rather than guessing when its checklist cannot be
+    /// read
+    fn into_domain(self) -> Result<Task, DomainError> {
+        let checklist = serde_json::from_str(&self.checklist).map_err(|e| {
+            DomainError::Unexpected(format!("Invalid stored checklist of task {}: {e}", self.id))
+        })?;
+        let task = Task::reconstitute(
             TaskId::from_trusted(self.id),
             UserId::from_trusted(self.user_id),
             self.title,
             self.description,
             self.completed,
             self.updated_at,
+        );
+        Ok(task.with_checklist(checklist))
+    }
+}
+
+/// `ORDER BY` clause of `sort`, matching [`TaskSort::compare`]; only these fixed
+/// clauses ever reach the SQL
+fn order_by(sort: TaskSort) -> &'static str {
+    match sort {
+        TaskSort::Id => "id",
+        TaskSort::Title => "lower(title) COLLATE \"C\", id",
+        TaskSort::RecentlyUpdated => "updated_at DESC NULLS LAST, id",
+    }
+}
+
+/// `PostgreSQL` implementation of attachment repository
+///
+/// Deleted rows, also those cascading from a deleted task or user, queue their blob
+/// in `blob_deletions` through a trigger of the `task_attachments` table.
+#[derive(Clone)]
+pub struct PgAttachmentRepository {
+    pool: PgPool,
+}
+
+impl PgAttachmentRepository {
+    /// Create a new `PostgreSQL` attachment repository
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl AttachmentRepository for PgAttachmentRepository {
+    async fn insert(&self, tenant: &TenantId, attachment: &Attachment) -> Result<(), DomainError> {
+        let query = sqlx::query(
+            "INSERT INTO task_attachments \
+             (id, tenant_id, task_id, filename, content_type, size_bytes, storage_key, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(attachment.id().value())
+        .bind(tenant.value())
+        .bind(attachment.task_id().value())
+        .bind(attachment.filename())
+        .bind(attachment.content_type())
+        .bind(i64::try_from(attachment.size()).unwrap_or(i64::MAX))
+        .bind(attachment.storage_key())
+        .bind(attachment.created_at());
+        let mut conn = acquire(&self.pool, "insert", "attachment").await?;
+        run_query(query.execute(&mut *conn), "insert", "attachment").await?;
+        Ok(())
+    }
+
+    async fn find_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<Vec<Attachment>, DomainError> {
+        let query = sqlx::query_as::<_, AttachmentRow>(
+            "SELECT id, task_id, filename, content_type, size_bytes, storage_key, created_at \
+             FROM task_attachments WHERE tenant_id = $1 AND task_id = $2 \
+             ORDER BY created_at, id",
+        )
+        .bind(tenant.value())
+        .bind(task_id.value());
+        let mut conn = acquire(&self.pool, "find", "attachment").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find", "attachment").await?;
+        Ok(rows.into_iter().map(AttachmentRow::into_domain).collect())
+    }
+
+    async fn delete(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+        id: &AttachmentId,
+    ) -> Result<bool, DomainError> {
+        let query = sqlx::query(
+            "DELETE FROM task_attachments WHERE tenant_id = $1 AND task_id = $2 AND id = $3",
+        )
+        .bind(tenant.value())
+        .bind(task_id.value())
+        .bind(id.value());
+        let mut conn = acquire(&self.pool, "delete", "attachment").await?;
+        let result = run_query(query.execute(&mut *conn), "delete", "attachment").await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<u64, DomainError> {
+        let query = sqlx::query("DELETE FROM task_attachments WHERE tenant_id = $1 AND task_id = $2")
+            .bind(tenant.value())
+            .bind(task_id.value());
+        let mut conn = acquire(&self.pool, "delete", "attachment").await?;
+        let result = run_query(query.execute(&mut *conn), "delete", "attachment").await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn queued_blob_deletions(&self, limit: u32) -> Result<Vec<String>, DomainError> {
+        let query = sqlx::query_scalar::<_, String>(
+            "SELECT storage_key FROM blob_deletions ORDER BY enqueued_at, storage_key LIMIT $1",
+        )
+        .bind(i64::from(limit));
+        let mut conn = acquire(&self.pool, "find", "blob deletion").await?;
+        run_query(query.fetch_all(&mut *conn), "find", "blob deletion").await
+    }
+
+    async fn dequeue_blob_deletion(&self, storage_key: &str) -> Result<(), DomainError> {
+        let query =
+            sqlx::query("DELETE FROM blob_deletions WHERE storage_key = $1").bind(storage_key);
+        let mut conn = acquire(&self.pool, "delete", "blob deletion").await?;
+        run_query(query.execute(&mut *conn), "delete", "blob deletion").await?;
+        Ok(())
+    }
+}
+
+/// `PostgreSQL` implementation of task view preferences repository, storing them in
+/// the `task_view` JSONB column of `user_preferences`
+#[derive(Clone)]
+pub struct PgTaskViewPreferencesRepository {
+    pool: PgPool,
+}
+
+impl PgTaskViewPreferencesRepository {
+    /// Create a new `PostgreSQL` task view preferences repository
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskViewPreferencesRepository for PgTaskViewPreferencesRepository {
+    async fn find(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Option<TaskViewPreferences>, DomainError> {
+        let query = sqlx::query_scalar::<_, String>(
+            "SELECT task_view::text FROM user_preferences WHERE tenant_id = $1 AND user_id = $2",
+        )
+        .bind(tenant.value())
+        .bind(user_id.value());
+        let mut conn = acquire(&self.pool, "find", "preferences").await?;
+        let stored = run_query(query.fetch_optional(&mut *conn), "find", "preferences").await?;
+        stored.map(|json| TaskViewRecord::parse(&json)).transpose()
+    }
+
+    async fn save(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        preferences: &TaskViewPreferences,
+    ) -> Result<(), DomainError> {
+        let query = sqlx::query(
+            "INSERT INTO user_preferences (tenant_id, user_id, task_view) \
+             VALUES ($1, $2, $3::jsonb) \
+             ON CONFLICT (tenant_id, user_id) \
+             DO UPDATE SET task_view = EXCLUDED.task_view, updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(tenant.value())
+        .bind(user_id.value())
+        .bind(TaskViewRecord::from(preferences).to_json());
+        let mut conn = acquire(&self.pool, "save", "preferences").await?;
+        run_query(query.execute(&mut *conn), "save", "preferences").await?;
+        Ok(())
+    }
+}
+
+/// Stored form of [`TaskViewPreferences`]
+#[derive(serde::Deserialize)]
+struct TaskViewRecord {
+    sort: String,
+    show_completed: bool,
+    page_size: Option<u32>,
+}
+
+impl From<&TaskViewPreferences> for TaskViewRecord {
+    fn from(preferences: &TaskViewPreferences) -> Self {
+        Self {
+            sort: preferences.sort().name().to_owned(),
+            show_completed: preferences.show_completed(),
+            page_size: preferences.page_size(),
+        }
+    }
+}
+
+impl TaskViewRecord {
+    fn to_json(&self) -> String {
+        serde_json::json!({
+            "sort": self.sort,
+            "show_completed": self.show_completed,
+            "page_size": self.page_size,
+        })
+        .to_string()
+    }
+
+    /// The preferences stored as `json`; failing rather than guessing when they no
+    /// longer pass validation
+    fn parse(json: &str) -> Result<TaskViewPreferences, DomainError> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            DomainError::Unexpected(format!("Invalid stored task view preferences: {e}"))
+        };
+        let record: Self = serde_json::from_str(json).map_err(|e| invalid(&e))?;
+        TaskSort::parse(&record.sort)
+            .and_then(|sort| {
+                TaskViewPreferences::new(sort, record.show_completed, record.page_size)
+            })
+            .map_err(|e| invalid(&e))
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct AttachmentRow {
+    id: String,
+    task_id: String,
+    filename: String,
+    content_type: String,
+    size_bytes: i64,
+    storage_key: String,
+    created_at: DateTime<Utc>,
+}
+
+impl AttachmentRow {
+    fn into_domain(self) -> Attachment {
+        Attachment::reconstitute(
+            AttachmentId::from_trusted(self.id),
+            TaskId::from_trusted(self.task_id),
+            self.filename,
+            self.content_type,
+            u64::try_from(self.size_bytes).unwrap_or_default(),
+            self.storage_key,
+            self.created_at,
+        )
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::application::{OnboardUserCommand, OnboardUserUseCase};
+    use crate::features::task::domain::AttachmentRules;
+    use crate::features::user::domain::UserRepository;
+    use crate::features::user::infrastructure::PgUserRepository;
+    use crate::shared::application::Validated;
+    use crate::shared::infrastructure::email::ConsoleEmailSender;
+    use std::sync::Arc;
+    use crate::shared::infrastructure::database::{
+        run_repeatable_migrations, verify_open_task_title_index, MigrationRetry,
+    };
+    use crate::testing::repository_contract;
+
+    async fn seed_user(pool: &PgPool, id: &str) {
+        sqlx::query("INSERT INTO users (id, name, email) VALUES ($1, $1, $1 || '@example.com')")
+            .bind(id)
+            .execute(pool)
+            .await
+            .expect("seed user");
+    }
+
+    fn task(user_id: &str, title: &str) -> Task {
+        let user_id = UserId::new(user_id).expect("valid user id");
+        Task::new(TaskId::generate(), user_id, title, String::new()).expect("valid task")
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn lists_should_be_ordered_by_id(pool: PgPool) {
+        let users = PgUserRepository::new(pool.clone());
+        repository_contract::tasks_should_list_in_id_order(&users, &PgTaskRepository::new(pool))
+            .await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn updates_of_deleted_tasks_should_fail(pool: PgPool) {
+        let users = PgUserRepository::new(pool.clone());
+        let tasks = PgTaskRepository::new(pool);
+        repository_contract::tasks_should_not_update_deleted_rows(&users, &tasks).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn deletions_should_return_the_deleted_task(pool: PgPool) {
+        let users = PgUserRepository::new(pool.clone());
+        let tasks = PgTaskRepository::new(pool);
+        repository_contract::tasks_should_return_deleted_rows(&users, &tasks).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn pages_should_follow_the_sort_order(pool: PgPool) {
+        let users = PgUserRepository::new(pool.clone());
+        let tasks = PgTaskRepository::new(pool);
+        repository_contract::tasks_should_page_in_sort_order(&users, &tasks).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn checklists_should_round_trip_through_jsonb(pool: PgPool) {
+        let users = PgUserRepository::new(pool.clone());
+        let tasks = PgTaskRepository::new(pool);
+        repository_contract::tasks_should_store_checklists(&users, &tasks).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn tasks_should_be_counted_per_requested_owner_in_one_grouped_query(pool: PgPool) {
+        let users = PgUserRepository::new(pool.clone());
+        let tasks = PgTaskRepository::new(pool);
+        repository_contract::tasks_should_count_by_user_ids(&users, &tasks).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn tasks_inserted_completed_should_keep_their_completion_time(pool: PgPool) {
+        // The contract reads the task_summaries view of the repeatable migrations
+        let retry = MigrationRetry { retries: 0, delay: std::time::Duration::ZERO };
+        run_repeatable_migrations(&pool, retry).await.expect("repeatable migrations");
+        let users = PgUserRepository::new(pool.clone());
+        let tasks = PgTaskRepository::new(pool);
+        repository_contract::tasks_should_insert_completed_at_their_time(&users, &tasks).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn unreadable_stored_checklists_should_fail_the_read(pool: PgPool) {
+        seed_user(&pool, "user1").await;
+        sqlx::query(
+            "INSERT INTO tasks (id, user_id, title, description, checklist) \
+             VALUES ('t1', 'user1', 'Pack', '', '{\"not\": \"a list\"}')",
+        )
+        .execute(&pool)
+        .await
+        .expect("insert task");
+        let repo = PgTaskRepository::new(pool);
+        let id = TaskId::from_trusted("t1".into());
+        let result = repo.find_by_id(&TenantId::default(), &id).await;
+        assert!(matches!(result, Err(DomainError::Unexpected(_))), "{result:?}");
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn saved_preferences_should_replace_earlier_ones(pool: PgPool) {
+        let users = PgUserRepository::new(pool.clone());
+        let preferences = PgTaskViewPreferencesRepository::new(pool);
+        repository_contract::task_view_preferences_should_be_replaced(&users, &preferences).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn preferences_should_be_refused_for_unknown_users_and_removed_with_theirs(
+        pool: PgPool,
+    ) {
+        let tenant = TenantId::default();
+        let preferences = PgTaskViewPreferencesRepository::new(pool.clone());
+        let saved = TaskViewPreferences::new(TaskSort::Title, false, Some(10)).expect("valid");
+        let nobody = UserId::from_trusted("nobody".into());
+        let result = preferences.save(&tenant, &nobody, &saved).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))), "{result:?}");
+
+        seed_user(&pool, "user1").await;
+        let user_id = UserId::from_trusted("user1".into());
+        preferences.save(&tenant, &user_id, &saved).await.expect("save");
+        PgUserRepository::new(pool).delete(&tenant, &user_id).await.expect("delete user");
+        assert!(preferences.find(&tenant, &user_id).await.expect("find").is_none());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn exists_open_with_title_should_match_case_insensitively_and_skip_completed(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        let repo = PgTaskRepository::new(pool);
+        let user_id = UserId::new("user1").expect("valid user id");
+        let mut open = task("user1", "Buy milk");
+        repo.insert(&tenant, &open).await.expect("insert");
+
+        let found =
+            repo.exists_open_with_title(&tenant, &user_id, "BUY MILK").await.expect("query");
+        assert_eq!(found.as_ref(), Some(open.id()));
+
+        open.complete().expect("complete");
+        repo.update(&tenant, &open).await.expect("update");
+        let found =
+            repo.exists_open_with_title(&tenant, &user_id, "Buy milk").await.expect("query");
+        assert!(found.is_none());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn open_title_index_should_reject_racing_duplicates_only_when_enabled(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        let repo = PgTaskRepository::new(pool.clone());
+
+        let apply = async |sql: &str| sqlx::raw_sql(sql).execute(&pool).await.expect("apply");
+        assert!(verify_open_task_title_index(&pool, true).await.is_err());
+        apply(include_str!("../../../../migrations/optional/open_task_title_index.up.sql")).await;
+        verify_open_task_title_index(&pool, true).await.expect("index matches");
+        assert!(verify_open_task_title_index(&pool, false).await.is_err());
+        repo.insert(&tenant, &task("user1", "Buy milk")).await.expect("insert");
+        let duplicate = repo.insert(&tenant, &task("user1", "buy milk")).await;
+        assert!(matches!(duplicate, Err(DomainError::AlreadyExists(_))));
+
+        apply(include_str!("../../../../migrations/optional/open_task_title_index.down.sql")).await;
+        verify_open_task_title_index(&pool, false).await.expect("index matches");
+        assert!(repo.insert(&tenant, &task("user1", "buy milk")).await.is_ok());
+    }
+
+    fn onboard(email: &str) -> Validated<OnboardUserCommand> {
+        Validated::new(OnboardUserCommand {
+            name: "Alice".into(),
+            email: email.into(),
+            task_title: "Getting started".into(),
+            task_description: String::new(),
+            client_ip: None,
+        })
+        .expect("valid command")
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn unit_of_work_should_commit_the_user_with_their_task(pool: PgPool) {
+        let tenant = TenantId::default();
+        let unit_of_work = Arc::new(PgUnitOfWork::new(pool.clone()));
+        let use_case = OnboardUserUseCase::new(unit_of_work, Arc::new(ConsoleEmailSender));
+
+        let onboarded =
+            use_case.execute(&tenant, onboard("alice@example.com")).await.expect("onboarded");
+        assert!(onboarded.task.updated_at().is_some(), "returned as persisted");
+        let tasks = PgTaskRepository::new(pool.clone())
+            .find_by_user_id(&tenant, onboarded.user.id())
+            .await
+            .expect("find");
+        let ids: Vec<_> = tasks.iter().map(Entity::id).collect();
+        assert_eq!(ids, [onboarded.task.id()]);
+        let details: String = sqlx::query_scalar(
+            "SELECT details::text FROM audit_log WHERE action = 'user.onboarded'",
         )
+        .fetch_one(&pool)
+        .await
+        .expect("audit entry");
+        let details: serde_json::Value = serde_json::from_str(&details).expect("json");
+        let user = User::from_snapshot(details["user"].clone()).expect("user snapshot");
+        assert_eq!(user.email(), onboarded.user.email());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn unit_of_work_should_roll_back_the_user_when_the_task_insert_fails(pool: PgPool) {
+        sqlx::raw_sql(
+            "CREATE FUNCTION reject_task() RETURNS trigger AS \
+             $$ BEGIN RAISE EXCEPTION 'task rejected'; END $$ LANGUAGE plpgsql; \
+             CREATE TRIGGER reject_task BEFORE INSERT ON tasks \
+             FOR EACH ROW EXECUTE FUNCTION reject_task();",
+        )
+        .execute(&pool)
+        .await
+        .expect("install trigger");
+        let tenant = TenantId::default();
+        let unit_of_work = Arc::new(PgUnitOfWork::new(pool.clone()));
+        let use_case = OnboardUserUseCase::new(unit_of_work, Arc::new(ConsoleEmailSender));
+
+        let result = use_case.execute(&tenant, onboard("alice@example.com")).await;
+        assert!(matches!(result, Err(DomainError::Infrastructure(_))), "{result:?}");
+        let users = PgUserRepository::new(pool.clone());
+        assert!(users.find_all_unbounded(&tenant).await.expect("users").is_empty());
+        let tasks = PgTaskRepository::new(pool);
+        assert!(tasks.find_all_unbounded(&tenant).await.expect("tasks").is_empty());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn tasks_read_for_update_should_stay_locked_until_the_transaction_ends(pool: PgPool) {
+        let users = PgUserRepository::new(pool.clone());
+        let tasks = PgTaskRepository::new(pool.clone());
+        let unit_of_work = PgUnitOfWork::new(pool);
+        repository_contract::tasks_should_lock_rows_read_for_update(&users, &tasks, &unit_of_work)
+            .await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn reassign_tasks_should_move_open_tasks_with_their_audit_entry(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        seed_user(&pool, "user2").await;
+        let repo = PgTaskRepository::new(pool.clone());
+        let mut done = task("user1", "Done");
+        repo.insert(&tenant, &task("user1", "Open")).await.expect("insert");
+        repo.insert(&tenant, &done).await.expect("insert");
+        done.complete().expect("complete");
+        repo.update(&tenant, &done).await.expect("update");
+        let unit_of_work = PgUnitOfWork::new(pool.clone());
+        let (from, to) = (UserId::new("user1").expect("id"), UserId::new("user2").expect("id"));
+        let entry = AuditEntry {
+            action: "tasks.reassigned",
+            subject: "user1".into(),
+            details: serde_json::json!({"to_user_id": "user2"}),
+            client_ip: Some([203, 0, 113, 5].into()),
+        };
+
+        let mut transaction = unit_of_work.begin().await.expect("begin");
+        let moved = transaction.reassign_tasks(&tenant, &from, &to, false).await;
+        assert_eq!(moved.expect("reassigned"), 2);
+        drop(transaction);
+        assert_eq!(repo.find_by_user_id(&tenant, &from).await.expect("find").len(), 2);
+
+        let mut transaction = unit_of_work.begin().await.expect("begin");
+        let moved = transaction.reassign_tasks(&tenant, &from, &to, true).await;
+        assert_eq!(moved.expect("reassigned"), 1);
+        transaction.record_audit(&tenant, &entry).await.expect("audit");
+        transaction.commit().await.expect("commit");
+        let moved = repo.find_by_user_id(&tenant, &to).await.expect("find");
+        assert_eq!(moved.iter().map(Task::title).collect::<Vec<_>>(), ["Open"]);
+        let (action, details, client_ip): (String, String, String) = sqlx::query_as(
+            "SELECT action, details::text, host(client_ip) FROM audit_log WHERE subject = 'user1'",
+        )
+        .fetch_one(&pool)
+        .await
+        .expect("audit entry");
+        assert_eq!(action, "tasks.reassigned");
+        assert_eq!(details, r#"{"to_user_id": "user2"}"#);
+        assert_eq!(client_ip, "203.0.113.5");
+
+        let mut transaction = unit_of_work.begin().await.expect("begin");
+        let missing = UserId::new("nobody").expect("valid user id");
+        let result = transaction.reassign_tasks(&tenant, &to, &missing, false).await;
+        assert!(matches!(result, Err(DomainError::NotFound(m)) if m == "User not found"));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn insert_and_update_should_return_persisted_row(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        let repo = PgTaskRepository::new(pool);
+
+        let inserted = repo.insert(&tenant, &task("user1", "Buy milk")).await.expect("insert");
+        let inserted_at = inserted.updated_at().expect("database-assigned updated_at");
+
+        let mut completed = inserted.clone();
+        completed.complete().expect("complete");
+        let persisted = repo.update(&tenant, &completed).await.expect("update");
+        assert!(persisted.is_completed());
+        assert!(persisted.updated_at().expect("updated_at") >= inserted_at);
+        let fetched = repo.find_by_id(&tenant, inserted.id()).await.expect("find").expect("exists");
+        assert_eq!(fetched.updated_at(), persisted.updated_at());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn upsert_should_insert_then_update_only_the_owners_task(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        seed_user(&pool, "user2").await;
+        let repo = PgTaskRepository::new(pool);
+        let mut task = task("user1", "Buy milk");
+
+        let created = repo.upsert(&tenant, &task).await.expect("insert");
+        assert!(matches!(created, UpsertOutcome::Created(t) if t.title() == "Buy milk"));
+        repo.complete_if_open(&tenant, task.id()).await.expect("complete");
+        task.edit("Buy bread", "Wholegrain".into()).expect("valid edit");
+        let updated = repo.upsert(&tenant, &task).await.expect("update");
+        assert!(matches!(
+            updated,
+            UpsertOutcome::Updated(t) if t.title() == "Buy bread" && t.is_completed()
+        ));
+
+        let stolen = Task::reconstitute(
+            task.id().clone(),
+            UserId::from_trusted("user2".into()),
+            "Mine now".into(),
+            String::new(),
+            false,
+            None,
+        );
+        let result = repo.upsert(&tenant, &stolen).await;
+        assert!(matches!(result, Err(DomainError::Conflict(_))), "{result:?}");
+        let stored = repo.find_by_id(&tenant, task.id()).await.expect("find").expect("exists");
+        assert_eq!((stored.user_id().value(), stored.title()), ("user1", "Buy bread"));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn find_page_should_filter_then_page_by_id(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        seed_user(&pool, "user2").await;
+        for (id, user, title, description, completed) in [
+            ("a", "user1", "Buy milk", "", false),
+            ("b", "user1", "Walk", "past the MILL", false),
+            ("c", "user1", "Milkshake", "", true),
+            ("d", "user1", "Read", "", false),
+            ("e", "user2", "Buy milk", "", false),
+        ] {
+            sqlx::query(
+                "INSERT INTO tasks (id, user_id, title, description, completed) \
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(id)
+            .bind(user)
+            .bind(title)
+            .bind(description)
+            .bind(completed)
+            .execute(&pool)
+            .await
+            .expect("seed task");
+        }
+        let repo = PgTaskRepository::new(pool);
+        let ids = |tasks: Vec<Task>| -> Vec<String> {
+            tasks.iter().map(|t| t.id().value().to_owned()).collect()
+        };
+        let page = |limit, offset| Page { limit, offset };
+
+        let all = TaskFilter::default();
+        let find = |filter, page| repo.find_page(&tenant, filter, TaskSort::Id, page);
+        assert_eq!(ids(find(&all, page(2, 0)).await.expect("query")), ["a", "b"]);
+        assert_eq!(ids(find(&all, page(2, 4)).await.expect("query")), ["e"]);
+        assert!(find(&all, page(2, 5)).await.expect("query").is_empty());
+        let filter = TaskFilter {
+            user_id: Some(UserId::from_trusted("user1".into())),
+            completed: Some(false),
+            search: Some("mil".into()),
+        };
+        assert_eq!(ids(find(&filter, page(10, 0)).await.expect("query")), ["a", "b"]);
+    }
+
+    /// Resident set size of this process in bytes, on Linux
+    fn resident_bytes() -> u64 {
+        let statm = std::fs::read_to_string("/proc/self/statm").expect("statm");
+        let pages: u64 = statm.split(' ').nth(1).and_then(|p| p.parse().ok()).expect("rss");
+        pages * 4096
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL); seeds 200k tasks"]
+    async fn stream_filtered_should_read_large_exports_in_bounded_memory(pool: PgPool) {
+        const TASKS: i64 = 200_000;
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        // 1 KiB descriptions: holding every task would take over 200 MB
+        sqlx::query(
+            "INSERT INTO tasks (id, user_id, title, description) \
+             SELECT lpad(n::TEXT, 8, '0'), 'user1', 'Task ' || n, repeat('x', 1024) \
+             FROM generate_series(1, $1) AS n",
+        )
+        .bind(TASKS)
+        .execute(&pool)
+        .await
+        .expect("seed tasks");
+        let repo = PgTaskRepository::new(pool);
+
+        let baseline = resident_bytes();
+        let (mut read, mut peak) = (0_i64, baseline);
+        let all = TaskFilter::default();
+        let mut tasks = repo.stream_filtered(&tenant, &all);
+        while let Some(task) = tasks.try_next().await.expect("stream") {
+            assert_eq!(task.description().len(), 1024);
+            read += 1;
+            if read % 10_000 == 0 {
+                peak = peak.max(resident_bytes());
+            }
+        }
+
+        assert_eq!(read, TASKS);
+        let growth = peak.saturating_sub(baseline);
+        assert!(growth < 64 * 1024 * 1024, "resident memory grew by {growth} bytes");
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn update_should_return_not_found_for_missing_row(pool: PgPool) {
+        let tenant = TenantId::default();
+        let repo = PgTaskRepository::new(pool);
+        let result = repo.update(&tenant, &task("user1", "Buy milk")).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn concurrent_complete_if_open_should_succeed_exactly_once(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        let repo = PgTaskRepository::new(pool);
+        let inserted = repo.insert(&tenant, &task("user1", "Buy milk")).await.expect("insert");
+
+        let id = inserted.id();
+        let (first, second) =
+            tokio::join!(repo.complete_if_open(&tenant, id), repo.complete_if_open(&tenant, id));
+        let outcomes = [first.expect("first"), second.expect("second")];
+        let count = |pred: fn(&CompleteOutcome) -> bool| outcomes.iter().filter(|o| pred(o)).count();
+        let completed = count(|o| matches!(o, CompleteOutcome::Completed(_)));
+        let rejected = count(|o| matches!(o, CompleteOutcome::AlreadyCompleted));
+        assert_eq!((completed, rejected), (1, 1));
+
+        let missing = repo.complete_if_open(&tenant, &TaskId::generate()).await.expect("query");
+        assert!(matches!(missing, CompleteOutcome::NotFound));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn count_by_user_id_should_count_only_the_users_tasks(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        seed_user(&pool, "user2").await;
+        let repo = PgTaskRepository::new(pool);
+        for (user, title) in [("user1", "a"), ("user1", "b"), ("user2", "c")] {
+            repo.insert(&tenant, &task(user, title)).await.expect("insert");
+        }
+        let user1 = UserId::new("user1").expect("valid id");
+        assert_eq!(repo.count_by_user_id(&tenant, &user1).await.expect("count"), 2);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn find_recent_by_users_should_limit_and_order_per_user(pool: PgPool) {
+        let tenant = TenantId::default();
+        for user in ["user1", "user2", "user3"] {
+            seed_user(&pool, user).await;
+        }
+        for (id, user, minute) in
+            [("a", "user1", 1), ("b", "user1", 3), ("c", "user1", 2), ("d", "user2", 1)]
+        {
+            sqlx::query(
+                "INSERT INTO tasks (id, user_id, title, description, created_at) \
+                 VALUES ($1, $2, $1, '', \
+                         TIMESTAMPTZ '2026-03-01 09:00Z' + $3 * INTERVAL '1 minute')",
+            )
+            .bind(id)
+            .bind(user)
+            .bind(minute)
+            .execute(&pool)
+            .await
+            .expect("seed task");
+        }
+        let repo = PgTaskRepository::new(pool);
+        let users: Vec<UserId> =
+            ["user1", "user2", "user3"].map(|id| UserId::from_trusted(id.into())).to_vec();
+
+        let ids = |tasks: Vec<Task>| -> Vec<String> {
+            tasks.iter().map(|t| t.id().value().to_owned()).collect()
+        };
+        let recent = repo.find_recent_by_users(&tenant, &users, 2).await.expect("query");
+        assert_eq!(ids(recent), ["b", "c", "d"]);
+        let recent = repo.find_recent_by_users(&tenant, &users[1..], 5).await.expect("query");
+        assert_eq!(ids(recent), ["d"]);
+        assert!(repo.find_recent_by_users(&tenant, &[], 5).await.expect("query").is_empty());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn hourly_counts_should_bucket_by_utc_hour(
+        options: sqlx::postgres::PgPoolOptions,
+        connect: sqlx::postgres::PgConnectOptions,
+    ) {
+        // A non-UTC session time zone must not shift the buckets
+        let connect = connect.options([("timezone", "Asia/Kolkata")]);
+        let pool = options.connect_with(connect).await.expect("pool");
+        // sqlx::test only runs the versioned migrations
+        let retry = MigrationRetry { retries: 0, delay: std::time::Duration::ZERO };
+        run_repeatable_migrations(&pool, retry).await.expect("repeatable migrations");
+        seed_user(&pool, "user1").await;
+        for (id, created_at, completed_at) in [
+            ("early", "2026-03-01 08:59:59.999Z", None),
+            ("a", "2026-03-01 09:00:00Z", Some("2026-03-01 11:00:00Z")),
+            ("b", "2026-03-01 10:00:00Z", None),
+            ("c", "2026-03-01 10:59:59.999Z", Some("2026-03-01 10:59:59.999Z")),
+            ("d", "2026-03-01 07:00:00Z", Some("2026-03-01 11:30:00Z")),
+        ] {
+            sqlx::query(
+                "INSERT INTO tasks (id, user_id, title, description, completed, created_at, \
+                 completed_at) VALUES ($1, 'user1', $1, '', $3::TIMESTAMPTZ IS NOT NULL, \
+                 $2::TIMESTAMPTZ, $3::TIMESTAMPTZ)",
+            )
+            .bind(id)
+            .bind(created_at)
+            .bind(completed_at)
+            .execute(&pool)
+            .await
+            .expect("seed task");
+        }
+        let at = |hour| {
+            chrono::NaiveDate::from_ymd_opt(2026, 3, 1)
+                .and_then(|d| d.and_hms_opt(hour, 0, 0))
+                .expect("valid time")
+                .and_utc()
+        };
+
+        let repo = PgTaskRepository::new(pool);
+        let counts = repo.hourly_counts(&TenantId::default(), at(9)).await.expect("counts");
+        let counts: Vec<_> = counts.iter().map(|c| (c.hour, c.created, c.completed)).collect();
+        assert_eq!(counts, [(at(9), 1, 0), (at(10), 2, 1), (at(11), 0, 2)]);
+        let other = TenantId::new("other").expect("tenant");
+        assert!(repo.hourly_counts(&other, at(9)).await.expect("counts").is_empty());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn tasks_of_another_tenant_should_be_invisible_and_untouchable(pool: PgPool) {
+        let (acme, other) = (TenantId::new("acme").expect("tenant"), TenantId::default());
+        sqlx::query(
+            "INSERT INTO users (tenant_id, id, name, email) \
+             VALUES ($1, 'user1', 'Alice', 'alice@example.com')",
+        )
+        .bind(acme.value())
+        .execute(&pool)
+        .await
+        .expect("seed user");
+        let repo = PgTaskRepository::new(pool);
+        let task = task("user1", "Buy milk");
+        repo.insert(&acme, &task).await.expect("insert");
+
+        assert!(repo.find_by_id(&other, task.id()).await.expect("find").is_none());
+        let page = Page { limit: 10, offset: 0 };
+        let all = TaskFilter::default();
+        let listed = repo.find_page(&other, &all, TaskSort::Id, page).await.expect("page");
+        assert!(listed.is_empty());
+        let outcome = repo.complete_if_open(&other, task.id()).await.expect("complete");
+        assert!(matches!(outcome, CompleteOutcome::NotFound));
+        assert!(!repo.delete(&other, task.id()).await.expect("delete"));
+        let result = repo.upsert(&other, &task).await;
+        assert!(matches!(result, Err(DomainError::Conflict(_))), "{result:?}");
+        // The owner only exists in its own tenant
+        let result = repo.insert(&other, &self::task("user1", "Walk")).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))), "{result:?}");
+
+        let stored = repo.find_by_id(&acme, task.id()).await.expect("find").expect("exists");
+        assert!(!stored.is_completed());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn attachment_deletions_should_queue_their_blobs(pool: PgPool) {
+        let tenant = TenantId::default();
+        seed_user(&pool, "user1").await;
+        let tasks = PgTaskRepository::new(pool.clone());
+        let repo = PgAttachmentRepository::new(pool);
+        let task = task("user1", "Report");
+        tasks.insert(&tenant, &task).await.expect("insert task");
+        let rules = AttachmentRules { max_size_bytes: 100, content_types: vec!["text/plain".into()] };
+        let attach = |name: &str| {
+            Attachment::new(&tenant, task.id().clone(), name, "text/plain", 1, &rules)
+                .expect("valid attachment")
+        };
+        let (first, second) = (attach("a.txt"), attach("b.txt"));
+        repo.insert(&tenant, &first).await.expect("insert");
+        repo.insert(&tenant, &second).await.expect("insert");
+        let listed = repo.find_by_task(&tenant, task.id()).await.expect("find");
+        let ids: Vec<_> = listed.iter().map(Attachment::id).collect();
+        assert_eq!(ids, [first.id(), second.id()]);
+        assert_eq!((listed[0].filename(), listed[0].size()), ("a.txt", 1));
+
+        let other = TenantId::new("other").expect("tenant");
+        assert!(repo.find_by_task(&other, task.id()).await.expect("find").is_empty());
+        assert!(!repo.delete(&other, task.id(), first.id()).await.expect("delete"));
+        let orphan = Attachment::new(&other, task.id().clone(), "c.txt", "text/plain", 1, &rules)
+            .expect("valid attachment");
+        let result = repo.insert(&other, &orphan).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))), "{result:?}");
+
+        assert!(repo.delete(&tenant, task.id(), first.id()).await.expect("delete"));
+        let queued = repo.queued_blob_deletions(10).await.expect("queue");
+        assert_eq!(queued, [first.storage_key()]);
+        repo.dequeue_blob_deletion(first.storage_key()).await.expect("dequeue");
+
+        // Deleting the task cascades to its attachments, queueing their blobs too
+        assert!(tasks.delete(&tenant, task.id()).await.expect("delete task"));
+        let queued = repo.queued_blob_deletions(10).await.expect("queue");
+        assert_eq!(queued, [second.storage_key()]);
+        assert_eq!(repo.delete_by_task(&tenant, task.id()).await.expect("delete"), 0);
     }
 }
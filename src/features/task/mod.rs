@@ -3,3 +3,11 @@
 pub mod application;
 pub mod domain;
 pub mod infrastructure;
+pub mod state;
+
+pub use state::{AttachmentSettings, DynTaskState, OnboardingSettings, TaskState};
+
+/// Feature name used in `ENABLED_FEATURES` / `DISABLED_FEATURES`
+pub const NAME: &str = "task";
+/// Features that must be enabled for this one to work (tasks reference users)
+pub const DEPENDS_ON: &[&str] = &[super::user::NAME];
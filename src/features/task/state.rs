@@ -0,0 +1,180 @@
+//! Task feature state shared across handlers
+
+use crate::features::task::application::{
+    AddChecklistItemUseCase, CompleteTaskUseCase, CreateAttachmentUseCase, CreateTaskUseCase,
+    DeleteAttachmentUseCase, DeleteTaskUseCase, DescriptionRenderer, GetTaskUseCase,
+    GetTaskViewPreferencesUseCase, ImportTasksUseCase, ListAttachmentsUseCase, ListTasksUseCase,
+    ListTasksWithOwnersUseCase, ListUserTasksUseCase, OnboardUserUseCase, ReassignTasksUseCase,
+    RemoveChecklistItemUseCase, RenderTaskDescriptionUseCase, SetTaskViewPreferencesUseCase,
+    TaskStatsQuery, ToggleChecklistItemUseCase, UpsertTaskUseCase, UserOverviewQuery,
+};
+use crate::features::task::domain::{
+    AttachmentRepository, BlobStorage, TaskRepository, TaskViewPreferencesRepository, UnitOfWork,
+};
+use crate::features::user::domain::UserRepository;
+use crate::shared::application::{DetachedWrites, FeatureToggles};
+use crate::shared::domain::EmailSender;
+use crate::shared::infrastructure::config::Config;
+use std::sync::Arc;
+
+/// Use cases of the task feature, generic over the task and user repositories
+///
+/// Wiring concrete repositories dispatches their calls statically; [`DynTaskState`],
+/// the default, takes any repositories behind `Arc<dyn ..>`.
+pub struct TaskState<T: ?Sized = dyn TaskRepository, U: ?Sized = dyn UserRepository> {
+    pub(crate) create_task: CreateTaskUseCase<T>,
+    pub(crate) upsert_task: UpsertTaskUseCase<T>,
+    pub(crate) get_task: GetTaskUseCase<T>,
+    pub(crate) list_tasks: ListTasksUseCase<T>,
+    pub(crate) list_tasks_with_owners: ListTasksWithOwnersUseCase<T, U>,
+    pub(crate) render_description: RenderTaskDescriptionUseCase<T>,
+    pub(crate) complete_task: CompleteTaskUseCase<T>,
+    pub(crate) add_checklist_item: AddChecklistItemUseCase<T>,
+    pub(crate) toggle_checklist_item: ToggleChecklistItemUseCase<T>,
+    pub(crate) remove_checklist_item: RemoveChecklistItemUseCase<T>,
+    pub(crate) delete_task: DeleteTaskUseCase<T>,
+    pub(crate) task_stats: TaskStatsQuery<T>,
+    pub(crate) user_overview: UserOverviewQuery<T, U>,
+    pub(crate) onboard_user: OnboardUserUseCase,
+    pub(crate) reassign_tasks: ReassignTasksUseCase<T, U>,
+    pub(crate) import_tasks: ImportTasksUseCase<T, U>,
+    pub(crate) create_attachment: CreateAttachmentUseCase<T>,
+    pub(crate) list_attachments: ListAttachmentsUseCase<T>,
+    pub(crate) delete_attachment: DeleteAttachmentUseCase,
+    pub(crate) list_user_tasks: ListUserTasksUseCase<T, U>,
+    pub(crate) get_task_view_preferences: GetTaskViewPreferencesUseCase<U>,
+    pub(crate) set_task_view_preferences: SetTaskViewPreferencesUseCase<U>,
+    /// Rollouts the use cases choose their code paths by, served at `/admin/flags`
+    pub(crate) feature_toggles: Arc<FeatureToggles>,
+    /// Runs the writes that outlive dropped requests
+    pub(crate) detached_writes: DetachedWrites,
+}
+
+/// [`TaskState`] wired to repositories behind `Arc<dyn TaskRepository>` and
+/// `Arc<dyn UserRepository>`
+pub type DynTaskState = TaskState;
+
+/// Where task attachments are recorded and their bytes stored
+pub struct AttachmentSettings {
+    /// Storage of attachment metadata
+    pub repository: Arc<dyn AttachmentRepository>,
+    /// Storage of attachment bytes
+    pub storage: Arc<dyn BlobStorage>,
+}
+
+/// How users are onboarded together with their first task
+pub struct OnboardingSettings {
+    /// Writes the user and the task in one transaction; also moves tasks between
+    /// users together with their audit entry, writes imported tasks and locks the
+    /// tasks completed by reading them
+    pub unit_of_work: Arc<dyn UnitOfWork>,
+    /// Welcomes onboarded users, as the user feature welcomes the users it creates
+    pub email_sender: Arc<dyn EmailSender>,
+}
+
+impl<T, U> TaskState<T, U>
+where
+    T: TaskRepository + ?Sized + 'static,
+    U: UserRepository + ?Sized + 'static,
+{
+    /// Wire every task use case to the given repositories
+    ///
+    /// The user repository backs read models that embed task owners; `preferences`
+    /// stores how each user's task listing is shown by default, `toggles` how far
+    /// new code paths are rolled out, and deletions run on `detached_writes`.
+    #[expect(clippy::too_many_arguments, reason = "one argument per dependency")]
+    pub fn new(
+        config: &Config,
+        repository: &Arc<T>,
+        user_repository: &Arc<U>,
+        renderer: Arc<dyn DescriptionRenderer>,
+        attachments: AttachmentSettings,
+        preferences: Arc<dyn TaskViewPreferencesRepository>,
+        onboarding: OnboardingSettings,
+        toggles: &Arc<FeatureToggles>,
+        detached_writes: &DetachedWrites,
+    ) -> Self {
+        Self {
+            feature_toggles: Arc::clone(toggles),
+            detached_writes: detached_writes.clone(),
+            create_task: CreateTaskUseCase::new(
+                Arc::clone(repository),
+                config.prevent_duplicate_open_tasks,
+            ),
+            upsert_task: UpsertTaskUseCase::new(
+                Arc::clone(repository),
+                config.prevent_duplicate_open_tasks,
+            ),
+            get_task: GetTaskUseCase::new(Arc::clone(repository)),
+            list_tasks: ListTasksUseCase::new(Arc::clone(repository), config.page_limits()),
+            list_tasks_with_owners: ListTasksWithOwnersUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(user_repository),
+                config.page_limits(),
+            ),
+            render_description: RenderTaskDescriptionUseCase::new(Arc::clone(repository), renderer),
+            complete_task: CompleteTaskUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(&onboarding.unit_of_work),
+                config.require_checked_checklist,
+                Arc::clone(toggles),
+            ),
+            add_checklist_item: AddChecklistItemUseCase::new(Arc::clone(repository)),
+            toggle_checklist_item: ToggleChecklistItemUseCase::new(Arc::clone(repository)),
+            remove_checklist_item: RemoveChecklistItemUseCase::new(Arc::clone(repository)),
+            delete_task: DeleteTaskUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(&attachments.repository),
+                detached_writes.clone(),
+            ),
+            task_stats: TaskStatsQuery::new(Arc::clone(repository)),
+            user_overview: UserOverviewQuery::new(
+                Arc::clone(user_repository),
+                Arc::clone(repository),
+                config.page_limits(),
+            ),
+            reassign_tasks: ReassignTasksUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(user_repository),
+                Arc::clone(&onboarding.unit_of_work),
+            ),
+            import_tasks: ImportTasksUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(user_repository),
+                Arc::clone(&onboarding.unit_of_work),
+                config.import_limits(),
+                config.prevent_duplicate_open_tasks,
+            ),
+            onboard_user: OnboardUserUseCase::new(
+                onboarding.unit_of_work,
+                onboarding.email_sender,
+            ),
+            create_attachment: CreateAttachmentUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(&attachments.repository),
+                Arc::clone(&attachments.storage),
+                config.attachment_rules(),
+            ),
+            list_attachments: ListAttachmentsUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(&attachments.repository),
+                attachments.storage,
+            ),
+            delete_attachment: DeleteAttachmentUseCase::new(attachments.repository),
+            list_user_tasks: ListUserTasksUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(user_repository),
+                Arc::clone(&preferences),
+                config.page_limits(),
+            ),
+            get_task_view_preferences: GetTaskViewPreferencesUseCase::new(
+                Arc::clone(user_repository),
+                Arc::clone(&preferences),
+            ),
+            set_task_view_preferences: SetTaskViewPreferencesUseCase::new(
+                Arc::clone(user_repository),
+                preferences,
+            ),
+        }
+    }
+}
@@ -1,9 +1,15 @@
 //! Create user use case
 
 use crate::features::user::domain::{User, UserId, UserRepository};
-use crate::shared::domain::DomainError;
+use crate::shared::application::{Validate, Validated, ValidationErrors};
+use crate::shared::domain::{
+    validation_message, DomainError, Email, EmailSender, Entity, TenantId, CANNOT_BE_EMPTY,
+};
 use std::sync::Arc;
 
+/// Subject of the email welcoming a new user
+pub const WELCOME_SUBJECT: &str = "Welcome aboard";
+
 /// Command to create a new user
 #[derive(Debug)]
 pub struct CreateUserCommand {
@@ -13,20 +19,139 @@ pub struct CreateUserCommand {
     pub email: String,
 }
 
+impl Validate for CreateUserCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        validate_profile(&self.name, &self.email)
+    }
+}
+
+/// Rules shared by the commands setting a user's name and email
+pub(crate) fn validate_profile(name: &str, email: &str) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+    check_profile(&mut errors, name, email);
+    errors.into_result()
+}
+
+/// Record the rules of [`validate_profile`] that `name` and `email` break in `errors`
+pub(crate) fn check_profile(errors: &mut ValidationErrors, name: &str, email: &str) {
+    if name.is_empty() {
+        errors.add("name", validation_message("Name", CANNOT_BE_EMPTY));
+    }
+    errors.check("email", Email::new(email));
+}
+
 /// Use case for creating a user
-pub struct CreateUserUseCase {
-    repository: Arc<dyn UserRepository>,
+pub struct CreateUserUseCase<U: ?Sized = dyn UserRepository> {
+    repository: Arc<U>,
+    email_sender: Arc<dyn EmailSender>,
 }
 
-impl CreateUserUseCase {
-    /// Create a new use case instance
-    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
-        Self { repository }
+impl<U: UserRepository + ?Sized> CreateUserUseCase<U> {
+    /// Create a new use case instance welcoming new users through `email_sender`
+    pub fn new(repository: Arc<U>, email_sender: Arc<dyn EmailSender>) -> Self {
+        Self { repository, email_sender }
     }
 
-    pub async fn execute(&self, command: CreateUserCommand) -> Result<User, DomainError> {
+    /// Create a user of `tenant` with a generated ID, then welcome them by email
+    ///
+    /// The welcome email is sent in the background: failing to send it is logged and
+    /// never fails the creation.
+    ///
+    /// # Errors
+    /// `AlreadyExists` if the email is taken within the tenant.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        command: Validated<CreateUserCommand>,
+    ) -> Result<User, DomainError> {
+        let command = command.into_inner();
         let user = User::new(UserId::generate(), command.name, &command.email)?;
-        self.repository.insert(&user).await?;
+        self.repository.insert(tenant, &user).await?;
+        welcome(&self.email_sender, &user);
         Ok(user)
     }
 }
+
+/// Welcome the newly created `user` by email in the background, logging a failure
+pub(crate) fn welcome(email_sender: &Arc<dyn EmailSender>, user: &User) {
+    let sender = Arc::clone(email_sender);
+    let (user_id, to) = (user.id().clone(), user.email().clone());
+    let body = format!("Hi {},\n\nyour account is ready.\n", user.name());
+    // Detached from the request span, which would otherwise stay open until sent
+    tokio::spawn(async move {
+        if let Err(e) = sender.send(&to, WELCOME_SUBJECT, &body).await {
+            tracing::warn!(user_id = user_id.value(), "Welcome email not sent: {e}");
+        }
+    });
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::testing::RecordingEmailSender;
+
+    fn command(name: &str, email: &str) -> Validated<CreateUserCommand> {
+        let command = CreateUserCommand { name: name.into(), email: email.into() };
+        Validated::new(command).expect("valid command")
+    }
+
+    #[test]
+    fn validate_should_report_every_invalid_field() {
+        let command = CreateUserCommand { name: String::new(), email: "invalid".into() };
+        let errors = command.validate().err().unwrap_or_default();
+        let fields: Vec<_> =
+            errors.fields().iter().map(|e| (e.field, e.message.as_str())).collect();
+        assert_eq!(fields, [("name", "Name cannot be empty"), ("email", "Invalid email format")]);
+
+        let command = CreateUserCommand { name: "Alice".into(), email: "alice@example.com".into() };
+        assert!(command.validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn execute_should_welcome_each_new_user_by_email() {
+        let sender = Arc::new(RecordingEmailSender::default());
+        let use_case = CreateUserUseCase::new(
+            Arc::new(InMemoryUserRepository::default()),
+            Arc::clone(&sender) as _,
+        );
+        let tenant = TenantId::default();
+        use_case.execute(&tenant, command("Alice", "alice@example.com")).await.expect("created");
+        use_case.execute(&tenant, command("Bob", "bob@example.com")).await.expect("created");
+        let taken = use_case.execute(&tenant, command("Eve", "bob@example.com")).await;
+        assert!(matches!(taken, Err(DomainError::AlreadyExists(_))), "{taken:?}");
+
+        let mut sent: Vec<_> =
+            sender.wait_for(2).await.into_iter().map(|e| (e.to, e.subject)).collect();
+        sent.sort();
+        assert_eq!(
+            sent,
+            [
+                ("alice@example.com".to_owned(), WELCOME_SUBJECT.to_owned()),
+                ("bob@example.com".to_owned(), WELCOME_SUBJECT.to_owned()),
+            ]
+        );
+        assert_eq!(sender.attempts(), 2);
+    }
+
+    #[tokio::test]
+    async fn execute_should_create_the_user_when_the_welcome_email_fails() {
+        let sender = Arc::new(RecordingEmailSender::default());
+        sender.fail("send", || DomainError::Unavailable("relay down".into()));
+        let use_case = CreateUserUseCase::new(
+            Arc::new(InMemoryUserRepository::default()),
+            Arc::clone(&sender) as _,
+        );
+
+        let user = use_case
+            .execute(&TenantId::default(), command("Alice", "alice@example.com"))
+            .await
+            .expect("created despite the email");
+        assert_eq!(user.name(), "Alice");
+        while sender.attempts() == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert!(sender.sent().is_empty());
+    }
+}
@@ -1,29 +1,327 @@
 //! Delete user use case
 
-use crate::features::user::domain::{UserId, UserRepository};
-use crate::shared::domain::DomainError;
+use crate::features::user::domain::{
+    has_dependents, User, UserDependents, UserId, UserRepository,
+};
+use crate::shared::application::DetachedWrites;
+use crate::shared::domain::{DomainError, TenantId};
 use std::sync::Arc;
 
+/// Options of a user deletion
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeleteUserOptions {
+    /// Delete even when the user still owns tasks (which are cascade-deleted)
+    pub force: bool,
+    /// Only report what would be deleted; nothing is written
+    pub dry_run: bool,
+}
+
+/// Entities removed by a user deletion (or that would be, for a dry run)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeletionImpact {
+    /// Deleted users
+    pub users: u64,
+    /// Cascade-deleted tasks
+    pub tasks: u64,
+}
+
 /// Use case for deleting a user
-pub struct DeleteUserUseCase {
-    repository: Arc<dyn UserRepository>,
+pub struct DeleteUserUseCase<U: ?Sized = dyn UserRepository> {
+    repository: Arc<U>,
+    dependents: Option<Arc<dyn UserDependents>>,
+    /// Told of deletions without being counted, such as caches of other features
+    forgetting: Vec<Arc<dyn UserDependents>>,
+    detached: DetachedWrites,
 }
 
-impl DeleteUserUseCase {
-    /// Create a new use case instance
-    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
-        Self { repository }
+impl<U: UserRepository + ?Sized + 'static> DeleteUserUseCase<U> {
+    /// Create a new use case instance; `dependents` counts the user's tasks, if the
+    /// task feature is enabled, and deletions run on `detached`
+    pub fn new(
+        repository: Arc<U>,
+        dependents: Option<Arc<dyn UserDependents>>,
+        detached: DetachedWrites,
+    ) -> Self {
+        Self { repository, dependents, forgetting: Vec::new(), detached }
+    }
+
+    /// Also let `dependents` forget the entities of deleted users, which deleting a
+    /// user cascades to without them being counted
+    #[must_use]
+    pub fn also_forgetting(mut self, dependents: Arc<dyn UserDependents>) -> Self {
+        self.forgetting.push(dependents);
+        self
     }
 
+    /// Delete the user of `tenant` with `id`
+    ///
     /// Note: deleting a user will cascade-delete all their tasks
-    /// (enforced by `ON DELETE CASCADE` on the tasks FK constraint).
-    pub async fn execute(&self, id: &str) -> Result<(), DomainError> {
+    /// (enforced by `ON DELETE CASCADE` on the tasks FK constraint), so a user
+    /// owning tasks is only deleted with `options.force`.
+    ///
+    /// With `options.dry_run` the same checks run on read-only count queries and the
+    /// impact is returned without deleting anything.
+    ///
+    /// The deletion and the dependents forgetting the user's entities run detached,
+    /// so a dropped request cannot leave the entities of a deleted user cached.
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `HAS_DEPENDENTS` if the user owns tasks and
+    /// `force` is not set, `NotFound` if the user doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+        options: DeleteUserOptions,
+    ) -> Result<DeletionImpact, DomainError> {
+        let user_id = UserId::new(id)?;
+        let tasks = self.check_dependents(tenant, &user_id, options).await?;
+
+        let found = if options.dry_run {
+            self.repository.find_by_id(tenant, &user_id).await?.is_some()
+        } else {
+            let (users, dependents) = (Arc::clone(&self.repository), self.all_dependents());
+            let (tenant, user_id) = (tenant.clone(), user_id.clone());
+            self.detached.run(async move {
+                let found = users.delete(&tenant, &user_id).await?;
+                if found {
+                    user_deleted(&dependents, &tenant, &user_id).await?;
+                }
+                Ok(found)
+            })
+            .await?
+        };
+        if !found {
+            return Err(DomainError::not_found(UserId::entity_name()));
+        }
+
+        Ok(DeletionImpact { users: 1, tasks })
+    }
+
+    /// [`execute`](Self::execute), also returning the user as it was deleted (as it
+    /// is, for a dry run)
+    ///
+    /// The user is read by the deletion itself, so no concurrent update can slip in
+    /// between.
+    ///
+    /// # Errors
+    /// As [`execute`](Self::execute).
+    pub async fn execute_returning(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+        options: DeleteUserOptions,
+    ) -> Result<(DeletionImpact, User), DomainError> {
         let user_id = UserId::new(id)?;
+        let tasks = self.check_dependents(tenant, &user_id, options).await?;
 
-        if !self.repository.delete(&user_id).await? {
-            return Err(DomainError::NotFound("User not found".into()));
+        let user = if options.dry_run {
+            self.repository.find_by_id(tenant, &user_id).await?
+        } else {
+            let (users, dependents) = (Arc::clone(&self.repository), self.all_dependents());
+            let (tenant, user_id) = (tenant.clone(), user_id.clone());
+            self.detached.run(async move {
+                let user = users.delete_returning(&tenant, &user_id).await?;
+                if user.is_some() {
+                    user_deleted(&dependents, &tenant, &user_id).await?;
+                }
+                Ok(user)
+            })
+            .await?
+        };
+        let user = user.ok_or_else(|| DomainError::not_found(UserId::entity_name()))?;
+
+        Ok((DeletionImpact { users: 1, tasks }, user))
+    }
+
+    /// Every dependent to tell of a deletion, counted or not
+    fn all_dependents(&self) -> Vec<Arc<dyn UserDependents>> {
+        self.dependents.iter().chain(&self.forgetting).cloned().collect()
+    }
+
+    /// Count the tasks deleting the user would cascade to, refused without
+    /// `options.force` when there are any
+    async fn check_dependents(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        options: DeleteUserOptions,
+    ) -> Result<u64, DomainError> {
+        let tasks = match &self.dependents {
+            Some(dependents) => dependents.count_by_user_id(tenant, user_id).await?,
+            None => 0,
+        };
+        if tasks > 0 {
+            if !options.force {
+                let message = format!("User owns {tasks} tasks; pass force=true to delete them too");
+                return Err(has_dependents(tasks, message));
+            }
+            if !options.dry_run {
+                tracing::info!("Force-deleting user {} with {tasks} tasks", user_id.value());
+            }
         }
+        Ok(tasks)
+    }
+}
+
+/// Let each of `dependents` forget the entities of the deleted user `user_id`
+async fn user_deleted(
+    dependents: &[Arc<dyn UserDependents>],
+    tenant: &TenantId,
+    user_id: &UserId,
+) -> Result<(), DomainError> {
+    for dependents in dependents {
+        dependents.user_deleted(tenant, user_id).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::{Task, TaskId, TaskRepository};
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::features::user::domain::HAS_DEPENDENTS;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::Entity;
+    use crate::testing::{CancellationSafety, FakeUserRepository};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    async fn setup(tasks: usize) -> (DeleteUserUseCase, Arc<InMemoryUserRepository>, UserId) {
+        let tenant = TenantId::default();
+        let users = Arc::new(InMemoryUserRepository::default());
+        let user =
+            User::new(UserId::generate(), "Alice".into(), "alice@example.com").expect("valid user");
+        users.insert(&tenant, &user).await.expect("insert user");
+        let task_repository = Arc::new(InMemoryTaskRepository::default());
+        for i in 0..tasks {
+            let task =
+                Task::new(TaskId::generate(), user.id().clone(), &format!("t{i}"), String::new())
+                    .expect("valid task");
+            task_repository.insert(&tenant, &task).await.expect("insert task");
+        }
+        let use_case = DeleteUserUseCase::new(
+            Arc::clone(&users) as _,
+            Some(task_repository as _),
+            DetachedWrites::default(),
+        );
+        (use_case, users, user.id().clone())
+    }
+
+    #[tokio::test]
+    async fn execute_should_refuse_user_with_tasks_without_force() {
+        let tenant = TenantId::default();
+        let (use_case, users, id) = setup(2).await;
+        let result = use_case.execute(&tenant, id.value(), DeleteUserOptions::default()).await;
+        assert!(matches!(result, Err(DomainError::Custom { code: HAS_DEPENDENTS, details, .. }) if details["count"] == 2));
+        assert!(users.find_by_id(&tenant, &id).await.expect("find").is_some());
+    }
+
+    #[tokio::test]
+    async fn execute_should_delete_user_with_tasks_when_forced() {
+        let tenant = TenantId::default();
+        let (use_case, users, id) = setup(2).await;
+        let options = DeleteUserOptions { force: true, ..DeleteUserOptions::default() };
+        let result = use_case.execute(&tenant, id.value(), options).await;
+        assert_eq!(result.ok(), Some(DeletionImpact { users: 1, tasks: 2 }));
+        assert!(users.find_by_id(&tenant, &id).await.expect("find").is_none());
+    }
+
+    #[tokio::test]
+    async fn dry_run_should_report_impact_without_deleting() {
+        let tenant = TenantId::default();
+        let (use_case, users, id) = setup(3).await;
+        let options = DeleteUserOptions { force: true, dry_run: true };
+        let result = use_case.execute(&tenant, id.value(), options).await;
+        assert_eq!(result.ok(), Some(DeletionImpact { users: 1, tasks: 3 }));
+        assert!(users.find_by_id(&tenant, &id).await.expect("find").is_some());
+
+        // The same checks apply as for a real deletion
+        let options = DeleteUserOptions { dry_run: true, ..DeleteUserOptions::default() };
+        let result = use_case.execute(&tenant, id.value(), options).await;
+        assert!(matches!(result, Err(DomainError::Custom { code: HAS_DEPENDENTS, details, .. }) if details["count"] == 3));
+        let result = use_case.execute(&tenant, "missing", options).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn execute_should_delete_user_without_tasks_without_force() {
+        let tenant = TenantId::default();
+        let (use_case, users, id) = setup(0).await;
+        assert!(use_case.execute(&tenant, id.value(), DeleteUserOptions::default()).await.is_ok());
+        assert!(users.find_by_id(&tenant, &id).await.expect("find").is_none());
+    }
+
+    #[tokio::test]
+    async fn execute_returning_should_return_the_deleted_user() {
+        let tenant = TenantId::default();
+        let (use_case, users, id) = setup(1).await;
+        let options = DeleteUserOptions { force: true, ..DeleteUserOptions::default() };
+        let (impact, user) =
+            use_case.execute_returning(&tenant, id.value(), options).await.expect("deleted");
+        assert_eq!(impact, DeletionImpact { users: 1, tasks: 1 });
+        assert_eq!((user.id(), user.name()), (&id, "Alice"));
+        assert!(users.find_by_id(&tenant, &id).await.expect("find").is_none());
+
+        let result = use_case.execute_returning(&tenant, id.value(), options).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))));
+    }
+
+    /// Dependents recording the users they are told were deleted, after a while
+    #[derive(Default)]
+    struct Forgetting(Mutex<Vec<UserId>>);
+
+    #[async_trait::async_trait]
+    impl UserDependents for Forgetting {
+        async fn count_by_user_id(&self, _: &TenantId, _: &UserId) -> Result<u64, DomainError> {
+            Ok(0)
+        }
+
+        async fn count_by_user_ids(
+            &self,
+            _: &TenantId,
+            _: &[UserId],
+        ) -> Result<HashMap<UserId, u64>, DomainError> {
+            Ok(HashMap::new())
+        }
+
+        async fn user_deleted(&self, _: &TenantId, user_id: &UserId) -> Result<(), DomainError> {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.0.lock().expect("lock").push(user_id.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_dropped_deletion_should_still_let_dependents_forget_the_user() {
+        let tenant = TenantId::default();
+        let points = CancellationSafety::at_every_point(async |harness| {
+            let users = Arc::new(FakeUserRepository::default());
+            users.slow("delete", Duration::from_millis(10));
+            let user = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+                .expect("valid user");
+            users.insert(&tenant, &user).await.expect("insert user");
+            let dependents = Arc::new(Forgetting::default());
+            let detached = DetachedWrites::default();
+            let use_case = DeleteUserUseCase::new(
+                Arc::clone(&users),
+                Some(Arc::clone(&dependents) as _),
+                detached.clone(),
+            );
 
-        Ok(())
+            let options = DeleteUserOptions::default();
+            let deletion = use_case.execute(&tenant, user.id().value(), options);
+            let finished = harness.run(deletion).await.is_some();
+            detached.drained().await;
+            let deleted = users.find_by_id(&tenant, user.id()).await.expect("find").is_none();
+            let forgotten = dependents.0.lock().expect("lock").clone();
+            assert_eq!(forgotten, if deleted { vec![user.id().clone()] } else { Vec::new() });
+            finished
+        })
+        .await;
+        assert!(points > 0);
     }
 }
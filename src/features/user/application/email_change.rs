@@ -0,0 +1,383 @@
+//! Email change use cases: request a change, then confirm it with the issued token
+
+use crate::features::user::domain::email_change::{generate_token, hash_token};
+use crate::features::user::domain::{
+    EmailChangeNotifier, EmailChangeRepository, PendingEmailChange, User, UserId, UserRepository,
+};
+use crate::shared::application::{DetachedWrites, Validate, Validated, ValidationErrors};
+use crate::shared::domain::{DomainError, Email, TenantId, CANNOT_BE_EMPTY};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Command to request changing a user's email
+#[derive(Debug)]
+pub struct RequestEmailChangeCommand {
+    /// Address to change to
+    pub new_email: String,
+}
+
+impl Validate for RequestEmailChangeCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.check("new_email", Email::new(&self.new_email));
+        errors.into_result()
+    }
+}
+
+/// A requested email change, with the token that confirms it
+#[derive(Debug)]
+pub struct IssuedEmailChange {
+    /// Confirmation token; only its hash is stored
+    pub token: String,
+    /// End of the confirmation window
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Use case for requesting an email change
+pub struct RequestEmailChangeUseCase<U: ?Sized = dyn UserRepository> {
+    users: Arc<U>,
+    changes: Arc<dyn EmailChangeRepository>,
+    notifier: Option<Arc<dyn EmailChangeNotifier>>,
+    token_ttl: Duration,
+}
+
+impl<U: UserRepository + ?Sized> RequestEmailChangeUseCase<U> {
+    /// Create a new use case instance issuing tokens valid for `token_ttl`, delivered
+    /// through `notifier` when there is one
+    pub fn new(
+        users: Arc<U>,
+        changes: Arc<dyn EmailChangeRepository>,
+        notifier: Option<Arc<dyn EmailChangeNotifier>>,
+        token_ttl: Duration,
+    ) -> Self {
+        Self { users, changes, notifier, token_ttl }
+    }
+
+    /// Issue a token changing the email of the user of `tenant` with `id`, replacing
+    /// any earlier unconfirmed request of that user
+    ///
+    /// The address is not checked for uniqueness here; that happens on confirmation.
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID or the current address, `NotFound` if the user
+    /// doesn't exist, and whatever the notifier fails with.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+        command: Validated<RequestEmailChangeCommand>,
+    ) -> Result<IssuedEmailChange, DomainError> {
+        let user_id = UserId::new(id)?;
+        let new_email = Email::new(&command.into_inner().new_email)?;
+        let user =
+            self.users.find_by_id(tenant, &user_id).await?.ok_or_else(|| {
+                DomainError::not_found(UserId::entity_name())
+            })?;
+        if *user.email() == new_email {
+            return Err(DomainError::Validation(
+                "New email must differ from the current one".into(),
+            ));
+        }
+
+        let token = generate_token();
+        let now = Utc::now();
+        let expires_at = TimeDelta::from_std(self.token_ttl)
+            .ok()
+            .and_then(|ttl| now.checked_add_signed(ttl))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let change = PendingEmailChange::new(user_id, new_email, hash_token(&token), expires_at);
+        self.changes.save(&change).await?;
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(&change, &token).await?;
+        }
+        Ok(IssuedEmailChange { token, expires_at })
+    }
+}
+
+/// Use case for confirming an email change
+pub struct ConfirmEmailChangeUseCase<U: ?Sized = dyn UserRepository> {
+    users: Arc<U>,
+    changes: Arc<dyn EmailChangeRepository>,
+    detached: DetachedWrites,
+}
+
+impl<U: UserRepository + ?Sized + 'static> ConfirmEmailChangeUseCase<U> {
+    /// Create a new use case instance, applying confirmed changes on `detached`
+    pub fn new(
+        users: Arc<U>,
+        changes: Arc<dyn EmailChangeRepository>,
+        detached: DetachedWrites,
+    ) -> Self {
+        Self { users, changes, detached }
+    }
+
+    /// Apply the email change confirmed by `token`, at most once
+    ///
+    /// The user is looked up within `tenant`, so a token only confirms in the tenant
+    /// of the user who requested it.
+    ///
+    /// # Errors
+    /// `Validation` for an empty token, `NotFound` for an unknown token or a deleted
+    /// user, `Conflict` for a used token, `Expired` for an expired one, and
+    /// `AlreadyExists` if another user took the address in the meantime.
+    pub async fn execute(&self, tenant: &TenantId, token: &str) -> Result<User, DomainError> {
+        if token.is_empty() {
+            return Err(DomainError::validation("Token", CANNOT_BE_EMPTY));
+        }
+        let change = self
+            .changes
+            .find_by_token_hash(&hash_token(token))
+            .await?
+            .ok_or_else(|| DomainError::not_found("Email change token"))?;
+        change.ensure_confirmable(Utc::now())?;
+
+        let mut user =
+            self.users.find_by_id(tenant, change.user_id()).await?.ok_or_else(|| {
+                DomainError::not_found(UserId::entity_name())
+            })?;
+        let name = user.name().to_owned();
+        user.update(name, change.new_email().value())?;
+        // Detached, so a dropped request cannot apply the change and leave its token
+        // usable
+        let (users, changes) = (Arc::clone(&self.users), Arc::clone(&self.changes));
+        let tenant = tenant.clone();
+        self.detached.run(async move {
+            users.update(&tenant, &user).await?;
+            // A concurrent confirmation applied the same change first
+            if !changes.mark_confirmed(change.token_hash()).await? {
+                return Err(DomainError::Conflict("Email change token was already used".into()));
+            }
+            Ok(user)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::user::infrastructure::{
+        InMemoryEmailChangeRepository, InMemoryUserRepository,
+    };
+    use crate::shared::domain::Entity;
+    use crate::testing::{CancellationSafety, FakeEmailChangeRepository, FakeUserRepository};
+    use std::sync::Mutex;
+
+    /// Notifier recording every delivered token
+    #[derive(Default)]
+    struct RecordingNotifier(Mutex<Vec<(String, String)>>);
+
+    #[async_trait::async_trait]
+    impl EmailChangeNotifier for RecordingNotifier {
+        async fn notify(
+            &self,
+            change: &PendingEmailChange,
+            token: &str,
+        ) -> Result<(), DomainError> {
+            let mut sent = self.0.lock().map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            sent.push((change.new_email().value().to_owned(), token.to_owned()));
+            Ok(())
+        }
+    }
+
+    struct Fixture {
+        users: Arc<InMemoryUserRepository>,
+        changes: Arc<InMemoryEmailChangeRepository>,
+        notifier: Arc<RecordingNotifier>,
+        request: RequestEmailChangeUseCase,
+        confirm: ConfirmEmailChangeUseCase,
+    }
+
+    fn fixture(token_ttl: Duration) -> Fixture {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let changes = Arc::new(InMemoryEmailChangeRepository::default());
+        let notifier = Arc::new(RecordingNotifier::default());
+        let request = RequestEmailChangeUseCase::new(
+            Arc::clone(&users) as _,
+            Arc::clone(&changes) as _,
+            Some(Arc::clone(&notifier) as _),
+            token_ttl,
+        );
+        let confirm = ConfirmEmailChangeUseCase::new(
+            Arc::clone(&users) as _,
+            Arc::clone(&changes) as _,
+            DetachedWrites::default(),
+        );
+        Fixture { users, changes, notifier, request, confirm }
+    }
+
+    async fn insert_user(users: &InMemoryUserRepository, name: &str) -> User {
+        let tenant = TenantId::default();
+        let user = User::new(UserId::generate(), name.into(), &format!("{name}@example.com"))
+            .expect("valid user");
+        users.insert(&tenant, &user).await.expect("insert");
+        user
+    }
+
+    fn command(new_email: &str) -> Validated<RequestEmailChangeCommand> {
+        Validated::new(RequestEmailChangeCommand { new_email: new_email.into() }).expect("valid")
+    }
+
+    #[tokio::test]
+    async fn request_should_store_only_the_hash_and_deliver_the_token() {
+        let tenant = TenantId::default();
+        let f = fixture(Duration::from_mins(1));
+        let alice = insert_user(&f.users, "alice").await;
+
+        let issued = f
+            .request
+            .execute(&tenant, alice.id().value(), command("new@example.com"))
+            .await
+            .expect("issue");
+
+        let sent = f.notifier.0.lock().expect("lock").clone();
+        assert_eq!(sent, [("new@example.com".to_owned(), issued.token.clone())]);
+        assert!(f.changes.find_by_token_hash(&issued.token).await.expect("query").is_none());
+        let stored = f.changes.find_by_token_hash(&hash_token(&issued.token)).await.expect("query");
+        assert_eq!(stored.map(|c| c.expires_at()), Some(issued.expires_at));
+        // The email only changes on confirmation
+        let unchanged =
+            f.users.find_by_id(&tenant, alice.id()).await.expect("query").expect("user");
+        assert_eq!(unchanged.email().value(), "alice@example.com");
+    }
+
+    #[tokio::test]
+    async fn request_should_reject_current_email_and_unknown_user() {
+        let tenant = TenantId::default();
+        let f = fixture(Duration::from_mins(1));
+        let alice = insert_user(&f.users, "alice").await;
+
+        let same =
+            f.request.execute(&tenant, alice.id().value(), command("alice@example.com")).await;
+        assert!(matches!(same, Err(DomainError::Validation(_))), "{same:?}");
+        let unknown =
+            f.request.execute(&tenant, UserId::generate().value(), command("x@example.com")).await;
+        assert!(matches!(unknown, Err(DomainError::NotFound(_))), "{unknown:?}");
+        assert!(f.notifier.0.lock().expect("lock").is_empty());
+        assert!(Validated::new(RequestEmailChangeCommand { new_email: "nope".into() }).is_err());
+    }
+
+    #[tokio::test]
+    async fn confirm_should_apply_the_change_once() {
+        let tenant = TenantId::default();
+        let f = fixture(Duration::from_mins(1));
+        let alice = insert_user(&f.users, "alice").await;
+        let issued = f
+            .request
+            .execute(&tenant, alice.id().value(), command("new@example.com"))
+            .await
+            .expect("issue");
+
+        let user = f.confirm.execute(&tenant, &issued.token).await.expect("confirm");
+        assert_eq!(user.email().value(), "new@example.com");
+        assert_eq!(user.name(), "alice");
+        let stored = f.users.find_by_id(&tenant, alice.id()).await.expect("query").expect("user");
+        assert_eq!(stored.email().value(), "new@example.com");
+
+        let reused = f.confirm.execute(&tenant, &issued.token).await;
+        assert!(matches!(reused, Err(DomainError::Conflict(_))), "{reused:?}");
+    }
+
+    #[tokio::test]
+    async fn confirm_should_reject_unknown_empty_and_expired_tokens() {
+        let tenant = TenantId::default();
+        let f = fixture(Duration::ZERO);
+        let alice = insert_user(&f.users, "alice").await;
+        let issued = f
+            .request
+            .execute(&tenant, alice.id().value(), command("new@example.com"))
+            .await
+            .expect("issue");
+
+        let expired = f.confirm.execute(&tenant, &issued.token).await;
+        assert!(matches!(expired, Err(DomainError::Expired(_))), "{expired:?}");
+        let unknown = f.confirm.execute(&tenant, &generate_token()).await;
+        assert!(matches!(unknown, Err(DomainError::NotFound(_))), "{unknown:?}");
+        let empty = f.confirm.execute(&tenant, "").await;
+        assert!(matches!(empty, Err(DomainError::Validation(_))), "{empty:?}");
+    }
+
+    #[tokio::test]
+    async fn confirm_should_check_uniqueness_and_keep_token_when_address_is_taken() {
+        let tenant = TenantId::default();
+        let f = fixture(Duration::from_mins(1));
+        let alice = insert_user(&f.users, "alice").await;
+        let issued = f
+            .request
+            .execute(&tenant, alice.id().value(), command("carol@example.com"))
+            .await
+            .expect("issue");
+        let carol = insert_user(&f.users, "carol").await;
+
+        let taken = f.confirm.execute(&tenant, &issued.token).await;
+        assert!(matches!(taken, Err(DomainError::AlreadyExists(_))), "{taken:?}");
+
+        f.users.delete(&tenant, carol.id()).await.expect("delete");
+        let user = f.confirm.execute(&tenant, &issued.token).await.expect("confirm once free");
+        assert_eq!(user.email().value(), "carol@example.com");
+    }
+
+    #[tokio::test]
+    async fn new_request_should_supersede_the_previous_token() {
+        let tenant = TenantId::default();
+        let f = fixture(Duration::from_mins(1));
+        let alice = insert_user(&f.users, "alice").await;
+        let first = f
+            .request
+            .execute(&tenant, alice.id().value(), command("one@example.com"))
+            .await
+            .expect("issue");
+        let second = f
+            .request
+            .execute(&tenant, alice.id().value(), command("two@example.com"))
+            .await
+            .expect("issue");
+
+        let superseded = f.confirm.execute(&tenant, &first.token).await;
+        assert!(matches!(superseded, Err(DomainError::NotFound(_))), "{superseded:?}");
+        let user = f.confirm.execute(&tenant, &second.token).await.expect("confirm");
+        assert_eq!(user.email().value(), "two@example.com");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_dropped_confirmation_should_apply_the_change_and_use_the_token_together() {
+        let tenant = TenantId::default();
+        let points = CancellationSafety::at_every_point(async |harness| {
+            let users = Arc::new(FakeUserRepository::default());
+            let changes = Arc::new(FakeEmailChangeRepository::default());
+            users.slow("update", Duration::from_millis(10));
+            changes.slow("mark_confirmed", Duration::from_millis(10));
+            let alice = User::new(UserId::generate(), "alice".into(), "alice@example.com")
+                .expect("valid user");
+            users.insert(&tenant, &alice).await.expect("insert");
+            let request = RequestEmailChangeUseCase::new(
+                Arc::clone(&users) as _,
+                Arc::clone(&changes) as _,
+                None,
+                Duration::from_mins(1),
+            );
+            let issued = request
+                .execute(&tenant, alice.id().value(), command("new@example.com"))
+                .await
+                .expect("issue");
+            let detached = DetachedWrites::default();
+            let confirm = ConfirmEmailChangeUseCase::new(
+                Arc::clone(&users),
+                Arc::clone(&changes) as _,
+                detached.clone(),
+            );
+
+            let finished = harness.run(confirm.execute(&tenant, &issued.token)).await.is_some();
+            detached.drained().await;
+            let user = users.find_by_id(&tenant, alice.id()).await.expect("query").expect("user");
+            let change = changes.find_by_token_hash(&hash_token(&issued.token)).await;
+            let confirmed = change.expect("query").expect("change").confirmed_at().is_some();
+            assert_eq!(user.email().value() == "new@example.com", confirmed);
+            finished
+        })
+        .await;
+        assert!(points > 0);
+    }
+}
@@ -1,41 +1,98 @@
 //! Get user use case
 
 use crate::features::user::domain::{User, UserId, UserRepository};
-use crate::shared::domain::DomainError;
+use crate::shared::application::{PageLimits, PageRequest};
+use crate::shared::domain::{DomainError, TenantId};
 use std::sync::Arc;
 
 /// Use case for getting a user by ID
-pub struct GetUserUseCase {
-    repository: Arc<dyn UserRepository>,
+pub struct GetUserUseCase<U: ?Sized = dyn UserRepository> {
+    repository: Arc<U>,
 }
 
-impl GetUserUseCase {
+impl<U: UserRepository + ?Sized> GetUserUseCase<U> {
     /// Create a new use case instance
-    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
+    pub fn new(repository: Arc<U>) -> Self {
         Self { repository }
     }
 
-    pub async fn execute(&self, id: &str) -> Result<User, DomainError> {
+    /// Get the user of `tenant` with `id`
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the user doesn't exist.
+    pub async fn execute(&self, tenant: &TenantId, id: &str) -> Result<User, DomainError> {
         let user_id = UserId::new(id)?;
         self.repository
-            .find_by_id(&user_id)
+            .find_by_id(tenant, &user_id)
             .await?
-            .ok_or_else(|| DomainError::NotFound(format!("{} not found", UserId::entity_name())))
+            .ok_or_else(|| DomainError::not_found(UserId::entity_name()))
     }
 }
 
-/// Use case for listing all users
-pub struct ListUsersUseCase {
-    repository: Arc<dyn UserRepository>,
+/// Use case for listing users, one page at a time
+pub struct ListUsersUseCase<U: ?Sized = dyn UserRepository> {
+    repository: Arc<U>,
+    limits: PageLimits,
 }
 
-impl ListUsersUseCase {
+impl<U: UserRepository + ?Sized> ListUsersUseCase<U> {
+    /// Create a new use case instance returning pages within `limits`
+    pub fn new(repository: Arc<U>, limits: PageLimits) -> Self {
+        Self { repository, limits }
+    }
+
+    /// Bounds of the pages listed
+    #[must_use]
+    pub fn limits(&self) -> PageLimits {
+        self.limits
+    }
+
+    /// List the requested page of users of `tenant` ordered by ID, only those with an
+    /// address at `email_domain` when given
+    ///
+    /// # Errors
+    /// `Validation` if `email_domain` is empty or contains `@` or the page is outside
+    /// the limits; repository failures.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        email_domain: Option<&str>,
+        page: PageRequest,
+    ) -> Result<Vec<User>, DomainError> {
+        if let Some(domain) = email_domain
+            && (domain.is_empty() || domain.contains('@'))
+        {
+            return Err(DomainError::Validation(format!(
+                "email_domain must be a domain such as example.com (got '{domain}')"
+            )));
+        }
+        let page = self.limits.resolve(page)?;
+        self.repository.find_page(tenant, email_domain, page).await
+    }
+}
+
+/// Use case for getting several users by ID in one repository call
+pub struct GetUsersByIdsUseCase<U: ?Sized = dyn UserRepository> {
+    repository: Arc<U>,
+}
+
+impl<U: UserRepository + ?Sized> GetUsersByIdsUseCase<U> {
     /// Create a new use case instance
-    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
+    pub fn new(repository: Arc<U>) -> Self {
         Self { repository }
     }
 
-    pub async fn execute(&self) -> Result<Vec<User>, DomainError> {
-        self.repository.find_all().await
+    /// Unknown IDs, and those of other tenants, are omitted from the result rather
+    /// than reported as errors.
+    ///
+    /// # Errors
+    /// `Validation` if any ID is empty.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        ids: &[String],
+    ) -> Result<Vec<User>, DomainError> {
+        let user_ids = ids.iter().map(|id| UserId::new(id)).collect::<Result<Vec<_>, _>>()?;
+        self.repository.find_by_ids(tenant, &user_ids).await
     }
 }
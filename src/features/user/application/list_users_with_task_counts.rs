@@ -0,0 +1,161 @@
+//! List users with the number of tasks each owns (read model spanning user and task)
+
+use crate::features::user::application::ListUsersUseCase;
+use crate::features::user::domain::{User, UserDependents, UserRepository};
+use crate::shared::application::{PageLimits, PageRequest};
+use crate::shared::domain::{DomainError, Entity, TenantId};
+use std::sync::Arc;
+
+/// A user together with the number of tasks they own
+#[derive(Debug)]
+pub struct UserWithTaskCount {
+    /// The user
+    pub user: User,
+    /// Tasks owned by the user, 0 for none
+    pub task_count: u64,
+}
+
+/// Read-model service listing users with their task counts, counting the tasks of
+/// the whole page in one query
+pub struct ListUsersWithTaskCountsUseCase<U: ?Sized = dyn UserRepository> {
+    list_users: ListUsersUseCase<U>,
+    dependents: Arc<dyn UserDependents>,
+}
+
+impl<U: UserRepository + ?Sized> ListUsersWithTaskCountsUseCase<U> {
+    /// Create a new use case instance returning pages within `limits`, counting tasks
+    /// through `dependents`
+    pub fn new(
+        repository: Arc<U>,
+        dependents: Arc<dyn UserDependents>,
+        limits: PageLimits,
+    ) -> Self {
+        Self { list_users: ListUsersUseCase::new(repository, limits), dependents }
+    }
+
+    /// List the requested page of users of `tenant` ordered by ID with their task
+    /// counts, only those with an address at `email_domain` when given
+    ///
+    /// # Errors
+    /// As [`ListUsersUseCase::execute`]; repository failures.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        email_domain: Option<&str>,
+        page: PageRequest,
+    ) -> Result<Vec<UserWithTaskCount>, DomainError> {
+        let users = self.list_users.execute(tenant, email_domain, page).await?;
+        if users.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: Vec<_> = users.iter().map(|u| u.id().clone()).collect();
+        let counts = self.dependents.count_by_user_ids(tenant, &ids).await?;
+        Ok(users
+            .into_iter()
+            .map(|user| {
+                let task_count = counts.get(user.id()).copied().unwrap_or_default();
+                UserWithTaskCount { user, task_count }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::{Task, TaskId, TaskRepository};
+    use crate::features::task::infrastructure::InMemoryTaskRepository;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::UserId;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts `count_by_user_ids` calls on top of an in-memory repository
+    #[derive(Default)]
+    struct CountingDependents {
+        inner: InMemoryTaskRepository,
+        batch_calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl UserDependents for CountingDependents {
+        async fn count_by_user_id(
+            &self,
+            tenant: &TenantId,
+            user_id: &UserId,
+        ) -> Result<u64, DomainError> {
+            self.inner.count_by_user_id(tenant, user_id).await
+        }
+        async fn count_by_user_ids(
+            &self,
+            tenant: &TenantId,
+            user_ids: &[UserId],
+        ) -> Result<HashMap<UserId, u64>, DomainError> {
+            self.batch_calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.count_by_user_ids(tenant, user_ids).await
+        }
+    }
+
+    /// Users named after `tasks`, each owning as many tasks as given
+    async fn seed(
+        users: &InMemoryUserRepository,
+        dependents: &CountingDependents,
+        tasks: &[(&str, usize)],
+    ) {
+        let tenant = TenantId::default();
+        for &(name, count) in tasks {
+            let email = format!("{name}@example.com");
+            let user = User::new(UserId::new(name).expect("valid user id"), name.to_owned(), &email)
+                .expect("valid user");
+            users.insert(&tenant, &user).await.expect("insert user");
+            for _ in 0..count {
+                let task = Task::new(TaskId::generate(), user.id().clone(), "Task", String::new())
+                    .expect("valid task");
+                dependents.inner.insert(&tenant, &task).await.expect("insert task");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_should_count_the_tasks_of_a_page_with_a_single_query() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let dependents = Arc::new(CountingDependents::default());
+        seed(&users, &dependents, &[("alice", 12), ("bob", 0), ("carol", 1)]).await;
+        let use_case = ListUsersWithTaskCountsUseCase::new(
+            users,
+            Arc::clone(&dependents) as _,
+            PageLimits::default(),
+        );
+
+        let listed = use_case
+            .execute(&TenantId::default(), None, PageRequest::default())
+            .await
+            .expect("list");
+
+        assert_eq!(dependents.batch_calls.load(Ordering::SeqCst), 1);
+        let counts: Vec<_> =
+            listed.iter().map(|u| (u.user.name().to_owned(), u.task_count)).collect();
+        let expected = [("alice", 12), ("bob", 0), ("carol", 1)].map(|(n, c)| (n.to_owned(), c));
+        assert_eq!(counts, expected);
+    }
+
+    #[tokio::test]
+    async fn execute_should_not_count_for_an_empty_page() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let dependents = Arc::new(CountingDependents::default());
+        seed(&users, &dependents, &[("alice", 1)]).await;
+        let use_case = ListUsersWithTaskCountsUseCase::new(
+            users,
+            Arc::clone(&dependents) as _,
+            PageLimits::default(),
+        );
+
+        let page = PageRequest { limit: None, offset: Some(1) };
+        let listed = use_case.execute(&TenantId::default(), None, page).await.expect("list");
+
+        assert!(listed.is_empty());
+        assert_eq!(dependents.batch_calls.load(Ordering::SeqCst), 0);
+    }
+}
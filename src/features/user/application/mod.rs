@@ -2,10 +2,19 @@
 
 pub mod create_user;
 pub mod delete_user;
+pub mod email_change;
 pub mod get_user;
+pub mod list_users_with_task_counts;
+pub mod sync_users;
 pub mod update_user;
 
 pub use create_user::{CreateUserCommand, CreateUserUseCase};
-pub use delete_user::DeleteUserUseCase;
-pub use get_user::{GetUserUseCase, ListUsersUseCase};
+pub use delete_user::{DeleteUserOptions, DeleteUserUseCase, DeletionImpact};
+pub use email_change::{
+    ConfirmEmailChangeUseCase, IssuedEmailChange, RequestEmailChangeCommand,
+    RequestEmailChangeUseCase,
+};
+pub use get_user::{GetUserUseCase, GetUsersByIdsUseCase, ListUsersUseCase};
+pub use list_users_with_task_counts::{ListUsersWithTaskCountsUseCase, UserWithTaskCount};
+pub use sync_users::{SyncUsersUseCase, UserSync};
 pub use update_user::{UpdateUserCommand, UpdateUserUseCase};
@@ -0,0 +1,99 @@
+//! Sync users use case
+
+use crate::features::user::domain::{SyncWatermark, User, UserRepository};
+use crate::shared::application::{PageLimits, PageRequest};
+use crate::shared::domain::{DomainError, TenantId};
+use std::sync::Arc;
+
+/// Users updated since a watermark and the watermark to resume from
+#[derive(Debug)]
+pub struct UserSync {
+    /// Users ordered by last update, then ID
+    pub users: Vec<User>,
+    /// Watermark after the last user returned; the requested one when none was
+    pub watermark: SyncWatermark,
+}
+
+/// Use case for sync consumers paging through the users updated since they last
+/// synced
+pub struct SyncUsersUseCase<U: ?Sized = dyn UserRepository> {
+    repository: Arc<U>,
+    limits: PageLimits,
+}
+
+impl<U: UserRepository + ?Sized> SyncUsersUseCase<U> {
+    /// Create a new use case instance returning at most the largest page of `limits`
+    pub fn new(repository: Arc<U>, limits: PageLimits) -> Self {
+        Self { repository, limits }
+    }
+
+    /// List up to `limit` users of `tenant` updated after `updated_since`, a
+    /// [`SyncWatermark`]
+    ///
+    /// # Errors
+    /// `Validation` if `updated_since` is not a watermark or `limit` is outside the
+    /// limits; repository failures.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        updated_since: &str,
+        limit: Option<u32>,
+    ) -> Result<UserSync, DomainError> {
+        let since: SyncWatermark = updated_since.parse()?;
+        let page = self.limits.resolve(PageRequest { limit, offset: None })?;
+        let users = self.repository.find_updated_since(tenant, &since, page.limit).await?;
+        let watermark = users.last().and_then(SyncWatermark::after).unwrap_or(since);
+        Ok(UserSync { users, watermark })
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::{Entity, UserId};
+
+    fn use_case() -> (Arc<InMemoryUserRepository>, SyncUsersUseCase<InMemoryUserRepository>) {
+        let repository = Arc::new(InMemoryUserRepository::default());
+        let limits = PageLimits { max_page_size: 2, ..PageLimits::default() };
+        (Arc::clone(&repository), SyncUsersUseCase::new(repository, limits))
+    }
+
+    #[tokio::test]
+    async fn execute_should_resume_from_the_returned_watermark() {
+        let (repository, use_case) = use_case();
+        let tenant = TenantId::default();
+        for name in ["a", "b", "c"] {
+            let user = User::new(UserId::generate(), name.into(), &format!("{name}@example.com"))
+                .expect("valid user");
+            repository.insert(&tenant, &user).await.expect("insert");
+        }
+
+        let first = use_case.execute(&tenant, "2000-01-01T00:00:00Z", None).await.expect("sync");
+        assert_eq!(first.users.len(), 2);
+        let resumed = first.watermark.to_string();
+        let second = use_case.execute(&tenant, &resumed, None).await.expect("sync");
+        assert_eq!(second.users.len(), 1);
+        assert_ne!(second.users[0].id(), first.users[1].id());
+    }
+
+    #[tokio::test]
+    async fn execute_should_keep_the_watermark_of_an_empty_delta() {
+        let (_, use_case) = use_case();
+        let since = "2026-01-02T03:04:05.500Z,u-1";
+        let sync = use_case.execute(&TenantId::default(), since, None).await.expect("sync");
+        assert!(sync.users.is_empty());
+        assert_eq!(sync.watermark.to_string(), since);
+    }
+
+    #[tokio::test]
+    async fn execute_should_reject_invalid_watermarks_and_limits() {
+        let (_, use_case) = use_case();
+        let tenant = TenantId::default();
+        let invalid = use_case.execute(&tenant, "yesterday", None).await;
+        assert!(matches!(invalid, Err(DomainError::Validation(_))), "{invalid:?}");
+        let too_large = use_case.execute(&tenant, "2026-01-02T03:04:05Z", Some(3)).await;
+        assert!(matches!(too_large, Err(DomainError::Validation(_))), "{too_large:?}");
+    }
+}
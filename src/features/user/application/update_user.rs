@@ -1,7 +1,9 @@
 //! Update user use case
 
+use super::create_user::validate_profile;
 use crate::features::user::domain::{User, UserId, UserRepository};
-use crate::shared::domain::DomainError;
+use crate::shared::application::{Validate, Validated, ValidationErrors};
+use crate::shared::domain::{DomainError, TenantId};
 use std::sync::Arc;
 
 /// Command to update a user
@@ -13,28 +15,50 @@ pub struct UpdateUserCommand {
     pub email: String,
 }
 
+impl Validate for UpdateUserCommand {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        validate_profile(&self.name, &self.email)
+    }
+}
+
 /// Use case for updating a user
-pub struct UpdateUserUseCase {
-    repository: Arc<dyn UserRepository>,
+pub struct UpdateUserUseCase<U: ?Sized = dyn UserRepository> {
+    repository: Arc<U>,
 }
 
-impl UpdateUserUseCase {
+impl<U: UserRepository + ?Sized> UpdateUserUseCase<U> {
     /// Create a new use case instance
-    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
+    pub fn new(repository: Arc<U>) -> Self {
         Self { repository }
     }
 
-    pub async fn execute(&self, id: &str, command: UpdateUserCommand) -> Result<User, DomainError> {
+    /// Replace the name and email of the user of `tenant` with `id`
+    ///
+    /// The user is locked from the read until the write, so a concurrent update or
+    /// delete waits for this one instead of landing in between.
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the user doesn't exist, and
+    /// `AlreadyExists` if the email belongs to another user of the tenant.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+        command: Validated<UpdateUserCommand>,
+    ) -> Result<User, DomainError> {
         let user_id = UserId::new(id)?;
+        let command = command.into_inner();
 
-        let mut user = self
-            .repository
-            .find_by_id(&user_id)
+        // Dropping the transaction on an early return releases the lock
+        let mut transaction = self.repository.begin().await?;
+        let mut user = transaction
+            .find_by_id_for_update(tenant, &user_id)
             .await?
-            .ok_or_else(|| DomainError::NotFound(format!("{} not found", UserId::entity_name())))?;
+            .ok_or_else(|| DomainError::not_found(UserId::entity_name()))?;
 
         user.update(command.name, &command.email)?;
-        self.repository.update(&user).await?;
+        transaction.update(tenant, &user).await?;
+        transaction.commit().await?;
         Ok(user)
     }
 }
@@ -0,0 +1,154 @@
+//! Email changes awaiting confirmation
+//!
+//! Requesting a change issues a random token, delivered out of band; only its hash is
+//! stored. Confirming with the token applies the new address once, before it expires.
+
+use crate::shared::domain::{DomainError, Email, UserId};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// Issue a new confirmation token: 64 hex characters from two random UUIDs
+#[must_use]
+pub fn generate_token() -> String {
+    let (a, b) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    format!("{}{}", a.simple(), b.simple())
+}
+
+/// Hex-encoded SHA-256 of `token`, the form in which tokens are stored and looked up
+#[must_use]
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().fold(String::with_capacity(64), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// A requested email change, identified by the hash of its token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEmailChange {
+    user_id: UserId,
+    new_email: Email,
+    token_hash: String,
+    expires_at: DateTime<Utc>,
+    confirmed_at: Option<DateTime<Utc>>,
+}
+
+impl PendingEmailChange {
+    /// Request changing the email of `user_id` to `new_email`, confirmable with the
+    /// token hashed to `token_hash` until `expires_at`
+    #[must_use]
+    pub fn new(
+        user_id: UserId,
+        new_email: Email,
+        token_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        Self { user_id, new_email, token_hash, expires_at, confirmed_at: None }
+    }
+
+    /// Reconstitute a change from persistence (bypasses business rules)
+    #[must_use]
+    pub fn reconstitute(
+        user_id: UserId,
+        new_email: Email,
+        token_hash: String,
+        expires_at: DateTime<Utc>,
+        confirmed_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self { user_id, new_email, token_hash, expires_at, confirmed_at }
+    }
+
+    /// User whose email changes
+    #[must_use]
+    pub fn user_id(&self) -> &UserId {
+        &self.user_id
+    }
+
+    /// Address the user's email changes to
+    #[must_use]
+    pub fn new_email(&self) -> &Email {
+        &self.new_email
+    }
+
+    /// Hash of the confirmation token (see [`hash_token`])
+    #[must_use]
+    pub fn token_hash(&self) -> &str {
+        &self.token_hash
+    }
+
+    /// End of the confirmation window
+    #[must_use]
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+
+    /// When the change was confirmed, once it was
+    #[must_use]
+    pub fn confirmed_at(&self) -> Option<DateTime<Utc>> {
+        self.confirmed_at
+    }
+
+    /// Check that the change can still be confirmed at `now`
+    ///
+    /// # Errors
+    /// `Conflict` when the token was already used, `Expired` when the window closed.
+    pub fn ensure_confirmable(&self, now: DateTime<Utc>) -> Result<(), DomainError> {
+        if self.confirmed_at.is_some() {
+            return Err(DomainError::Conflict("Email change token was already used".into()));
+        }
+        if now >= self.expires_at {
+            return Err(DomainError::Expired("Email change token has expired".into()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn change(
+        expires_at: DateTime<Utc>,
+        confirmed_at: Option<DateTime<Utc>>,
+    ) -> PendingEmailChange {
+        PendingEmailChange::reconstitute(
+            UserId::generate(),
+            Email::from_trusted("new@example.com".into()),
+            hash_token("token"),
+            expires_at,
+            confirmed_at,
+        )
+    }
+
+    #[test]
+    fn generate_token_should_be_random_hex() {
+        let (a, b) = (generate_token(), generate_token());
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()), "{a}");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn hash_token_should_be_stable_sha256_hex() {
+        assert_eq!(
+            hash_token("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_ne!(hash_token("abc"), hash_token("abd"));
+    }
+
+    #[test]
+    fn ensure_confirmable_should_reject_used_then_expired_tokens() {
+        let now = Utc::now();
+        let later = now + TimeDelta::minutes(5);
+        assert!(change(later, None).ensure_confirmable(now).is_ok());
+        assert!(matches!(change(now, None).ensure_confirmable(now), Err(DomainError::Expired(_))));
+        // A used token reports reuse even after it expired
+        assert!(matches!(
+            change(now, Some(now)).ensure_confirmable(later),
+            Err(DomainError::Conflict(_))
+        ));
+    }
+}
@@ -1,6 +1,12 @@
 //! User domain
 
-use crate::shared::domain::{DomainError, Email, Entity, UserId};
+use crate::shared::domain::{snapshot, DomainError, Email, Entity, UserId, CANNOT_BE_EMPTY};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Version of the snapshots [`User::to_snapshot`] writes; bump it when adding a
+/// field, which must have a default
+pub const USER_SNAPSHOT_VERSION: u32 = 1;
 
 /// User aggregate root
 #[derive(Debug, Clone)]
@@ -8,34 +14,47 @@ pub struct User {
     id: UserId,
     name: String,
     email: Email,
-    #[expect(dead_code, reason = "persisted for auditing, not yet exposed in API")]
     updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl User {
     /// Create a new user
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the name is empty or the email is invalid.
     pub fn new(id: UserId, name: String, email: &str) -> Result<Self, DomainError> {
         if name.is_empty() {
-            return Err(DomainError::Validation("Name cannot be empty".into()));
+            return Err(DomainError::validation("Name", CANNOT_BE_EMPTY));
         }
         let email = Email::new(email)?;
         Ok(Self { id, name, email, updated_at: None })
     }
 
     /// Get user name
+    #[must_use]
     pub fn name(&self) -> &str {
         &self.name
     }
 
     /// Get user email
+    #[must_use]
     pub fn email(&self) -> &Email {
         &self.email
     }
 
+    /// Time of the last persisted change, once stored
+    #[must_use]
+    pub fn updated_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.updated_at
+    }
+
     /// Update user name and email
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the name is empty or the email is invalid.
     pub fn update(&mut self, name: String, email: &str) -> Result<(), DomainError> {
         if name.is_empty() {
-            return Err(DomainError::Validation("Name cannot be empty".into()));
+            return Err(DomainError::validation("Name", CANNOT_BE_EMPTY));
         }
         let email = Email::new(email)?;
         self.name = name;
@@ -44,6 +63,7 @@ impl User {
     }
 
     /// Reconstitute a user from persistence (bypasses business rules)
+    #[must_use]
     pub fn reconstitute(
         id: UserId,
         name: String,
@@ -52,6 +72,47 @@ impl User {
     ) -> Self {
         Self { id, name, email, updated_at }
     }
+
+    /// The user as a JSON snapshot with its `schema_version`, as recorded in the audit
+    /// trail of an onboarding
+    #[must_use]
+    pub fn to_snapshot(&self) -> serde_json::Value {
+        let fields = UserSnapshot {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            email: self.email.clone(),
+            updated_at: self.updated_at,
+        };
+        snapshot::write(&fields, USER_SNAPSHOT_VERSION)
+    }
+
+    /// Read a user back from a snapshot of any version up to
+    /// [`USER_SNAPSHOT_VERSION`]
+    ///
+    /// Unlike [`User::reconstitute`], the rules of [`User::new`] apply, as snapshots
+    /// may come from outside, e.g. a data import.
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the snapshot has an unknown version, lacks
+    /// a field, or its ID, name or email is invalid.
+    pub fn from_snapshot(snapshot: serde_json::Value) -> Result<Self, DomainError> {
+        let fields: UserSnapshot =
+            snapshot::read(snapshot, UserId::entity_name(), USER_SNAPSHOT_VERSION)?;
+        let id = UserId::new(fields.id.value())?;
+        let mut user = Self::new(id, fields.name, fields.email.value())?;
+        user.updated_at = fields.updated_at;
+        Ok(user)
+    }
+}
+
+/// Fields of a user snapshot
+#[derive(Serialize, Deserialize)]
+struct UserSnapshot {
+    id: UserId,
+    name: String,
+    email: Email,
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
 }
 
 impl Entity for User {
@@ -88,4 +149,55 @@ mod tests {
     fn user_id_new_should_reject_empty() {
         assert!(matches!(UserId::new(""), Err(DomainError::Validation(_))));
     }
+
+    #[test]
+    fn snapshots_should_round_trip() {
+        let names = ["Alice", "Zoë \"Z\" O'Brien", "名前", " padded "];
+        let emails = ["alice@example.com", "a.b+tag@sub.example.org"];
+        let times = [None, DateTime::from_timestamp(1_700_000_000, 123_000)];
+        let fields = |u: &User| (u.id.clone(), u.name.clone(), u.email.clone(), u.updated_at);
+        for (i, name) in names.iter().enumerate() {
+            for email in emails {
+                for updated_at in times {
+                    let id = UserId::from_trusted(format!("user-{i}"));
+                    let email = Email::from_trusted(email.into());
+                    let user = User::reconstitute(id, (*name).to_owned(), email, updated_at);
+                    let read = User::from_snapshot(user.to_snapshot()).ok();
+                    assert_eq!(read.as_ref().map(fields), Some(fields(&user)), "{name}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn v1_snapshots_should_still_be_read() {
+        // As written by the first version; fields added since must take defaults
+        let v1 = serde_json::json!({
+            "schema_version": 1,
+            "id": "u1",
+            "name": "Alice",
+            "email": "alice@example.com",
+            "updated_at": "2025-01-02T03:04:05Z"
+        });
+        let user = User::from_snapshot(v1).ok();
+        assert_eq!(user.as_ref().map(|u| u.email().value()), Some("alice@example.com"));
+        let without_optional = serde_json::json!({
+            "schema_version": 1, "id": "u1", "name": "Alice", "email": "alice@example.com"
+        });
+        let user = User::from_snapshot(without_optional).ok();
+        assert_eq!(user.map(|u| u.updated_at()), Some(None));
+    }
+
+    #[test]
+    fn snapshots_should_be_held_to_the_rules_of_new_users() {
+        for invalid in [
+            serde_json::json!({"schema_version": 1, "id": "", "name": "A", "email": "a@b.co"}),
+            serde_json::json!({"schema_version": 1, "id": "u1", "name": "", "email": "a@b.co"}),
+            serde_json::json!({"schema_version": 1, "id": "u1", "name": "A", "email": "nope"}),
+            serde_json::json!({"schema_version": 2, "id": "u1", "name": "A", "email": "a@b.co"}),
+        ] {
+            let result = User::from_snapshot(invalid.clone());
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{invalid}");
+        }
+    }
 }
@@ -0,0 +1,18 @@
+//! Error codes of the user feature
+
+use crate::shared::domain::{DomainError, ErrorStatus};
+
+/// Code of the refusal to delete a user other entities depend on
+pub const HAS_DEPENDENTS: &str = "HAS_DEPENDENTS";
+
+/// Refuse to delete a user because `count` other entities depend on them; the
+/// count is reported in `details.count`
+#[must_use]
+pub fn has_dependents(count: u64, message: String) -> DomainError {
+    DomainError::Custom {
+        code: HAS_DEPENDENTS,
+        status_hint: ErrorStatus::Conflict,
+        message,
+        details: serde_json::json!({ "count": count }),
+    }
+}
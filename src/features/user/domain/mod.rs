@@ -1,8 +1,16 @@
 //! User domain layer
 
+pub mod email_change;
 pub mod entity;
+pub mod error;
 pub mod repository;
+pub mod sync;
 
 pub use crate::shared::domain::UserId;
-pub use entity::User;
-pub use repository::UserRepository;
+pub use email_change::PendingEmailChange;
+pub use entity::{User, USER_SNAPSHOT_VERSION};
+pub use error::{has_dependents, HAS_DEPENDENTS};
+pub use repository::{
+    EmailChangeNotifier, EmailChangeRepository, UserDependents, UserRepository, UserTransaction,
+};
+pub use sync::SyncWatermark;
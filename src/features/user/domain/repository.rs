@@ -1,19 +1,131 @@
 //! User repository port
 
+use super::email_change::PendingEmailChange;
 use super::entity::User;
-use crate::shared::domain::{DomainError, UserId};
+use super::sync::SyncWatermark;
+use crate::shared::domain::{DomainError, Page, TenantId, UserId};
+use std::collections::HashMap;
 
 /// Repository for user aggregate
+///
+/// Every method is scoped to `tenant`: users of other tenants are neither found
+/// nor changed. Methods returning several users order them by ID, whatever order
+/// the users were inserted in, so lists and their pages are stable.
 #[async_trait::async_trait]
 pub trait UserRepository: Send + Sync {
     /// Find user by ID
-    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, DomainError>;
-    /// Find all users
-    async fn find_all(&self) -> Result<Vec<User>, DomainError>;
-    /// Insert a new user (fails if ID or email already exists)
-    async fn insert(&self, user: &User) -> Result<(), DomainError>;
-    /// Update an existing user
-    async fn update(&self, user: &User) -> Result<(), DomainError>;
+    async fn find_by_id(&self, tenant: &TenantId, id: &UserId)
+        -> Result<Option<User>, DomainError>;
+    /// Find the users with the given IDs in one round trip, ordered by ID; unknown IDs
+    /// are skipped
+    async fn find_by_ids(
+        &self,
+        tenant: &TenantId,
+        ids: &[UserId],
+    ) -> Result<Vec<User>, DomainError>;
+    /// Find the `page` of users ordered by ID, only those whose email domain is
+    /// `email_domain` (compared case-insensitively) when given
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        email_domain: Option<&str>,
+        page: Page,
+    ) -> Result<Vec<User>, DomainError>;
+    /// Find all users, ordered by ID, however many there are; for internal jobs, never
+    /// request handlers
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<User>, DomainError>;
+    /// Find up to `limit` users updated after `since`, ordered by last update then ID
+    /// rather than by ID alone
+    async fn find_updated_since(
+        &self,
+        tenant: &TenantId,
+        since: &SyncWatermark,
+        limit: u32,
+    ) -> Result<Vec<User>, DomainError>;
+    /// Insert a new user (fails if the ID exists, or the email exists in the tenant)
+    async fn insert(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError>;
+    /// Update an existing user (fails with `NotFound` if the user no longer exists,
+    /// or `AlreadyExists` if the email belongs to another user of the tenant)
+    async fn update(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError>;
     /// Delete user by ID, returns true if a row was deleted
-    async fn delete(&self, id: &UserId) -> Result<bool, DomainError>;
+    async fn delete(&self, tenant: &TenantId, id: &UserId) -> Result<bool, DomainError>;
+    /// Delete user by ID, returning the user as it was deleted, read in the same
+    /// atomic step so no concurrent write lands in between; `None` if there was none
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError>;
+    /// Begin a transaction reading users for update, for read-modify-write use cases
+    /// that must not lose a concurrent write
+    async fn begin(&self) -> Result<Box<dyn UserTransaction + '_>, DomainError>;
+}
+
+/// Transaction of the user repository, begun by [`UserRepository::begin`]
+///
+/// A user read through it stays locked until the transaction ends: another
+/// transaction reading it for update, or a write of the repository, waits until
+/// this one commits or is dropped, which undoes its writes. Reading for update is
+/// only possible here, so no lock outlives the transaction that took it.
+///
+/// Transactions locking several rows lock users before tasks, and rows of one kind
+/// in ID order, so that no two of them wait on each other.
+#[async_trait::async_trait]
+pub trait UserTransaction: Send {
+    /// Find user by ID, locking it until the transaction ends
+    async fn find_by_id_for_update(
+        &mut self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError>;
+    /// Update an existing user, failing as [`UserRepository::update`] does
+    async fn update(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError>;
+    /// Make the updates visible and release the locks
+    async fn commit(self: Box<Self>) -> Result<(), DomainError>;
+}
+
+/// Counts the entities owned by a user that deleting the user would cascade to
+#[async_trait::async_trait]
+pub trait UserDependents: Send + Sync {
+    /// Count the entities owned by `user_id` in `tenant`
+    async fn count_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<u64, DomainError>;
+
+    /// Count the entities owned by each of `user_ids` in `tenant` in one round trip;
+    /// users owning none are left out of the map
+    async fn count_by_user_ids(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+    ) -> Result<HashMap<UserId, u64>, DomainError>;
+
+    /// Forget any state kept about the entities of `user_id`, whose deletion has
+    /// just cascaded to them; nothing to do unless they are cached
+    async fn user_deleted(&self, _tenant: &TenantId, _user_id: &UserId) -> Result<(), DomainError> {
+        Ok(())
+    }
+}
+
+/// Repository for pending email changes
+#[async_trait::async_trait]
+pub trait EmailChangeRepository: Send + Sync {
+    /// Store `change`, discarding any unconfirmed change of the same user
+    async fn save(&self, change: &PendingEmailChange) -> Result<(), DomainError>;
+    /// Find the change whose token hashes to `token_hash`, confirmed or not
+    async fn find_by_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<PendingEmailChange>, DomainError>;
+    /// Mark the change confirmed, returns false if it was confirmed already
+    async fn mark_confirmed(&self, token_hash: &str) -> Result<bool, DomainError>;
+}
+
+/// Delivers email change tokens to the user, e.g. through a webhook that mails them
+#[async_trait::async_trait]
+pub trait EmailChangeNotifier: Send + Sync {
+    /// Deliver `token`, which confirms `change`
+    async fn notify(&self, change: &PendingEmailChange, token: &str) -> Result<(), DomainError>;
 }
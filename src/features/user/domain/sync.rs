@@ -0,0 +1,114 @@
+//! Watermarks of incremental user syncs
+
+use super::User;
+use crate::shared::domain::{DomainError, Entity, UserId};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// Position of a sync consumer in the users ordered by last update, then ID
+///
+/// Written as `<rfc3339>` or `<rfc3339>,<id>`. With an ID, the users updated at
+/// exactly `updated_at` with a greater ID are still to come, so a page ending among
+/// users updated at the same instant neither skips nor repeats any of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncWatermark {
+    /// Last update already synced
+    pub updated_at: DateTime<Utc>,
+    /// Last user synced among those updated at `updated_at`; `None` when all were
+    pub after_id: Option<UserId>,
+}
+
+impl SyncWatermark {
+    /// Watermark after every user updated up to `updated_at`
+    #[must_use]
+    pub fn since(updated_at: DateTime<Utc>) -> Self {
+        Self { updated_at, after_id: None }
+    }
+
+    /// Watermark just after `user`; `None` if it was never stored
+    #[must_use]
+    pub fn after(user: &User) -> Option<Self> {
+        let updated_at = user.updated_at()?;
+        Some(Self { updated_at, after_id: Some(user.id().clone()) })
+    }
+
+    /// Whether a user updated at `updated_at` with ID `id` comes after the watermark
+    #[must_use]
+    pub fn precedes(&self, updated_at: DateTime<Utc>, id: &UserId) -> bool {
+        match &self.after_id {
+            Some(after_id) => {
+                updated_at > self.updated_at
+                    || (updated_at == self.updated_at && id.value() > after_id.value())
+            }
+            None => updated_at > self.updated_at,
+        }
+    }
+}
+
+impl fmt::Display for SyncWatermark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.updated_at.to_rfc3339_opts(SecondsFormat::AutoSi, true))?;
+        match &self.after_id {
+            Some(id) => write!(f, ",{}", id.value()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for SyncWatermark {
+    type Err = DomainError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (updated_at, after_id) = match value.split_once(',') {
+            Some((updated_at, id)) => (updated_at, Some(UserId::new(id)?)),
+            None => (value, None),
+        };
+        let updated_at = DateTime::parse_from_rfc3339(updated_at)
+            .map_err(|_| {
+                DomainError::Validation(format!(
+                    "updated_since must be an RFC3339 timestamp, optionally followed by \
+                     ',<id>' (got '{value}')"
+                ))
+            })?
+            .with_timezone(&Utc);
+        Ok(Self { updated_at, after_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn watermarks_should_round_trip_through_their_text() {
+        let at = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).single().unwrap_or_default()
+            + chrono::Duration::microseconds(123_456);
+        for watermark in [
+            SyncWatermark::since(at),
+            SyncWatermark { updated_at: at, after_id: Some(UserId::from_trusted("u-1".into())) },
+        ] {
+            assert_eq!(watermark.to_string().parse::<SyncWatermark>().ok(), Some(watermark));
+        }
+        assert_eq!(SyncWatermark::since(at).to_string(), "2026-01-02T03:04:05.123456Z");
+        let offset = "2026-01-02T12:04:05.123456+09:00".parse::<SyncWatermark>().ok();
+        assert_eq!(offset, Some(SyncWatermark::since(at)));
+        for invalid in ["", "yesterday", "2026-01-02T03:04:05Z,", ",u-1"] {
+            assert!(invalid.parse::<SyncWatermark>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn precedes_should_break_ties_on_the_id_only_after_one() {
+        let at = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).single().unwrap_or_default();
+        let id = |id: &str| UserId::from_trusted(id.into());
+        let since = SyncWatermark::since(at);
+        assert!(!since.precedes(at, &id("b")));
+        assert!(since.precedes(at + chrono::Duration::microseconds(1), &id("a")));
+        let after_b = SyncWatermark { updated_at: at, after_id: Some(id("b")) };
+        assert!(!after_b.precedes(at, &id("a")));
+        assert!(!after_b.precedes(at, &id("b")));
+        assert!(after_b.precedes(at, &id("c")));
+    }
+}
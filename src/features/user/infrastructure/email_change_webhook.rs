@@ -0,0 +1,123 @@
+//! Delivery of email change tokens through a webhook
+
+use crate::features::user::domain::{EmailChangeNotifier, PendingEmailChange};
+use crate::shared::domain::DomainError;
+use crate::shared::events::{EventEnvelope, EventPayload, UserEmailChangeRequestedV1};
+use crate::shared::infrastructure::http_client::{retrying_post_json, OutboundHttp, RetryPolicy};
+use std::sync::Arc;
+
+/// Event type posted for every requested email change
+pub const EMAIL_CHANGE_REQUESTED: &str = UserEmailChangeRequestedV1::SCHEMA.event_type;
+
+/// Posts each token to a webhook, e.g. a mailer sending it to the new address
+pub struct WebhookEmailChangeNotifier {
+    http: Arc<dyn OutboundHttp>,
+    url: String,
+    policy: RetryPolicy,
+}
+
+impl WebhookEmailChangeNotifier {
+    /// Post tokens to `url` through `http`, retrying transient failures
+    #[must_use]
+    pub fn new(http: Arc<dyn OutboundHttp>, url: String) -> Self {
+        Self { http, url, policy: RetryPolicy::default() }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailChangeNotifier for WebhookEmailChangeNotifier {
+    async fn notify(&self, change: &PendingEmailChange, token: &str) -> Result<(), DomainError> {
+        // The receiver mails `token` to `new_email`
+        let payload = UserEmailChangeRequestedV1 {
+            user_id: change.user_id().value().to_owned(),
+            new_email: change.new_email().value().to_owned(),
+            token: token.to_owned(),
+            expires_at: change.expires_at(),
+        };
+        let body = EventEnvelope::new(payload, chrono::Utc::now());
+        retrying_post_json(&*self.http, &self.url, &body, &self.policy).await.map_err(|e| {
+            tracing::error!("Email change webhook failed: {e}");
+            DomainError::Unavailable("Could not deliver the email change token".into())
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::shared::domain::{Email, UserId};
+    use crate::shared::infrastructure::http_client::OutboundError;
+    use axum::http::{HeaderMap, StatusCode};
+    use std::sync::Mutex;
+
+    /// Fake answering with `status` and recording each request
+    struct RecordingHttp {
+        status: StatusCode,
+        requests: Mutex<Vec<(String, serde_json::Value)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl OutboundHttp for RecordingHttp {
+        async fn post_json_with_headers(
+            &self,
+            url: &str,
+            _: HeaderMap,
+            body: Vec<u8>,
+        ) -> Result<StatusCode, OutboundError> {
+            let body = serde_json::from_slice(&body).expect("JSON body");
+            self.requests.lock().expect("lock").push((url.to_owned(), body));
+            Ok(self.status)
+        }
+    }
+
+    fn change() -> PendingEmailChange {
+        PendingEmailChange::new(
+            UserId::from_trusted("user-1".into()),
+            Email::from_trusted("new@example.com".into()),
+            "hash".into(),
+            chrono::DateTime::UNIX_EPOCH,
+        )
+    }
+
+    #[tokio::test]
+    async fn notify_should_post_the_token_to_the_webhook() {
+        let http =
+            Arc::new(RecordingHttp { status: StatusCode::ACCEPTED, requests: Mutex::default() });
+        let notifier =
+            WebhookEmailChangeNotifier::new(Arc::clone(&http) as _, "http://mailer".into());
+
+        notifier.notify(&change(), "secret").await.expect("delivered");
+
+        let mut requests = http.requests.lock().expect("lock").clone();
+        assert_eq!(requests.len(), 1);
+        let (url, mut body) = requests.remove(0);
+        assert_eq!(url, "http://mailer");
+        assert!(body["occurred_at"].as_str().is_some_and(|at| at.ends_with('Z')), "{body}");
+        body["occurred_at"].take();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "event_type": "user.email_change_requested",
+                "version": 1,
+                "occurred_at": null,
+                "payload": {
+                    "user_id": "user-1",
+                    "new_email": "new@example.com",
+                    "token": "secret",
+                    "expires_at": "1970-01-01T00:00:00.000Z",
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_delivery_should_be_unavailable() {
+        let http =
+            Arc::new(RecordingHttp { status: StatusCode::BAD_REQUEST, requests: Mutex::default() });
+        let notifier = WebhookEmailChangeNotifier::new(http, "http://mailer".into());
+        let result = notifier.notify(&change(), "secret").await;
+        assert!(matches!(result, Err(DomainError::Unavailable(_))), "{result:?}");
+    }
+}
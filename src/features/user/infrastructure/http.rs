@@ -1,110 +1,786 @@
 //! User HTTP handlers
 
-use crate::features::user::application::{CreateUserCommand, UpdateUserCommand};
-use crate::features::user::domain::User;
+pub use crate::api_types::{
+    ConfirmEmailChangeRequest, CreateUserRequest, DeletionCounts, DryRunResponse,
+    EmailChangeRequest, EmailChangeResponse, UpdateUserRequest, UserResponse, UserSyncResponse,
+};
+use crate::features::user::application::{
+    CreateUserCommand, DeleteUserOptions, RequestEmailChangeCommand, UpdateUserCommand,
+    UserWithTaskCount,
+};
+use crate::features::user::domain::{User, UserRepository};
+use crate::features::user::{UserState, NAME};
+use crate::shared::application::{PageRequest, Validated};
 use crate::shared::domain::entity::Entity;
-use crate::shared::infrastructure::http::ApiError;
-use crate::AppState;
+use crate::shared::domain::{timestamp, DomainError, TenantId};
+use crate::shared::infrastructure::cache_control;
+use crate::shared::infrastructure::conditional;
+use crate::shared::infrastructure::feature::{ExpectedRoute, FeatureRouter};
+use crate::shared::infrastructure::fields::{self, FieldSelection};
+use crate::shared::infrastructure::http::{
+    ApiError, ApiPath, ApiQuery, Negotiated, ReturnPreference,
+};
+use crate::shared::infrastructure::instrumentation::budgeted;
+use crate::shared::infrastructure::page_links::page_links;
+use crate::shared::infrastructure::request_context::{self, RequestContext};
+use crate::shared::infrastructure::tenant::TenantContext;
 use std::sync::Arc;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{OriginalUri, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::map_response,
+    response::{IntoResponse, Response},
     routing::{get, post},
-    Json, Router,
+    Router,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
 type ApiResult<T> = Result<T, ApiError>;
 
-/// HTTP response body for a user
-#[derive(Serialize)]
-pub struct UserResponse {
-    pub id: String,
-    pub name: String,
-    pub email: String,
-}
-
 impl From<User> for UserResponse {
     fn from(u: User) -> Self {
         Self {
             id: u.id().value().to_owned(),
             name: u.name().to_owned(),
             email: u.email().value().to_owned(),
+            task_count: None,
         }
     }
 }
 
-/// HTTP request body for creating a user
-#[derive(Deserialize)]
-pub struct CreateUserRequest {
-    pub name: String,
-    pub email: String,
+impl From<UserWithTaskCount> for UserResponse {
+    fn from(UserWithTaskCount { user, task_count }: UserWithTaskCount) -> Self {
+        Self { task_count: Some(task_count), ..user.into() }
+    }
 }
 
-/// HTTP request body for updating a user
-#[derive(Deserialize)]
-pub struct UpdateUserRequest {
-    pub name: String,
-    pub email: String,
-}
+/// Routes served by [`routes`]
+pub const ROUTES: &[ExpectedRoute] = &[
+    ExpectedRoute::get("/"),
+    ExpectedRoute::post("/"),
+    ExpectedRoute::get("/{id}"),
+    ExpectedRoute::put("/{id}"),
+    ExpectedRoute::delete("/{id}"),
+    ExpectedRoute::post("/{id}/email-change"),
+    ExpectedRoute::post("/email-change/confirm"),
+];
 
-/// User feature router
-pub fn router() -> Router<Arc<AppState>> {
-    Router::new()
-        .route("/users", post(create_user).get(list_users))
+/// User feature routes, nested under `/users`
+pub fn routes<U: UserRepository + ?Sized + 'static>(
+    state: Arc<UserState<U>>,
+) -> FeatureRouter<()> {
+    let router = Router::new()
+        .route("/", get(list_users).layer(map_response(cache_control::list)).post(create_user))
         .route(
-            "/users/{id}",
-            get(get_user).put(update_user).delete(delete_user),
+            "/{id}",
+            get(get_user)
+                .layer(map_response(cache_control::entity))
+                .put(update_user)
+                .delete(delete_user),
         )
+        .route("/{id}/email-change", post(request_email_change))
+        .route("/email-change/confirm", post(confirm_email_change));
+    FeatureRouter {
+        name: NAME,
+        prefix: "/users",
+        router: router.with_state(state),
+        manifest: ROUTES,
+    }
 }
 
-/// Create a new user
-pub async fn create_user(
-    State(state): State<Arc<AppState>>,
-    Json(body): Json<CreateUserRequest>,
-) -> ApiResult<(StatusCode, Json<UserResponse>)> {
-    let user = state
-        .create_user
-        .execute(CreateUserCommand { name: body.name, email: body.email })
+/// Create a new user, linking to it in `Location`
+async fn create_user<U: UserRepository + ?Sized>(
+    State(state): State<Arc<UserState<U>>>,
+    TenantContext(tenant): TenantContext,
+    context: RequestContext,
+    Negotiated(body): Negotiated<CreateUserRequest>,
+) -> ApiResult<Response> {
+    let command = Validated::new(CreateUserCommand { name: body.name, email: body.email })?;
+    let user = budgeted("create_user", state.create_user.execute(&tenant, command))
         .await
         .map_err(ApiError::from)?;
-    Ok((StatusCode::CREATED, Json(user.into())))
+    let location = format!("/users/{}", user.id().value());
+    Ok(request_context::created(&context, &location, UserResponse::from(user)))
 }
 
-/// Get a user by ID
-pub async fn get_user(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> ApiResult<Json<UserResponse>> {
-    let user = state.get_user.execute(&id).await.map_err(ApiError::from)?;
-    Ok(Json(user.into()))
+/// Get a user by ID, honouring `If-Modified-Since`
+async fn get_user<U: UserRepository + ?Sized>(
+    State(state): State<Arc<UserState<U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let user = budgeted("get_user", state.get_user.execute(&tenant, &id))
+        .await
+        .map_err(ApiError::from)?;
+    // Stored users always have one: the column is NOT NULL
+    let last_modified = user
+        .updated_at()
+        .ok_or_else(|| DomainError::Unexpected(format!("User {id} has no updated_at")))?;
+    Ok(conditional::respond(&headers, last_modified, Negotiated(UserResponse::from(user))))
+}
+
+/// Query parameters of `GET /users`
+#[derive(Debug, Deserialize)]
+pub struct ListUsersQuery {
+    /// Comma-separated fields to return (see [`LIST_FIELDS`]); `id` is always included
+    pub fields: Option<String>,
+    /// Only users with an address at this domain (e.g. `example.com`)
+    pub email_domain: Option<String>,
+    /// Users per page; defaults to the largest page allowed
+    pub limit: Option<u32>,
+    /// Users to skip
+    pub offset: Option<u32>,
+    /// Only users updated after this watermark (`<rfc3339>` or `<rfc3339>,<id>`),
+    /// ordered by last update then ID in a `{items, watermark}` envelope
+    pub updated_since: Option<String>,
+    /// Comma-separated extras to embed (see [`LIST_EMBEDS`])
+    pub embed: Option<String>,
+}
+
+/// Extras that can be embedded in user listings
+pub const LIST_EMBEDS: &[&str] = &["task_count"];
+
+/// Fields that can be selected in user listings with `?fields=`
+pub const LIST_FIELDS: &[&str] = &["id", "name", "email", "taskCount"];
+
+/// List a page of users (`limit=`, `offset=`), filtered by `email_domain=`, with the
+/// number of tasks each owns with `embed=task_count` and projected to a subset of
+/// fields with `fields=`; with `updated_since=`, the users updated since a sync
+/// consumer's watermark instead
+async fn list_users<U: UserRepository + ?Sized>(
+    State(state): State<Arc<UserState<U>>>,
+    TenantContext(tenant): TenantContext,
+    context: RequestContext,
+    OriginalUri(uri): OriginalUri,
+    ApiQuery(query): ApiQuery<ListUsersQuery>,
+) -> ApiResult<Response> {
+    if let Some(updated_since) = &query.updated_since {
+        return sync_users(&state, &tenant, updated_since, &query).await;
+    }
+    let selection = FieldSelection::parse(query.fields.as_deref(), LIST_FIELDS)?;
+    let page = PageRequest { limit: query.limit, offset: query.offset };
+    let email_domain = query.email_domain.as_deref();
+    let embed_task_count = fields::embeds(query.embed.as_deref(), LIST_EMBEDS, "task_count")?;
+    let users: Vec<UserResponse> = if embed_task_count {
+        let Some(list_users) = &state.list_users_with_task_counts else {
+            return Err(ApiError::from_query(DomainError::Validation(
+                "task_count cannot be embedded without the task feature".into(),
+            )));
+        };
+        budgeted("list_users_with_task_counts", list_users.execute(&tenant, email_domain, page))
+            .await
+            .map_err(ApiError::from_query)?
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    } else {
+        budgeted("list_users", state.list_users.execute(&tenant, email_domain, page))
+            .await
+            .map_err(ApiError::from_query)?
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    };
+    let links = page_links(&context, &uri, state.list_users.limits(), page, users.len());
+    let mut response = fields::project_list(users, selection.as_ref())?;
+    if let Some(links) = links {
+        response.headers_mut().insert(header::LINK, links);
+    }
+    Ok(response)
 }
 
-/// List all users
-pub async fn list_users(State(state): State<Arc<AppState>>) -> ApiResult<Json<Vec<UserResponse>>> {
-    let users = state.list_users.execute().await.map_err(ApiError::from)?;
-    Ok(Json(users.into_iter().map(Into::into).collect()))
+/// Users of `tenant` updated since the watermark `updated_since`, up to `limit=`
+async fn sync_users<U: UserRepository + ?Sized>(
+    state: &UserState<U>,
+    tenant: &TenantId,
+    updated_since: &str,
+    query: &ListUsersQuery,
+) -> ApiResult<Response> {
+    if query.offset.is_some()
+        || query.email_domain.is_some()
+        || query.fields.is_some()
+        || query.embed.is_some()
+    {
+        return Err(ApiError::from_query(DomainError::Validation(
+            "updated_since cannot be combined with offset, email_domain, fields or embed".into(),
+        )));
+    }
+    let sync = budgeted("sync_users", state.sync_users.execute(tenant, updated_since, query.limit))
+        .await
+        .map_err(ApiError::from_query)?;
+    let items = sync.users.into_iter().map(Into::into).collect();
+    let body = UserSyncResponse { items, watermark: sync.watermark.to_string() };
+    Ok(Negotiated(body).into_response())
 }
 
 /// Update a user
-pub async fn update_user(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-    Json(body): Json<UpdateUserRequest>,
-) -> ApiResult<Json<UserResponse>> {
-    let user = state
-        .update_user
-        .execute(&id, UpdateUserCommand { name: body.name, email: body.email })
+async fn update_user<U: UserRepository + ?Sized>(
+    State(state): State<Arc<UserState<U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    Negotiated(body): Negotiated<UpdateUserRequest>,
+) -> ApiResult<Negotiated<UserResponse>> {
+    let command = Validated::new(UpdateUserCommand { name: body.name, email: body.email })?;
+    let user = budgeted("update_user", state.update_user.execute(&tenant, &id, command))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Negotiated(user.into()))
+}
+
+/// Request changing a user's email; `202` once the confirmation token is issued
+async fn request_email_change<U: UserRepository + ?Sized>(
+    State(state): State<Arc<UserState<U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    Negotiated(body): Negotiated<EmailChangeRequest>,
+) -> ApiResult<Response> {
+    let command = Validated::new(RequestEmailChangeCommand { new_email: body.new_email })?;
+    let issued =
+        budgeted("request_email_change", state.request_email_change.execute(&tenant, &id, command))
+            .await
+            .map_err(ApiError::from)?;
+    let response = EmailChangeResponse {
+        expires_at: timestamp::format(&issued.expires_at),
+        token: state.email_change_token_in_response.then_some(issued.token),
+    };
+    Ok(Negotiated(response).with_status(StatusCode::ACCEPTED))
+}
+
+/// Apply the email change confirmed by a token
+async fn confirm_email_change<U: UserRepository + ?Sized + 'static>(
+    State(state): State<Arc<UserState<U>>>,
+    TenantContext(tenant): TenantContext,
+    Negotiated(body): Negotiated<ConfirmEmailChangeRequest>,
+) -> ApiResult<Negotiated<UserResponse>> {
+    let user =
+        budgeted("confirm_email_change", state.confirm_email_change.execute(&tenant, &body.token))
+            .await
+            .map_err(ApiError::from)?;
+    Ok(Negotiated(user.into()))
+}
+
+/// Query parameters of `DELETE /users/{id}`
+#[derive(Debug, Deserialize)]
+pub struct DeleteUserQuery {
+    /// Also delete a user who still owns tasks (cascade-deleting them)
+    #[serde(default)]
+    pub force: bool,
+    /// Only report what would be deleted (`200` with counts instead of `204`)
+    #[serde(default)]
+    pub dry_run: bool,
+    /// `representation` to get the deleted user back (`200` instead of `204`); a dry
+    /// run reports its counts either way
+    #[serde(default, rename = "return")]
+    pub return_preference: ReturnPreference,
+}
+
+/// Delete a user by ID; a user owning tasks needs `?force=true`
+async fn delete_user<U: UserRepository + ?Sized + 'static>(
+    State(state): State<Arc<UserState<U>>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    ApiQuery(query): ApiQuery<DeleteUserQuery>,
+) -> ApiResult<Response> {
+    let options = DeleteUserOptions { force: query.force, dry_run: query.dry_run };
+    if query.return_preference == ReturnPreference::Representation && !query.dry_run {
+        let deletion = state.delete_user.execute_returning(&tenant, &id, options);
+        let (_, user) = budgeted("delete_user", deletion).await.map_err(ApiError::from)?;
+        return Ok(Negotiated(UserResponse::from(user)).into_response());
+    }
+    let impact = budgeted("delete_user", state.delete_user.execute(&tenant, &id, options))
         .await
         .map_err(ApiError::from)?;
-    Ok(Json(user.into()))
+    if !query.dry_run {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    }
+    let would_delete = DeletionCounts { users: impact.users, tasks: impact.tasks };
+    Ok(Negotiated(DryRunResponse { dry_run: true, would_delete }).into_response())
 }
 
-/// Delete a user by ID
-pub async fn delete_user(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> ApiResult<StatusCode> {
-    state.delete_user.execute(&id).await.map_err(ApiError::from)?;
-    Ok(StatusCode::NO_CONTENT)
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use crate::shared::infrastructure::config::Config;
+    use crate::test_support::{
+        get_if_modified_since, in_memory_app, in_memory_app_with, send, send_as, send_request,
+    };
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use serde_json::json;
+
+    fn raw_post(uri: &str, body: &'static str) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .expect("valid request")
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn update_user_racing_a_delete_should_finish_before_the_delete() {
+        use crate::testing::{test_app, FakeRepositories};
+        use std::time::Duration;
+
+        let fakes = FakeRepositories::default();
+        let app = test_app(&fakes).expect("valid app");
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        let uri = format!("/users/{}", user["id"].as_str().unwrap_or_default());
+        fakes.users.slow("update", Duration::from_millis(50));
+
+        // The delete comes after the update read the user, and waits for its lock
+        let update = json!({"name": "Bob", "email": "bob@example.com"});
+        let ((status, body), (deleted, _)) = tokio::join!(
+            send(&app, Method::PUT, &uri, Some(update)),
+            async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                send(&app, Method::DELETE, &uri, None).await
+            },
+        );
+        assert_eq!((status, &body["name"]), (StatusCode::OK, &json!("Bob")));
+        assert_eq!(deleted, StatusCode::NO_CONTENT);
+        assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::NOT_FOUND);
+    }
+
+    fn typed_post(content_type: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder().method(Method::POST).uri("/users");
+        if let Some(content_type) = content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        let body = r#"{"name":"Alice","email":"alice@example.com"}"#;
+        request.body(Body::from(body)).expect("valid request")
+    }
+
+    /// Media types `POST /users` lists as accepted
+    const ACCEPTED: &str = if cfg!(feature = "msgpack") {
+        "application/json or application/msgpack"
+    } else {
+        "application/json"
+    };
+
+    #[tokio::test]
+    async fn create_user_should_refuse_other_media_types_with_415() {
+        let (status, body) = send_request(&in_memory_app(), typed_post(Some("text/plain"))).await;
+        let unsupported = (StatusCode::UNSUPPORTED_MEDIA_TYPE, &json!("UNSUPPORTED_MEDIA_TYPE"));
+        assert_eq!((status, &body["code"]), unsupported);
+        assert_eq!(body["message"], format!("Content-Type must be {ACCEPTED}; got text/plain"));
+    }
+
+    #[tokio::test]
+    async fn create_user_should_accept_json_with_a_charset() {
+        let request = typed_post(Some("application/json; charset=utf-8"));
+        let (status, body) = send_request(&in_memory_app(), request).await;
+        assert_eq!((status, &body["name"]), (StatusCode::CREATED, &json!("Alice")));
+    }
+
+    #[tokio::test]
+    async fn create_user_should_read_an_untyped_body_as_json_unless_disabled() {
+        let (status, body) = send_request(&in_memory_app(), typed_post(None)).await;
+        assert_eq!((status, &body["name"]), (StatusCode::CREATED, &json!("Alice")));
+
+        let mut config = Config::default();
+        config.assume_json_content_type = false;
+        let (status, body) = send_request(&in_memory_app_with(&config), typed_post(None)).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["message"], format!("Content-Type must be {ACCEPTED}"));
+    }
+
+    #[tokio::test]
+    async fn create_user_should_return_400_for_malformed_json() {
+        let (status, body) = send_request(&in_memory_app(), raw_post("/users", "{\"name\":")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_BODY");
+    }
+
+    #[tokio::test]
+    async fn create_user_should_return_400_for_missing_field_or_wrong_type() {
+        let app = in_memory_app();
+        for payload in [json!({"name": "Alice"}), json!({"name": 1, "email": "a@example.com"})] {
+            let (status, body) = send(&app, Method::POST, "/users", Some(payload)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["code"], "INVALID_BODY");
+        }
+    }
+
+    #[tokio::test]
+    async fn create_user_should_return_422_for_domain_validation_error() {
+        let payload = json!({"name": "Alice", "email": "invalid"});
+        let (status, body) = send(&in_memory_app(), Method::POST, "/users", Some(payload)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "VALIDATION_ERROR");
+    }
+
+    #[tokio::test]
+    async fn create_user_should_list_every_invalid_field() {
+        let payload = json!({"name": "", "email": "invalid"});
+        let (status, body) = send(&in_memory_app(), Method::POST, "/users", Some(payload)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "VALIDATION_ERROR");
+        assert_eq!(
+            body["message"],
+            "Validation error: Name cannot be empty; Invalid email format"
+        );
+        let fields = json!([
+            {"field": "name", "message": "Name cannot be empty"},
+            {"field": "email", "message": "Invalid email format"},
+        ]);
+        assert_eq!(body["details"], json!({"fields": fields}));
+    }
+
+    #[tokio::test]
+    async fn create_user_should_return_400_for_validation_error_in_legacy_mode() {
+        let mut config = Config::default();
+        config.legacy_validation_status = true;
+        let payload = json!({"name": "Alice", "email": "invalid"});
+        let (status, body) =
+            send(&in_memory_app_with(&config), Method::POST, "/users", Some(payload)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "VALIDATION_ERROR");
+    }
+
+    #[tokio::test]
+    async fn get_user_should_return_304_when_not_modified_since() {
+        let app = in_memory_app();
+        let payload = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(payload)).await;
+        let uri = format!("/users/{}", user["id"].as_str().unwrap_or_default());
+
+        let (status, last_modified) = get_if_modified_since(&app, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let last_modified = last_modified.unwrap_or_default();
+        let (status, _) = get_if_modified_since(&app, &uri, Some(&last_modified)).await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+
+        let stale = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let (status, _) = get_if_modified_since(&app, &uri, Some(stale)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn delete_user_with_tasks_should_require_force() {
+        let app = in_memory_app();
+        let payload = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(payload)).await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/users/{}", user["id"].as_str().unwrap_or_default());
+
+        let (status, body) = send(&app, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "HAS_DEPENDENTS");
+        assert_eq!(body["details"], json!({"count": 1}));
+        assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::OK);
+
+        let (status, _) = send(&app, Method::DELETE, &format!("{uri}?force=true"), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn delete_user_without_tasks_should_not_need_force() {
+        let app = in_memory_app();
+        let payload = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(payload)).await;
+        let uri = format!("/users/{}", user["id"].as_str().unwrap_or_default());
+        assert_eq!(send(&app, Method::DELETE, &uri, None).await.0, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn delete_user_returning_representation_should_return_the_deleted_user() {
+        let app = in_memory_app();
+        let payload = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(payload)).await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/users/{}", user["id"].as_str().unwrap_or_default());
+
+        let (status, body) =
+            send(&app, Method::DELETE, &format!("{uri}?return=representation"), None).await;
+        assert_eq!((status, &body["code"]), (StatusCode::CONFLICT, &json!("HAS_DEPENDENTS")));
+        let dry_run = format!("{uri}?force=true&dry_run=true&return=representation");
+        let (status, body) = send(&app, Method::DELETE, &dry_run, None).await;
+        assert_eq!((status, &body["dryRun"]), (StatusCode::OK, &json!(true)));
+
+        let forced = format!("{uri}?force=true&return=representation");
+        let (status, body) = send(&app, Method::DELETE, &forced, None).await;
+        assert_eq!((status, body), (StatusCode::OK, user));
+        assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::NOT_FOUND);
+        let (status, _) = send(&app, Method::DELETE, &forced, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, body) = send(&app, Method::DELETE, &format!("{uri}?return=all"), None).await;
+        assert_eq!((status, &body["code"]), (StatusCode::BAD_REQUEST, &json!("INVALID_QUERY")));
+    }
+
+    #[tokio::test]
+    async fn delete_user_dry_run_should_report_counts_without_deleting() {
+        let app = in_memory_app();
+        let payload = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(payload)).await;
+        for title in ["Buy milk", "Walk dog"] {
+            let task = json!({"userId": user["id"], "title": title, "description": ""});
+            send(&app, Method::POST, "/tasks", Some(task)).await;
+        }
+        let uri = format!("/users/{}", user["id"].as_str().unwrap_or_default());
+
+        let dry_run = format!("{uri}?force=true&dry_run=true");
+        let (status, body) = send(&app, Method::DELETE, &dry_run, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({"dryRun": true, "wouldDelete": {"users": 1, "tasks": 2}}));
+        assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::OK);
+        let tasks_uri = format!("/tasks?user_id={}", user["id"].as_str().unwrap_or_default());
+        let (_, tasks) = send(&app, Method::GET, &tasks_uri, None).await;
+        assert_eq!(tasks.as_array().map(Vec::len), Some(2));
+
+        let (status, body) =
+            send(&app, Method::DELETE, &format!("{uri}?dry_run=true"), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "HAS_DEPENDENTS");
+        let (status, _) = send(&app, Method::DELETE, "/users/missing?dry_run=true", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn emails_should_be_unique_per_tenant() {
+        let app = in_memory_app();
+        let payload = json!({"name": "Alice", "email": "alice@example.com"});
+        for tenant in [None, Some("acme")] {
+            let (status, _) =
+                send_as(&app, tenant, Method::POST, "/users", Some(payload.clone())).await;
+            assert_eq!(status, StatusCode::CREATED, "{tenant:?}");
+        }
+        let (status, _) = send_as(&app, Some("acme"), Method::POST, "/users", Some(payload)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (_, users) = send_as(&app, Some("acme"), Method::GET, "/users", None).await;
+        assert_eq!(users.as_array().map(Vec::len), Some(1));
+        let uri = format!("/users/{}", users[0]["id"].as_str().unwrap_or_default());
+        assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn user_routes_should_require_a_valid_tenant_when_multi_tenancy_is_on() {
+        let mut config = Config::default();
+        config.multi_tenancy = true;
+        let app = in_memory_app_with(&config);
+
+        for tenant in [None, Some("Not A Tenant")] {
+            let (status, body) = send_as(&app, tenant, Method::GET, "/users", None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{tenant:?}");
+            assert_eq!(body["code"], "INVALID_TENANT");
+        }
+        let (status, _) = send_as(&app, Some("acme"), Method::GET, "/users", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(send(&app, Method::GET, "/health", None).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn list_users_should_project_requested_fields_and_reject_unknown_ones() {
+        let app = in_memory_app();
+        let payload = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(payload)).await;
+
+        let (status, body) = send(&app, Method::GET, "/users?fields=email", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!([{"id": user["id"], "email": "alice@example.com"}]));
+
+        let (status, body) = send(&app, Method::GET, "/users?fields=age", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_QUERY");
+        let message = "Unknown field 'age'; valid fields: id, name, email, taskCount";
+        assert_eq!(body["message"], message);
+    }
+
+    #[tokio::test]
+    async fn list_users_should_filter_by_email_domain() {
+        let app = in_memory_app();
+        for email in ["alice@example.com", "bob@Example.COM", "carol@example.org"] {
+            let payload = json!({"name": "User", "email": email});
+            send(&app, Method::POST, "/users", Some(payload)).await;
+        }
+
+        let uri = "/users?email_domain=example.com&fields=email";
+        let (status, body) = send(&app, Method::GET, uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let mut emails: Vec<&str> =
+            body.as_array().into_iter().flatten().filter_map(|u| u["email"].as_str()).collect();
+        emails.sort_unstable();
+        assert_eq!(emails, ["alice@example.com", "bob@Example.COM"]);
+
+        let uri = "/users?email_domain=example.com&limit=1&offset=1";
+        let (status, body) = send(&app, Method::GET, uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().map(Vec::len), Some(1));
+
+        for uri in ["/users?email_domain=a@b.c", "/users?limit=0", "/users?offset=10001"] {
+            let (status, body) = send(&app, Method::GET, uri, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["code"], "INVALID_QUERY");
+        }
+    }
+
+    #[tokio::test]
+    async fn list_users_should_embed_task_counts_only_when_requested() {
+        let app = in_memory_app();
+        let mut ids = Vec::new();
+        for (name, tasks) in [("Alice", 2), ("Bob", 0)] {
+            let email = format!("{}@example.com", name.to_lowercase());
+            let payload = json!({"name": name, "email": email});
+            let (_, user) = send(&app, Method::POST, "/users", Some(payload)).await;
+            for n in 0..tasks {
+                let title = format!("Task {n}");
+                let task = json!({"userId": user["id"], "title": title, "description": ""});
+                send(&app, Method::POST, "/tasks", Some(task)).await;
+            }
+            ids.push(user["id"].clone());
+        }
+
+        let (status, plain) = send(&app, Method::GET, "/users", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(plain.as_array().into_iter().flatten().all(|u| u.get("taskCount").is_none()));
+
+        let uri = "/users?embed=task_count&fields=taskCount";
+        let (status, body) = send(&app, Method::GET, uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let listed = body.as_array().cloned().unwrap_or_default();
+        assert_eq!(listed.len(), 2, "{body}");
+        for (id, count) in ids.iter().zip([2, 0]) {
+            assert!(listed.contains(&json!({"id": id, "taskCount": count})), "{body}");
+        }
+
+        let (status, body) = send(&app, Method::GET, "/users?embed=tasks", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Unsupported embed 'tasks'; supported embeds: task_count");
+        let uri = "/users?updated_since=2000-01-01T00:00:00Z&embed=task_count";
+        assert_eq!(send(&app, Method::GET, uri, None).await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn list_users_updated_since_should_page_by_watermark() {
+        let app = in_memory_app();
+        for email in ["alice@example.com", "bob@example.com", "carol@example.org"] {
+            let payload = json!({"name": "User", "email": email});
+            send(&app, Method::POST, "/users", Some(payload)).await;
+        }
+
+        let mut uri = "/users?updated_since=2000-01-01T00:00:00Z&limit=2".to_owned();
+        let mut emails = Vec::new();
+        for expected in [2, 1, 0] {
+            let (status, body) = send(&app, Method::GET, &uri, None).await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            let items = body["items"].as_array().cloned().unwrap_or_default();
+            assert_eq!(items.len(), expected, "{body}");
+            emails.extend(items.iter().filter_map(|u| u["email"].as_str().map(str::to_owned)));
+            let watermark = body["watermark"].as_str().unwrap_or_default();
+            let next = format!("/users?updated_since={watermark}&limit=2");
+            if expected == 0 {
+                assert_eq!(next, uri, "an empty delta keeps the watermark");
+            }
+            uri = next;
+        }
+        emails.sort_unstable();
+        assert_eq!(emails, ["alice@example.com", "bob@example.com", "carol@example.org"]);
+
+        for uri in [
+            "/users?updated_since=yesterday",
+            "/users?updated_since=2000-01-01T00:00:00Z&offset=1",
+            "/users?updated_since=2000-01-01T00:00:00Z&fields=email",
+        ] {
+            let (status, body) = send(&app, Method::GET, uri, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["code"], "INVALID_QUERY");
+        }
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn list_users_should_link_the_next_page_until_the_last() {
+        use tower::ServiceExt;
+        let app = in_memory_app();
+        for email in ["alice@example.com", "bob@example.com", "carol@example.org"] {
+            let payload = json!({"name": "User", "email": email});
+            send(&app, Method::POST, "/users", Some(payload)).await;
+        }
+        let links = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let request = Request::get(uri).body(Body::empty()).expect("request");
+                let response = app.oneshot(request).await.expect("infallible router");
+                let links = response.headers()[header::LINK].to_str().map(str::to_owned);
+                links.expect("ASCII links")
+            }
+        };
+
+        assert_eq!(
+            links("/users?email_domain=example.com&limit=1").await,
+            "</users?email_domain=example.com&limit=1&offset=0>; rel=\"first\", \
+             </users?email_domain=example.com&limit=1&offset=1>; rel=\"next\""
+        );
+        let last = links("/users?email_domain=example.com&offset=1").await;
+        assert!(last.contains("offset=0>; rel=\"prev\""), "{last}");
+        assert!(!last.contains("rel=\"next\""), "{last}");
+    }
+
+    #[tokio::test]
+    async fn email_change_should_apply_only_after_confirmation() {
+        let mut config = Config::default();
+        config.email_change_token_in_response = true;
+        let app = in_memory_app_with(&config);
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let uri = format!("/users/{}", user["id"].as_str().unwrap_or_default());
+
+        let change = Some(json!({"newEmail": "alice@new.example"}));
+        let (status, issued) =
+            send(&app, Method::POST, &format!("{uri}/email-change"), change).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert!(issued["expiresAt"].is_string());
+        let (_, unchanged) = send(&app, Method::GET, &uri, None).await;
+        assert_eq!(unchanged["email"], "alice@example.com");
+
+        let confirm = Some(json!({"token": issued["token"]}));
+        let (status, confirmed) =
+            send(&app, Method::POST, "/users/email-change/confirm", confirm.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(confirmed["email"], "alice@new.example");
+        let (status, reused) =
+            send(&app, Method::POST, "/users/email-change/confirm", confirm).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(reused["code"], "CONFLICT");
+
+        let unknown = Some(json!({"token": "0".repeat(64)}));
+        let (status, _) = send(&app, Method::POST, "/users/email-change/confirm", unknown).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn email_change_token_should_stay_out_of_the_response_by_default() {
+        let app = in_memory_app();
+        let (_, user) = send(
+            &app,
+            Method::POST,
+            "/users",
+            Some(json!({"name": "Alice", "email": "alice@example.com"})),
+        )
+        .await;
+        let uri = format!("/users/{}/email-change", user["id"].as_str().unwrap_or_default());
+
+        let (status, issued) =
+            send(&app, Method::POST, &uri, Some(json!({"newEmail": "alice@new.example"}))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert!(issued.get("token").is_none(), "{issued}");
+
+        let (status, invalid) =
+            send(&app, Method::POST, &uri, Some(json!({"newEmail": "not-an-email"}))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(invalid["details"]["fields"][0]["field"], "newEmail");
+    }
 }
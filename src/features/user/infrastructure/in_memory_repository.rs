@@ -0,0 +1,308 @@
+//! In-memory user repository implementation for tests and examples
+
+use crate::features::user::domain::{
+    EmailChangeRepository, PendingEmailChange, SyncWatermark, User, UserRepository,
+    UserTransaction,
+};
+use crate::shared::domain::{DomainError, Entity, Page, TenantId, UserId};
+use crate::shared::infrastructure::maintenance::CleanupTask;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+/// Users with their tenant, by user ID
+pub(crate) type StoredUsers = BTreeMap<String, (TenantId, User)>;
+
+/// In-memory implementation of user repository, keyed by user ID
+///
+/// IDs are unique across tenants and emails unique within a tenant, as the
+/// constraints of the `users` table.
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    users: RwLock<StoredUsers>,
+}
+
+impl InMemoryUserRepository {
+    /// Users of `tenant` in ID order
+    async fn of_tenant(&self, tenant: &TenantId) -> Vec<User> {
+        let users = self.users.read().await;
+        users.values().filter(|(t, _)| t == tenant).map(|(_, u)| u.clone()).collect()
+    }
+
+    /// Lock the users for writing, e.g. for the length of a unit of work
+    pub(crate) async fn write(&self) -> RwLockWriteGuard<'_, StoredUsers> {
+        self.users.write().await
+    }
+}
+
+/// Insert `user` into `users`, enforcing the constraints of the `users` table
+pub(crate) fn insert_user(
+    users: &mut StoredUsers,
+    tenant: &TenantId,
+    user: &User,
+) -> Result<(), DomainError> {
+    if users.values().any(|(t, u)| t == tenant && u.email() == user.email()) {
+        return Err(DomainError::already_exists(UserId::entity_name(), "email"));
+    }
+    if users.contains_key(user.id().value()) {
+        return Err(DomainError::already_exists(UserId::entity_name(), "ID"));
+    }
+    users.insert(user.id().value().to_owned(), (tenant.clone(), touched(user)));
+    Ok(())
+}
+
+/// Update `user` in `users`, returning it as it was; fails as the `UPDATE` would
+fn update_user(
+    users: &mut StoredUsers,
+    tenant: &TenantId,
+    user: &User,
+) -> Result<User, DomainError> {
+    if users.get(user.id().value()).is_none_or(|(t, _)| t != tenant) {
+        return Err(DomainError::not_found(UserId::entity_name()));
+    }
+    if users.values().any(|(t, u)| t == tenant && u.email() == user.email() && u.id() != user.id())
+    {
+        return Err(DomainError::already_exists(UserId::entity_name(), "email"));
+    }
+    let stored = users.get_mut(user.id().value()).map(|(_, stored)| stored);
+    let stored = stored.ok_or_else(|| DomainError::not_found(UserId::entity_name()))?;
+    Ok(std::mem::replace(stored, touched(user)))
+}
+
+#[async_trait::async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        let users = self.users.read().await;
+        Ok(users.get(id.value()).filter(|(t, _)| t == tenant).map(|(_, u)| u.clone()))
+    }
+
+    async fn find_by_ids(
+        &self,
+        tenant: &TenantId,
+        ids: &[UserId],
+    ) -> Result<Vec<User>, DomainError> {
+        let users = self.of_tenant(tenant).await;
+        Ok(users.into_iter().filter(|u| ids.contains(u.id())).collect())
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        email_domain: Option<&str>,
+        page: Page,
+    ) -> Result<Vec<User>, DomainError> {
+        let users = self.of_tenant(tenant).await;
+        let matching = users
+            .into_iter()
+            .filter(|u| email_domain.is_none_or(|domain| u.email().provider_is(domain)));
+        Ok(page.slice(matching))
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<User>, DomainError> {
+        Ok(self.of_tenant(tenant).await)
+    }
+
+    async fn find_updated_since(
+        &self,
+        tenant: &TenantId,
+        since: &SyncWatermark,
+        limit: u32,
+    ) -> Result<Vec<User>, DomainError> {
+        let mut users: Vec<(DateTime<Utc>, User)> = self
+            .of_tenant(tenant)
+            .await
+            .into_iter()
+            .filter_map(|u| u.updated_at().map(|at| (at, u)))
+            .filter(|(at, u)| since.precedes(*at, u.id()))
+            .collect();
+        users.sort_by(|(a, u), (b, v)| a.cmp(b).then_with(|| u.id().value().cmp(v.id().value())));
+        Ok(users.into_iter().take(limit as usize).map(|(_, u)| u).collect())
+    }
+
+    async fn insert(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        insert_user(&mut *self.users.write().await, tenant, user)
+    }
+
+    async fn update(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        update_user(&mut *self.users.write().await, tenant, user).map(drop)
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &UserId) -> Result<bool, DomainError> {
+        let mut users = self.users.write().await;
+        if users.get(id.value()).is_none_or(|(t, _)| t != tenant) {
+            return Ok(false);
+        }
+        Ok(users.remove(id.value()).is_some())
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        let mut users = self.users.write().await;
+        if users.get(id.value()).is_none_or(|(t, _)| t != tenant) {
+            return Ok(None);
+        }
+        Ok(users.remove(id.value()).map(|(_, user)| user))
+    }
+
+    async fn begin(&self) -> Result<Box<dyn UserTransaction + '_>, DomainError> {
+        let users = self.users.write().await;
+        Ok(Box::new(InMemoryUserTransaction { users, replaced: Vec::new() }))
+    }
+}
+
+/// Transaction of [`InMemoryUserRepository`], locking every user until it ends and
+/// undoing its updates when dropped uncommitted
+struct InMemoryUserTransaction<'a> {
+    users: RwLockWriteGuard<'a, StoredUsers>,
+    /// Users updated so far, as they were before
+    replaced: Vec<User>,
+}
+
+#[async_trait::async_trait]
+impl UserTransaction for InMemoryUserTransaction<'_> {
+    async fn find_by_id_for_update(
+        &mut self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        Ok(self.users.get(id.value()).filter(|(t, _)| t == tenant).map(|(_, u)| u.clone()))
+    }
+
+    async fn update(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        let replaced = update_user(&mut self.users, tenant, user)?;
+        self.replaced.push(replaced);
+        Ok(())
+    }
+
+    async fn commit(mut self: Box<Self>) -> Result<(), DomainError> {
+        self.replaced.clear();
+        Ok(())
+    }
+}
+
+impl Drop for InMemoryUserTransaction<'_> {
+    fn drop(&mut self) {
+        for user in self.replaced.drain(..).rev() {
+            if let Some((_, stored)) = self.users.get_mut(user.id().value()) {
+                *stored = user;
+            }
+        }
+    }
+}
+
+/// In-memory implementation of email change repository, keyed by token hash
+#[derive(Default)]
+pub struct InMemoryEmailChangeRepository {
+    changes: RwLock<BTreeMap<String, PendingEmailChange>>,
+}
+
+#[async_trait::async_trait]
+impl EmailChangeRepository for InMemoryEmailChangeRepository {
+    async fn save(&self, change: &PendingEmailChange) -> Result<(), DomainError> {
+        let mut changes = self.changes.write().await;
+        changes.retain(|_, c| c.user_id() != change.user_id() || c.confirmed_at().is_some());
+        changes.insert(change.token_hash().to_owned(), change.clone());
+        Ok(())
+    }
+
+    async fn find_by_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<PendingEmailChange>, DomainError> {
+        Ok(self.changes.read().await.get(token_hash).cloned())
+    }
+
+    async fn mark_confirmed(&self, token_hash: &str) -> Result<bool, DomainError> {
+        let mut changes = self.changes.write().await;
+        let Some(change) = changes.get_mut(token_hash).filter(|c| c.confirmed_at().is_none())
+        else {
+            return Ok(false);
+        };
+        *change = PendingEmailChange::reconstitute(
+            change.user_id().clone(),
+            change.new_email().clone(),
+            change.token_hash().to_owned(),
+            change.expires_at(),
+            Some(chrono::Utc::now()),
+        );
+        Ok(true)
+    }
+}
+
+/// Deletes email changes that expired before the cutoff, confirmed or not
+#[async_trait::async_trait]
+impl CleanupTask for InMemoryEmailChangeRepository {
+    fn name(&self) -> &'static str {
+        "email_changes"
+    }
+
+    async fn delete_batch(&self, before: DateTime<Utc>, limit: u32) -> Result<u64, DomainError> {
+        let mut changes = self.changes.write().await;
+        let mut deleted = 0;
+        changes.retain(|_, change| {
+            let delete = change.expires_at() < before && deleted < u64::from(limit);
+            deleted += u64::from(delete);
+            !delete
+        });
+        Ok(deleted)
+    }
+}
+
+/// Copy of `user` with `updated_at` set to now, as the database default/update would
+fn touched(user: &User) -> User {
+    User::reconstitute(
+        user.id().clone(),
+        user.name().to_owned(),
+        user.email().clone(),
+        Some(chrono::Utc::now()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::repository_contract;
+
+    #[tokio::test]
+    async fn lists_should_be_ordered_by_id() {
+        repository_contract::users_should_list_in_id_order(&InMemoryUserRepository::default())
+            .await;
+    }
+
+    #[tokio::test]
+    async fn updates_of_deleted_users_should_fail() {
+        let users = InMemoryUserRepository::default();
+        repository_contract::users_should_not_update_deleted_rows(&users).await;
+    }
+
+    #[tokio::test]
+    async fn deletions_should_return_the_deleted_user() {
+        let users = InMemoryUserRepository::default();
+        repository_contract::users_should_return_deleted_rows(&users).await;
+    }
+
+    #[tokio::test]
+    async fn users_read_for_update_should_stay_locked_until_the_transaction_ends() {
+        let users = InMemoryUserRepository::default();
+        repository_contract::users_should_lock_rows_read_for_update(&users).await;
+    }
+
+    #[tokio::test]
+    async fn syncs_should_return_tied_updates_exactly_once() {
+        let users = InMemoryUserRepository::default();
+        let pin = async |id: &UserId, at| {
+            if let Some((_, user)) = users.write().await.get_mut(id.value()) {
+                let (name, email) = (user.name().to_owned(), user.email().clone());
+                *user = User::reconstitute(id.clone(), name, email, Some(at));
+            }
+        };
+        repository_contract::users_should_sync_ties_exactly_once(&users, pin).await;
+    }
+}
@@ -0,0 +1,198 @@
+//! User repository decorators recording call timings
+
+use crate::features::user::domain::{
+    EmailChangeRepository, PendingEmailChange, SyncWatermark, User, UserRepository,
+    UserTransaction,
+};
+use crate::shared::domain::{DomainError, Page, TenantId, UserId};
+use crate::shared::infrastructure::instrumentation::timed;
+use std::sync::Arc;
+
+const ENTITY: &str = "user";
+
+/// Times every call of the wrapped user repository (see
+/// [`instrumentation`](crate::shared::infrastructure::instrumentation) for how it
+/// composes with other decorators)
+pub struct InstrumentedUserRepository {
+    inner: Arc<dyn UserRepository>,
+}
+
+impl InstrumentedUserRepository {
+    /// Wrap `inner`
+    #[must_use]
+    pub fn new(inner: Arc<dyn UserRepository>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait::async_trait]
+impl UserRepository for InstrumentedUserRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        timed(ENTITY, "find_by_id", self.inner.find_by_id(tenant, id)).await
+    }
+
+    async fn find_by_ids(
+        &self,
+        tenant: &TenantId,
+        ids: &[UserId],
+    ) -> Result<Vec<User>, DomainError> {
+        timed(ENTITY, "find_by_ids", self.inner.find_by_ids(tenant, ids)).await
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        email_domain: Option<&str>,
+        page: Page,
+    ) -> Result<Vec<User>, DomainError> {
+        timed(ENTITY, "find_page", self.inner.find_page(tenant, email_domain, page)).await
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<User>, DomainError> {
+        timed(ENTITY, "find_all_unbounded", self.inner.find_all_unbounded(tenant)).await
+    }
+
+    async fn find_updated_since(
+        &self,
+        tenant: &TenantId,
+        since: &SyncWatermark,
+        limit: u32,
+    ) -> Result<Vec<User>, DomainError> {
+        timed(ENTITY, "find_updated_since", self.inner.find_updated_since(tenant, since, limit))
+            .await
+    }
+
+    async fn insert(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        timed(ENTITY, "insert", self.inner.insert(tenant, user)).await
+    }
+
+    async fn update(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        timed(ENTITY, "update", self.inner.update(tenant, user)).await
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &UserId) -> Result<bool, DomainError> {
+        timed(ENTITY, "delete", self.inner.delete(tenant, id)).await
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        timed(ENTITY, "delete_returning", self.inner.delete_returning(tenant, id)).await
+    }
+
+    /// Times beginning the transaction; the transaction times its own calls
+    async fn begin(&self) -> Result<Box<dyn UserTransaction + '_>, DomainError> {
+        let inner = timed(ENTITY, "begin", self.inner.begin()).await?;
+        Ok(Box::new(InstrumentedUserTransaction { inner }))
+    }
+}
+
+/// Times every call of the wrapped user transaction, under the names of the
+/// repository methods it mirrors
+struct InstrumentedUserTransaction<'a> {
+    inner: Box<dyn UserTransaction + 'a>,
+}
+
+#[async_trait::async_trait]
+impl UserTransaction for InstrumentedUserTransaction<'_> {
+    async fn find_by_id_for_update(
+        &mut self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        let find = self.inner.find_by_id_for_update(tenant, id);
+        timed(ENTITY, "find_by_id_for_update", find).await
+    }
+
+    async fn update(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        timed(ENTITY, "update", self.inner.update(tenant, user)).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DomainError> {
+        timed(ENTITY, "commit", self.inner.commit()).await
+    }
+}
+
+/// Times every call of the wrapped email change repository
+pub struct InstrumentedEmailChangeRepository {
+    inner: Arc<dyn EmailChangeRepository>,
+}
+
+impl InstrumentedEmailChangeRepository {
+    /// Wrap `inner`
+    #[must_use]
+    pub fn new(inner: Arc<dyn EmailChangeRepository>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailChangeRepository for InstrumentedEmailChangeRepository {
+    async fn save(&self, change: &PendingEmailChange) -> Result<(), DomainError> {
+        timed("email_change", "save", self.inner.save(change)).await
+    }
+
+    async fn find_by_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<PendingEmailChange>, DomainError> {
+        timed("email_change", "find_by_token_hash", self.inner.find_by_token_hash(token_hash)).await
+    }
+
+    async fn mark_confirmed(&self, token_hash: &str) -> Result<bool, DomainError> {
+        timed("email_change", "mark_confirmed", self.inner.mark_confirmed(token_hash)).await
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::user::infrastructure::InMemoryUserRepository;
+    use crate::shared::domain::Entity;
+    use crate::test_support::recorded_repository_calls;
+
+    #[test]
+    fn calls_should_be_recorded_with_entity_method_and_outcome() {
+        let calls = recorded_repository_calls(async {
+            let repo = InstrumentedUserRepository::new(Arc::new(InMemoryUserRepository::default()));
+            let user = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+                .expect("valid user");
+            let tenant = TenantId::default();
+            repo.insert(&tenant, &user).await.expect("insert");
+            assert!(repo.insert(&tenant, &user).await.is_err());
+            repo.find_by_id(&tenant, user.id()).await.expect("find");
+        });
+        assert_eq!(calls, ["user.find_by_id ok: 1", "user.insert error: 1", "user.insert ok: 1"]);
+    }
+
+    #[test]
+    fn transaction_calls_should_be_recorded_too() {
+        let calls = recorded_repository_calls(async {
+            let repo = InstrumentedUserRepository::new(Arc::new(InMemoryUserRepository::default()));
+            let user = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+                .expect("valid user");
+            let tenant = TenantId::default();
+            repo.insert(&tenant, &user).await.expect("insert");
+            let mut transaction = repo.begin().await.expect("begin");
+            let found = transaction.find_by_id_for_update(&tenant, user.id()).await;
+            let found = found.expect("find").expect("user");
+            transaction.update(&tenant, &found).await.expect("update");
+            transaction.commit().await.expect("commit");
+        });
+        let expected = [
+            "user.begin ok: 1",
+            "user.commit ok: 1",
+            "user.find_by_id_for_update ok: 1",
+            "user.insert ok: 1",
+            "user.update ok: 1",
+        ];
+        assert_eq!(calls, expected);
+    }
+}
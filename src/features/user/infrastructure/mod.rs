@@ -1,6 +1,12 @@
 //! User infrastructure layer
 
+pub mod email_change_webhook;
 pub mod http;
+pub mod in_memory_repository;
+pub mod instrumented_repository;
 pub mod pg_repository;
 
-pub use pg_repository::PgUserRepository;
+pub use email_change_webhook::WebhookEmailChangeNotifier;
+pub use in_memory_repository::{InMemoryEmailChangeRepository, InMemoryUserRepository};
+pub use instrumented_repository::{InstrumentedEmailChangeRepository, InstrumentedUserRepository};
+pub use pg_repository::{PgEmailChangeRepository, PgUserRepository};
@@ -1,9 +1,19 @@
-//! `PostgreSQL` user repository implementation
+//! `PostgreSQL` user repository implementations
 
-use crate::features::user::domain::{User, UserRepository};
-use crate::shared::domain::{DomainError, Email, Entity, UserId};
-use crate::shared::infrastructure::database::map_db_error;
-use sqlx::PgPool;
+use crate::features::user::domain::{
+    EmailChangeRepository, PendingEmailChange, SyncWatermark, User, UserRepository,
+    UserTransaction,
+};
+use crate::shared::domain::{DomainError, Email, Entity, Page, TenantId, UserId};
+use crate::shared::infrastructure::database::{acquire, run_query};
+use crate::shared::infrastructure::maintenance::CleanupTask;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::{PgPool, Postgres};
+
+/// Query without results, its arguments borrowed for `'a`
+type PgQuery<'a> = Query<'a, Postgres, PgArguments>;
 
 /// `PostgreSQL` implementation of user repository
 #[derive(Clone)]
@@ -13,6 +23,7 @@ pub struct PgUserRepository {
 
 impl PgUserRepository {
     /// Create a new `PostgreSQL` user repository
+    #[must_use]
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
@@ -20,59 +31,288 @@ impl PgUserRepository {
 
 #[async_trait::async_trait]
 impl UserRepository for PgUserRepository {
-    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, DomainError> {
-        Ok(sqlx::query_as::<_, UserRow>("SELECT id, name, email, updated_at FROM users WHERE id = $1")
-            .bind(id.value())
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| map_db_error(e, "find", "user"))?
-            .map(UserRow::into_domain))
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        let query = sqlx::query_as::<_, UserRow>(
+            "SELECT id, name, email, updated_at FROM users WHERE tenant_id = $1 AND id = $2",
+        )
+        .bind(tenant.value())
+        .bind(id.value());
+        let mut conn = acquire(&self.pool, "find", "user").await?;
+        let row = run_query(query.fetch_optional(&mut *conn), "find", "user").await?;
+        Ok(row.map(UserRow::into_domain))
     }
 
-    async fn find_all(&self) -> Result<Vec<User>, DomainError> {
-        Ok(sqlx::query_as::<_, UserRow>("SELECT id, name, email, updated_at FROM users")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| map_db_error(e, "find_all", "user"))?
-            .into_iter()
-            .map(UserRow::into_domain)
-            .collect())
-    }
-
-    async fn insert(&self, user: &User) -> Result<(), DomainError> {
-        sqlx::query("INSERT INTO users (id, name, email) VALUES ($1, $2, $3)")
-            .bind(user.id().value())
-            .bind(user.name())
-            .bind(user.email().value())
-            .execute(&self.pool)
-            .await
-            .map_err(|e| map_db_error(e, "insert", "user"))?;
+    async fn find_by_ids(
+        &self,
+        tenant: &TenantId,
+        ids: &[UserId],
+    ) -> Result<Vec<User>, DomainError> {
+        let ids: Vec<&str> = ids.iter().map(UserId::value).collect();
+        let query = sqlx::query_as::<_, UserRow>(
+            "SELECT id, name, email, updated_at FROM users WHERE tenant_id = $1 AND id = ANY($2) \
+             ORDER BY id",
+        )
+        .bind(tenant.value())
+        .bind(ids);
+        let mut conn = acquire(&self.pool, "find_by_ids", "user").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_by_ids", "user").await?;
+        Ok(rows.into_iter().map(UserRow::into_domain).collect())
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        email_domain: Option<&str>,
+        page: Page,
+    ) -> Result<Vec<User>, DomainError> {
+        let query = sqlx::query_as::<_, UserRow>(
+            r"SELECT id, name, email, updated_at FROM users
+              WHERE tenant_id = $1
+              AND ($2::TEXT IS NULL OR email ILIKE ('%@' || $2) ESCAPE '\')
+              ORDER BY id LIMIT $3 OFFSET $4",
+        )
+        .bind(tenant.value())
+        .bind(email_domain.map(escape_like))
+        .bind(i64::from(page.limit))
+        .bind(i64::from(page.offset));
+        let mut conn = acquire(&self.pool, "find_page", "user").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_page", "user").await?;
+        Ok(rows.into_iter().map(UserRow::into_domain).collect())
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<User>, DomainError> {
+        let query = sqlx::query_as::<_, UserRow>(
+            "SELECT id, name, email, updated_at FROM users WHERE tenant_id = $1 ORDER BY id",
+        )
+        .bind(tenant.value());
+        let mut conn = acquire(&self.pool, "find_all", "user").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_all", "user").await?;
+        Ok(rows.into_iter().map(UserRow::into_domain).collect())
+    }
+
+    async fn find_updated_since(
+        &self,
+        tenant: &TenantId,
+        since: &SyncWatermark,
+        limit: u32,
+    ) -> Result<Vec<User>, DomainError> {
+        let query = sqlx::query_as::<_, UserRow>(
+            r"SELECT id, name, email, updated_at FROM users
+              WHERE tenant_id = $1 AND updated_at >= $2
+              AND (updated_at > $2 OR ($3::TEXT IS NOT NULL AND id > $3))
+              ORDER BY updated_at, id LIMIT $4",
+        )
+        .bind(tenant.value())
+        .bind(since.updated_at)
+        .bind(since.after_id.as_ref().map(UserId::value))
+        .bind(i64::from(limit));
+        let mut conn = acquire(&self.pool, "find_updated_since", "user").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_updated_since", "user").await?;
+        Ok(rows.into_iter().map(UserRow::into_domain).collect())
+    }
+
+    async fn insert(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        let mut conn = acquire(&self.pool, "insert", "user").await?;
+        run_query(insert_query(tenant, user).execute(&mut *conn), "insert", "user").await?;
         Ok(())
     }
 
-    async fn update(&self, user: &User) -> Result<(), DomainError> {
-        sqlx::query(
-            "UPDATE users SET name = $1, email = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3",
+    async fn update(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        let query = update_query(tenant, user);
+        let mut conn = acquire(&self.pool, "update", "user").await?;
+        let result = run_query(query.execute(&mut *conn), "update", "user").await?;
+        if result.rows_affected() == 0 {
+            return Err(DomainError::not_found(UserId::entity_name()));
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &UserId) -> Result<bool, DomainError> {
+        let query = sqlx::query("DELETE FROM users WHERE tenant_id = $1 AND id = $2")
+            .bind(tenant.value())
+            .bind(id.value());
+        let mut conn = acquire(&self.pool, "delete", "user").await?;
+        let result = run_query(query.execute(&mut *conn), "delete", "user").await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        let query = sqlx::query_as::<_, UserRow>(
+            "DELETE FROM users WHERE tenant_id = $1 AND id = $2 \
+             RETURNING id, name, email, updated_at",
+        )
+        .bind(tenant.value())
+        .bind(id.value());
+        let mut conn = acquire(&self.pool, "delete", "user").await?;
+        let row = run_query(query.fetch_optional(&mut *conn), "delete", "user").await?;
+        Ok(row.map(UserRow::into_domain))
+    }
+
+    async fn begin(&self) -> Result<Box<dyn UserTransaction + '_>, DomainError> {
+        let conn = acquire(&self.pool, "begin", "transaction").await?;
+        let tx = run_query(sqlx::Transaction::begin(conn, None), "begin", "transaction").await?;
+        Ok(Box::new(PgUserTransaction { tx }))
+    }
+}
+
+/// Transaction of [`PgUserRepository`]; sqlx rolls it back when dropped uncommitted
+struct PgUserTransaction {
+    tx: sqlx::Transaction<'static, Postgres>,
+}
+
+#[async_trait::async_trait]
+impl UserTransaction for PgUserTransaction {
+    async fn find_by_id_for_update(
+        &mut self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        let query = sqlx::query_as::<_, UserRow>(
+            "SELECT id, name, email, updated_at FROM users WHERE tenant_id = $1 AND id = $2 \
+             FOR UPDATE",
         )
+        .bind(tenant.value())
+        .bind(id.value());
+        let row = run_query(query.fetch_optional(&mut *self.tx), "find", "user").await?;
+        Ok(row.map(UserRow::into_domain))
+    }
+
+    async fn update(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        let query = update_query(tenant, user);
+        let result = run_query(query.execute(&mut *self.tx), "update", "user").await?;
+        if result.rows_affected() == 0 {
+            return Err(DomainError::not_found(UserId::entity_name()));
+        }
+        Ok(())
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DomainError> {
+        run_query(self.tx.commit(), "commit", "transaction").await
+    }
+}
+
+/// Query inserting `user`, shared with the unit of work
+pub(crate) fn insert_query<'a>(tenant: &'a TenantId, user: &'a User) -> PgQuery<'a> {
+    sqlx::query("INSERT INTO users (tenant_id, id, name, email) VALUES ($1, $2, $3, $4)")
+        .bind(tenant.value())
+        .bind(user.id().value())
         .bind(user.name())
         .bind(user.email().value())
-        .bind(user.id().value())
-        .execute(&self.pool)
-        .await
-        .map_err(|e| map_db_error(e, "update", "user"))?;
+}
+
+/// Query updating the name and email of `user`
+fn update_query<'a>(tenant: &'a TenantId, user: &'a User) -> PgQuery<'a> {
+    sqlx::query(
+        "UPDATE users SET name = $1, email = $2, updated_at = CURRENT_TIMESTAMP \
+         WHERE tenant_id = $3 AND id = $4",
+    )
+    .bind(user.name())
+    .bind(user.email().value())
+    .bind(tenant.value())
+    .bind(user.id().value())
+}
+
+/// `PostgreSQL` implementation of email change repository
+#[derive(Clone)]
+pub struct PgEmailChangeRepository {
+    pool: PgPool,
+}
+
+impl PgEmailChangeRepository {
+    /// Create a new `PostgreSQL` email change repository
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailChangeRepository for PgEmailChangeRepository {
+    async fn save(&self, change: &PendingEmailChange) -> Result<(), DomainError> {
+        // One statement, so a concurrent request of the same user cannot interleave
+        let query = sqlx::query(
+            "WITH superseded AS (
+                 DELETE FROM pending_email_changes WHERE user_id = $1 AND confirmed_at IS NULL
+             )
+             INSERT INTO pending_email_changes (user_id, new_email, token_hash, expires_at)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(change.user_id().value())
+        .bind(change.new_email().value())
+        .bind(change.token_hash())
+        .bind(change.expires_at());
+        let mut conn = acquire(&self.pool, "save", "email change").await?;
+        run_query(query.execute(&mut *conn), "save", "email change").await?;
         Ok(())
     }
 
-    async fn delete(&self, id: &UserId) -> Result<bool, DomainError> {
-        let result = sqlx::query("DELETE FROM users WHERE id = $1")
-            .bind(id.value())
-            .execute(&self.pool)
-            .await
-            .map_err(|e| map_db_error(e, "delete", "user"))?;
+    async fn find_by_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<PendingEmailChange>, DomainError> {
+        let query = sqlx::query_as::<_, EmailChangeRow>(
+            "SELECT user_id, new_email, token_hash, expires_at, confirmed_at
+             FROM pending_email_changes WHERE token_hash = $1",
+        )
+        .bind(token_hash);
+        let mut conn = acquire(&self.pool, "find", "email change").await?;
+        let row = run_query(query.fetch_optional(&mut *conn), "find", "email change").await?;
+        Ok(row.map(EmailChangeRow::into_domain))
+    }
+
+    async fn mark_confirmed(&self, token_hash: &str) -> Result<bool, DomainError> {
+        let query = sqlx::query(
+            "UPDATE pending_email_changes SET confirmed_at = CURRENT_TIMESTAMP
+             WHERE token_hash = $1 AND confirmed_at IS NULL",
+        )
+        .bind(token_hash);
+        let mut conn = acquire(&self.pool, "confirm", "email change").await?;
+        let result = run_query(query.execute(&mut *conn), "confirm", "email change").await?;
         Ok(result.rows_affected() > 0)
     }
 }
 
+/// Deletes email changes that expired before the cutoff, confirmed or not
+#[async_trait::async_trait]
+impl CleanupTask for PgEmailChangeRepository {
+    fn name(&self) -> &'static str {
+        "email_changes"
+    }
+
+    async fn delete_batch(&self, before: DateTime<Utc>, limit: u32) -> Result<u64, DomainError> {
+        let query = sqlx::query(
+            "DELETE FROM pending_email_changes WHERE token_hash IN ( \
+                 SELECT token_hash FROM pending_email_changes WHERE expires_at < $1 \
+                 ORDER BY expires_at LIMIT $2 FOR UPDATE SKIP LOCKED)",
+        )
+        .bind(before)
+        .bind(i64::from(limit));
+        let mut conn = acquire(&self.pool, "cleanup", "email change").await?;
+        let result = run_query(query.execute(&mut *conn), "cleanup", "email change").await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Escape the `LIKE` wildcards `%` and `_` (and the escape character `\`) in `value`
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 #[derive(sqlx::FromRow)]
 struct UserRow {
     id: String,
@@ -91,3 +331,185 @@ impl UserRow {
         )
     }
 }
+
+#[derive(sqlx::FromRow)]
+struct EmailChangeRow {
+    user_id: String,
+    new_email: String,
+    token_hash: String,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    confirmed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl EmailChangeRow {
+    fn into_domain(self) -> PendingEmailChange {
+        PendingEmailChange::reconstitute(
+            UserId::from_trusted(self.user_id),
+            Email::from_trusted(self.new_email),
+            self.token_hash,
+            self.expires_at,
+            self.confirmed_at,
+        )
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::testing::repository_contract;
+
+    fn user(name: &str) -> User {
+        User::new(UserId::generate(), name.to_owned(), &format!("{name}@example.com"))
+            .expect("valid user")
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn lists_should_be_ordered_by_id(pool: PgPool) {
+        repository_contract::users_should_list_in_id_order(&PgUserRepository::new(pool)).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn updates_of_deleted_users_should_fail(pool: PgPool) {
+        let users = PgUserRepository::new(pool);
+        repository_contract::users_should_not_update_deleted_rows(&users).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn deletions_should_return_the_deleted_user(pool: PgPool) {
+        let users = PgUserRepository::new(pool);
+        repository_contract::users_should_return_deleted_rows(&users).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn users_read_for_update_should_stay_locked_until_the_transaction_ends(pool: PgPool) {
+        let users = PgUserRepository::new(pool);
+        repository_contract::users_should_lock_rows_read_for_update(&users).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn syncs_should_return_tied_updates_exactly_once(pool: PgPool) {
+        let pin = async |id: &UserId, at: DateTime<Utc>| {
+            sqlx::query("UPDATE users SET updated_at = $2 WHERE id = $1")
+                .bind(id.value())
+                .bind(at)
+                .execute(&pool)
+                .await
+                .expect("pin updated_at");
+        };
+        let users = PgUserRepository::new(pool.clone());
+        repository_contract::users_should_sync_ties_exactly_once(&users, pin).await;
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn find_by_ids_should_return_only_requested_users(pool: PgPool) {
+        let tenant = TenantId::default();
+        let repo = PgUserRepository::new(pool);
+        let (alice, bob, carol) = (user("alice"), user("bob"), user("carol"));
+        for u in [&alice, &bob, &carol] {
+            repo.insert(&tenant, u).await.expect("insert");
+        }
+
+        let ids = [alice.id().clone(), carol.id().clone(), UserId::generate()];
+        let mut found: Vec<String> = repo
+            .find_by_ids(&tenant, &ids)
+            .await
+            .expect("query")
+            .iter()
+            .map(|u| u.name().to_owned())
+            .collect();
+        found.sort();
+        assert_eq!(found, ["alice", "carol"]);
+        assert!(repo.find_by_ids(&tenant, &[]).await.expect("query").is_empty());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn find_page_should_match_whole_email_domain_literally(pool: PgPool) {
+        let tenant = TenantId::default();
+        let repo = PgUserRepository::new(pool);
+        for (name, email) in [
+            ("alice", "alice@Example.com"),
+            ("bob", "bob@mail.example.com"),
+            ("carol", "carol@example_com"),
+            ("dave", "dave@exampleXcom"),
+        ] {
+            let user = User::new(UserId::generate(), name.to_owned(), email).expect("valid user");
+            repo.insert(&tenant, &user).await.expect("insert");
+        }
+
+        let names = |users: Vec<User>| {
+            let mut names: Vec<String> = users.iter().map(|u| u.name().to_owned()).collect();
+            names.sort();
+            names
+        };
+        let page = Page { limit: 10, offset: 0 };
+        let found = repo.find_page(&tenant, Some("example.com"), page).await.expect("query");
+        assert_eq!(names(found), ["alice"]);
+        let found = repo.find_page(&tenant, Some("example_com"), page).await.expect("query");
+        assert_eq!(names(found), ["carol"]);
+        assert!(repo.find_page(&tenant, Some("%"), page).await.expect("query").is_empty());
+        assert_eq!(repo.find_page(&tenant, None, page).await.expect("query").len(), 4);
+        let second = Page { limit: 3, offset: 3 };
+        assert_eq!(repo.find_page(&tenant, None, second).await.expect("query").len(), 1);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn emails_should_be_unique_per_tenant_and_users_invisible_across_tenants(pool: PgPool) {
+        let (acme, other) = (TenantId::new("acme").expect("tenant"), TenantId::default());
+        let repo = PgUserRepository::new(pool);
+        let (alice, alice_elsewhere) = (user("alice"), user("alice"));
+        repo.insert(&acme, &alice).await.expect("insert");
+        repo.insert(&other, &alice_elsewhere).await.expect("same email in another tenant");
+        let duplicate = repo.insert(&acme, &user("alice")).await;
+        assert!(matches!(duplicate, Err(DomainError::AlreadyExists(_))), "{duplicate:?}");
+
+        assert!(repo.find_by_id(&other, alice.id()).await.expect("find").is_none());
+        assert!(repo.find_by_ids(&other, &[alice.id().clone()]).await.expect("find").is_empty());
+        let page = Page { limit: 10, offset: 0 };
+        assert_eq!(repo.find_page(&acme, None, page).await.expect("page").len(), 1);
+        assert!(!repo.delete(&other, alice.id()).await.expect("delete"));
+        let mut renamed = alice.clone();
+        renamed.update("Mallory".into(), "mallory@example.com").expect("valid update");
+        let result = repo.update(&other, &renamed).await;
+        assert!(matches!(result, Err(DomainError::NotFound(_))), "{result:?}");
+        let stored = repo.find_by_id(&acme, alice.id()).await.expect("find").expect("exists");
+        assert_eq!(stored.name(), "alice");
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn email_changes_should_supersede_pending_and_confirm_once(pool: PgPool) {
+        let tenant = TenantId::default();
+        let users = PgUserRepository::new(pool.clone());
+        let changes = PgEmailChangeRepository::new(pool);
+        let alice = user("alice");
+        users.insert(&tenant, &alice).await.expect("insert");
+        let expires_at = chrono::Utc::now() + chrono::TimeDelta::hours(1);
+        let change = |hash: &str| {
+            let email = Email::from_trusted(format!("{hash}@example.com"));
+            PendingEmailChange::new(alice.id().clone(), email, hash.to_owned(), expires_at)
+        };
+
+        changes.save(&change("first")).await.expect("save");
+        changes.save(&change("second")).await.expect("save");
+        assert!(changes.find_by_token_hash("first").await.expect("query").is_none());
+        let stored = changes.find_by_token_hash("second").await.expect("query").expect("stored");
+        assert_eq!(stored.new_email().value(), "second@example.com");
+        assert!(stored.confirmed_at().is_none());
+
+        assert!(changes.mark_confirmed("second").await.expect("confirm"));
+        assert!(!changes.mark_confirmed("second").await.expect("confirm"));
+        // Confirmed changes are kept, so reusing their token is recognised
+        changes.save(&change("third")).await.expect("save");
+        let used = changes.find_by_token_hash("second").await.expect("query").expect("kept");
+        assert!(used.confirmed_at().is_some());
+    }
+}
@@ -3,3 +3,11 @@
 pub mod application;
 pub mod domain;
 pub mod infrastructure;
+pub mod state;
+
+pub use state::{DynUserState, EmailChangeSettings, UserState};
+
+/// Feature name used in `ENABLED_FEATURES` / `DISABLED_FEATURES`
+pub const NAME: &str = "user";
+/// Features that must be enabled for this one to work
+pub const DEPENDS_ON: &[&str] = &[];
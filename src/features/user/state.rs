@@ -0,0 +1,96 @@
+//! User feature state shared across handlers
+
+use crate::features::user::application::{
+    ConfirmEmailChangeUseCase, CreateUserUseCase, DeleteUserUseCase, GetUserUseCase,
+    GetUsersByIdsUseCase, ListUsersUseCase, ListUsersWithTaskCountsUseCase,
+    RequestEmailChangeUseCase, SyncUsersUseCase, UpdateUserUseCase,
+};
+use crate::features::user::domain::{
+    EmailChangeNotifier, EmailChangeRepository, UserDependents, UserRepository,
+};
+use crate::shared::application::{DetachedWrites, PageLimits};
+use crate::shared::domain::EmailSender;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How email change tokens are stored, delivered and how long they are valid
+pub struct EmailChangeSettings {
+    /// Storage of pending changes
+    pub repository: Arc<dyn EmailChangeRepository>,
+    /// Delivery of tokens; without one they only reach clients in dev mode
+    pub notifier: Option<Arc<dyn EmailChangeNotifier>>,
+    /// Validity of a token
+    pub token_ttl: Duration,
+    /// Return the token in the response (dev mode)
+    pub token_in_response: bool,
+}
+
+/// Use cases of the user feature, generic over the user repository
+///
+/// Wiring a concrete repository dispatches its calls statically; [`DynUserState`],
+/// the default, takes any repository behind `Arc<dyn UserRepository>`.
+pub struct UserState<U: ?Sized = dyn UserRepository> {
+    pub(crate) create_user: CreateUserUseCase<U>,
+    pub(crate) get_user: GetUserUseCase<U>,
+    #[cfg_attr(not(feature = "graphql"), expect(dead_code, reason = "only the GraphQL loader batches"))]
+    pub(crate) get_users_by_ids: GetUsersByIdsUseCase<U>,
+    pub(crate) list_users: ListUsersUseCase<U>,
+    /// Absent without a task feature to count the tasks of
+    pub(crate) list_users_with_task_counts: Option<ListUsersWithTaskCountsUseCase<U>>,
+    pub(crate) sync_users: SyncUsersUseCase<U>,
+    pub(crate) update_user: UpdateUserUseCase<U>,
+    pub(crate) delete_user: DeleteUserUseCase<U>,
+    pub(crate) request_email_change: RequestEmailChangeUseCase<U>,
+    pub(crate) confirm_email_change: ConfirmEmailChangeUseCase<U>,
+    pub(crate) email_change_token_in_response: bool,
+    /// Runs the writes that outlive dropped requests
+    pub(crate) detached_writes: DetachedWrites,
+}
+
+/// [`UserState`] wired to a user repository behind `Arc<dyn UserRepository>`
+pub type DynUserState = UserState;
+
+impl<U: UserRepository + ?Sized + 'static> UserState<U> {
+    /// Wire every user use case to the given repository; `dependents` counts what
+    /// deleting a user would cascade to and the tasks of listed users, listings
+    /// return pages within `page_limits`, new users are welcomed through
+    /// `email_sender` and deletions run on `detached_writes`
+    pub fn new(
+        repository: &Arc<U>,
+        dependents: Option<Arc<dyn UserDependents>>,
+        email_changes: EmailChangeSettings,
+        page_limits: PageLimits,
+        email_sender: Arc<dyn EmailSender>,
+        detached_writes: &DetachedWrites,
+    ) -> Self {
+        Self {
+            create_user: CreateUserUseCase::new(Arc::clone(repository), email_sender),
+            get_user: GetUserUseCase::new(Arc::clone(repository)),
+            get_users_by_ids: GetUsersByIdsUseCase::new(Arc::clone(repository)),
+            list_users: ListUsersUseCase::new(Arc::clone(repository), page_limits),
+            list_users_with_task_counts: dependents.clone().map(|dependents| {
+                ListUsersWithTaskCountsUseCase::new(Arc::clone(repository), dependents, page_limits)
+            }),
+            sync_users: SyncUsersUseCase::new(Arc::clone(repository), page_limits),
+            update_user: UpdateUserUseCase::new(Arc::clone(repository)),
+            delete_user: DeleteUserUseCase::new(
+                Arc::clone(repository),
+                dependents,
+                detached_writes.clone(),
+            ),
+            request_email_change: RequestEmailChangeUseCase::new(
+                Arc::clone(repository),
+                Arc::clone(&email_changes.repository),
+                email_changes.notifier,
+                email_changes.token_ttl,
+            ),
+            confirm_email_change: ConfirmEmailChangeUseCase::new(
+                Arc::clone(repository),
+                email_changes.repository,
+                detached_writes.clone(),
+            ),
+            email_change_token_in_response: email_changes.token_in_response,
+            detached_writes: detached_writes.clone(),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Create webhook use case
+
+use crate::features::webhook::domain::{
+    ensure_public, generate_secret, EndpointResolver, WebhookId, WebhookRepository,
+    WebhookSubscription,
+};
+use crate::shared::domain::{DomainError, TenantId};
+use std::sync::Arc;
+
+/// Command to subscribe an endpoint to events
+#[derive(Debug)]
+pub struct CreateWebhookCommand {
+    /// URL events are posted to
+    pub url: String,
+    /// Secret deliveries are signed with; generated when `None`
+    pub secret: Option<String>,
+    /// Event types delivered; every event when empty
+    pub event_types: Vec<String>,
+}
+
+/// Use case for creating a webhook subscription
+pub struct CreateWebhookUseCase {
+    repository: Arc<dyn WebhookRepository>,
+    resolver: Arc<dyn EndpointResolver>,
+}
+
+impl CreateWebhookUseCase {
+    /// Create a new use case instance resolving endpoints through `resolver`
+    pub fn new(
+        repository: Arc<dyn WebhookRepository>,
+        resolver: Arc<dyn EndpointResolver>,
+    ) -> Self {
+        Self { repository, resolver }
+    }
+
+    /// Create an active subscription of `tenant` with a generated ID
+    ///
+    /// # Errors
+    /// `Validation` if the URL, an event type or the given secret is invalid, or the
+    /// URL points to an address that is not public.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        command: CreateWebhookCommand,
+    ) -> Result<WebhookSubscription, DomainError> {
+        let secret = command.secret.unwrap_or_else(generate_secret);
+        let subscription = WebhookSubscription::new(
+            WebhookId::generate(),
+            &command.url,
+            secret,
+            command.event_types,
+        )?;
+        ensure_public(subscription.url(), self.resolver.as_ref()).await?;
+        self.repository.insert(tenant, &subscription).await?;
+        Ok(subscription)
+    }
+}
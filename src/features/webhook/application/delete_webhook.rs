@@ -0,0 +1,31 @@
+//! Delete webhook use case
+
+use crate::features::webhook::domain::{WebhookId, WebhookRepository};
+use crate::shared::domain::{DomainError, TenantId};
+use std::sync::Arc;
+
+/// Use case for deleting a webhook subscription
+pub struct DeleteWebhookUseCase {
+    repository: Arc<dyn WebhookRepository>,
+}
+
+impl DeleteWebhookUseCase {
+    /// Create a new use case instance
+    pub fn new(repository: Arc<dyn WebhookRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Delete the subscription of `tenant` with `id`; its pending deliveries are
+    /// dropped
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the subscription doesn't exist.
+    pub async fn execute(&self, tenant: &TenantId, id: &str) -> Result<(), DomainError> {
+        let id = WebhookId::new(id)?;
+        if self.repository.delete(tenant, &id).await? {
+            Ok(())
+        } else {
+            Err(DomainError::not_found(WebhookId::entity_name()))
+        }
+    }
+}
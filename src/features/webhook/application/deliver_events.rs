@@ -0,0 +1,326 @@
+//! Deliver events use case: fanning outbox events out to subscriptions and posting them
+
+use crate::features::webhook::domain::{
+    Delivery, DeliveryStatus, WebhookDeliveries, WebhookId, WebhookRepository, WebhookSender,
+    WebhookSubscription,
+};
+use crate::shared::domain::{AuditEntry, DomainError, Entity, StoredEvent, TenantId};
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Audit action of a subscription deactivated for failing; the subject is its ID
+pub const WEBHOOK_DEACTIVATED: &str = "webhooks.deactivated";
+
+/// Outbox events fanned out per run
+const EVENT_BATCH_SIZE: u32 = 100;
+
+/// Deliveries attempted per run, one after the other
+const DELIVERY_BATCH_SIZE: u32 = 20;
+
+/// How long claimed deliveries are left to this worker; outlasts a batch of timeouts
+const DELIVERY_LEASE: Duration = Duration::from_mins(5);
+
+/// Delay before the first retry of a delivery; doubled for each further retry
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Upper bound of the delay between two attempts of a delivery
+const MAX_RETRY_DELAY: Duration = Duration::from_hours(1);
+
+/// When deliveries give up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryPolicy {
+    /// Attempts of one delivery, the first one included, before it is dropped
+    pub max_attempts: u32,
+    /// Failed attempts in a row after which a subscription is deactivated
+    pub max_consecutive_failures: u32,
+}
+
+/// What one run did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    /// Events fanned out to their subscriptions
+    pub fanned_out: usize,
+    /// Deliveries the endpoint accepted
+    pub delivered: usize,
+    /// Deliveries that failed, to be retried or dropped
+    pub failed: usize,
+    /// Subscriptions deactivated for failing
+    pub deactivated: usize,
+    /// Whether a batch was full, so that more work may be waiting
+    pub more: bool,
+}
+
+/// Use case delivering the events of the outbox to the subscriptions accepting them
+///
+/// Each run first fans the events committed since the last run out into one delivery
+/// per matching active subscription, then attempts the deliveries that are due. A
+/// failed delivery is retried with exponential backoff up to
+/// [`DeliveryPolicy::max_attempts`] times; a subscription failing
+/// [`DeliveryPolicy::max_consecutive_failures`] attempts in a row is deactivated,
+/// which is recorded in the audit trail as [`WEBHOOK_DEACTIVATED`].
+pub struct DeliverEventsUseCase {
+    repository: Arc<dyn WebhookRepository>,
+    deliveries: Arc<dyn WebhookDeliveries>,
+    sender: Arc<dyn WebhookSender>,
+    policy: DeliveryPolicy,
+}
+
+impl DeliverEventsUseCase {
+    /// Create a new use case instance posting through `sender`
+    pub fn new(
+        repository: Arc<dyn WebhookRepository>,
+        deliveries: Arc<dyn WebhookDeliveries>,
+        sender: Arc<dyn WebhookSender>,
+        policy: DeliveryPolicy,
+    ) -> Self {
+        Self { repository, deliveries, sender, policy }
+    }
+
+    /// Fan out a batch of events, then attempt a batch of due deliveries
+    ///
+    /// # Errors
+    /// Repository failures; the outcomes recorded before stay recorded.
+    pub async fn execute(&self) -> Result<DeliveryReport, DomainError> {
+        let mut report = DeliveryReport::default();
+        let events = self.deliveries.pending_events(EVENT_BATCH_SIZE).await?;
+        let mut subscriptions = HashMap::new();
+        for event in &events {
+            if !subscriptions.contains_key(&event.tenant) {
+                let found = self.repository.find_all(&event.tenant).await?;
+                subscriptions.insert(event.tenant.clone(), found);
+            }
+            let matching = matching(&subscriptions[&event.tenant], event);
+            if self.deliveries.fan_out(event, &matching).await? {
+                report.fanned_out += 1;
+            }
+        }
+
+        let due = self.deliveries.claim_due(DELIVERY_LEASE, DELIVERY_BATCH_SIZE).await?;
+        let mut deactivated = HashSet::new();
+        for delivery in &due {
+            let subscription = &delivery.subscription;
+            if !subscription.is_active() || deactivated.contains(subscription.id()) {
+                self.deliveries.discard(delivery).await?;
+                continue;
+            }
+            let Some(failures) = self.attempt(delivery).await? else {
+                report.delivered += 1;
+                continue;
+            };
+            report.failed += 1;
+            if failures >= self.policy.max_consecutive_failures
+                && self.deactivate(&delivery.tenant, subscription, failures).await?
+            {
+                deactivated.insert(subscription.id().clone());
+                report.deactivated += 1;
+            }
+        }
+        report.more = events.len() == EVENT_BATCH_SIZE as usize
+            || due.len() == DELIVERY_BATCH_SIZE as usize;
+        Ok(report)
+    }
+
+    /// Post `delivery` and record its outcome; the consecutive failures of its
+    /// subscription if it failed
+    async fn attempt(&self, delivery: &Delivery) -> Result<Option<u32>, DomainError> {
+        let (status_code, error) =
+            match self.sender.send(&delivery.subscription, &delivery.event).await {
+                Ok(code) if (200..300).contains(&code) => (Some(code), None),
+                Ok(code) => (Some(code), Some(format!("unexpected response status {code}"))),
+                Err(e) => (None, Some(e.to_string())),
+            };
+        let status = DeliveryStatus { at: Utc::now(), status_code, error };
+        if status.succeeded() {
+            self.deliveries.delivered(delivery, &status).await?;
+            return Ok(None);
+        }
+        let attempts = delivery.attempts.saturating_add(1);
+        let retry_at = (attempts < self.policy.max_attempts)
+            .then(|| status.at + retry_delay(attempts));
+        if retry_at.is_none() {
+            let (id, event) = (delivery.subscription.id().value(), delivery.event.id);
+            tracing::warn!("Dropped event {event} for webhook '{id}' after {attempts} attempts");
+        }
+        Ok(Some(self.deliveries.failed(delivery, &status, retry_at).await?))
+    }
+
+    /// Deactivate `subscription` for failing `failures` times in a row, unless it
+    /// no longer is active
+    async fn deactivate(
+        &self,
+        tenant: &TenantId,
+        subscription: &WebhookSubscription,
+        failures: u32,
+    ) -> Result<bool, DomainError> {
+        let entry = AuditEntry {
+            action: WEBHOOK_DEACTIVATED,
+            subject: subscription.id().value().to_owned(),
+            details: serde_json::json!({
+                "url": subscription.url(),
+                "consecutive_failures": failures,
+            }),
+            client_ip: None,
+        };
+        let deactivated = self.repository.deactivate(tenant, subscription.id(), &entry).await?;
+        if deactivated {
+            let id = subscription.id().value();
+            tracing::warn!("Deactivated webhook '{id}' after {failures} failed deliveries");
+        }
+        Ok(deactivated)
+    }
+}
+
+/// IDs of the subscriptions `event` is delivered to
+fn matching(subscriptions: &[WebhookSubscription], event: &StoredEvent) -> Vec<WebhookId> {
+    let accepting = subscriptions.iter().filter(|s| s.accepts(&event.event_type));
+    accepting.map(|subscription| subscription.id().clone()).collect()
+}
+
+/// Delay before attempt `attempts + 1` of a delivery, after `attempts` failed ones
+fn retry_delay(attempts: u32) -> chrono::Duration {
+    let exponential = FIRST_RETRY_DELAY.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+    chrono::Duration::from_std(exponential.min(MAX_RETRY_DELAY)).unwrap_or(chrono::Duration::MAX)
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::webhook::domain::generate_secret;
+    use crate::features::webhook::infrastructure::InMemoryWebhookRepository;
+    use crate::shared::domain::OutboxEvent;
+    use crate::shared::infrastructure::outbox::InMemoryOutbox;
+    use std::sync::Mutex;
+
+    const POLICY: DeliveryPolicy = DeliveryPolicy { max_attempts: 3, max_consecutive_failures: 2 };
+
+    /// Sender answering every post with `status`, recording the URL and event type
+    struct FakeSender {
+        status: u16,
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl WebhookSender for FakeSender {
+        async fn send(
+            &self,
+            subscription: &WebhookSubscription,
+            event: &StoredEvent,
+        ) -> Result<u16, DomainError> {
+            let sent = (subscription.url().to_owned(), event.event_type.clone());
+            self.sent.lock().expect("lock").push(sent);
+            Ok(self.status)
+        }
+    }
+
+    struct Fixture {
+        outbox: Arc<InMemoryOutbox>,
+        webhooks: Arc<InMemoryWebhookRepository>,
+        sender: Arc<FakeSender>,
+        use_case: DeliverEventsUseCase,
+    }
+
+    impl Fixture {
+        fn new(status: u16, policy: DeliveryPolicy) -> Self {
+            let outbox = Arc::new(InMemoryOutbox::default());
+            let webhooks = Arc::new(InMemoryWebhookRepository::new(Arc::clone(&outbox)));
+            let sender = Arc::new(FakeSender { status, sent: Mutex::default() });
+            let use_case = DeliverEventsUseCase::new(
+                Arc::clone(&webhooks) as _,
+                Arc::clone(&webhooks) as _,
+                Arc::clone(&sender) as _,
+                policy,
+            );
+            Self { outbox, webhooks, sender, use_case }
+        }
+
+        async fn subscribe(&self, url: &str, event_types: &[&str]) -> WebhookSubscription {
+            let event_types = event_types.iter().map(|&t| t.to_owned()).collect();
+            let subscription =
+                WebhookSubscription::new(WebhookId::generate(), url, generate_secret(), event_types)
+                    .expect("valid subscription");
+            let tenant = TenantId::default();
+            self.webhooks.insert(&tenant, &subscription).await.expect("insert");
+            subscription
+        }
+
+        async fn record(&self, event_type: &'static str) {
+            let payload = serde_json::json!({});
+            let event = OutboxEvent { event_type, version: 1, subject: "user-1".into(), payload };
+            self.outbox.append([(TenantId::default(), event)]).await;
+        }
+
+        /// Run once, as the job does after retry delays passed
+        async fn run(&self) -> DeliveryReport {
+            self.webhooks.expire_retry_delays().await;
+            self.use_case.execute().await.expect("delivered")
+        }
+
+        fn sent(&self) -> Vec<(String, String)> {
+            self.sender.sent.lock().expect("lock").clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_should_deliver_events_only_to_subscriptions_accepting_them() {
+        let fixture = Fixture::new(204, POLICY);
+        fixture.subscribe("https://a.example.com/hook", &["tasks.reassigned"]).await;
+        fixture.subscribe("https://b.example.com/hook", &["user.onboarded"]).await;
+        fixture.record("tasks.reassigned").await;
+
+        let report = fixture.run().await;
+        assert_eq!((report.fanned_out, report.delivered, report.failed), (1, 1, 0));
+        let expected = ("https://a.example.com/hook".to_owned(), "tasks.reassigned".to_owned());
+        assert_eq!(fixture.sent(), [expected]);
+        assert!(fixture.outbox.pending(10).await.is_empty(), "fanned out once");
+        let report = fixture.run().await;
+        assert_eq!((report.fanned_out, report.delivered, fixture.sent().len()), (0, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn execute_should_deactivate_a_failing_subscription_with_an_audit_entry() {
+        let fixture = Fixture::new(500, POLICY);
+        let subscription = fixture.subscribe("https://a.example.com/hook", &[]).await;
+        fixture.record("tasks.reassigned").await;
+        fixture.record("user.onboarded").await;
+
+        let report = fixture.run().await;
+        assert_eq!((report.failed, report.deactivated), (2, 1));
+        let tenant = TenantId::default();
+        let found = fixture.webhooks.find_by_id(&tenant, subscription.id()).await;
+        let found = found.expect("found").expect("still subscribed");
+        assert!(!found.is_active());
+        assert_eq!(found.last_delivery().and_then(|d| d.status_code), Some(500));
+        let trail = fixture.webhooks.audit_trail(&tenant).await;
+        assert_eq!(trail.len(), 1);
+        assert_eq!(trail[0].action, WEBHOOK_DEACTIVATED);
+        assert_eq!(trail[0].subject, found.id().value());
+        assert_eq!(trail[0].details["consecutive_failures"], 2);
+
+        // Deliveries to a deactivated subscription are dropped unsent
+        let report = fixture.run().await;
+        assert_eq!((report.failed, fixture.sent().len()), (0, 2));
+    }
+
+    #[tokio::test]
+    async fn execute_should_drop_a_delivery_after_its_last_attempt() {
+        let policy = DeliveryPolicy { max_attempts: 2, max_consecutive_failures: 10 };
+        let fixture = Fixture::new(503, policy);
+        fixture.subscribe("https://a.example.com/hook", &[]).await;
+        fixture.record("tasks.reassigned").await;
+
+        for _ in 0..3 {
+            fixture.run().await;
+        }
+        assert_eq!(fixture.sent().len(), 2);
+    }
+
+    #[test]
+    fn retry_delay_should_double_up_to_the_maximum() {
+        let secs = |attempts| retry_delay(attempts).num_seconds();
+        assert_eq!([secs(1), secs(2), secs(3)], [30, 60, 120]);
+        assert_eq!(secs(40), 3_600);
+    }
+}
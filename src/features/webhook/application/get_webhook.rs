@@ -0,0 +1,56 @@
+//! Get webhook use cases
+
+use crate::features::webhook::domain::{WebhookId, WebhookRepository, WebhookSubscription};
+use crate::shared::domain::{DomainError, TenantId};
+use std::sync::Arc;
+
+/// Use case for getting a webhook subscription by ID
+pub struct GetWebhookUseCase {
+    repository: Arc<dyn WebhookRepository>,
+}
+
+impl GetWebhookUseCase {
+    /// Create a new use case instance
+    pub fn new(repository: Arc<dyn WebhookRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Get the subscription of `tenant` with `id`
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, `NotFound` if the subscription doesn't exist.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+    ) -> Result<WebhookSubscription, DomainError> {
+        let id = WebhookId::new(id)?;
+        self.repository
+            .find_by_id(tenant, &id)
+            .await?
+            .ok_or_else(|| DomainError::not_found(WebhookId::entity_name()))
+    }
+}
+
+/// Use case for listing webhook subscriptions
+pub struct ListWebhooksUseCase {
+    repository: Arc<dyn WebhookRepository>,
+}
+
+impl ListWebhooksUseCase {
+    /// Create a new use case instance
+    pub fn new(repository: Arc<dyn WebhookRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// List every subscription of `tenant`, oldest first; tenants have few of them
+    ///
+    /// # Errors
+    /// Repository failures.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+    ) -> Result<Vec<WebhookSubscription>, DomainError> {
+        self.repository.find_all(tenant).await
+    }
+}
@@ -0,0 +1,15 @@
+//! Webhook application layer
+
+pub mod create_webhook;
+pub mod delete_webhook;
+pub mod deliver_events;
+pub mod get_webhook;
+pub mod update_webhook;
+
+pub use create_webhook::{CreateWebhookCommand, CreateWebhookUseCase};
+pub use delete_webhook::DeleteWebhookUseCase;
+pub use deliver_events::{
+    DeliverEventsUseCase, DeliveryPolicy, DeliveryReport, WEBHOOK_DEACTIVATED,
+};
+pub use get_webhook::{GetWebhookUseCase, ListWebhooksUseCase};
+pub use update_webhook::{UpdateWebhookCommand, UpdateWebhookUseCase};
@@ -0,0 +1,60 @@
+//! Update webhook use case
+
+use crate::features::webhook::domain::{
+    ensure_public, EndpointResolver, WebhookId, WebhookRepository, WebhookSubscription,
+};
+use crate::shared::domain::{DomainError, TenantId};
+use std::sync::Arc;
+
+/// Command to replace the settings of a webhook subscription
+#[derive(Debug)]
+pub struct UpdateWebhookCommand {
+    /// URL events are posted to
+    pub url: String,
+    /// Event types delivered; every event when empty
+    pub event_types: Vec<String>,
+    /// Whether events are delivered
+    pub active: bool,
+}
+
+/// Use case for updating a webhook subscription
+pub struct UpdateWebhookUseCase {
+    repository: Arc<dyn WebhookRepository>,
+    resolver: Arc<dyn EndpointResolver>,
+}
+
+impl UpdateWebhookUseCase {
+    /// Create a new use case instance resolving endpoints through `resolver`
+    pub fn new(
+        repository: Arc<dyn WebhookRepository>,
+        resolver: Arc<dyn EndpointResolver>,
+    ) -> Self {
+        Self { repository, resolver }
+    }
+
+    /// Replace the URL, event types and active flag of the subscription of `tenant`
+    /// with `id`; reactivating it forgets its failures
+    ///
+    /// # Errors
+    /// `Validation` for an empty ID, URL or event type or a URL pointing to an address
+    /// that is not public, `NotFound` if the subscription doesn't exist, deleted
+    /// concurrently included.
+    pub async fn execute(
+        &self,
+        tenant: &TenantId,
+        id: &str,
+        command: UpdateWebhookCommand,
+    ) -> Result<WebhookSubscription, DomainError> {
+        let id = WebhookId::new(id)?;
+        let mut subscription = self
+            .repository
+            .find_by_id(tenant, &id)
+            .await?
+            .ok_or_else(|| DomainError::not_found(WebhookId::entity_name()))?;
+
+        subscription.update(&command.url, command.event_types, command.active)?;
+        ensure_public(subscription.url(), self.resolver.as_ref()).await?;
+        self.repository.update(tenant, &subscription).await?;
+        Ok(subscription)
+    }
+}
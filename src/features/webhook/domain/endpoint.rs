@@ -0,0 +1,157 @@
+//! Webhook endpoints: events are only ever delivered to public addresses, never to
+//! the loopback, private or link-local services around the server
+
+use crate::shared::domain::DomainError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use url::{Host, Url};
+
+/// Resolves the host name of an endpoint to the addresses a delivery connects to
+#[async_trait::async_trait]
+pub trait EndpointResolver: Send + Sync {
+    /// Addresses of the domain name `host`
+    async fn resolve(&self, host: &str, port: u16) -> Result<Vec<IpAddr>, DomainError>;
+}
+
+/// Whether `ip` is reachable on the public internet: not unspecified, loopback,
+/// private, shared, link-local (the cloud metadata address included), multicast,
+/// documentation, benchmarking or reserved
+#[must_use]
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    let special = a == 0
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_multicast()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240;
+    !special
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    let bits = |high: u16, low: u16| u32::from(high) << 16 | u32::from(low);
+    // IPv4 addresses embedded in IPv6 ones (mapped, NAT64, 6to4) are judged as IPv4
+    let embedded = match segments {
+        [0, 0, 0, 0, 0, 0xffff, high, low] | [0x64, 0xff9b, 0, 0, 0, 0, high, low] => {
+            Some(bits(high, low))
+        }
+        [0x2002, high, low, ..] => Some(bits(high, low)),
+        _ => None,
+    };
+    if let Some(ip) = embedded {
+        return is_public_v4(Ipv4Addr::from_bits(ip));
+    }
+    let special = ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || segments[0] & 0xfe00 == 0xfc00
+        || segments[0] & 0xffc0 == 0xfe80
+        || segments[..2] == [0x2001, 0x0db8]
+        || segments[..6] == [0; 6];
+    !special
+}
+
+/// Check that `url` points to public addresses only: its IP address, or every
+/// address its host name resolves to through `resolver`
+///
+/// # Errors
+/// Returns `DomainError::Validation` if the URL has no host, its host name resolves
+/// to no address or to one that is not public; the errors of `resolver`.
+pub async fn ensure_public(url: &str, resolver: &dyn EndpointResolver) -> Result<(), DomainError> {
+    let url = Url::parse(url.trim())
+        .map_err(|e| DomainError::validation("URL", format!("is invalid: {e}")))?;
+    let port = url.port_or_known_default().unwrap_or_default();
+    let addresses = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(host)) => resolver.resolve(host, port).await?,
+        None => return Err(DomainError::validation("URL", "must have a host")),
+    };
+    if addresses.is_empty() {
+        return Err(DomainError::validation("URL", "must have a host that resolves"));
+    }
+    if !addresses.into_iter().all(is_public) {
+        let reason = "must not point to a loopback, private or link-local address";
+        return Err(DomainError::validation("URL", reason));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    /// Resolves every host name to the same addresses
+    struct Resolving(Vec<IpAddr>);
+
+    #[async_trait::async_trait]
+    impl EndpointResolver for Resolving {
+        async fn resolve(&self, _: &str, _: u16) -> Result<Vec<IpAddr>, DomainError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn is_public_should_reject_special_purpose_addresses() {
+        for ip in [
+            "0.0.0.0",
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "192.0.0.8",
+            "198.18.0.1",
+            "203.0.113.7",
+            "224.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "ff02::1",
+            "2001:db8::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+            "64:ff9b::a00:1",
+            "2002:c0a8:101::1",
+            "::127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().expect("IP")), "{ip}");
+        }
+        for ip in ["93.184.215.14", "8.8.8.8", "2606:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public(ip.parse().expect("IP")), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn ensure_public_should_check_literal_and_resolved_addresses() {
+        let public = Resolving(vec!["93.184.215.14".parse().expect("IP")]);
+        assert!(ensure_public("https://hooks.example.com/tasks", &public).await.is_ok());
+        assert!(ensure_public("http://8.8.8.8:8080/", &public).await.is_ok());
+        for url in ["http://127.0.0.1:9090/metrics", "http://[::1]/", "http://169.254.169.254/"] {
+            let result = ensure_public(url, &public).await;
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{url}");
+        }
+
+        let mixed = Resolving(vec!["8.8.8.8".parse().expect("IP"), [10, 0, 0, 1].into()]);
+        let result = ensure_public("https://hooks.example.com", &mixed).await;
+        assert!(matches!(result, Err(DomainError::Validation(_))), "{result:?}");
+        let result = ensure_public("https://hooks.example.com", &Resolving(Vec::new())).await;
+        assert!(matches!(result, Err(DomainError::Validation(_))), "{result:?}");
+    }
+}
@@ -0,0 +1,266 @@
+//! Webhook subscription domain
+
+use super::value_objects::WebhookId;
+use crate::shared::domain::{DomainError, Entity, CANNOT_BE_EMPTY};
+use chrono::{DateTime, Utc};
+use url::Url;
+
+/// Most event types a subscription may filter on
+pub const MAX_EVENT_TYPES: usize = 50;
+
+/// Shortest secret a subscription may be signed with
+const MIN_SECRET_LEN: usize = 16;
+
+/// Issue a new signing secret: `whsec_` and 64 hex characters from two random UUIDs
+#[must_use]
+pub fn generate_secret() -> String {
+    let (a, b) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    format!("whsec_{}{}", a.simple(), b.simple())
+}
+
+/// Outcome of the latest delivery attempt to a subscription
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryStatus {
+    /// When the attempt ended
+    pub at: DateTime<Utc>,
+    /// Status the endpoint answered with; `None` when no response arrived
+    pub status_code: Option<u16>,
+    /// Why the attempt failed; `None` when it succeeded
+    pub error: Option<String>,
+}
+
+impl DeliveryStatus {
+    /// Whether the endpoint accepted the event
+    #[must_use]
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Webhook subscription aggregate root: an endpoint events are delivered to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookSubscription {
+    id: WebhookId,
+    url: String,
+    secret: String,
+    event_types: Vec<String>,
+    active: bool,
+    consecutive_failures: u32,
+    last_delivery: Option<DeliveryStatus>,
+}
+
+impl WebhookSubscription {
+    /// Create an active subscription of `url` to `event_types`, every event when empty,
+    /// signed with `secret`
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if the URL is not an absolute `http` or
+    /// `https` URL, an event type is empty, there are more than [`MAX_EVENT_TYPES`]
+    /// or the secret is shorter than 16 characters.
+    pub fn new(
+        id: WebhookId,
+        url: &str,
+        secret: String,
+        event_types: Vec<String>,
+    ) -> Result<Self, DomainError> {
+        if secret.chars().count() < MIN_SECRET_LEN {
+            let reason = format!("must have at least {MIN_SECRET_LEN} characters");
+            return Err(DomainError::validation("Secret", reason));
+        }
+        let mut subscription = Self {
+            id,
+            url: String::new(),
+            secret,
+            event_types: Vec::new(),
+            active: true,
+            consecutive_failures: 0,
+            last_delivery: None,
+        };
+        subscription.update(url, event_types, true)?;
+        Ok(subscription)
+    }
+
+    /// URL events are posted to
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Secret deliveries are signed with
+    #[must_use]
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// Event types delivered; every event when empty
+    #[must_use]
+    pub fn event_types(&self) -> &[String] {
+        &self.event_types
+    }
+
+    /// Whether events are delivered at all
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Failed delivery attempts since the last successful one
+    #[must_use]
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Outcome of the latest delivery attempt, if any
+    #[must_use]
+    pub fn last_delivery(&self) -> Option<&DeliveryStatus> {
+        self.last_delivery.as_ref()
+    }
+
+    /// Whether events of `event_type` are delivered to this subscription
+    #[must_use]
+    pub fn accepts(&self, event_type: &str) -> bool {
+        if !self.active {
+            return false;
+        }
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type)
+    }
+
+    /// Replace the URL, event types and active flag; reactivating a subscription
+    /// forgets its failures
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` as [`Self::new`] does.
+    pub fn update(
+        &mut self,
+        url: &str,
+        mut event_types: Vec<String>,
+        active: bool,
+    ) -> Result<(), DomainError> {
+        let parsed = Url::parse(url.trim())
+            .map_err(|e| DomainError::validation("URL", format!("is invalid: {e}")))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(DomainError::validation("URL", "must be an http or https URL"));
+        }
+        if event_types.iter().any(|event_type| event_type.trim().is_empty()) {
+            return Err(DomainError::validation("Event type", CANNOT_BE_EMPTY));
+        }
+        if event_types.len() > MAX_EVENT_TYPES {
+            let reason = format!("must have at most {MAX_EVENT_TYPES} entries");
+            return Err(DomainError::validation("Event types", reason));
+        }
+        for event_type in &mut event_types {
+            *event_type = event_type.trim().to_owned();
+        }
+        event_types.sort_unstable();
+        event_types.dedup();
+        if active && !self.active {
+            self.consecutive_failures = 0;
+        }
+        self.url = parsed.into();
+        self.event_types = event_types;
+        self.active = active;
+        Ok(())
+    }
+
+    /// Record `status` as the outcome of the latest delivery: a success forgets the
+    /// failures, a failure adds one
+    pub fn record_delivery(&mut self, status: DeliveryStatus) {
+        self.consecutive_failures = if status.succeeded() {
+            0
+        } else {
+            self.consecutive_failures.saturating_add(1)
+        };
+        self.last_delivery = Some(status);
+    }
+
+    /// Stop delivering events, e.g. to an endpoint failing every delivery
+    pub fn deactivate(&mut self) {
+        self.active = false;
+    }
+
+    /// Reconstitute a subscription from persistence (bypasses business rules)
+    #[must_use]
+    pub fn reconstitute(
+        id: WebhookId,
+        url: String,
+        secret: String,
+        event_types: Vec<String>,
+        active: bool,
+        consecutive_failures: u32,
+        last_delivery: Option<DeliveryStatus>,
+    ) -> Self {
+        Self { id, url, secret, event_types, active, consecutive_failures, last_delivery }
+    }
+}
+
+impl Entity for WebhookSubscription {
+    type Id = WebhookId;
+
+    fn id(&self) -> &Self::Id {
+        &self.id
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    fn subscription(event_types: &[&str]) -> WebhookSubscription {
+        let event_types = event_types.iter().map(|&t| t.to_owned()).collect();
+        let url = "https://example.com/hook";
+        WebhookSubscription::new(WebhookId::generate(), url, generate_secret(), event_types)
+            .expect("valid subscription")
+    }
+
+    #[test]
+    fn accepts_should_filter_by_event_type_unless_the_filter_is_empty() {
+        let filtered = subscription(&["tasks.reassigned", "user.onboarded"]);
+        assert!(filtered.accepts("tasks.reassigned"));
+        assert!(!filtered.accepts("tasks.imported"));
+        assert!(subscription(&[]).accepts("tasks.imported"));
+    }
+
+    #[test]
+    fn inactive_subscriptions_should_accept_nothing() {
+        let mut subscription = subscription(&[]);
+        subscription.update("https://example.com/hook", Vec::new(), false).expect("valid update");
+        assert!(!subscription.accepts("tasks.reassigned"));
+    }
+
+    #[test]
+    fn new_should_reject_invalid_urls_event_types_and_secrets() {
+        let new = |url: &str, secret: &str, event_types: &[&str]| {
+            let event_types = event_types.iter().map(|&t| t.to_owned()).collect();
+            WebhookSubscription::new(WebhookId::generate(), url, secret.to_owned(), event_types)
+        };
+        let secret = generate_secret();
+        for result in [
+            new("example.com/hook", &secret, &[]),
+            new("ftp://example.com/hook", &secret, &[]),
+            new("https://example.com/hook", &secret, &[" "]),
+            new("https://example.com/hook", "short", &[]),
+        ] {
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{result:?}");
+        }
+        let created = new("https://example.com/hook", &secret, &["b", " a", "b"]);
+        assert_eq!(created.expect("valid subscription").event_types(), ["a", "b"]);
+    }
+
+    #[test]
+    fn reactivating_should_forget_failures() {
+        let id = WebhookId::generate();
+        let url = "https://example.com/hook";
+        let mut subscription = WebhookSubscription::reconstitute(
+            id,
+            url.into(),
+            generate_secret(),
+            Vec::new(),
+            false,
+            10,
+            None,
+        );
+        subscription.update(url, Vec::new(), true).expect("valid update");
+        assert_eq!((subscription.is_active(), subscription.consecutive_failures()), (true, 0));
+    }
+}
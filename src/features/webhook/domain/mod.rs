@@ -0,0 +1,11 @@
+//! Webhook domain layer
+
+pub mod endpoint;
+pub mod entity;
+pub mod repository;
+pub mod value_objects;
+
+pub use endpoint::{ensure_public, is_public, EndpointResolver};
+pub use entity::{generate_secret, DeliveryStatus, WebhookSubscription, MAX_EVENT_TYPES};
+pub use repository::{Delivery, WebhookDeliveries, WebhookRepository, WebhookSender};
+pub use value_objects::WebhookId;
@@ -0,0 +1,116 @@
+//! Webhook repository, delivery and sender ports
+
+use super::entity::{DeliveryStatus, WebhookSubscription};
+use super::value_objects::WebhookId;
+use crate::shared::domain::{AuditEntry, DomainError, StoredEvent, TenantId};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Repository for webhook subscription aggregate
+///
+/// Every method is scoped to `tenant`: subscriptions of other tenants are neither
+/// found nor changed.
+#[async_trait::async_trait]
+pub trait WebhookRepository: Send + Sync {
+    /// Find subscription by ID
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &WebhookId,
+    ) -> Result<Option<WebhookSubscription>, DomainError>;
+    /// Find every subscription of the tenant, oldest first
+    async fn find_all(&self, tenant: &TenantId) -> Result<Vec<WebhookSubscription>, DomainError>;
+    /// Insert a new subscription (fails if the ID exists)
+    async fn insert(
+        &self,
+        tenant: &TenantId,
+        subscription: &WebhookSubscription,
+    ) -> Result<(), DomainError>;
+    /// Write the URL, event types and active flag of an existing subscription, and
+    /// its failure count when it is reactivated (fails with `NotFound` if it no
+    /// longer exists)
+    async fn update(
+        &self,
+        tenant: &TenantId,
+        subscription: &WebhookSubscription,
+    ) -> Result<(), DomainError>;
+    /// Delete subscription by ID with its pending deliveries, returns true if it existed
+    async fn delete(&self, tenant: &TenantId, id: &WebhookId) -> Result<bool, DomainError>;
+    /// Deactivate the subscription and append `entry` to the audit trail of
+    /// `tenant` at once; returns false, recording nothing, if it was not active
+    async fn deactivate(
+        &self,
+        tenant: &TenantId,
+        id: &WebhookId,
+        entry: &AuditEntry,
+    ) -> Result<bool, DomainError>;
+}
+
+/// An outbox event due to be delivered to a subscription
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    /// Identifies the delivery among those of every subscription
+    pub id: i64,
+    /// Tenant of the subscription and the event
+    pub tenant: TenantId,
+    /// Subscription, as it was when the delivery was claimed
+    pub subscription: WebhookSubscription,
+    /// Event to deliver
+    pub event: StoredEvent,
+    /// Attempts made before this one
+    pub attempts: u32,
+}
+
+/// Outbox events on their way to subscriptions, with the retry state of each
+/// delivery
+///
+/// Deliveries are claimed for a lease: a worker that stops before reporting the
+/// outcome leaves them to be claimed again once it expires, so an event may be
+/// delivered more than once but is never lost.
+#[async_trait::async_trait]
+pub trait WebhookDeliveries: Send + Sync {
+    /// At most `limit` outbox events not fanned out yet, oldest first, of every tenant
+    async fn pending_events(&self, limit: u32) -> Result<Vec<StoredEvent>, DomainError>;
+    /// Mark `event` fanned out and schedule its delivery to each of `subscriptions`
+    /// at once; returns false, scheduling nothing, if it already was
+    async fn fan_out(
+        &self,
+        event: &StoredEvent,
+        subscriptions: &[WebhookId],
+    ) -> Result<bool, DomainError>;
+    /// Claim at most `limit` deliveries that are due, oldest first, for `lease`
+    async fn claim_due(&self, lease: Duration, limit: u32) -> Result<Vec<Delivery>, DomainError>;
+    /// Remove `delivery`, which succeeded, and record `status` as the latest
+    /// delivery of its subscription, forgetting its failures
+    async fn delivered(
+        &self,
+        delivery: &Delivery,
+        status: &DeliveryStatus,
+    ) -> Result<(), DomainError>;
+    /// Record `status` as the latest delivery of the subscription of `delivery`,
+    /// which failed, and retry it at `retry_at`, or remove it when `None`; returns
+    /// the consecutive failures of the subscription, this one included
+    async fn failed(
+        &self,
+        delivery: &Delivery,
+        status: &DeliveryStatus,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<u32, DomainError>;
+    /// Remove `delivery` without attempting it, e.g. as its subscription is inactive
+    async fn discard(&self, delivery: &Delivery) -> Result<(), DomainError>;
+}
+
+/// Posts events to the endpoints of subscriptions
+#[async_trait::async_trait]
+pub trait WebhookSender: Send + Sync {
+    /// POST `event` to the URL of `subscription`, signed with its secret, and return
+    /// the status of the response
+    ///
+    /// # Errors
+    /// `Unavailable` when no response arrives.
+    async fn send(
+        &self,
+        subscription: &WebhookSubscription,
+        event: &StoredEvent,
+    ) -> Result<u16, DomainError>;
+}
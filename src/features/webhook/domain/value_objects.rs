@@ -0,0 +1,3 @@
+//! Webhook value objects
+
+crate::string_id!(WebhookId, "Webhook");
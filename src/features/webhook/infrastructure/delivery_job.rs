@@ -0,0 +1,40 @@
+//! Background delivery of outbox events to webhook subscriptions
+
+use crate::features::webhook::application::DeliverEventsUseCase;
+use crate::shared::infrastructure::jobs::{BackgroundJob, JobContext};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Runs [`DeliverEventsUseCase`] until no work is left, then waits for the next run
+#[derive(Clone)]
+pub struct WebhookDeliveryJob {
+    deliver_events: Arc<DeliverEventsUseCase>,
+}
+
+impl WebhookDeliveryJob {
+    /// Deliver events through `deliver_events`
+    #[must_use]
+    pub fn new(deliver_events: Arc<DeliverEventsUseCase>) -> Self {
+        Self { deliver_events }
+    }
+}
+
+#[async_trait::async_trait]
+impl BackgroundJob for WebhookDeliveryJob {
+    fn name(&self) -> &'static str {
+        "webhook_delivery"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    async fn run(&self, ctx: &JobContext) -> anyhow::Result<()> {
+        loop {
+            let report = self.deliver_events.execute().await?;
+            if !report.more || ctx.is_shutting_down() {
+                return Ok(());
+            }
+        }
+    }
+}
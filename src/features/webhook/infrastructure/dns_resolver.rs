@@ -0,0 +1,70 @@
+//! Resolution of webhook endpoints through the system resolver
+
+use crate::features::webhook::domain::{is_public, EndpointResolver};
+use crate::shared::domain::DomainError;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::IpAddr;
+
+/// [`EndpointResolver`] asking the system resolver
+///
+/// The outbound client of the deliveries resolves through it too, refusing to connect
+/// when a name resolves to an address that is not public. That closes the gap between
+/// the check before a delivery and the connection, in which the name could be
+/// rebound to an internal address.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DnsResolver;
+
+async fn lookup(host: &str, port: u16) -> std::io::Result<Vec<IpAddr>> {
+    let addresses = tokio::net::lookup_host((host, port)).await?;
+    Ok(addresses.map(|address| address.ip()).collect())
+}
+
+#[async_trait::async_trait]
+impl EndpointResolver for DnsResolver {
+    async fn resolve(&self, host: &str, port: u16) -> Result<Vec<IpAddr>, DomainError> {
+        lookup(host, port).await.map_err(|e| {
+            DomainError::validation("URL", format!("must have a host that resolves: {e}"))
+        })
+    }
+}
+
+impl Resolve for DnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses = lookup(name.as_str(), 0).await?;
+            if !addresses.iter().copied().all(is_public) {
+                let name = name.as_str();
+                return Err(format!("{name} resolves to an address that is not public").into());
+            }
+            let addresses: Addrs =
+                Box::new(addresses.into_iter().map(|ip| std::net::SocketAddr::new(ip, 0)));
+            Ok(addresses)
+        })
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::shared::infrastructure::http_client::{
+        HttpClientConfig, OutboundError, OutboundHttp, ReqwestHttp,
+    };
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn client_should_refuse_to_connect_to_names_of_internal_addresses() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bound");
+        let port = listener.local_addr().expect("address").port();
+        let config = HttpClientConfig {
+            request_timeout: std::time::Duration::from_secs(1),
+            ..HttpClientConfig::default()
+        };
+        let http = ReqwestHttp::with_resolver(&config, Arc::new(DnsResolver)).expect("client");
+
+        // Connecting would succeed, the listener never answering the request
+        let result = http.post_json(&format!("http://localhost:{port}/"), b"{}".to_vec()).await;
+        let error = result.expect_err("refused");
+        assert!(matches!(error, OutboundError::Connect(_)), "{error:?}");
+    }
+}
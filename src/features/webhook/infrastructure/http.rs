@@ -0,0 +1,189 @@
+//! Webhook HTTP handlers
+
+pub use crate::api_types::{
+    CreateWebhookRequest, UpdateWebhookRequest, WebhookDeliveryResponse, WebhookResponse,
+};
+use crate::features::webhook::application::{CreateWebhookCommand, UpdateWebhookCommand};
+use crate::features::webhook::domain::WebhookSubscription;
+use crate::features::webhook::{WebhookState, NAME};
+use crate::shared::domain::entity::Entity;
+use crate::shared::infrastructure::feature::{ExpectedRoute, FeatureRouter};
+use crate::shared::infrastructure::http::{ApiError, ApiPath, Negotiated};
+use crate::shared::infrastructure::instrumentation::budgeted;
+use crate::shared::infrastructure::request_context::{self, RequestContext};
+use crate::shared::infrastructure::tenant::TenantContext;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Response,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+type ApiResult<T> = Result<T, ApiError>;
+
+impl From<WebhookSubscription> for WebhookResponse {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            id: subscription.id().value().to_owned(),
+            url: subscription.url().to_owned(),
+            event_types: subscription.event_types().to_vec(),
+            active: subscription.is_active(),
+            consecutive_failures: subscription.consecutive_failures(),
+            last_delivery: subscription.last_delivery().map(|status| WebhookDeliveryResponse {
+                at: status.at,
+                succeeded: status.succeeded(),
+                status_code: status.status_code,
+                error: status.error.clone(),
+            }),
+            secret: None,
+        }
+    }
+}
+
+/// Routes served by [`routes`]
+pub const ROUTES: &[ExpectedRoute] = &[
+    ExpectedRoute::get("/"),
+    ExpectedRoute::post("/"),
+    ExpectedRoute::get("/{id}"),
+    ExpectedRoute::put("/{id}"),
+    ExpectedRoute::delete("/{id}"),
+];
+
+/// Webhook feature routes, nested under `/webhooks`
+pub fn routes(state: Arc<WebhookState>) -> FeatureRouter<()> {
+    let router = Router::new()
+        .route("/", get(list_webhooks).post(create_webhook))
+        .route("/{id}", get(get_webhook).put(update_webhook).delete(delete_webhook));
+    FeatureRouter {
+        name: NAME,
+        prefix: "/webhooks",
+        router: router.with_state(state),
+        manifest: ROUTES,
+    }
+}
+
+/// Subscribe an endpoint to events, returning its secret this once
+async fn create_webhook(
+    State(state): State<Arc<WebhookState>>,
+    TenantContext(tenant): TenantContext,
+    context: RequestContext,
+    Negotiated(body): Negotiated<CreateWebhookRequest>,
+) -> ApiResult<Response> {
+    let command =
+        CreateWebhookCommand { url: body.url, secret: body.secret, event_types: body.event_types };
+    let subscription = budgeted("create_webhook", state.create_webhook.execute(&tenant, command))
+        .await
+        .map_err(ApiError::from)?;
+    let location = format!("/webhooks/{}", subscription.id().value());
+    let secret = subscription.secret().to_owned();
+    let response = WebhookResponse { secret: Some(secret), ..subscription.into() };
+    Ok(request_context::created(&context, &location, response))
+}
+
+/// Get a webhook subscription by ID, with the outcome of its latest delivery
+async fn get_webhook(
+    State(state): State<Arc<WebhookState>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+) -> ApiResult<Negotiated<WebhookResponse>> {
+    let subscription = budgeted("get_webhook", state.get_webhook.execute(&tenant, &id))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Negotiated(subscription.into()))
+}
+
+/// List every webhook subscription, oldest first, with the outcome of its latest delivery
+async fn list_webhooks(
+    State(state): State<Arc<WebhookState>>,
+    TenantContext(tenant): TenantContext,
+) -> ApiResult<Negotiated<Vec<WebhookResponse>>> {
+    let subscriptions = budgeted("list_webhooks", state.list_webhooks.execute(&tenant))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Negotiated(subscriptions.into_iter().map(Into::into).collect()))
+}
+
+/// Replace the URL, event types and active flag of a webhook subscription
+async fn update_webhook(
+    State(state): State<Arc<WebhookState>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+    Negotiated(body): Negotiated<UpdateWebhookRequest>,
+) -> ApiResult<Negotiated<WebhookResponse>> {
+    let command =
+        UpdateWebhookCommand { url: body.url, event_types: body.event_types, active: body.active };
+    let subscription =
+        budgeted("update_webhook", state.update_webhook.execute(&tenant, &id, command))
+            .await
+            .map_err(ApiError::from)?;
+    Ok(Negotiated(subscription.into()))
+}
+
+/// Delete a webhook subscription with its pending deliveries
+async fn delete_webhook(
+    State(state): State<Arc<WebhookState>>,
+    TenantContext(tenant): TenantContext,
+    ApiPath(id): ApiPath<String>,
+) -> ApiResult<StatusCode> {
+    budgeted("delete_webhook", state.delete_webhook.execute(&tenant, &id))
+        .await
+        .map_err(ApiError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{in_memory_app, send};
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn webhooks_should_be_created_updated_and_deleted_without_revealing_secrets() {
+        let app = in_memory_app();
+        let body = json!({
+            "url": "https://93.184.215.14/tasks",
+            "eventTypes": ["user.onboarded", " tasks.reassigned", "user.onboarded"],
+        });
+        let (status, created) = send(&app, Method::POST, "/webhooks", Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(created["secret"].as_str().is_some_and(|s| s.starts_with("whsec_")), "{created}");
+        assert_eq!(created["eventTypes"], json!(["tasks.reassigned", "user.onboarded"]));
+        assert_eq!((&created["active"], &created["lastDelivery"]), (&json!(true), &json!(null)));
+        let uri = format!("/webhooks/{}", created["id"].as_str().unwrap_or_default());
+
+        let (status, listed) = send(&app, Method::GET, "/webhooks", None).await;
+        assert_eq!((status, listed.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+        assert!(listed[0].get("secret").is_none(), "{listed}");
+        let url = "http://[2606:4700::1111]/tasks";
+        let update = json!({"url": url, "eventTypes": [], "active": false});
+        let (status, updated) = send(&app, Method::PUT, &uri, Some(update)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((&updated["url"], &updated["active"]), (&json!(url), &json!(false)));
+        let internal = json!({"url": "http://10.0.0.5/", "eventTypes": [], "active": true});
+        let (status, _) = send(&app, Method::PUT, &uri, Some(internal)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        assert_eq!(send(&app, Method::DELETE, &uri, None).await.0, StatusCode::NO_CONTENT);
+        assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(send(&app, Method::DELETE, &uri, None).await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn webhooks_should_reject_invalid_or_internal_urls_and_short_secrets() {
+        let app = in_memory_app();
+        for body in [
+            json!({"url": "ftp://hooks.example.com"}),
+            json!({"url": "not a url"}),
+            json!({"url": "https://hooks.example.com", "secret": "short"}),
+            json!({"url": "http://127.0.0.1:9090/metrics"}),
+            json!({"url": "http://169.254.169.254/latest/meta-data/"}),
+            json!({"url": "http://[::ffff:10.0.0.1]/"}),
+        ] {
+            let (status, error) = send(&app, Method::POST, "/webhooks", Some(body)).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(error["code"], "VALIDATION_ERROR");
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Delivery of events to webhook endpoints over HTTP, signed with HMAC-SHA256
+
+use crate::features::webhook::domain::{
+    ensure_public, EndpointResolver, WebhookSender, WebhookSubscription,
+};
+use crate::shared::domain::{DomainError, Entity, StoredEvent};
+use crate::shared::events::EventEnvelope;
+use crate::shared::infrastructure::http_client::{OutboundError, OutboundHttp};
+use axum::http::{HeaderMap, HeaderValue};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// Header carrying the outbox ID of the event, identical across retries
+pub const WEBHOOK_ID_HEADER: &str = "x-webhook-id";
+/// Header carrying the Unix time the delivery was signed at
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+/// Header carrying `sha256=` and the hex HMAC-SHA256, keyed with the subscription's
+/// secret, of the timestamp, a `.` and the body
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// Body posted for every event: its outbox ID and subject next to the envelope
+#[derive(Serialize)]
+struct EventBody<'a> {
+    id: i64,
+    subject: &'a str,
+    #[serde(flatten)]
+    envelope: EventEnvelope<&'a serde_json::Value>,
+}
+
+/// Posts events through [`OutboundHttp`] once each; the deliveries retry them
+pub struct HttpWebhookSender {
+    http: Arc<dyn OutboundHttp>,
+    resolver: Arc<dyn EndpointResolver>,
+}
+
+impl HttpWebhookSender {
+    /// Post events through `http` to endpoints `resolver` resolves to public addresses
+    #[must_use]
+    pub fn new(http: Arc<dyn OutboundHttp>, resolver: Arc<dyn EndpointResolver>) -> Self {
+        Self { http, resolver }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookSender for HttpWebhookSender {
+    async fn send(
+        &self,
+        subscription: &WebhookSubscription,
+        event: &StoredEvent,
+    ) -> Result<u16, DomainError> {
+        let envelope = EventEnvelope {
+            event_type: event.event_type.clone(),
+            version: event.version,
+            occurred_at: event.occurred_at,
+            payload: &event.payload,
+        };
+        let body = EventBody { id: event.id, subject: &event.subject, envelope };
+        let body = serde_json::to_vec(&body).map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        let signed_at = chrono::Utc::now().timestamp();
+        let signature = signature(subscription.secret(), &signed_at.to_string(), &body);
+        let mut headers = HeaderMap::new();
+        headers.insert(WEBHOOK_ID_HEADER, HeaderValue::from(event.id));
+        headers.insert(WEBHOOK_TIMESTAMP_HEADER, HeaderValue::from(signed_at));
+        headers.insert(WEBHOOK_SIGNATURE_HEADER, signature);
+        let url = subscription.url();
+        // The host may resolve elsewhere than when the subscription was saved
+        ensure_public(url, self.resolver.as_ref()).await?;
+        match self.http.post_json_with_headers(url, headers, body).await {
+            Ok(status) => Ok(status.as_u16()),
+            Err(e @ (OutboundError::Connect(_) | OutboundError::Request(_))) => {
+                let id = subscription.id().value();
+                tracing::warn!("Webhook '{id}' did not answer: {e}");
+                Err(DomainError::Unavailable(e.to_string()))
+            }
+            Err(e) => Err(DomainError::Unexpected(e.to_string())),
+        }
+    }
+}
+
+/// Value of [`WEBHOOK_SIGNATURE_HEADER`] for `body` signed at `signed_at` with `secret`
+fn signature(secret: &str, signed_at: &str, body: &[u8]) -> HeaderValue {
+    // HMAC takes keys of any length, so creating the MAC cannot fail
+    let digest = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_or_else(
+        |_| Vec::new(),
+        |mut mac| {
+            mac.update(signed_at.as_bytes());
+            mac.update(b".");
+            mac.update(body);
+            mac.finalize().into_bytes().to_vec()
+        },
+    );
+    let hex = digest.iter().fold(String::from("sha256="), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    });
+    HeaderValue::from_str(&hex).unwrap_or(HeaderValue::from_static(""))
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::webhook::domain::WebhookId;
+    use crate::shared::domain::TenantId;
+    use axum::http::StatusCode;
+    use std::net::IpAddr;
+    use std::sync::Mutex;
+
+    /// Fake answering with `answer` and recording the headers and body of each request
+    struct RecordingHttp {
+        answer: fn() -> Result<StatusCode, OutboundError>,
+        requests: Mutex<Vec<(HeaderMap, Vec<u8>)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl OutboundHttp for RecordingHttp {
+        async fn post_json_with_headers(
+            &self,
+            _: &str,
+            headers: HeaderMap,
+            body: Vec<u8>,
+        ) -> Result<StatusCode, OutboundError> {
+            self.requests.lock().expect("lock").push((headers, body));
+            (self.answer)()
+        }
+    }
+
+    /// Resolves every host name to the same address
+    struct Resolving(IpAddr);
+
+    #[async_trait::async_trait]
+    impl EndpointResolver for Resolving {
+        async fn resolve(&self, _: &str, _: u16) -> Result<Vec<IpAddr>, DomainError> {
+            Ok(vec![self.0])
+        }
+    }
+
+    /// Resolver of the endpoints to a public address
+    fn public() -> Arc<Resolving> {
+        Arc::new(Resolving([93, 184, 215, 14].into()))
+    }
+
+    fn subscription() -> WebhookSubscription {
+        let secret = "whsec_0123456789abcdef".to_owned();
+        WebhookSubscription::new(WebhookId::generate(), "https://hooks.example.com", secret, vec![])
+            .expect("valid subscription")
+    }
+
+    fn event() -> StoredEvent {
+        StoredEvent {
+            id: 42,
+            tenant: TenantId::default(),
+            event_type: "user.onboarded".into(),
+            version: 1,
+            subject: "user-1".into(),
+            payload: serde_json::json!({"name": "Alice"}),
+            occurred_at: chrono::DateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[tokio::test]
+    async fn send_should_sign_the_timestamp_and_body_with_the_secret() {
+        let http = Arc::new(RecordingHttp {
+            answer: || Ok(StatusCode::ACCEPTED),
+            requests: Mutex::default(),
+        });
+        let sender = HttpWebhookSender::new(Arc::clone(&http) as _, public());
+        let subscription = subscription();
+
+        let status = sender.send(&subscription, &event()).await.expect("sent");
+        assert_eq!(status, 202);
+        let (headers, body) = http.requests.lock().expect("lock").remove(0);
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+        assert_eq!(header(WEBHOOK_ID_HEADER), "42");
+        let signed_at = header(WEBHOOK_TIMESTAMP_HEADER);
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(subscription.secret().as_bytes()).expect("any key");
+        mac.update(format!("{signed_at}.").as_bytes());
+        mac.update(&body);
+        let digest = header(WEBHOOK_SIGNATURE_HEADER).strip_prefix("sha256=").unwrap_or_default();
+        let digest: Vec<u8> = (0..digest.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digest[i..i + 2], 16).expect("hex"))
+            .collect();
+        mac.verify_slice(&digest).expect("valid signature");
+
+        let body: serde_json::Value = serde_json::from_slice(&body).expect("JSON body");
+        let expected = serde_json::json!({
+            "id": 42,
+            "subject": "user-1",
+            "event_type": "user.onboarded",
+            "version": 1,
+            "occurred_at": "1970-01-01T00:00:00.000Z",
+            "payload": {"name": "Alice"},
+        });
+        assert_eq!(body, expected);
+    }
+
+    #[tokio::test]
+    async fn unanswered_posts_should_be_unavailable() {
+        let http = Arc::new(RecordingHttp {
+            answer: || Err(OutboundError::Connect("connection refused".into())),
+            requests: Mutex::default(),
+        });
+        let result = HttpWebhookSender::new(http, public()).send(&subscription(), &event()).await;
+        assert!(matches!(result, Err(DomainError::Unavailable(_))), "{result:?}");
+    }
+
+    #[tokio::test]
+    async fn send_should_refuse_endpoints_resolving_to_internal_addresses() {
+        let http = Arc::new(RecordingHttp {
+            answer: || Ok(StatusCode::OK),
+            requests: Mutex::default(),
+        });
+        let rebound = Arc::new(Resolving([169, 254, 169, 254].into()));
+        let sender = HttpWebhookSender::new(Arc::clone(&http) as _, rebound);
+
+        let result = sender.send(&subscription(), &event()).await;
+        assert!(matches!(result, Err(DomainError::Validation(_))), "{result:?}");
+        assert!(http.requests.lock().expect("lock").is_empty());
+    }
+}
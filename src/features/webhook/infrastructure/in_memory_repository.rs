@@ -0,0 +1,291 @@
+//! In-memory webhook repository implementation for tests and examples
+
+use crate::features::webhook::domain::{
+    Delivery, DeliveryStatus, WebhookDeliveries, WebhookId, WebhookRepository,
+    WebhookSubscription,
+};
+use crate::shared::domain::{AuditEntry, DomainError, Entity, StoredEvent, TenantId};
+use crate::shared::infrastructure::maintenance::CleanupTask;
+use crate::shared::infrastructure::outbox::InMemoryOutbox;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// A delivery waiting for its next attempt
+struct StoredDelivery {
+    id: i64,
+    subscription_id: WebhookId,
+    event: StoredEvent,
+    attempts: u32,
+    next_attempt_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Subscriptions with their tenant, oldest first
+    subscriptions: Vec<(TenantId, WebhookSubscription)>,
+    deliveries: Vec<StoredDelivery>,
+    last_delivery_id: i64,
+    audit_trail: Vec<(TenantId, AuditEntry)>,
+}
+
+impl State {
+    fn subscription_mut(&mut self, id: &WebhookId) -> Option<&mut WebhookSubscription> {
+        self.subscriptions.iter_mut().map(|(_, s)| s).find(|s| s.id() == id)
+    }
+
+    fn remove_delivery(&mut self, id: i64) {
+        self.deliveries.retain(|delivery| delivery.id != id);
+    }
+}
+
+/// In-memory implementation of the webhook repository and deliveries, reading
+/// events from an [`InMemoryOutbox`]
+#[derive(Default)]
+pub struct InMemoryWebhookRepository {
+    state: RwLock<State>,
+    outbox: Arc<InMemoryOutbox>,
+}
+
+impl InMemoryWebhookRepository {
+    /// Deliver the events of `outbox`, e.g. the one of the in-memory unit of work
+    #[must_use]
+    pub fn new(outbox: Arc<InMemoryOutbox>) -> Self {
+        Self { state: RwLock::default(), outbox }
+    }
+
+    /// Outbox the events are read from; hand it to the units of work recording them
+    #[must_use]
+    pub fn outbox(&self) -> &Arc<InMemoryOutbox> {
+        &self.outbox
+    }
+
+    /// Audit entries of `tenant` recorded by deactivations, oldest first
+    pub async fn audit_trail(&self, tenant: &TenantId) -> Vec<AuditEntry> {
+        let state = self.state.read().await;
+        let trail = state.audit_trail.iter().filter(|(t, _)| t == tenant);
+        trail.map(|(_, entry)| entry.clone()).collect()
+    }
+
+    /// Make every delivery due now, as if its retry delay had passed
+    pub async fn expire_retry_delays(&self) {
+        let now = Utc::now();
+        let mut state = self.state.write().await;
+        state.deliveries.iter_mut().for_each(|delivery| delivery.next_attempt_at = now);
+    }
+
+    /// Record `status` as the latest delivery of the subscription of `delivery`,
+    /// returning the subscription
+    fn record<'a>(
+        state: &'a mut State,
+        delivery: &Delivery,
+        status: &DeliveryStatus,
+    ) -> Option<&'a mut WebhookSubscription> {
+        let subscription = state.subscription_mut(delivery.subscription.id())?;
+        subscription.record_delivery(status.clone());
+        Some(subscription)
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookRepository for InMemoryWebhookRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &WebhookId,
+    ) -> Result<Option<WebhookSubscription>, DomainError> {
+        let state = self.state.read().await;
+        let mut found = state.subscriptions.iter().filter(|(t, s)| t == tenant && s.id() == id);
+        Ok(found.next().map(|(_, subscription)| subscription.clone()))
+    }
+
+    async fn find_all(&self, tenant: &TenantId) -> Result<Vec<WebhookSubscription>, DomainError> {
+        let state = self.state.read().await;
+        let found = state.subscriptions.iter().filter(|(t, _)| t == tenant);
+        Ok(found.map(|(_, subscription)| subscription.clone()).collect())
+    }
+
+    async fn insert(
+        &self,
+        tenant: &TenantId,
+        subscription: &WebhookSubscription,
+    ) -> Result<(), DomainError> {
+        let mut state = self.state.write().await;
+        if state.subscription_mut(subscription.id()).is_some() {
+            return Err(DomainError::already_exists(WebhookId::entity_name(), "ID"));
+        }
+        state.subscriptions.push((tenant.clone(), subscription.clone()));
+        Ok(())
+    }
+
+    async fn update(
+        &self,
+        tenant: &TenantId,
+        subscription: &WebhookSubscription,
+    ) -> Result<(), DomainError> {
+        let mut state = self.state.write().await;
+        let stored = state
+            .subscriptions
+            .iter_mut()
+            .find(|(t, s)| t == tenant && s.id() == subscription.id())
+            .map(|(_, stored)| stored)
+            .ok_or_else(|| DomainError::not_found(WebhookId::entity_name()))?;
+        // The delivery state is the worker's, but for the failures a reactivation forgets
+        let failures = if subscription.is_active() && !stored.is_active() {
+            0
+        } else {
+            stored.consecutive_failures()
+        };
+        *stored = WebhookSubscription::reconstitute(
+            stored.id().clone(),
+            subscription.url().to_owned(),
+            stored.secret().to_owned(),
+            subscription.event_types().to_vec(),
+            subscription.is_active(),
+            failures,
+            stored.last_delivery().cloned(),
+        );
+        Ok(())
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &WebhookId) -> Result<bool, DomainError> {
+        let mut state = self.state.write().await;
+        let before = state.subscriptions.len();
+        state.subscriptions.retain(|(t, s)| !(t == tenant && s.id() == id));
+        let deleted = state.subscriptions.len() < before;
+        if deleted {
+            state.deliveries.retain(|delivery| &delivery.subscription_id != id);
+        }
+        Ok(deleted)
+    }
+
+    async fn deactivate(
+        &self,
+        tenant: &TenantId,
+        id: &WebhookId,
+        entry: &AuditEntry,
+    ) -> Result<bool, DomainError> {
+        let mut state = self.state.write().await;
+        let Some(stored) = state
+            .subscriptions
+            .iter_mut()
+            .find(|(t, s)| t == tenant && s.id() == id && s.is_active())
+            .map(|(_, stored)| stored)
+        else {
+            return Ok(false);
+        };
+        stored.deactivate();
+        state.audit_trail.push((tenant.clone(), entry.clone()));
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookDeliveries for InMemoryWebhookRepository {
+    async fn pending_events(&self, limit: u32) -> Result<Vec<StoredEvent>, DomainError> {
+        Ok(self.outbox.pending(limit as usize).await)
+    }
+
+    async fn fan_out(
+        &self,
+        event: &StoredEvent,
+        subscriptions: &[WebhookId],
+    ) -> Result<bool, DomainError> {
+        let mut state = self.state.write().await;
+        if !self.outbox.mark_processed(event.id).await {
+            return Ok(false);
+        }
+        let now = Utc::now();
+        for subscription_id in subscriptions {
+            state.last_delivery_id += 1;
+            let delivery = StoredDelivery {
+                id: state.last_delivery_id,
+                subscription_id: subscription_id.clone(),
+                event: event.clone(),
+                attempts: 0,
+                next_attempt_at: now,
+            };
+            state.deliveries.push(delivery);
+        }
+        Ok(true)
+    }
+
+    async fn claim_due(&self, lease: Duration, limit: u32) -> Result<Vec<Delivery>, DomainError> {
+        let now = Utc::now();
+        let leased_until = now + lease;
+        let mut state = self.state.write().await;
+        let State { subscriptions, deliveries, .. } = &mut *state;
+        deliveries.sort_by_key(|delivery| (delivery.next_attempt_at, delivery.id));
+        let due = deliveries.iter_mut().filter(|delivery| delivery.next_attempt_at <= now);
+        let mut claimed = Vec::new();
+        for delivery in due.take(limit as usize) {
+            delivery.next_attempt_at = leased_until;
+            let found = subscriptions.iter().find(|(_, s)| s.id() == &delivery.subscription_id);
+            if let Some((tenant, subscription)) = found {
+                claimed.push(Delivery {
+                    id: delivery.id,
+                    tenant: tenant.clone(),
+                    subscription: subscription.clone(),
+                    event: delivery.event.clone(),
+                    attempts: delivery.attempts,
+                });
+            }
+        }
+        Ok(claimed)
+    }
+
+    async fn delivered(
+        &self,
+        delivery: &Delivery,
+        status: &DeliveryStatus,
+    ) -> Result<(), DomainError> {
+        let mut state = self.state.write().await;
+        Self::record(&mut state, delivery, status);
+        state.remove_delivery(delivery.id);
+        Ok(())
+    }
+
+    async fn failed(
+        &self,
+        delivery: &Delivery,
+        status: &DeliveryStatus,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<u32, DomainError> {
+        let mut state = self.state.write().await;
+        let failures = Self::record(&mut state, delivery, status)
+            .map_or(0, |subscription| subscription.consecutive_failures());
+        match retry_at {
+            Some(retry_at) => {
+                let stored = state.deliveries.iter_mut().find(|d| d.id == delivery.id);
+                if let Some(stored) = stored {
+                    stored.attempts = stored.attempts.saturating_add(1);
+                    stored.next_attempt_at = retry_at;
+                }
+            }
+            None => state.remove_delivery(delivery.id),
+        }
+        Ok(failures)
+    }
+
+    async fn discard(&self, delivery: &Delivery) -> Result<(), DomainError> {
+        self.state.write().await.remove_delivery(delivery.id);
+        Ok(())
+    }
+}
+
+/// Deletes outbox events processed before the cutoff whose deliveries have all ended
+#[async_trait::async_trait]
+impl CleanupTask for InMemoryWebhookRepository {
+    fn name(&self) -> &'static str {
+        "outbox_events"
+    }
+
+    async fn delete_batch(&self, before: DateTime<Utc>, limit: u32) -> Result<u64, DomainError> {
+        // The state first, as in fan_out, so that no delivery is scheduled meanwhile
+        let state = self.state.read().await;
+        let delivering = |id| state.deliveries.iter().any(|delivery| delivery.event.id == id);
+        let deleted = self.outbox.delete_processed(before, limit as usize, delivering).await;
+        Ok(deleted as u64)
+    }
+}
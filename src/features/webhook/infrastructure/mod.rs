@@ -0,0 +1,14 @@
+//! Webhook infrastructure layer
+
+pub mod delivery_job;
+pub mod dns_resolver;
+pub mod http;
+pub mod http_sender;
+pub mod in_memory_repository;
+pub mod pg_repository;
+
+pub use delivery_job::WebhookDeliveryJob;
+pub use dns_resolver::DnsResolver;
+pub use http_sender::HttpWebhookSender;
+pub use in_memory_repository::InMemoryWebhookRepository;
+pub use pg_repository::PgWebhookRepository;
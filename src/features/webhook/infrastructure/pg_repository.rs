@@ -0,0 +1,489 @@
+//! `PostgreSQL` webhook repository implementation
+
+use crate::features::webhook::domain::{
+    Delivery, DeliveryStatus, WebhookDeliveries, WebhookId, WebhookRepository,
+    WebhookSubscription,
+};
+use crate::shared::domain::{AuditEntry, DomainError, Entity, StoredEvent, TenantId};
+use crate::shared::infrastructure::database::{acquire, run_query};
+use crate::shared::infrastructure::maintenance::CleanupTask;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// Columns of [`SubscriptionRow`]
+const SUBSCRIPTION_COLUMNS: &str = "s.id, s.url, s.secret, s.event_types, s.active, \
+     s.consecutive_failures, s.last_delivery_at, s.last_delivery_status, s.last_delivery_error";
+
+/// `PostgreSQL` implementation of the webhook repository and deliveries, reading
+/// events from the `outbox_events` table
+#[derive(Clone)]
+pub struct PgWebhookRepository {
+    pool: PgPool,
+}
+
+impl PgWebhookRepository {
+    /// Create a new `PostgreSQL` webhook repository
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record `status` as the latest delivery of the subscription of `delivery`,
+    /// returning its consecutive failures
+    async fn record(
+        conn: &mut sqlx::PgConnection,
+        delivery: &Delivery,
+        status: &DeliveryStatus,
+    ) -> Result<u32, DomainError> {
+        let query = sqlx::query_scalar::<_, i32>(
+            "UPDATE webhook_subscriptions SET \
+             consecutive_failures = CASE WHEN $1 THEN 0 ELSE consecutive_failures + 1 END, \
+             last_delivery_at = $2, last_delivery_status = $3, last_delivery_error = $4 \
+             WHERE id = $5 RETURNING consecutive_failures",
+        )
+        .bind(status.succeeded())
+        .bind(status.at)
+        .bind(status.status_code.map(|code| i16::try_from(code).unwrap_or(i16::MAX)))
+        .bind(status.error.as_deref())
+        .bind(delivery.subscription.id().value());
+        let failures = run_query(query.fetch_optional(&mut *conn), "record", "delivery").await?;
+        Ok(failures.map_or(0, |failures| u32::try_from(failures).unwrap_or_default()))
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookRepository for PgWebhookRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &WebhookId,
+    ) -> Result<Option<WebhookSubscription>, DomainError> {
+        let sql = format!(
+            "SELECT {SUBSCRIPTION_COLUMNS} FROM webhook_subscriptions s \
+             WHERE s.tenant_id = $1 AND s.id = $2"
+        );
+        let query =
+            sqlx::query_as::<_, SubscriptionRow>(&sql).bind(tenant.value()).bind(id.value());
+        let mut conn = acquire(&self.pool, "find", "webhook").await?;
+        let row = run_query(query.fetch_optional(&mut *conn), "find", "webhook").await?;
+        Ok(row.map(SubscriptionRow::into_domain))
+    }
+
+    async fn find_all(&self, tenant: &TenantId) -> Result<Vec<WebhookSubscription>, DomainError> {
+        let sql = format!(
+            "SELECT {SUBSCRIPTION_COLUMNS} FROM webhook_subscriptions s \
+             WHERE s.tenant_id = $1 ORDER BY s.created_at, s.id"
+        );
+        let query = sqlx::query_as::<_, SubscriptionRow>(&sql).bind(tenant.value());
+        let mut conn = acquire(&self.pool, "find_all", "webhook").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find_all", "webhook").await?;
+        Ok(rows.into_iter().map(SubscriptionRow::into_domain).collect())
+    }
+
+    async fn insert(
+        &self,
+        tenant: &TenantId,
+        subscription: &WebhookSubscription,
+    ) -> Result<(), DomainError> {
+        let query = sqlx::query(
+            "INSERT INTO webhook_subscriptions (id, tenant_id, url, secret, event_types, active) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(subscription.id().value())
+        .bind(tenant.value())
+        .bind(subscription.url())
+        .bind(subscription.secret())
+        .bind(subscription.event_types())
+        .bind(subscription.is_active());
+        let mut conn = acquire(&self.pool, "insert", "webhook").await?;
+        run_query(query.execute(&mut *conn), "insert", "webhook").await?;
+        Ok(())
+    }
+
+    async fn update(
+        &self,
+        tenant: &TenantId,
+        subscription: &WebhookSubscription,
+    ) -> Result<(), DomainError> {
+        let query = sqlx::query(
+            "UPDATE webhook_subscriptions SET url = $1, event_types = $2, active = $3, \
+             consecutive_failures = CASE WHEN $3 AND NOT active THEN 0 \
+             ELSE consecutive_failures END \
+             WHERE tenant_id = $4 AND id = $5",
+        )
+        .bind(subscription.url())
+        .bind(subscription.event_types())
+        .bind(subscription.is_active())
+        .bind(tenant.value())
+        .bind(subscription.id().value());
+        let mut conn = acquire(&self.pool, "update", "webhook").await?;
+        let result = run_query(query.execute(&mut *conn), "update", "webhook").await?;
+        if result.rows_affected() == 0 {
+            return Err(DomainError::not_found(WebhookId::entity_name()));
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &WebhookId) -> Result<bool, DomainError> {
+        // Pending deliveries cascade
+        let query =
+            sqlx::query("DELETE FROM webhook_subscriptions WHERE tenant_id = $1 AND id = $2")
+                .bind(tenant.value())
+                .bind(id.value());
+        let mut conn = acquire(&self.pool, "delete", "webhook").await?;
+        let result = run_query(query.execute(&mut *conn), "delete", "webhook").await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn deactivate(
+        &self,
+        tenant: &TenantId,
+        id: &WebhookId,
+        entry: &AuditEntry,
+    ) -> Result<bool, DomainError> {
+        let conn = acquire(&self.pool, "deactivate", "webhook").await?;
+        let mut tx =
+            run_query(sqlx::Transaction::begin(conn, None), "deactivate", "webhook").await?;
+        let query = sqlx::query(
+            "UPDATE webhook_subscriptions SET active = FALSE \
+             WHERE tenant_id = $1 AND id = $2 AND active",
+        )
+        .bind(tenant.value())
+        .bind(id.value());
+        let result = run_query(query.execute(&mut *tx), "deactivate", "webhook").await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        let query = sqlx::query(
+            "INSERT INTO audit_log (tenant_id, action, subject, details, client_ip) \
+             VALUES ($1, $2, $3, $4::jsonb, $5::inet)",
+        )
+        .bind(tenant.value())
+        .bind(entry.action)
+        .bind(&entry.subject)
+        .bind(entry.details.to_string())
+        .bind(entry.client_ip.map(|ip| ip.to_string()));
+        run_query(query.execute(&mut *tx), "record", "audit entry").await?;
+        run_query(tx.commit(), "deactivate", "webhook").await?;
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookDeliveries for PgWebhookRepository {
+    async fn pending_events(&self, limit: u32) -> Result<Vec<StoredEvent>, DomainError> {
+        let query = sqlx::query_as::<_, EventRow>(
+            "SELECT id AS event_id, tenant_id, event_type, version, subject, \
+             payload::text AS payload, occurred_at \
+             FROM outbox_events WHERE processed_at IS NULL ORDER BY id LIMIT $1",
+        )
+        .bind(i64::from(limit));
+        let mut conn = acquire(&self.pool, "find", "outbox event").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "find", "outbox event").await?;
+        Ok(rows.into_iter().map(EventRow::into_domain).collect())
+    }
+
+    async fn fan_out(
+        &self,
+        event: &StoredEvent,
+        subscriptions: &[WebhookId],
+    ) -> Result<bool, DomainError> {
+        let conn = acquire(&self.pool, "fan out", "outbox event").await?;
+        let mut tx =
+            run_query(sqlx::Transaction::begin(conn, None), "fan out", "outbox event").await?;
+        // Concurrent workers wait for this row lock, then find the event processed
+        let query = sqlx::query(
+            "UPDATE outbox_events SET processed_at = CURRENT_TIMESTAMP \
+             WHERE id = $1 AND processed_at IS NULL",
+        )
+        .bind(event.id);
+        let result = run_query(query.execute(&mut *tx), "fan out", "outbox event").await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        let ids: Vec<&str> = subscriptions.iter().map(WebhookId::value).collect();
+        let query = sqlx::query(
+            "INSERT INTO webhook_deliveries (subscription_id, event_id) \
+             SELECT subscription_id, $2 FROM UNNEST($1::TEXT[]) AS subscription_id \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(ids)
+        .bind(event.id);
+        run_query(query.execute(&mut *tx), "schedule", "delivery").await?;
+        run_query(tx.commit(), "fan out", "outbox event").await?;
+        Ok(true)
+    }
+
+    async fn claim_due(&self, lease: Duration, limit: u32) -> Result<Vec<Delivery>, DomainError> {
+        let sql = format!(
+            "WITH claimed AS ( \
+                 UPDATE webhook_deliveries d \
+                 SET next_attempt_at = CURRENT_TIMESTAMP + $1 * INTERVAL '1 millisecond' \
+                 FROM ( \
+                     SELECT id FROM webhook_deliveries WHERE next_attempt_at <= CURRENT_TIMESTAMP \
+                     ORDER BY next_attempt_at, id LIMIT $2 FOR UPDATE SKIP LOCKED \
+                 ) due \
+                 WHERE d.id = due.id \
+                 RETURNING d.id, d.subscription_id, d.event_id, d.attempts \
+             ) \
+             SELECT c.id AS delivery_id, c.attempts, {SUBSCRIPTION_COLUMNS}, \
+                 e.id AS event_id, e.tenant_id, e.event_type, e.version, e.subject, \
+                 e.payload::text AS payload, e.occurred_at \
+             FROM claimed c \
+             JOIN webhook_subscriptions s ON s.id = c.subscription_id \
+             JOIN outbox_events e ON e.id = c.event_id \
+             ORDER BY c.id"
+        );
+        let query = sqlx::query_as::<_, DeliveryRow>(&sql)
+            .bind(i64::try_from(lease.as_millis()).unwrap_or(i64::MAX))
+            .bind(i64::from(limit));
+        let mut conn = acquire(&self.pool, "claim", "delivery").await?;
+        let rows = run_query(query.fetch_all(&mut *conn), "claim", "delivery").await?;
+        Ok(rows.into_iter().map(DeliveryRow::into_domain).collect())
+    }
+
+    async fn delivered(
+        &self,
+        delivery: &Delivery,
+        status: &DeliveryStatus,
+    ) -> Result<(), DomainError> {
+        let conn = acquire(&self.pool, "record", "delivery").await?;
+        let mut tx = run_query(sqlx::Transaction::begin(conn, None), "record", "delivery").await?;
+        Self::record(&mut tx, delivery, status).await?;
+        let query = sqlx::query("DELETE FROM webhook_deliveries WHERE id = $1").bind(delivery.id);
+        run_query(query.execute(&mut *tx), "delete", "delivery").await?;
+        run_query(tx.commit(), "record", "delivery").await
+    }
+
+    async fn failed(
+        &self,
+        delivery: &Delivery,
+        status: &DeliveryStatus,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<u32, DomainError> {
+        let conn = acquire(&self.pool, "record", "delivery").await?;
+        let mut tx = run_query(sqlx::Transaction::begin(conn, None), "record", "delivery").await?;
+        let failures = Self::record(&mut tx, delivery, status).await?;
+        let query = match retry_at {
+            Some(retry_at) => sqlx::query(
+                "UPDATE webhook_deliveries SET attempts = attempts + 1, next_attempt_at = $2 \
+                 WHERE id = $1",
+            )
+            .bind(delivery.id)
+            .bind(retry_at),
+            None => sqlx::query("DELETE FROM webhook_deliveries WHERE id = $1").bind(delivery.id),
+        };
+        run_query(query.execute(&mut *tx), "reschedule", "delivery").await?;
+        run_query(tx.commit(), "record", "delivery").await?;
+        Ok(failures)
+    }
+
+    async fn discard(&self, delivery: &Delivery) -> Result<(), DomainError> {
+        let query = sqlx::query("DELETE FROM webhook_deliveries WHERE id = $1").bind(delivery.id);
+        let mut conn = acquire(&self.pool, "delete", "delivery").await?;
+        run_query(query.execute(&mut *conn), "delete", "delivery").await?;
+        Ok(())
+    }
+}
+
+/// Deletes outbox events processed before the cutoff whose deliveries have all ended
+#[async_trait::async_trait]
+impl CleanupTask for PgWebhookRepository {
+    fn name(&self) -> &'static str {
+        "outbox_events"
+    }
+
+    async fn delete_batch(&self, before: DateTime<Utc>, limit: u32) -> Result<u64, DomainError> {
+        let query = sqlx::query(
+            "DELETE FROM outbox_events WHERE id IN ( \
+                 SELECT e.id FROM outbox_events e WHERE e.processed_at < $1 \
+                 AND NOT EXISTS (SELECT 1 FROM webhook_deliveries d WHERE d.event_id = e.id) \
+                 ORDER BY e.id LIMIT $2 FOR UPDATE SKIP LOCKED)",
+        )
+        .bind(before)
+        .bind(i64::from(limit));
+        let mut conn = acquire(&self.pool, "cleanup", "outbox event").await?;
+        let result = run_query(query.execute(&mut *conn), "cleanup", "outbox event").await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SubscriptionRow {
+    id: String,
+    url: String,
+    secret: String,
+    event_types: Vec<String>,
+    active: bool,
+    consecutive_failures: i32,
+    last_delivery_at: Option<DateTime<Utc>>,
+    last_delivery_status: Option<i16>,
+    last_delivery_error: Option<String>,
+}
+
+impl SubscriptionRow {
+    fn into_domain(self) -> WebhookSubscription {
+        let last_delivery = self.last_delivery_at.map(|at| DeliveryStatus {
+            at,
+            status_code: self.last_delivery_status.and_then(|code| u16::try_from(code).ok()),
+            error: self.last_delivery_error,
+        });
+        WebhookSubscription::reconstitute(
+            WebhookId::from_trusted(self.id),
+            self.url,
+            self.secret,
+            self.event_types,
+            self.active,
+            u32::try_from(self.consecutive_failures).unwrap_or_default(),
+            last_delivery,
+        )
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct EventRow {
+    event_id: i64,
+    tenant_id: String,
+    event_type: String,
+    version: i32,
+    subject: String,
+    /// JSON text, parsed here as the `json` feature of sqlx is not enabled
+    payload: String,
+    occurred_at: DateTime<Utc>,
+}
+
+impl EventRow {
+    fn into_domain(self) -> StoredEvent {
+        StoredEvent {
+            id: self.event_id,
+            tenant: TenantId::from_trusted(self.tenant_id),
+            event_type: self.event_type,
+            version: u16::try_from(self.version).unwrap_or_default(),
+            subject: self.subject,
+            payload: serde_json::from_str(&self.payload).unwrap_or_default(),
+            occurred_at: self.occurred_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DeliveryRow {
+    delivery_id: i64,
+    attempts: i32,
+    #[sqlx(flatten)]
+    subscription: SubscriptionRow,
+    #[sqlx(flatten)]
+    event: EventRow,
+}
+
+impl DeliveryRow {
+    fn into_domain(self) -> Delivery {
+        let event = self.event.into_domain();
+        Delivery {
+            id: self.delivery_id,
+            tenant: event.tenant.clone(),
+            subscription: self.subscription.into_domain(),
+            event,
+            attempts: u32::try_from(self.attempts).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::task::domain::UnitOfWork;
+    use crate::features::task::infrastructure::PgUnitOfWork;
+    use crate::features::webhook::domain::generate_secret;
+    use crate::shared::domain::OutboxEvent;
+
+    const LEASE: Duration = Duration::from_mins(1);
+
+    async fn record_event(pool: &PgPool, tenant: &TenantId) {
+        let unit_of_work = PgUnitOfWork::new(pool.clone());
+        let mut transaction = unit_of_work.begin().await.expect("begin");
+        let payload = serde_json::json!({"name": "Alice"});
+        let subject = "user-1".into();
+        let event = OutboxEvent { event_type: "user.onboarded", version: 1, subject, payload };
+        transaction.record_event(tenant, &event).await.expect("record");
+        transaction.commit().await.expect("commit");
+    }
+
+    fn status(status_code: u16, error: Option<&str>) -> DeliveryStatus {
+        let error = error.map(Into::into);
+        DeliveryStatus { at: Utc::now(), status_code: Some(status_code), error }
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn events_should_be_fanned_out_once_and_deliveries_leased_and_retried(pool: PgPool) {
+        let tenant = TenantId::default();
+        let repo = PgWebhookRepository::new(pool.clone());
+        let subscription = WebhookSubscription::new(
+            WebhookId::generate(),
+            "https://hooks.example.com",
+            generate_secret(),
+            vec!["user.onboarded".into()],
+        )
+        .expect("valid subscription");
+        repo.insert(&tenant, &subscription).await.expect("insert");
+        record_event(&pool, &tenant).await;
+
+        let events = repo.pending_events(10).await.expect("events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].payload, serde_json::json!({"name": "Alice"}));
+        let ids = [subscription.id().clone()];
+        assert!(repo.fan_out(&events[0], &ids).await.expect("fanned out"));
+        assert!(!repo.fan_out(&events[0], &ids).await.expect("fanned out"), "processed once");
+        assert!(repo.pending_events(10).await.expect("events").is_empty());
+
+        let due = repo.claim_due(LEASE, 10).await.expect("claimed");
+        assert_eq!(due.len(), 1);
+        assert_eq!((due[0].attempts, due[0].event.id), (0, events[0].id));
+        assert!(repo.claim_due(LEASE, 10).await.expect("claimed").is_empty(), "leased");
+
+        let failed = status(500, Some("boom"));
+        let failures = repo.failed(&due[0], &failed, Some(Utc::now())).await.expect("failed");
+        assert_eq!(failures, 1);
+        let due = repo.claim_due(LEASE, 10).await.expect("claimed");
+        assert_eq!((due.len(), due[0].attempts), (1, 1));
+        assert_eq!(due[0].subscription.consecutive_failures(), 1);
+        repo.delivered(&due[0], &status(204, None)).await.expect("delivered");
+
+        let found = repo.find_by_id(&tenant, subscription.id()).await.expect("found");
+        let found = found.expect("still subscribed");
+        assert_eq!(found.consecutive_failures(), 0);
+        assert!(found.last_delivery().is_some_and(DeliveryStatus::succeeded));
+        assert!(repo.claim_due(Duration::ZERO, 10).await.expect("claimed").is_empty());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn deactivate_should_record_an_audit_entry_once(pool: PgPool) {
+        let tenant = TenantId::default();
+        let repo = PgWebhookRepository::new(pool);
+        let subscription = WebhookSubscription::new(
+            WebhookId::generate(),
+            "https://hooks.example.com",
+            generate_secret(),
+            vec![],
+        )
+        .expect("valid subscription");
+        repo.insert(&tenant, &subscription).await.expect("insert");
+        let entry = AuditEntry {
+            action: "webhooks.deactivated",
+            subject: subscription.id().value().to_owned(),
+            details: serde_json::json!({}),
+            client_ip: None,
+        };
+
+        assert!(repo.deactivate(&tenant, subscription.id(), &entry).await.expect("deactivated"));
+        assert!(!repo.deactivate(&tenant, subscription.id(), &entry).await.expect("inactive"));
+        let found = repo.find_by_id(&tenant, subscription.id()).await.expect("found");
+        assert!(found.is_some_and(|s| !s.is_active()));
+        assert!(repo.delete(&tenant, subscription.id()).await.expect("deleted"));
+        assert!(repo.find_all(&tenant).await.expect("listed").is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+//! Webhook feature module: subscriptions to outbox events, delivered over HTTP
+
+pub mod application;
+pub mod domain;
+pub mod infrastructure;
+pub mod state;
+
+pub use state::{DeliverySettings, WebhookState};
+
+/// Feature name used in `ENABLED_FEATURES` / `DISABLED_FEATURES`
+pub const NAME: &str = "webhook";
+/// Features that must be enabled for this one to work
+pub const DEPENDS_ON: &[&str] = &[];
@@ -0,0 +1,55 @@
+//! Webhook feature state shared across handlers
+
+use crate::features::webhook::application::{
+    CreateWebhookUseCase, DeleteWebhookUseCase, DeliverEventsUseCase, DeliveryPolicy,
+    GetWebhookUseCase, ListWebhooksUseCase, UpdateWebhookUseCase,
+};
+use crate::features::webhook::domain::{
+    EndpointResolver, WebhookDeliveries, WebhookRepository, WebhookSender,
+};
+use std::sync::Arc;
+
+/// Where events are delivered from and through, and when deliveries give up
+pub struct DeliverySettings {
+    /// Outbox events on their way to subscriptions
+    pub deliveries: Arc<dyn WebhookDeliveries>,
+    /// Posts events to endpoints
+    pub sender: Arc<dyn WebhookSender>,
+    /// When deliveries give up and subscriptions are deactivated
+    pub policy: DeliveryPolicy,
+}
+
+/// Use cases of the webhook feature
+pub struct WebhookState {
+    pub(crate) create_webhook: CreateWebhookUseCase,
+    pub(crate) get_webhook: GetWebhookUseCase,
+    pub(crate) list_webhooks: ListWebhooksUseCase,
+    pub(crate) update_webhook: UpdateWebhookUseCase,
+    pub(crate) delete_webhook: DeleteWebhookUseCase,
+    pub(crate) deliver_events: Arc<DeliverEventsUseCase>,
+}
+
+impl WebhookState {
+    /// Wire every webhook use case to `repository`, checking endpoints through
+    /// `resolver` and delivering events as `delivery` says
+    #[must_use]
+    pub fn new(
+        repository: &Arc<dyn WebhookRepository>,
+        resolver: &Arc<dyn EndpointResolver>,
+        delivery: DeliverySettings,
+    ) -> Self {
+        Self {
+            create_webhook: CreateWebhookUseCase::new(Arc::clone(repository), Arc::clone(resolver)),
+            get_webhook: GetWebhookUseCase::new(Arc::clone(repository)),
+            list_webhooks: ListWebhooksUseCase::new(Arc::clone(repository)),
+            update_webhook: UpdateWebhookUseCase::new(Arc::clone(repository), Arc::clone(resolver)),
+            delete_webhook: DeleteWebhookUseCase::new(Arc::clone(repository)),
+            deliver_events: Arc::new(DeliverEventsUseCase::new(
+                Arc::clone(repository),
+                delivery.deliveries,
+                delivery.sender,
+                delivery.policy,
+            )),
+        }
+    }
+}
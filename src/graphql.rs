@@ -0,0 +1,289 @@
+//! GraphQL endpoint over the user and task use cases (`graphql` cargo feature)
+//!
+//! Resolvers only call use cases from the feature states, scoped to the tenant of the
+//! request. The owner of a task is resolved through a per-request [`DataLoader`], so
+//! a list of tasks costs one batched user lookup instead of one per task.
+
+use crate::features::task::application::{CreateTaskCommand, TaskListQuery};
+use crate::features::task::domain::Task;
+use crate::features::task::TaskState;
+use crate::features::user::application::CreateUserCommand;
+use crate::features::user::domain::User;
+use crate::features::user::UserState;
+use crate::shared::application::{PageRequest, Validated};
+use crate::shared::domain::{timestamp, DomainError, Entity, TenantId};
+use crate::shared::infrastructure::http::ApiError;
+use crate::shared::infrastructure::tenant::TenantContext;
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{Context, EmptySubscription, ErrorExtensions, Object, Schema, ID};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{extract::State, routing::post, Router};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+type GqlResult<T> = async_graphql::Result<T>;
+
+/// Executable schema over the user and task features
+pub type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Map a domain error to a GraphQL error carrying the REST error code in `extensions.code`
+fn to_gql(e: DomainError) -> async_graphql::Error {
+    let error = ApiError::from(e);
+    async_graphql::Error::new(error.message).extend_with(|_, ext| ext.set("code", error.code))
+}
+
+/// GraphQL view of a user
+#[derive(Clone)]
+pub struct UserObject(User);
+
+#[Object(name = "User")]
+impl UserObject {
+    async fn id(&self) -> ID {
+        ID(self.0.id().value().to_owned())
+    }
+
+    async fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    async fn email(&self) -> &str {
+        self.0.email().value()
+    }
+
+    /// First page of tasks owned by this user
+    async fn tasks(&self, ctx: &Context<'_>) -> GqlResult<Vec<TaskObject>> {
+        let (tasks, tenant) = (ctx.data::<Arc<TaskState>>()?, ctx.data::<TenantId>()?);
+        let query = Validated::new(TaskListQuery::for_user(self.0.id().value()))
+            .map_err(|e| to_gql(e.into()))?;
+        let page = PageRequest::default();
+        let list = tasks.list_tasks.execute(tenant, query, page).await.map_err(to_gql)?;
+        Ok(list.into_iter().map(TaskObject).collect())
+    }
+}
+
+/// GraphQL view of a task
+pub struct TaskObject(Task);
+
+#[Object(name = "Task")]
+impl TaskObject {
+    async fn id(&self) -> ID {
+        ID(self.0.id().value().to_owned())
+    }
+
+    async fn user_id(&self) -> ID {
+        ID(self.0.user_id().value().to_owned())
+    }
+
+    async fn title(&self) -> &str {
+        self.0.title()
+    }
+
+    async fn description(&self) -> &str {
+        self.0.description()
+    }
+
+    async fn completed(&self) -> bool {
+        self.0.is_completed()
+    }
+
+    /// RFC3339 in UTC with millisecond precision, as in the REST API
+    async fn updated_at(&self) -> Option<String> {
+        self.0.updated_at().as_ref().map(timestamp::format)
+    }
+
+    /// Owner of the task, batched across the whole response
+    async fn user(&self, ctx: &Context<'_>) -> GqlResult<Option<UserObject>> {
+        let loader = ctx.data::<DataLoader<UserLoader>>()?;
+        loader.load_one(self.0.user_id().value().to_owned()).await
+    }
+}
+
+/// Batches user lookups by ID within a tenant through [`UserState::get_users_by_ids`]
+pub struct UserLoader(Arc<UserState>, TenantId);
+
+impl Loader<String> for UserLoader {
+    type Value = UserObject;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, UserObject>, Self::Error> {
+        let users = self.0.get_users_by_ids.execute(&self.1, keys).await.map_err(to_gql)?;
+        Ok(users.into_iter().map(|u| (u.id().value().to_owned(), UserObject(u))).collect())
+    }
+}
+
+/// Read operations
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn user(&self, ctx: &Context<'_>, id: ID) -> GqlResult<UserObject> {
+        let (users, tenant) = (ctx.data::<Arc<UserState>>()?, ctx.data::<TenantId>()?);
+        users.get_user.execute(tenant, &id).await.map(UserObject).map_err(to_gql)
+    }
+
+    /// A page of users
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> GqlResult<Vec<UserObject>> {
+        let (users, tenant) = (ctx.data::<Arc<UserState>>()?, ctx.data::<TenantId>()?);
+        let page = PageRequest { limit, offset };
+        let list = users.list_users.execute(tenant, None, page).await.map_err(to_gql)?;
+        Ok(list.into_iter().map(UserObject).collect())
+    }
+
+    async fn task(&self, ctx: &Context<'_>, id: ID) -> GqlResult<TaskObject> {
+        let (tasks, tenant) = (ctx.data::<Arc<TaskState>>()?, ctx.data::<TenantId>()?);
+        tasks.get_task.execute(tenant, &id).await.map(TaskObject).map_err(to_gql)
+    }
+
+    /// A page of tasks, optionally filtered by owner
+    async fn tasks(
+        &self,
+        ctx: &Context<'_>,
+        user_id: Option<ID>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> GqlResult<Vec<TaskObject>> {
+        let (tasks, tenant) = (ctx.data::<Arc<TaskState>>()?, ctx.data::<TenantId>()?);
+        let query = TaskListQuery { user_id: user_id.map(|id| id.0), ..TaskListQuery::default() };
+        let query = Validated::new(query).map_err(|e| to_gql(e.into()))?;
+        let page = PageRequest { limit, offset };
+        let list = tasks.list_tasks.execute(tenant, query, page).await.map_err(to_gql)?;
+        Ok(list.into_iter().map(TaskObject).collect())
+    }
+}
+
+/// Write operations
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_user(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        email: String,
+    ) -> GqlResult<UserObject> {
+        let (users, tenant) = (ctx.data::<Arc<UserState>>()?, ctx.data::<TenantId>()?);
+        let command =
+            Validated::new(CreateUserCommand { name, email }).map_err(|e| to_gql(e.into()))?;
+        let user = users.create_user.execute(tenant, command).await.map_err(to_gql)?;
+        Ok(UserObject(user))
+    }
+
+    async fn create_task(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ID,
+        title: String,
+        #[graphql(default)] description: String,
+    ) -> GqlResult<TaskObject> {
+        let (tasks, tenant) = (ctx.data::<Arc<TaskState>>()?, ctx.data::<TenantId>()?);
+        // Soft-rule warnings are not exposed over GraphQL
+        let command = CreateTaskCommand { user_id: user_id.0, title, description };
+        let command = Validated::new(command).map_err(|e| to_gql(e.into()))?;
+        let (task, _warnings) = tasks.create_task.execute(tenant, command).await.map_err(to_gql)?;
+        Ok(TaskObject(task))
+    }
+
+    async fn complete_task(&self, ctx: &Context<'_>, id: ID) -> GqlResult<TaskObject> {
+        let (tasks, tenant) = (ctx.data::<Arc<TaskState>>()?, ctx.data::<TenantId>()?);
+        let (task, _warnings) = tasks.complete_task.execute(tenant, &id).await.map_err(to_gql)?;
+        Ok(TaskObject(task))
+    }
+}
+
+#[derive(Clone)]
+struct GraphqlState {
+    schema: AppSchema,
+    users: Arc<UserState>,
+}
+
+/// `POST /graphql`, plus the GraphQL Playground on `GET /graphql` in debug builds
+pub fn routes(users: Arc<UserState>, tasks: Arc<TaskState>) -> Router {
+    let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(Arc::clone(&users))
+        .data(tasks)
+        .finish();
+    let route = post(graphql_handler);
+    #[cfg(debug_assertions)]
+    let route = route.get(playground);
+    Router::new().route("/graphql", route).with_state(GraphqlState { schema, users })
+}
+
+async fn graphql_handler(
+    State(state): State<GraphqlState>,
+    TenantContext(tenant): TenantContext,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    // A fresh loader per request keeps its cache from outliving the request and tenant
+    let loader = DataLoader::new(UserLoader(state.users, tenant.clone()), tokio::spawn);
+    state.schema.execute(request.into_inner().data(loader).data(tenant)).await.into()
+}
+
+#[cfg(debug_assertions)]
+async fn playground() -> axum::response::Html<String> {
+    use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
+    axum::response::Html(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{in_memory_app, send};
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    async fn graphql(app: &axum::Router, query: &str) -> Value {
+        let (status, body) =
+            send(app, Method::POST, "/graphql", Some(json!({ "query": query }))).await;
+        assert_eq!(status, StatusCode::OK);
+        body
+    }
+
+    #[tokio::test]
+    async fn tasks_should_resolve_owners() {
+        let app = in_memory_app();
+        let created = graphql(
+            &app,
+            r#"mutation { createUser(name: "Alice", email: "alice@example.com") { id } }"#,
+        )
+        .await;
+        let user_id = created["data"]["createUser"]["id"].as_str().unwrap_or_default().to_owned();
+        for title in ["one", "two"] {
+            graphql(
+                &app,
+                &format!(
+                    r#"mutation {{ createTask(userId: "{user_id}", title: "{title}") {{ id }} }}"#
+                ),
+            )
+            .await;
+        }
+
+        let body = graphql(&app, "{ tasks { title user { name } } }").await;
+        let tasks = body["data"]["tasks"].as_array().cloned().unwrap_or_default();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks.iter().all(|t| t["user"]["name"] == "Alice"));
+
+        let body =
+            graphql(&app, &format!(r#"{{ user(id: "{user_id}") {{ tasks {{ title }} }} }}"#)).await;
+        assert_eq!(body["data"]["user"]["tasks"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[tokio::test]
+    async fn domain_errors_should_carry_error_codes() {
+        let app = in_memory_app();
+        let id = uuid::Uuid::new_v4();
+        let body = graphql(&app, &format!(r#"{{ task(id: "{id}") {{ id }} }}"#)).await;
+        assert_eq!(body["errors"][0]["extensions"]["code"], "NOT_FOUND");
+
+        let body = graphql(
+            &app,
+            r#"mutation { createUser(name: "Alice", email: "not-an-email") { id } }"#,
+        )
+        .await;
+        assert_eq!(body["errors"][0]["extensions"]["code"], "VALIDATION_ERROR");
+    }
+}
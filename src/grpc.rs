@@ -0,0 +1,307 @@
+//! gRPC services over the user and task use cases (`grpc` feature)
+//!
+//! The services mirror the REST endpoints and share the HTTP server's feature
+//! states; a disabled feature simply has no service registered. The tenant of a
+//! call comes from its `x-tenant-id` metadata, as over HTTP.
+
+use crate::app::AppState;
+use crate::features::task::application::{CreateTaskCommand, TaskListQuery};
+use crate::features::task::domain::Task;
+use crate::features::task::TaskState;
+use crate::features::user::application::{CreateUserCommand, DeleteUserOptions, UpdateUserCommand};
+use crate::features::user::domain::User;
+use crate::features::user::UserState;
+use crate::shared::application::{PageRequest, Validated, ValidationErrors};
+use crate::shared::domain::{timestamp, DomainError, Entity, ErrorStatus, TenantId};
+use crate::shared::infrastructure::tenant::{TenantPolicy, TENANT_HEADER};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tonic::transport::server::TcpIncoming;
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Response, Status};
+
+/// Code generated from `proto/*.proto`
+#[allow(missing_docs, dead_code, clippy::all, clippy::pedantic, reason = "generated code")]
+pub mod proto {
+    tonic::include_proto!("template.v1");
+}
+
+use proto::task_service_server::{TaskService, TaskServiceServer};
+use proto::user_service_server::{UserService, UserServiceServer};
+
+type GrpcResult<T> = Result<Response<T>, Status>;
+
+impl From<ValidationErrors> for Status {
+    fn from(errors: ValidationErrors) -> Self {
+        DomainError::from(errors).into()
+    }
+}
+
+impl From<DomainError> for Status {
+    fn from(e: DomainError) -> Self {
+        match e {
+            DomainError::NotFound(_) => Self::not_found(e.to_string()),
+            DomainError::Validation(_) => Self::invalid_argument(e.to_string()),
+            DomainError::AlreadyExists(_) => Self::already_exists(e.to_string()),
+            DomainError::Conflict(_) | DomainError::Expired(_) => {
+                Self::failed_precondition(e.to_string())
+            }
+            DomainError::Custom { status_hint, message, .. } => match status_hint {
+                ErrorStatus::Invalid => Self::invalid_argument(message),
+                ErrorStatus::NotFound => Self::not_found(message),
+                ErrorStatus::Conflict | ErrorStatus::Gone => Self::failed_precondition(message),
+                ErrorStatus::LimitExceeded => Self::resource_exhausted(message),
+                ErrorStatus::Unavailable => Self::unavailable(message),
+            },
+            DomainError::Unavailable(_) => Self::unavailable(e.to_string()),
+            // Don't leak internal details to the client
+            DomainError::Infrastructure(_) | DomainError::Unexpected(_) => {
+                Self::internal("Internal server error")
+            }
+        }
+    }
+}
+
+impl From<User> for proto::User {
+    fn from(u: User) -> Self {
+        Self {
+            id: u.id().value().to_owned(),
+            name: u.name().to_owned(),
+            email: u.email().value().to_owned(),
+        }
+    }
+}
+
+impl From<Task> for proto::Task {
+    fn from(t: Task) -> Self {
+        Self {
+            id: t.id().value().to_owned(),
+            user_id: t.user_id().value().to_owned(),
+            title: t.title().to_owned(),
+            description: t.description().to_owned(),
+            completed: t.is_completed(),
+            updated_at: t.updated_at().as_ref().map(timestamp::format),
+        }
+    }
+}
+
+/// Tenant named by the `x-tenant-id` metadata of `request`
+fn tenant_of<T>(policy: TenantPolicy, request: &Request<T>) -> Result<TenantId, DomainError> {
+    let header = request.metadata().get(TENANT_HEADER).map(MetadataValue::as_bytes);
+    policy.resolve(header)
+}
+
+/// `UserService` backed by the user use cases
+pub struct GrpcUserService(Arc<UserState>, TenantPolicy);
+
+#[tonic::async_trait]
+impl UserService for GrpcUserService {
+    async fn create_user(
+        &self,
+        request: Request<proto::CreateUserRequest>,
+    ) -> GrpcResult<proto::User> {
+        let tenant = tenant_of(self.1, &request)?;
+        let proto::CreateUserRequest { name, email } = request.into_inner();
+        let command = Validated::new(CreateUserCommand { name, email })?;
+        let user = self.0.create_user.execute(&tenant, command).await.map_err(Status::from)?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn get_user(&self, request: Request<proto::GetUserRequest>) -> GrpcResult<proto::User> {
+        let tenant = tenant_of(self.1, &request)?;
+        let id = request.into_inner().id;
+        let user = self.0.get_user.execute(&tenant, &id).await.map_err(Status::from)?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn list_users(
+        &self,
+        request: Request<proto::ListUsersRequest>,
+    ) -> GrpcResult<proto::ListUsersResponse> {
+        let tenant = tenant_of(self.1, &request)?;
+        let proto::ListUsersRequest { limit, offset } = request.into_inner();
+        let page = PageRequest { limit, offset };
+        let users = self.0.list_users.execute(&tenant, None, page).await.map_err(Status::from)?;
+        Ok(Response::new(proto::ListUsersResponse {
+            users: users.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn update_user(
+        &self,
+        request: Request<proto::UpdateUserRequest>,
+    ) -> GrpcResult<proto::User> {
+        let tenant = tenant_of(self.1, &request)?;
+        let proto::UpdateUserRequest { id, name, email } = request.into_inner();
+        let command = Validated::new(UpdateUserCommand { name, email })?;
+        let user =
+            self.0.update_user.execute(&tenant, &id, command).await.map_err(Status::from)?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn delete_user(
+        &self,
+        request: Request<proto::DeleteUserRequest>,
+    ) -> GrpcResult<proto::DeleteUserResponse> {
+        let tenant = tenant_of(self.1, &request)?;
+        let proto::DeleteUserRequest { id, force } = request.into_inner();
+        let options = DeleteUserOptions { force, ..DeleteUserOptions::default() };
+        self.0.delete_user.execute(&tenant, &id, options).await.map_err(Status::from)?;
+        Ok(Response::new(proto::DeleteUserResponse {}))
+    }
+}
+
+/// `TaskService` backed by the task use cases
+pub struct GrpcTaskService(Arc<TaskState>, TenantPolicy);
+
+#[tonic::async_trait]
+impl TaskService for GrpcTaskService {
+    async fn create_task(
+        &self,
+        request: Request<proto::CreateTaskRequest>,
+    ) -> GrpcResult<proto::Task> {
+        let tenant = tenant_of(self.1, &request)?;
+        let proto::CreateTaskRequest { user_id, title, description } = request.into_inner();
+        let command = Validated::new(CreateTaskCommand { user_id, title, description })?;
+        // gRPC has no warnings channel; soft-rule warnings are dropped
+        let (task, _warnings) =
+            self.0.create_task.execute(&tenant, command).await.map_err(Status::from)?;
+        Ok(Response::new(task.into()))
+    }
+
+    async fn get_task(&self, request: Request<proto::GetTaskRequest>) -> GrpcResult<proto::Task> {
+        let tenant = tenant_of(self.1, &request)?;
+        let id = request.into_inner().id;
+        let task = self.0.get_task.execute(&tenant, &id).await.map_err(Status::from)?;
+        Ok(Response::new(task.into()))
+    }
+
+    async fn list_tasks(
+        &self,
+        request: Request<proto::ListTasksRequest>,
+    ) -> GrpcResult<proto::ListTasksResponse> {
+        let tenant = tenant_of(self.1, &request)?;
+        let proto::ListTasksRequest { user_id, limit, offset } = request.into_inner();
+        let query = Validated::new(TaskListQuery { user_id, ..TaskListQuery::default() })?;
+        let page = PageRequest { limit, offset };
+        let tasks =
+            self.0.list_tasks.execute(&tenant, query, page).await.map_err(Status::from)?;
+        Ok(Response::new(proto::ListTasksResponse {
+            tasks: tasks.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn complete_task(
+        &self,
+        request: Request<proto::CompleteTaskRequest>,
+    ) -> GrpcResult<proto::Task> {
+        let tenant = tenant_of(self.1, &request)?;
+        let id = request.into_inner().id;
+        // gRPC has no warnings channel; soft-rule warnings are dropped
+        let (task, _warnings) =
+            self.0.complete_task.execute(&tenant, &id).await.map_err(Status::from)?;
+        Ok(Response::new(task.into()))
+    }
+
+    async fn delete_task(
+        &self,
+        request: Request<proto::DeleteTaskRequest>,
+    ) -> GrpcResult<proto::DeleteTaskResponse> {
+        let tenant = tenant_of(self.1, &request)?;
+        let id = request.into_inner().id;
+        self.0.delete_task.execute(&tenant, &id).await.map_err(Status::from)?;
+        Ok(Response::new(proto::DeleteTaskResponse {}))
+    }
+}
+
+/// Serve the services of every enabled feature on `listener` until `shutdown` resolves
+///
+/// # Errors
+/// Fails when the gRPC transport fails.
+pub async fn serve(
+    listener: TcpListener,
+    state: &AppState,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    let policy = state.tenant_policy;
+    let users =
+        state.user.as_ref().map(|s| UserServiceServer::new(GrpcUserService(Arc::clone(s), policy)));
+    let tasks =
+        state.task.as_ref().map(|s| TaskServiceServer::new(GrpcTaskService(Arc::clone(s), policy)));
+    tonic::transport::Server::builder()
+        .add_optional_service(users)
+        .add_optional_service(tasks)
+        .serve_with_incoming_shutdown(TcpIncoming::from(listener), shutdown)
+        .await
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::shared::infrastructure::config::Config;
+    use crate::app::InMemoryRepositories;
+    use proto::task_service_client::TaskServiceClient;
+    use proto::user_service_client::UserServiceClient;
+    use tonic::Code;
+
+    #[tokio::test]
+    async fn client_should_create_user_and_get_task_over_a_local_channel() {
+        let state = AppState::build(&Config::default(), &InMemoryRepositories::default())
+            .expect("valid feature config");
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("local addr"));
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            serve(listener, &state, async {
+                stopped.await.ok();
+            })
+            .await
+        });
+
+        let mut users = UserServiceClient::connect(url.clone()).await.expect("connect");
+        let mut tasks = TaskServiceClient::connect(url).await.expect("connect");
+
+        let user = users
+            .create_user(proto::CreateUserRequest {
+                name: "Alice".into(),
+                email: "alice@example.com".into(),
+            })
+            .await
+            .expect("create user")
+            .into_inner();
+        let created = tasks
+            .create_task(proto::CreateTaskRequest {
+                user_id: user.id.clone(),
+                title: "Buy milk".into(),
+                description: String::new(),
+            })
+            .await
+            .expect("create task")
+            .into_inner();
+
+        let fetched = tasks
+            .get_task(proto::GetTaskRequest { id: created.id.clone() })
+            .await
+            .expect("get task")
+            .into_inner();
+        assert_eq!(fetched, created);
+        assert_eq!(fetched.user_id, user.id);
+
+        let missing = tasks
+            .get_task(proto::GetTaskRequest { id: uuid::Uuid::new_v4().to_string() })
+            .await
+            .expect_err("unknown task");
+        assert_eq!(missing.code(), Code::NotFound);
+
+        let invalid = users
+            .create_user(proto::CreateUserRequest { name: "Bob".into(), email: "bob".into() })
+            .await
+            .expect_err("invalid email");
+        assert_eq!(invalid.code(), Code::InvalidArgument);
+
+        stop.send(()).ok();
+        server.await.expect("server task").expect("clean shutdown");
+    }
+}
@@ -0,0 +1,33 @@
+//! Axum DDD Template - A Domain-Driven Design template using Axum framework.
+//!
+//! The binary in `main.rs` wires the `PostgreSQL` repositories; the library exposes the
+//! building blocks needed to add features of your own (see `examples/custom_feature.rs`).
+
+pub mod api_types;
+pub mod app;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "consumers")]
+pub mod consumers;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+// Endpoints simulating slow and failing responses have no place in a release build
+#[cfg(all(feature = "dev-tools", debug_assertions))]
+pub mod dev_tools;
+pub mod features;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod shared;
+#[cfg(test)]
+mod test_support;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// Dependencies used by exported macros, so downstream crates need not depend on them
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+    pub use uuid;
+}
@@ -1,92 +1,129 @@
 //! Axum DDD Template - A Domain-Driven Design template using Axum framework.
 
-mod features;
-mod shared;
-
-use axum::{routing::get, Router};
-use features::task::application::{
-    CompleteTaskUseCase, CreateTaskUseCase, DeleteTaskUseCase, GetTaskUseCase, ListTasksUseCase,
+use axum_ddd_template::app::{
+    self, build_admin_router, build_router_checked, AppState, PgRepositories,
 };
-use features::task::infrastructure::{http as task_http, PgTaskRepository};
-use features::user::application::{
-    CreateUserUseCase, DeleteUserUseCase, GetUserUseCase, ListUsersUseCase, UpdateUserUseCase,
+#[cfg(feature = "consumers")]
+use axum_ddd_template::consumers;
+#[cfg(feature = "grpc")]
+use axum_ddd_template::grpc;
+use axum_ddd_template::shared::domain::TenantId;
+use axum_ddd_template::shared::infrastructure::{
+    admin, config::Config, database, healthcheck, http, log_level,
 };
-use features::user::infrastructure::{http as user_http, PgUserRepository};
-use shared::infrastructure::{config::Config, database, http::health_check};
-use std::sync::Arc;
-use std::time::Duration;
-use tower::ServiceBuilder;
-use tower_http::{timeout::TimeoutLayer, trace::TraceLayer};
+use metrics_exporter_prometheus::PrometheusBuilder;
 use tracing::info;
-use tracing_subscriber::EnvFilter;
-
-/// Application state shared across handlers
-pub struct AppState {
-    pub(crate) create_user: CreateUserUseCase,
-    pub(crate) get_user: GetUserUseCase,
-    pub(crate) list_users: ListUsersUseCase,
-    pub(crate) update_user: UpdateUserUseCase,
-    pub(crate) delete_user: DeleteUserUseCase,
-    pub(crate) create_task: CreateTaskUseCase,
-    pub(crate) get_task: GetTaskUseCase,
-    pub(crate) list_tasks: ListTasksUseCase,
-    pub(crate) complete_task: CompleteTaskUseCase,
-    pub(crate) delete_task: DeleteTaskUseCase,
-}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
-        .init();
+    let log_level = log_level::install();
 
     let config = Config::from_env()?;
+    if std::env::args().nth(1).as_deref() == Some("healthcheck") {
+        let url = healthcheck::probe_url(&config, std::env::args().skip(2))?;
+        healthcheck::probe(&url).await?;
+        return Ok(());
+    }
+    // Fail fast on feature misconfiguration before touching the database
+    let features = app::enabled_features(&config)?;
+    if std::env::args().nth(1).as_deref() == Some("check-config") {
+        println!("Configuration OK");
+        println!("  database: {}", config.redacted_database_url());
+        println!("  server:   {}", config.server_addr);
+        println!("  features: {}", features.join(", "));
+        println!("  cargo:    {}", http::cargo_features().join(", "));
+        return Ok(());
+    }
+    // Installed before connecting so the first pool acquires are recorded too
+    let metrics = PrometheusBuilder::new().install_recorder()?;
+    info!("Connecting to {}", config.redacted_database_url());
     let pool = database::create_pool(&config).await?;
-    database::run_migrations(&pool).await?;
+    // For the separate job running migrations when the server starts with SKIP_MIGRATIONS
+    if std::env::args().nth(1).as_deref() == Some("migrate") {
+        let retry = database::MigrationRetry::from_config(&config);
+        database::run_migrations(&pool, &config.db_schema, retry).await?;
+        info!("Migrations applied");
+        return Ok(());
+    }
+    // The API only issues tokens to users holding one, so each user's first comes from here
+    if std::env::args().nth(1).as_deref() == Some("issue-token") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        let [user_id, name, rest @ ..] = args.as_slice() else {
+            anyhow::bail!("Usage: issue-token <user-id> <name> [tenant]");
+        };
+        let tenant = match rest {
+            [] => TenantId::default(),
+            [tenant] => TenantId::new(tenant)?,
+            _ => anyhow::bail!("Usage: issue-token <user-id> <name> [tenant]"),
+        };
+        let repositories = PgRepositories::new(pool);
+        let issued = app::issue_api_token(&repositories, &tenant, user_id, name).await?;
+        println!("{}", issued.plaintext);
+        return Ok(());
+    }
+    if config.skip_migrations {
+        database::verify_migrations(&pool).await?;
+    } else {
+        let retry = database::MigrationRetry::from_config(&config);
+        database::run_migrations(&pool, &config.db_schema, retry).await?;
+    }
+    database::verify_open_task_title_index(&pool, config.prevent_duplicate_open_tasks).await?;
 
-    let user_repo: Arc<dyn features::user::domain::UserRepository> =
-        Arc::new(PgUserRepository::new(pool.clone()));
-    let task_repo: Arc<dyn features::task::domain::TaskRepository> =
-        Arc::new(PgTaskRepository::new(pool));
+    let state = AppState::build(&config, &PgRepositories::new(pool.clone()))?
+        .with_log_level(log_level.revert_after(config.log_level_ttl()));
+    let app = build_router_checked(&state, &config).await?;
+    let admin_app = build_admin_router(&state, metrics, Some(pool.clone()));
 
-    let state = Arc::new(AppState {
-        create_user: CreateUserUseCase::new(Arc::clone(&user_repo)),
-        get_user: GetUserUseCase::new(Arc::clone(&user_repo)),
-        list_users: ListUsersUseCase::new(Arc::clone(&user_repo)),
-        update_user: UpdateUserUseCase::new(Arc::clone(&user_repo)),
-        delete_user: DeleteUserUseCase::new(Arc::clone(&user_repo)),
-        create_task: CreateTaskUseCase::new(Arc::clone(&task_repo)),
-        get_task: GetTaskUseCase::new(Arc::clone(&task_repo)),
-        list_tasks: ListTasksUseCase::new(Arc::clone(&task_repo)),
-        complete_task: CompleteTaskUseCase::new(Arc::clone(&task_repo)),
-        delete_task: DeleteTaskUseCase::new(Arc::clone(&task_repo)),
+    let listener = tokio::net::TcpListener::bind(config.server_addr).await?;
+    info!("Server running on http://{}", config.server_addr);
+    let admin_listener = tokio::net::TcpListener::bind(config.admin_addr).await?;
+    info!("Admin server running on http://{}", config.admin_addr);
+
+    // Every server drains on the same signal
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(());
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        shutdown_tx.send(()).ok();
     });
 
-    let app = Router::new()
-        .route("/health", get(health_check))
-        .merge(user_http::router())
-        .merge(task_http::router())
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(TimeoutLayer::with_status_code(
-                    axum::http::StatusCode::SERVICE_UNAVAILABLE,
-                    Duration::from_secs(30),
-                )),
-        )
-        .with_state(state);
+    // Periodic work is registered here rather than spawned ad hoc
+    let jobs = state
+        .job_runner()
+        .register(database::PoolStatsJob::new(pool))
+        .start(&shutdown_rx);
 
-    let listener = tokio::net::TcpListener::bind(config.server_addr).await?;
-    info!("Server running on http://{}", config.server_addr);
+    let http = admin::serve(listener, app, admin_listener, admin_app, shutdown_rx.clone());
+    let http = async { http.await.map_err(anyhow::Error::from) };
+    // Task creations from a queue stop taking messages on the same signal; they run on a
+    // task of their own, so a broker failure never cuts the HTTP server short
+    #[cfg(feature = "consumers")]
+    let consumer = consumers::spawn(&state, &config, until_shutdown(shutdown_rx.clone()))?;
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    #[cfg(feature = "grpc")]
+    {
+        let grpc_listener = tokio::net::TcpListener::bind(config.grpc_addr).await?;
+        info!("gRPC server running on {}", config.grpc_addr);
+        let grpc = grpc::serve(grpc_listener, &state, until_shutdown(shutdown_rx));
+        tokio::try_join!(http, async { grpc.await.map_err(anyhow::Error::from) })?;
+    }
+    #[cfg(not(feature = "grpc"))]
+    http.await?;
+    #[cfg(feature = "consumers")]
+    if let Some(consumer) = consumer {
+        consumer.await?;
+    }
+    jobs.join().await;
+    // Writes of requests dropped by their clients finish before the pool closes
+    state.detached_writes().drained().await;
     Ok(())
 }
 
+/// Resolve once the shutdown signal has been broadcast (or its sender is gone)
+#[cfg(any(feature = "grpc", feature = "consumers"))]
+async fn until_shutdown(mut shutdown: tokio::sync::watch::Receiver<()>) {
+    shutdown.changed().await.ok();
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c().await.ok();
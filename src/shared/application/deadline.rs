@@ -0,0 +1,99 @@
+//! Request deadlines propagated to infrastructure calls
+//!
+//! The HTTP layer scopes each request with a [`Deadline`]; anything awaited inside
+//! that scope (use cases, repositories) can race its work against it via
+//! [`within_deadline`] without the deadline being threaded through every signature.
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+tokio::task_local! {
+    static CURRENT: Deadline;
+}
+
+/// Point in time after which work on behalf of the current request is abandoned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// Deadline `budget` from now
+    #[must_use]
+    pub fn after(budget: Duration) -> Self {
+        Self(Instant::now() + budget)
+    }
+
+    /// Time left before the deadline, zero once it has passed
+    #[must_use]
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    /// Deadline of the enclosing [`Deadline::scope`], if any
+    #[must_use]
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|deadline| *deadline).ok()
+    }
+
+    /// Run `future` with `self` as the current deadline.
+    ///
+    /// A nested scope never extends an enclosing deadline, only shortens it.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        let deadline = Self::current().map_or(self, |outer| outer.min(self));
+        CURRENT.scope(deadline, future).await
+    }
+
+    fn min(self, other: Self) -> Self {
+        Self(self.0.min(other.0))
+    }
+}
+
+/// Error returned by [`within_deadline`] when the deadline passes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineExceeded;
+
+/// Run `future` to completion unless the current deadline passes first.
+///
+/// Without a current deadline the future simply runs. On expiry the future is
+/// dropped, which cancels it.
+///
+/// # Errors
+/// Returns [`DeadlineExceeded`] when the deadline passes before `future` completes.
+pub async fn within_deadline<F: Future>(future: F) -> Result<F::Output, DeadlineExceeded> {
+    match Deadline::current() {
+        Some(deadline) => {
+            tokio::time::timeout_at(deadline.0, future).await.map_err(|_| DeadlineExceeded)
+        }
+        None => Ok(future.await),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn within_deadline_should_cancel_slow_work() {
+        let slow = tokio::time::sleep(Duration::from_secs(5));
+        let result = Deadline::after(Duration::from_millis(100)).scope(within_deadline(slow)).await;
+        assert_eq!(result, Err(DeadlineExceeded));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn within_deadline_should_pass_through_fast_work_and_no_deadline() {
+        let fast = async { 42 };
+        let result = Deadline::after(Duration::from_secs(1)).scope(within_deadline(fast)).await;
+        assert_eq!(result, Ok(42));
+        assert_eq!(within_deadline(async { 7 }).await, Ok(7));
+        assert_eq!(Deadline::current(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn nested_scope_should_not_extend_deadline() {
+        let outer = Deadline::after(Duration::from_secs(1));
+        let inner = outer
+            .scope(Deadline::after(Duration::from_secs(10)).scope(async { Deadline::current() }))
+            .await;
+        assert_eq!(inner, Some(outer));
+    }
+}
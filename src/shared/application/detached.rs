@@ -0,0 +1,152 @@
+//! Writes that finish even when the request making them is dropped
+//!
+//! Axum drops a handler's future when its client disconnects, wherever that future
+//! is suspended. Writes that must not be separated, such as a deletion and the
+//! cleanup following it, either go through one transaction of the unit of work or
+//! run with [`DetachedWrites::run`] on a task of their own, which completes even when
+//! the request is dropped. The application state owns the [`DetachedWrites`] of its
+//! use cases, and the binary waits for those still running with
+//! [`DetachedWrites::drained`] at shutdown, once the servers stopped taking requests.
+
+use crate::shared::application::Deadline;
+use crate::shared::domain::DomainError;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::sync::oneshot;
+use tokio_util::task::TaskTracker;
+use tracing::Instrument;
+
+/// Detached writes as handed to the hook of [`DetachedWrites::propagating`]
+pub type DetachedTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Tasks running the writes detached from the requests making them
+///
+/// Clones share their tasks, so every use case of one application state is drained
+/// together, and no other state waits for them.
+#[derive(Debug, Clone, Default)]
+pub struct DetachedWrites {
+    tracker: TaskTracker,
+    /// Scopes the task-locals of the caller, besides its deadline, around the writes
+    propagate: Option<fn(DetachedTask) -> DetachedTask>,
+}
+
+impl DetachedWrites {
+    /// `self` passing detached writes through `propagate` when they start, to scope
+    /// them with the task-locals of their caller that the application layer doesn't
+    /// know of, such as its request metrics
+    #[must_use]
+    pub fn propagating(self, propagate: fn(DetachedTask) -> DetachedTask) -> Self {
+        Self { propagate: Some(propagate), ..self }
+    }
+
+    /// Run `writes` on a task of its own and return their outcome
+    ///
+    /// Dropping the returned future no longer cancels `writes`, which run to the end in
+    /// the span and under the deadline of the caller.
+    ///
+    /// # Errors
+    /// The error of `writes`; `Unexpected` if they panic.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        writes: impl Future<Output = Result<T, DomainError>> + Send + 'static,
+    ) -> Result<T, DomainError> {
+        let (sender, outcome) = oneshot::channel();
+        let mut task: DetachedTask = Box::pin(async move {
+            sender.send(writes.await).ok();
+        });
+        if let Some(deadline) = Deadline::current() {
+            task = Box::pin(deadline.scope(task));
+        }
+        if let Some(propagate) = self.propagate {
+            task = propagate(task);
+        }
+        let failed = |e: &dyn std::fmt::Display| {
+            DomainError::Unexpected(format!("Detached writes failed: {e}"))
+        };
+        let task = self.tracker.spawn(task.instrument(tracing::Span::current()));
+        task.await.map_err(|e| failed(&e))?;
+        outcome.await.map_err(|e| failed(&e))?
+    }
+
+    /// Wait until none of the writes started by [`run`](Self::run) is running
+    ///
+    /// Writes started afterwards still run, but are no longer waited for.
+    pub async fn drained(&self) {
+        self.tracker.close();
+        self.tracker.wait().await;
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::testing::CancellationSafety;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    #[tokio::test]
+    async fn detached_writes_should_finish_after_their_caller_is_dropped() {
+        let detached = DetachedWrites::default();
+        let finished = Arc::new(Semaphore::new(0));
+        let signal = Arc::clone(&finished);
+        let writes = detached.run(async move {
+            tokio::task::yield_now().await;
+            signal.add_permits(1);
+            Ok(())
+        });
+
+        assert!(CancellationSafety::after_polls(1).run(writes).await.is_none());
+        assert_eq!(finished.available_permits(), 0);
+        detached.drained().await;
+        assert_eq!(finished.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn detached_writes_should_report_a_panic_as_unexpected() {
+        let panicked = DetachedWrites::default().run::<()>(async { panic!("boom") }).await;
+        assert!(matches!(panicked, Err(DomainError::Unexpected(m)) if m.contains("panicked")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn draining_should_not_wait_for_the_writes_of_other_trackers() {
+        let (drained, busy) = (DetachedWrites::default(), DetachedWrites::default());
+        let blocked = Arc::new(Semaphore::new(0));
+        let permit = Arc::clone(&blocked);
+        let writes = busy.run(async move {
+            let _permit = permit.acquire().await;
+            Ok(())
+        });
+        assert!(CancellationSafety::after_polls(1).run(writes).await.is_none());
+
+        drained.drained().await;
+        let waiting = tokio::time::timeout(Duration::from_secs(1), busy.drained()).await;
+        assert!(waiting.is_err(), "the writes of the other tracker are still running");
+        blocked.add_permits(1);
+        busy.drained().await;
+    }
+
+    tokio::task_local! {
+        static CALLER: &'static str;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn detached_writes_should_keep_the_deadline_and_task_locals_of_their_caller() {
+        fn propagate(task: DetachedTask) -> DetachedTask {
+            match CALLER.try_with(|caller| *caller) {
+                Ok(caller) => Box::pin(CALLER.scope(caller, task)),
+                Err(_) => task,
+            }
+        }
+        let detached = DetachedWrites::default().propagating(propagate);
+        let deadline = Deadline::after(Duration::from_secs(5));
+
+        let writes = detached.run(async { Ok((Deadline::current(), CALLER.try_with(|c| *c))) });
+        let (seen, caller) =
+            CALLER.scope("request", deadline.scope(writes)).await.expect("writes");
+        assert_eq!((seen, caller), (Some(deadline), Ok("request")));
+        let outside = detached.run(async { Ok(Deadline::current()) }).await;
+        assert!(matches!(outside, Ok(None)));
+    }
+}
@@ -0,0 +1,145 @@
+//! Percentage rollouts of new code paths
+//!
+//! A use case with an old and a new way of doing something asks [`FeatureToggles`]
+//! whether a flag is on for the entity at hand. Each entity falls into one of 100
+//! buckets by a stable hash of the flag name and its ID, and the flag is on for the
+//! buckets below its percentage: an entity keeps its path across requests, instances
+//! and restarts, and raising the percentage only ever adds entities.
+
+use crate::shared::domain::DomainError;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+/// Number of buckets entities are spread over, one per percent
+pub const BUCKETS: u8 = 100;
+
+/// Bucket of the entity `entity_id` for the flag `name`, below [`BUCKETS`]
+///
+/// The 64-bit FNV-1a hash of `name:entity_id`: stable across Rust versions and
+/// platforms, unlike `std`'s hashers, and different per flag so that the same
+/// entities are not always the first to get every new path.
+#[must_use]
+pub fn bucket(name: &str, entity_id: &str) -> u8 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let bytes = name.bytes().chain([b':']).chain(entity_id.bytes());
+    let hash =
+        bytes.fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME));
+    u8::try_from(hash % u64::from(BUCKETS)).unwrap_or_default()
+}
+
+/// A flag and how far it is rolled out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FlagState {
+    /// Name of the flag
+    pub name: &'static str,
+    /// Percentage of entities the flag is on for
+    pub percentage: u8,
+    /// Percentage it started with, from the configuration
+    pub configured: u8,
+}
+
+/// Named flags with their rollout percentages, changeable at runtime
+#[derive(Debug, Default)]
+pub struct FeatureToggles {
+    flags: RwLock<BTreeMap<&'static str, FlagState>>,
+}
+
+impl FeatureToggles {
+    /// Toggles of `flags`, each a name and the percentage it starts with (capped at
+    /// 100)
+    #[must_use]
+    pub fn new(flags: &[(&'static str, u8)]) -> Self {
+        let flags = flags
+            .iter()
+            .map(|&(name, percentage)| {
+                let percentage = percentage.min(BUCKETS);
+                (name, FlagState { name, percentage, configured: percentage })
+            })
+            .collect();
+        Self { flags: RwLock::new(flags) }
+    }
+
+    /// Whether the flag `name` is on for the entity `entity_id`; unknown flags are off
+    #[must_use]
+    pub fn is_enabled(&self, name: &str, entity_id: &str) -> bool {
+        let Ok(flags) = self.flags.read() else {
+            return false;
+        };
+        flags.get(name).is_some_and(|flag| bucket(name, entity_id) < flag.percentage)
+    }
+
+    /// Every flag, by name
+    #[must_use]
+    pub fn flags(&self) -> Vec<FlagState> {
+        self.flags.read().map(|flags| flags.values().copied().collect()).unwrap_or_default()
+    }
+
+    /// Roll the flag `name` out to `percentage` percent of entities until the next
+    /// change or restart, returning its new state
+    ///
+    /// # Errors
+    /// `NotFound` for an unknown flag, `Validation` for a percentage over 100.
+    pub fn set(&self, name: &str, percentage: u8) -> Result<FlagState, DomainError> {
+        if percentage > BUCKETS {
+            return Err(DomainError::Validation(format!(
+                "Percentage must be between 0 and {BUCKETS}"
+            )));
+        }
+        let mut flags = self
+            .flags
+            .write()
+            .map_err(|_| DomainError::Infrastructure("Feature flags are unavailable".into()))?;
+        let flag = flags.get_mut(name).ok_or_else(|| DomainError::not_found("Feature flag"))?;
+        flag.percentage = percentage;
+        Ok(*flag)
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_should_be_stable() {
+        // Pinned: changing the hash would move entities between code paths
+        let buckets: Vec<u8> = ["task-1", "task-2", "0b6f2a34-9d1e-4c55-8f0e-3a7d21c4e9b0", ""]
+            .iter()
+            .map(|id| bucket("atomic_complete", id))
+            .collect();
+        assert_eq!(buckets, [7, 18, 48, 40]);
+        assert_ne!(bucket("atomic_complete", "task-1"), bucket("other_flag", "task-1"));
+    }
+
+    #[test]
+    fn percentages_should_spread_entities_evenly_and_only_add_to_them() {
+        let ids: Vec<String> = (0..10_000).map(|n| format!("task-{n}")).collect();
+        let enabled = |percentage| {
+            let toggles = FeatureToggles::new(&[("flag", percentage)]);
+            ids.iter().filter(|id| toggles.is_enabled("flag", id)).cloned().collect::<Vec<_>>()
+        };
+        assert!(enabled(0).is_empty());
+        assert_eq!(enabled(100).len(), ids.len());
+        let (quarter, half) = (enabled(25), enabled(50));
+        assert!((2_300..2_700).contains(&quarter.len()), "{}", quarter.len());
+        assert!(quarter.iter().all(|id| half.contains(id)));
+    }
+
+    #[test]
+    fn set_should_override_the_percentage_at_runtime() {
+        let toggles = FeatureToggles::new(&[("atomic_complete", 100), ("other", 250)]);
+        assert!(toggles.is_enabled("atomic_complete", "task-1"));
+
+        let state = toggles.set("atomic_complete", 0).expect("known flag");
+        assert_eq!(state, FlagState { name: "atomic_complete", percentage: 0, configured: 100 });
+        assert!(!toggles.is_enabled("atomic_complete", "task-1"));
+        let names: Vec<_> = toggles.flags().iter().map(|f| (f.name, f.percentage)).collect();
+        assert_eq!(names, [("atomic_complete", 0), ("other", 100)]);
+
+        assert!(matches!(toggles.set("missing", 10), Err(DomainError::NotFound(_))));
+        assert!(matches!(toggles.set("other", 101), Err(DomainError::Validation(_))));
+        assert!(!toggles.is_enabled("missing", "task-1"));
+    }
+}
@@ -1 +1,13 @@
 //! Shared application layer abstractions
+
+pub mod deadline;
+pub mod detached;
+pub mod feature_toggle;
+pub mod pagination;
+pub mod validation;
+
+pub use deadline::{within_deadline, Deadline, DeadlineExceeded};
+pub use detached::DetachedWrites;
+pub use feature_toggle::FeatureToggles;
+pub use pagination::{PageLimits, PageRequest};
+pub use validation::{Validate, Validated, ValidationErrors};
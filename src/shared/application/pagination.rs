@@ -0,0 +1,93 @@
+//! Paging of list use cases within configured bounds
+//!
+//! Deep offsets make the database walk and discard every skipped row, so both the
+//! page size and the offset are capped; clients needing more must narrow their
+//! filters.
+
+use crate::shared::domain::{DomainError, Page};
+
+/// Default of the largest page a listing returns
+pub const DEFAULT_MAX_PAGE_SIZE: u32 = 100;
+/// Default of the deepest offset a listing accepts
+pub const DEFAULT_MAX_OFFSET: u32 = 10_000;
+
+/// Page requested by a client; unset values take defaults
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PageRequest {
+    /// Items per page; defaults to the largest page allowed
+    pub limit: Option<u32>,
+    /// Items to skip; defaults to 0
+    pub offset: Option<u32>,
+}
+
+/// Bounds on the pages list use cases return
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageLimits {
+    /// Largest page
+    pub max_page_size: u32,
+    /// Deepest offset
+    pub max_offset: u32,
+}
+
+impl Default for PageLimits {
+    fn default() -> Self {
+        Self { max_page_size: DEFAULT_MAX_PAGE_SIZE, max_offset: DEFAULT_MAX_OFFSET }
+    }
+}
+
+impl PageLimits {
+    /// Resolve `request` into a page within these bounds
+    ///
+    /// # Errors
+    /// `Validation` for a limit outside 1 to `max_page_size` or an offset beyond
+    /// `max_offset`.
+    pub fn resolve(&self, request: PageRequest) -> Result<Page, DomainError> {
+        let limit = request.limit.unwrap_or(self.max_page_size);
+        if limit == 0 || limit > self.max_page_size {
+            return Err(DomainError::Validation(format!(
+                "limit must be between 1 and {}",
+                self.max_page_size
+            )));
+        }
+        let offset = request.offset.unwrap_or(0);
+        if offset > self.max_offset {
+            return Err(DomainError::Validation(format!(
+                "offset cannot exceed {}; narrow the filters instead of paging deeper",
+                self.max_offset
+            )));
+        }
+        Ok(Page { limit, offset })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(limit: Option<u32>, offset: Option<u32>) -> Result<Page, DomainError> {
+        let limits = PageLimits { max_page_size: 50, max_offset: 1000 };
+        limits.resolve(PageRequest { limit, offset })
+    }
+
+    #[test]
+    fn resolve_should_default_to_the_largest_first_page() {
+        assert_eq!(resolve(None, None).ok(), Some(Page { limit: 50, offset: 0 }));
+    }
+
+    #[test]
+    fn resolve_should_accept_the_bounds_and_reject_beyond() {
+        assert_eq!(resolve(Some(1), Some(1000)).ok(), Some(Page { limit: 1, offset: 1000 }));
+        assert_eq!(resolve(Some(50), None).ok(), Some(Page { limit: 50, offset: 0 }));
+        for (limit, offset) in [(Some(0), None), (Some(51), None), (None, Some(1001))] {
+            let result = resolve(limit, offset);
+            assert!(matches!(result, Err(DomainError::Validation(_))), "{result:?}");
+        }
+    }
+
+    #[test]
+    fn slice_should_skip_offset_and_take_limit() {
+        let page = Page { limit: 2, offset: 1 };
+        assert_eq!(page.slice(1..=5), [2, 3]);
+        assert!(Page { limit: 2, offset: 9 }.slice(1..=5).is_empty());
+    }
+}
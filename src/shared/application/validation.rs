@@ -0,0 +1,104 @@
+//! Validation of commands before they reach a use case
+//!
+//! A command implements [`Validate`], checking every field and reporting all
+//! problems at once. Use cases take a [`Validated`] command, which can only be built
+//! by passing validation, so they work on well-formed input.
+
+use crate::shared::domain::DomainError;
+use serde::Serialize;
+use std::fmt;
+use std::ops::Deref;
+
+/// A rule broken by one field of a command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Name of the field, as in the request body
+    pub field: &'static str,
+    /// Human-readable explanation
+    pub message: String,
+}
+
+/// Every rule broken by a command, in field order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    /// Record that `field` broke a rule
+    pub fn add(&mut self, field: &'static str, message: impl Into<String>) {
+        self.0.push(FieldError { field, message: message.into() });
+    }
+
+    /// Record the message of a failed domain constructor (e.g. `Email::new`) for `field`
+    pub fn check<T>(&mut self, field: &'static str, result: Result<T, DomainError>) {
+        if let Err(DomainError::Validation(message)) = result {
+            self.add(field, message);
+        }
+    }
+
+    /// `Ok` when no rule was broken
+    ///
+    /// # Errors
+    /// `self` when it holds at least one error.
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.0.is_empty() { Ok(()) } else { Err(self) }
+    }
+
+    /// The broken rules
+    #[must_use]
+    pub fn fields(&self) -> &[FieldError] {
+        &self.0
+    }
+}
+
+/// The messages of every broken rule, separated by `; `
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<_> = self.0.iter().map(|e| e.message.as_str()).collect();
+        f.write_str(&messages.join("; "))
+    }
+}
+
+impl From<ValidationErrors> for DomainError {
+    fn from(errors: ValidationErrors) -> Self {
+        Self::Validation(errors.to_string())
+    }
+}
+
+/// Input checked field by field before execution
+pub trait Validate {
+    /// Check every field, reporting all broken rules
+    ///
+    /// # Errors
+    /// The rules the input breaks.
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// A value that passed [`Validate::validate`]
+#[derive(Debug)]
+pub struct Validated<T>(T);
+
+impl<T: Validate> Validated<T> {
+    /// Validate `value`
+    ///
+    /// # Errors
+    /// The rules `value` breaks.
+    pub fn new(value: T) -> Result<Self, ValidationErrors> {
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+impl<T> Validated<T> {
+    /// The validated value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
@@ -0,0 +1,18 @@
+//! Audit trail entries
+
+use std::net::IpAddr;
+
+/// A change made to the data of a tenant, recorded for later review in the same
+/// transaction as the change itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// What was done, as `entity.past_tense_verb`, e.g. `tasks.reassigned`
+    pub action: &'static str,
+    /// ID of the entity the change was made to
+    pub subject: String,
+    /// Specifics of the change; `Value::Null` when there are none
+    pub details: serde_json::Value,
+    /// Address of the client that requested the change; `None` for changes made by
+    /// the application itself
+    pub client_ip: Option<IpAddr>,
+}
@@ -0,0 +1,14 @@
+//! Outbound email port
+
+use crate::shared::domain::{DomainError, Email};
+
+/// Sends plain-text email, e.g. through an SMTP relay
+#[async_trait::async_trait]
+pub trait EmailSender: Send + Sync {
+    /// Send `body` to `to` under `subject`
+    ///
+    /// # Errors
+    /// `Unavailable` when sending may succeed if retried, `Infrastructure` when it
+    /// cannot, such as a rejected recipient.
+    async fn send(&self, to: &Email, subject: &str, body: &str) -> Result<(), DomainError>;
+}
@@ -4,7 +4,9 @@ use std::fmt::Debug;
 
 /// Entity with unique identity
 pub trait Entity: Debug {
+    /// Identity type
     type Id: Clone + PartialEq + Eq + Debug;
 
+    /// Identity of the entity
     fn id(&self) -> &Self::Id;
 }
@@ -1,22 +1,239 @@
 //! Domain errors
+//!
+//! The variants cover failures every feature shares. A feature that needs its own
+//! precise error code defines it in its domain layer as a [`DomainError::Custom`]
+//! instead of adding a variant here, e.g.
+//!
+//! ```
+//! use axum_ddd_template::shared::domain::{DomainError, ErrorStatus};
+//!
+//! /// Code of the refusal to add a task past the user's quota
+//! pub const TASK_QUOTA_EXCEEDED: &str = "TASK_QUOTA_EXCEEDED";
+//!
+//! fn task_quota_exceeded(limit: u32) -> DomainError {
+//!     DomainError::Custom {
+//!         code: TASK_QUOTA_EXCEEDED,
+//!         status_hint: ErrorStatus::LimitExceeded,
+//!         message: format!("At most {limit} open tasks per user"),
+//!         details: serde_json::json!({ "limit": limit }),
+//!     }
+//! }
+//! ```
+//!
+//! Adapters render custom errors generically: the REST API answers with the status
+//! of the hint and `{"code", "message", "details"}`.
+//!
+//! The shared variants are built through the constructors of the message catalog
+//! ([`DomainError::not_found`], [`DomainError::already_exists`],
+//! [`DomainError::validation`]), so the same situation is worded the same way
+//! wherever it is detected. Entities are named by their ID type's `entity_name()`.
 
+use std::fmt::Display;
 use thiserror::Error;
 
+/// Reason of a required field left empty, for [`DomainError::validation`]
+pub const CANNOT_BE_EMPTY: &str = "cannot be empty";
+
+/// Message of a rule broken by `field`: `"{field} {reason}"`, as in
+/// [`DomainError::validation`]; for collecting several in `ValidationErrors`
+#[must_use]
+pub fn validation_message(field: &str, reason: impl Display) -> String {
+    format!("{field} {reason}")
+}
+
+/// How a [`DomainError::Custom`] should be reported, independent of the transport
+///
+/// Each adapter maps it to its own status, e.g. an HTTP status or a gRPC code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorStatus {
+    /// Input breaks a business rule (HTTP 422)
+    Invalid,
+    /// Something referenced does not exist (HTTP 404)
+    NotFound,
+    /// The operation conflicts with the current state (HTTP 409)
+    Conflict,
+    /// Something existed but can no longer be used (HTTP 410)
+    Gone,
+    /// A limit on usage has been reached (HTTP 429)
+    LimitExceeded,
+    /// A dependency is temporarily unavailable; retry later (HTTP 503)
+    Unavailable,
+}
+
+/// Error returned by domain rules, use cases and repositories
 #[derive(Debug, Error)]
 pub enum DomainError {
+    /// Input violates a business rule
     #[error("Validation error: {0}")]
     Validation(String),
 
+    /// Referenced entity does not exist
     #[error("Not found: {0}")]
     NotFound(String),
 
+    /// Entity conflicts with an existing one
     #[error("Already exists: {0}")]
     AlreadyExists(String),
 
+    /// Operation conflicts with the entity's current state
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// Entity existed but can no longer be used (e.g. an expired token)
+    #[error("Expired: {0}")]
+    Expired(String),
+
+    /// Error defined by a feature with its own code (see the module documentation)
+    #[error("{message}")]
+    Custom {
+        /// Machine-readable code in `SCREAMING_SNAKE_CASE`, e.g. `HAS_DEPENDENTS`
+        code: &'static str,
+        /// Class of the error, which decides the status adapters report
+        status_hint: ErrorStatus,
+        /// Human-readable explanation
+        message: String,
+        /// Machine-readable specifics; `Value::Null` when there are none
+        details: serde_json::Value,
+    },
+
+    /// A dependency is temporarily overloaded (e.g. the connection pool is exhausted);
+    /// the caller should retry later
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    /// Storage or other infrastructure failure
     #[error("Infrastructure error: {0}")]
     Infrastructure(String),
 
-    #[expect(dead_code, reason = "reserved for future use")]
+    /// Broken invariant; reserved for future use
     #[error("Unexpected error: {0}")]
     Unexpected(String),
 }
+
+impl DomainError {
+    /// `NotFound` for a missing `entity`: `"{entity} not found"`, which
+    /// [`Self::entity`] reads back
+    #[must_use]
+    pub fn not_found(entity: &str) -> Self {
+        Self::NotFound(format!("{entity} not found"))
+    }
+
+    /// `AlreadyExists` for an `entity` whose `field` is taken:
+    /// `"{entity} with this {field} already exists"`
+    #[must_use]
+    pub fn already_exists(entity: &str, field: &str) -> Self {
+        Self::AlreadyExists(format!("{entity} with this {field} already exists"))
+    }
+
+    /// [`Self::already_exists`] naming the ID of the `existing` entity holding the
+    /// value: `"{entity} with this {field} already exists: {existing}"`
+    #[must_use]
+    pub fn already_exists_as(entity: &str, field: &str, existing: &str) -> Self {
+        Self::AlreadyExists(format!("{entity} with this {field} already exists: {existing}"))
+    }
+
+    /// `Validation` of a rule broken by `field`: `"{field} {reason}"`, e.g.
+    /// `"Title cannot be empty"` (see [`CANNOT_BE_EMPTY`])
+    #[must_use]
+    pub fn validation(field: &str, reason: impl Display) -> Self {
+        Self::Validation(validation_message(field, reason))
+    }
+
+    /// Variant name in `snake_case` (e.g. `not_found`), for telemetry
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Validation(_) => "validation",
+            Self::NotFound(_) => "not_found",
+            Self::AlreadyExists(_) => "already_exists",
+            Self::Conflict(_) => "conflict",
+            Self::Expired(_) => "expired",
+            Self::Custom { .. } => "custom",
+            Self::Unavailable(_) => "unavailable",
+            Self::Infrastructure(_) => "infrastructure",
+            Self::Unexpected(_) => "unexpected",
+        }
+    }
+
+    /// Entity a `NotFound` error is about, read from its `"{entity} not found"` message
+    #[must_use]
+    pub fn entity(&self) -> Option<&str> {
+        match self {
+            Self::NotFound(message) => message.strip_suffix(" not found"),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_and_entity_should_describe_the_error() {
+        let not_found = DomainError::NotFound("Task not found".into());
+        assert_eq!((not_found.kind(), not_found.entity()), ("not_found", Some("Task")));
+        let other = DomainError::NotFound("Nothing here".into());
+        assert_eq!(other.entity(), None);
+        let failed = DomainError::Infrastructure("connection reset".into());
+        assert_eq!((failed.kind(), failed.entity()), ("infrastructure", None));
+        let custom = DomainError::Custom {
+            code: "QUOTA_EXCEEDED",
+            status_hint: ErrorStatus::LimitExceeded,
+            message: "Too many tasks".into(),
+            details: serde_json::Value::Null,
+        };
+        assert_eq!((custom.kind(), custom.to_string()), ("custom", "Too many tasks".into()));
+    }
+
+    #[test]
+    fn catalog_should_word_each_situation_exactly() {
+        let not_found = DomainError::not_found("Task");
+        assert_eq!(not_found.to_string(), "Not found: Task not found");
+        assert_eq!(not_found.entity(), Some("Task"));
+        assert_eq!(
+            DomainError::already_exists("User", "email").to_string(),
+            "Already exists: User with this email already exists"
+        );
+        assert_eq!(
+            DomainError::already_exists_as("Open task", "title", "t1").to_string(),
+            "Already exists: Open task with this title already exists: t1"
+        );
+        assert_eq!(
+            DomainError::validation("Title", CANNOT_BE_EMPTY).to_string(),
+            "Validation error: Title cannot be empty"
+        );
+        let message = validation_message("Email", "must be at most 5 characters");
+        assert_eq!(message, "Email must be at most 5 characters");
+    }
+
+    /// Feature code outside tests builds these variants through the catalog only
+    #[test]
+    fn feature_code_should_not_word_shared_errors_itself() {
+        fn sources(dir: &std::path::Path, found: &mut Vec<std::path::PathBuf>) {
+            for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    sources(&path, found);
+                } else if path.extension().is_some_and(|ext| ext == "rs") {
+                    found.push(path);
+                }
+            }
+        }
+        let mut files = Vec::new();
+        let features = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/features");
+        sources(&features, &mut files);
+        assert!(!files.is_empty());
+        let raw = ["NotFound(format!", "NotFound(\"", "AlreadyExists(format!", "AlreadyExists(\""];
+        let offenders: Vec<_> = files
+            .iter()
+            .filter(|path| {
+                let source = std::fs::read_to_string(path).unwrap_or_default();
+                let code = source.split("#[cfg(test)]").next().unwrap_or_default();
+                let code: String = code.split_whitespace().collect();
+                raw.iter().any(|pattern| code.contains(pattern))
+            })
+            .collect();
+        assert!(offenders.is_empty(), "use the DomainError constructors in {offenders:?}");
+    }
+}
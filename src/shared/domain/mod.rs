@@ -1,9 +1,23 @@
 //! Shared domain types and abstractions
 
+pub mod audit;
+pub mod email_sender;
 pub mod entity;
 pub mod error;
+pub mod outbox;
+pub mod page;
+pub mod snapshot;
+pub mod tenant;
+pub mod timestamp;
 pub mod value_objects;
+pub mod warning;
 
+pub use audit::AuditEntry;
+pub use email_sender::EmailSender;
 pub use entity::Entity;
-pub use error::DomainError;
+pub use error::{validation_message, DomainError, ErrorStatus, CANNOT_BE_EMPTY};
+pub use outbox::{OutboxEvent, StoredEvent};
+pub use page::Page;
+pub use tenant::TenantId;
 pub use value_objects::{Email, UserId};
+pub use warning::DomainWarning;
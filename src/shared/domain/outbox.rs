@@ -0,0 +1,50 @@
+//! Transactional outbox: events raised by a change, published after it commits
+
+use super::{DomainError, TenantId};
+use crate::shared::events::EventPayload;
+use chrono::{DateTime, Utc};
+
+/// Something that happened to the data of a tenant, written to the outbox in the same
+/// transaction as the change, so that it is published exactly when the change commits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxEvent {
+    /// What happened, as `entity.past_tense_verb`, e.g. `tasks.reassigned`
+    pub event_type: &'static str,
+    /// Version of the payload's schema, see [`crate::shared::events`]
+    pub version: u16,
+    /// ID of the entity it happened to
+    pub subject: String,
+    /// Specifics of the event, published as they are
+    pub payload: serde_json::Value,
+}
+
+impl OutboxEvent {
+    /// Event of `payload`, typed and versioned by its schema, that happened to `subject`
+    ///
+    /// # Errors
+    /// `Unexpected` if the payload cannot be serialized.
+    pub fn new<P: EventPayload>(subject: String, payload: &P) -> Result<Self, DomainError> {
+        let payload =
+            serde_json::to_value(payload).map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        Ok(Self { event_type: P::SCHEMA.event_type, version: P::SCHEMA.version, subject, payload })
+    }
+}
+
+/// An event as stored in the outbox, waiting to be published
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredEvent {
+    /// Position in the outbox; later events have larger IDs
+    pub id: i64,
+    /// Tenant whose data changed
+    pub tenant: TenantId,
+    /// What happened, see [`OutboxEvent::event_type`]
+    pub event_type: String,
+    /// Version of the payload's schema
+    pub version: u16,
+    /// ID of the entity it happened to
+    pub subject: String,
+    /// Specifics of the event
+    pub payload: serde_json::Value,
+    /// When the event was recorded
+    pub occurred_at: DateTime<Utc>,
+}
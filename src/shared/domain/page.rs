@@ -0,0 +1,22 @@
+//! Bounded slices of list results
+
+/// A bounded slice of a listing: at most `limit` items after skipping `offset`
+///
+/// Repositories return pages in a stable order so consecutive pages neither
+/// repeat nor skip items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    /// Most items to return
+    pub limit: u32,
+    /// Items to skip
+    pub offset: u32,
+}
+
+impl Page {
+    /// The items of `items`, already in listing order, that fall on this page
+    pub fn slice<T>(self, items: impl IntoIterator<Item = T>) -> Vec<T> {
+        let offset = usize::try_from(self.offset).unwrap_or(usize::MAX);
+        let limit = usize::try_from(self.limit).unwrap_or(usize::MAX);
+        items.into_iter().skip(offset).take(limit).collect()
+    }
+}
@@ -0,0 +1,84 @@
+//! Versioned JSON snapshots of aggregates
+//!
+//! A snapshot is a JSON object of an aggregate's fields and its `schema_version`.
+//! An aggregate bumps its version whenever it adds a field, giving the new field a
+//! default, so that snapshots of every earlier version can still be read. Snapshots
+//! of a later version than the reader knows are rejected.
+
+use crate::shared::domain::DomainError;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Field holding the version of a snapshot's schema
+pub const SCHEMA_VERSION: &str = "schema_version";
+
+/// Snapshot of `fields`, a struct serializing to a JSON object, at `version`
+pub(crate) fn write(fields: &impl Serialize, version: u32) -> Value {
+    let mut snapshot = serde_json::to_value(fields).unwrap_or_default();
+    if let Value::Object(map) = &mut snapshot {
+        map.insert(SCHEMA_VERSION.to_owned(), version.into());
+    }
+    snapshot
+}
+
+/// Fields of the `entity` snapshot `snapshot`, written at any version from 1 to
+/// `current`; fields added after its version take their defaults
+///
+/// # Errors
+/// Returns `DomainError::Validation` if the snapshot has no supported version or its
+/// fields cannot be read.
+pub(crate) fn read<T: DeserializeOwned>(
+    snapshot: Value,
+    entity: &str,
+    current: u32,
+) -> Result<T, DomainError> {
+    match snapshot.get(SCHEMA_VERSION).and_then(Value::as_u64) {
+        Some(version) if (1..=u64::from(current)).contains(&version) => {}
+        Some(version) => {
+            return Err(DomainError::Validation(format!(
+                "{entity} snapshot schema version {version} is not between 1 and {current}"
+            )))
+        }
+        None => {
+            return Err(DomainError::Validation(format!(
+                "{entity} snapshot has no {SCHEMA_VERSION}"
+            )))
+        }
+    }
+    serde_json::from_value(snapshot)
+        .map_err(|e| DomainError::Validation(format!("Invalid {entity} snapshot: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Fields {
+        name: String,
+        #[serde(default)]
+        added_in_v2: bool,
+    }
+
+    #[test]
+    fn snapshots_should_be_read_at_every_known_version_only() {
+        let snapshot = write(&Fields { name: "a".into(), added_in_v2: true }, 2);
+        let expected = serde_json::json!({"name": "a", "added_in_v2": true, "schema_version": 2});
+        assert_eq!(snapshot, expected);
+        let read = |snapshot: Value| read::<Fields>(snapshot, "Thing", 2);
+        assert_eq!(read(snapshot).ok(), Some(Fields { name: "a".into(), added_in_v2: true }));
+
+        let v1 = serde_json::json!({"name": "a", "schema_version": 1});
+        assert_eq!(read(v1).ok(), Some(Fields { name: "a".into(), added_in_v2: false }));
+        for invalid in [
+            serde_json::json!({"name": "a", "schema_version": 3}),
+            serde_json::json!({"name": "a", "schema_version": 0}),
+            serde_json::json!({"name": "a"}),
+            serde_json::json!({"schema_version": 1}),
+            serde_json::json!("a"),
+        ] {
+            assert!(matches!(read(invalid.clone()), Err(DomainError::Validation(_))), "{invalid}");
+        }
+    }
+}
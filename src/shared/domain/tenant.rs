@@ -0,0 +1,73 @@
+//! Tenant value object: the organization every aggregate belongs to
+
+use crate::shared::domain::DomainError;
+
+/// Longest tenant ID accepted
+pub const MAX_TENANT_ID_LENGTH: usize = 64;
+
+/// Identifier of the tenant (organization) owning an aggregate
+///
+/// Every repository call is scoped to one tenant, so data of another tenant is
+/// never read or written. Deployments without multi-tenancy keep everything in
+/// the [`TenantId::DEFAULT`] tenant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TenantId(String);
+
+impl TenantId {
+    /// Tenant of single-tenant deployments and of rows that predate tenants
+    pub const DEFAULT: &str = "default";
+
+    /// Create a tenant ID from user input
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` unless `id` is 1 to [`MAX_TENANT_ID_LENGTH`]
+    /// lowercase ASCII letters, digits, `-` or `_`.
+    pub fn new(id: &str) -> Result<Self, DomainError> {
+        if id.is_empty() || id.len() > MAX_TENANT_ID_LENGTH {
+            return Err(DomainError::Validation(format!(
+                "Tenant ID must be between 1 and {MAX_TENANT_ID_LENGTH} characters"
+            )));
+        }
+        if !id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_".contains(c)) {
+            return Err(DomainError::Validation(
+                "Tenant ID may only contain lowercase letters, digits, '-' and '_'".into(),
+            ));
+        }
+        Ok(Self(id.to_owned()))
+    }
+
+    /// Reconstitute from trusted storage without re-validation
+    #[must_use]
+    pub fn from_trusted(value: String) -> Self {
+        Self(value)
+    }
+
+    /// Get the ID value
+    #[must_use]
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The [`TenantId::DEFAULT`] tenant
+impl Default for TenantId {
+    fn default() -> Self {
+        Self(Self::DEFAULT.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_should_accept_only_short_lowercase_slugs() {
+        for id in ["acme", "acme-corp_2", TenantId::DEFAULT] {
+            assert_eq!(TenantId::new(id).map(|t| t.value().to_owned()).ok().as_deref(), Some(id));
+        }
+        let too_long = "a".repeat(MAX_TENANT_ID_LENGTH + 1);
+        for id in ["", "Acme", "acme corp", "acme/other", "ａcme", too_long.as_str()] {
+            assert!(matches!(TenantId::new(id), Err(DomainError::Validation(_))), "{id}");
+        }
+    }
+}
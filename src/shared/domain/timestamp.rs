@@ -0,0 +1,82 @@
+//! Timestamp wire format shared by every API and event: RFC3339 in UTC with a `Z`
+//! suffix and millisecond precision (e.g. `2026-01-01T00:00:00.000Z`)
+
+use crate::shared::domain::DomainError;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serializer;
+
+/// Format `at` in the API timestamp format
+#[must_use]
+pub fn format(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Parse an incoming RFC3339 timestamp with any offset, normalized to UTC.
+///
+/// # Errors
+/// Returns a validation error naming `field` when `value` is not RFC3339.
+pub fn parse(field: &str, value: &str) -> Result<DateTime<Utc>, DomainError> {
+    DateTime::parse_from_rfc3339(value).map(|at| at.with_timezone(&Utc)).map_err(|_| {
+        DomainError::Validation(format!(
+            "{field} must be an RFC3339 timestamp (e.g. 2026-01-01T09:00:00+09:00)"
+        ))
+    })
+}
+
+/// `serialize_with` helper for `DateTime<Utc>` fields
+///
+/// # Errors
+/// Propagates serializer errors.
+pub fn serialize<S: Serializer>(at: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(at))
+}
+
+/// `serialize_with` helper for `Option<DateTime<Utc>>` fields
+///
+/// # Errors
+/// Propagates serializer errors.
+pub fn serialize_option<S: Serializer>(
+    at: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match at {
+        Some(at) => serializer.serialize_str(&format(at)),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_should_normalize_offsets_to_utc_with_millis() {
+        let at = parse("due_at", "2026-01-01T09:00:00+09:00");
+        let formatted = at.map(|at| format(&at));
+        assert_eq!(formatted.ok().as_deref(), Some("2026-01-01T00:00:00.000Z"));
+    }
+
+    #[test]
+    fn timestamps_should_round_trip_edge_values() {
+        for (input, expected) in [
+            ("2016-12-31T23:59:60Z", "2016-12-31T23:59:60.000Z"),
+            ("1999-12-31T23:59:59.9999-00:30", "2000-01-01T00:29:59.999Z"),
+            ("2026-06-30T23:59:59.5+14:00", "2026-06-30T09:59:59.500Z"),
+        ] {
+            let formatted = parse("due_at", input).map(|at| format(&at));
+            assert_eq!(formatted.ok().as_deref(), Some(expected), "{input}");
+            let reparsed = parse("due_at", expected).map(|at| format(&at));
+            assert_eq!(reparsed.ok().as_deref(), Some(expected), "{expected}");
+        }
+    }
+
+    #[test]
+    fn parse_should_reject_non_rfc3339_with_field_name() {
+        for input in ["2026-01-01", "2026-01-01 09:00:00", "tomorrow"] {
+            let message = parse("due_at", input).err().map(|e| e.to_string());
+            let expected = "Validation error: due_at must be an RFC3339 timestamp \
+                            (e.g. 2026-01-01T09:00:00+09:00)";
+            assert_eq!(message.as_deref(), Some(expected));
+        }
+    }
+}
@@ -4,46 +4,105 @@ use crate::shared::domain::DomainError;
 use email_address::EmailAddress;
 use serde::{Deserialize, Serialize};
 
-/// Generate a typed string ID value object with validation.
+/// Define a typed string ID value object with validation.
+///
+/// `string_id!(ProjectId, "Project")` defines `pub struct ProjectId` with:
+///
+/// - `generate()` — a new random UUID v4 ID
+/// - `new(&str)` — validated input; an empty string is a
+///   [`DomainError::Validation`](crate::shared::domain::DomainError::Validation)
+///   ("Project ID cannot be empty")
+/// - `from_trusted(String)` — reconstitution from storage without validation
+/// - `value()` — the ID as `&str`
+/// - `entity_name()` — the label (`"Project"`), used in error messages
+///
+/// The type derives `Debug`, `Clone`, `PartialEq`, `Eq` and `Hash`, and serializes
+/// as a plain string. This shape is stable; downstream crates need no extra
+/// dependencies to use it.
+///
+/// ```
+/// axum_ddd_template::string_id!(ProjectId, "Project");
+///
+/// let id = ProjectId::new("p-1")?;
+/// assert_eq!(id.value(), "p-1");
+/// assert_eq!(ProjectId::entity_name(), "Project");
+/// assert!(ProjectId::new("").is_err());
+/// # Ok::<(), axum_ddd_template::shared::domain::DomainError>(())
+/// ```
+#[macro_export]
 macro_rules! string_id {
     ($name:ident, $label:literal) => {
-        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[doc = concat!("Identifier of a ", $label)]
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
         pub struct $name(String);
 
         impl $name {
+            /// Generate a new random (UUID v4) ID
             #[must_use]
             pub fn generate() -> Self {
-                Self(uuid::Uuid::new_v4().to_string())
+                Self($crate::__private::uuid::Uuid::new_v4().to_string())
             }
 
-            pub fn new(id: &str) -> Result<Self, crate::shared::domain::DomainError> {
+            /// Create an ID from user input
+            ///
+            /// # Errors
+            /// Returns `DomainError::Validation` if `id` is empty.
+            pub fn new(id: &str) -> Result<Self, $crate::shared::domain::DomainError> {
                 if id.is_empty() {
-                    return Err(crate::shared::domain::DomainError::Validation(
-                        concat!($label, " ID cannot be empty").into(),
+                    return Err($crate::shared::domain::DomainError::validation(
+                        concat!($label, " ID"),
+                        $crate::shared::domain::CANNOT_BE_EMPTY,
                     ));
                 }
                 Ok(Self(id.to_owned()))
             }
 
             /// Reconstitute from trusted storage without re-validation
+            #[must_use]
             pub fn from_trusted(value: String) -> Self {
                 Self(value)
             }
 
+            /// Get the ID value
+            #[must_use]
             pub fn value(&self) -> &str {
                 &self.0
             }
 
+            /// Name of the entity this ID identifies, for messages
+            #[must_use]
             pub fn entity_name() -> &'static str {
                 $label
             }
         }
+
+        impl $crate::__private::serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: $crate::__private::serde::Serializer,
+            {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> $crate::__private::serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: $crate::__private::serde::Deserializer<'de>,
+            {
+                let value: String = $crate::__private::serde::Deserialize::deserialize(deserializer)?;
+                Ok(Self(value))
+            }
+        }
     };
 }
 
-pub(crate) use string_id;
+crate::string_id!(UserId, "User");
 
-string_id!(UserId, "User");
+/// Maximum length of an email address (RFC 5321 path limit minus the angle brackets)
+pub const MAX_EMAIL_LENGTH: usize = 254;
+/// Maximum length of the local part of an email address (RFC 5321)
+pub const MAX_EMAIL_LOCAL_PART_LENGTH: usize = 64;
 
 /// Email value object
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -51,7 +110,24 @@ pub struct Email(String);
 
 impl Email {
     /// Create a new email with validation
+    ///
+    /// # Errors
+    /// Returns `DomainError::Validation` if `email` is longer than [`MAX_EMAIL_LENGTH`],
+    /// its local part is longer than [`MAX_EMAIL_LOCAL_PART_LENGTH`], or it is not a
+    /// valid address.
     pub fn new(email: &str) -> Result<Self, DomainError> {
+        if email.chars().count() > MAX_EMAIL_LENGTH {
+            return Err(DomainError::validation(
+                "Email",
+                format!("must be at most {MAX_EMAIL_LENGTH} characters"),
+            ));
+        }
+        if split(email).0.chars().count() > MAX_EMAIL_LOCAL_PART_LENGTH {
+            return Err(DomainError::validation(
+                "Email local part",
+                format!("must be at most {MAX_EMAIL_LOCAL_PART_LENGTH} characters"),
+            ));
+        }
         if !EmailAddress::is_valid(email) {
             return Err(DomainError::Validation("Invalid email format".into()));
         }
@@ -59,14 +135,39 @@ impl Email {
     }
 
     /// Reconstitute from trusted storage without re-validation
+    #[must_use]
     pub fn from_trusted(value: String) -> Self {
         Self(value)
     }
 
     /// Get email value
+    #[must_use]
     pub fn value(&self) -> &str {
         &self.0
     }
+
+    /// Part before the last `@` (the mailbox)
+    #[must_use]
+    pub fn local_part(&self) -> &str {
+        split(&self.0).0
+    }
+
+    /// Part after the last `@` (the mail provider)
+    #[must_use]
+    pub fn domain(&self) -> &str {
+        split(&self.0).1
+    }
+
+    /// Whether the address belongs to `domain`, compared case-insensitively
+    #[must_use]
+    pub fn provider_is(&self, domain: &str) -> bool {
+        self.domain().eq_ignore_ascii_case(domain)
+    }
+}
+
+/// Split an address at its last `@`; quoted local parts may contain `@` themselves
+fn split(email: &str) -> (&str, &str) {
+    email.rsplit_once('@').unwrap_or((email, ""))
 }
 
 #[cfg(test)]
@@ -84,6 +185,49 @@ mod tests {
         assert!(Email::new("test@example.com").is_ok());
     }
 
+    fn validation_message(email: &str) -> String {
+        match Email::new(email) {
+            Err(DomainError::Validation(message)) => message,
+            other => panic!("expected validation error for {email}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn email_new_should_enforce_length_limits() {
+        let domain = format!("{}.com", "d".repeat(60));
+        let local = "l".repeat(MAX_EMAIL_LOCAL_PART_LENGTH);
+        assert!(Email::new(&format!("{local}@{domain}")).is_ok());
+        assert_eq!(
+            validation_message(&format!("{local}x@{domain}")),
+            "Email local part must be at most 64 characters"
+        );
+
+        let labels = ["a".repeat(63), "b".repeat(63), "c".repeat(63), "d".repeat(59)];
+        let at_limit = format!("ab@{}", labels.join("."));
+        assert_eq!(at_limit.len(), MAX_EMAIL_LENGTH);
+        assert!(Email::new(&at_limit).is_ok());
+        assert_eq!(
+            validation_message(&format!("x{at_limit}")),
+            "Email must be at most 254 characters"
+        );
+    }
+
+    #[test]
+    fn email_should_expose_local_part_and_domain() {
+        for (email, local, domain) in [
+            ("alice@example.com", "alice", "example.com"),
+            ("first.last+tag@mail.example.co.uk", "first.last+tag", "mail.example.co.uk"),
+            ("\"a@b\"@example.com", "\"a@b\"", "example.com"),
+            ("o'brien@Example.ORG", "o'brien", "Example.ORG"),
+        ] {
+            let email = Email::new(email).unwrap_or_else(|e| panic!("{email}: {e}"));
+            assert_eq!((email.local_part(), email.domain()), (local, domain));
+        }
+        let email = Email::from_trusted("o'brien@Example.ORG".into());
+        assert!(email.provider_is("example.org"));
+        assert!(!email.provider_is("ample.org"));
+    }
+
     #[test]
     fn user_id_new_should_reject_empty() {
         assert!(UserId::new("").is_err());
@@ -93,4 +237,13 @@ mod tests {
     fn user_id_generate_should_be_non_empty() {
         assert!(!UserId::generate().value().is_empty());
     }
+
+    #[test]
+    fn ids_should_serialize_as_plain_strings() {
+        let id = UserId::from_trusted("u-1".into());
+        let json = serde_json::to_string(&id).ok();
+        assert_eq!(json.as_deref(), Some(r#""u-1""#));
+        let decoded = json.and_then(|json| serde_json::from_str::<UserId>(&json).ok());
+        assert_eq!(decoded, Some(id));
+    }
 }
@@ -0,0 +1,15 @@
+//! Soft validation warnings
+
+/// A soft rule the input broke: the input is accepted, but the client is told about it.
+///
+/// Domain methods enforcing soft rules return `(value, Vec<DomainWarning>)`; hard rules
+/// keep failing with [`DomainError`](crate::shared::domain::DomainError).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainWarning {
+    /// Stable machine-readable code, e.g. `TITLE_TOO_LONG`
+    pub code: &'static str,
+    /// Human-readable explanation
+    pub message: String,
+    /// Input field the warning is about
+    pub field: &'static str,
+}
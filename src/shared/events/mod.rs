@@ -0,0 +1,266 @@
+//! Versioned schemas of the events we emit
+//!
+//! Every event leaves the service (webhook deliveries of outbox events, the email
+//! change webhook) in an [`EventEnvelope`] of `{event_type, version, occurred_at,
+//! payload}`, its payload serialized from one of the structs below. A payload struct
+//! is a published contract: once released its shape is frozen, and a change that
+//! renames, removes or retypes a field is a new struct with the next version
+//! (`TasksReassignedV2`), emitted instead of the old one. Golden JSON fixtures of every
+//! version in `fixtures/` fail the tests when a released shape changes.
+//!
+//! [`SCHEMAS`] lists every type and version, and is served by `GET /events/schema`.
+
+use crate::shared::domain::timestamp;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A released version of an event type and the fields of its payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSchema {
+    /// What happened, as `entity.past_tense_verb`
+    pub event_type: &'static str,
+    /// Version of the payload, from 1
+    pub version: u16,
+    /// When the event is emitted
+    pub description: &'static str,
+    /// Fields of the payload, in the order they are serialized
+    pub fields: &'static [EventField],
+}
+
+/// A field of an event payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EventField {
+    /// Name of the field in the payload
+    pub name: &'static str,
+    /// JSON type: `string`, `integer`, `boolean`, or `timestamp` for an RFC 3339
+    /// string
+    #[serde(rename = "type")]
+    pub json_type: &'static str,
+}
+
+impl EventField {
+    const fn new(name: &'static str, json_type: &'static str) -> Self {
+        Self { name, json_type }
+    }
+}
+
+/// Payload of one version of an event type
+pub trait EventPayload: Serialize {
+    /// Type, version and fields of the payload
+    const SCHEMA: EventSchema;
+}
+
+/// What every event is sent as, whatever its type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventEnvelope<P = serde_json::Value> {
+    /// What happened, see [`EventSchema::event_type`]
+    pub event_type: String,
+    /// Version of the payload's schema
+    pub version: u16,
+    /// When it happened
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub occurred_at: DateTime<Utc>,
+    /// Specifics of the event, shaped by the type and version
+    pub payload: P,
+}
+
+impl<P: EventPayload> EventEnvelope<P> {
+    /// Envelope of `payload`, typed and versioned by its schema
+    pub fn new(payload: P, occurred_at: DateTime<Utc>) -> Self {
+        Self {
+            event_type: P::SCHEMA.event_type.to_owned(),
+            version: P::SCHEMA.version,
+            occurred_at,
+            payload,
+        }
+    }
+}
+
+/// A user was onboarded together with their first task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserOnboardedV1 {
+    /// Name of the new user
+    pub name: String,
+    /// Email address of the new user
+    pub email: String,
+    /// ID of their first task
+    pub task_id: String,
+}
+
+impl EventPayload for UserOnboardedV1 {
+    const SCHEMA: EventSchema = EventSchema {
+        event_type: "user.onboarded",
+        version: 1,
+        description: "A user was onboarded together with their first task",
+        fields: &[
+            EventField::new("name", "string"),
+            EventField::new("email", "string"),
+            EventField::new("task_id", "string"),
+        ],
+    };
+}
+
+/// The tasks of a user were moved to another user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TasksReassignedV1 {
+    /// User the tasks were moved to
+    pub to_user_id: String,
+    /// Whether only open tasks were moved
+    pub only_open: bool,
+    /// Number of tasks moved
+    pub reassigned: u64,
+}
+
+impl EventPayload for TasksReassignedV1 {
+    const SCHEMA: EventSchema = EventSchema {
+        event_type: "tasks.reassigned",
+        version: 1,
+        description: "The tasks of a user were moved to another user",
+        fields: &[
+            EventField::new("to_user_id", "string"),
+            EventField::new("only_open", "boolean"),
+            EventField::new("reassigned", "integer"),
+        ],
+    };
+}
+
+/// A user asked to change their email address; the receiver mails `token` to
+/// `new_email`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserEmailChangeRequestedV1 {
+    /// User changing their address
+    pub user_id: String,
+    /// Address to change to once confirmed
+    pub new_email: String,
+    /// Token confirming the change
+    pub token: String,
+    /// When the token stops being valid
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub expires_at: DateTime<Utc>,
+}
+
+impl EventPayload for UserEmailChangeRequestedV1 {
+    const SCHEMA: EventSchema = EventSchema {
+        event_type: "user.email_change_requested",
+        version: 1,
+        description: "A user asked to change their email address",
+        fields: &[
+            EventField::new("user_id", "string"),
+            EventField::new("new_email", "string"),
+            EventField::new("token", "string"),
+            EventField::new("expires_at", "timestamp"),
+        ],
+    };
+}
+
+/// Every event type and version emitted, by type then version
+pub const SCHEMAS: &[EventSchema] = &[
+    TasksReassignedV1::SCHEMA,
+    UserEmailChangeRequestedV1::SCHEMA,
+    UserOnboardedV1::SCHEMA,
+];
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+    use std::fmt::Debug;
+
+    /// Golden envelope of every schema, by type and version
+    const FIXTURES: &[(&str, u16, &str)] = &[
+        ("tasks.reassigned", 1, include_str!("fixtures/tasks.reassigned.v1.json")),
+        (
+            "user.email_change_requested",
+            1,
+            include_str!("fixtures/user.email_change_requested.v1.json"),
+        ),
+        ("user.onboarded", 1, include_str!("fixtures/user.onboarded.v1.json")),
+    ];
+
+    fn occurred_at() -> DateTime<Utc> {
+        "2026-01-01T09:30:00Z".parse().expect("valid timestamp")
+    }
+
+    fn fixture(schema: &EventSchema) -> Value {
+        let (_, _, json) = FIXTURES
+            .iter()
+            .find(|(event_type, version, _)| {
+                (*event_type, *version) == (schema.event_type, schema.version)
+            })
+            .expect("a fixture for every schema");
+        serde_json::from_str(json).expect("fixture is JSON")
+    }
+
+    /// Assert that `example` serializes exactly as its golden fixture, which reads back
+    /// as `example`, and that its schema lists the fields it serializes
+    fn assert_matches_fixture<P>(example: P)
+    where
+        P: EventPayload + DeserializeOwned + PartialEq + Debug + Clone,
+    {
+        let schema = P::SCHEMA;
+        let name = format!("{} v{}", schema.event_type, schema.version);
+        let golden = fixture(&schema);
+        let serialized = serde_json::to_value(EventEnvelope::new(example.clone(), occurred_at()))
+            .expect("serializable");
+        assert_eq!(
+            serialized, golden,
+            "the shape of {name} changed: add a new version instead of changing a released one"
+        );
+        let read: EventEnvelope<P> = serde_json::from_value(golden.clone()).expect("readable");
+        assert_eq!(read, EventEnvelope::new(example, occurred_at()), "{name}");
+
+        let payload = golden["payload"].as_object().expect("payload object");
+        let mut keys: Vec<&str> = payload.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        let mut listed: Vec<&str> = schema.fields.iter().map(|f| f.name).collect();
+        listed.sort_unstable();
+        assert_eq!(keys, listed, "fields of {name}");
+        for field in schema.fields {
+            let value = &payload[field.name];
+            let matches = match field.json_type {
+                "string" => value.is_string(),
+                "integer" => value.is_u64() || value.is_i64(),
+                "boolean" => value.is_boolean(),
+                "timestamp" => value.as_str().is_some_and(|v| v.parse::<DateTime<Utc>>().is_ok()),
+                other => panic!("unknown type '{other}' of {name}.{}", field.name),
+            };
+            assert!(matches, "{name}.{} is not a {}: {value}", field.name, field.json_type);
+        }
+    }
+
+    #[test]
+    fn payloads_should_serialize_as_their_golden_fixtures() {
+        assert_matches_fixture(UserOnboardedV1 {
+            name: "Alice".into(),
+            email: "alice@example.com".into(),
+            task_id: "0b6f2a34-9d1e-4c55-8f0e-3a7d21c4e9b0".into(),
+        });
+        assert_matches_fixture(TasksReassignedV1 {
+            to_user_id: "user-2".into(),
+            only_open: true,
+            reassigned: 3,
+        });
+        assert_matches_fixture(UserEmailChangeRequestedV1 {
+            user_id: "user-1".into(),
+            new_email: "new@example.com".into(),
+            token: "token".into(),
+            expires_at: "2026-01-02T09:30:00Z".parse().expect("valid timestamp"),
+        });
+    }
+
+    #[test]
+    fn every_schema_should_have_exactly_one_fixture() {
+        let listed: Vec<(&str, u16)> = SCHEMAS.iter().map(|s| (s.event_type, s.version)).collect();
+        let mut sorted = listed.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(listed, sorted, "SCHEMAS are unique and ordered by type then version");
+        for schema in SCHEMAS {
+            assert!(fixture(schema)["payload"].is_object(), "{}", schema.event_type);
+        }
+        assert_eq!(FIXTURES.len(), SCHEMAS.len(), "a fixture without a schema");
+    }
+}
@@ -0,0 +1,224 @@
+//! Admin listener: metrics, readiness and internal endpoints on `ADMIN_PORT`
+//!
+//! The public API never serves these routes; operators reach them on the admin port
+//! only, which should not be exposed outside the cluster. `/metrics` renders every
+//! metric recorded through the `metrics` facade in the Prometheus text format.
+
+use crate::shared::infrastructure::http::{json_response, Health};
+use axum::{
+    extract::{connect_info::Connected, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    serve::{IncomingStream, Listener},
+    Router,
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use sqlx::PgPool;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+/// Gauge of open connections to the public API
+pub const HTTP_CONNECTIONS_ACTIVE: &str = "http_connections_active";
+
+/// How long `/ready` waits for the database to answer
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Shared state of the admin routes
+#[derive(Clone)]
+pub struct AdminState {
+    metrics: PrometheusHandle,
+    pool: Option<PgPool>,
+}
+
+impl AdminState {
+    /// Render metrics from `metrics` and report ready once `pool` answers a query;
+    /// without a pool the service is always ready
+    #[must_use]
+    pub fn new(metrics: PrometheusHandle, pool: Option<PgPool>) -> Self {
+        Self { metrics, pool }
+    }
+}
+
+/// `GET /metrics` and `GET /ready`
+pub fn routes(state: AdminState) -> Router {
+    Router::new().route("/metrics", get(metrics)).route("/ready", get(ready)).with_state(state)
+}
+
+/// `GET /metrics`: every recorded metric in the Prometheus text format
+async fn metrics(State(state): State<AdminState>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], state.metrics.render())
+}
+
+/// `GET /ready`: 200 once the database answers, 503 otherwise
+async fn ready(State(state): State<AdminState>) -> Response {
+    let reachable = match &state.pool {
+        Some(pool) => {
+            let ping = sqlx::query("SELECT 1").execute(pool);
+            matches!(tokio::time::timeout(READY_TIMEOUT, ping).await, Ok(Ok(_)))
+        }
+        None => true,
+    };
+    if reachable {
+        json_response(StatusCode::OK, &Health { status: "ready" })
+    } else {
+        json_response(StatusCode::SERVICE_UNAVAILABLE, &Health { status: "unavailable" })
+    }
+}
+
+/// Serve `app` on `listener` and `admin` on `admin_listener` until `shutdown` changes
+///
+/// Both servers drain on the same signal. Connections to `app` are counted in
+/// [`HTTP_CONNECTIONS_ACTIVE`].
+///
+/// # Errors
+/// Fails when either server fails.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    admin_listener: TcpListener,
+    admin: Router,
+    shutdown: watch::Receiver<()>,
+) -> io::Result<()> {
+    let app = app.into_make_service_with_connect_info::<PeerAddr>();
+    let app = axum::serve(CountedListener(listener), app)
+        .with_graceful_shutdown(until_shutdown(shutdown.clone()));
+    let admin = axum::serve(admin_listener, admin).with_graceful_shutdown(until_shutdown(shutdown));
+    tokio::try_join!(async { app.await }, async { admin.await })?;
+    Ok(())
+}
+
+/// Resolve once the shutdown signal has been broadcast (or its sender is gone)
+async fn until_shutdown(mut shutdown: watch::Receiver<()>) {
+    shutdown.changed().await.ok();
+}
+
+/// Listener counting its open connections in [`HTTP_CONNECTIONS_ACTIVE`]
+pub struct CountedListener<L>(pub L);
+
+impl<L: Listener> Listener for CountedListener<L> {
+    type Io = CountedIo<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let (io, addr) = self.0.accept().await;
+        metrics::gauge!(HTTP_CONNECTIONS_ACTIVE).increment(1.0);
+        (CountedIo(io), addr)
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.0.local_addr()
+    }
+}
+
+/// Address of the client end of a connection to the public API, available to
+/// handlers and middleware as `ConnectInfo<PeerAddr>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerAddr(pub SocketAddr);
+
+impl Connected<IncomingStream<'_, CountedListener<TcpListener>>> for PeerAddr {
+    fn connect_info(stream: IncomingStream<'_, CountedListener<TcpListener>>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+/// Connection accepted by a [`CountedListener`], uncounted when dropped
+pub struct CountedIo<I>(I);
+
+impl<I> Drop for CountedIo<I> {
+    fn drop(&mut self) {
+        metrics::gauge!(HTTP_CONNECTIONS_ACTIVE).decrement(1.0);
+    }
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for CountedIo<I> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for CountedIo<I> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.0.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::app::{build_admin_router, build_router, AppState, InMemoryRepositories};
+    use crate::shared::infrastructure::config::Config;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    #[tokio::test]
+    async fn admin_listener_should_serve_metrics_and_internal_routes_apart_from_the_api() {
+        // The only test installing a global recorder, which the servers' tasks record into
+        let metrics = PrometheusBuilder::new().install_recorder().expect("recorder");
+        let config = Config::default();
+        let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+        let app = build_router(&state, &config).expect("router");
+        let admin = build_admin_router(&state, metrics, None);
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let admin_listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let api = format!("http://{}", listener.local_addr().expect("addr"));
+        let ops = format!("http://{}", admin_listener.local_addr().expect("addr"));
+        let (shutdown, receiver) = watch::channel(());
+        let servers = tokio::spawn(serve(listener, app, admin_listener, admin, receiver));
+
+        let client = reqwest::Client::new();
+        let status = |url: String| {
+            let request = client.get(url).send();
+            async move { request.await.expect("request").status().as_u16() }
+        };
+        assert_eq!(status(format!("{api}/health")).await, 200);
+        for path in ["/metrics", "/ready", "/internal/jobs", "/internal/routes"] {
+            assert_eq!(status(format!("{api}{path}")).await, 404, "{path}");
+            assert_eq!(status(format!("{ops}{path}")).await, 200, "{path}");
+        }
+        assert_eq!(status(format!("{ops}/users")).await, 404);
+
+        // The client keeps its connection to the API open
+        let scraped = client.get(format!("{ops}/metrics")).send().await.expect("scrape");
+        let body = scraped.text().await.expect("body");
+        assert!(body.lines().any(|line| line == "http_connections_active 1"), "{body}");
+
+        drop(client);
+        shutdown.send(()).expect("servers listening");
+        servers.await.expect("servers task").expect("servers");
+    }
+}
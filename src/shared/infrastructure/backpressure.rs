@@ -0,0 +1,50 @@
+//! Bound on the API requests handled at once
+//!
+//! Past the bound, requests are shed with `503 SERVICE_BUSY` rather than queued, so a
+//! traffic spike cannot pile up tasks and memory faster than they are served.
+
+use crate::shared::domain::DomainError;
+use crate::shared::infrastructure::http::ApiError;
+use axum::{error_handling::HandleErrorLayer, extract::Request, middleware, middleware::Next};
+use axum::{response::Response, BoxError, Router};
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::load_shed::{error::Overloaded, LoadShedLayer};
+use tower::ServiceBuilder;
+
+/// Gauge of the API requests being handled, shed ones excluded
+pub const HTTP_REQUESTS_IN_FLIGHT: &str = "http_requests_in_flight";
+
+/// `router` handling at most `max` requests at once, across all of its routes, and
+/// counting them in [`HTTP_REQUESTS_IN_FLIGHT`]; only the routes already added are
+/// limited
+pub fn limit_in_flight(router: Router, max: usize) -> Router {
+    router.layer(middleware::from_fn(count_in_flight)).layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(shed))
+            .layer(LoadShedLayer::new())
+            // Global: the router layers each route with its own clone
+            .layer(GlobalConcurrencyLimitLayer::new(max)),
+    )
+}
+
+async fn count_in_flight(request: Request, next: Next) -> Response {
+    /// Uncounts the request however its handling ends, cancellation included
+    struct InFlight;
+    impl Drop for InFlight {
+        fn drop(&mut self) {
+            metrics::gauge!(HTTP_REQUESTS_IN_FLIGHT).decrement(1.0);
+        }
+    }
+    metrics::gauge!(HTTP_REQUESTS_IN_FLIGHT).increment(1.0);
+    let _in_flight = InFlight;
+    next.run(request).await
+}
+
+/// `503 SERVICE_BUSY` for a request shed at the bound
+async fn shed(error: BoxError) -> ApiError {
+    if error.is::<Overloaded>() {
+        DomainError::Unavailable("too many requests in flight".to_owned()).into()
+    } else {
+        DomainError::Unexpected(error.to_string()).into()
+    }
+}
@@ -0,0 +1,179 @@
+//! `Cache-Control` directives of API responses
+//!
+//! Routes mark their successful GET responses as an [`entity`] or a [`list`] with
+//! `axum::middleware::map_response`, and the [`cache_control`] middleware turns the
+//! mark into the directive configured for that kind. Responses are `private`: they
+//! are scoped to a tenant, so shared caches must not serve them to other clients.
+//! Everything else, including errors and every response to a mutation, is `no-store`.
+
+use super::config::Config;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+
+/// Kind of GET response, marked on its route by [`entity`] or [`list`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheClass {
+    Entity,
+    List,
+}
+
+/// Seconds clients may reuse each kind of GET response; 0 forbids storing it
+#[derive(Debug, Clone, Copy)]
+pub struct CachePolicy {
+    /// Responses with a single entity, e.g. `GET /tasks/{id}`
+    pub entity_secs: u64,
+    /// Lists of entities, e.g. `GET /tasks`
+    pub list_secs: u64,
+}
+
+impl CachePolicy {
+    /// Policy of `CACHE_CONTROL_ENTITY_SECS` and `CACHE_CONTROL_LIST_SECS`
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            entity_secs: config.cache_control_entity_secs,
+            list_secs: config.cache_control_list_secs,
+        }
+    }
+
+    fn directive(self, class: CacheClass) -> HeaderValue {
+        let secs = match class {
+            CacheClass::Entity => self.entity_secs,
+            CacheClass::List => self.list_secs,
+        };
+        if secs == 0 {
+            return HeaderValue::from_static("no-store");
+        }
+        HeaderValue::try_from(format!("private, max-age={secs}"))
+            .unwrap_or(HeaderValue::from_static("no-store"))
+    }
+}
+
+/// Response mapping marking a route's responses as a single entity
+pub async fn entity(mut response: Response) -> Response {
+    response.extensions_mut().insert(CacheClass::Entity);
+    response
+}
+
+/// Response mapping marking a route's responses as a list of entities
+pub async fn list(mut response: Response) -> Response {
+    response.extensions_mut().insert(CacheClass::List);
+    response
+}
+
+/// Middleware setting `Cache-Control` on every response.
+///
+/// Successful and `304 Not Modified` responses to GET and HEAD get the directive of
+/// their mark, so revalidated responses keep the directives of the full ones; a
+/// directive the handler set itself is kept. Responses to other methods always get
+/// `no-store`.
+pub async fn cache_control(
+    State(policy): State<CachePolicy>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let safe = matches!(*request.method(), Method::GET | Method::HEAD);
+    let mut response = next.run(request).await;
+    let no_store = HeaderValue::from_static("no-store");
+    if !safe {
+        response.headers_mut().insert(header::CACHE_CONTROL, no_store);
+        return response;
+    }
+    let status = response.status();
+    let reusable = status.is_success() || status == axum::http::StatusCode::NOT_MODIFIED;
+    let directive = match response.extensions().get::<CacheClass>() {
+        Some(class) if reusable => policy.directive(*class),
+        _ => no_store,
+    };
+    response.headers_mut().entry(header::CACHE_CONTROL).or_insert(directive);
+    response
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use crate::shared::infrastructure::conditional::format_http_date;
+    use crate::shared::infrastructure::config::Config;
+    use crate::test_support::{in_memory_app_with, send};
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use axum::Router;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    async fn cache_control(app: &Router, request: Request<Body>) -> (StatusCode, String) {
+        let response = app.clone().oneshot(request).await.expect("infallible router");
+        let value = response.headers().get(header::CACHE_CONTROL).expect("Cache-Control");
+        (response.status(), value.to_str().expect("ASCII").to_owned())
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).expect("request")
+    }
+
+    /// App with a user and a task of it, and the task's URI
+    async fn seeded(config: &Config) -> (Router, String) {
+        let app = in_memory_app_with(config);
+        let user = json!({"name": "Alice", "email": "alice@example.com"});
+        let (_, user) = send(&app, Method::POST, "/users", Some(user)).await;
+        let task = json!({"userId": user["id"], "title": "Buy milk", "description": ""});
+        let (_, task) = send(&app, Method::POST, "/tasks", Some(task)).await;
+        let uri = format!("/tasks/{}", task["id"].as_str().expect("task id"));
+        (app, uri)
+    }
+
+    #[tokio::test]
+    async fn get_responses_should_carry_the_directive_of_their_route_class() {
+        let mut config = Config::default();
+        config.cache_control_entity_secs = 30;
+        config.cache_control_list_secs = 5;
+        let (app, task) = seeded(&config).await;
+
+        for uri in [task.as_str(), "/users/missing-user"] {
+            let expected = if uri == task { "private, max-age=30" } else { "no-store" };
+            assert_eq!(cache_control(&app, get(uri)).await.1, expected, "{uri}");
+        }
+        for uri in ["/tasks", "/users", "/tasks/export"] {
+            assert_eq!(cache_control(&app, get(uri)).await.1, "private, max-age=5", "{uri}");
+        }
+        assert_eq!(cache_control(&app, get("/health")).await.1, "no-store");
+        assert_eq!(cache_control(&app, get("/stats/tasks")).await.1, "max-age=60");
+
+        let revalidate = Request::get(&task)
+            .header(header::IF_MODIFIED_SINCE, format_http_date(&chrono::Utc::now()))
+            .body(Body::empty())
+            .expect("request");
+        let (status, directive) = cache_control(&app, revalidate).await;
+        assert_eq!((status, directive.as_str()), (StatusCode::NOT_MODIFIED, "private, max-age=30"));
+    }
+
+    #[tokio::test]
+    async fn lists_should_not_be_stored_by_default() {
+        let (app, task) = seeded(&Config::default()).await;
+        assert_eq!(cache_control(&app, get("/tasks")).await.1, "no-store");
+        assert_eq!(cache_control(&app, get(&task)).await.1, "no-store");
+    }
+
+    #[tokio::test]
+    async fn mutations_should_never_get_caching_directives() {
+        let mut config = Config::default();
+        config.cache_control_entity_secs = 30;
+        config.cache_control_list_secs = 5;
+        let (app, task) = seeded(&config).await;
+
+        let create = Request::post("/users")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"name": "Bob", "email": "bob@example.com"}"#))
+            .expect("request");
+        assert_eq!(cache_control(&app, create).await, (StatusCode::CREATED, "no-store".into()));
+        let complete = Request::patch(format!("{task}/complete")).body(Body::empty());
+        let (status, directive) = cache_control(&app, complete.expect("request")).await;
+        assert_eq!((status, directive.as_str()), (StatusCode::OK, "no-store"));
+        let delete = Request::delete(&task).body(Body::empty()).expect("request");
+        assert_eq!(cache_control(&app, delete).await.1, "no-store");
+    }
+}
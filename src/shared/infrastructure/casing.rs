@@ -0,0 +1,191 @@
+//! Casing of the field names of request and response bodies
+//!
+//! Bodies name their fields in camelCase (`userId`), which the DTOs declare with
+//! `#[serde(rename_all = "camelCase")]`. Earlier releases used `snake_case`
+//! (`user_id`); clients not migrated yet keep it for one more release, per request
+//! with `Accept-Profile: snake_case` or by default with `LEGACY_SNAKE_CASE`. Their
+//! bodies go through a [`Value`] whose keys are renamed, so the same DTOs serve both
+//! casings.
+//!
+//! Cutover: once clients have migrated, drop `LEGACY_SNAKE_CASE`, the `snake_case`
+//! profile and this module; the DTOs stay as they are.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io;
+
+/// Header a client selects the casing of its bodies with
+pub const ACCEPT_PROFILE: HeaderName = HeaderName::from_static("accept-profile");
+
+/// Casing of the field names of bodies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldCasing {
+    /// `userId`, the casing of the DTOs
+    #[default]
+    Camel,
+    /// `user_id`, the legacy casing
+    Snake,
+}
+
+tokio::task_local! {
+    static FIELD_CASING: FieldCasing;
+}
+
+impl FieldCasing {
+    /// Casing asked for by `Accept-Profile` (`snake_case` or `camelCase`), or `default`
+    #[must_use]
+    pub fn requested(headers: &HeaderMap, default: Self) -> Self {
+        match headers.get(ACCEPT_PROFILE).and_then(|value| value.to_str().ok()).map(str::trim) {
+            Some(profile) if profile.eq_ignore_ascii_case("snake_case") => Self::Snake,
+            Some(profile) if profile.eq_ignore_ascii_case("camelCase") => Self::Camel,
+            _ => default,
+        }
+    }
+
+    /// Casing of the bodies of the current request; camelCase outside
+    /// [`negotiate_casing`]
+    #[must_use]
+    pub fn current() -> Self {
+        FIELD_CASING.try_with(|casing| *casing).unwrap_or_default()
+    }
+
+    /// Write `value` to `writer` as JSON in this casing, for bodies streamed past
+    /// [`negotiate_casing`], which read [`FieldCasing::current`] beforehand
+    ///
+    /// # Errors
+    ///
+    /// When `value` fails to serialize or `writer` to write
+    pub fn write_json(
+        self,
+        writer: impl io::Write,
+        value: &impl Serialize,
+    ) -> serde_json::Result<()> {
+        match self {
+            Self::Camel => serde_json::to_writer(writer, value),
+            Self::Snake => {
+                serde_json::to_writer(writer, &snake_keys(serde_json::to_value(value)?))
+            }
+        }
+    }
+}
+
+/// Middleware reading and writing the bodies of the request, errors included, in the
+/// casing it asks for, `default` unless `Accept-Profile` says otherwise
+pub async fn negotiate_casing(
+    State(default): State<FieldCasing>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let casing = FieldCasing::requested(request.headers(), default);
+    let mut response = FIELD_CASING.scope(casing, next.run(request)).await;
+    response.headers_mut().append(header::VARY, HeaderValue::from_static("accept-profile"));
+    response
+}
+
+/// `value` with the keys of its objects, nested ones included, in `snake_case`
+#[must_use]
+pub fn snake_keys(value: Value) -> Value {
+    rename_keys(value, &snake_case)
+}
+
+/// `value` with the keys of its objects, nested ones included, in camelCase
+#[must_use]
+pub fn camel_keys(value: Value) -> Value {
+    rename_keys(value, &camel_case)
+}
+
+fn rename_keys(value: Value, rename: &dyn Fn(&str) -> String) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (rename(&key), rename_keys(value, rename)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(|item| rename_keys(item, rename)).collect())
+        }
+        other => other,
+    }
+}
+
+/// `userId` as `user_id`
+#[must_use]
+pub fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// `user_id` as `userId`
+#[must_use]
+pub fn camel_case(name: &str) -> String {
+    let mut camel = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        match c {
+            '_' if !camel.is_empty() => upper = true,
+            c if upper => {
+                camel.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            c => camel.push(c),
+        }
+    }
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn names_should_convert_both_ways() {
+        let names = [("user_id", "userId"), ("id", "id"), ("upload_expires_at", "uploadExpiresAt")];
+        for (snake, camel) in names {
+            assert_eq!(camel_case(snake), camel);
+            assert_eq!(snake_case(camel), snake);
+        }
+        assert_eq!(camel_case("userId"), "userId");
+    }
+
+    #[test]
+    fn keys_should_be_renamed_in_nested_objects_but_not_values() {
+        let camel = json!({
+            "userId": "task_id",
+            "lastDelivery": {"statusCode": 200},
+            "recentTasks": [{"updatedAt": null}],
+        });
+        let snake = json!({
+            "user_id": "task_id",
+            "last_delivery": {"status_code": 200},
+            "recent_tasks": [{"updated_at": null}],
+        });
+        assert_eq!(snake_keys(camel.clone()), snake);
+        assert_eq!(camel_keys(snake), camel);
+    }
+
+    #[test]
+    fn accept_profile_should_override_the_default() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(FieldCasing::requested(&headers, FieldCasing::Snake), FieldCasing::Snake);
+        headers.insert(ACCEPT_PROFILE, HeaderValue::from_static("snake_case"));
+        assert_eq!(FieldCasing::requested(&headers, FieldCasing::Camel), FieldCasing::Snake);
+        headers.insert(ACCEPT_PROFILE, HeaderValue::from_static("camelCase"));
+        assert_eq!(FieldCasing::requested(&headers, FieldCasing::Snake), FieldCasing::Camel);
+    }
+}
@@ -0,0 +1,279 @@
+//! Address of the client making a request, seen through trusted proxies
+//!
+//! Behind a load balancer the connection comes from the balancer, so the client is
+//! only known from the `Forwarded` (RFC 7239) or `X-Forwarded-For` header it sends.
+//! Those headers are believed only from peers within `TRUSTED_PROXIES`: the hops are
+//! walked from the nearest one, skipping trusted proxies, and the first address that
+//! is not one is the client. A client behind an untrusted peer is that peer, so
+//! addresses anyone can put in the headers never count.
+//!
+//! [`capture_client_ip`] stores the result as [`ClientIp`] in the request extensions,
+//! where the rate limiter and handlers writing the audit trail read it, and records it
+//! on the request span as `client.ip`.
+
+use crate::shared::infrastructure::admin::PeerAddr;
+use crate::shared::infrastructure::http::ApiError;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, HeaderName, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use ipnet::IpNet;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+/// De facto standard header listing the addresses a request was forwarded for
+pub const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// Networks of the proxies whose forwarding headers are believed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    /// Proxies within the networks of `items`, each a CIDR (`10.0.0.0/8`, `fd00::/8`)
+    /// or a single address
+    ///
+    /// # Errors
+    /// Fails for an item that is neither, or a CIDR with bits set past its prefix.
+    pub fn parse(items: &[String]) -> anyhow::Result<Self> {
+        items
+            .iter()
+            .map(|item| {
+                let net = match item.parse::<IpAddr>() {
+                    Ok(ip) => IpNet::from(ip),
+                    Err(_) => item.parse::<IpNet>().map_err(|_| {
+                        anyhow::anyhow!(
+                            "Invalid TRUSTED_PROXIES item {item:?}: expected a CIDR such as \
+                             10.0.0.0/8 or an IP address"
+                        )
+                    })?,
+                };
+                anyhow::ensure!(
+                    net.trunc() == net,
+                    "Invalid TRUSTED_PROXIES item {item:?}: bits are set past the prefix; \
+                     did you mean {}?",
+                    net.trunc()
+                );
+                Ok(net)
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Self)
+    }
+
+    /// Whether `ip` is a trusted proxy; IPv4-mapped IPv6 addresses count as IPv4
+    #[must_use]
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.0.iter().any(|net| net.contains(&ip))
+    }
+
+    /// The client of a request from `peer` with `headers`: the nearest forwarded hop
+    /// that is not a trusted proxy, or `peer` itself when it is not one
+    ///
+    /// Walking stops at a hop that is not an address (`unknown`, an obfuscated
+    /// identifier), leaving the proxy that forwarded it as the client.
+    #[must_use]
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let mut client = peer.to_canonical();
+        if !self.contains(client) {
+            return client;
+        }
+        for hop in forwarded_hops(headers).into_iter().rev() {
+            let Some(hop) = hop else {
+                break;
+            };
+            client = hop.to_canonical();
+            if !self.contains(client) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Addresses a request was forwarded for, farthest first: the `for=` parameters of
+/// `Forwarded` when it is sent, else `X-Forwarded-For`; `None` for a hop that is not an
+/// address
+fn forwarded_hops(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let (name, hop_ip): (_, fn(&str) -> Option<IpAddr>) =
+        if headers.contains_key(header::FORWARDED) {
+            (header::FORWARDED, forwarded_for)
+        } else {
+            (X_FORWARDED_FOR, node_ip)
+        };
+    headers
+        .get_all(name)
+        .iter()
+        .flat_map(|value| match value.to_str() {
+            Ok(value) => value.split(',').map(hop_ip).collect(),
+            Err(_) => vec![None],
+        })
+        .collect()
+}
+
+/// Address of the `for=` parameter of an element of `Forwarded`
+fn forwarded_for(element: &str) -> Option<IpAddr> {
+    element.split(';').find_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        name.trim().eq_ignore_ascii_case("for").then(|| node_ip(value)).flatten()
+    })
+}
+
+/// Address of a node (`192.0.2.1`, `"192.0.2.1:4711"`, `"[2001:db8::1]:4711"`),
+/// without its port
+fn node_ip(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Some(bracketed) = node.strip_prefix('[') {
+        return bracketed.split_once(']')?.0.parse().ok();
+    }
+    node.parse().ok().or_else(|| node.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+/// IP address of the client making the request, as resolved by [`capture_client_ip`]
+///
+/// Handlers that can do without it take `Option<ClientIp>`; requiring it where the
+/// middleware did not run, or the connection address is unknown, is a 500.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Infallible> {
+        Ok(parts.extensions.get::<Self>().copied())
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, ApiError> {
+        parts.extensions.get::<Self>().copied().ok_or_else(|| {
+            let message = "Client IP address is unavailable";
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", message)
+        })
+    }
+}
+
+/// Middleware storing the [`ClientIp`] of requests whose connection address is known
+/// ([`PeerAddr`], or `ConnectInfo<SocketAddr>` when the router is served by other
+/// means) and recording it on the request span
+pub async fn capture_client_ip(
+    State(proxies): State<Arc<TrustedProxies>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let extensions = request.extensions();
+    let peer = match extensions.get::<ConnectInfo<PeerAddr>>() {
+        Some(ConnectInfo(PeerAddr(peer))) => Some(peer.ip()),
+        None => extensions.get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip()),
+    };
+    if let Some(peer) = peer {
+        let ip = proxies.client_ip(peer, request.headers());
+        tracing::Span::current().record("client.ip", tracing::field::display(ip));
+        request.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn proxies(items: &[&str]) -> TrustedProxies {
+        let items: Vec<String> = items.iter().map(|&item| item.to_owned()).collect();
+        TrustedProxies::parse(&items).expect("valid proxies")
+    }
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().expect("valid IP")
+    }
+
+    fn headers(pairs: &[(&HeaderName, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for &(name, value) in pairs {
+            headers.append(name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn parse_should_accept_cidrs_and_addresses_only() {
+        let parsed = proxies(&["10.0.0.0/8", "192.0.2.7", "fd00::/8", "::1"]);
+        assert_eq!(parsed.0.len(), 4);
+        for invalid in ["10.0.0.0/33", "10.0.0.1/8", "fd00::1/8", "lb.internal", "10.0.0.0/"] {
+            let result = TrustedProxies::parse(&[invalid.to_owned()]);
+            assert!(result.is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn contains_should_match_addresses_within_the_networks() {
+        let trusted = proxies(&["10.0.0.0/8", "192.0.2.7", "2001:db8:1::/48"]);
+        for inside in ["10.0.0.1", "10.255.255.255", "192.0.2.7", "2001:db8:1::42"] {
+            assert!(trusted.contains(ip(inside)), "{inside}");
+        }
+        for outside in ["11.0.0.1", "192.0.2.8", "2001:db8:2::42", "::1"] {
+            assert!(!trusted.contains(ip(outside)), "{outside}");
+        }
+        // Dual-stack listeners see IPv4 peers as IPv4-mapped IPv6 addresses
+        assert!(trusted.contains(ip("::ffff:10.1.2.3")));
+    }
+
+    #[test]
+    fn spoofed_headers_from_untrusted_peers_should_be_ignored() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let spoofed =
+            headers(&[(&X_FORWARDED_FOR, "10.0.0.9"), (&header::FORWARDED, "for=1.1.1.1")]);
+        assert_eq!(trusted.client_ip(ip("203.0.113.5"), &spoofed), ip("203.0.113.5"));
+        assert_eq!(proxies(&[]).client_ip(ip("10.0.0.1"), &spoofed), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn client_ip_should_skip_trusted_hops_from_the_nearest() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let peer = ip("10.0.0.1");
+        // The client claims to be 1.1.1.1; the edge proxy saw 203.0.113.5
+        let chained = headers(&[(&X_FORWARDED_FOR, "1.1.1.1, 203.0.113.5, 10.0.0.2")]);
+        assert_eq!(trusted.client_ip(peer, &chained), ip("203.0.113.5"));
+        // Several header lines form a single list
+        let split = headers(&[
+            (&X_FORWARDED_FOR, "1.1.1.1"),
+            (&X_FORWARDED_FOR, "203.0.113.5,10.0.0.2"),
+        ]);
+        assert_eq!(trusted.client_ip(peer, &split), ip("203.0.113.5"));
+        // Only proxies: the farthest one is the client
+        let internal = headers(&[(&X_FORWARDED_FOR, "10.0.0.3, 10.0.0.2")]);
+        assert_eq!(trusted.client_ip(peer, &internal), ip("10.0.0.3"));
+        // A hop that is not an address stops the walk at the proxy forwarding it
+        let garbled = headers(&[(&X_FORWARDED_FOR, "203.0.113.5, garbage, 10.0.0.2")]);
+        assert_eq!(trusted.client_ip(peer, &garbled), ip("10.0.0.2"));
+        assert_eq!(trusted.client_ip(peer, &HeaderMap::new()), peer);
+    }
+
+    #[test]
+    fn forwarded_should_take_precedence_and_handle_ipv6() {
+        let trusted = proxies(&["fd00::/8", "10.0.0.0/8"]);
+        let forwarded = headers(&[
+            (&X_FORWARDED_FOR, "198.51.100.1"),
+            (
+                &header::FORWARDED,
+                r#"for="[2001:db8:cafe::17]:4711";proto=https, For="10.0.0.2:80";by=fd00::1"#,
+            ),
+        ]);
+        assert_eq!(trusted.client_ip(ip("fd00::2"), &forwarded), ip("2001:db8:cafe::17"));
+        let xff = headers(&[(&X_FORWARDED_FOR, "2001:db8::5, fd00::3")]);
+        assert_eq!(trusted.client_ip(ip("fd00::2"), &xff), ip("2001:db8::5"));
+        let mapped = headers(&[(&X_FORWARDED_FOR, "::ffff:203.0.113.5")]);
+        assert_eq!(trusted.client_ip(ip("::ffff:10.0.0.1"), &mapped), ip("203.0.113.5"));
+        let unknown = headers(&[(&header::FORWARDED, "for=unknown, for=10.0.0.2")]);
+        assert_eq!(trusted.client_ip(ip("10.0.0.1"), &unknown), ip("10.0.0.2"));
+    }
+}
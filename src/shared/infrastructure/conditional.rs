@@ -0,0 +1,155 @@
+//! Conditional GET support based on modification time (`Last-Modified` / `If-Modified-Since`)
+//!
+//! Preconditions follow RFC 7232: `If-Modified-Since` is ignored when the request also
+//! carries `If-None-Match` (entity tags take precedence), when its value is not a valid
+//! HTTP-date, or when it lies in the future of the server clock. HTTP-dates have
+//! one-second granularity, so modification times are compared truncated to the second.
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDateTime, SubsecRound, Utc};
+
+/// IMF-fixdate, the preferred HTTP-date format (`Sun, 06 Nov 1994 08:49:37 GMT`)
+const IMF_FIXDATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+/// Obsolete RFC 850 format (`Sunday, 06-Nov-94 08:49:37 GMT`)
+const RFC_850: &str = "%A, %d-%b-%y %H:%M:%S GMT";
+/// Obsolete asctime format (`Sun Nov  6 08:49:37 1994`)
+const ASCTIME: &str = "%a %b %e %H:%M:%S %Y";
+
+/// Format `at` as an IMF-fixdate HTTP-date
+#[must_use]
+pub fn format_http_date(at: &DateTime<Utc>) -> String {
+    at.format(IMF_FIXDATE).to_string()
+}
+
+/// Parse an HTTP-date in any of the three formats recipients must accept
+#[must_use]
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    [IMF_FIXDATE, RFC_850, ASCTIME]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim(), format).ok())
+        .map(|naive| naive.and_utc())
+}
+
+/// Whether the request's preconditions allow answering `304 Not Modified` for a
+/// resource last modified at `last_modified`, as observed at `now`.
+#[must_use]
+pub fn is_not_modified(
+    headers: &HeaderMap,
+    last_modified: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> bool {
+    if headers.contains_key(header::IF_NONE_MATCH) {
+        return false;
+    }
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date);
+    match since {
+        Some(since) if since <= now => last_modified.trunc_subsecs(0) <= since,
+        _ => false,
+    }
+}
+
+/// Respond with `body` and a `Last-Modified` header, or with `304 Not Modified`
+/// when the request's `If-Modified-Since` precondition says the client is current.
+pub fn respond(
+    headers: &HeaderMap,
+    last_modified: DateTime<Utc>,
+    body: impl IntoResponse,
+) -> Response {
+    let mut response = if is_not_modified(headers, last_modified, Utc::now()) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        body.into_response()
+    };
+    if let Ok(value) = HeaderValue::from_str(&format_http_date(&last_modified)) {
+        response.headers_mut().insert(header::LAST_MODIFIED, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(secs: i64, millis: u32) -> DateTime<Utc> {
+        Utc.timestamp_opt(784_111_777 + secs, millis * 1_000_000).single().unwrap_or_default()
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .filter_map(|(name, value)| Some((name.clone(), HeaderValue::from_str(value).ok()?)))
+            .collect()
+    }
+
+    fn since(value: &str) -> HeaderMap {
+        headers(&[(header::IF_MODIFIED_SINCE, value)])
+    }
+
+    const BASE: &str = "Sun, 06 Nov 1994 08:49:37 GMT";
+
+    #[test]
+    fn http_dates_should_round_trip_and_accept_obsolete_formats() {
+        assert_eq!(format_http_date(&at(0, 0)), BASE);
+        assert_eq!(parse_http_date(BASE), Some(at(0, 0)));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), Some(at(0, 0)));
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), Some(at(0, 0)));
+        assert_eq!(parse_http_date("2026-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn equal_timestamp_should_be_not_modified_despite_subsecond_precision() {
+        let now = at(60, 0);
+        assert!(is_not_modified(&since(BASE), at(0, 0), now));
+        assert!(is_not_modified(&since(BASE), at(0, 999), now));
+    }
+
+    #[test]
+    fn later_modification_should_be_modified() {
+        assert!(!is_not_modified(&since(BASE), at(1, 0), at(60, 0)));
+    }
+
+    #[test]
+    fn older_modification_should_be_not_modified() {
+        assert!(is_not_modified(&since(BASE), at(-3600, 0), at(60, 0)));
+    }
+
+    #[test]
+    fn if_modified_since_in_the_future_should_be_ignored() {
+        // Client clock ahead of the server: the date is invalid, not a free pass
+        assert!(!is_not_modified(&since(BASE), at(-3600, 0), at(-60, 0)));
+    }
+
+    #[test]
+    fn missing_or_invalid_header_or_date_should_be_modified() {
+        let now = at(60, 0);
+        assert!(!is_not_modified(&HeaderMap::new(), at(0, 0), now));
+        assert!(!is_not_modified(&since("yesterday"), at(0, 0), now));
+    }
+
+    #[test]
+    fn if_none_match_should_take_precedence() {
+        let both = headers(&[(header::IF_MODIFIED_SINCE, BASE), (header::IF_NONE_MATCH, "\"x\"")]);
+        assert!(!is_not_modified(&both, at(0, 0), at(60, 0)));
+    }
+
+    #[test]
+    fn respond_should_set_last_modified_on_both_outcomes() {
+        let response = respond(&since(BASE), at(0, 0), "body");
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response.headers().get(header::LAST_MODIFIED).map(HeaderValue::as_bytes),
+            Some(BASE.as_bytes())
+        );
+
+        let response = respond(&HeaderMap::new(), at(0, 0), "body");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+    }
+}
@@ -1,11 +1,29 @@
 //! Database connection and pool management
 
+use crate::shared::application::within_deadline;
+use crate::shared::domain::DomainError;
 use crate::shared::infrastructure::config::Config;
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use crate::shared::infrastructure::jobs::{BackgroundJob, JobContext};
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::pool::PoolConnection;
+use sha2::{Digest, Sha256};
+use sqlx::{postgres::PgPoolOptions, Connection, Executor, PgPool, Postgres};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long a released connection may take to answer the pool's liveness ping.
+///
+/// A connection whose query was abandoned at a deadline is still busy on the server
+/// and cannot answer; it is closed instead of holding a pool slot until the query ends.
+const RELEASE_PING_TIMEOUT: Duration = Duration::from_millis(500);
 
 /// Create database connection pool with configurable settings
+///
+/// # Errors
+/// Fails when the database cannot be reached within the acquire timeout.
 pub async fn create_pool(config: &Config) -> Result<PgPool, anyhow::Error> {
-    Ok(PgPoolOptions::new()
+    Ok(with_schema(with_release_check(PgPoolOptions::new()), &config.db_schema)
         .max_connections(config.db_max_connections)
         .min_connections(config.db_min_connections)
         .acquire_timeout(config.db_acquire_timeout())
@@ -14,37 +32,758 @@ pub async fn create_pool(config: &Config) -> Result<PgPool, anyhow::Error> {
         .await?)
 }
 
-/// Run pending migrations from the `migrations/` directory.
+/// Close connections on release when they do not answer a ping within [`RELEASE_PING_TIMEOUT`].
+fn with_release_check(options: PgPoolOptions) -> PgPoolOptions {
+    options.after_release(|conn, _| {
+        Box::pin(async move {
+            match tokio::time::timeout(RELEASE_PING_TIMEOUT, conn.ping()).await {
+                Ok(ping) => ping.map(|()| true),
+                Err(_) => Ok(false),
+            }
+        })
+    })
+}
+
+/// Set `search_path` to `schema` on every new connection.
+///
+/// Queries use unqualified table names throughout and rely on this to resolve
+/// them in the configured schema (`DB_SCHEMA`).
+pub(crate) fn with_schema(options: PgPoolOptions, schema: &str) -> PgPoolOptions {
+    let statement = format!("SET search_path TO {}", quote_identifier(schema));
+    options.after_connect(move |conn, _| {
+        let statement = statement.clone();
+        Box::pin(async move { conn.execute(statement.as_str()).await.map(|_| ()) })
+    })
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Migrations embedded from the `migrations/` directory
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Repeatable migrations embedded from `migrations/repeatable/` as `(file name, SQL)`,
+/// in file name order
+static REPEATABLE_MIGRATIONS: &[(&str, &str)] =
+    include!(concat!(env!("OUT_DIR"), "/repeatable_migrations.rs"));
+
+/// How [`run_migrations`] retries after lock contention with another instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationRetry {
+    /// Retries after the first attempt
+    pub retries: u32,
+    /// Wait before each retry
+    pub delay: Duration,
+}
+
+impl MigrationRetry {
+    /// Retries configured by `MIGRATION_LOCK_RETRIES` and `MIGRATION_RETRY_DELAY_MS`
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        Self { retries: config.migration_lock_retries, delay: config.migration_retry_delay() }
+    }
+}
+
+/// Create `schema` if missing and run pending migrations from the `migrations/`
+/// directory into it, then the repeatable migrations whose SQL changed (see
+/// [`run_repeatable_migrations`]).
 ///
 /// Uses sqlx's built-in migration runner which tracks applied migrations
-/// in a `_sqlx_migrations` table and verifies checksums.
-pub async fn run_migrations(pool: &PgPool) -> Result<(), anyhow::Error> {
-    sqlx::migrate!("./migrations").run(pool).await?;
+/// in a `_sqlx_migrations` table and verifies checksums. The pool must come from
+/// [`create_pool`] (or otherwise have its `search_path` set to `schema`), so the
+/// tables and the tracking table land in `schema`.
+///
+/// Instances deploying together serialize on the migrator's advisory lock. An
+/// attempt that fails on lock contention instead (`lock_timeout`, a deadlock, or
+/// racing `CREATE ... IF NOT EXISTS`) succeeds once another instance has applied
+/// every migration, and is otherwise retried as configured by `retry`.
+///
+/// # Errors
+/// Fails when the schema cannot be created, a migration fails, an applied
+/// migration's checksum changed, or contention outlasts the retries.
+pub async fn run_migrations(
+    pool: &PgPool,
+    schema: &str,
+    retry: MigrationRetry,
+) -> Result<(), anyhow::Error> {
+    let mut retries_left = retry.retries;
+    loop {
+        let Err(e) = migrate_once(pool, schema).await else { break };
+        if !is_lock_contention(&e) {
+            return Err(e.into());
+        }
+        if verify_versioned_migrations(pool).await.is_ok() {
+            tracing::info!("Migrations were applied by another instance ({e})");
+            break;
+        }
+        if retries_left == 0 {
+            return Err(anyhow::Error::new(e).context("Migrations still locked after retries"));
+        }
+        retries_left -= 1;
+        tracing::warn!("Migrations locked by another instance, retrying in {:?}: {e}", retry.delay);
+        tokio::time::sleep(retry.delay).await;
+    }
+    run_repeatable_migrations(pool, retry).await
+}
+
+async fn migrate_once(pool: &PgPool, schema: &str) -> Result<(), MigrateError> {
+    // Checked first: CREATE SCHEMA IF NOT EXISTS needs the CREATE privilege even
+    // when the schema already exists
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_namespace WHERE nspname = $1)")
+            .bind(schema)
+            .fetch_one(pool)
+            .await?;
+    if !exists {
+        let statement = format!("CREATE SCHEMA IF NOT EXISTS {}", quote_identifier(schema));
+        sqlx::query(&statement).execute(pool).await?;
+    }
+    let mut conn = pool.acquire().await?;
+    // `run_direct` rather than `run`, which would make this future not `Send`
+    let result = MIGRATOR.run_direct(&mut *conn).await;
+    if result.is_err() {
+        // The session may still hold the advisory lock; never return it to the pool
+        conn.close().await.ok();
+    }
+    result
+}
+
+/// Whether `e` comes from waiting on, or racing, another migrating instance
+fn is_lock_contention(e: &MigrateError) -> bool {
+    let (MigrateError::Execute(e) | MigrateError::ExecuteMigration(e, _)) = e else {
+        return false;
+    };
+    is_contention(e)
+}
+
+/// Whether the database failed `e` for contention rather than for its SQL
+fn is_contention(e: &sqlx::Error) -> bool {
+    let sqlx::Error::Database(db) = e else { return false };
+    match db.code().as_deref() {
+        // lock_not_available, deadlock_detected, serialization_failure
+        Some("55P03" | "40P01" | "40001") => true,
+        // Concurrent CREATE SCHEMA/TABLE IF NOT EXISTS colliding in the catalog
+        Some("23505") => db.constraint().is_some_and(|name| name.starts_with("pg_")),
+        _ => false,
+    }
+}
+
+/// Apply the repeatable migrations of `migrations/repeatable/`, in file name order,
+/// that were never applied or whose SQL changed since.
+///
+/// Each file is hashed and run in its own transaction, which also records its
+/// checksum in the `app_repeatable_migrations` table. Files must therefore be safe
+/// to re-run, e.g. `DROP VIEW IF EXISTS` before `CREATE VIEW`. Instances serialize
+/// on an advisory lock; waits failing on `lock_timeout` are retried as configured
+/// by `retry`. [`run_migrations`] calls this after the versioned migrations.
+///
+/// # Errors
+/// Fails, naming the file, when its SQL fails or contention outlasts the retries.
+pub async fn run_repeatable_migrations(
+    pool: &PgPool,
+    retry: MigrationRetry,
+) -> Result<(), anyhow::Error> {
+    apply_repeatable_migrations(pool, REPEATABLE_MIGRATIONS, retry).await
+}
+
+async fn apply_repeatable_migrations(
+    pool: &PgPool,
+    migrations: &[(&str, &str)],
+    retry: MigrationRetry,
+) -> Result<(), anyhow::Error> {
+    let mut migrations = migrations.to_vec();
+    migrations.sort_unstable_by_key(|(name, _)| *name);
+    for (name, sql) in migrations {
+        let mut retries_left = retry.retries;
+        loop {
+            match apply_repeatable(pool, name, sql).await {
+                Ok(true) => tracing::info!("Applied repeatable migration {name}"),
+                Ok(false) => {}
+                Err(e) if is_contention(&e) && retries_left > 0 => {
+                    retries_left -= 1;
+                    tracing::warn!("Repeatable migration {name} locked, retrying: {e}");
+                    tokio::time::sleep(retry.delay).await;
+                    continue;
+                }
+                Err(e) => {
+                    return Err(anyhow::Error::new(e)
+                        .context(format!("Repeatable migration {name} failed")));
+                }
+            }
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Run `sql` unless it was applied as `name` with the same checksum; whether it ran
+async fn apply_repeatable(pool: &PgPool, name: &str, sql: &str) -> Result<bool, sqlx::Error> {
+    let checksum = Sha256::digest(sql.as_bytes()).to_vec();
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('app_repeatable_migrations'))")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS app_repeatable_migrations ( \
+             name TEXT PRIMARY KEY, \
+             checksum BYTEA NOT NULL, \
+             applied_at TIMESTAMPTZ NOT NULL DEFAULT now())",
+    )
+    .execute(&mut *tx)
+    .await?;
+    let applied: Option<Vec<u8>> =
+        sqlx::query_scalar("SELECT checksum FROM app_repeatable_migrations WHERE name = $1")
+            .bind(name)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if applied.as_deref() == Some(checksum.as_slice()) {
+        return Ok(false);
+    }
+    tx.execute(sqlx::raw_sql(sql)).await?;
+    sqlx::query(
+        "INSERT INTO app_repeatable_migrations (name, checksum) VALUES ($1, $2) \
+         ON CONFLICT (name) DO UPDATE SET checksum = $2, applied_at = now()",
+    )
+    .bind(name)
+    .bind(&checksum)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Check that every migration is applied, unchanged, to the schema of `pool`, and
+/// every repeatable migration in its current version.
+///
+/// Guards startup with `SKIP_MIGRATIONS`, where a separate job runs the migrations.
+///
+/// # Errors
+/// Fails when a migration is pending, partially applied or changed since it was
+/// applied, a repeatable migration is not applied in its current version, or the
+/// database cannot be queried.
+pub async fn verify_migrations(pool: &PgPool) -> Result<(), anyhow::Error> {
+    verify_versioned_migrations(pool).await?;
+    let tracked: Option<String> =
+        sqlx::query_scalar("SELECT to_regclass('app_repeatable_migrations')::text")
+            .fetch_one(pool)
+            .await?;
+    let applied: Vec<(String, Vec<u8>)> = match tracked {
+        Some(_) => {
+            sqlx::query_as("SELECT name, checksum FROM app_repeatable_migrations")
+                .fetch_all(pool)
+                .await?
+        }
+        None => Vec::new(),
+    };
+    let stale: Vec<&str> = REPEATABLE_MIGRATIONS
+        .iter()
+        .filter(|(name, sql)| {
+            let checksum = Sha256::digest(sql.as_bytes());
+            !applied.iter().any(|(n, c)| n == name && c.as_slice() == checksum.as_slice())
+        })
+        .map(|(name, _)| *name)
+        .collect();
+    if !stale.is_empty() {
+        anyhow::bail!("Repeatable migrations {stale:?} are not applied in their current version");
+    }
     Ok(())
 }
 
+/// Check the versioned migrations only, see [`verify_migrations`]
+async fn verify_versioned_migrations(pool: &PgPool) -> Result<(), anyhow::Error> {
+    let tracked: Option<String> = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations')::text")
+        .fetch_one(pool)
+        .await?;
+    if tracked.is_none() {
+        anyhow::bail!("Database is not migrated: no _sqlx_migrations table in the schema");
+    }
+    let applied: Vec<(i64, Vec<u8>, bool)> =
+        sqlx::query_as("SELECT version, checksum, success FROM _sqlx_migrations")
+            .fetch_all(pool)
+            .await?;
+    let mut pending = Vec::new();
+    for migration in MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration()) {
+        match applied.iter().find(|(version, _, _)| *version == migration.version) {
+            None => pending.push(migration.version),
+            Some((version, _, false)) => anyhow::bail!("Migration {version} is partially applied"),
+            Some((version, checksum, _)) if *checksum != *migration.checksum => {
+                anyhow::bail!("Migration {version} was modified after it was applied");
+            }
+            Some(_) => {}
+        }
+    }
+    if !pending.is_empty() {
+        anyhow::bail!("Database is missing migrations {pending:?}; run them before starting");
+    }
+    Ok(())
+}
+
+/// Check that the partial unique index backing `PREVENT_DUPLICATE_OPEN_TASKS` exists
+/// exactly when the policy is `enabled`.
+///
+/// The index only exists while the policy is enabled, so it is not a versioned
+/// migration: operators apply `migrations/optional/open_task_title_index.up.sql`
+/// before enabling the policy and its `.down.sql` after disabling it.
+///
+/// # Errors
+/// Fails when the query fails or the index does not match the policy.
+pub async fn verify_open_task_title_index(
+    pool: &PgPool,
+    enabled: bool,
+) -> Result<(), anyhow::Error> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT to_regclass('idx_tasks_open_title_unique') IS NOT NULL",
+    )
+    .fetch_one(pool)
+    .await?;
+    match (enabled, exists) {
+        (true, false) => anyhow::bail!(
+            "PREVENT_DUPLICATE_OPEN_TASKS needs idx_tasks_open_title_unique; apply \
+             migrations/optional/open_task_title_index.up.sql first"
+        ),
+        (false, true) => anyhow::bail!(
+            "idx_tasks_open_title_unique rejects duplicate open tasks while \
+             PREVENT_DUPLICATE_OPEN_TASKS is off; apply \
+             migrations/optional/open_task_title_index.down.sql or enable it"
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Await a query within the current request deadline, mapping failures with [`map_db_error`].
+///
+/// Repositories wrap every query in this, run on a connection from [`acquire`], e.g.
+/// `run_query(query.fetch_one(&mut *conn), "insert", "task").await?`.
+/// When the deadline passes the query future is dropped; its connection is closed
+/// on release rather than returned to the pool while the query is still running.
+///
+/// # Errors
+/// `DomainError::Infrastructure("deadline exceeded")` when the deadline passes first,
+/// otherwise the mapped database error.
+pub async fn run_query<T>(
+    query: impl Future<Output = Result<T, sqlx::Error>>,
+    operation: &str,
+    entity: &str,
+) -> Result<T, DomainError> {
+    let Ok(result) = within_deadline(query).await else {
+        tracing::warn!("Deadline exceeded in {operation} {entity}");
+        return Err(DomainError::Infrastructure("deadline exceeded".into()));
+    };
+    result.map_err(|e| map_db_error(e, operation, entity))
+}
+
+/// Histogram of seconds spent waiting for a pool connection, failed waits included
+pub const POOL_ACQUIRE_WAIT_SECONDS: &str = "db_pool_acquire_wait_seconds";
+
+/// Check a connection out of `pool` for a query, recording the wait in
+/// [`POOL_ACQUIRE_WAIT_SECONDS`]
+///
+/// sqlx does not report how long a query waited for its connection, so repositories
+/// acquire through here instead of passing the pool to the query. The wait counts
+/// against the request deadline like the query itself.
+///
+/// # Errors
+/// As [`run_query`]; an exhausted pool maps to `DomainError::Unavailable`.
+pub async fn acquire(
+    pool: &PgPool,
+    operation: &str,
+    entity: &str,
+) -> Result<PoolConnection<Postgres>, DomainError> {
+    let started = Instant::now();
+    let result = run_query(pool.acquire(), operation, entity).await;
+    metrics::histogram!(POOL_ACQUIRE_WAIT_SECONDS).record(started.elapsed().as_secs_f64());
+    result
+}
+
+/// Gauge of open pool connections, idle or in use
+pub const POOL_CONNECTIONS: &str = "db_pool_connections";
+
+/// Gauge of idle pool connections
+pub const POOL_IDLE_CONNECTIONS: &str = "db_pool_idle_connections";
+
+/// Samples the pool's size into [`POOL_CONNECTIONS`] and [`POOL_IDLE_CONNECTIONS`]
+pub struct PoolStatsJob {
+    pool: PgPool,
+}
+
+impl PoolStatsJob {
+    /// Sample `pool`
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl BackgroundJob for PoolStatsJob {
+    fn name(&self) -> &'static str {
+        "pool_stats"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    async fn run(&self, _ctx: &JobContext) -> anyhow::Result<()> {
+        let idle = u32::try_from(self.pool.num_idle()).unwrap_or(u32::MAX);
+        metrics::gauge!(POOL_CONNECTIONS).set(f64::from(self.pool.size()));
+        metrics::gauge!(POOL_IDLE_CONNECTIONS).set(f64::from(idle));
+        Ok(())
+    }
+}
+
+/// Number of queries that failed because no pool connection became available
+static POOL_EXHAUSTED: AtomicU64 = AtomicU64::new(0);
+
+/// Total count of pool exhaustion (`PoolTimedOut` / `PoolClosed`) errors since startup
+#[must_use]
+pub fn pool_exhausted_total() -> u64 {
+    POOL_EXHAUSTED.load(Ordering::Relaxed)
+}
+
+/// Meaning of a violated constraint, worded by the [`DomainError`] catalog
+#[derive(Debug, Clone, Copy)]
+enum Violation {
+    /// A unique constraint: an entity with the same field value exists
+    Taken { entity: &'static str, field: &'static str },
+    /// A foreign key: the *referenced* entity is missing, not the one being written
+    Missing { entity: &'static str },
+}
+
+/// Meaning of each named constraint, keyed by `PostgreSQL` constraint name
+const CONSTRAINTS: &[(&str, Violation)] = &[
+    ("users_pkey", Violation::Taken { entity: "User", field: "ID" }),
+    ("users_tenant_email_key", Violation::Taken { entity: "User", field: "email" }),
+    ("tasks_pkey", Violation::Taken { entity: "Task", field: "ID" }),
+    ("tasks_tenant_user_fkey", Violation::Missing { entity: "User" }),
+    ("idx_tasks_open_title_unique", Violation::Taken { entity: "Open task", field: "title" }),
+    ("task_attachments_pkey", Violation::Taken { entity: "Attachment", field: "ID" }),
+    ("task_attachments_tenant_task_fkey", Violation::Missing { entity: "Task" }),
+    ("user_preferences_tenant_user_fkey", Violation::Missing { entity: "User" }),
+];
+
 /// Map a sqlx error to a `DomainError`, checking for common `PostgreSQL` constraint codes.
 ///
 /// - `23505` `unique_violation` → `DomainError::AlreadyExists`
 /// - `23503` `foreign_key_violation` → `DomainError::NotFound`
+/// - pool exhausted or closed → `DomainError::Unavailable`, counted in [`pool_exhausted_total`]
 /// - anything else → `DomainError::Infrastructure`
+///
+/// The entity named in the message comes from [`CONSTRAINTS`] when the violated
+/// constraint is registered there.
 #[expect(clippy::needless_pass_by_value, reason = "sqlx::Error is not Clone; consumed by value")]
-pub fn map_db_error(e: sqlx::Error, operation: &str, entity: &str) -> crate::shared::domain::DomainError {
-    use crate::shared::domain::DomainError;
-    if let sqlx::Error::Database(ref db_err) = e {
-        match db_err.code().as_deref() {
-            Some("23505") => {
-                let msg = db_err.message();
-                return if msg.contains("email") {
-                    DomainError::AlreadyExists("Email already exists".into())
-                } else {
-                    DomainError::AlreadyExists(format!("{entity} already exists"))
-                };
-            }
-            Some("23503") => return DomainError::NotFound(format!("{entity} not found")),
-            _ => {}
-        }
+pub fn map_db_error(e: sqlx::Error, operation: &str, entity: &str) -> DomainError {
+    if matches!(e, sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed) {
+        POOL_EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!("No database connection available in {operation}: {e}");
+        return DomainError::Unavailable(format!("No database connection to {operation} {entity}"));
+    }
+    if let sqlx::Error::Database(ref db_err) = e
+        && let Some(error) = constraint_error(db_err.code().as_deref(), db_err.constraint(), entity)
+    {
+        return error;
     }
     tracing::error!("Database error in {operation}: {e}");
     DomainError::Infrastructure(format!("Failed to {operation} {entity}"))
 }
+
+fn constraint_error(
+    code: Option<&str>,
+    constraint: Option<&str>,
+    entity: &str,
+) -> Option<DomainError> {
+    let registered = constraint
+        .and_then(|name| CONSTRAINTS.iter().find(|(known, _)| *known == name))
+        .map(|&(_, violation)| violation);
+    match (code, registered) {
+        (Some("23505"), Some(Violation::Taken { entity, field })) => {
+            Some(DomainError::already_exists(entity, field))
+        }
+        (Some("23505"), _) => Some(DomainError::already_exists(entity, "key")),
+        (Some("23503"), Some(Violation::Missing { entity })) => Some(DomainError::not_found(entity)),
+        (Some("23503"), _) => Some(DomainError::not_found("Referenced record")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::shared::application::Deadline;
+    use sqlx::postgres::PgConnectOptions;
+    use std::time::Instant;
+
+    const NO_RETRY: MigrationRetry = MigrationRetry { retries: 0, delay: Duration::ZERO };
+
+    async fn schema_pool(options: PgPoolOptions, connect: PgConnectOptions) -> PgPool {
+        let pool = with_schema(options, "deploy").connect_with(connect).await;
+        pool.expect("connect")
+    }
+
+    #[test]
+    fn foreign_key_violation_should_name_the_referenced_entity() {
+        let error = constraint_error(Some("23503"), Some("tasks_tenant_user_fkey"), "task");
+        assert!(matches!(error, Some(DomainError::NotFound(m)) if m == "User not found"));
+        let error = constraint_error(Some("23503"), Some("unknown_fkey"), "task");
+        let expected = "Referenced record not found";
+        assert!(matches!(error, Some(DomainError::NotFound(m)) if m == expected));
+    }
+
+    #[test]
+    fn unique_violation_should_use_registered_message_or_entity() {
+        let error = constraint_error(Some("23505"), Some("users_tenant_email_key"), "user");
+        let expected = "User with this email already exists";
+        assert!(matches!(error, Some(DomainError::AlreadyExists(m)) if m == expected));
+        let error = constraint_error(Some("23505"), None, "task");
+        let expected = "task with this key already exists";
+        assert!(matches!(error, Some(DomainError::AlreadyExists(m)) if m == expected));
+        assert!(constraint_error(Some("42P01"), None, "task").is_none());
+    }
+
+    #[test]
+    fn pool_stats_job_should_sample_open_and_idle_connections() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let debugging = DebuggingRecorder::new();
+        let snapshotter = debugging.snapshotter();
+        metrics::with_local_recorder(&debugging, || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build();
+            runtime.expect("runtime").block_on(async {
+                // A lazy pool opens no connection until the first query
+                let pool = PgPoolOptions::new().connect_lazy("postgres://localhost/app");
+                let (_shutdown, receiver) = tokio::sync::watch::channel(());
+                let job = PoolStatsJob::new(pool.expect("lazy pool"));
+                job.run(&JobContext::new(receiver)).await.expect("run");
+            });
+        });
+        let mut gauges: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .filter_map(|(key, _, _, value)| match value {
+                DebugValue::Gauge(value) => Some((key.key().name().to_owned(), value.0)),
+                DebugValue::Counter(_) | DebugValue::Histogram(_) => None,
+            })
+            .collect();
+        gauges.sort_by(|a, b| a.0.cmp(&b.0));
+        let expected = [(POOL_CONNECTIONS.to_owned(), 0.0), (POOL_IDLE_CONNECTIONS.to_owned(), 0.0)];
+        assert_eq!(gauges, expected);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn run_query_should_abandon_query_and_release_connection_at_deadline(
+        options: PgPoolOptions,
+        connect: PgConnectOptions,
+    ) -> sqlx::Result<()> {
+        let pool = with_release_check(options).connect_with(connect).await?;
+        let started = Instant::now();
+        let slow = sqlx::query("SELECT pg_sleep(5)").execute(&pool);
+        let result = Deadline::after(Duration::from_millis(200))
+            .scope(run_query(slow, "sleep", "test"))
+            .await;
+
+        assert!(
+            matches!(&result, Err(DomainError::Infrastructure(m)) if m == "deadline exceeded"),
+            "{result:?}"
+        );
+        assert!(started.elapsed() < Duration::from_secs(1));
+        // The release check gives up on the busy connection well before pg_sleep ends
+        tokio::time::sleep(RELEASE_PING_TIMEOUT * 2).await;
+        let in_use = pool.size() - u32::try_from(pool.num_idle()).unwrap_or(u32::MAX);
+        assert_eq!(in_use, 0, "connection still checked out");
+        assert!(started.elapsed() < Duration::from_secs(2));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn concurrent_migrations_should_all_succeed(
+        options: PgPoolOptions,
+        connect: PgConnectOptions,
+    ) {
+        // A short lock_timeout makes waiting instances fail on contention and retry
+        let connect = connect.options([("lock_timeout", "1ms")]);
+        let pool = schema_pool(options, connect).await;
+        assert!(verify_migrations(&pool).await.is_err());
+
+        let retry = MigrationRetry { retries: 50, delay: Duration::from_millis(20) };
+        let runs: Vec<_> = (0..4)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move { run_migrations(&pool, "deploy", retry).await })
+            })
+            .collect();
+        for run in runs {
+            let result = run.await.expect("join");
+            assert!(result.is_ok(), "{result:?}");
+        }
+        assert!(verify_migrations(&pool).await.is_ok());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn run_migrations_should_retry_while_another_instance_holds_the_lock(
+        options: PgPoolOptions,
+        connect: PgConnectOptions,
+    ) {
+        let pool = schema_pool(options, connect.options([("lock_timeout", "50ms")])).await;
+        run_migrations(&pool, "deploy", NO_RETRY).await.expect("first deploy");
+        let mut holder = pool.begin().await.expect("begin");
+        sqlx::query("LOCK TABLE _sqlx_migrations IN ACCESS EXCLUSIVE MODE")
+            .execute(&mut *holder)
+            .await
+            .expect("lock");
+
+        let locked = run_migrations(&pool, "deploy", NO_RETRY).await;
+        assert!(locked.is_err());
+
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            holder.commit().await
+        });
+        let retry = MigrationRetry { retries: 20, delay: Duration::from_millis(50) };
+        let result = run_migrations(&pool, "deploy", retry).await;
+        assert!(result.is_ok(), "{result:?}");
+        release.await.expect("join").expect("commit");
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn verify_migrations_should_report_pending_and_modified_migrations(
+        options: PgPoolOptions,
+        connect: PgConnectOptions,
+    ) {
+        let pool = schema_pool(options, connect).await;
+        run_migrations(&pool, "deploy", NO_RETRY).await.expect("migrate");
+        assert!(verify_migrations(&pool).await.is_ok());
+
+        let latest = MIGRATOR.iter().map(|m| m.version).max().unwrap_or_default();
+        sqlx::query("UPDATE _sqlx_migrations SET checksum = '\\x00' WHERE version = $1")
+            .bind(latest)
+            .execute(&pool)
+            .await
+            .expect("tamper");
+        let modified = verify_migrations(&pool).await.expect_err("modified");
+        assert!(modified.to_string().contains("modified"), "{modified}");
+
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version = $1")
+            .bind(latest)
+            .execute(&pool)
+            .await
+            .expect("forget");
+        let pending = verify_migrations(&pool).await.expect_err("pending");
+        assert!(pending.to_string().contains(&format!("[{latest}]")), "{pending}");
+    }
+
+    #[test]
+    fn repeatable_migrations_should_be_embedded_in_file_name_order() {
+        let names: Vec<&str> = REPEATABLE_MIGRATIONS.iter().map(|(name, _)| *name).collect();
+        assert!(names.contains(&"task_summaries.sql"), "{names:?}");
+        assert!(names.is_sorted());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn repeatable_migration_should_rerun_only_when_its_sql_changes(
+        options: PgPoolOptions,
+        connect: PgConnectOptions,
+    ) {
+        let pool = schema_pool(options, connect).await;
+        run_migrations(&pool, "deploy", NO_RETRY).await.expect("migrate");
+        let probe = |version: u8| {
+            format!(
+                "CREATE TABLE IF NOT EXISTS probe_runs (version INT); \
+                 INSERT INTO probe_runs VALUES ({version})"
+            )
+        };
+        let runs = async || -> Vec<i32> {
+            sqlx::query_scalar("SELECT version FROM probe_runs ORDER BY version")
+                .fetch_all(&pool)
+                .await
+                .expect("runs")
+        };
+
+        for _ in 0..2 {
+            let v1 = probe(1);
+            apply_repeatable_migrations(&pool, &[("probe.sql", &v1)], NO_RETRY).await.expect("v1");
+        }
+        assert_eq!(runs().await, [1]);
+        let v2 = probe(2);
+        apply_repeatable_migrations(&pool, &[("probe.sql", &v2)], NO_RETRY).await.expect("v2");
+        assert_eq!(runs().await, [1, 2]);
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn edited_view_file_should_be_reapplied(
+        options: PgPoolOptions,
+        connect: PgConnectOptions,
+    ) {
+        let pool = schema_pool(options, connect).await;
+        run_migrations(&pool, "deploy", NO_RETRY).await.expect("migrate");
+        assert!(verify_migrations(&pool).await.is_ok());
+        let columns = async || -> Vec<String> {
+            sqlx::query_scalar(
+                "SELECT column_name::TEXT FROM information_schema.columns \
+                 WHERE table_schema = 'deploy' AND table_name = 'task_summaries' \
+                 ORDER BY ordinal_position",
+            )
+            .fetch_all(&pool)
+            .await
+            .expect("columns")
+        };
+        assert_eq!(columns().await, ["tenant_id", "hour", "created", "completed"]);
+
+        let (name, sql) = REPEATABLE_MIGRATIONS
+            .iter()
+            .find(|(name, _)| *name == "task_summaries.sql")
+            .expect("task_summaries.sql");
+        let summed = "SUM(completed)::BIGINT AS completed";
+        let edited = sql.replace(summed, &format!("{summed}, COUNT(*) AS events"));
+        assert_ne!(edited, *sql);
+        apply_repeatable_migrations(&pool, &[(name, &edited)], NO_RETRY).await.expect("edited");
+        assert_eq!(columns().await, ["tenant_id", "hour", "created", "completed", "events"]);
+        let stale = verify_migrations(&pool).await.expect_err("stale view");
+        assert!(stale.to_string().contains("task_summaries.sql"), "{stale}");
+
+        // Deploying the embedded file again restores it
+        run_migrations(&pool, "deploy", NO_RETRY).await.expect("redeploy");
+        assert_eq!(columns().await, ["tenant_id", "hour", "created", "completed"]);
+        assert!(verify_migrations(&pool).await.is_ok());
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn failing_repeatable_migration_should_name_its_file_and_roll_back(
+        options: PgPoolOptions,
+        connect: PgConnectOptions,
+    ) {
+        let pool = schema_pool(options, connect).await;
+        run_migrations(&pool, "deploy", NO_RETRY).await.expect("migrate");
+        // Listed out of order; b_view.sql only succeeds after a_table.sql
+        let ordered = [
+            ("b_view.sql", "CREATE VIEW b_view AS SELECT * FROM a_table"),
+            ("a_table.sql", "CREATE TABLE a_table (id INT)"),
+        ];
+        apply_repeatable_migrations(&pool, &ordered, NO_RETRY).await.expect("file name order");
+
+        let broken = [("c_broken.sql", "CREATE TABLE c_partial (id INT); SELEC 1")];
+        let error = apply_repeatable_migrations(&pool, &broken, NO_RETRY).await.expect_err("SQL");
+        assert_eq!(error.to_string(), "Repeatable migration c_broken.sql failed");
+        let partial: Option<String> = sqlx::query_scalar("SELECT to_regclass('c_partial')::text")
+            .fetch_one(&pool)
+            .await
+            .expect("query");
+        assert_eq!(partial, None);
+        let recorded: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM app_repeatable_migrations WHERE name = 'c_broken.sql'",
+        )
+        .fetch_one(&pool)
+        .await
+        .expect("query");
+        assert_eq!(recorded, 0);
+    }
+}
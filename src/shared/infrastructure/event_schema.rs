@@ -0,0 +1,47 @@
+//! `GET /events/schema`: the event types and versions consumers may receive
+//!
+//! Lists [`SCHEMAS`] with the fields of each payload, so that consumers written in
+//! any language can check which versions they handle.
+
+use crate::shared::events::SCHEMAS;
+use crate::shared::infrastructure::http::json_response;
+use axum::{http::StatusCode, response::Response, routing::get, Router};
+
+/// `GET /events/schema`
+pub fn routes() -> Router {
+    Router::new().route("/events/schema", get(schema))
+}
+
+/// Every event type and version emitted, by type then version
+async fn schema() -> Response {
+    json_response(StatusCode::OK, SCHEMAS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::send;
+    use axum::http::Method;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn schema_should_list_every_event_type_and_version() {
+        let (status, body) = send(&routes(), Method::GET, "/events/schema", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let listed: Vec<_> = body
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|schema| (schema["eventType"].clone(), schema["version"].clone()))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                (json!("tasks.reassigned"), json!(1)),
+                (json!("user.email_change_requested"), json!(1)),
+                (json!("user.onboarded"), json!(1)),
+            ]
+        );
+        assert_eq!(body[0]["fields"][1], json!({"name": "only_open", "type": "boolean"}));
+    }
+}
@@ -0,0 +1,331 @@
+//! Feature router registration
+//!
+//! Each feature exposes its routes relative to a path prefix. The registry nests
+//! them under their prefixes and fails startup on conflicting registrations
+//! instead of letting `Router::merge` panic on overlapping paths.
+//!
+//! Next to its router, each feature declares the routes it serves in a manifest.
+//! [`FeatureRegistry::check`] compares the manifests with the routers before the
+//! listener is bound, so a route lost to a wrong path or a missing state type is
+//! reported with the feature that declared it rather than surfacing as a 404 later.
+
+use crate::shared::infrastructure::http::json_response;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    response::Response,
+    Router,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+/// Routes of a single feature, relative to its prefix
+pub struct FeatureRouter<S> {
+    /// Feature name, used in error messages
+    pub name: &'static str,
+    /// Path prefix the routes are nested under (e.g. `/tasks`)
+    pub prefix: &'static str,
+    /// Routes relative to the prefix
+    pub router: Router<S>,
+    /// Every route `router` is expected to serve
+    pub manifest: &'static [ExpectedRoute],
+}
+
+/// A method and path, relative to the feature prefix, that a feature declares it serves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedRoute {
+    /// Request method
+    pub method: Method,
+    /// Path relative to the prefix, with `{param}` segments as routed
+    pub path: &'static str,
+}
+
+impl ExpectedRoute {
+    /// `GET path`
+    #[must_use]
+    pub const fn get(path: &'static str) -> Self {
+        Self { method: Method::GET, path }
+    }
+
+    /// `POST path`
+    #[must_use]
+    pub const fn post(path: &'static str) -> Self {
+        Self { method: Method::POST, path }
+    }
+
+    /// `PUT path`
+    #[must_use]
+    pub const fn put(path: &'static str) -> Self {
+        Self { method: Method::PUT, path }
+    }
+
+    /// `PATCH path`
+    #[must_use]
+    pub const fn patch(path: &'static str) -> Self {
+        Self { method: Method::PATCH, path }
+    }
+
+    /// `DELETE path`
+    #[must_use]
+    pub const fn delete(path: &'static str) -> Self {
+        Self { method: Method::DELETE, path }
+    }
+}
+
+/// A declared route with its full path, as listed by `GET /internal/routes`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManifestRoute {
+    /// Name of the feature declaring the route
+    pub feature: &'static str,
+    /// Request method
+    pub method: String,
+    /// Path including the feature prefix
+    pub path: String,
+}
+
+impl ManifestRoute {
+    /// The path as a request could reach it: every `{param}` segment filled in
+    fn probe_path(&self) -> String {
+        self.path
+            .split('/')
+            .map(|segment| if segment.starts_with('{') { "probe" } else { segment })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// The method and path with parameter names dropped, equal for routes axum
+    /// would treat as the same
+    fn shape(&self) -> (String, String) {
+        let path = self
+            .path
+            .split('/')
+            .map(|segment| if segment.starts_with('{') { "{}" } else { segment })
+            .collect::<Vec<_>>()
+            .join("/");
+        (self.method.clone(), path)
+    }
+}
+
+/// `GET /internal/routes`: every route the enabled features declare
+pub async fn route_manifest(State(routes): State<Arc<[ManifestRoute]>>) -> Response {
+    json_response(StatusCode::OK, &*routes)
+}
+
+/// Collects feature routers and nests them under their prefixes
+pub struct FeatureRegistry<S> {
+    features: Vec<FeatureRouter<S>>,
+}
+
+impl<S> Default for FeatureRegistry<S> {
+    fn default() -> Self {
+        Self { features: Vec::new() }
+    }
+}
+
+impl<S: Clone + Send + Sync + 'static> FeatureRegistry<S> {
+    /// Register a feature's routes
+    #[must_use]
+    pub fn register(mut self, feature: FeatureRouter<S>) -> Self {
+        self.features.push(feature);
+        self
+    }
+
+    /// Nest every registered feature under its prefix.
+    ///
+    /// # Errors
+    /// Fails when two features share a name or prefix.
+    pub fn build(self) -> Result<Router<S>, anyhow::Error> {
+        for (i, feature) in self.features.iter().enumerate() {
+            if let Some(other) = self.features[..i]
+                .iter()
+                .find(|other| other.prefix == feature.prefix || other.name == feature.name)
+            {
+                anyhow::bail!(
+                    "Conflicting feature registration: '{}' ({}) and '{}' ({})",
+                    other.name,
+                    other.prefix,
+                    feature.name,
+                    feature.prefix
+                );
+            }
+        }
+
+        Ok(self
+            .features
+            .into_iter()
+            .fold(Router::new(), |router, feature| router.nest(feature.prefix, feature.router)))
+    }
+
+    /// Every route the registered features declare, in registration order
+    #[must_use]
+    pub fn manifest(&self) -> Vec<ManifestRoute> {
+        self.features
+            .iter()
+            .flat_map(|feature| {
+                feature.manifest.iter().map(|route| ManifestRoute {
+                    feature: feature.name,
+                    method: route.method.to_string(),
+                    path: match route.path {
+                        "/" => feature.prefix.to_owned(),
+                        path => format!("{}{path}", feature.prefix),
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+impl FeatureRegistry<()> {
+    /// Check the declared routes against the registered routers
+    ///
+    /// Routes declared twice are reported first, as nesting them would panic. Then the
+    /// path of every declared route is probed through the nested routers with a
+    /// `PROBE` request: no route takes that method, so no handler or state is
+    /// touched, and the router answers `405` listing the methods the path is routed
+    /// for in `Allow`, or `404` when it is not routed at all.
+    ///
+    /// # Errors
+    /// Lists every duplicated or missing route with the feature declaring it.
+    pub async fn check(&self) -> Result<(), anyhow::Error> {
+        let manifest = self.manifest();
+        let mut problems = Vec::new();
+        let mut seen: HashMap<(String, String), &ManifestRoute> = HashMap::new();
+        for route in &manifest {
+            if let Some(first) = seen.insert(route.shape(), route) {
+                problems.push(format!(
+                    "'{}' and '{}' both declare {} {}",
+                    first.feature, route.feature, first.method, first.path
+                ));
+            }
+        }
+        if problems.is_empty() {
+            let probe = self.features.iter().fold(Router::new(), |router, feature| {
+                router.nest(feature.prefix, feature.router.clone())
+            });
+            for route in &manifest {
+                let request = Request::builder()
+                    .method(Method::from_bytes(b"PROBE")?)
+                    .uri(route.probe_path())
+                    .body(Body::empty())?;
+                let response = probe.clone().oneshot(request).await?;
+                let allowed = response
+                    .headers()
+                    .get(header::ALLOW)
+                    .and_then(|allow| allow.to_str().ok())
+                    .unwrap_or_default();
+                let reason = match response.status() {
+                    StatusCode::METHOD_NOT_ALLOWED
+                        if allowed.split(',').any(|method| method.trim() == route.method) =>
+                    {
+                        continue;
+                    }
+                    StatusCode::METHOD_NOT_ALLOWED => format!("routed for {allowed} only"),
+                    status => status.to_string(),
+                };
+                problems.push(format!(
+                    "{} {} is declared by '{}' but not routed ({reason})",
+                    route.method, route.path, route.feature
+                ));
+            }
+        }
+        if !problems.is_empty() {
+            anyhow::bail!("Route check failed: {}", problems.join("; "));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+
+    const ROOT: &[ExpectedRoute] = &[ExpectedRoute::get("/")];
+
+    fn feature(name: &'static str, prefix: &'static str) -> FeatureRouter<()> {
+        FeatureRouter {
+            name,
+            prefix,
+            router: Router::new().route("/", get(|| async {})),
+            manifest: ROOT,
+        }
+    }
+
+    #[test]
+    fn build_should_reject_duplicate_prefix() {
+        let result = FeatureRegistry::default()
+            .register(feature("user", "/users"))
+            .register(feature("account", "/users"))
+            .build();
+        let message = result.err().map(|e| e.to_string()).unwrap_or_default();
+        assert_eq!(
+            message,
+            "Conflicting feature registration: 'user' (/users) and 'account' (/users)"
+        );
+    }
+
+    #[test]
+    fn build_should_reject_duplicate_name() {
+        let result = FeatureRegistry::default()
+            .register(feature("user", "/users"))
+            .register(feature("user", "/people"))
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn check_should_accept_routers_serving_their_manifest() {
+        let registry = FeatureRegistry::default()
+            .register(feature("user", "/users"))
+            .register(feature("task", "/tasks"));
+        assert!(registry.check().await.is_ok());
+        let paths: Vec<_> = registry.manifest().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/users", "/tasks"]);
+    }
+
+    #[tokio::test]
+    async fn check_should_report_routes_declared_twice() {
+        const NOTE: &[ExpectedRoute] = &[ExpectedRoute::get("/{id}")];
+        let notes = FeatureRouter {
+            name: "note",
+            prefix: "/notes",
+            router: Router::new().route("/{id}", get(|| async {})),
+            manifest: NOTE,
+        };
+        // Nesting this router next to the other would panic on the overlapping route
+        let registry = FeatureRegistry::default()
+            .register(notes)
+            .register(feature("note_detail", "/notes/{note_id}"));
+        let message = registry.check().await.err().map(|e| e.to_string());
+        assert_eq!(
+            message.as_deref(),
+            Some(
+                "Route check failed: 'note' and 'note_detail' both declare GET /notes/{id}"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn check_should_report_declared_routes_not_routed() {
+        const NOTE: &[ExpectedRoute] =
+            &[ExpectedRoute::get("/"), ExpectedRoute::post("/"), ExpectedRoute::get("/{id}")];
+        let notes = FeatureRouter {
+            name: "note",
+            prefix: "/notes",
+            router: Router::new().route("/", get(|| async {})).route("/{id}/x", get(|| async {})),
+            manifest: NOTE,
+        };
+        let message = FeatureRegistry::default().register(notes).check().await.err();
+        assert_eq!(
+            message.map(|e| e.to_string()).as_deref(),
+            Some(
+                "Route check failed: POST /notes is declared by 'note' but not routed \
+                 (routed for GET,HEAD only); GET /notes/{id} is declared by 'note' but not \
+                 routed (404 Not Found)"
+            )
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Admin routes of the feature flags
+//!
+//! `GET /admin/flags` lists every flag of the [`FeatureToggles`] with the percentage it
+//! is rolled out to and the one it was configured with; `PUT /admin/flags/{name}`
+//! with `{"percentage": 25}` changes a rollout until the next change or restart.
+//! Changes apply to this instance only.
+
+use crate::shared::application::FeatureToggles;
+use crate::shared::infrastructure::http::{json_response, ApiError, ApiPath, Negotiated};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Response,
+    routing::{get, put},
+    Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Body of `PUT /admin/flags/{name}`
+#[derive(Debug, Deserialize)]
+struct SetFlagRequest {
+    /// Percentage of entities the flag is on for, 0 to 100
+    percentage: u8,
+}
+
+/// `GET /admin/flags` and `PUT /admin/flags/{name}`
+pub fn routes(toggles: Arc<FeatureToggles>) -> Router {
+    Router::new()
+        .route("/admin/flags", get(list_flags))
+        .route("/admin/flags/{name}", put(set_flag))
+        .with_state(toggles)
+}
+
+/// `GET /admin/flags`: every flag, by name
+async fn list_flags(State(toggles): State<Arc<FeatureToggles>>) -> Response {
+    json_response(StatusCode::OK, &toggles.flags())
+}
+
+/// `PUT /admin/flags/{name}`: roll the flag out to the percentage of the body,
+/// returning its new state; 404 for an unknown flag
+async fn set_flag(
+    State(toggles): State<Arc<FeatureToggles>>,
+    ApiPath(name): ApiPath<String>,
+    Negotiated(body): Negotiated<SetFlagRequest>,
+) -> Result<Response, ApiError> {
+    let state = toggles.set(&name, body.percentage)?;
+    tracing::info!(flag = state.name, percentage = state.percentage, "Feature flag changed");
+    Ok(json_response(StatusCode::OK, &state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::send;
+    use axum::http::Method;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn flags_should_be_listed_and_changed_at_runtime() {
+        let toggles = Arc::new(FeatureToggles::new(&[("atomic_complete", 100)]));
+        let app = routes(Arc::clone(&toggles));
+
+        let (status, body) = send(&app, Method::GET, "/admin/flags", None).await;
+        let flag = json!({"name": "atomic_complete", "percentage": 100, "configured": 100});
+        assert_eq!((status, body), (StatusCode::OK, json!([flag])));
+
+        let uri = "/admin/flags/atomic_complete";
+        let (status, body) = send(&app, Method::PUT, uri, Some(json!({"percentage": 0}))).await;
+        let flag = json!({"name": "atomic_complete", "percentage": 0, "configured": 100});
+        assert_eq!((status, body), (StatusCode::OK, flag.clone()));
+        assert!(!toggles.is_enabled("atomic_complete", "task-1"));
+        assert_eq!(send(&app, Method::GET, "/admin/flags", None).await.1, json!([flag]));
+
+        let (status, _) = send(&app, Method::PUT, uri, Some(json!({"percentage": 101}))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let missing = "/admin/flags/missing";
+        let (status, _) = send(&app, Method::PUT, missing, Some(json!({"percentage": 5}))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,163 @@
+//! Sparse fieldsets: `?fields=a,b` projects response objects to a subset of their fields
+//!
+//! Without the parameter responses are serialized exactly as before; with it each
+//! object is serialized to a JSON value and pruned to the requested fields. `id` is
+//! always kept so clients can still address what they received. Fields are selected
+//! by their camelCase names, valid lists included. `?embed=` names the extras to add
+//! to a response and is checked against the extras an endpoint supports.
+
+use crate::shared::infrastructure::casing;
+use crate::shared::infrastructure::http::{ApiError, Negotiated};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Field names requested via `?fields=`, validated and always including `id`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSelection(Vec<String>);
+
+impl FieldSelection {
+    /// Parse the comma-separated `fields` parameter against the `valid` field names.
+    ///
+    /// Returns `None` when the parameter is absent, meaning "every field".
+    ///
+    /// # Errors
+    /// A 400 `INVALID_QUERY` error listing the valid fields when a name is unknown.
+    pub fn parse(fields: Option<&str>, valid: &[&str]) -> Result<Option<Self>, ApiError> {
+        let Some(fields) = fields else {
+            return Ok(None);
+        };
+        let mut selected = vec!["id".to_owned()];
+        // Fields are named as in the body; the legacy snake_case names work as well
+        let names = fields.split(',').map(str::trim).filter(|name| !name.is_empty());
+        for name in names.map(casing::camel_case) {
+            let name = name.as_str();
+            if !valid.contains(&name) {
+                return Err(ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    "INVALID_QUERY",
+                    format!("Unknown field '{name}'; valid fields: {}", valid.join(", ")),
+                ));
+            }
+            if !selected.iter().any(|s| s == name) {
+                selected.push(name.to_owned());
+            }
+        }
+        Ok(Some(Self(selected)))
+    }
+
+    fn retain(&self, value: Value) -> Value {
+        match value {
+            Value::Object(mut object) => {
+                object.retain(|key, _| self.0.iter().any(|field| field == key));
+                Value::Object(object)
+            }
+            other => other,
+        }
+    }
+}
+
+/// Whether the comma-separated `?embed=` parameter requests `wanted`
+///
+/// # Errors
+/// A 400 `INVALID_QUERY` error listing the `supported` embeds when a name is unknown.
+pub fn embeds(embed: Option<&str>, supported: &[&str], wanted: &str) -> Result<bool, ApiError> {
+    let mut found = false;
+    for name in embed.into_iter().flat_map(|e| e.split(',')).map(str::trim) {
+        if !supported.contains(&name) {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_QUERY",
+                format!("Unsupported embed '{name}'; supported embeds: {}", supported.join(", ")),
+            ));
+        }
+        found |= name == wanted;
+    }
+    Ok(found)
+}
+
+/// Respond with `items` as a JSON array, projected to `selection` when there is one.
+///
+/// # Errors
+/// A 500 error if an item cannot be serialized to a JSON value.
+pub fn project_list<T: Serialize>(
+    items: Vec<T>,
+    selection: Option<&FieldSelection>,
+) -> Result<Response, ApiError> {
+    let Some(selection) = selection else {
+        return Ok(Negotiated(items).into_response());
+    };
+    let projected = items
+        .iter()
+        .map(|item| serde_json::to_value(item).map(|value| selection.retain(value)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            tracing::error!("Failed to project response fields: {e}");
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "Internal server error",
+            )
+        })?;
+    Ok(Negotiated(projected).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const VALID: &[&str] = &["id", "title", "completed"];
+
+    #[derive(Serialize)]
+    struct Item {
+        id: &'static str,
+        title: &'static str,
+        completed: bool,
+    }
+
+    fn selection(fields: &str) -> Option<FieldSelection> {
+        FieldSelection::parse(Some(fields), VALID).ok().flatten()
+    }
+
+    #[test]
+    fn parse_should_always_include_id_and_skip_blanks_and_duplicates() {
+        let expected = FieldSelection(vec!["id".into(), "title".into()]);
+        assert_eq!(selection(" title, ,title,id"), Some(expected));
+        assert_eq!(selection(""), Some(FieldSelection(vec!["id".into()])));
+        assert!(matches!(FieldSelection::parse(None, VALID), Ok(None)));
+    }
+
+    #[test]
+    fn parse_should_reject_unknown_fields_listing_valid_ones() {
+        let error = FieldSelection::parse(Some("title,color"), VALID).err();
+        let message = error.as_ref().map(|e| e.message.as_str());
+        assert_eq!(message, Some("Unknown field 'color'; valid fields: id, title, completed"));
+    }
+
+    #[tokio::test]
+    async fn project_list_should_prune_objects_to_selection() {
+        let items = vec![Item { id: "1", title: "Buy milk", completed: false }];
+        let response = project_list(items, selection("completed").as_ref());
+        let body = match response {
+            Ok(response) => axum::body::to_bytes(response.into_body(), usize::MAX).await.ok(),
+            Err(_) => None,
+        };
+        let json = body.and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok());
+        assert_eq!(json, Some(json!([{"id": "1", "completed": false}])));
+    }
+
+    #[tokio::test]
+    async fn project_list_without_selection_should_serialize_unchanged() {
+        let items = vec![Item { id: "1", title: "Buy milk", completed: false }];
+        let expected = serde_json::to_vec(&items).ok();
+        let body = match project_list(items, None) {
+            Ok(response) => axum::body::to_bytes(response.into_body(), usize::MAX).await.ok(),
+            Err(_) => None,
+        };
+        assert_eq!(body.map(|bytes| bytes.to_vec()), expected);
+    }
+}
@@ -0,0 +1,190 @@
+//! `healthcheck` subcommand: probes a running server, for container health checks
+//!
+//! Images without `curl` run `HEALTHCHECK CMD ["app", "healthcheck"]`: the binary
+//! loads the same configuration as the server, asks it over HTTP whether it is ready
+//! and exits 0 or 1.
+
+use crate::shared::infrastructure::config::Config;
+use crate::shared::infrastructure::http_client::USER_AGENT;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+/// Time allowed for the whole probe, connection included
+pub const HEALTHCHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Failed probe, reported before exiting with 1
+#[derive(Debug, thiserror::Error)]
+pub enum HealthcheckError {
+    /// The arguments after `healthcheck` were not understood
+    #[error("{0}; usage: healthcheck [--liveness] [--url URL]")]
+    Usage(String),
+    /// No response arrived in time
+    #[error("{url} did not answer: {reason}")]
+    Unreachable {
+        /// URL probed
+        url: String,
+        /// Why the request failed
+        reason: String,
+    },
+    /// The server answered with a non-success status
+    #[error("{url} answered {status}: {body}")]
+    Unhealthy {
+        /// URL probed
+        url: String,
+        /// Status of the response
+        status: u16,
+        /// Body of the response, the JSON health report
+        body: String,
+    },
+}
+
+/// URL the probe requests given the arguments following `healthcheck`
+///
+/// `/ready` on the admin port by default, `/health` on the API port with
+/// `--liveness`, or whatever `--url` names. A server listening on every interface
+/// (`SERVER_HOST=0.0.0.0`) is reached through the loopback address.
+///
+/// # Errors
+/// [`HealthcheckError::Usage`] for an unknown argument or `--url` without a value.
+pub fn probe_url(
+    config: &Config,
+    args: impl IntoIterator<Item = String>,
+) -> Result<String, HealthcheckError> {
+    let (mut liveness, mut url) = (false, None);
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--liveness" => liveness = true,
+            "--url" => {
+                let value = args.next().ok_or_else(|| usage("--url needs a value"))?;
+                url = Some(value);
+            }
+            other => return Err(usage(&format!("unknown argument {other}"))),
+        }
+    }
+    Ok(url.unwrap_or_else(|| {
+        if liveness {
+            format!("http://{}/health", reachable(config.server_addr))
+        } else {
+            format!("http://{}/ready", reachable(config.admin_addr))
+        }
+    }))
+}
+
+/// GET `url`, succeeding on a 2xx response within [`HEALTHCHECK_TIMEOUT`]
+///
+/// # Errors
+/// [`HealthcheckError::Unreachable`] without a response, and
+/// [`HealthcheckError::Unhealthy`] with the body of any other response.
+pub async fn probe(url: &str) -> Result<(), HealthcheckError> {
+    let unreachable = |e: reqwest::Error| {
+        // reqwest only names the step that failed; the cause says why
+        let mut reason = e.to_string();
+        let mut source = std::error::Error::source(&e);
+        while let Some(cause) = source {
+            reason = format!("{reason}: {cause}");
+            source = cause.source();
+        }
+        HealthcheckError::Unreachable { url: url.to_owned(), reason }
+    };
+    let client = reqwest::Client::builder()
+        .timeout(HEALTHCHECK_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(unreachable)?;
+    let response = client.get(url).send().await.map_err(unreachable)?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.map_err(unreachable)?;
+    Err(HealthcheckError::Unhealthy { url: url.to_owned(), status: status.as_u16(), body })
+}
+
+/// `addr`, with the loopback address in place of an unspecified one
+fn reachable(addr: SocketAddr) -> SocketAddr {
+    let loopback: IpAddr = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => Ipv4Addr::LOCALHOST.into(),
+        IpAddr::V6(ip) if ip.is_unspecified() => Ipv6Addr::LOCALHOST.into(),
+        _ => return addr,
+    };
+    SocketAddr::new(loopback, addr.port())
+}
+
+fn usage(message: &str) -> HealthcheckError {
+    HealthcheckError::Usage(message.to_owned())
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::app::{build_admin_router, build_router, AppState, InMemoryRepositories};
+    use crate::shared::infrastructure::http::Health;
+    use axum::http::StatusCode;
+    use axum::{routing::get, Json, Router};
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use tokio::net::TcpListener;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| (*arg).to_owned()).collect()
+    }
+
+    /// Serve `router` on a free port of every interface, returning the port
+    async fn spawn(router: Router) -> u16 {
+        let listener = TcpListener::bind("0.0.0.0:0").await.expect("bind");
+        let port = listener.local_addr().expect("addr").port();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        port
+    }
+
+    #[test]
+    fn probe_url_should_reach_servers_on_every_interface_through_loopback() {
+        let mut config = Config::default();
+        let url = |given: &[&str]| probe_url(&config, args(given)).expect("valid arguments");
+        assert_eq!(url(&[]), "http://127.0.0.1:9000/ready");
+        assert_eq!(url(&["--liveness"]), "http://127.0.0.1:3000/health");
+        assert_eq!(url(&["--url", "http://app:8080/ready"]), "http://app:8080/ready");
+
+        config.server_addr = "[::]:3000".parse().expect("addr");
+        let url = probe_url(&config, args(&["--liveness"])).expect("valid arguments");
+        assert_eq!(url, "http://[::1]:3000/health");
+        let error = probe_url(&config, args(&["--url"])).err().map(|e| e.to_string());
+        let usage = "usage: healthcheck [--liveness] [--url URL]";
+        assert_eq!(error, Some(format!("--url needs a value; {usage}")));
+        assert!(probe_url(&config, args(&["--verbose"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn probe_should_pass_against_a_running_server() {
+        let mut config = Config::default();
+        let state = AppState::build(&config, &InMemoryRepositories::default()).expect("state");
+        let metrics = PrometheusBuilder::new().build_recorder().handle();
+        let api = spawn(build_router(&state, &config).expect("router")).await;
+        let admin = spawn(build_admin_router(&state, metrics, None)).await;
+        config.server_addr = SocketAddr::from(([0, 0, 0, 0], api));
+        config.admin_addr = SocketAddr::from(([0, 0, 0, 0], admin));
+
+        for given in [&[][..], &["--liveness"]] {
+            let url = probe_url(&config, args(given)).expect("valid arguments");
+            probe(&url).await.expect("healthy");
+        }
+    }
+
+    #[tokio::test]
+    async fn probe_should_fail_with_the_body_of_an_unhealthy_or_absent_server() {
+        let unavailable =
+            || async { (StatusCode::SERVICE_UNAVAILABLE, Json(Health { status: "unavailable" })) };
+        let router = Router::new().route("/ready", get(unavailable));
+        let url = format!("http://127.0.0.1:{}/ready", spawn(router).await);
+        let error = probe(&url).await.err().map(|e| e.to_string());
+        assert_eq!(error, Some(format!("{url} answered 503: {{\"status\":\"unavailable\"}}")));
+
+        // Nothing listens on the port once its listener is dropped
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("http://{}/ready", listener.local_addr().expect("addr"));
+        drop(listener);
+        let result = probe(&url).await;
+        assert!(matches!(result, Err(HealthcheckError::Unreachable { .. })), "{result:?}");
+    }
+}
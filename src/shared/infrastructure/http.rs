@@ -1,45 +1,754 @@
 //! HTTP error handling and shared response types
 
-use crate::shared::domain::DomainError;
+use crate::shared::application::{Deadline, ValidationErrors};
+use crate::shared::domain::{DomainError, DomainWarning, ErrorStatus};
+use crate::shared::infrastructure::casing::{self, FieldCasing};
+use crate::shared::infrastructure::config::Config;
 use axum::{
-    http::StatusCode,
+    body::HttpBody,
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, MatchedPath, Path, Query, Request, State,
+    },
+    http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// API error response
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ApiError {
     /// Error code
     pub code: &'static str,
     /// Error message
     pub message: String,
+    /// Machine-readable specifics of the error, when it has any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
     #[serde(skip)]
     status: StatusCode,
+    /// [`DomainError::kind`] of the error this one was converted from
+    #[serde(skip)]
+    kind: Option<&'static str>,
+    /// [`DomainError::entity`] of the error this one was converted from
+    #[serde(skip)]
+    entity: Option<String>,
+}
+
+impl ApiError {
+    /// Create an error with an explicit status and code
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), details: None, status, kind: None, entity: None }
+    }
+
+    /// Map an error caused by query parameters: validation errors become
+    /// 400 `INVALID_QUERY`, others map like any [`DomainError`]
+    #[must_use]
+    pub fn from_query(e: DomainError) -> Self {
+        match e {
+            DomainError::Validation(message) => {
+                Self::new(StatusCode::BAD_REQUEST, "INVALID_QUERY", message)
+            }
+            other => other.into(),
+        }
+    }
+
+    /// 400 `INVALID_QUERY` for query parameters breaking rules, listing each in
+    /// `details.fields`
+    #[must_use]
+    pub fn invalid_query(errors: &ValidationErrors) -> Self {
+        let error = Self::new(StatusCode::BAD_REQUEST, "INVALID_QUERY", errors.to_string());
+        Self { details: Some(serde_json::json!({ "fields": errors.fields() })), ..error }
+    }
+
+    /// 400 `INVALID_BODY` for a request body that cannot be acted on at all, listing
+    /// the offending fields in `details.fields`; rules of the domain stay 422
+    #[must_use]
+    pub fn invalid_body(errors: &ValidationErrors) -> Self {
+        let error = Self::new(StatusCode::BAD_REQUEST, "INVALID_BODY", errors.to_string());
+        Self { details: Some(body_field_details(errors)), ..error }
+    }
+
+    /// 415 `UNSUPPORTED_MEDIA_TYPE` for a body sent with the `received` `Content-Type`,
+    /// if any, listing the `accepted` media types
+    #[must_use]
+    pub fn unsupported_media_type(accepted: &[&str], received: Option<&HeaderValue>) -> Self {
+        let accepted = accepted.join(" or ");
+        let message = match received.map(HeaderValue::to_str) {
+            Some(Ok(received)) => format!("Content-Type must be {accepted}; got {received}"),
+            _ => format!("Content-Type must be {accepted}"),
+        };
+        Self::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, "UNSUPPORTED_MEDIA_TYPE", message)
+    }
+}
+
+/// `details.fields` of `errors` of a request body, naming the fields in the casing
+/// of the body
+fn body_field_details(errors: &ValidationErrors) -> serde_json::Value {
+    let fields: Vec<_> = errors
+        .fields()
+        .iter()
+        .map(|error| {
+            let field = match FieldCasing::current() {
+                FieldCasing::Camel => casing::camel_case(error.field),
+                FieldCasing::Snake => error.field.to_owned(),
+            };
+            serde_json::json!({ "field": field, "message": error.message })
+        })
+        .collect();
+    serde_json::json!({ "fields": fields })
+}
+
+/// HTTP status of a [`DomainError::Custom`] error
+fn custom_status(hint: ErrorStatus) -> StatusCode {
+    match hint {
+        ErrorStatus::Invalid => StatusCode::UNPROCESSABLE_ENTITY,
+        ErrorStatus::NotFound => StatusCode::NOT_FOUND,
+        ErrorStatus::Conflict => StatusCode::CONFLICT,
+        ErrorStatus::Gone => StatusCode::GONE,
+        ErrorStatus::LimitExceeded => StatusCode::TOO_MANY_REQUESTS,
+        ErrorStatus::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+    }
 }
 
+/// Every variant is matched explicitly, so adding one to [`DomainError`] fails to
+/// compile here rather than falling into the internal-error arm
 impl From<DomainError> for ApiError {
     fn from(e: DomainError) -> Self {
-        let (code, status, message) = match &e {
+        let kind = Some(e.kind());
+        let entity = e.entity().map(str::to_owned);
+        let (code, status, message) = match e {
+            // Features define these, so they render generically
+            DomainError::Custom { code, status_hint, message, details } => {
+                let details = (!details.is_null()).then_some(details);
+                let error = Self::new(custom_status(status_hint), code, message);
+                return Self { details, kind, entity, ..error };
+            }
             DomainError::NotFound(_) => ("NOT_FOUND", StatusCode::NOT_FOUND, e.to_string()),
-            DomainError::Validation(_) => ("VALIDATION_ERROR", StatusCode::BAD_REQUEST, e.to_string()),
+            DomainError::Validation(_) => {
+                ("VALIDATION_ERROR", StatusCode::UNPROCESSABLE_ENTITY, e.to_string())
+            }
             DomainError::AlreadyExists(_) => ("ALREADY_EXISTS", StatusCode::CONFLICT, e.to_string()),
+            DomainError::Conflict(_) => ("CONFLICT", StatusCode::CONFLICT, e.to_string()),
+            DomainError::Expired(_) => ("GONE", StatusCode::GONE, e.to_string()),
+            DomainError::Unavailable(_) => (
+                "SERVICE_BUSY",
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service busy, retry later".to_string(),
+            ),
             DomainError::Infrastructure(_) | DomainError::Unexpected(_) => {
-                // Don't leak internal details to the client
+                // Don't leak internal details to the client; they only go to the log
+                tracing::error!(error = %e, "Internal error");
                 ("INTERNAL_ERROR", StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
             }
         };
-        Self { code, message, status }
+        Self { kind, entity, ..Self::new(status, code, message) }
+    }
+}
+
+/// Rendered like [`DomainError::Validation`], listing each broken rule in
+/// `details.fields`
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        let details = body_field_details(&errors);
+        let error = Self::from(DomainError::from(errors));
+        Self { details: Some(details), ..error }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        // Syntactic failures (malformed JSON, wrong types, missing fields) are 400;
+        // only a wrong content type gets the more specific 415.
+        let status = match rejection {
+            JsonRejection::MissingJsonContentType(_) => {
+                return Self::unsupported_media_type(&[JSON], None);
+            }
+            JsonRejection::BytesRejection(_)
+                if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE =>
+            {
+                return Self::new(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "BODY_TOO_LARGE",
+                    rejection.body_text(),
+                );
+            }
+            _ => StatusCode::BAD_REQUEST,
+        };
+        Self::new(status, "INVALID_BODY", rejection.body_text())
+    }
+}
+
+/// 400 `INVALID_QUERY`, naming the parameter that could not be read
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "INVALID_QUERY", rejection.body_text())
+    }
+}
+
+/// 400 `INVALID_PATH`, naming the segment that could not be read; a route whose
+/// parameters do not fit its extractor is a bug, rendered as an internal error
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        if rejection.status().is_server_error() {
+            return DomainError::Unexpected(rejection.body_text()).into();
+        }
+        Self::new(StatusCode::BAD_REQUEST, "INVALID_PATH", rejection.body_text())
     }
 }
 
 impl IntoResponse for ApiError {
+    fn into_response(mut self) -> Response {
+        let busy = self.code == "SERVICE_BUSY";
+        let outcome = ErrorOutcome { code: self.code, kind: self.kind, entity: self.entity.take() };
+        let status = self.status;
+        let mut response = Negotiated(self).with_status(status);
+        if busy {
+            response.extensions_mut().insert(ServiceBusy);
+        }
+        response.extensions_mut().insert(outcome);
+        response
+    }
+}
+
+/// Counter of error responses, labelled with the error `code` and the matched `route`
+pub const HTTP_ERRORS_TOTAL: &str = "http_errors_total";
+
+/// Response extension describing an [`ApiError`], picked up by [`record_error_outcome`]
+#[derive(Debug, Clone)]
+struct ErrorOutcome {
+    code: &'static str,
+    kind: Option<&'static str>,
+    entity: Option<String>,
+}
+
+/// Span wrapping each request, with empty error fields for [`record_error_outcome`],
+/// database fields for [`count_queries`](super::instrumentation::count_queries) and
+/// the client address for [`capture_client_ip`](super::client_ip::capture_client_ip)
+pub fn request_span<B>(request: &axum::http::Request<B>) -> tracing::Span {
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        client.ip = tracing::field::Empty,
+        error.code = tracing::field::Empty,
+        error.kind = tracing::field::Empty,
+        entity = tracing::field::Empty,
+        db.queries = tracing::field::Empty,
+        db.time_ms = tracing::field::Empty,
+        otel.status_code = tracing::field::Empty,
+    )
+}
+
+/// Middleware recording error responses on the [`request_span`] and in
+/// [`HTTP_ERRORS_TOTAL`]; only server errors mark the span as failed
+pub async fn record_error_outcome(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_owned(), |path| path.as_str().to_owned());
+    let response = next.run(request).await;
+    if let Some(outcome) = response.extensions().get::<ErrorOutcome>() {
+        let span = tracing::Span::current();
+        span.record("error.code", outcome.code);
+        if let Some(kind) = outcome.kind {
+            span.record("error.kind", kind);
+        }
+        if let Some(entity) = &outcome.entity {
+            span.record("entity", entity.as_str());
+        }
+        if response.status().is_server_error() {
+            span.record("otel.status_code", "ERROR");
+        }
+        metrics::counter!(HTTP_ERRORS_TOTAL, "code" => outcome.code, "route" => route).increment(1);
+    }
+    response
+}
+
+/// Response extension marking a `SERVICE_BUSY` error, picked up by [`busy_retry_after`]
+#[derive(Debug, Clone, Copy)]
+struct ServiceBusy;
+
+/// Middleware adding `Retry-After: {retry_after_secs}` to `SERVICE_BUSY` responses.
+pub async fn busy_retry_after(
+    State(retry_after_secs): State<u64>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    if response.extensions().get::<ServiceBusy>().is_some() {
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    }
+    response
+}
+
+/// Soft validation warning reported next to a successful response body
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiWarning {
+    /// Warning code
+    pub code: &'static str,
+    /// Warning message
+    pub message: String,
+    /// Request field the warning is about
+    pub field: &'static str,
+}
+
+impl From<DomainWarning> for ApiWarning {
+    fn from(warning: DomainWarning) -> Self {
+        Self { code: warning.code, message: warning.message, field: warning.field }
+    }
+}
+
+/// Response body extended with a `warnings` array, omitted when there are none
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithWarnings<T> {
+    /// The regular response body
+    #[serde(flatten)]
+    pub body: T,
+    /// Warnings produced by soft validation rules
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ApiWarning>,
+}
+
+impl<T> WithWarnings<T> {
+    /// Wrap `body` with the given domain warnings
+    pub fn new(body: T, warnings: Vec<DomainWarning>) -> Self {
+        Self { body, warnings: warnings.into_iter().map(Into::into).collect() }
+    }
+}
+
+/// JSON body extractor that rejects with an [`ApiError`] instead of axum's plain-text body
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ApiJson<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        json_body(req, state, &[JSON]).await.map(Self)
+    }
+}
+
+/// JSON body of `req`, refusing another `Content-Type` with the `accepted` ones listed
+async fn json_body<S, T>(req: Request, state: &S, accepted: &[&str]) -> Result<T, ApiError>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    let content_type = req.headers().get(header::CONTENT_TYPE).cloned();
+    let rejected = |rejection| match rejection {
+        JsonRejection::MissingJsonContentType(_) => {
+            ApiError::unsupported_media_type(accepted, content_type.as_ref())
+        }
+        rejection => ApiError::from(rejection),
+    };
+    if FieldCasing::current() == FieldCasing::Snake {
+        let Json(value) =
+            Json::<serde_json::Value>::from_request(req, state).await.map_err(rejected)?;
+        return decode_legacy(value, "JSON");
+    }
+    let Json(value) = Json::<T>::from_request(req, state).await.map_err(rejected)?;
+    Ok(value)
+}
+
+/// `value`, a body in the legacy `snake_case`, decoded into the camelCase DTO `T`
+fn decode_legacy<T: serde::de::DeserializeOwned>(
+    value: serde_json::Value,
+    format: &str,
+) -> Result<T, ApiError> {
+    serde_json::from_value(casing::camel_keys(value)).map_err(|e| {
+        let message = format!("Failed to deserialize the {format} body: {e}");
+        ApiError::new(StatusCode::BAD_REQUEST, "INVALID_BODY", message)
+    })
+}
+
+/// Query string extractor that rejects with an [`ApiError`] instead of axum's
+/// plain-text body
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiQuery<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for ApiQuery<T>
+where
+    Query<T>: FromRequestParts<S, Rejection = QueryRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
+
+/// Path parameters extractor that rejects with an [`ApiError`] instead of axum's
+/// plain-text body
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiPath<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for ApiPath<T>
+where
+    Path<T>: FromRequestParts<S, Rejection = PathRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
+
+/// `?return=` of a deletion, after the `return` preference of RFC 7240
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReturnPreference {
+    /// `204 No Content`
+    #[default]
+    Minimal,
+    /// `200 OK` with the entity as it was deleted
+    Representation,
+}
+
+/// `Content-Type` of JSON bodies
+pub const JSON: &str = "application/json";
+
+/// `Content-Type` of `MessagePack` bodies, negotiated by [`Negotiated`] when built with
+/// the `msgpack` feature
+pub const MSGPACK: &str = "application/msgpack";
+
+/// Media types [`Negotiated`] bodies are read from
+#[cfg(feature = "msgpack")]
+const NEGOTIATED_TYPES: &[&str] = &[JSON, MSGPACK];
+#[cfg(not(feature = "msgpack"))]
+const NEGOTIATED_TYPES: &[&str] = &[JSON];
+
+/// Format [`Negotiated`] bodies are encoded in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyFormat {
+    /// `application/json`, the default
+    #[default]
+    Json,
+    /// `application/msgpack`, with maps keyed by field name like JSON objects
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+}
+
+tokio::task_local! {
+    static RESPONSE_FORMAT: BodyFormat;
+}
+
+impl BodyFormat {
+    /// Format asked for by `Accept`: `MessagePack` when it lists `application/msgpack`
+    /// (with a non-zero `q`), otherwise JSON
+    #[must_use]
+    pub fn accepted(headers: &HeaderMap) -> Self {
+        #[cfg(feature = "msgpack")]
+        if media_types(headers, header::ACCEPT).any(|(essence, params)| {
+            let refused = params
+                .split(';')
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .any(|q| q.parse::<f32>().is_ok_and(|q| q <= 0.0));
+            essence.eq_ignore_ascii_case(MSGPACK) && !refused
+        }) {
+            return Self::MessagePack;
+        }
+        // JSON is the only format without the msgpack feature
+        #[cfg(not(feature = "msgpack"))]
+        let _ = headers;
+        Self::Json
+    }
+
+    /// Format responses of the current request are encoded in; JSON outside
+    /// [`negotiate_format`]
+    #[must_use]
+    pub fn current() -> Self {
+        RESPONSE_FORMAT.try_with(|format| *format).unwrap_or_default()
+    }
+}
+
+/// Media types listed in `name` as `(essence, parameters)`
+#[cfg(feature = "msgpack")]
+fn media_types(headers: &HeaderMap, name: HeaderName) -> impl Iterator<Item = (&str, &str)> {
+    headers
+        .get_all(name)
+        .into_iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|media| media.split_once(';').unwrap_or((media, "")))
+        .map(|(essence, params)| (essence.trim(), params))
+}
+
+/// Body encoded in the format negotiated for the request, JSON unless `MessagePack`
+/// was asked for
+///
+/// As an extractor it decodes the request body by its `Content-Type`, rejecting a
+/// type other than JSON (or `application/msgpack`) with 415 like [`ApiJson`]. As a
+/// response it encodes the body in the [`BodyFormat::current`] format, which
+/// [`negotiate_format`] picks from `Accept`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Negotiated<T>(pub T);
+
+impl<S, T> FromRequest<S> for Negotiated<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        #[cfg(feature = "msgpack")]
+        if media_types(req.headers(), header::CONTENT_TYPE)
+            .any(|(essence, _)| essence.eq_ignore_ascii_case(MSGPACK))
+        {
+            let bytes = axum::body::Bytes::from_request(req, state)
+                .await
+                .map_err(|e| ApiError::from(JsonRejection::BytesRejection(e)))?;
+            let invalid = |e: rmp_serde::decode::Error| {
+                let message = format!("Failed to deserialize the MessagePack body: {e}");
+                ApiError::new(StatusCode::BAD_REQUEST, "INVALID_BODY", message)
+            };
+            if FieldCasing::current() == FieldCasing::Snake {
+                let value = rmp_serde::from_slice(&bytes).map_err(invalid)?;
+                return decode_legacy(value, "MessagePack").map(Self);
+            }
+            return rmp_serde::from_slice(&bytes).map(Self).map_err(invalid);
+        }
+        json_body(req, state, NEGOTIATED_TYPES).await.map(Self)
+    }
+}
+
+impl<T: Serialize> Negotiated<T> {
+    /// The body, encoded in the [`BodyFormat::current`] format, with `status`
+    ///
+    /// Use this rather than a `(StatusCode, Negotiated<T>)` tuple: the tuple would
+    /// overwrite the 500 of a body that failed to encode with its success status.
+    pub fn with_status(self, status: StatusCode) -> Response {
+        match BodyFormat::current() {
+            BodyFormat::Json => json_response(status, &self.0),
+            #[cfg(feature = "msgpack")]
+            BodyFormat::MessagePack => {
+                let encoded = encode_body(&self.0, "MessagePack", |value| {
+                    match FieldCasing::current() {
+                        FieldCasing::Camel => rmp_serde::to_vec_named(value),
+                        FieldCasing::Snake => rmp_serde::to_vec_named(&legacy_value(value)?),
+                    }
+                    .map_err(|e| e.to_string())
+                });
+                match encoded {
+                    Ok(bytes) => (status, [(header::CONTENT_TYPE, MSGPACK)], bytes).into_response(),
+                    Err(e) => e.into_response(),
+                }
+            }
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Negotiated<T> {
     fn into_response(self) -> Response {
-        (self.status, Json(self)).into_response()
+        self.with_status(StatusCode::OK)
+    }
+}
+
+/// `value` as a JSON response with `status`, or a 500 `INTERNAL_ERROR` if it cannot
+/// be serialized
+///
+/// The body is serialized in full before the response exists, as axum's `Json` does,
+/// so this costs no more than it: one buffer the size of the body. What it adds is
+/// that a `Serialize` impl returning an error or panicking becomes an [`ApiError`],
+/// logged with its cause, rather than a plain-text 500 or a dropped connection.
+/// Streamed bodies such as `GET /tasks/export` build their response by hand and
+/// handle the failures of each chunk themselves.
+pub fn json_response<T: Serialize + ?Sized>(status: StatusCode, value: &T) -> Response {
+    let encoded = encode_body(value, "JSON", |value| {
+        match FieldCasing::current() {
+            FieldCasing::Camel => serde_json::to_vec(value),
+            FieldCasing::Snake => serde_json::to_vec(&legacy_value(value)?),
+        }
+        .map_err(|e| e.to_string())
+    });
+    match encoded {
+        Ok(bytes) => {
+            let content_type = HeaderValue::from_static("application/json");
+            (status, [(header::CONTENT_TYPE, content_type)], bytes).into_response()
+        }
+        Err(e) => e.into_response(),
     }
 }
 
+/// `value` as a JSON value in the legacy `snake_case`
+fn legacy_value<T: Serialize + ?Sized>(value: &T) -> Result<serde_json::Value, String> {
+    serde_json::to_value(value).map(casing::snake_keys).map_err(|e| e.to_string())
+}
+
+/// `value` encoded by `encode`, with failures and panics turned into a 500
+fn encode_body<T: ?Sized>(
+    value: &T,
+    format: &str,
+    encode: impl FnOnce(&T) -> Result<Vec<u8>, String>,
+) -> Result<Vec<u8>, ApiError> {
+    // Nothing outlives a panic but its message: the half-written buffer is dropped
+    let encoded = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| encode(value)));
+    let reason = match encoded {
+        Ok(Ok(bytes)) => return Ok(bytes),
+        Ok(Err(e)) => e,
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| (*message).to_owned())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            format!("serializer panicked: {message}")
+        }
+    };
+    let message = format!("Failed to encode the response as {format}: {reason}");
+    Err(DomainError::Unexpected(message).into())
+}
+
+/// Middleware encoding the [`Negotiated`] responses of the request, errors included,
+/// in the format its `Accept` header asks for
+pub async fn negotiate_format(request: Request, next: Next) -> Response {
+    let format = BodyFormat::accepted(request.headers());
+    let mut response = RESPONSE_FORMAT.scope(format, next.run(request)).await;
+    response.headers_mut().append(header::VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// Middleware giving a request body sent without a `Content-Type` the JSON one, so
+/// it is read as JSON rather than refused with 415
+///
+/// Enabled via `ASSUME_JSON_CONTENT_TYPE`, the default.
+pub async fn assume_json_content_type(mut request: Request, next: Next) -> Response {
+    let untyped = !request.headers().contains_key(header::CONTENT_TYPE);
+    if untyped && request.body().size_hint().exact() != Some(0) {
+        request.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(JSON));
+    }
+    next.run(request).await
+}
+
+/// Middleware restoring the pre-422 behavior: domain validation errors render as 400.
+///
+/// Enabled via `LEGACY_VALIDATION_STATUS=true` for consumers that still expect 400.
+pub async fn legacy_validation_status(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    if response.status() == StatusCode::UNPROCESSABLE_ENTITY {
+        *response.status_mut() = StatusCode::BAD_REQUEST;
+    }
+    response
+}
+
+/// Middleware rejecting request bodies declared with a `Content-Encoding` other than
+/// `identity` or one of `accepted` with 415 `UNSUPPORTED_ENCODING`.
+///
+/// Runs ahead of request decompression so undecodable bodies never reach the JSON
+/// extractor, where they would surface as malformed JSON.
+pub async fn reject_unsupported_encoding(
+    State(accepted): State<&'static [&'static str]>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let unsupported = request
+        .headers()
+        .get_all(header::CONTENT_ENCODING)
+        .iter()
+        .flat_map(|value| value.to_str().unwrap_or("?").split(','))
+        .map(str::trim)
+        .find(|coding| {
+            !coding.eq_ignore_ascii_case("identity")
+                && !accepted.iter().any(|a| coding.eq_ignore_ascii_case(a))
+        })
+        .map(str::to_owned);
+    if let Some(coding) = unsupported {
+        let supported = std::iter::once("identity").chain(accepted.iter().copied());
+        let message = format!(
+            "Unsupported Content-Encoding '{coding}'; supported encodings: {}",
+            supported.collect::<Vec<_>>().join(", ")
+        );
+        return ApiError::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, "UNSUPPORTED_ENCODING", message)
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Security headers sent with every response
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders(Arc<[(HeaderName, HeaderValue)]>);
+
+impl SecurityHeaders {
+    /// Headers configured in `config`; an empty value disables a header, and
+    /// `Strict-Transport-Security` is only sent with `behind_tls_proxy`
+    ///
+    /// # Errors
+    /// Fails when a configured value is not a valid header value.
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let hsts =
+            if config.behind_tls_proxy { config.strict_transport_security.as_str() } else { "" };
+        let headers = [
+            (
+                "X_CONTENT_TYPE_OPTIONS",
+                header::X_CONTENT_TYPE_OPTIONS,
+                config.content_type_options.as_str(),
+            ),
+            ("X_FRAME_OPTIONS", header::X_FRAME_OPTIONS, config.frame_options.as_str()),
+            ("REFERRER_POLICY", header::REFERRER_POLICY, config.referrer_policy.as_str()),
+            ("STRICT_TRANSPORT_SECURITY", header::STRICT_TRANSPORT_SECURITY, hsts),
+        ]
+        .into_iter()
+        .filter(|(_, _, value)| !value.is_empty())
+        .map(|(key, name, value)| {
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| anyhow::anyhow!("Invalid {key}={value:?}: {e}"))?;
+            Ok((name, value))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self(headers.into()))
+    }
+}
+
+/// Middleware adding the [`SecurityHeaders`] a handler did not set itself.
+///
+/// Layered outermost, so error responses, timeouts and unmatched routes carry them too.
+pub async fn security_headers(
+    State(headers): State<SecurityHeaders>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    for (name, value) in headers.0.iter() {
+        response.headers_mut().entry(name).or_insert_with(|| value.clone());
+    }
+    response
+}
+
+/// Middleware scoping the rest of the request with a [`Deadline`] `budget` from now.
+///
+/// Repository calls race against it (see `database::run_query`), so work is abandoned
+/// and connections released once the request can no longer succeed. The deadline is
+/// also stored in the request extensions.
+pub async fn propagate_deadline(
+    State(budget): State<Duration>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let deadline = Deadline::after(budget);
+    request.extensions_mut().insert(deadline);
+    deadline.scope(next.run(request)).await
+}
+
 /// Health check response
 #[derive(Serialize)]
 pub struct Health {
@@ -48,6 +757,269 @@ pub struct Health {
 }
 
 /// Health check handler
-pub async fn health_check() -> Json<Health> {
-    Json(Health { status: "ok" })
+pub async fn health_check() -> Response {
+    json_response(StatusCode::OK, &Health { status: "ok" })
+}
+
+/// Every cargo feature of the crate, and whether the binary was built with it
+const CARGO_FEATURES: &[(&str, bool)] = &[
+    ("client", cfg!(feature = "client")),
+    ("consumers", cfg!(feature = "consumers")),
+    ("dashboard", cfg!(feature = "dashboard")),
+    ("dev-tools", cfg!(feature = "dev-tools")),
+    ("graphql", cfg!(feature = "graphql")),
+    ("grpc", cfg!(feature = "grpc")),
+    ("msgpack", cfg!(feature = "msgpack")),
+    ("s3", cfg!(feature = "s3")),
+    ("smtp", cfg!(feature = "smtp")),
+    ("testing", cfg!(feature = "testing")),
+];
+
+/// Cargo features the binary was built with
+#[must_use]
+pub fn cargo_features() -> Vec<&'static str> {
+    CARGO_FEATURES.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect()
+}
+
+/// What `GET /health/details` reports beside the status
+#[derive(Debug, Clone)]
+pub struct RuntimeInfo {
+    /// When the application state was built
+    pub started_at: Instant,
+    /// Names of the enabled application features
+    pub features: Vec<&'static str>,
+}
+
+/// Detailed health check response
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthDetails {
+    /// Service status
+    pub status: &'static str,
+    /// Crate version of the running build
+    pub version: &'static str,
+    /// Whole seconds since startup
+    pub uptime_seconds: u64,
+    /// Enabled application features, e.g. `user`
+    pub features: Vec<&'static str>,
+    /// Cargo features compiled in, e.g. `graphql`
+    pub cargo_features: Vec<&'static str>,
+}
+
+/// Detailed health check handler
+pub async fn health_details(State(info): State<RuntimeInfo>) -> Response {
+    let details = HealthDetails {
+        status: "ok",
+        version: env!("CARGO_PKG_VERSION"),
+        uptime_seconds: info.started_at.elapsed().as_secs(),
+        features: info.features,
+        cargo_features: cargo_features(),
+    };
+    json_response(StatusCode::OK, &details)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_features_should_list_every_feature_of_the_manifest() {
+        let manifest = include_str!("../../../Cargo.toml");
+        let mut declared: Vec<&str> = manifest
+            .lines()
+            .skip_while(|line| line.trim() != "[features]")
+            .skip(1)
+            .take_while(|line| !line.starts_with('['))
+            .filter_map(|line| line.split_once('=').map(|(name, _)| name.trim()))
+            .filter(|name| !name.is_empty() && *name != "default")
+            .collect();
+        declared.sort_unstable();
+        let listed: Vec<&str> = CARGO_FEATURES.iter().map(|(name, _)| *name).collect();
+        assert_eq!(listed, declared);
+    }
+
+    #[test]
+    fn validation_error_should_map_to_unprocessable_entity() {
+        let error = ApiError::from(DomainError::Validation("Title cannot be empty".into()));
+        assert_eq!(error.code, "VALIDATION_ERROR");
+        assert_eq!(error.into_response().status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn custom_error_should_render_its_code_status_and_details() {
+        let error = ApiError::from(DomainError::Custom {
+            code: "QUOTA_EXCEEDED",
+            status_hint: ErrorStatus::LimitExceeded,
+            message: "At most 3 tasks".into(),
+            details: serde_json::json!({ "limit": 3 }),
+        });
+        assert_eq!((error.code, error.kind), ("QUOTA_EXCEEDED", Some("custom")));
+        let expected = serde_json::json!({
+            "code": "QUOTA_EXCEEDED",
+            "message": "At most 3 tasks",
+            "details": {"limit": 3},
+        });
+        assert_eq!(serde_json::to_value(&error).ok(), Some(expected));
+        assert_eq!(error.into_response().status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Without details the field is left out
+        let error = ApiError::from(DomainError::Custom {
+            code: "SNAPSHOT_EXPIRED",
+            status_hint: ErrorStatus::Gone,
+            message: "Snapshot expired".into(),
+            details: serde_json::Value::Null,
+        });
+        let expected = serde_json::json!({"code": "SNAPSHOT_EXPIRED", "message": "Snapshot expired"});
+        assert_eq!(serde_json::to_value(&error).ok(), Some(expected));
+        assert_eq!(error.into_response().status(), StatusCode::GONE);
+    }
+
+    /// Status, code and body message of an error of each variant; `None` for messages
+    /// rendered as they are. Without a `_` arm a new variant fails to compile here
+    /// until its rendering is decided, and it then needs an example in the test below.
+    fn expected(e: &DomainError) -> (StatusCode, &'static str, Option<&'static str>) {
+        match e {
+            DomainError::Validation(_) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "VALIDATION_ERROR", None)
+            }
+            DomainError::NotFound(_) => (StatusCode::NOT_FOUND, "NOT_FOUND", None),
+            DomainError::AlreadyExists(_) => (StatusCode::CONFLICT, "ALREADY_EXISTS", None),
+            DomainError::Conflict(_) => (StatusCode::CONFLICT, "CONFLICT", None),
+            DomainError::Expired(_) => (StatusCode::GONE, "GONE", None),
+            DomainError::Custom { code, .. } => (StatusCode::CONFLICT, code, None),
+            DomainError::Unavailable(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "SERVICE_BUSY", Some("Service busy, retry later"))
+            }
+            DomainError::Infrastructure(_) | DomainError::Unexpected(_) => {
+                let redacted = Some("Internal server error");
+                (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", redacted)
+            }
+        }
+    }
+
+    #[test]
+    fn every_domain_error_should_map_to_its_status_code_and_message() {
+        let errors = [
+            DomainError::Validation("Title cannot be empty".into()),
+            DomainError::not_found("Task"),
+            DomainError::already_exists("User", "email"),
+            DomainError::Conflict("Task is already completed".into()),
+            DomainError::Expired("Token has expired".into()),
+            DomainError::Custom {
+                code: "HAS_DEPENDENTS",
+                status_hint: ErrorStatus::Conflict,
+                message: "User still owns tasks".into(),
+                details: serde_json::Value::Null,
+            },
+            DomainError::Unavailable("pool exhausted".into()),
+            DomainError::Infrastructure("connection to 10.0.0.5 refused".into()),
+            DomainError::Unexpected("invariant broken".into()),
+        ];
+        let mut kinds: Vec<_> = errors.iter().map(DomainError::kind).collect();
+        kinds.sort_unstable();
+        kinds.dedup();
+        assert_eq!(kinds.len(), errors.len(), "one example per variant");
+
+        for error in errors {
+            let (status, code, redacted) = expected(&error);
+            let kind = error.kind();
+            let message = redacted.map_or_else(|| error.to_string(), str::to_owned);
+            let api = ApiError::from(error);
+            assert_eq!((api.code, api.status, &api.message), (code, status, &message), "{kind}");
+            assert_eq!(api.kind, Some(kind));
+        }
+    }
+
+    #[test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    fn internal_errors_should_be_logged_but_hidden_from_the_body() {
+        use crate::test_support::Logs;
+
+        for (error, secret) in [
+            (DomainError::Infrastructure("connection to 10.0.0.5 refused".into()), "10.0.0.5"),
+            (DomainError::Unexpected("balance went negative".into()), "balance went negative"),
+        ] {
+            let logs = Logs::default();
+            let subscriber = logs.subscriber();
+            let api = tracing::subscriber::with_default(subscriber, || ApiError::from(error));
+            let body = serde_json::to_string(&api).expect("serializable");
+            assert!(!body.contains(secret), "{body}");
+            let logged = logs.contents();
+            assert!(logged.contains(secret), "{logged}");
+        }
+    }
+
+    #[test]
+    fn expired_should_map_to_gone() {
+        let error = ApiError::from(DomainError::Expired("Token has expired".into()));
+        assert_eq!(error.code, "GONE");
+        assert_eq!(error.into_response().status(), StatusCode::GONE);
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn unavailable_should_render_503_with_retry_after() {
+        use tower::ServiceExt;
+        let app = axum::Router::new()
+            .route(
+                "/",
+                axum::routing::get(|| async {
+                    ApiError::from(DomainError::Unavailable("pool exhausted".into()))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(7, busy_retry_after));
+        let request = Request::get("/").body(axum::body::Body::empty()).expect("request");
+        let response = app.oneshot(request).await.expect("response");
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let retry_after = response.headers().get(header::RETRY_AFTER);
+        assert_eq!(retry_after.map(HeaderValue::as_bytes), Some(&b"7"[..]));
+    }
+
+    /// Response DTO whose `Serialize` impl fails, or panics with `panics`
+    struct Unserializable {
+        panics: bool,
+    }
+
+    impl Serialize for Unserializable {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            assert!(!self.panics, "field went missing");
+            Err(serde::ser::Error::custom("field went missing"))
+        }
+    }
+
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn rendered(response: Response) -> (StatusCode, serde_json::Value) {
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("body");
+        (status, serde_json::from_slice(&body).expect("JSON body"))
+    }
+
+    #[tokio::test]
+    async fn unserializable_bodies_should_render_500_api_errors_whatever_the_status() {
+        for panics in [false, true] {
+            let responses = [
+                Negotiated(Unserializable { panics }).into_response(),
+                Negotiated(Unserializable { panics }).with_status(StatusCode::CREATED),
+                json_response(StatusCode::ACCEPTED, &Unserializable { panics }),
+            ];
+            for response in responses {
+                let (status, body) = rendered(response).await;
+                assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "panics: {panics}");
+                assert_eq!(body["code"], "INTERNAL_ERROR");
+            }
+        }
+        let (status, body) = rendered(json_response(StatusCode::ACCEPTED, &[1, 2])).await;
+        assert_eq!((status, body), (StatusCode::ACCEPTED, serde_json::json!([1, 2])));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn unserializable_message_pack_bodies_should_render_500() {
+        let response = RESPONSE_FORMAT
+            .scope(BodyFormat::MessagePack, async {
+                Negotiated(Unserializable { panics: true }).with_status(StatusCode::CREATED)
+            })
+            .await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
 }
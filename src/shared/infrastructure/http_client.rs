@@ -0,0 +1,346 @@
+//! Outbound HTTP client shared by integrations
+//!
+//! Integrations talk to [`OutboundHttp`] instead of configuring their own client, and
+//! deliver JSON through [`retrying_post_json`], which retries transient failures with
+//! bounded exponential backoff. Tests inject a recording fake implementing the trait.
+
+use axum::http::{header, HeaderMap, StatusCode};
+use reqwest::dns::Resolve;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Instrument;
+
+/// `User-Agent` sent with every outbound request
+pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Failure of an outbound request
+#[derive(Debug, thiserror::Error)]
+pub enum OutboundError {
+    /// The connection could not be established (retried)
+    #[error("connection failed: {0}")]
+    Connect(String),
+    /// The request failed after connecting, e.g. timed out (not retried)
+    #[error("request failed: {0}")]
+    Request(String),
+    /// The server answered with a non-success status
+    #[error("unexpected response status {0}")]
+    Status(StatusCode),
+    /// The body could not be serialized
+    #[error("invalid request body: {0}")]
+    Body(String),
+}
+
+/// A single outbound HTTP exchange, without retries
+#[async_trait::async_trait]
+pub trait OutboundHttp: Send + Sync {
+    /// POST `body` (already JSON-encoded) to `url` with `headers` added, e.g. a
+    /// signature, and return the response status.
+    ///
+    /// # Errors
+    /// [`OutboundError::Connect`] or [`OutboundError::Request`] when no response arrives.
+    async fn post_json_with_headers(
+        &self,
+        url: &str,
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<StatusCode, OutboundError>;
+
+    /// POST `body` (already JSON-encoded) to `url` and return the response status.
+    ///
+    /// # Errors
+    /// As [`Self::post_json_with_headers`].
+    async fn post_json(&self, url: &str, body: Vec<u8>) -> Result<StatusCode, OutboundError> {
+        self.post_json_with_headers(url, HeaderMap::new(), body).await
+    }
+}
+
+/// Timeouts and pool limits of the outbound client
+#[derive(Debug, Clone)]
+pub struct HttpClientConfig {
+    /// Time allowed to establish a connection
+    pub connect_timeout: Duration,
+    /// Time allowed for a whole request, response included
+    pub request_timeout: Duration,
+    /// Idle connections kept per host
+    pub max_idle_per_host: usize,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10),
+            max_idle_per_host: 8,
+        }
+    }
+}
+
+/// [`OutboundHttp`] backed by a configured `reqwest` client
+#[derive(Debug, Clone)]
+pub struct ReqwestHttp {
+    client: reqwest::Client,
+}
+
+impl ReqwestHttp {
+    /// Build a client from `config`, identifying as [`USER_AGENT`]
+    ///
+    /// Redirects are not followed: a 3xx answer is returned as it is, so a request
+    /// goes nowhere but to the URL it was sent to.
+    ///
+    /// # Errors
+    /// Fails when the TLS backend cannot be initialized.
+    pub fn new(config: &HttpClientConfig) -> Result<Self, OutboundError> {
+        Self::build(Self::builder(config))
+    }
+
+    /// Build a client as [`Self::new`] does, resolving host names through `resolver`
+    ///
+    /// # Errors
+    /// As [`Self::new`].
+    pub fn with_resolver<R: Resolve + 'static>(
+        config: &HttpClientConfig,
+        resolver: Arc<R>,
+    ) -> Result<Self, OutboundError> {
+        Self::build(Self::builder(config).dns_resolver(resolver))
+    }
+
+    fn builder(config: &HttpClientConfig) -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
+            .pool_max_idle_per_host(config.max_idle_per_host)
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent(USER_AGENT)
+    }
+
+    fn build(builder: reqwest::ClientBuilder) -> Result<Self, OutboundError> {
+        let client = builder.build().map_err(|e| OutboundError::Request(e.to_string()))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait::async_trait]
+impl OutboundHttp for ReqwestHttp {
+    async fn post_json_with_headers(
+        &self,
+        url: &str,
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<StatusCode, OutboundError> {
+        let response = self
+            .client
+            .post(url)
+            .headers(headers)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_connect() {
+                    OutboundError::Connect(e.to_string())
+                } else {
+                    OutboundError::Request(e.to_string())
+                }
+            })?;
+        Ok(response.status())
+    }
+}
+
+/// How often and how patiently [`retrying_post_json`] retries
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first one included
+    pub max_attempts: u32,
+    /// Backoff before the first retry; doubled for each further retry
+    pub base_delay: Duration,
+    /// Upper bound of a single backoff
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff after failed attempt `attempt` (1-based): exponential, capped at
+    /// `max_delay`, with the upper half jittered so retrying clients spread out
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self.base_delay.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        let capped = exponential.min(self.max_delay);
+        capped / 2 + capped.mul_f64(fastrand::f64() / 2.0)
+    }
+}
+
+/// POST `body` as JSON to `url`, retrying connect errors and 5xx responses.
+///
+/// Each attempt runs in its own `outbound_post` tracing span. Other failures,
+/// including 4xx responses, are returned immediately.
+///
+/// # Errors
+/// The last attempt's error once `policy.max_attempts` is exhausted, or the first
+/// non-retryable error.
+pub async fn retrying_post_json<T: Serialize + ?Sized>(
+    http: &dyn OutboundHttp,
+    url: &str,
+    body: &T,
+    policy: &RetryPolicy,
+) -> Result<StatusCode, OutboundError> {
+    let body = serde_json::to_vec(body).map_err(|e| OutboundError::Body(e.to_string()))?;
+    let mut attempt = 1;
+    loop {
+        let span = tracing::info_span!("outbound_post", url, attempt);
+        let result = async {
+            let result = match http.post_json(url, body.clone()).await {
+                Ok(status) if status.is_success() => Ok(status),
+                Ok(status) => Err(OutboundError::Status(status)),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = &result {
+                tracing::warn!("Outbound request failed: {e}");
+            }
+            result
+        }
+        .instrument(span)
+        .await;
+
+        let retryable = match &result {
+            Err(OutboundError::Connect(_)) => true,
+            Err(OutboundError::Status(status)) => status.is_server_error(),
+            _ => false,
+        };
+        if !retryable || attempt >= policy.max_attempts {
+            return result;
+        }
+        tokio::time::sleep(policy.backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Fake answering from a script and recording each request
+    #[derive(Default)]
+    struct RecordingHttp {
+        script: Mutex<VecDeque<Result<StatusCode, OutboundError>>>,
+        requests: Mutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    impl RecordingHttp {
+        fn scripted(script: impl IntoIterator<Item = Result<StatusCode, OutboundError>>) -> Self {
+            Self { script: Mutex::new(script.into_iter().collect()), ..Self::default() }
+        }
+
+        fn attempts(&self) -> usize {
+            self.requests.lock().map(|requests| requests.len()).unwrap_or_default()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl OutboundHttp for RecordingHttp {
+        async fn post_json_with_headers(
+            &self,
+            url: &str,
+            _: HeaderMap,
+            body: Vec<u8>,
+        ) -> Result<StatusCode, OutboundError> {
+            if let Ok(mut requests) = self.requests.lock() {
+                requests.push((url.to_owned(), body));
+            }
+            let next = self.script.lock().ok().and_then(|mut script| script.pop_front());
+            next.unwrap_or(Err(OutboundError::Request("script exhausted".into())))
+        }
+    }
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy { max_attempts, ..RetryPolicy::default() }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_retry_connect_errors_and_5xx_then_succeed() {
+        let http = RecordingHttp::scripted([
+            Err(OutboundError::Connect("refused".into())),
+            Ok(StatusCode::SERVICE_UNAVAILABLE),
+            Ok(StatusCode::NO_CONTENT),
+        ]);
+        let result = retrying_post_json(&http, "http://hook", &[1, 2], &policy(4)).await;
+        assert!(matches!(result, Ok(StatusCode::NO_CONTENT)), "{result:?}");
+        assert_eq!(http.attempts(), 3);
+        let requests = http.requests.lock().map(|r| r.clone()).unwrap_or_default();
+        assert!(requests.iter().all(|(url, body)| url == "http://hook" && body == b"[1,2]"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_give_up_after_max_attempts() {
+        let http = RecordingHttp::scripted((0..5).map(|_| Ok(StatusCode::BAD_GATEWAY)));
+        let result = retrying_post_json(&http, "http://hook", "event", &policy(3)).await;
+        assert!(matches!(result, Err(OutboundError::Status(StatusCode::BAD_GATEWAY))));
+        assert_eq!(http.attempts(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_not_retry_client_errors_or_timeouts() {
+        let http = RecordingHttp::scripted([Ok(StatusCode::BAD_REQUEST)]);
+        let result = retrying_post_json(&http, "http://hook", "event", &policy(3)).await;
+        assert!(matches!(result, Err(OutboundError::Status(StatusCode::BAD_REQUEST))));
+        assert_eq!(http.attempts(), 1);
+
+        let http = RecordingHttp::scripted([Err(OutboundError::Request("timed out".into()))]);
+        let result = retrying_post_json(&http, "http://hook", "event", &policy(3)).await;
+        assert!(matches!(result, Err(OutboundError::Request(_))));
+        assert_eq!(http.attempts(), 1);
+    }
+
+    #[test]
+    fn backoff_should_grow_exponentially_within_bounds() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        for (attempt, ceiling) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (30, 1000)] {
+            let ceiling = Duration::from_millis(ceiling);
+            let delay = policy.backoff(attempt);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "attempt {attempt}: {delay:?}");
+        }
+    }
+
+    #[tokio::test]
+    #[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+    async fn reqwest_client_should_post_json_with_user_agent() {
+        use axum::{routing::post, Router};
+        let app = Router::new().route(
+            "/hook",
+            post(|headers: axum::http::HeaderMap, body: String| async move {
+                let agent = headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok());
+                let typed = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+                if agent == Some(USER_AGENT) && typed == Some("application/json") && body == "{}" {
+                    StatusCode::ACCEPTED
+                } else {
+                    StatusCode::BAD_REQUEST
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("http://{}/hook", listener.local_addr().expect("addr"));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let http = ReqwestHttp::new(&HttpClientConfig::default()).expect("client");
+        let status = http.post_json(&url, b"{}".to_vec()).await.expect("response");
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let closed = "http://127.0.0.1:1/hook";
+        let error = http.post_json(closed, b"{}".to_vec()).await;
+        assert!(matches!(error, Err(OutboundError::Connect(_))), "{error:?}");
+    }
+}
@@ -0,0 +1,321 @@
+//! Timing of repository calls and use cases
+//!
+//! The instrumented repository decorators wrap any implementation of their repository
+//! trait, so they compose with other decorators in either order. The order decides
+//! what is measured: wrapped directly around the `PostgreSQL` repository they time
+//! database round trips only; wrapped around a caching decorator they time calls as
+//! the application sees them, cache hits included.
+//!
+//! Calls made while handling a request are also counted per request by
+//! [`count_queries`], which flags requests making more calls than expected (an N+1
+//! query pattern, typically).
+//!
+//! Use cases called by the HTTP handlers are timed by [`budgeted`] against their
+//! budget, the request timeout unless configured per use case, to warn of slow use
+//! cases before requests start timing out.
+
+use crate::shared::application::detached::DetachedTask;
+use crate::shared::domain::DomainError;
+use crate::shared::infrastructure::config::Config;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+/// Histogram of repository call durations in seconds, labelled with `entity`,
+/// `method` and `outcome` (`ok` or `error`)
+pub const REPOSITORY_CALL_SECONDS: &str = "repository_call_duration_seconds";
+
+/// Run a repository call inside a debug-level `repository` span and record its
+/// duration in [`REPOSITORY_CALL_SECONDS`]
+pub(crate) async fn timed<T>(
+    entity: &'static str,
+    method: &'static str,
+    call: impl Future<Output = Result<T, DomainError>>,
+) -> Result<T, DomainError> {
+    let started = Instant::now();
+    let result = call.instrument(tracing::debug_span!("repository", entity, method)).await;
+    record(entity, method, result.is_ok(), started.elapsed());
+    result
+}
+
+/// Pass a streaming repository call through, recording it as one call in
+/// [`REPOSITORY_CALL_SECONDS`] from its first poll until it ends, with an `error`
+/// outcome if any item failed
+///
+/// A stream dropped before its end is not recorded. Streams feeding a response body
+/// are read after [`count_queries`] returned, so they are not counted per request.
+pub(crate) fn timed_stream<'a, T: Send + 'a>(
+    entity: &'static str,
+    method: &'static str,
+    mut items: BoxStream<'a, Result<T, DomainError>>,
+) -> BoxStream<'a, Result<T, DomainError>> {
+    Box::pin(async_stream::stream! {
+        let started = Instant::now();
+        let mut ok = true;
+        while let Some(item) = items.next().await {
+            ok &= item.is_ok();
+            yield item;
+        }
+        record(entity, method, ok, started.elapsed());
+    })
+}
+
+fn record(entity: &'static str, method: &'static str, ok: bool, elapsed: Duration) {
+    let outcome = if ok { "ok" } else { "error" };
+    metrics::histogram!(
+        REPOSITORY_CALL_SECONDS,
+        "entity" => entity,
+        "method" => method,
+        "outcome" => outcome,
+    )
+    .record(elapsed.as_secs_f64());
+    // Outside a request (jobs, startup) there is nothing to count
+    let _ = REQUEST_QUERIES.try_with(|queries| queries.add(elapsed));
+}
+
+tokio::task_local! {
+    /// Repository calls of the request being handled, set by [`count_queries`]
+    static REQUEST_QUERIES: Arc<QueryStats>;
+}
+
+/// Count the repository calls of `writes` with those of the request detaching them,
+/// if any; the hook of
+/// [`DetachedWrites::propagating`](crate::shared::application::DetachedWrites::propagating)
+///
+/// Only the calls made before the request finished are counted.
+pub(crate) fn in_current_request(writes: DetachedTask) -> DetachedTask {
+    match REQUEST_QUERIES.try_with(Arc::clone) {
+        Ok(queries) => Box::pin(REQUEST_QUERIES.scope(queries, writes)),
+        Err(_) => writes,
+    }
+}
+
+/// Repository calls made while handling one request
+#[derive(Debug, Default)]
+struct QueryStats {
+    count: AtomicU64,
+    nanos: AtomicU64,
+}
+
+impl QueryStats {
+    fn add(&self, elapsed: Duration) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+/// Middleware counting the repository calls made by each request and their cumulative
+/// time, recorded as `db.queries` and `db.time_ms` on the request span
+///
+/// A request making more than `warn_at` calls is logged as a warning naming its route;
+/// 0 never warns. Calls are only counted by the instrumented decorators, so this is
+/// installed with `METRICS_DB` only, and calls made by spawned tasks are not counted,
+/// except for the detached writes of the request (see [`in_current_request`]).
+pub async fn count_queries(State(warn_at): State<u64>, request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_owned(), |path| path.as_str().to_owned());
+    let method = request.method().clone();
+    let queries = Arc::new(QueryStats::default());
+    let response = REQUEST_QUERIES.scope(Arc::clone(&queries), next.run(request)).await;
+    let count = queries.count.load(Ordering::Relaxed);
+    let db_time = Duration::from_nanos(queries.nanos.load(Ordering::Relaxed));
+    let db_time_ms = db_time.as_secs_f64() * 1000.0;
+    let span = tracing::Span::current();
+    span.record("db.queries", count);
+    span.record("db.time_ms", db_time_ms);
+    if warn_at > 0 && count > warn_at {
+        tracing::warn!(
+            %method,
+            route,
+            queries = count,
+            db_time_ms,
+            "{method} {route} made {count} repository calls (WARN_QUERY_COUNT={warn_at})"
+        );
+    } else {
+        tracing::debug!(%method, route, queries = count, db_time_ms, "Repository calls of request");
+    }
+    response
+}
+
+/// Histogram of use case durations as a fraction of their budget, labelled with
+/// `use_case`; samples above 1 outlasted their budget
+pub const USE_CASE_BUDGET_RATIO: &str = "use_case_budget_ratio";
+
+/// How long each use case may take, by use case name
+#[derive(Debug, Clone)]
+pub struct UseCaseBudgets {
+    default: Duration,
+    budgets: HashMap<String, Duration>,
+    warn_ratio: f64,
+}
+
+impl UseCaseBudgets {
+    /// Budgets of `default` for every use case, warning above `warn_ratio` of it; 0
+    /// never warns
+    #[must_use]
+    pub fn new(default: Duration, warn_ratio: f64) -> Self {
+        Self { default, budgets: HashMap::new(), warn_ratio }
+    }
+
+    /// `self` with `budget` for the use case named `use_case`
+    #[must_use]
+    pub fn with_budget(mut self, use_case: impl Into<String>, budget: Duration) -> Self {
+        self.budgets.insert(use_case.into(), budget);
+        self
+    }
+
+    /// Budgets of `USE_CASE_BUDGETS_MS`, the request timeout for other use cases
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let budgets = Self::new(config.request_timeout(), config.budget_warn_ratio);
+        config.use_case_budgets_ms.iter().fold(budgets, |budgets, (use_case, millis)| {
+            budgets.with_budget(use_case, Duration::from_millis(*millis))
+        })
+    }
+
+    /// Budget of the use case named `use_case`
+    #[must_use]
+    pub fn of(&self, use_case: &str) -> Duration {
+        self.budgets.get(use_case).copied().unwrap_or(self.default)
+    }
+
+    fn record(&self, use_case: &'static str, elapsed: Duration) {
+        let budget = self.of(use_case);
+        let ratio = elapsed.as_secs_f64() / budget.as_secs_f64();
+        metrics::histogram!(USE_CASE_BUDGET_RATIO, "use_case" => use_case).record(ratio);
+        if self.warn_ratio > 0.0 && ratio >= self.warn_ratio {
+            let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+            let budget_ms = budget.as_secs_f64() * 1000.0;
+            tracing::warn!(
+                use_case,
+                elapsed_ms,
+                budget_ms,
+                "{use_case} took {:.0}% of its {budget_ms} ms budget",
+                ratio * 100.0
+            );
+        }
+    }
+}
+
+tokio::task_local! {
+    /// Budgets of the use cases of the request being handled, set by [`scope_budgets`]
+    static REQUEST_BUDGETS: Arc<UseCaseBudgets>;
+}
+
+/// Middleware making `budgets` those that [`budgeted`] measures the use cases of the
+/// request against
+pub async fn scope_budgets(
+    State(budgets): State<Arc<UseCaseBudgets>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    REQUEST_BUDGETS.scope(budgets, next.run(request)).await
+}
+
+/// Run the use case call `call` inside a debug-level `use_case` span and record its
+/// duration against the budget of `use_case` in [`USE_CASE_BUDGET_RATIO`], warning
+/// when it took more than the warning ratio of its budget
+///
+/// Only measured; a use case over budget is not cancelled, the request timeout is.
+pub(crate) async fn budgeted<F: Future>(use_case: &'static str, call: F) -> F::Output {
+    let started = tokio::time::Instant::now();
+    let output = call.instrument(tracing::debug_span!("use_case", use_case)).await;
+    // Outside a request (jobs, other APIs) there is no budget to measure against
+    let _ = REQUEST_BUDGETS.try_with(|budgets| budgets.record(use_case, started.elapsed()));
+    output
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::app::{build_router, AppState};
+    use crate::features::user::domain::{User, UserRepository};
+    use crate::shared::domain::{Entity, TenantId, UserId};
+    use crate::test_support::{send, Logs};
+    use crate::testing::FakeRepositories;
+    use axum::http::{Method, StatusCode};
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    #[test]
+    fn budgets_should_default_to_the_request_timeout() {
+        let mut config = Config::default();
+        config.use_case_budgets_ms = vec![("get_task".into(), 250)];
+        let budgets = UseCaseBudgets::from_config(&config);
+        assert_eq!(budgets.of("get_task"), Duration::from_millis(250));
+        assert_eq!(budgets.of("list_tasks"), config.request_timeout());
+    }
+
+    #[test]
+    fn use_cases_should_be_measured_against_their_budget_and_warned_about_near_it() {
+        let fakes = FakeRepositories::default();
+        fakes.users.slow("find_by_id", Duration::from_millis(50));
+        let mut config = Config::default();
+        config.use_case_budgets_ms = vec![("get_user".into(), 10)];
+        let state = AppState::build(&config, &fakes).expect("valid feature config");
+        let app = build_router(&state, &config).expect("valid router");
+        let user = User::new(UserId::generate(), "Alice".into(), "alice@example.com")
+            .expect("valid user");
+
+        let logs = Logs::default();
+        let subscriber = logs.subscriber();
+        let debugging = DebuggingRecorder::new();
+        let snapshotter = debugging.snapshotter();
+        tracing::subscriber::with_default(subscriber, || {
+            metrics::with_local_recorder(&debugging, || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .start_paused(true)
+                    .build()
+                    .expect("runtime");
+                runtime.block_on(async {
+                    fakes.users.insert(&TenantId::default(), &user).await.expect("seeded");
+                    let uri = format!("/users/{}", user.id().value());
+                    assert_eq!(send(&app, Method::GET, &uri, None).await.0, StatusCode::OK);
+                    assert_eq!(send(&app, Method::GET, "/users", None).await.0, StatusCode::OK);
+                });
+            });
+        });
+
+        let mut ratios: Vec<(String, Vec<f64>)> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .filter(|(key, ..)| key.key().name() == USE_CASE_BUDGET_RATIO)
+            .map(|(key, _, _, value)| {
+                let use_case = key.key().labels().find(|label| label.key() == "use_case");
+                let samples = match value {
+                    DebugValue::Histogram(samples) => {
+                        samples.into_iter().map(|sample| sample.0).collect()
+                    }
+                    DebugValue::Counter(_) | DebugValue::Gauge(_) => Vec::new(),
+                };
+                (use_case.map(|label| label.value().to_owned()).unwrap_or_default(), samples)
+            })
+            .collect();
+        ratios.sort_by(|a, b| a.0.cmp(&b.0));
+        let [(get_user, slow), (list_users, fast)] = &ratios[..] else {
+            panic!("one histogram per use case: {ratios:?}");
+        };
+        assert_eq!((get_user.as_str(), list_users.as_str()), ("get_user", "list_users"));
+        assert!(matches!(slow[..], [ratio] if ratio >= 5.0), "50 ms of a 10 ms budget: {slow:?}");
+        assert!(matches!(fast[..], [ratio] if ratio < 0.8), "{fast:?}");
+
+        let logged = logs.contents();
+        assert!(logged.contains("WARN"), "{logged}");
+        assert!(logged.contains("get_user took 500% of its 10 ms budget"), "{logged}");
+        assert!(!logged.contains("list_users took"), "{logged}");
+    }
+}
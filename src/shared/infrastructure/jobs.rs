@@ -0,0 +1,305 @@
+//! Periodic background jobs and their status
+//!
+//! Jobs implement [`BackgroundJob`] and are registered with a [`JobRunner`] instead of
+//! being spawned by hand. The runner staggers their starts, runs each on its interval
+//! until shutdown, survives panics of single iterations and records the outcome of
+//! every run in [`JobStatuses`], served at `GET /internal/jobs`.
+
+use crate::shared::infrastructure::http::json_response;
+use axum::{extract::State, http::StatusCode, response::Response};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// A unit of periodic background work
+#[async_trait::async_trait]
+pub trait BackgroundJob: Send + Sync + 'static {
+    /// Unique name, used as the key in [`JobStatuses`]
+    fn name(&self) -> &'static str;
+
+    /// Pause between the end of one run and the start of the next
+    fn interval(&self) -> Duration;
+
+    /// Perform one run. Long runs should check [`JobContext::is_shutting_down`].
+    ///
+    /// # Errors
+    /// Any error is logged and recorded as the job's `last_error`; the job keeps running.
+    async fn run(&self, ctx: &JobContext) -> anyhow::Result<()>;
+}
+
+/// What a job run can observe about its environment
+#[derive(Debug, Clone)]
+pub struct JobContext {
+    shutdown: watch::Receiver<()>,
+}
+
+impl JobContext {
+    /// Context of runs that stop once `shutdown` changes
+    #[must_use]
+    pub fn new(shutdown: watch::Receiver<()>) -> Self {
+        Self { shutdown }
+    }
+
+    /// Whether the shutdown signal has been broadcast (or its sender is gone)
+    #[must_use]
+    pub fn is_shutting_down(&self) -> bool {
+        !matches!(self.shutdown.has_changed(), Ok(false))
+    }
+}
+
+/// Outcome of a job's most recent runs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct JobStatus {
+    /// Start of the last finished run
+    pub last_run: Option<DateTime<Utc>>,
+    /// Error (or panic) of the last run; `None` when it succeeded
+    pub last_error: Option<String>,
+    /// Duration of the last run in milliseconds
+    pub last_duration_ms: Option<u64>,
+    /// Number of finished runs since startup
+    pub runs: u64,
+}
+
+/// Shared status of every registered job, keyed by job name
+#[derive(Debug, Clone, Default)]
+pub struct JobStatuses(Arc<Mutex<BTreeMap<&'static str, JobStatus>>>);
+
+impl JobStatuses {
+    /// Snapshot of every job's status
+    #[must_use]
+    pub fn snapshot(&self) -> BTreeMap<&'static str, JobStatus> {
+        self.0.lock().map(|statuses| statuses.clone()).unwrap_or_default()
+    }
+
+    fn update(&self, name: &'static str, update: impl FnOnce(&mut JobStatus)) {
+        if let Ok(mut statuses) = self.0.lock() {
+            update(statuses.entry(name).or_default());
+        }
+    }
+}
+
+/// `GET /internal/jobs`: status of every registered job
+pub async fn job_statuses(State(statuses): State<JobStatuses>) -> Response {
+    json_response(StatusCode::OK, &statuses.snapshot())
+}
+
+/// Owns the registered jobs until [`JobRunner::start`] spawns them
+pub struct JobRunner {
+    jobs: Vec<Arc<dyn BackgroundJob>>,
+    statuses: JobStatuses,
+    stagger: Duration,
+}
+
+impl JobRunner {
+    /// Delay added before the first run of each further job, so jobs do not start at once
+    pub const DEFAULT_STAGGER: Duration = Duration::from_secs(1);
+
+    /// Create a runner recording into `statuses`
+    #[must_use]
+    pub fn new(statuses: JobStatuses) -> Self {
+        Self { jobs: Vec::new(), statuses, stagger: Self::DEFAULT_STAGGER }
+    }
+
+    /// Replace [`Self::DEFAULT_STAGGER`]
+    #[must_use]
+    pub fn with_stagger(mut self, stagger: Duration) -> Self {
+        self.stagger = stagger;
+        self
+    }
+
+    /// Add `job`; it shows up in the statuses before its first run
+    #[must_use]
+    pub fn register(self, job: impl BackgroundJob) -> Self {
+        self.register_arc(Arc::new(job))
+    }
+
+    fn register_arc(mut self, job: Arc<dyn BackgroundJob>) -> Self {
+        self.statuses.update(job.name(), |_| {});
+        self.jobs.push(job);
+        self
+    }
+
+    /// Spawn every job's loop; the loops end once `shutdown` changes
+    #[must_use]
+    pub fn start(self, shutdown: &watch::Receiver<()>) -> RunningJobs {
+        let handles = (0..)
+            .zip(self.jobs)
+            .map(|(index, job)| {
+                let ctx = JobContext::new(shutdown.clone());
+                let first_delay = self.stagger.saturating_mul(index);
+                tokio::spawn(run_loop(job, ctx, self.statuses.clone(), first_delay))
+            })
+            .collect();
+        RunningJobs { handles }
+    }
+}
+
+/// Handles of the spawned job loops
+pub struct RunningJobs {
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl RunningJobs {
+    /// Wait until every job loop has stopped
+    pub async fn join(self) {
+        for handle in self.handles {
+            handle.await.ok();
+        }
+    }
+}
+
+async fn run_loop(
+    job: Arc<dyn BackgroundJob>,
+    mut ctx: JobContext,
+    statuses: JobStatuses,
+    first_delay: Duration,
+) {
+    let name = job.name();
+    let mut delay = first_delay;
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep(delay) => {}
+            _ = ctx.shutdown.changed() => break,
+        }
+        if ctx.is_shutting_down() {
+            break;
+        }
+        let started_at = Utc::now();
+        let started = tokio::time::Instant::now();
+        // Each run is its own task, so a panic ends the run but not the loop
+        let run = {
+            let (job, ctx) = (Arc::clone(&job), ctx.clone());
+            tokio::spawn(async move { job.run(&ctx).await })
+        };
+        let error = match run.await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(format!("{e:#}")),
+            Err(e) if e.is_panic() => Some("job panicked".to_owned()),
+            Err(e) => Some(e.to_string()),
+        };
+        let elapsed = started.elapsed();
+        if let Some(error) = &error {
+            tracing::error!(job = name, "Background job failed: {error}");
+        }
+        statuses.update(name, |status| {
+            status.last_run = Some(started_at);
+            status.last_error = error;
+            status.last_duration_ms = Some(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX));
+            status.runs += 1;
+        });
+        delay = job.interval();
+    }
+    tracing::info!(job = name, "Background job stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails on its first run, panics on the second and succeeds afterwards
+    struct Flaky {
+        runs: AtomicU32,
+    }
+
+    #[async_trait::async_trait]
+    impl BackgroundJob for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn interval(&self) -> Duration {
+            Duration::from_secs(10)
+        }
+
+        async fn run(&self, _ctx: &JobContext) -> anyhow::Result<()> {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            match self.runs.fetch_add(1, Ordering::Relaxed) + 1 {
+                1 => anyhow::bail!("upstream down"),
+                2 => panic!("boom"),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    struct Quick;
+
+    #[async_trait::async_trait]
+    impl BackgroundJob for Quick {
+        fn name(&self) -> &'static str {
+            "quick"
+        }
+
+        fn interval(&self) -> Duration {
+            Duration::from_secs(30)
+        }
+
+        async fn run(&self, _ctx: &JobContext) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn runner() -> (JobRunner, JobStatuses) {
+        let statuses = JobStatuses::default();
+        let runner = JobRunner::new(statuses.clone())
+            .with_stagger(Duration::from_secs(1))
+            .register(Flaky { runs: AtomicU32::new(0) })
+            .register(Quick);
+        (runner, statuses)
+    }
+
+    /// Advance the paused clock in small steps so spawned tasks keep up
+    async fn advance(by: Duration) {
+        let step = Duration::from_millis(50);
+        let mut elapsed = Duration::ZERO;
+        while elapsed < by {
+            tokio::time::sleep(step).await;
+            elapsed += step;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn runner_should_record_errors_and_survive_panics() {
+        let (runner, statuses) = runner();
+        assert_eq!(statuses.snapshot().get("flaky"), Some(&JobStatus::default()));
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        let running = runner.start(&shutdown_rx);
+
+        advance(Duration::from_millis(500)).await;
+        let flaky = statuses.snapshot().remove("flaky").unwrap_or_default();
+        assert_eq!(flaky.runs, 1);
+        assert_eq!(flaky.last_error.as_deref(), Some("upstream down"));
+        assert_eq!(flaky.last_duration_ms, Some(250));
+        // Staggered: the second job has not started yet
+        assert_eq!(statuses.snapshot().get("quick").map(|s| s.runs), Some(0));
+
+        advance(Duration::from_secs(11)).await;
+        let flaky = statuses.snapshot().remove("flaky").unwrap_or_default();
+        assert_eq!(flaky.runs, 2);
+        assert_eq!(flaky.last_error.as_deref(), Some("job panicked"));
+        assert_eq!(statuses.snapshot().get("quick").map(|s| s.runs), Some(1));
+
+        advance(Duration::from_secs(10)).await;
+        let flaky = statuses.snapshot().remove("flaky").unwrap_or_default();
+        assert_eq!((flaky.runs, flaky.last_error), (3, None));
+
+        shutdown_tx.send(()).ok();
+        let stopped = tokio::time::timeout(Duration::from_secs(1), running.join()).await;
+        assert!(stopped.is_ok(), "jobs did not stop on shutdown");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn jobs_should_stop_before_their_first_run_on_shutdown() {
+        let (runner, statuses) = runner();
+        let (shutdown_tx, shutdown_rx) = watch::channel(());
+        shutdown_tx.send(()).ok();
+        let running = runner.start(&shutdown_rx);
+        let stopped = tokio::time::timeout(Duration::from_millis(10), running.join()).await;
+        assert!(stopped.is_ok());
+        assert!(statuses.snapshot().values().all(|status| status.runs == 0));
+    }
+}
@@ -0,0 +1,262 @@
+//! Log level changed at runtime
+//!
+//! Events are filtered by an [`EnvFilter`] that can be swapped while the server
+//! runs: `GET /admin/log-level` shows the directives in force and those the process
+//! started with (`RUST_LOG`, `info` when unset), and `PUT /admin/log-level` with
+//! `{"directives": "debug,sqlx=warn"}` applies new ones, returning the previous ones
+//! to revert to. With `LOG_LEVEL_TTL_SECS`, a change reverts to the startup
+//! directives after that long. Changes apply to this instance only.
+
+use crate::shared::domain::DomainError;
+use crate::shared::infrastructure::http::{json_response, ApiError, Negotiated};
+use axum::{extract::State, http::StatusCode, response::Response, routing::get, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Handle swapping the filter of the global subscriber
+pub type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Directives of the running process and those it started with
+pub struct LogLevel {
+    handle: FilterHandle,
+    startup: String,
+    current: Mutex<Applied>,
+    revert_after: Option<Duration>,
+}
+
+/// Directives in force, and how many changes led to them
+struct Applied {
+    directives: String,
+    generation: u64,
+}
+
+/// Body of `PUT /admin/log-level`
+#[derive(Debug, Deserialize)]
+struct SetLogLevelRequest {
+    /// `EnvFilter` directives, as in `RUST_LOG`
+    directives: String,
+}
+
+/// Body of `GET /admin/log-level`
+#[derive(Debug, Serialize)]
+struct LogLevelResponse {
+    directives: String,
+    startup: String,
+}
+
+/// Body of `PUT /admin/log-level`'s response
+#[derive(Debug, Serialize)]
+struct LogLevelChange {
+    directives: String,
+    previous: String,
+}
+
+/// Install the global subscriber, filtered by the `RUST_LOG` directives (`info` when
+/// unset or invalid), and return the handle changing them
+#[must_use]
+pub fn install() -> LogLevel {
+    let startup = std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|directives| EnvFilter::try_new(directives).is_ok())
+        .unwrap_or_else(|| "info".to_owned());
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(&startup));
+    tracing_subscriber::registry().with(filter).with(tracing_subscriber::fmt::layer()).init();
+    LogLevel::new(handle, startup)
+}
+
+impl LogLevel {
+    /// Directives changed through `handle`, whose filter started as `startup`
+    #[must_use]
+    pub fn new(handle: FilterHandle, startup: String) -> Self {
+        let current = Mutex::new(Applied { directives: startup.clone(), generation: 0 });
+        Self { handle, startup, current, revert_after: None }
+    }
+
+    /// Revert each change to the startup directives after `ttl`; `None` keeps changes
+    /// until the next one
+    #[must_use]
+    pub fn revert_after(self, ttl: Option<Duration>) -> Self {
+        Self { revert_after: ttl, ..self }
+    }
+
+    /// The directives in force
+    pub fn current(&self) -> String {
+        self.applied().directives.clone()
+    }
+
+    /// Apply `directives`, returning the ones they replace, both as written
+    ///
+    /// # Errors
+    /// `400 INVALID_BODY` with the parse error when `directives` are invalid, before
+    /// anything changes; an infrastructure error if the subscriber is gone.
+    pub fn set(self: &Arc<Self>, directives: &str) -> Result<String, ApiError> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| {
+            let message = format!("Invalid log directives: {e}");
+            ApiError::new(StatusCode::BAD_REQUEST, "INVALID_BODY", message)
+        })?;
+        let mut applied = self.applied();
+        self.reload(filter)?;
+        let previous = std::mem::replace(&mut applied.directives, directives.trim().to_owned());
+        applied.generation += 1;
+        if let Some(ttl) = self.revert_after {
+            let (log_level, generation) = (Arc::clone(self), applied.generation);
+            tokio::spawn(async move {
+                tokio::time::sleep(ttl).await;
+                log_level.revert(generation);
+            });
+        }
+        Ok(previous)
+    }
+
+    /// Go back to the startup directives unless they changed since change `generation`
+    fn revert(&self, generation: u64) {
+        let mut applied = self.applied();
+        if applied.generation != generation || applied.directives == self.startup {
+            return;
+        }
+        // The startup directives were checked when the subscriber was installed
+        let Ok(filter) = EnvFilter::try_new(&self.startup) else { return };
+        match self.reload(filter) {
+            Ok(()) => {
+                tracing::info!(directives = self.startup, "Log level reverted");
+                applied.directives.clone_from(&self.startup);
+                applied.generation += 1;
+            }
+            Err(e) => tracing::warn!(error = %e, "Log level could not be reverted"),
+        }
+    }
+
+    fn reload(&self, filter: EnvFilter) -> Result<(), DomainError> {
+        self.handle
+            .reload(filter)
+            .map_err(|e| DomainError::Infrastructure(format!("Log filter is unavailable: {e}")))
+    }
+
+    fn applied(&self) -> std::sync::MutexGuard<'_, Applied> {
+        self.current.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// `GET /admin/log-level` and `PUT /admin/log-level`
+pub fn routes(log_level: Arc<LogLevel>) -> Router {
+    Router::new()
+        .route("/admin/log-level", get(show_log_level).put(set_log_level))
+        .with_state(log_level)
+}
+
+/// `GET /admin/log-level`: the directives in force and the startup ones
+async fn show_log_level(State(log_level): State<Arc<LogLevel>>) -> Response {
+    let startup = log_level.startup.clone();
+    json_response(StatusCode::OK, &LogLevelResponse { directives: log_level.current(), startup })
+}
+
+/// `PUT /admin/log-level`: apply the directives of the body, returning them with the
+/// previous ones
+async fn set_log_level(
+    State(log_level): State<Arc<LogLevel>>,
+    Negotiated(body): Negotiated<SetLogLevelRequest>,
+) -> Result<Response, ApiError> {
+    let previous = log_level.set(&body.directives)?;
+    let directives = log_level.current();
+    tracing::info!(directives, previous, "Log level changed");
+    Ok(json_response(StatusCode::OK, &LogLevelChange { directives, previous }))
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::test_support::send;
+    use axum::http::Method;
+    use serde_json::json;
+    use tracing::subscriber::DefaultGuard;
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer};
+
+    /// Counts the events of the `log_level_probe` target that pass the filter
+    #[derive(Clone, Default)]
+    struct Probes(Arc<Mutex<usize>>);
+
+    impl<S: Subscriber> Layer<S> for Probes {
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            if event.metadata().target() == "log_level_probe" {
+                *self.0.lock().expect("lock") += 1;
+            }
+        }
+    }
+
+    impl Probes {
+        /// Whether a debug event emitted now is captured
+        fn debug_captured(&self) -> bool {
+            let before = *self.0.lock().expect("lock");
+            tracing::debug!(target: "log_level_probe", "probe");
+            *self.0.lock().expect("lock") > before
+        }
+    }
+
+    /// A subscriber filtered by `info` for this thread, with its log level and probes
+    fn subscriber(ttl: Option<Duration>) -> (DefaultGuard, Arc<LogLevel>, Probes) {
+        let (filter, handle) = reload::Layer::new(EnvFilter::new("info"));
+        let probes = Probes::default();
+        let subscriber = tracing_subscriber::registry().with(filter).with(probes.clone());
+        let guard = subscriber.set_default();
+        let log_level = Arc::new(LogLevel::new(handle, "info".into()).revert_after(ttl));
+        (guard, log_level, probes)
+    }
+
+    #[tokio::test]
+    async fn log_level_should_be_changed_and_reverted_at_runtime() {
+        let (_guard, log_level, probes) = subscriber(None);
+        let app = routes(log_level);
+        assert!(!probes.debug_captured());
+
+        let debug = Some(json!({"directives": "info,log_level_probe=debug"}));
+        let (status, body) = send(&app, Method::PUT, "/admin/log-level", debug).await;
+        let change = json!({"directives": "info,log_level_probe=debug", "previous": "info"});
+        assert_eq!((status, body), (StatusCode::OK, change));
+        assert!(probes.debug_captured());
+        let (status, body) = send(&app, Method::GET, "/admin/log-level", None).await;
+        let shown = json!({"directives": "info,log_level_probe=debug", "startup": "info"});
+        assert_eq!((status, body), (StatusCode::OK, shown));
+
+        let revert = Some(json!({"directives": "info"}));
+        let (status, body) = send(&app, Method::PUT, "/admin/log-level", revert).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["previous"], "info,log_level_probe=debug");
+        assert!(!probes.debug_captured());
+    }
+
+    #[tokio::test]
+    async fn invalid_directives_should_be_rejected_without_changing_the_level() {
+        let (_guard, log_level, probes) = subscriber(None);
+        let app = routes(Arc::clone(&log_level));
+
+        let invalid = Some(json!({"directives": "log_level_probe=loud"}));
+        let (status, body) = send(&app, Method::PUT, "/admin/log-level", invalid).await;
+        assert_eq!((status, &body["code"]), (StatusCode::BAD_REQUEST, &json!("INVALID_BODY")));
+        let message = body["message"].as_str().expect("message");
+        assert!(message.starts_with("Invalid log directives: "), "{message}");
+        assert_eq!(log_level.current(), "info");
+        assert!(!probes.debug_captured());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn changes_should_revert_to_the_startup_level_after_the_ttl() {
+        let (_guard, log_level, probes) = subscriber(Some(Duration::from_mins(1)));
+
+        log_level.set("log_level_probe=debug").expect("valid directives");
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        assert!(probes.debug_captured());
+        // A later change restarts the countdown
+        log_level.set("debug").expect("valid directives");
+        tokio::time::sleep(Duration::from_secs(45)).await;
+        assert_eq!(log_level.current(), "debug");
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        assert_eq!(log_level.current(), "info");
+        assert!(!probes.debug_captured());
+    }
+}
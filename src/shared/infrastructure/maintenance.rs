@@ -0,0 +1,338 @@
+//! Bounded cleanup of stale data: the maintenance service, its route and its job
+//!
+//! Features register [`CleanupTask`]s, each deleting one category of stale rows in
+//! small batches. A run of the [`MaintenanceService`] works through the categories
+//! one batch at a time until each is clean or the time budget is spent, so it holds
+//! no long locks and is safe to run alongside traffic. Operators run it through
+//! `POST /admin/maintenance/cleanup` on the admin listener; the `maintenance_cleanup`
+//! job runs it every `CLEANUP_INTERVAL_SECS`.
+
+use crate::shared::domain::DomainError;
+use crate::shared::infrastructure::database::{acquire, run_query};
+use crate::shared::infrastructure::http::{json_response, ApiError};
+use crate::shared::infrastructure::jobs::{BackgroundJob, JobContext};
+use axum::{extract::State, http::StatusCode, response::Response, routing::post, Router};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// One category of stale rows, deleted in batches
+#[async_trait::async_trait]
+pub trait CleanupTask: Send + Sync {
+    /// Name of the category, the key of its count in a [`CleanupReport`]
+    fn name(&self) -> &'static str;
+
+    /// Delete at most `limit` rows that went stale before `before`, returning how
+    /// many were deleted; rows locked by other transactions are skipped
+    ///
+    /// # Errors
+    /// Returns `DomainError` when the rows cannot be deleted.
+    async fn delete_batch(&self, before: DateTime<Utc>, limit: u32) -> Result<u64, DomainError>;
+}
+
+/// How cleanup runs delete stale rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CleanupPolicy {
+    /// How long rows are kept after they went stale
+    pub retention: Duration,
+    /// Most rows deleted by one statement
+    pub batch_size: u32,
+    /// Time after which a run starts no further batch
+    pub time_budget: Duration,
+    /// Pause between the runs of the `maintenance_cleanup` job; `None` runs cleanup
+    /// on request only
+    pub interval: Option<Duration>,
+}
+
+/// Keep stale rows for 30 days, delete them 500 at a time for up to 5 seconds an hour
+impl Default for CleanupPolicy {
+    fn default() -> Self {
+        Self {
+            retention: Duration::from_hours(24 * 30),
+            batch_size: 500,
+            time_budget: Duration::from_secs(5),
+            interval: Some(Duration::from_hours(1)),
+        }
+    }
+}
+
+/// Outcome of a cleanup run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CleanupReport {
+    /// Rows deleted per category, every registered category included
+    pub deleted: BTreeMap<&'static str, u64>,
+    /// Whether every category was cleaned up within the time budget; the next run
+    /// continues where an incomplete one stopped
+    pub complete: bool,
+}
+
+/// Runs the registered cleanup tasks within a [`CleanupPolicy`]
+#[derive(Clone, Default)]
+pub struct MaintenanceService {
+    tasks: Vec<Arc<dyn CleanupTask>>,
+    policy: CleanupPolicy,
+}
+
+impl MaintenanceService {
+    /// Create a service without tasks, running them within `policy`
+    #[must_use]
+    pub fn new(policy: CleanupPolicy) -> Self {
+        Self { tasks: Vec::new(), policy }
+    }
+
+    /// Add `task`, run after the tasks registered before it
+    #[must_use]
+    pub fn register(mut self, task: Arc<dyn CleanupTask>) -> Self {
+        self.tasks.push(task);
+        self
+    }
+
+    /// Add every task of `tasks`
+    #[must_use]
+    pub fn register_all(self, tasks: impl IntoIterator<Item = Arc<dyn CleanupTask>>) -> Self {
+        tasks.into_iter().fold(self, Self::register)
+    }
+
+    /// Policy the runs follow
+    #[must_use]
+    pub fn policy(&self) -> &CleanupPolicy {
+        &self.policy
+    }
+
+    /// Whether no task is registered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Delete the rows of every category that went stale more than the retention
+    /// ago, one batch after another while batches come back full
+    ///
+    /// The time budget is checked before each batch, so a run ends at most one batch
+    /// after it is spent.
+    ///
+    /// # Errors
+    /// Returns the error of the first batch that fails; the batches before it stay
+    /// deleted.
+    pub async fn run(&self) -> Result<CleanupReport, DomainError> {
+        let deadline = Instant::now() + self.policy.time_budget;
+        let retention = TimeDelta::from_std(self.policy.retention).unwrap_or(TimeDelta::MAX);
+        let before = Utc::now().checked_sub_signed(retention).unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let batch_size = self.policy.batch_size.max(1);
+        let mut report = CleanupReport {
+            deleted: self.tasks.iter().map(|task| (task.name(), 0)).collect(),
+            complete: true,
+        };
+        for task in &self.tasks {
+            loop {
+                if Instant::now() >= deadline {
+                    report.complete = false;
+                    return Ok(report);
+                }
+                let deleted = task.delete_batch(before, batch_size).await?;
+                *report.deleted.entry(task.name()).or_default() += deleted;
+                if deleted < u64::from(batch_size) {
+                    break;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// `POST /admin/maintenance/cleanup`
+pub fn routes(service: Arc<MaintenanceService>) -> Router {
+    Router::new().route("/admin/maintenance/cleanup", post(cleanup)).with_state(service)
+}
+
+/// `POST /admin/maintenance/cleanup`: run every cleanup task once, reporting the rows
+/// deleted per category
+async fn cleanup(State(service): State<Arc<MaintenanceService>>) -> Result<Response, ApiError> {
+    let report = service.run().await?;
+    Ok(json_response(StatusCode::OK, &report))
+}
+
+/// Runs the [`MaintenanceService`] periodically
+pub struct MaintenanceJob {
+    service: Arc<MaintenanceService>,
+    interval: Duration,
+}
+
+impl MaintenanceJob {
+    /// Run `service` with a pause of `interval` between runs
+    #[must_use]
+    pub fn new(service: Arc<MaintenanceService>, interval: Duration) -> Self {
+        Self { service, interval }
+    }
+}
+
+#[async_trait::async_trait]
+impl BackgroundJob for MaintenanceJob {
+    fn name(&self) -> &'static str {
+        "maintenance_cleanup"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, _ctx: &JobContext) -> anyhow::Result<()> {
+        let report = self.service.run().await?;
+        tracing::info!(deleted = ?report.deleted, complete = report.complete, "Cleaned up");
+        Ok(())
+    }
+}
+
+/// Deletes rows of the `audit_log` recorded more than the retention ago
+#[derive(Clone)]
+pub struct PgAuditLogCleanup {
+    pool: PgPool,
+}
+
+impl PgAuditLogCleanup {
+    /// Clean up the audit log on `pool`
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl CleanupTask for PgAuditLogCleanup {
+    fn name(&self) -> &'static str {
+        "audit_log"
+    }
+
+    async fn delete_batch(&self, before: DateTime<Utc>, limit: u32) -> Result<u64, DomainError> {
+        let query = sqlx::query(
+            "DELETE FROM audit_log WHERE id IN ( \
+                 SELECT id FROM audit_log WHERE recorded_at < $1 \
+                 ORDER BY id LIMIT $2 FOR UPDATE SKIP LOCKED)",
+        )
+        .bind(before)
+        .bind(i64::from(limit));
+        let mut conn = acquire(&self.pool, "cleanup", "audit entry").await?;
+        let result = run_query(query.execute(&mut *conn), "cleanup", "audit entry").await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::app::{PgRepositories, RepositoryProvider};
+    use crate::features::{user, webhook};
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tower::ServiceExt;
+
+    /// Category of `stale` rows, deleting them as asked, in batches that take a second
+    struct Stale {
+        stale: AtomicU64,
+        batches: AtomicU64,
+    }
+
+    impl Stale {
+        fn new(stale: u64) -> Arc<Self> {
+            Arc::new(Self { stale: AtomicU64::new(stale), batches: AtomicU64::new(0) })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl CleanupTask for Stale {
+        fn name(&self) -> &'static str {
+            "stale"
+        }
+
+        async fn delete_batch(&self, _: DateTime<Utc>, limit: u32) -> Result<u64, DomainError> {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            self.batches.fetch_add(1, Ordering::Relaxed);
+            let stale = self.stale.load(Ordering::Relaxed);
+            let deleted = stale.min(u64::from(limit));
+            self.stale.store(stale - deleted, Ordering::Relaxed);
+            Ok(deleted)
+        }
+    }
+
+    fn policy(batch_size: u32, time_budget: Duration) -> CleanupPolicy {
+        CleanupPolicy { retention: Duration::ZERO, batch_size, time_budget, interval: None }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn run_should_delete_in_batches_until_clean_or_out_of_time() {
+        let stale = Stale::new(5);
+        let service = MaintenanceService::new(policy(2, Duration::from_secs(10)))
+            .register(Arc::clone(&stale) as _);
+        let report = service.run().await.expect("run");
+        assert_eq!(report.deleted, BTreeMap::from([("stale", 5)]));
+        assert!(report.complete);
+        assert_eq!(stale.batches.load(Ordering::Relaxed), 3);
+
+        let stale = Stale::new(100);
+        let service = MaintenanceService::new(policy(2, Duration::from_secs(3)))
+            .register(Arc::clone(&stale) as _);
+        let report = service.run().await.expect("run");
+        assert_eq!(report.deleted, BTreeMap::from([("stale", 6)]));
+        assert!(!report.complete);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cleanup_route_should_report_the_deleted_rows() {
+        let service = MaintenanceService::new(policy(10, Duration::from_secs(10)))
+            .register(Stale::new(3) as _);
+        let request = Request::post("/admin/maintenance/cleanup").body(Body::empty());
+        let response = routes(Arc::new(service))
+            .oneshot(request.expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("body");
+        let body: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert_eq!(body, serde_json::json!({ "deleted": { "stale": 3 }, "complete": true }));
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn pg_cleanup_should_delete_only_stale_rows_of_each_kind(pool: PgPool) {
+        sqlx::raw_sql(
+            "INSERT INTO users (id, tenant_id, name, email) \
+                 VALUES ('u1', 't1', 'Alice', 'a@example.com'); \
+             INSERT INTO audit_log (tenant_id, action, subject, recorded_at) VALUES \
+                 ('t1', 'tasks.reassigned', 'u1', now() - interval '40 days'), \
+                 ('t1', 'tasks.reassigned', 'u1', now() - interval '35 days'), \
+                 ('t1', 'tasks.reassigned', 'u1', now()); \
+             INSERT INTO outbox_events (tenant_id, event_type, subject, processed_at) VALUES \
+                 ('t1', 'task.created', 't1', now() - interval '40 days'), \
+                 ('t1', 'task.created', 't2', now() - interval '40 days'), \
+                 ('t1', 'task.created', 't3', now()), \
+                 ('t1', 'task.created', 't4', NULL); \
+             INSERT INTO webhook_subscriptions (id, tenant_id, url, secret) \
+                 VALUES ('w1', 't1', 'https://example.com/hook', 'secret'); \
+             INSERT INTO webhook_deliveries (subscription_id, event_id) \
+                 SELECT 'w1', id FROM outbox_events WHERE subject = 't2'; \
+             INSERT INTO pending_email_changes (token_hash, user_id, new_email, expires_at) \
+                 VALUES ('h1', 'u1', 'b@example.com', now() - interval '40 days'), \
+                        ('h2', 'u1', 'c@example.com', now() + interval '1 hour');",
+        )
+        .execute(&pool)
+        .await
+        .expect("seed");
+        let tasks = PgRepositories::new(pool).cleanup_tasks(&[user::NAME, webhook::NAME]);
+        let policy = CleanupPolicy { batch_size: 1, ..CleanupPolicy::default() };
+        let service = MaintenanceService::new(policy).register_all(tasks);
+
+        let report = service.run().await.expect("run");
+        let expected = [("audit_log", 2), ("email_changes", 1), ("outbox_events", 1)];
+        assert_eq!(report.deleted, BTreeMap::from(expected));
+        assert!(report.complete);
+        // Events still being delivered, recent rows and pending events stay
+        let report = service.run().await.expect("second run");
+        assert!(report.deleted.values().all(|&deleted| deleted == 0), "{report:?}");
+    }
+}
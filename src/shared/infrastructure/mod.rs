@@ -1,5 +1,27 @@
 //! Shared infrastructure implementations
 
+pub mod admin;
+pub mod backpressure;
+pub mod cache_control;
+pub mod casing;
+pub mod client_ip;
+pub mod conditional;
 pub mod config;
 pub mod database;
+pub mod email;
+pub mod event_schema;
+pub mod feature;
+pub mod feature_toggle;
+pub mod fields;
+pub mod healthcheck;
 pub mod http;
+pub mod http_client;
+pub mod instrumentation;
+pub mod jobs;
+pub mod log_level;
+pub mod maintenance;
+pub mod outbox;
+pub mod page_links;
+pub mod rate_limit;
+pub mod request_context;
+pub mod tenant;
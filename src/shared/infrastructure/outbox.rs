@@ -0,0 +1,103 @@
+//! In-memory outbox, shared by the in-memory unit of work and webhook deliveries
+
+use crate::shared::domain::{OutboxEvent, StoredEvent, TenantId};
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+/// Committed events, each with the time it was processed, if it was
+type Events = Vec<(StoredEvent, Option<DateTime<Utc>>)>;
+
+/// Outbox of committed events, each marked with the time it is processed
+///
+/// [`InMemoryUnitOfWork`](crate::features::task::infrastructure::InMemoryUnitOfWork)
+/// appends the events of a transaction when it commits.
+#[derive(Debug, Default)]
+pub struct InMemoryOutbox {
+    events: RwLock<Events>,
+    /// ID of the latest event appended; IDs are not reused once events are deleted
+    last_id: AtomicI64,
+}
+
+impl InMemoryOutbox {
+    /// Append `events` of `tenant`, as committed now
+    pub async fn append(&self, events: impl IntoIterator<Item = (TenantId, OutboxEvent)>) {
+        self.appender().await.append(events);
+    }
+
+    /// Lock the outbox for appending without awaiting anything more, so that events
+    /// are appended in the same step as the writes they record
+    pub async fn appender(&self) -> OutboxAppender<'_> {
+        OutboxAppender { events: self.events.write().await, last_id: &self.last_id }
+    }
+
+    /// At most `limit` events not processed yet, oldest first
+    pub async fn pending(&self, limit: usize) -> Vec<StoredEvent> {
+        let stored = self.events.read().await;
+        let pending = stored.iter().filter(|(_, processed_at)| processed_at.is_none());
+        pending.take(limit).map(|(event, _)| event.clone()).collect()
+    }
+
+    /// Mark the event `id` processed; false if it already was, or does not exist
+    pub async fn mark_processed(&self, id: i64) -> bool {
+        let mut stored = self.events.write().await;
+        match stored.iter_mut().find(|(event, _)| event.id == id) {
+            Some((_, processed_at)) if processed_at.is_none() => {
+                *processed_at = Some(Utc::now());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Every event of `tenant`, processed or not, oldest first
+    pub async fn events(&self, tenant: &TenantId) -> Vec<StoredEvent> {
+        let stored = self.events.read().await;
+        let events = stored.iter().filter(|(event, _)| &event.tenant == tenant);
+        events.map(|(event, _)| event.clone()).collect()
+    }
+
+    /// Delete at most `limit` events processed before `before` that `keep` does not
+    /// retain, oldest first, returning how many were deleted
+    pub async fn delete_processed(
+        &self,
+        before: DateTime<Utc>,
+        limit: usize,
+        keep: impl Fn(i64) -> bool,
+    ) -> usize {
+        let mut stored = self.events.write().await;
+        let mut deleted = 0;
+        stored.retain(|(event, processed_at)| {
+            let stale = processed_at.is_some_and(|at| at < before) && !keep(event.id);
+            let delete = stale && deleted < limit;
+            deleted += usize::from(delete);
+            !delete
+        });
+        deleted
+    }
+}
+
+/// Outbox locked by [`InMemoryOutbox::appender`]
+pub struct OutboxAppender<'a> {
+    events: RwLockWriteGuard<'a, Events>,
+    last_id: &'a AtomicI64,
+}
+
+impl OutboxAppender<'_> {
+    /// Append `events` of `tenant`, as committed now
+    pub fn append(&mut self, events: impl IntoIterator<Item = (TenantId, OutboxEvent)>) {
+        for (tenant, event) in events {
+            let id = self.last_id.fetch_add(1, Ordering::Relaxed).saturating_add(1);
+            let event = StoredEvent {
+                id,
+                tenant,
+                event_type: event.event_type.to_owned(),
+                version: event.version,
+                subject: event.subject,
+                payload: event.payload,
+                occurred_at: Utc::now(),
+            };
+            self.events.push((event, None));
+        }
+    }
+}
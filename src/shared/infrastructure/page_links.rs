@@ -0,0 +1,86 @@
+//! `Link` headers (RFC 8288, formerly RFC 5988) to the neighbouring pages of a listing
+//!
+//! Listings return no total, so a full page is assumed to have a next one: the last
+//! page of a listing whose length is a multiple of the limit links to an empty page.
+
+use crate::shared::application::{PageLimits, PageRequest};
+use crate::shared::infrastructure::request_context::RequestContext;
+use axum::http::{HeaderValue, Uri};
+use url::form_urlencoded;
+
+/// `Link` header to the `first`, `prev` and `next` pages of the listing at `uri`,
+/// whose page `request` returned `returned` items
+///
+/// Links keep the other query parameters of `uri`, set `limit` and `offset`, and
+/// are built from `context` like any other link. `prev` is omitted on the first page
+/// and `next` on a page that is not full or would page beyond the limits. `None`
+/// when `request` is outside `limits`.
+#[must_use]
+pub fn page_links(
+    context: &RequestContext,
+    uri: &Uri,
+    limits: PageLimits,
+    request: PageRequest,
+    returned: usize,
+) -> Option<HeaderValue> {
+    let page = limits.resolve(request).ok()?;
+    let query = uri.query().unwrap_or_default();
+    let filters: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+        .filter(|(name, _)| name != "limit" && name != "offset")
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    let link = |offset: u32, rel: &str| {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query.extend_pairs(&filters);
+        query.append_pair("limit", &page.limit.to_string());
+        query.append_pair("offset", &offset.to_string());
+        let target = context.link(&format!("{}?{}", uri.path(), query.finish()));
+        format!("<{target}>; rel=\"{rel}\"")
+    };
+    let mut links = vec![link(0, "first")];
+    if page.offset > 0 {
+        links.push(link(page.offset.saturating_sub(page.limit), "prev"));
+    }
+    let next = page.offset.checked_add(page.limit).filter(|&next| next <= limits.max_offset);
+    if let Some(next) = next.filter(|_| u32::try_from(returned).is_ok_and(|n| n >= page.limit)) {
+        links.push(link(next, "next"));
+    }
+    HeaderValue::from_str(&links.join(", ")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(uri: &'static str, limit: Option<u32>, offset: Option<u32>, n: usize) -> String {
+        let limits = PageLimits { max_page_size: 50, max_offset: 100 };
+        let request = PageRequest { limit, offset };
+        let uri = Uri::from_static(uri);
+        let header = page_links(&RequestContext::default(), &uri, limits, request, n);
+        header.and_then(|value| value.to_str().ok().map(str::to_owned)).unwrap_or_default()
+    }
+
+    #[test]
+    fn page_links_should_keep_filters_and_link_the_neighbouring_pages() {
+        assert_eq!(
+            links("/tasks?completed=false&q=a%20b&limit=10&offset=20", Some(10), Some(20), 10),
+            "</tasks?completed=false&q=a+b&limit=10&offset=0>; rel=\"first\", \
+             </tasks?completed=false&q=a+b&limit=10&offset=10>; rel=\"prev\", \
+             </tasks?completed=false&q=a+b&limit=10&offset=30>; rel=\"next\""
+        );
+        assert_eq!(
+            links("/users", None, None, 50),
+            "</users?limit=50&offset=0>; rel=\"first\", </users?limit=50&offset=50>; rel=\"next\""
+        );
+    }
+
+    #[test]
+    fn page_links_should_omit_next_on_the_last_page_and_beyond_the_limits() {
+        assert_eq!(
+            links("/users?limit=10&offset=5", Some(10), Some(5), 3),
+            "</users?limit=10&offset=0>; rel=\"first\", </users?limit=10&offset=0>; rel=\"prev\""
+        );
+        assert!(!links("/users?limit=10&offset=100", Some(10), Some(100), 10).contains("next"));
+        assert_eq!(links("/users?limit=0", Some(0), None, 0), "");
+    }
+}
@@ -0,0 +1,376 @@
+//! Per-client request counting, reported in `X-RateLimit-*` headers
+//!
+//! Clients are identified by the API token they authenticated with, else by their
+//! [`ClientIp`], which sees through trusted proxies. A token only counts once
+//! authentication has verified it, so made-up tokens cannot open buckets of their own:
+//! [`rate_limit`] wraps the whole router but the health checks, and counts requests
+//! presenting no token, and those whose token was not accepted, by address, and
+//! [`rate_limit_authenticated`], inside authentication, counts the others by token.
+//! Each client has a fixed window of `RATE_LIMIT_REQUESTS` requests per
+//! `RATE_LIMIT_WINDOW_SECS`, and every response to an identified client, errors
+//! included, reports the bucket the request was counted in:
+//!
+//! - `X-RateLimit-Limit`: requests allowed per window
+//! - `X-RateLimit-Remaining`: requests left in the current window
+//! - `X-RateLimit-Reset`: seconds until the window starts over
+//!
+//! With `RATE_LIMIT_ENFORCE`, requests past the limit are answered
+//! `429 RATE_LIMITED` from the same bucket, so the headers are exactly what the limit
+//! enforces. A request presenting a token is refused before the token is looked up
+//! when its address has run out, by requests without a usable token. Unidentified
+//! clients get no headers and are never limited.
+
+use crate::features::api_token::infrastructure::Authenticated;
+use crate::shared::infrastructure::client_ip::ClientIp;
+use crate::shared::infrastructure::http::ApiError;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Requests allowed per window
+pub const X_RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+
+/// Requests left in the current window
+pub const X_RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+
+/// Seconds until the current window starts over
+pub const X_RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// Buckets kept before those of past windows are dropped
+const PRUNE_AT: usize = 10_000;
+
+/// How many requests each client may make
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    /// Requests allowed per window
+    pub limit: u32,
+    /// Length of a window
+    pub window: Duration,
+    /// Answer requests past the limit with `429` instead of only reporting them
+    pub enforce: bool,
+}
+
+/// State of a client's bucket after counting a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Requests allowed per window
+    pub limit: u32,
+    /// Requests left in the current window
+    pub remaining: u32,
+    /// Time until the window starts over
+    pub reset: Duration,
+    /// The request was past the limit
+    pub exceeded: bool,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    started: Instant,
+    used: u32,
+}
+
+/// Fixed-window request counts by client
+#[derive(Debug)]
+pub struct RateLimiter {
+    policy: RateLimitPolicy,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Limiter counting requests under `policy`
+    #[must_use]
+    pub fn new(policy: RateLimitPolicy) -> Self {
+        Self { policy, buckets: Mutex::default() }
+    }
+
+    /// Whether requests past the limit are refused
+    #[must_use]
+    pub fn enforces(&self) -> bool {
+        self.policy.enforce
+    }
+
+    /// Count a request of `client` at `now`; `None` when the counts are unavailable
+    pub fn acquire(&self, client: &str, now: Instant) -> Option<Quota> {
+        let RateLimitPolicy { limit, window, .. } = self.policy;
+        let mut buckets = self.buckets.lock().ok()?;
+        if buckets.len() >= PRUNE_AT {
+            buckets.retain(|_, bucket| now.duration_since(bucket.started) < window);
+        }
+        let bucket =
+            buckets.entry(client.to_owned()).or_insert(Bucket { started: now, used: 0 });
+        if now.duration_since(bucket.started) >= window {
+            *bucket = Bucket { started: now, used: 0 };
+        }
+        let exceeded = bucket.used >= limit;
+        if !exceeded || !self.policy.enforce {
+            bucket.used = bucket.used.saturating_add(1);
+        }
+        Some(Quota {
+            limit,
+            remaining: limit.saturating_sub(bucket.used),
+            reset: window.saturating_sub(now.duration_since(bucket.started)),
+            exceeded,
+        })
+    }
+
+    /// Whether `client` has run out at `now`, without counting a request
+    fn exhausted(&self, client: &str, now: Instant) -> Option<Quota> {
+        let RateLimitPolicy { limit, window, .. } = self.policy;
+        let buckets = self.buckets.lock().ok()?;
+        let bucket = buckets.get(client)?;
+        let elapsed = now.duration_since(bucket.started);
+        (elapsed < window && bucket.used >= limit).then(|| Quota {
+            limit,
+            remaining: 0,
+            reset: window.saturating_sub(elapsed),
+            exceeded: true,
+        })
+    }
+
+    /// Whether a request counted in `quota` is refused
+    fn refuses(&self, quota: Quota) -> bool {
+        quota.exceeded && self.policy.enforce
+    }
+}
+
+/// Bucket key of the address of the client making `request`
+fn ip_key(request: &Request) -> Option<String> {
+    let ClientIp(ip) = request.extensions().get::<ClientIp>()?;
+    Some(format!("ip:{ip}"))
+}
+
+/// Quota of the token a request authenticated with, left by
+/// [`rate_limit_authenticated`] for [`rate_limit`] to report
+#[derive(Clone, Default)]
+struct TokenQuota(Arc<Mutex<Option<Quota>>>);
+
+/// Middleware counting the requests of identified clients and reporting their quota
+/// in `X-RateLimit-*` headers; past the limit it answers `429` when enforcing
+///
+/// Requests presenting an `Authorization` header are left to
+/// [`rate_limit_authenticated`] and only counted by address here if authentication
+/// did not accept them.
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let ip = ip_key(&request);
+    if !request.headers().contains_key(header::AUTHORIZATION) {
+        let Some(quota) = ip.and_then(|key| limiter.acquire(&key, Instant::now())) else {
+            return next.run(request).await;
+        };
+        let response =
+            if limiter.refuses(quota) { rate_limited(quota) } else { next.run(request).await };
+        return report(response, quota, &limiter);
+    }
+    // Spares the token lookup of clients that ran out trying tokens
+    let exhausted = ip.as_deref().and_then(|key| limiter.exhausted(key, Instant::now()));
+    if let Some(quota) = exhausted.filter(|quota| limiter.refuses(*quota)) {
+        return report(rate_limited(quota), quota, &limiter);
+    }
+    let token_quota = TokenQuota::default();
+    request.extensions_mut().insert(token_quota.clone());
+    let response = next.run(request).await;
+    let counted = token_quota.0.lock().ok().and_then(|mut quota| quota.take());
+    match counted.or_else(|| ip.and_then(|key| limiter.acquire(&key, Instant::now()))) {
+        Some(quota) => report(response, quota, &limiter),
+        None => response,
+    }
+}
+
+/// Middleware counting the requests that authenticated with an API token by token,
+/// for [`rate_limit`] to report; past the limit it answers `429` when enforcing
+pub async fn rate_limit_authenticated(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let extensions = request.extensions();
+    let (Some(Authenticated { token_id, .. }), Some(TokenQuota(slot))) =
+        (extensions.get::<Authenticated>(), extensions.get::<TokenQuota>())
+    else {
+        return next.run(request).await;
+    };
+    let Some(quota) = limiter.acquire(&format!("token:{}", token_id.value()), Instant::now())
+    else {
+        return next.run(request).await;
+    };
+    if let Ok(mut slot) = slot.lock() {
+        *slot = Some(quota);
+    }
+    if limiter.refuses(quota) {
+        return rate_limited(quota);
+    }
+    next.run(request).await
+}
+
+fn rate_limited(quota: Quota) -> Response {
+    let message = format!("Rate limit of {} requests exceeded", quota.limit);
+    ApiError::new(StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED", message).into_response()
+}
+
+/// `response` with `quota` in the `X-RateLimit-*` headers, and `Retry-After` when
+/// the request was refused
+fn report(mut response: Response, quota: Quota, limiter: &RateLimiter) -> Response {
+    let reset = quota.reset.as_secs() + u64::from(quota.reset.subsec_nanos() > 0);
+    let headers = response.headers_mut();
+    headers.insert(X_RATE_LIMIT_LIMIT, HeaderValue::from(quota.limit));
+    headers.insert(X_RATE_LIMIT_REMAINING, HeaderValue::from(quota.remaining));
+    headers.insert(X_RATE_LIMIT_RESET, HeaderValue::from(reset));
+    if limiter.refuses(quota) {
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(reset));
+    }
+    response
+}
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod tests {
+    use super::*;
+    use crate::features::api_token::domain::ApiTokenId;
+    use crate::shared::domain::UserId;
+    use axum::{routing::get, Router};
+    use std::net::IpAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    /// Tokens the stand-in for authentication accepts
+    const TOKENS: [&str; 2] = ["secret", "other"];
+
+    /// Router behind both middleware, authenticating `TOKENS` and rejecting other
+    /// tokens with `401`, with the number of tokens it looked up
+    fn app(enforce: bool) -> (Router, Arc<AtomicUsize>) {
+        let policy = RateLimitPolicy { limit: 3, window: Duration::from_mins(1), enforce };
+        let limiter = Arc::new(RateLimiter::new(policy));
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let authenticate = {
+            let lookups = Arc::clone(&lookups);
+            move |mut request: Request, next: Next| {
+                let lookups = Arc::clone(&lookups);
+                async move {
+                    let Some(authorization) = request.headers().get(header::AUTHORIZATION) else {
+                        return next.run(request).await;
+                    };
+                    lookups.fetch_add(1, Ordering::Relaxed);
+                    let token = authorization.to_str().ok().and_then(|v| v.split_once(' '));
+                    let Some((_, token)) = token.filter(|(_, token)| TOKENS.contains(token))
+                    else {
+                        return StatusCode::UNAUTHORIZED.into_response();
+                    };
+                    let token_id = ApiTokenId::from_trusted(token.to_owned());
+                    let user = Authenticated { user_id: UserId::generate(), token_id };
+                    request.extensions_mut().insert(user);
+                    next.run(request).await
+                }
+            }
+        };
+        let router = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::clone(&limiter),
+                rate_limit_authenticated,
+            ))
+            .layer(axum::middleware::from_fn(authenticate))
+            .layer(axum::middleware::from_fn_with_state(limiter, rate_limit));
+        (router, lookups)
+    }
+
+    async fn call(
+        app: &Router,
+        authorization: Option<&str>,
+        client: Option<IpAddr>,
+    ) -> Response {
+        let mut request = Request::get("/");
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        if let Some(client) = client {
+            request = request.extension(ClientIp(client));
+        }
+        let request = request.body(axum::body::Body::empty()).expect("request");
+        app.clone().oneshot(request).await.expect("response")
+    }
+
+    fn header_value(response: &Response, name: &HeaderName) -> Option<u64> {
+        response.headers().get(name)?.to_str().ok()?.parse().ok()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn remaining_should_decrease_per_request_and_reset_after_the_window() {
+        let client = Some(IpAddr::from([203, 0, 113, 7]));
+        let (app, _) = app(false);
+
+        let mut remaining = Vec::new();
+        for _ in 0..5 {
+            let response = call(&app, None, client).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(header_value(&response, &X_RATE_LIMIT_LIMIT), Some(3));
+            remaining.push(header_value(&response, &X_RATE_LIMIT_REMAINING));
+        }
+        // Reporting only: requests past the limit still go through
+        assert_eq!(remaining, [Some(2), Some(1), Some(0), Some(0), Some(0)]);
+
+        tokio::time::advance(Duration::from_secs(45)).await;
+        let response = call(&app, None, client).await;
+        assert_eq!(header_value(&response, &X_RATE_LIMIT_RESET), Some(15));
+        tokio::time::advance(Duration::from_secs(15)).await;
+        let response = call(&app, None, client).await;
+        assert_eq!(header_value(&response, &X_RATE_LIMIT_REMAINING), Some(2));
+        assert_eq!(header_value(&response, &X_RATE_LIMIT_RESET), Some(60));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn enforced_limits_should_answer_429_with_the_headers() {
+        let client = Some(IpAddr::from([203, 0, 113, 7]));
+        let (app, _) = app(true);
+        for remaining in [2, 1, 0] {
+            let response = call(&app, Some("Bearer secret"), client).await;
+            assert_eq!(header_value(&response, &X_RATE_LIMIT_REMAINING), Some(remaining));
+        }
+        let limited = call(&app, Some("Bearer secret"), client).await;
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header_value(&limited, &X_RATE_LIMIT_REMAINING), Some(0));
+        assert_eq!(header_value(&limited, &X_RATE_LIMIT_RESET), Some(60));
+        assert_eq!(header_value(&limited, &header::RETRY_AFTER), Some(60));
+
+        // Another token, and the address itself, have buckets of their own
+        let other = call(&app, Some("bearer other"), client).await;
+        assert_eq!(header_value(&other, &X_RATE_LIMIT_REMAINING), Some(2));
+        let anonymous = call(&app, None, client).await;
+        assert_eq!(header_value(&anonymous, &X_RATE_LIMIT_REMAINING), Some(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rejected_tokens_should_be_counted_by_address_and_refused_before_lookup() {
+        let client = Some(IpAddr::from([203, 0, 113, 7]));
+        let (app, lookups) = app(true);
+        for (i, remaining) in [2, 1, 0].into_iter().enumerate() {
+            let response = call(&app, Some(&format!("Bearer made-up-{i}")), client).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(header_value(&response, &X_RATE_LIMIT_REMAINING), Some(remaining));
+        }
+        let limited = call(&app, Some("Bearer made-up-3"), client).await;
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header_value(&limited, &header::RETRY_AFTER), Some(60));
+        assert_eq!(lookups.load(Ordering::Relaxed), 3);
+        assert_eq!(call(&app, None, client).await.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn unidentified_clients_should_get_no_headers() {
+        let (app, _) = app(true);
+        for authorization in [None, Some("Bearer made-up")] {
+            let response = call(&app, authorization, None).await;
+            assert!(response.headers().get(X_RATE_LIMIT_LIMIT).is_none());
+        }
+        assert_eq!(call(&app, None, None).await.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,192 @@
+//! Public location of the application, for links in responses
+//!
+//! Behind a reverse proxy the application may be reachable under another scheme, host
+//! and path prefix than it sees itself (e.g. `https://example.com/todo-api/`). Links
+//! are built from the [`RequestContext`] of the request: `PUBLIC_BASE_URL` by default,
+//! overridden per request by `X-Forwarded-Proto`, `X-Forwarded-Host` and
+//! `X-Forwarded-Prefix` when `TRUST_PROXY_HEADERS` is set. Without either, links
+//! are absolute paths.
+
+use crate::shared::infrastructure::http::Negotiated;
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use std::convert::Infallible;
+
+/// Where the client reached the application
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestContext {
+    /// Scheme and authority (`https://example.com`), when known
+    origin: Option<String>,
+    /// Path the application is mounted under (`/todo-api`), empty at the root
+    prefix: String,
+}
+
+impl RequestContext {
+    /// Context of a deployment at `base_url` (e.g. `https://example.com/todo-api`);
+    /// empty for a deployment at the root whose origin is unknown
+    ///
+    /// # Errors
+    /// Fails when `base_url` is not an `http` or `https` URL without query or fragment.
+    pub fn from_base_url(base_url: &str) -> anyhow::Result<Self> {
+        if base_url.is_empty() {
+            return Ok(Self::default());
+        }
+        let invalid = || {
+            anyhow::anyhow!(
+                "Invalid PUBLIC_BASE_URL={base_url:?}: expected an http(s) URL such as \
+                 https://example.com/todo-api"
+            )
+        };
+        let (scheme, rest) = base_url.split_once("://").ok_or_else(invalid)?;
+        let (host, path) = rest.find('/').map_or((rest, ""), |at| rest.split_at(at));
+        let scheme = valid_scheme(scheme).ok_or_else(invalid)?;
+        let host = valid_host(host).ok_or_else(invalid)?;
+        let prefix = valid_prefix(path).ok_or_else(invalid)?;
+        Ok(Self { origin: Some(format!("{scheme}://{host}")), prefix })
+    }
+
+    /// Link to `path` (starting with `/`) as the client must request it
+    #[must_use]
+    pub fn link(&self, path: &str) -> String {
+        format!("{}{}{path}", self.origin.as_deref().unwrap_or_default(), self.prefix)
+    }
+
+    /// This context overridden by the forwarding headers of a proxy
+    fn forwarded(&self, headers: &HeaderMap) -> Self {
+        let first = |name| {
+            let value = headers.get(name)?.to_str().ok()?;
+            value.split(',').next().map(str::trim)
+        };
+        let mut context = self.clone();
+        if let Some(prefix) = first("x-forwarded-prefix").and_then(valid_prefix) {
+            context.prefix = prefix;
+        }
+        if let Some(host) = first("x-forwarded-host").and_then(valid_host) {
+            let scheme = first("x-forwarded-proto").and_then(valid_scheme).unwrap_or("http");
+            context.origin = Some(format!("{scheme}://{host}"));
+        }
+        context
+    }
+}
+
+/// Falls back to the default context when the middleware did not run
+impl<S: Send + Sync> FromRequestParts<S> for RequestContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+        Ok(parts.extensions.get::<Self>().cloned().unwrap_or_default())
+    }
+}
+
+/// How the [`RequestContext`] of each request is determined
+#[derive(Debug, Clone)]
+pub struct ContextSource {
+    /// Context of the configured public base URL
+    pub base: RequestContext,
+    /// Honour `X-Forwarded-*` headers; only safe behind a proxy that sets them
+    pub trust_proxy_headers: bool,
+}
+
+/// Middleware storing the [`RequestContext`] of the request in its extensions
+pub async fn capture_request_context(
+    State(source): State<ContextSource>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let context = if source.trust_proxy_headers {
+        source.base.forwarded(request.headers())
+    } else {
+        source.base
+    };
+    request.extensions_mut().insert(context);
+    next.run(request).await
+}
+
+/// `201 Created` with `body` and a `Location` header linking to `path`, or the 500
+/// of a body that could not be encoded
+pub fn created<T: Serialize>(context: &RequestContext, path: &str, body: T) -> Response {
+    let mut response = Negotiated(body).with_status(StatusCode::CREATED);
+    if let (StatusCode::CREATED, Ok(location)) =
+        (response.status(), HeaderValue::from_str(&context.link(path)))
+    {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+    response
+}
+
+fn valid_scheme(scheme: &str) -> Option<&str> {
+    ["http", "https"].into_iter().find(|s| scheme.eq_ignore_ascii_case(s))
+}
+
+fn valid_host(host: &str) -> Option<&str> {
+    let valid =
+        !host.is_empty() && host.chars().all(|c| c.is_ascii_alphanumeric() || "-.:[]".contains(c));
+    valid.then_some(host)
+}
+
+/// `prefix` without trailing slashes, when it is a plain absolute path
+fn valid_prefix(prefix: &str) -> Option<String> {
+    let prefix = prefix.trim_end_matches('/');
+    let valid = (prefix.is_empty() || prefix.starts_with('/'))
+        && !prefix.starts_with("//")
+        && prefix.chars().all(|c| c.is_ascii_alphanumeric() || "/-._~%".contains(c));
+    valid.then(|| prefix.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for &(name, value) in pairs {
+            headers.insert(name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn from_base_url_should_split_origin_and_prefix() {
+        let context = RequestContext::from_base_url("https://example.com/todo-api/").ok();
+        assert_eq!(
+            context.map(|c| c.link("/tasks/1")).as_deref(),
+            Some("https://example.com/todo-api/tasks/1")
+        );
+        let context = RequestContext::from_base_url("http://localhost:3000").ok();
+        assert_eq!(
+            context.map(|c| c.link("/tasks/1")).as_deref(),
+            Some("http://localhost:3000/tasks/1")
+        );
+        assert_eq!(RequestContext::default().link("/tasks/1"), "/tasks/1");
+        for invalid in
+            ["example.com", "ftp://example.com", "https://", "https://a b/", "https://x/?q"]
+        {
+            assert!(RequestContext::from_base_url(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn forwarded_should_override_only_valid_headers() {
+        let base = RequestContext::default();
+        let forwarded = base.forwarded(&headers(&[
+            ("x-forwarded-prefix", "/todo-api/"),
+            ("x-forwarded-host", "example.com, internal:8080"),
+            ("x-forwarded-proto", "https"),
+        ]));
+        assert_eq!(forwarded.link("/users/1"), "https://example.com/todo-api/users/1");
+
+        let forwarded = base.forwarded(&headers(&[("x-forwarded-prefix", "/todo-api")]));
+        assert_eq!(forwarded.link("/users/1"), "/todo-api/users/1");
+
+        let forwarded = base.forwarded(&headers(&[
+            ("x-forwarded-prefix", "//evil.example"),
+            ("x-forwarded-host", "evil.example/path"),
+        ]));
+        assert_eq!(forwarded, base);
+    }
+}
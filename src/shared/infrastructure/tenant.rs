@@ -0,0 +1,102 @@
+//! Tenant of a request, from the `x-tenant-id` header
+//!
+//! Every repository call is scoped to the [`TenantId`] of the request, so one tenant
+//! can never read or write the data of another. With `MULTI_TENANCY=true` the header
+//! is required; otherwise a request without it belongs to the default tenant.
+
+use crate::shared::domain::{DomainError, TenantId};
+use crate::shared::infrastructure::http::ApiError;
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+/// Header (and gRPC metadata key) naming the tenant of a request
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+/// Whether requests must name their tenant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TenantPolicy {
+    /// Reject requests without an `x-tenant-id` header instead of using the default
+    /// tenant
+    pub required: bool,
+}
+
+impl TenantPolicy {
+    /// The tenant named by the raw `x-tenant-id` value of a request
+    ///
+    /// # Errors
+    /// `Validation` for a value that is not a valid [`TenantId`], or a missing one when
+    /// the tenant is required.
+    pub fn resolve(self, header: Option<&[u8]>) -> Result<TenantId, DomainError> {
+        match header {
+            Some(value) => {
+                let value = std::str::from_utf8(value).map_err(|_| {
+                    DomainError::Validation(format!("{TENANT_HEADER} must be ASCII"))
+                })?;
+                TenantId::new(value)
+            }
+            None if self.required => {
+                Err(DomainError::Validation(format!("{TENANT_HEADER} header is required")))
+            }
+            None => Ok(TenantId::default()),
+        }
+    }
+}
+
+/// Tenant of the request, extracted by handlers of tenant-scoped routes
+///
+/// Rejects the request with `400 INVALID_TENANT` when the header is invalid, or
+/// missing while required.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantContext(pub TenantId);
+
+/// Middleware storing the [`TenantPolicy`] of the application in the request
+/// extensions; without it every request may fall back to the default tenant
+pub async fn capture_tenant_policy(
+    State(policy): State<TenantPolicy>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    request.extensions_mut().insert(policy);
+    next.run(request).await
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for TenantContext {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, ApiError> {
+        let policy = parts.extensions.get::<TenantPolicy>().copied().unwrap_or_default();
+        let header = parts.headers.get(TENANT_HEADER).map(axum::http::HeaderValue::as_bytes);
+        policy.resolve(header).map(Self).map_err(|e| {
+            let message = match e {
+                DomainError::Validation(message) => message,
+                other => other.to_string(),
+            };
+            ApiError::new(StatusCode::BAD_REQUEST, "INVALID_TENANT", message)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_should_require_a_valid_tenant_only_when_configured() {
+        let optional = TenantPolicy::default();
+        let required = TenantPolicy { required: true };
+        let acme = TenantId::new("acme").ok();
+
+        assert_eq!(optional.resolve(None).ok(), Some(TenantId::default()));
+        assert_eq!(optional.resolve(Some(b"acme")).ok(), acme);
+        assert_eq!(required.resolve(Some(b"acme")).ok(), acme);
+        for policy in [optional, required] {
+            assert!(matches!(policy.resolve(Some(b"Acme")), Err(DomainError::Validation(_))));
+            assert!(matches!(policy.resolve(Some(b"\xff")), Err(DomainError::Validation(_))));
+        }
+        assert!(matches!(required.resolve(None), Err(DomainError::Validation(_))));
+    }
+}
@@ -2,4 +2,5 @@
 
 pub mod application;
 pub mod domain;
+pub mod events;
 pub mod infrastructure;
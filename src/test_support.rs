@@ -0,0 +1,288 @@
+//! Helpers for driving the HTTP layer in tests against in-memory repositories
+
+use crate::app::{build_router, issue_api_token, AppState, InMemoryRepositories};
+use crate::shared::domain::TenantId;
+use crate::shared::infrastructure::config::Config;
+use crate::shared::infrastructure::tenant::TENANT_HEADER;
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+
+/// Build the full application router backed by fresh in-memory repositories
+pub(crate) fn in_memory_app() -> Router {
+    in_memory_app_with(&Config::default())
+}
+
+/// Build the application router from `config`, backed by fresh in-memory repositories
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) fn in_memory_app_with(config: &Config) -> Router {
+    let state = AppState::build(config, &InMemoryRepositories::default()).expect("valid feature config");
+    build_router(&state, config).expect("valid router")
+}
+
+/// [`in_memory_app_with`], with its repositories to [`issue_token`]s from
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) fn in_memory_app_with_repositories(
+    config: &Config,
+) -> (Router, Arc<InMemoryRepositories>) {
+    let repositories = Arc::new(InMemoryRepositories::default());
+    let state = AppState::build(config, repositories.as_ref()).expect("valid feature config");
+    (build_router(&state, config).expect("valid router"), repositories)
+}
+
+/// Issue an API token to the user with `user_id`, as the `issue-token` command does
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) async fn issue_token(repositories: &InMemoryRepositories, user_id: &str) -> String {
+    let issued = issue_api_token(repositories, &TenantId::default(), user_id, "test").await;
+    issued.expect("issued token").plaintext
+}
+
+/// Send a request with an optional JSON body and decode the JSON response
+///
+/// Empty response bodies decode to `Value::Null`.
+pub(crate) async fn send(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    send_as(app, None, method, uri, body).await
+}
+
+/// [`send`] authenticated by the API token `token`
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) async fn send_with_token(
+    app: &Router,
+    token: &str,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {token}"));
+    send_request(app, json_body(request, body).expect("valid request")).await
+}
+
+/// [`send`] on behalf of `tenant`, through the `x-tenant-id` header
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) async fn send_as(
+    app: &Router,
+    tenant: Option<&str>,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(tenant) = tenant {
+        request = request.header(TENANT_HEADER, tenant);
+    }
+    send_request(app, json_body(request, body).expect("valid request")).await
+}
+
+/// Finish `request` with `body`, if any, as JSON
+fn json_body(
+    request: axum::http::request::Builder,
+    body: Option<Value>,
+) -> axum::http::Result<Request<Body>> {
+    match body {
+        Some(json) => request
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json.to_string())),
+        None => request.body(Body::empty()),
+    }
+}
+
+/// Send a prebuilt request and decode the JSON response
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) async fn send_request(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.expect("infallible router");
+    let status = response.status();
+    let bytes =
+        axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("readable body");
+    let json =
+        if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).expect("JSON") };
+    (status, json)
+}
+
+/// Log output shared with the test
+#[derive(Clone, Default)]
+pub(crate) struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Logs {
+    /// Plain-text subscriber writing to these logs
+    pub(crate) fn subscriber(&self) -> impl tracing::Subscriber + Send + Sync + 'static {
+        let writer = self.clone();
+        tracing_subscriber::fmt().with_writer(move || writer.clone()).with_ansi(false).finish()
+    }
+
+    /// Everything logged so far
+    #[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().expect("lock").clone()).expect("UTF-8")
+    }
+}
+
+impl std::io::Write for Logs {
+    #[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().expect("lock").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run `calls` on a current-thread runtime and return the repository calls it made, as
+/// sorted `entity.method outcome: count` lines
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) fn recorded_repository_calls(
+    calls: impl Future<Output = ()>,
+) -> Vec<String> {
+    use crate::shared::infrastructure::instrumentation::REPOSITORY_CALL_SECONDS;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    let debugging = DebuggingRecorder::new();
+    let snapshotter = debugging.snapshotter();
+    metrics::with_local_recorder(&debugging, || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime")
+            .block_on(calls);
+    });
+    let mut calls: Vec<_> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter(|(key, ..)| key.key().name() == REPOSITORY_CALL_SECONDS)
+        .map(|(key, _, _, value)| {
+            let label = |name: &str| {
+                let label = key.key().labels().find(|label| label.key() == name);
+                label.map(|label| label.value().to_owned()).unwrap_or_default()
+            };
+            let count = match value {
+                DebugValue::Histogram(samples) => samples.len(),
+                DebugValue::Counter(_) | DebugValue::Gauge(_) => 0,
+            };
+            format!("{}.{} {}: {count}", label("entity"), label("method"), label("outcome"))
+        })
+        .collect();
+    calls.sort();
+    calls
+}
+
+/// Run `calls` and return the fields recorded on each closed request span
+/// (`error.code=NOT_FOUND entity=Task ...`), then each error counter
+/// (`NOT_FOUND /tasks/{id}: 1`)
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) fn recorded_request_errors(
+    calls: impl Future<Output = ()>,
+) -> (Vec<String>, Vec<String>) {
+    use crate::shared::infrastructure::http::HTTP_ERRORS_TOTAL;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use std::collections::BTreeMap;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+
+    #[derive(Default)]
+    struct Fields(BTreeMap<&'static str, String>);
+
+    impl Visit for Fields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name(), value.to_owned());
+        }
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name(), format!("{value:?}"));
+        }
+    }
+
+    /// Collects the fields recorded after creation on each `request` span
+    #[derive(Clone, Default)]
+    struct RequestSpans(Arc<Mutex<Vec<String>>>);
+
+    impl<S: tracing::Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RequestSpans {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(id).filter(|_| attrs.metadata().name() == "request") {
+                span.extensions_mut().insert(Fields::default());
+            }
+        }
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            let Some(span) = ctx.span(id) else { return };
+            if let Some(fields) = span.extensions_mut().get_mut::<Fields>() {
+                values.record(fields);
+            }
+        }
+        fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+            let Some(span) = ctx.span(&id) else { return };
+            if let Some(Fields(fields)) = span.extensions_mut().remove::<Fields>() {
+                let fields: Vec<_> = fields.iter().map(|(k, v)| format!("{k}={v}")).collect();
+                self.0.lock().expect("lock").push(fields.join(" "));
+            }
+        }
+    }
+
+    let spans = RequestSpans::default();
+    let subscriber = tracing_subscriber::registry().with(spans.clone());
+    let debugging = DebuggingRecorder::new();
+    let snapshotter = debugging.snapshotter();
+    tracing::subscriber::with_default(subscriber, || {
+        metrics::with_local_recorder(&debugging, || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("runtime")
+                .block_on(calls);
+        });
+    });
+    let mut counters: Vec<_> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter(|(key, ..)| key.key().name() == HTTP_ERRORS_TOTAL)
+        .map(|(key, _, _, value)| {
+            let label = |name: &str| {
+                let label = key.key().labels().find(|label| label.key() == name);
+                label.map(|label| label.value().to_owned()).unwrap_or_default()
+            };
+            let count = match value {
+                DebugValue::Counter(count) => count,
+                DebugValue::Gauge(_) | DebugValue::Histogram(_) => 0,
+            };
+            format!("{} {}: {count}", label("code"), label("route"))
+        })
+        .collect();
+    counters.sort();
+    let spans = spans.0.lock().expect("lock").clone();
+    (spans, counters)
+}
+
+/// Send a GET with an optional `If-Modified-Since` and return the status and `Last-Modified`
+#[expect(clippy::expect_used, reason = "test helper; failures should abort the test")]
+pub(crate) async fn get_if_modified_since(
+    app: &Router,
+    uri: &str,
+    since: Option<&str>,
+) -> (StatusCode, Option<String>) {
+    let mut request = Request::get(uri);
+    if let Some(since) = since {
+        request = request.header(header::IF_MODIFIED_SINCE, since);
+    }
+    let request = request.body(Body::empty()).expect("valid request");
+    let response = app.clone().oneshot(request).await.expect("infallible router");
+    let last_modified = response
+        .headers()
+        .get(header::LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    (response.status(), last_modified)
+}
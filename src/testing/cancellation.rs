@@ -0,0 +1,55 @@
+//! Dropping use-case futures part way, as axum does when a client disconnects
+
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::task::Poll;
+
+/// Polls a future a fixed number of times, then drops it unless it finished
+///
+/// Each poll after the first happens once the future is woken, so the future makes
+/// the progress it would in a server until it is dropped at its `polls`-th
+/// suspension point. A use case is cancellation safe when whatever it wrote by then
+/// is consistent, checked by [`at_every_point`](Self::at_every_point) for each point
+/// in turn.
+#[derive(Debug, Clone, Copy)]
+pub struct CancellationSafety {
+    polls: usize,
+}
+
+impl CancellationSafety {
+    /// Drop futures when their `polls`-th poll leaves them pending
+    #[must_use]
+    pub fn after_polls(polls: usize) -> Self {
+        Self { polls }
+    }
+
+    /// Run `future` until it finishes, returning its output, or until it was polled
+    /// as often as allowed, returning `None`
+    pub async fn run<F: Future>(&self, future: F) -> Option<F::Output> {
+        let mut future = pin!(future);
+        let mut polls = 0;
+        poll_fn(|cx| {
+            polls += 1;
+            match future.as_mut().poll(cx) {
+                Poll::Ready(output) => Poll::Ready(Some(output)),
+                Poll::Pending if polls >= self.polls => Poll::Ready(None),
+                Poll::Pending => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// Call `attempt` with harnesses dropping its future after 1, 2, ... polls until
+    /// it reports that the future finished, returning at how many points it was
+    /// dropped
+    ///
+    /// Each attempt sets up its own state, runs the use case with the harness and
+    /// checks the state it finds consistent.
+    pub async fn at_every_point(mut attempt: impl AsyncFnMut(Self) -> bool) -> usize {
+        let mut polls = 1;
+        while !attempt(Self::after_polls(polls)).await {
+            polls += 1;
+        }
+        polls - 1
+    }
+}
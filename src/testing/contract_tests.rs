@@ -0,0 +1,345 @@
+//! HTTP contract of every route, driven against the fake repositories
+//!
+//! Each test pins the exact status and JSON body of a route's success and error
+//! responses, so a change in the application layer that alters what clients receive
+//! fails here. Timestamps and presigned URLs vary between runs and are masked.
+
+use super::*;
+use crate::features::user::domain::has_dependents;
+use crate::test_support::send;
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+const TASK: &str = "00000000-0000-4000-8000-000000000001";
+
+/// Fakes holding user `u-alice` and her open task [`TASK`], and the app on them
+async fn seeded() -> (FakeRepositories, Router) {
+    let fakes = FakeRepositories::default();
+    let tenant = TenantId::default();
+    let alice = UserId::new("u-alice").expect("valid user id");
+    let user = User::new(alice.clone(), "Alice".into(), "alice@example.com").expect("valid user");
+    fakes.users.insert(&tenant, &user).await.expect("seed user");
+    let task = Task::new(TaskId::new(TASK).expect("valid id"), alice, "Buy milk", "2 *l*".into())
+        .expect("valid task");
+    fakes.tasks.insert(&tenant, &task).await.expect("seed task");
+    let app = test_app(&fakes).expect("valid app");
+    (fakes, app)
+}
+
+/// Send a request and return its status and body, timestamps and URLs masked
+async fn call(app: &Router, method: Method, uri: &str, body: Option<Value>) -> (u16, Value) {
+    let (status, mut json) = send(app, method, uri, body).await;
+    mask(&mut json);
+    (status.as_u16(), json)
+}
+
+fn mask(value: &mut Value) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(mask),
+        Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                if key.ends_with("At") || key == "hour" {
+                    *value = json!("<timestamp>");
+                } else if key.ends_with("Url") {
+                    *value = json!("<url>");
+                } else {
+                    mask(value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn error(code: &str, message: &str) -> Value {
+    json!({"code": code, "message": message})
+}
+
+fn alice() -> Value {
+    json!({"id": "u-alice", "name": "Alice", "email": "alice@example.com"})
+}
+
+fn open_task() -> Value {
+    json!({
+        "id": TASK,
+        "userId": "u-alice",
+        "title": "Buy milk",
+        "description": "2 *l*",
+        "completed": false,
+        "updatedAt": "<timestamp>",
+        "checklist": [],
+    })
+}
+
+#[tokio::test]
+async fn post_users() {
+    let (_, app) = seeded().await;
+    let bob = json!({"name": "Bob", "email": "bob@example.com"});
+    let (status, created) = call(&app, Method::POST, "/users", Some(bob)).await;
+    assert_eq!(status, 201);
+    assert_eq!(created, json!({"id": created["id"], "name": "Bob", "email": "bob@example.com"}));
+
+    let taken = json!({"name": "Eve", "email": "alice@example.com"});
+    let conflict = error("ALREADY_EXISTS", "Already exists: User with this email already exists");
+    assert_eq!(call(&app, Method::POST, "/users", Some(taken)).await, (409, conflict));
+
+    let invalid = json!({"name": "", "email": "invalid"});
+    let fields = json!([
+        {"field": "name", "message": "Name cannot be empty"},
+        {"field": "email", "message": "Invalid email format"},
+    ]);
+    let expected = json!({
+        "code": "VALIDATION_ERROR",
+        "message": "Validation error: Name cannot be empty; Invalid email format",
+        "details": {"fields": fields},
+    });
+    assert_eq!(call(&app, Method::POST, "/users", Some(invalid)).await, (422, expected));
+
+    let (status, body) = call(&app, Method::POST, "/users", Some(json!({"name": "Eve"}))).await;
+    assert_eq!((status, &body["code"]), (400, &json!("INVALID_BODY")));
+}
+
+#[tokio::test]
+async fn get_users() {
+    let (fakes, app) = seeded().await;
+    assert_eq!(call(&app, Method::GET, "/users", None).await, (200, json!([alice()])));
+    let fields = json!([{"id": "u-alice", "name": "Alice"}]);
+    assert_eq!(call(&app, Method::GET, "/users?fields=name", None).await, (200, fields));
+    let limit = error("INVALID_QUERY", "limit must be between 1 and 100");
+    assert_eq!(call(&app, Method::GET, "/users?limit=0", None).await, (400, limit));
+
+    fakes.users.fail("find_page", || DomainError::Unavailable("pool exhausted".into()));
+    let busy = error("SERVICE_BUSY", "Service busy, retry later");
+    assert_eq!(call(&app, Method::GET, "/users", None).await, (503, busy));
+}
+
+#[tokio::test]
+async fn get_user() {
+    let (fakes, app) = seeded().await;
+    assert_eq!(call(&app, Method::GET, "/users/u-alice", None).await, (200, alice()));
+    let missing = error("NOT_FOUND", "Not found: User not found");
+    assert_eq!(call(&app, Method::GET, "/users/nobody", None).await, (404, missing));
+
+    fakes.users.fail("find_by_id", || DomainError::Infrastructure("connection reset".into()));
+    let internal = error("INTERNAL_ERROR", "Internal server error");
+    assert_eq!(call(&app, Method::GET, "/users/u-alice", None).await, (500, internal));
+}
+
+#[tokio::test]
+async fn put_user() {
+    let (_, app) = seeded().await;
+    let alicia = json!({"name": "Alicia", "email": "alicia@example.com"});
+    let expected = json!({"id": "u-alice", "name": "Alicia", "email": "alicia@example.com"});
+    let uri = "/users/u-alice";
+    assert_eq!(call(&app, Method::PUT, uri, Some(alicia.clone())).await, (200, expected));
+    let missing = error("NOT_FOUND", "Not found: User not found");
+    assert_eq!(call(&app, Method::PUT, "/users/nobody", Some(alicia)).await, (404, missing));
+}
+
+#[tokio::test]
+async fn delete_user() {
+    let (fakes, app) = seeded().await;
+    let dependents = json!({
+        "code": "HAS_DEPENDENTS",
+        "message": "User owns 1 tasks; pass force=true to delete them too",
+        "details": {"count": 1},
+    });
+    assert_eq!(call(&app, Method::DELETE, "/users/u-alice", None).await, (409, dependents));
+    let dry_run = json!({"dryRun": true, "wouldDelete": {"users": 1, "tasks": 1}});
+    let uri = "/users/u-alice?force=true&dry_run=true";
+    assert_eq!(call(&app, Method::DELETE, uri, None).await, (200, dry_run));
+    let uri = "/users/u-alice?force=true";
+    assert_eq!(call(&app, Method::DELETE, uri, None).await, (204, Value::Null));
+    let missing = error("NOT_FOUND", "Not found: User not found");
+    assert_eq!(call(&app, Method::DELETE, uri, None).await, (404, missing));
+
+    // A feature-defined error keeps its code, status and details
+    fakes.tasks.fail("count_by_user_id", || has_dependents(3, "Three tasks left".into()));
+    let custom =
+        json!({"code": "HAS_DEPENDENTS", "message": "Three tasks left", "details": {"count": 3}});
+    assert_eq!(call(&app, Method::DELETE, "/users/u-bob", None).await, (409, custom));
+}
+
+#[tokio::test]
+async fn email_change() {
+    let (_, app) = seeded().await;
+    let request = json!({"newEmail": "alice@example.org"});
+    let uri = "/users/u-alice/email-change";
+    let accepted = json!({"expiresAt": "<timestamp>"});
+    assert_eq!(call(&app, Method::POST, uri, Some(request)).await, (202, accepted));
+
+    let uri = "/users/email-change/confirm";
+    let unknown = error("NOT_FOUND", "Not found: Email change token not found");
+    let token = json!({"token": "bad"});
+    assert_eq!(call(&app, Method::POST, uri, Some(token)).await, (404, unknown));
+}
+
+#[tokio::test]
+async fn post_tasks() {
+    let (fakes, app) = seeded().await;
+    let task = json!({"userId": "u-alice", "title": "  Walk  the dog ", "description": ""});
+    let (status, created) = call(&app, Method::POST, "/tasks", Some(task.clone())).await;
+    assert_eq!(status, 201);
+    let expected = json!({
+        "id": created["id"],
+        "userId": "u-alice",
+        "title": "Walk the dog",
+        "description": "",
+        "completed": false,
+        "updatedAt": "<timestamp>",
+        "checklist": [],
+    });
+    assert_eq!(created, expected);
+
+    let empty = json!({"userId": "u-alice", "title": " ", "description": ""});
+    let (status, body) = call(&app, Method::POST, "/tasks", Some(empty)).await;
+    assert_eq!((status, &body["code"]), (422, &json!("VALIDATION_ERROR")));
+
+    fakes.tasks.fail("insert", || DomainError::NotFound("User not found".into()));
+    let missing = error("NOT_FOUND", "Not found: User not found");
+    assert_eq!(call(&app, Method::POST, "/tasks", Some(task)).await, (404, missing));
+}
+
+#[tokio::test]
+async fn get_tasks() {
+    let (fakes, app) = seeded().await;
+    assert_eq!(call(&app, Method::GET, "/tasks", None).await, (200, json!([open_task()])));
+    let mut embedded = open_task();
+    embedded["user"] = json!({"id": "u-alice", "name": "Alice"});
+    let uri = "/tasks?embed=user";
+    assert_eq!(call(&app, Method::GET, uri, None).await, (200, json!([embedded])));
+    let unknown = error("INVALID_QUERY", "Unsupported embed 'owner'; supported embeds: user");
+    assert_eq!(call(&app, Method::GET, "/tasks?embed=owner", None).await, (400, unknown));
+
+    fakes.tasks.fail("find_page", || DomainError::Infrastructure("disk full".into()));
+    let internal = error("INTERNAL_ERROR", "Internal server error");
+    assert_eq!(call(&app, Method::GET, "/tasks", None).await, (500, internal));
+}
+
+#[tokio::test]
+async fn export_tasks() {
+    let (_, app) = seeded().await;
+    let uri = "/tasks/export?user_id=u-alice&completed=false";
+    assert_eq!(call(&app, Method::GET, uri, None).await, (200, json!([open_task()])));
+    let message = "Search text must be at least 2 characters";
+    let mut short = error("INVALID_QUERY", message);
+    short["details"] = json!({"fields": [{"field": "q", "message": message}]});
+    assert_eq!(call(&app, Method::GET, "/tasks/export?q=m", None).await, (400, short));
+}
+
+#[tokio::test]
+async fn get_task() {
+    let (_, app) = seeded().await;
+    let uri = format!("/tasks/{TASK}");
+    assert_eq!(call(&app, Method::GET, &uri, None).await, (200, open_task()));
+    let mut rendered = open_task();
+    rendered["descriptionHtml"] = json!("<p>2 <em>l</em></p>\n");
+    let with_html = format!("{uri}?embed=description_html");
+    assert_eq!(call(&app, Method::GET, &with_html, None).await, (200, rendered));
+    let missing = error("NOT_FOUND", "Not found: Task not found");
+    let unknown = format!("/tasks/{}", TaskId::generate().value());
+    assert_eq!(call(&app, Method::GET, &unknown, None).await, (404, missing));
+}
+
+#[tokio::test]
+async fn put_task() {
+    let (_, app) = seeded().await;
+    let uri = format!("/tasks/{TASK}");
+    let replaced = json!({"userId": "u-alice", "title": "Buy oat milk", "description": "2 *l*"});
+    let mut expected = open_task();
+    expected["title"] = json!("Buy oat milk");
+    assert_eq!(call(&app, Method::PUT, &uri, Some(replaced)).await, (200, expected));
+
+    let stolen = json!({"userId": "u-bob", "title": "Mine now", "description": ""});
+    let (status, body) = call(&app, Method::PUT, &uri, Some(stolen)).await;
+    assert_eq!((status, &body["code"]), (409, &json!("CONFLICT")));
+}
+
+#[tokio::test]
+async fn complete_and_delete_task() {
+    let (_, app) = seeded().await;
+    let complete = format!("/tasks/{TASK}/complete");
+    let mut completed = open_task();
+    completed["completed"] = json!(true);
+    assert_eq!(call(&app, Method::PATCH, &complete, None).await, (200, completed));
+    let again = error("CONFLICT", "Conflict: Task is already completed");
+    assert_eq!(call(&app, Method::PATCH, &complete, None).await, (409, again));
+
+    let uri = format!("/tasks/{TASK}");
+    assert_eq!(call(&app, Method::DELETE, &uri, None).await, (204, Value::Null));
+    let missing = error("NOT_FOUND", "Not found: Task not found");
+    assert_eq!(call(&app, Method::DELETE, &uri, None).await, (404, missing.clone()));
+    assert_eq!(call(&app, Method::PATCH, &complete, None).await, (404, missing));
+}
+
+#[tokio::test]
+async fn task_attachments() {
+    let (_, app) = seeded().await;
+    let uri = format!("/tasks/{TASK}/attachments");
+    let file = json!({"filename": "receipt.png", "contentType": "image/png", "size": 512});
+    let (status, created) = call(&app, Method::POST, &uri, Some(file)).await;
+    assert_eq!(status, 201);
+    let id = created["attachment"]["id"].clone();
+    let attachment = json!({
+        "id": id,
+        "taskId": TASK,
+        "filename": "receipt.png",
+        "contentType": "image/png",
+        "size": 512,
+        "createdAt": "<timestamp>",
+    });
+    let expected = json!({
+        "attachment": attachment,
+        "uploadUrl": "<url>",
+        "uploadExpiresAt": "<timestamp>",
+    });
+    assert_eq!(created, expected);
+
+    let mut listed = attachment;
+    listed["downloadUrl"] = json!("<url>");
+    assert_eq!(call(&app, Method::GET, &uri, None).await, (200, json!([listed])));
+
+    let huge = json!({"filename": "movie.png", "contentType": "image/png", "size": 1u64 << 40});
+    let too_large = json!({
+        "code": "ATTACHMENT_TOO_LARGE",
+        "message": "Attachment is larger than 10485760 bytes",
+        "details": {"maxSizeBytes": 10_485_760},
+    });
+    assert_eq!(call(&app, Method::POST, &uri, Some(huge)).await, (422, too_large));
+
+    let one = format!("{uri}/{}", id.as_str().unwrap_or_default());
+    assert_eq!(call(&app, Method::DELETE, &one, None).await, (204, Value::Null));
+    let missing = error("NOT_FOUND", "Not found: Attachment not found");
+    assert_eq!(call(&app, Method::DELETE, &one, None).await, (404, missing));
+}
+
+#[tokio::test]
+async fn task_stats() {
+    let (_, app) = seeded().await;
+    let (status, body) = call(&app, Method::GET, "/stats/tasks?window=2h", None).await;
+    assert_eq!(status, 200);
+    let buckets = json!([
+        {"hour": "<timestamp>", "created": 0, "completed": 0},
+        {"hour": "<timestamp>", "created": 1, "completed": 0},
+    ]);
+    assert_eq!(body, json!({"window": "2h", "buckets": buckets}));
+    let invalid = error(
+        "INVALID_QUERY",
+        "window must be between 1h and 30d, as <hours>h or <days>d (got '1y')",
+    );
+    assert_eq!(call(&app, Method::GET, "/stats/tasks?window=1y", None).await, (400, invalid));
+}
+
+#[tokio::test]
+async fn admin_overview() {
+    let (_, app) = seeded().await;
+    let overview = json!([{"user": alice(), "recentTasks": [open_task()]}]);
+    assert_eq!(call(&app, Method::GET, "/admin/overview", None).await, (200, overview));
+}
+
+#[tokio::test]
+async fn unknown_route() {
+    let (_, app) = seeded().await;
+    let (status, _) = send(&app, Method::GET, "/nowhere", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
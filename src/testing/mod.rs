@@ -0,0 +1,589 @@
+//! Fake repositories for testing the HTTP layer without a database (`testing` feature)
+//!
+//! Every fake implements its repository port on top of the in-memory repository, so
+//! it returns whatever it was seeded with through the port's own methods, and can be
+//! told to fail any method with a canned [`DomainError`] or to answer slowly:
+//!
+//! ```
+//! use axum_ddd_template::shared::domain::DomainError;
+//! use axum_ddd_template::testing::{test_app, FakeRepositories};
+//!
+//! let fakes = FakeRepositories::default();
+//! fakes.users.fail("find_page", || DomainError::Unavailable("pool exhausted".into()));
+//! // GET /users answers 503 SERVICE_BUSY
+//! let app = test_app(&fakes)?;
+//! # anyhow::Ok(())
+//! ```
+//!
+//! Only the repository ports are faked: the blob storage is the local one of the
+//! default configuration, no email change notifier is configured and email is only
+//! logged. [`RecordingEmailSender`] fakes the email port for use cases under test,
+//! and [`CancellationSafety`] drops their futures part way.
+
+use crate::app::{build_router, AppState, RepositoryProvider};
+use crate::features::api_token::domain::ApiTokenRepository;
+use crate::features::api_token::infrastructure::InMemoryApiTokenRepository;
+use crate::features::task::domain::{
+    Attachment, AttachmentId, AttachmentRepository, CompleteOutcome, HourlyTaskCounts, Task,
+    TaskFilter, TaskId, TaskRepository, TaskSort, TaskViewPreferencesRepository, UnitOfWork,
+    UpsertOutcome,
+};
+use crate::features::task::infrastructure::{
+    InMemoryAttachmentRepository, InMemoryTaskRepository, InMemoryTaskViewPreferencesRepository,
+    InMemoryUnitOfWork,
+};
+use crate::features::user::domain::{
+    EmailChangeRepository, PendingEmailChange, SyncWatermark, User, UserDependents,
+    UserRepository, UserTransaction,
+};
+use crate::features::user::infrastructure::{
+    InMemoryEmailChangeRepository, InMemoryUserRepository,
+};
+use crate::features::webhook::domain::{WebhookDeliveries, WebhookRepository};
+use crate::features::webhook::infrastructure::InMemoryWebhookRepository;
+use crate::shared::domain::{DomainError, Email, EmailSender, Page, TenantId, UserId};
+use crate::shared::infrastructure::config::Config;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+mod cancellation;
+pub use cancellation::CancellationSafety;
+
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+mod contract_tests;
+#[cfg(test)]
+#[expect(clippy::expect_used, reason = "expect is acceptable in tests")]
+pub(crate) mod repository_contract;
+
+/// Builds the error a failing method returns, once per call
+type ErrorFactory = Arc<dyn Fn() -> DomainError + Send + Sync>;
+
+/// Canned errors and delays of a fake, by method name
+#[derive(Clone, Default)]
+struct Faults {
+    errors: Arc<Mutex<HashMap<&'static str, ErrorFactory>>>,
+    delays: Arc<Mutex<HashMap<&'static str, Duration>>>,
+}
+
+impl Faults {
+    fn set(&self, method: &'static str, error: ErrorFactory) {
+        self.errors.lock().unwrap_or_else(PoisonError::into_inner).insert(method, error);
+    }
+
+    fn set_delay(&self, method: &'static str, delay: Duration) {
+        self.delays.lock().unwrap_or_else(PoisonError::into_inner).insert(method, delay);
+    }
+
+    /// The canned error of `method`, if any
+    fn error(&self, method: &'static str) -> Option<DomainError> {
+        let errors = self.errors.lock().unwrap_or_else(PoisonError::into_inner);
+        errors.get(method).cloned().map(|error| error())
+    }
+
+    /// Wait out the delay of `method`, if any, then fail with its canned error, if
+    /// any, or run `call`
+    async fn run<T>(
+        &self,
+        method: &'static str,
+        call: impl Future<Output = Result<T, DomainError>>,
+    ) -> Result<T, DomainError> {
+        let delay = self.delays.lock().unwrap_or_else(PoisonError::into_inner).get(method).copied();
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        match self.error(method) {
+            Some(error) => Err(error),
+            None => call.await,
+        }
+    }
+
+    /// Pass `items` through, then fail with the canned error of `method`, if any: a
+    /// stream failing after it started
+    fn run_stream<'a, T: Send + 'a>(
+        &self,
+        method: &'static str,
+        items: BoxStream<'a, Result<T, DomainError>>,
+    ) -> BoxStream<'a, Result<T, DomainError>> {
+        match self.error(method) {
+            Some(error) => Box::pin(items.chain(stream::once(async { Err(error) }))),
+            None => items,
+        }
+    }
+}
+
+/// Defines a fake wrapping the in-memory repository `$inner`
+macro_rules! fake {
+    ($(#[$doc:meta])* $name:ident($inner:ty)) => {
+        $(#[$doc])*
+        #[derive(Default)]
+        pub struct $name {
+            inner: Arc<$inner>,
+            faults: Faults,
+        }
+
+        impl $name {
+            /// Make every later call of the port method named `method` fail with `error()`;
+            /// streaming methods fail after yielding what they found
+            pub fn fail(
+                &self,
+                method: &'static str,
+                error: impl Fn() -> DomainError + Send + Sync + 'static,
+            ) -> &Self {
+                self.faults.set(method, Arc::new(error));
+                self
+            }
+
+            /// Make every later call of the non-streaming port method named `method`
+            /// take `delay` before answering
+            pub fn slow(&self, method: &'static str, delay: Duration) -> &Self {
+                self.faults.set_delay(method, delay);
+                self
+            }
+        }
+    };
+}
+
+fake!(
+    /// Fake [`UserRepository`]
+    FakeUserRepository(InMemoryUserRepository)
+);
+fake!(
+    /// Fake [`EmailChangeRepository`]
+    FakeEmailChangeRepository(InMemoryEmailChangeRepository)
+);
+fake!(
+    /// Fake [`TaskRepository`], including its [`UserDependents`] methods
+    FakeTaskRepository(InMemoryTaskRepository)
+);
+fake!(
+    /// Fake [`AttachmentRepository`]
+    FakeAttachmentRepository(InMemoryAttachmentRepository)
+);
+
+#[async_trait::async_trait]
+impl UserRepository for FakeUserRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        self.faults.run("find_by_id", self.inner.find_by_id(tenant, id)).await
+    }
+
+    async fn find_by_ids(
+        &self,
+        tenant: &TenantId,
+        ids: &[UserId],
+    ) -> Result<Vec<User>, DomainError> {
+        self.faults.run("find_by_ids", self.inner.find_by_ids(tenant, ids)).await
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        email_domain: Option<&str>,
+        page: Page,
+    ) -> Result<Vec<User>, DomainError> {
+        self.faults.run("find_page", self.inner.find_page(tenant, email_domain, page)).await
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<User>, DomainError> {
+        self.faults.run("find_all_unbounded", self.inner.find_all_unbounded(tenant)).await
+    }
+
+    async fn find_updated_since(
+        &self,
+        tenant: &TenantId,
+        since: &SyncWatermark,
+        limit: u32,
+    ) -> Result<Vec<User>, DomainError> {
+        let find = self.inner.find_updated_since(tenant, since, limit);
+        self.faults.run("find_updated_since", find).await
+    }
+
+    async fn insert(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        self.faults.run("insert", self.inner.insert(tenant, user)).await
+    }
+
+    async fn update(&self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        self.faults.run("update", self.inner.update(tenant, user)).await
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &UserId) -> Result<bool, DomainError> {
+        self.faults.run("delete", self.inner.delete(tenant, id)).await
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        self.faults.run("delete_returning", self.inner.delete_returning(tenant, id)).await
+    }
+
+    async fn begin(&self) -> Result<Box<dyn UserTransaction + '_>, DomainError> {
+        let inner = self.faults.run("begin", self.inner.begin()).await?;
+        Ok(Box::new(FakeUserTransaction { inner, faults: self.faults.clone() }))
+    }
+}
+
+/// Transaction of [`FakeUserRepository`], failing and slowed down as the repository
+/// methods of the same name are
+struct FakeUserTransaction<'a> {
+    inner: Box<dyn UserTransaction + 'a>,
+    faults: Faults,
+}
+
+#[async_trait::async_trait]
+impl UserTransaction for FakeUserTransaction<'_> {
+    async fn find_by_id_for_update(
+        &mut self,
+        tenant: &TenantId,
+        id: &UserId,
+    ) -> Result<Option<User>, DomainError> {
+        let find = self.inner.find_by_id_for_update(tenant, id);
+        self.faults.run("find_by_id_for_update", find).await
+    }
+
+    async fn update(&mut self, tenant: &TenantId, user: &User) -> Result<(), DomainError> {
+        self.faults.run("update", self.inner.update(tenant, user)).await
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), DomainError> {
+        self.faults.run("commit", self.inner.commit()).await
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailChangeRepository for FakeEmailChangeRepository {
+    async fn save(&self, change: &PendingEmailChange) -> Result<(), DomainError> {
+        self.faults.run("save", self.inner.save(change)).await
+    }
+
+    async fn find_by_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<PendingEmailChange>, DomainError> {
+        self.faults.run("find_by_token_hash", self.inner.find_by_token_hash(token_hash)).await
+    }
+
+    async fn mark_confirmed(&self, token_hash: &str) -> Result<bool, DomainError> {
+        self.faults.run("mark_confirmed", self.inner.mark_confirmed(token_hash)).await
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskRepository for FakeTaskRepository {
+    async fn find_by_id(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        self.faults.run("find_by_id", self.inner.find_by_id(tenant, id)).await
+    }
+
+    async fn find_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<Vec<Task>, DomainError> {
+        self.faults.run("find_by_user_id", self.inner.find_by_user_id(tenant, user_id)).await
+    }
+
+    async fn exists_open_with_title(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+        title: &str,
+    ) -> Result<Option<TaskId>, DomainError> {
+        let call = self.inner.exists_open_with_title(tenant, user_id, title);
+        self.faults.run("exists_open_with_title", call).await
+    }
+
+    async fn find_page(
+        &self,
+        tenant: &TenantId,
+        filter: &TaskFilter,
+        sort: TaskSort,
+        page: Page,
+    ) -> Result<Vec<Task>, DomainError> {
+        self.faults.run("find_page", self.inner.find_page(tenant, filter, sort, page)).await
+    }
+
+    fn stream_filtered<'a>(
+        &'a self,
+        tenant: &'a TenantId,
+        filter: &'a TaskFilter,
+    ) -> BoxStream<'a, Result<Task, DomainError>> {
+        self.faults.run_stream("stream_filtered", self.inner.stream_filtered(tenant, filter))
+    }
+
+    async fn find_all_unbounded(&self, tenant: &TenantId) -> Result<Vec<Task>, DomainError> {
+        self.faults.run("find_all_unbounded", self.inner.find_all_unbounded(tenant)).await
+    }
+
+    async fn find_recent_by_users(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+        per_user_limit: u32,
+    ) -> Result<Vec<Task>, DomainError> {
+        let call = self.inner.find_recent_by_users(tenant, user_ids, per_user_limit);
+        self.faults.run("find_recent_by_users", call).await
+    }
+
+    async fn insert(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        self.faults.run("insert", self.inner.insert(tenant, task)).await
+    }
+
+    async fn update(&self, tenant: &TenantId, task: &Task) -> Result<Task, DomainError> {
+        self.faults.run("update", self.inner.update(tenant, task)).await
+    }
+
+    async fn upsert(&self, tenant: &TenantId, task: &Task) -> Result<UpsertOutcome, DomainError> {
+        self.faults.run("upsert", self.inner.upsert(tenant, task)).await
+    }
+
+    async fn complete_if_open(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<CompleteOutcome, DomainError> {
+        self.faults.run("complete_if_open", self.inner.complete_if_open(tenant, id)).await
+    }
+
+    async fn delete(&self, tenant: &TenantId, id: &TaskId) -> Result<bool, DomainError> {
+        self.faults.run("delete", self.inner.delete(tenant, id)).await
+    }
+
+    async fn delete_returning(
+        &self,
+        tenant: &TenantId,
+        id: &TaskId,
+    ) -> Result<Option<Task>, DomainError> {
+        self.faults.run("delete_returning", self.inner.delete_returning(tenant, id)).await
+    }
+
+    async fn hourly_counts(
+        &self,
+        tenant: &TenantId,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<HourlyTaskCounts>, DomainError> {
+        self.faults.run("hourly_counts", self.inner.hourly_counts(tenant, since)).await
+    }
+}
+
+#[async_trait::async_trait]
+impl UserDependents for FakeTaskRepository {
+    async fn count_by_user_id(
+        &self,
+        tenant: &TenantId,
+        user_id: &UserId,
+    ) -> Result<u64, DomainError> {
+        self.faults.run("count_by_user_id", self.inner.count_by_user_id(tenant, user_id)).await
+    }
+
+    async fn count_by_user_ids(
+        &self,
+        tenant: &TenantId,
+        user_ids: &[UserId],
+    ) -> Result<HashMap<UserId, u64>, DomainError> {
+        let count = self.inner.count_by_user_ids(tenant, user_ids);
+        self.faults.run("count_by_user_ids", count).await
+    }
+}
+
+#[async_trait::async_trait]
+impl AttachmentRepository for FakeAttachmentRepository {
+    async fn insert(&self, tenant: &TenantId, attachment: &Attachment) -> Result<(), DomainError> {
+        self.faults.run("insert", self.inner.insert(tenant, attachment)).await
+    }
+
+    async fn find_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<Vec<Attachment>, DomainError> {
+        self.faults.run("find_by_task", self.inner.find_by_task(tenant, task_id)).await
+    }
+
+    async fn delete(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+        id: &AttachmentId,
+    ) -> Result<bool, DomainError> {
+        self.faults.run("delete", self.inner.delete(tenant, task_id, id)).await
+    }
+
+    async fn delete_by_task(
+        &self,
+        tenant: &TenantId,
+        task_id: &TaskId,
+    ) -> Result<u64, DomainError> {
+        self.faults.run("delete_by_task", self.inner.delete_by_task(tenant, task_id)).await
+    }
+
+    async fn queued_blob_deletions(&self, limit: u32) -> Result<Vec<String>, DomainError> {
+        self.faults.run("queued_blob_deletions", self.inner.queued_blob_deletions(limit)).await
+    }
+
+    async fn dequeue_blob_deletion(&self, storage_key: &str) -> Result<(), DomainError> {
+        let call = self.inner.dequeue_blob_deletion(storage_key);
+        self.faults.run("dequeue_blob_deletion", call).await
+    }
+}
+
+/// An email [`RecordingEmailSender`] accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentEmail {
+    /// Recipient
+    pub to: String,
+    /// Subject line
+    pub subject: String,
+    /// Plain-text body
+    pub body: String,
+}
+
+/// Fake [`EmailSender`] recording what it sends; `fail("send", ..)` makes it fail
+#[derive(Default)]
+pub struct RecordingEmailSender {
+    sent: Mutex<Vec<SentEmail>>,
+    attempts: AtomicUsize,
+    sent_one: Notify,
+    faults: Faults,
+}
+
+impl RecordingEmailSender {
+    /// Make every later call of `method` fail with `error()`
+    pub fn fail(
+        &self,
+        method: &'static str,
+        error: impl Fn() -> DomainError + Send + Sync + 'static,
+    ) -> &Self {
+        self.faults.set(method, Arc::new(error));
+        self
+    }
+
+    /// Emails sent so far, oldest first
+    pub fn sent(&self) -> Vec<SentEmail> {
+        self.sent.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Calls of `send` so far, failed ones included
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::SeqCst)
+    }
+
+    /// Wait until at least `count` emails were sent, e.g. by a background task, and
+    /// return them
+    pub async fn wait_for(&self, count: usize) -> Vec<SentEmail> {
+        loop {
+            let sent_one = self.sent_one.notified();
+            tokio::pin!(sent_one);
+            sent_one.as_mut().enable();
+            let sent = self.sent();
+            if sent.len() >= count {
+                return sent;
+            }
+            sent_one.await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailSender for RecordingEmailSender {
+    async fn send(&self, to: &Email, subject: &str, body: &str) -> Result<(), DomainError> {
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+        self.faults.run("send", async { Ok(()) }).await?;
+        let email = SentEmail {
+            to: to.value().to_owned(),
+            subject: subject.to_owned(),
+            body: body.to_owned(),
+        };
+        self.sent.lock().unwrap_or_else(PoisonError::into_inner).push(email);
+        self.sent_one.notify_waiters();
+        Ok(())
+    }
+}
+
+/// One fake per repository port, handed out by [`test_app`]
+///
+/// Seed them through their port methods and inject failures with `fail` before or
+/// after building the app; the app shares these instances.
+#[derive(Default)]
+pub struct FakeRepositories {
+    /// Users
+    pub users: Arc<FakeUserRepository>,
+    /// Pending email changes
+    pub email_changes: Arc<FakeEmailChangeRepository>,
+    /// Tasks
+    pub tasks: Arc<FakeTaskRepository>,
+    /// Task attachments
+    pub attachments: Arc<FakeAttachmentRepository>,
+    /// Task view preferences, not faked: they cannot fail
+    pub task_view_preferences: Arc<InMemoryTaskViewPreferencesRepository>,
+    /// Webhook subscriptions and deliveries, not faked: they cannot fail
+    pub webhooks: Arc<InMemoryWebhookRepository>,
+    /// API tokens, not faked either
+    pub api_tokens: Arc<InMemoryApiTokenRepository>,
+}
+
+impl RepositoryProvider for FakeRepositories {
+    fn user_repository(&self) -> Arc<dyn UserRepository> {
+        Arc::clone(&self.users) as _
+    }
+
+    fn email_change_repository(&self) -> Arc<dyn EmailChangeRepository> {
+        Arc::clone(&self.email_changes) as _
+    }
+
+    fn task_repository(&self) -> Arc<dyn TaskRepository> {
+        Arc::clone(&self.tasks) as _
+    }
+
+    fn attachment_repository(&self) -> Arc<dyn AttachmentRepository> {
+        Arc::clone(&self.attachments) as _
+    }
+
+    fn task_view_preferences_repository(&self) -> Arc<dyn TaskViewPreferencesRepository> {
+        Arc::clone(&self.task_view_preferences) as _
+    }
+
+    /// Writes to the repositories the fakes wrap, without their canned errors, and
+    /// records events for [`Self::webhooks`]
+    fn unit_of_work(&self) -> Arc<dyn UnitOfWork> {
+        let users = Arc::clone(&self.users.inner);
+        let unit_of_work = InMemoryUnitOfWork::new(users, Arc::clone(&self.tasks.inner));
+        Arc::new(unit_of_work.with_outbox(Arc::clone(self.webhooks.outbox())))
+    }
+
+    fn webhook_repository(&self) -> Arc<dyn WebhookRepository> {
+        Arc::clone(&self.webhooks) as _
+    }
+
+    fn webhook_deliveries(&self) -> Arc<dyn WebhookDeliveries> {
+        Arc::clone(&self.webhooks) as _
+    }
+
+    fn api_token_repository(&self) -> Arc<dyn ApiTokenRepository> {
+        Arc::clone(&self.api_tokens) as _
+    }
+}
+
+/// Build the full application router, with the default configuration, on `fakes`
+///
+/// # Errors
+/// Fails when the default configuration cannot be wired, which is a bug.
+pub fn test_app(fakes: &FakeRepositories) -> anyhow::Result<Router> {
+    let config = Config::default();
+    build_router(&AppState::build(&config, fakes)?, &config)
+}
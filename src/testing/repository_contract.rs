@@ -0,0 +1,444 @@
+//! Contracts of the repositories, run against every implementation
+//!
+//! The list methods return rows in ID order: rows are inserted out of ID order, so
+//! an implementation returning them in insertion or storage order fails. Updates of
+//! a deleted row fail with `NotFound` rather than pass as if it were written, and
+//! deletions returning the row return it as it was, only once. Sorted pages break
+//! ties by ID, and saved preferences replace the ones saved before. Paging through
+//! updates neither skips nor repeats rows updated at the same instant. Checklists are
+//! stored with their task and kept by writes that do not change them. Tasks are
+//! counted per owner only for the owners asked about. Tasks inserted completed keep
+//! the time they were completed at. Users and tasks read for update stay locked until
+//! their transaction ends, so concurrent read-modify-writes do not lose either write.
+
+use super::*;
+use crate::features::task::domain::TaskViewPreferences;
+use crate::shared::domain::Entity;
+use futures_util::TryStreamExt;
+use std::collections::HashMap;
+
+/// Order in which the rows with the IDs at these positions are inserted
+const INSERTION_ORDER: [usize; 5] = [3, 0, 4, 1, 2];
+
+fn user(id: &str) -> User {
+    let id = UserId::new(id).expect("valid user id");
+    User::new(id.clone(), id.value().to_owned(), &format!("{}@example.com", id.value()))
+        .expect("valid user")
+}
+
+/// Assert that every list method of `users` returns users in ID order
+pub(crate) async fn users_should_list_in_id_order(users: &dyn UserRepository) {
+    let tenant = TenantId::default();
+    let ids: Vec<String> = (0..INSERTION_ORDER.len()).map(|n| format!("u-order-{n}")).collect();
+    for position in INSERTION_ORDER {
+        users.insert(&tenant, &user(&ids[position])).await.expect("insert user");
+    }
+    let listed = |found: Vec<User>| -> Vec<String> {
+        found.iter().map(|u| u.id().value().to_owned()).collect()
+    };
+
+    let page = Page { limit: 10, offset: 0 };
+    assert_eq!(listed(users.find_page(&tenant, None, page).await.expect("query")), ids);
+    let second = Page { limit: 2, offset: 2 };
+    assert_eq!(listed(users.find_page(&tenant, None, second).await.expect("query")), ids[2..4]);
+    assert_eq!(listed(users.find_all_unbounded(&tenant).await.expect("query")), ids);
+    let requested: Vec<UserId> =
+        ids.iter().rev().map(|id| UserId::new(id).expect("valid user id")).collect();
+    assert_eq!(listed(users.find_by_ids(&tenant, &requested).await.expect("query")), ids);
+}
+
+/// Assert that every list method of `tasks` returns tasks in ID order; `users` must
+/// be the user repository `tasks` checks task owners against
+pub(crate) async fn tasks_should_list_in_id_order(
+    users: &dyn UserRepository,
+    tasks: &dyn TaskRepository,
+) {
+    let tenant = TenantId::default();
+    let owner = user("u-order");
+    users.insert(&tenant, &owner).await.expect("insert user");
+    let ids: Vec<String> = (0..INSERTION_ORDER.len())
+        .map(|n| format!("00000000-0000-4000-8000-00000000000{n}"))
+        .collect();
+    for position in INSERTION_ORDER {
+        let id = TaskId::new(&ids[position]).expect("valid task id");
+        let task =
+            Task::new(id, owner.id().clone(), "Same title", String::new()).expect("valid task");
+        tasks.insert(&tenant, &task).await.expect("insert task");
+    }
+    let listed = |found: Vec<Task>| -> Vec<String> {
+        found.iter().map(|t| t.id().value().to_owned()).collect()
+    };
+
+    let filter = TaskFilter::default();
+    let page = Page { limit: 10, offset: 0 };
+    let found = tasks.find_page(&tenant, &filter, TaskSort::Id, page).await.expect("query");
+    assert_eq!(listed(found), ids);
+    let second = Page { limit: 2, offset: 2 };
+    let found = tasks.find_page(&tenant, &filter, TaskSort::Id, second).await.expect("query");
+    assert_eq!(listed(found), ids[2..4]);
+    let streamed = tasks.stream_filtered(&tenant, &filter).try_collect().await.expect("query");
+    assert_eq!(listed(streamed), ids);
+    assert_eq!(listed(tasks.find_by_user_id(&tenant, owner.id()).await.expect("query")), ids);
+    assert_eq!(listed(tasks.find_all_unbounded(&tenant).await.expect("query")), ids);
+    let open = tasks
+        .exists_open_with_title(&tenant, owner.id(), "same title")
+        .await
+        .expect("query")
+        .expect("open task");
+    assert_eq!(open.value(), ids[0]);
+}
+
+/// Assert that `users` refuses to update a user deleted since it was read
+pub(crate) async fn users_should_not_update_deleted_rows(users: &dyn UserRepository) {
+    let tenant = TenantId::default();
+    let mut stale = user("u-deleted");
+    users.insert(&tenant, &stale).await.expect("insert user");
+    assert!(users.delete(&tenant, stale.id()).await.expect("delete"));
+
+    stale.update("Renamed".into(), "renamed@example.com").expect("valid update");
+    let result = users.update(&tenant, &stale).await;
+    assert!(matches!(result, Err(DomainError::NotFound(_))), "{result:?}");
+    assert!(users.find_by_id(&tenant, stale.id()).await.expect("query").is_none());
+}
+
+/// Assert that `tasks` refuses to update a task deleted since it was read; `users`
+/// must be the user repository `tasks` checks task owners against
+pub(crate) async fn tasks_should_not_update_deleted_rows(
+    users: &dyn UserRepository,
+    tasks: &dyn TaskRepository,
+) {
+    let tenant = TenantId::default();
+    let owner = user("u-deleted");
+    users.insert(&tenant, &owner).await.expect("insert user");
+    let mut stale = Task::new(TaskId::generate(), owner.id().clone(), "Buy milk", String::new())
+        .expect("valid task");
+    tasks.insert(&tenant, &stale).await.expect("insert task");
+    assert!(tasks.delete(&tenant, stale.id()).await.expect("delete"));
+
+    stale.complete().expect("open task");
+    let result = tasks.update(&tenant, &stale).await;
+    assert!(matches!(result, Err(DomainError::NotFound(_))), "{result:?}");
+    assert!(tasks.find_by_id(&tenant, stale.id()).await.expect("query").is_none());
+}
+
+/// Assert that `users` returns a user it deletes, within its tenant only
+pub(crate) async fn users_should_return_deleted_rows(users: &dyn UserRepository) {
+    let tenant = TenantId::default();
+    let other = TenantId::new("other").expect("tenant");
+    let alice = user("u-returned");
+    users.insert(&tenant, &alice).await.expect("insert user");
+
+    assert!(users.delete_returning(&other, alice.id()).await.expect("delete").is_none());
+    let deleted = users.delete_returning(&tenant, alice.id()).await.expect("delete");
+    let deleted = deleted.expect("deleted user");
+    assert_eq!((deleted.id(), deleted.email()), (alice.id(), alice.email()));
+    assert!(users.find_by_id(&tenant, alice.id()).await.expect("query").is_none());
+    assert!(users.delete_returning(&tenant, alice.id()).await.expect("delete").is_none());
+}
+
+/// Assert that `tasks` returns a task it deletes, within its tenant only; `users`
+/// must be the user repository `tasks` checks task owners against
+pub(crate) async fn tasks_should_return_deleted_rows(
+    users: &dyn UserRepository,
+    tasks: &dyn TaskRepository,
+) {
+    let tenant = TenantId::default();
+    let other = TenantId::new("other").expect("tenant");
+    let owner = user("u-returned");
+    users.insert(&tenant, &owner).await.expect("insert user");
+    let task = Task::new(TaskId::generate(), owner.id().clone(), "Buy milk", "2 l".into())
+        .expect("valid task");
+    let inserted = tasks.insert(&tenant, &task).await.expect("insert task");
+
+    assert!(tasks.delete_returning(&other, task.id()).await.expect("delete").is_none());
+    let deleted = tasks.delete_returning(&tenant, task.id()).await.expect("delete");
+    let deleted = deleted.expect("deleted task");
+    assert_eq!((deleted.id(), deleted.title()), (task.id(), "Buy milk"));
+    assert_eq!(deleted.updated_at(), inserted.updated_at());
+    assert!(tasks.find_by_id(&tenant, task.id()).await.expect("query").is_none());
+    assert!(tasks.delete_returning(&tenant, task.id()).await.expect("delete").is_none());
+}
+
+/// Assert that `tasks` pages tasks in every [`TaskSort`] order, ties broken by ID;
+/// `users` must be the user repository `tasks` checks task owners against
+pub(crate) async fn tasks_should_page_in_sort_order(
+    users: &dyn UserRepository,
+    tasks: &dyn TaskRepository,
+) {
+    let tenant = TenantId::default();
+    let owner = user("u-sorted");
+    users.insert(&tenant, &owner).await.expect("insert user");
+    let mut inserted = Vec::new();
+    for (id, title) in [("d", "banana"), ("a", "Cherry"), ("c", "apple"), ("b", "Banana")] {
+        let id = TaskId::new(id).expect("valid task id");
+        let task = Task::new(id, owner.id().clone(), title, String::new()).expect("valid task");
+        inserted.push(tasks.insert(&tenant, &task).await.expect("insert task"));
+    }
+    let updated = tasks.update(&tenant, &inserted[1]).await.expect("update task");
+    let filter = TaskFilter::default();
+    let page = |sort, offset| tasks.find_page(&tenant, &filter, sort, Page { limit: 3, offset });
+    let listed = |found: Vec<Task>| -> Vec<String> {
+        found.iter().map(|t| t.id().value().to_owned()).collect()
+    };
+
+    assert_eq!(listed(page(TaskSort::Id, 0).await.expect("query")), ["a", "b", "c"]);
+    assert_eq!(listed(page(TaskSort::Title, 0).await.expect("query")), ["c", "b", "d"]);
+    assert_eq!(listed(page(TaskSort::Title, 3).await.expect("query")), ["a"]);
+    let recent = page(TaskSort::RecentlyUpdated, 0).await.expect("query");
+    assert_eq!(recent[0].id(), updated.id());
+}
+
+/// Assert that `preferences` keeps the last task view preferences saved for each user
+/// of each tenant; `users` must be the user repository `preferences` checks users
+/// against
+pub(crate) async fn task_view_preferences_should_be_replaced(
+    users: &dyn UserRepository,
+    preferences: &dyn TaskViewPreferencesRepository,
+) {
+    let tenant = TenantId::default();
+    let other = TenantId::new("other").expect("tenant");
+    let owner = user("u-preferences");
+    users.insert(&tenant, &owner).await.expect("insert user");
+    assert!(preferences.find(&tenant, owner.id()).await.expect("query").is_none());
+
+    let first = TaskViewPreferences::new(TaskSort::Title, false, Some(20)).expect("valid");
+    preferences.save(&tenant, owner.id(), &first).await.expect("save");
+    let second = TaskViewPreferences::new(TaskSort::RecentlyUpdated, true, None).expect("valid");
+    preferences.save(&tenant, owner.id(), &second).await.expect("save");
+    assert_eq!(preferences.find(&tenant, owner.id()).await.expect("query"), Some(second));
+    assert!(preferences.find(&other, owner.id()).await.expect("query").is_none());
+}
+
+/// Assert that paging through the updates of `users` with the watermark of each page
+/// returns every user exactly once, even when a page ends among users updated at the
+/// same instant; `pin` sets the last update of a stored user
+pub(crate) async fn users_should_sync_ties_exactly_once(
+    users: &dyn UserRepository,
+    pin: impl AsyncFn(&UserId, DateTime<Utc>),
+) {
+    let tenant = TenantId::default();
+    let ids: Vec<String> = (0..INSERTION_ORDER.len()).map(|n| format!("u-sync-{n}")).collect();
+    for position in INSERTION_ORDER {
+        users.insert(&tenant, &user(&ids[position])).await.expect("insert user");
+    }
+    let tied = DateTime::parse_from_rfc3339("2026-01-02T03:04:05.123456Z").expect("timestamp");
+    let tied = tied.with_timezone(&Utc);
+    let second = chrono::Duration::seconds(1);
+    let times = [tied + second, tied, tied - second, tied, tied];
+    for (id, at) in ids.iter().zip(times) {
+        pin(&UserId::new(id).expect("valid user id"), at).await;
+    }
+    let listed = |found: &[User]| -> Vec<String> {
+        found.iter().map(|u| u.id().value().to_owned()).collect()
+    };
+
+    let mut watermark = SyncWatermark::since(tied - second * 60);
+    let mut pages = Vec::new();
+    loop {
+        let found = users.find_updated_since(&tenant, &watermark, 2).await.expect("query");
+        let Some(last) = found.last() else { break };
+        watermark = SyncWatermark::after(last).expect("stored user");
+        pages.push(listed(&found));
+    }
+    let expected = [["u-sync-2", "u-sync-1"].as_slice(), &["u-sync-3", "u-sync-4"], &["u-sync-0"]];
+    assert_eq!(pages, expected);
+    assert_eq!(watermark.updated_at, tied + second);
+
+    let after_ties = users.find_updated_since(&tenant, &SyncWatermark::since(tied), 10).await;
+    assert_eq!(listed(&after_ties.expect("query")), ["u-sync-0"]);
+    let other = TenantId::new("other").expect("tenant");
+    let since = SyncWatermark::since(tied - second * 60);
+    assert!(users.find_updated_since(&other, &since, 10).await.expect("query").is_empty());
+}
+
+/// Assert that `tasks` stores each task's checklist, replacing it on update and
+/// keeping it through completes and upserts; `users` must be the user repository
+/// `tasks` checks task owners against
+pub(crate) async fn tasks_should_store_checklists(
+    users: &dyn UserRepository,
+    tasks: &dyn TaskRepository,
+) {
+    let tenant = TenantId::default();
+    let owner = user("u-checklist");
+    users.insert(&tenant, &owner).await.expect("insert user");
+    let mut task = Task::new(TaskId::generate(), owner.id().clone(), "Pack", String::new())
+        .expect("valid task");
+    let socks = task.add_item("Socks \"wool\" 日本語").expect("valid item");
+    task.add_item("Shoes").expect("valid item");
+    let inserted = tasks.insert(&tenant, &task).await.expect("insert task");
+    assert_eq!(inserted.checklist(), task.checklist());
+
+    let mut stored = tasks.find_by_id(&tenant, task.id()).await.expect("query").expect("task");
+    assert_eq!(stored.checklist(), task.checklist());
+    stored.toggle_item(socks.id(), true).expect("known item");
+    let shoes = stored.checklist()[1].id().clone();
+    stored.remove_item(&shoes).expect("known item");
+    let updated = tasks.update(&tenant, &stored).await.expect("update");
+    assert_eq!(updated.checklist(), stored.checklist());
+
+    let mut edited = stored.clone();
+    edited.edit("Pack bags", String::new()).expect("valid edit");
+    let UpsertOutcome::Updated(upserted) = tasks.upsert(&tenant, &edited).await.expect("upsert")
+    else {
+        panic!("existing task")
+    };
+    assert_eq!(upserted.checklist(), stored.checklist());
+    let CompleteOutcome::Completed(completed) =
+        tasks.complete_if_open(&tenant, task.id()).await.expect("complete")
+    else {
+        panic!("open task")
+    };
+    assert_eq!(completed.checklist(), stored.checklist());
+    let listed = tasks.find_by_user_id(&tenant, owner.id()).await.expect("query");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].checklist(), stored.checklist());
+}
+
+/// Assert that `tasks` counts the tasks of each requested owner in one call, leaving
+/// out owners without tasks and those not requested; `users` must be the user
+/// repository `tasks` checks task owners against
+pub(crate) async fn tasks_should_count_by_user_ids<T: TaskRepository + UserDependents>(
+    users: &dyn UserRepository,
+    tasks: &T,
+) {
+    let tenant = TenantId::default();
+    let owners = ["u-count-a", "u-count-b", "u-count-c", "u-count-d"].map(user);
+    for (owner, count) in owners.iter().zip([2, 1, 0, 3]) {
+        users.insert(&tenant, owner).await.expect("insert user");
+        for n in 0..count {
+            let title = format!("Task {n}");
+            let task = Task::new(TaskId::generate(), owner.id().clone(), &title, String::new())
+                .expect("valid task");
+            tasks.insert(&tenant, &task).await.expect("insert task");
+        }
+    }
+    let requested: Vec<UserId> = owners[..3].iter().map(|u| u.id().clone()).collect();
+
+    let counts = tasks.count_by_user_ids(&tenant, &requested).await.expect("count");
+    let expected = HashMap::from([(requested[0].clone(), 2), (requested[1].clone(), 1)]);
+    assert_eq!(counts, expected);
+    assert!(tasks.count_by_user_ids(&tenant, &[]).await.expect("count").is_empty());
+    let other = TenantId::new("other").expect("tenant");
+    assert!(tasks.count_by_user_ids(&other, &requested).await.expect("count").is_empty());
+}
+
+/// Assert that `tasks` stores a task inserted completed as completed at its own
+/// completion time, and an open one as open; `users` must be the user repository
+/// `tasks` checks task owners against
+pub(crate) async fn tasks_should_insert_completed_at_their_time(
+    users: &dyn UserRepository,
+    tasks: &dyn TaskRepository,
+) {
+    let tenant = TenantId::default();
+    let owner = user("u-imported");
+    users.insert(&tenant, &owner).await.expect("insert user");
+    let now = Utc::now();
+    let completed_at: DateTime<Utc> = "2020-03-04T05:06:07Z".parse().expect("valid timestamp");
+    let id = TaskId::generate();
+    let done = Task::new_completed(id, owner.id().clone(), "Done", String::new(), completed_at, now)
+        .expect("valid task");
+    let open = Task::new(TaskId::generate(), owner.id().clone(), "Open", String::new())
+        .expect("valid task");
+    assert!(tasks.insert(&tenant, &done).await.expect("insert task").is_completed());
+    assert!(!tasks.insert(&tenant, &open).await.expect("insert task").is_completed());
+
+    let stored = tasks.find_by_id(&tenant, done.id()).await.expect("query").expect("task");
+    assert!(stored.is_completed());
+    let since: DateTime<Utc> = "2020-03-04T00:00:00Z".parse().expect("valid timestamp");
+    let counts = tasks.hourly_counts(&tenant, since).await.expect("counts");
+    let hour: DateTime<Utc> = "2020-03-04T05:00:00Z".parse().expect("valid timestamp");
+    let completed: Vec<_> =
+        counts.iter().filter(|c| c.completed > 0).map(|c| (c.hour, c.completed)).collect();
+    assert_eq!(completed, [(hour, 1)]);
+}
+
+/// Assert that a user read for update through a transaction of `users` is locked
+/// until the transaction ends, and that dropping a transaction undoes its updates
+pub(crate) async fn users_should_lock_rows_read_for_update(users: &dyn UserRepository) {
+    let tenant = TenantId::default();
+    let locked = user("u-locked");
+    users.insert(&tenant, &locked).await.expect("insert");
+    let rename = async |suffix: &str, hold: Duration| {
+        let mut transaction = users.begin().await.expect("begin");
+        let mut found = transaction
+            .find_by_id_for_update(&tenant, locked.id())
+            .await
+            .expect("query")
+            .expect("user");
+        tokio::time::sleep(hold).await;
+        let email = found.email().value().to_owned();
+        found.update(format!("{}{suffix}", found.name()), &email).expect("valid user");
+        transaction.update(&tenant, &found).await.expect("update");
+        transaction.commit().await.expect("commit");
+    };
+
+    // The second rename reads the user while the first holds it
+    tokio::join!(rename("-a", Duration::from_millis(50)), async {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        rename("-b", Duration::ZERO).await;
+    });
+    let stored = users.find_by_id(&tenant, locked.id()).await.expect("query").expect("user");
+    assert_eq!(stored.name(), "u-locked-a-b");
+
+    let mut transaction = users.begin().await.expect("begin");
+    let found = transaction.find_by_id_for_update(&tenant, locked.id()).await.expect("query");
+    let mut found = found.expect("user");
+    found.update("Dropped".into(), "dropped@example.com").expect("valid user");
+    transaction.update(&tenant, &found).await.expect("update");
+    drop(transaction);
+    let stored = users.find_by_id(&tenant, locked.id()).await.expect("query").expect("user");
+    assert_eq!(stored.name(), "u-locked-a-b");
+}
+
+/// Assert that a task read for update through a transaction of `unit_of_work` is
+/// locked until the transaction ends, and that dropping a transaction undoes its
+/// updates; `users` and `tasks` must be the repositories `unit_of_work` writes to
+pub(crate) async fn tasks_should_lock_rows_read_for_update(
+    users: &dyn UserRepository,
+    tasks: &dyn TaskRepository,
+    unit_of_work: &dyn UnitOfWork,
+) {
+    let tenant = TenantId::default();
+    let owner = user("u-task-locked");
+    users.insert(&tenant, &owner).await.expect("insert user");
+    let locked = Task::new(TaskId::generate(), owner.id().clone(), "Locked", String::new())
+        .expect("valid task");
+    tasks.insert(&tenant, &locked).await.expect("insert task");
+    let rename = async |suffix: &str, hold: Duration| {
+        let mut transaction = unit_of_work.begin().await.expect("begin");
+        let mut found = transaction
+            .find_task_for_update(&tenant, locked.id())
+            .await
+            .expect("query")
+            .expect("task");
+        tokio::time::sleep(hold).await;
+        let title = format!("{}{suffix}", found.title());
+        found.edit(&title, String::new()).expect("valid edit");
+        let updated = transaction.update_task(&tenant, &found).await.expect("update");
+        assert_eq!(updated.title(), title);
+        transaction.commit().await.expect("commit");
+    };
+
+    // The second rename reads the task while the first holds it
+    tokio::join!(rename("-a", Duration::from_millis(50)), async {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        rename("-b", Duration::ZERO).await;
+    });
+    let stored = tasks.find_by_id(&tenant, locked.id()).await.expect("query").expect("task");
+    assert_eq!(stored.title(), "Locked-a-b");
+
+    let mut transaction = unit_of_work.begin().await.expect("begin");
+    let found = transaction.find_task_for_update(&tenant, locked.id()).await.expect("query");
+    let mut found = found.expect("task");
+    found.complete().expect("open task");
+    transaction.update_task(&tenant, &found).await.expect("update");
+    drop(transaction);
+    let stored = tasks.find_by_id(&tenant, locked.id()).await.expect("query").expect("task");
+    assert!(!stored.is_completed());
+    let other = TenantId::new("other").expect("tenant");
+    let mut transaction = unit_of_work.begin().await.expect("begin");
+    let found = transaction.find_task_for_update(&other, locked.id()).await.expect("query");
+    assert!(found.is_none());
+    let missing = transaction.update_task(&other, &stored).await;
+    assert!(matches!(missing, Err(DomainError::NotFound(_))), "{missing:?}");
+}